@@ -0,0 +1,31 @@
+//! A module letting the root origin choose the GRANDPA authority set finalizing the chain.
+//!
+//! On a PoW chain, the block authors are not known in advance and can't vote on finality, so
+//! the GRANDPA authorities are chosen separately: they are set in the genesis, and can later be
+//! changed with the `set_grandpa_authorities` call.
+
+use support::{decl_module, dispatch};
+use system::ensure_root;
+use grandpa::AuthorityList;
+
+/// The module's configuration trait.
+pub trait Trait: grandpa::Trait {}
+
+decl_module! {
+	/// The module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// Schedule a change of the GRANDPA authority set. The new set is enacted once the block
+		/// `delay` blocks after the one including this call is finalized.
+		///
+		/// Fails if a change is already pending.
+		pub fn set_grandpa_authorities(
+			origin,
+			authorities: AuthorityList,
+			delay: T::BlockNumber,
+		) -> dispatch::Result {
+			ensure_root(origin)?;
+
+			<grandpa::Module<T>>::schedule_change(authorities, delay, None)
+		}
+	}
+}
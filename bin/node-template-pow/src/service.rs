@@ -0,0 +1,227 @@
+//! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
+//!
+//! Blocks are mined with hash-cash, and finalized by GRANDPA: the PoW block import wraps the
+//! GRANDPA one, so that the best chain is the heaviest one including the last finalized block,
+//! and GRANDPA votes for blocks of that chain through the PoW `SelectChain`.
+
+use std::sync::Arc;
+use std::time::Duration;
+use sc_client::{Client, LocalCallExecutor};
+use sc_client_db::Backend;
+use runtime::{self, GenesisConfig, opaque::Block, RuntimeApi};
+use sc_service::{error::{Error as ServiceError}, AbstractService, Configuration};
+use network::{construct_simple_protocol};
+use sc_executor::native_executor_instance;
+pub use sc_executor::NativeExecutor;
+use pow::{hashcash::Hashcash, difficulty::{MovingWindow, MovingWindowParams}};
+use grandpa::{self, FinalityProofProvider as GrandpaFinalityProofProvider};
+use basic_authorship;
+
+// Our native executor instance.
+native_executor_instance!(
+	pub Executor,
+	runtime::api::dispatch,
+	runtime::native_version,
+);
+
+construct_simple_protocol! {
+	/// Demo protocol attachment for substrate.
+	pub struct NodeProtocol where Block = Block { }
+}
+
+/// Full client type.
+pub type FullClient = Client<
+	Backend<Block>,
+	LocalCallExecutor<Backend<Block>, NativeExecutor<Executor>>,
+	Block,
+	RuntimeApi,
+>;
+
+/// The PoW algorithm of the node.
+pub type Algorithm = Hashcash<MovingWindow<Block, FullClient>>;
+
+/// Number of hashes the CPU miner tries before checking for new work.
+const MINING_ROUND: u32 = 10_000;
+
+/// Create the PoW algorithm: hash-cash, with a difficulty adjusted so that blocks are mined
+/// every `MILLISECS_PER_BLOCK` on average.
+pub fn algorithm(client: Arc<FullClient>) -> Algorithm {
+	Hashcash::new(MovingWindow::new(client, MovingWindowParams {
+		target_block_time: runtime::MILLISECS_PER_BLOCK,
+		..Default::default()
+	}))
+}
+
+/// Starts a `ServiceBuilder` for a full service.
+///
+/// Use this macro if you don't actually need the full service, but just the builder in order to
+/// be able to perform chain operations.
+macro_rules! new_full_start {
+	($config:expr) => {{
+		let mut import_setup = None;
+		let inherent_data_providers = inherents::InherentDataProviders::new();
+
+		let builder = sc_service::ServiceBuilder::new_full::<
+			runtime::opaque::Block, runtime::RuntimeApi, crate::service::Executor
+		>($config)?
+			.with_select_chain(|_config, backend| {
+				Ok(sc_client::LongestChain::new(backend.clone()))
+			})?
+			.with_transaction_pool(|config, client, _fetcher| {
+				let pool_api = txpool::FullChainApi::new(client.clone());
+				let pool = txpool::BasicPool::new(config, pool_api);
+				let maintainer = txpool::FullBasicPoolMaintainer::new(pool.pool().clone(), client);
+				let maintainable_pool = txpool_api::MaintainableTransactionPool::new(pool, maintainer);
+				Ok(maintainable_pool)
+			})?
+			.with_import_queue(|_config, client, mut select_chain, _transaction_pool| {
+				let select_chain = select_chain.take()
+					.ok_or_else(|| sc_service::Error::SelectChainRequired)?;
+
+				// GRANDPA only votes for blocks of the chain with the greatest total
+				// difficulty, which isn't necessarily the longest one.
+				let grandpa_select_chain =
+					pow::PowSelectChain::new(client.clone(), select_chain.clone());
+				let (grandpa_block_import, grandpa_link) =
+					grandpa::block_import::<_, _, _, runtime::RuntimeApi, _>(
+						client.clone(), &*client, grandpa_select_chain
+					)?;
+
+				// the PoW block import makes the fork choice, and hands the blocks over to
+				// the GRANDPA block import.
+				let pow_block_import = pow::PowBlockImport::<_, _, _, crate::service::Algorithm>::new(
+					grandpa_block_import.clone(),
+					client.clone(),
+				);
+
+				let import_queue = pow::import_queue(
+					Box::new(pow_block_import.clone()),
+					Some(Box::new(grandpa_block_import)),
+					None,
+					client.clone(),
+					crate::service::algorithm(client),
+					0,
+					Some(select_chain),
+					inherent_data_providers.clone(),
+				)?;
+
+				import_setup = Some((pow_block_import, grandpa_link));
+
+				Ok(import_queue)
+			})?;
+
+		(builder, import_setup, inherent_data_providers)
+	}}
+}
+
+/// Builds a new service for a full client.
+pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisConfig>)
+	-> Result<impl AbstractService, ServiceError>
+{
+	let is_authority = config.roles.is_authority();
+	let name = config.name.clone();
+	let disable_grandpa = config.disable_grandpa;
+
+	// sentry nodes announce themselves as authorities to the network
+	// and should run the same protocols authorities do, but it should
+	// never actively participate in any consensus process.
+	let participates_in_consensus = is_authority && !config.sentry_mode;
+
+	let (builder, mut import_setup, inherent_data_providers) = new_full_start!(config);
+
+	let (block_import, grandpa_link) =
+		import_setup.take()
+			.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
+
+	let service = builder.with_network_protocol(|_| Ok(NodeProtocol::new()))?
+		.with_finality_proof_provider(|client, backend|
+			Ok(Arc::new(GrandpaFinalityProofProvider::new(backend, client)) as _)
+		)?
+		.build()?;
+
+	if participates_in_consensus {
+		let proposer = basic_authorship::ProposerFactory {
+			client: service.client(),
+			transaction_pool: service.transaction_pool(),
+		};
+
+		let client = service.client();
+		let select_chain = service.select_chain()
+			.ok_or(ServiceError::SelectChainRequired)?;
+
+		let can_author_with =
+			consensus_common::CanAuthorWithNativeVersion::new(client.executor().clone());
+
+		// the mining threads are detached from the service, and run for the lifetime of the
+		// process.
+		pow::start_mine(
+			Box::new(block_import),
+			client.clone(),
+			algorithm(client),
+			proposer,
+			None,
+			MINING_ROUND,
+			1,
+			service.network(),
+			Duration::from_secs(2),
+			Some(select_chain),
+			inherent_data_providers.clone(),
+			can_author_with,
+		);
+	}
+
+	// the GRANDPA authorities are chosen independently from the miners: an authority node
+	// only votes if its keystore holds a key of the current GRANDPA set.
+	let keystore = if participates_in_consensus {
+		Some(service.keystore())
+	} else {
+		None
+	};
+
+	let grandpa_config = grandpa::Config {
+		// FIXME #1578 make this available through chainspec
+		gossip_duration: Duration::from_millis(333),
+		justification_period: 512,
+		name: Some(name),
+		observer_enabled: true,
+		keystore,
+		is_authority,
+	};
+
+	match (is_authority, disable_grandpa) {
+		(false, false) => {
+			// start the lightweight GRANDPA observer
+			service.spawn_task(grandpa::run_grandpa_observer(
+				grandpa_config,
+				grandpa_link,
+				service.network(),
+				service.on_exit(),
+			)?);
+		},
+		(true, false) => {
+			// start the full GRANDPA voter
+			let voter_config = grandpa::GrandpaParams {
+				config: grandpa_config,
+				link: grandpa_link,
+				network: service.network(),
+				inherent_data_providers: inherent_data_providers.clone(),
+				on_exit: service.on_exit(),
+				telemetry_on_connect: Some(service.telemetry_on_connect_stream()),
+				voting_rule: grandpa::VotingRulesBuilder::default().build(),
+			};
+
+			// the GRANDPA voter task is considered infallible, i.e.
+			// if it fails we take down the service with it.
+			service.spawn_essential_task(grandpa::run_grandpa_voter(voter_config)?);
+		},
+		(_, true) => {
+			grandpa::setup_disabled_grandpa(
+				service.client(),
+				&inherent_data_providers,
+				service.network(),
+			)?;
+		},
+	}
+
+	Ok(service)
+}
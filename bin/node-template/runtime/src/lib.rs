@@ -165,6 +165,14 @@ impl aura::Trait for Runtime {
 
 impl grandpa::Trait for Runtime {
 	type Event = Event;
+	type Call = Call;
+
+	// Equivocation reporting is disabled since this runtime doesn't include
+	// the session and offences modules.
+	type KeyOwnerProofSystem = ();
+	type KeyOwnerProof = support::Void;
+	type KeyOwnerIdentification = support::Void;
+	type HandleEquivocation = ();
 }
 
 impl indices::Trait for Runtime {
@@ -360,5 +368,23 @@ impl_runtime_apis! {
 		fn grandpa_authorities() -> GrandpaAuthorityList {
 			Grandpa::grandpa_authorities()
 		}
+
+		fn generate_key_ownership_proof(
+			_set_id: fg_primitives::SetId,
+			_authority_id: fg_primitives::AuthorityId,
+		) -> Option<fg_primitives::OpaqueKeyOwnershipProof> {
+			// equivocation reporting is disabled, see `grandpa::Trait` above.
+			None
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			_equivocation_proof: fg_primitives::EquivocationProof<
+				<Block as BlockT>::Hash,
+				NumberFor<Block>,
+			>,
+			_key_owner_proof: fg_primitives::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			None
+		}
 	}
 }
@@ -114,7 +114,7 @@ pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisCon
 		let can_author_with =
 			consensus_common::CanAuthorWithNativeVersion::new(client.executor().clone());
 
-		let aura = aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _, _>(
+		let aura = aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _, _, _>(
 			aura::SlotDuration::get_or_compute(&*client)?,
 			client,
 			select_chain,
@@ -125,6 +125,7 @@ pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisCon
 			force_authoring,
 			service.keystore(),
 			can_author_with,
+			Some(aura::BackoffAuthoringOnFinalizedHeadLagging::default()),
 		)?;
 
 		// the AURA authoring task is considered essential, i.e. if it
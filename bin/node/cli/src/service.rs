@@ -55,6 +55,7 @@ macro_rules! new_full_start {
 		type RpcExtension = jsonrpc_core::IoHandler<sc_rpc::Metadata>;
 		let mut import_setup = None;
 		let inherent_data_providers = inherents::InherentDataProviders::new();
+		let grandpa_voting_rpc = $config.grandpa_voting_rpc;
 
 		let builder = sc_service::ServiceBuilder::new_full::<
 			node_primitives::Block, node_runtime::RuntimeApi, node_executor::Executor
@@ -98,9 +99,35 @@ macro_rules! new_full_start {
 
 				import_setup = Some((block_import, grandpa_link, babe_link));
 				Ok(import_queue)
-			})?
+			})?;
+
+		let keystore = builder.keystore().clone();
+		let select_chain = builder.select_chain().cloned()
+			.ok_or_else(|| sc_service::Error::SelectChainRequired)?;
+
+		let builder = builder
 			.with_rpc_extensions(|client, pool, _backend, fetcher, _remote_blockchain| -> Result<RpcExtension, _> {
-				Ok(node_rpc::create(client, pool, node_rpc::LightDeps::none(fetcher)))
+				let (_, grandpa_link, babe_link) = import_setup.as_ref()
+					.expect("import setup is created along with the import queue; qed");
+				let babe = node_rpc::BabeDeps {
+					keystore,
+					config: babe_link.config().clone(),
+					epoch_changes: babe_link.epoch_changes().clone(),
+				};
+				let grandpa = node_rpc::GrandpaDeps {
+					round_state: grandpa_link.round_state(),
+					justification_stream: grandpa_link.justification_stream(),
+					voting_hold: grandpa_link.voting_hold(),
+					voting_rpc: grandpa_voting_rpc,
+					// the subscriptions are made from the tasks of the RPC servers.
+					subscriptions: sc_rpc::Subscriptions::new(
+						std::sync::Arc::new(tokio_executor::DefaultExecutor::current())
+					),
+				};
+				let full_deps = node_rpc::FullDeps { select_chain, babe, grandpa };
+				let light_deps = node_rpc::LightDeps::none(fetcher);
+
+				Ok(node_rpc::create(client, pool, Some(full_deps), light_deps))
 			})?;
 
 		(builder, import_setup, inherent_data_providers)
@@ -126,12 +153,14 @@ macro_rules! new_full {
 			force_authoring,
 			name,
 			disable_grandpa,
+			grandpa_pause_voting,
 			sentry_nodes,
 		) = (
 			$config.roles.is_authority(),
 			$config.force_authoring,
 			$config.name.clone(),
 			$config.disable_grandpa,
+			$config.grandpa_pause_voting,
 			$config.network.sentry_nodes.clone(),
 		);
 
@@ -185,6 +214,7 @@ macro_rules! new_full {
 				force_authoring,
 				babe_link,
 				can_author_with,
+				backoff_authoring_blocks: Some(babe::BackoffAuthoringOnFinalizedHeadLagging::default()),
 			};
 
 			let babe = babe::start_babe(babe_config)?;
@@ -234,6 +264,17 @@ macro_rules! new_full {
 				)?);
 			},
 			(true, false) => {
+				// hold our votes at the pauses scheduled on-chain, e.g. by
+				// governance ahead of risky runtime migrations.
+				let voting_rule = grandpa::VotingRulesBuilder::default()
+					.add(grandpa::ScheduledPauses::new(grandpa_link.voting_hold()))
+					.build();
+
+				if grandpa_pause_voting {
+					// only vote on finalized blocks until voting is resumed through RPC.
+					grandpa_link.voting_hold().pause();
+				}
+
 				// start the full GRANDPA voter
 				let grandpa_config = grandpa::GrandpaParams {
 					config: config,
@@ -242,7 +283,7 @@ macro_rules! new_full {
 					inherent_data_providers: inherent_data_providers.clone(),
 					on_exit: service.on_exit(),
 					telemetry_on_connect: Some(service.telemetry_on_connect_stream()),
-					voting_rule: grandpa::VotingRulesBuilder::default().build(),
+					voting_rule,
 				};
 				// the GRANDPA voter task is considered infallible, i.e.
 				// if it fails we take down the service with it.
@@ -371,14 +412,15 @@ pub fn new_light<C: Send + Default + 'static>(config: NodeConfiguration<C>)
 		.with_finality_proof_provider(|client, backend|
 			Ok(Arc::new(GrandpaFinalityProofProvider::new(backend, client)) as _)
 		)?
-		.with_rpc_extensions(|client, pool, _backend, fetcher, remote_blockchain| -> Result<RpcExtension, _> {
+		.with_rpc_extensions(|client, pool, backend, fetcher, remote_blockchain| -> Result<RpcExtension, _> {
 			let fetcher = fetcher
 				.ok_or_else(|| "Trying to start node RPC without active fetcher")?;
 			let remote_blockchain = remote_blockchain
 				.ok_or_else(|| "Trying to start node RPC without active remote blockchain")?;
 
+			let full_deps = node_rpc::FullDeps::none(Some(LongestChain::new(backend)));
 			let light_deps = node_rpc::LightDeps { remote_blockchain, fetcher };
-			Ok(node_rpc::create(client, pool, Some(light_deps)))
+			Ok(node_rpc::create(client, pool, full_deps, Some(light_deps)))
 		})?
 		.build()?;
 
@@ -31,8 +31,10 @@
 
 use std::sync::Arc;
 
-use node_primitives::{Block, AccountId, Index, Balance};
+use node_primitives::{Block, BlockNumber, AccountId, Index, Balance};
 use node_runtime::UncheckedExtrinsic;
+use consensus_common::SelectChain;
+use sp_blockchain::{HeaderMetadata, Error as BlockChainError};
 use sp_runtime::traits::ProvideRuntimeApi;
 use txpool_api::TransactionPool;
 
@@ -54,27 +56,76 @@ impl<F> LightDeps<F> {
 	}
 }
 
+/// Extra dependencies for GRANDPA.
+pub struct GrandpaDeps {
+	/// Votes of the round the voter is running.
+	pub round_state: grandpa::SharedRoundState,
+	/// Justifications of the blocks finalized by the voter or the block import.
+	pub justification_stream: grandpa::GrandpaJustificationStream,
+	/// Hold on the votes of the voter.
+	pub voting_hold: grandpa::SharedVotingHold<BlockNumber>,
+	/// Whether to expose the RPC methods changing the hold on the votes of the voter.
+	pub voting_rpc: bool,
+	/// Subscriptions to the justifications.
+	pub subscriptions: sc_rpc::Subscriptions,
+}
+
+/// Extra dependencies for BABE.
+pub struct BabeDeps {
+	/// Keystore holding the BABE keys of the node.
+	pub keystore: keystore::KeyStorePtr,
+	/// BABE configuration of the chain.
+	pub config: babe::Config,
+	/// Epoch changes tracked by the block import.
+	pub epoch_changes: babe::SharedEpochChanges<Block>,
+}
+
+/// Full client extra dependencies.
+pub struct FullDeps<SC> {
+	/// The select chain of the node.
+	pub select_chain: SC,
+	/// BABE specific dependencies.
+	pub babe: BabeDeps,
+	/// GRANDPA specific dependencies.
+	pub grandpa: GrandpaDeps,
+}
+
+impl<SC> FullDeps<SC> {
+	/// Create empty `FullDeps` with given `SC` type.
+	///
+	/// This is a convenience method to be used in the service builder of the light client,
+	/// to make sure the type of the `FullDeps<SC>` is matching.
+	pub fn none(_: Option<SC>) -> Option<Self> {
+		None
+	}
+}
+
 /// Instantiate all RPC extensions.
 ///
 /// If you provide `LightDeps`, the system is configured for light client.
-pub fn create<C, P, M, F>(
+/// Otherwise the `FullDeps`, if any, are used to extend the RPCs of a full client.
+pub fn create<C, P, F, SC>(
 	client: Arc<C>,
 	pool: Arc<P>,
+	full_deps: Option<FullDeps<SC>>,
 	light_deps: Option<LightDeps<F>>,
-) -> jsonrpc_core::IoHandler<M> where
+) -> jsonrpc_core::IoHandler<sc_rpc::Metadata> where
 	C: ProvideRuntimeApi,
 	C: client::blockchain::HeaderBackend<Block>,
+	C: HeaderMetadata<Block, Error=BlockChainError>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
 	F: client::light::fetcher::Fetcher<Block> + 'static,
 	P: TransactionPool + 'static,
-	M: jsonrpc_core::Metadata + Default,
+	SC: SelectChain<Block> + 'static,
 {
 	use substrate_frame_rpc_system::{FullSystem, LightSystem, SystemApi};
 	use pallet_contracts_rpc::{Contracts, ContractsApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use babe::rpc::{BabeApi, BabeRpcHandler};
+	use grandpa::rpc::{GrandpaApi, GrandpaRpcHandler, GrandpaVotingApi, GrandpaVotingRpcHandler};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 
@@ -94,8 +145,42 @@ pub fn create<C, P, M, F>(
 			ContractsApi::to_delegate(Contracts::new(client.clone()))
 		);
 		io.extend_with(
-			TransactionPaymentApi::to_delegate(TransactionPayment::new(client))
+			TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
 		);
+
+		if let Some(FullDeps { select_chain, babe, grandpa }) = full_deps {
+			let BabeDeps { keystore, config, epoch_changes } = babe;
+			io.extend_with(
+				BabeApi::to_delegate(BabeRpcHandler::new(
+					client,
+					epoch_changes,
+					keystore,
+					config,
+					select_chain,
+				))
+			);
+
+			let GrandpaDeps {
+				round_state,
+				justification_stream,
+				voting_hold,
+				voting_rpc,
+				subscriptions,
+			} = grandpa;
+			if voting_rpc {
+				io.extend_with(
+					GrandpaVotingApi::to_delegate(GrandpaVotingRpcHandler::new(voting_hold.clone()))
+				);
+			}
+			io.extend_with(
+				GrandpaApi::to_delegate(GrandpaRpcHandler::new(
+					round_state,
+					justification_stream,
+					voting_hold,
+					subscriptions,
+				))
+			);
+		}
 	}
 	io
 }
@@ -21,20 +21,24 @@
 #![recursion_limit="256"]
 
 use sp_std::prelude::*;
+use codec::{Encode, Decode};
 use support::{
 	construct_runtime, parameter_types,
 	weights::Weight,
-	traits::{SplitTwoWays, Currency, Randomness},
+	traits::{SplitTwoWays, Currency, Randomness, KeyOwnerProofSystem, InstanceFilter},
 };
 use primitives::u32_trait::{_1, _2, _3, _4};
 use node_primitives::{AccountId, AccountIndex, Balance, BlockNumber, Hash, Index, Moment, Signature};
 use sp_api::impl_runtime_apis;
-use sp_runtime::{Permill, Perbill, ApplyExtrinsicResult, impl_opaque_keys, generic, create_runtime_str};
+use sp_runtime::{
+	Permill, Perbill, ApplyExtrinsicResult, KeyTypeId, impl_opaque_keys, generic, create_runtime_str,
+	RuntimeDebug,
+};
 use sp_runtime::curve::PiecewiseLinear;
 use sp_runtime::transaction_validity::TransactionValidity;
 use sp_runtime::traits::{
 	self, BlakeTwo256, Block as BlockT, NumberFor, StaticLookup, SaturatedConversion,
-	OpaqueKeys,
+	OpaqueKeys, ConvertInto,
 };
 use version::RuntimeVersion;
 #[cfg(any(feature = "std", test))]
@@ -78,8 +82,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to equal spec_version. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 198,
-	impl_version: 198,
+	spec_version: 210,
+	impl_version: 210,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -127,20 +131,62 @@ impl system::Trait for Runtime {
 	type Version = Version;
 }
 
+parameter_types! {
+	// One storage item; value is size 4+4+16+32 bytes = 56 bytes.
+	pub const MultisigDepositBase: Balance = 30 * CENTS;
+	// Additional storage item size of 32 bytes.
+	pub const MultisigDepositFactor: Balance = 5 * CENTS;
+	pub const MaxSignatories: u16 = 100;
+}
+
 impl utility::Trait for Runtime {
 	type Event = Event;
 	type Call = Call;
+	type Currency = Balances;
+	type MultisigDepositBase = MultisigDepositBase;
+	type MultisigDepositFactor = MultisigDepositFactor;
+	type MaxSignatories = MaxSignatories;
+}
+
+parameter_types! {
+	// 80% of `MaximumBlockWeight`.
+	pub const MaximumSchedulerWeight: Weight = 800_000_000;
+}
+
+impl scheduler::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
 }
 
 parameter_types! {
 	pub const EpochDuration: u64 = EPOCH_DURATION_IN_SLOTS;
 	pub const ExpectedBlockTime: Moment = MILLISECS_PER_BLOCK;
+	pub const PrimaryProbability: (u64, u64) = PRIMARY_PROBABILITY;
+	pub const SecondarySlots: bool = true;
 }
 
 impl babe::Trait for Runtime {
 	type EpochDuration = EpochDuration;
 	type ExpectedBlockTime = ExpectedBlockTime;
 	type EpochChangeTrigger = babe::ExternalTrigger;
+	type PrimaryProbability = PrimaryProbability;
+	type SecondarySlots = SecondarySlots;
+	type Call = Call;
+
+	type KeyOwnerProofSystem = session::historical::Module<Self>;
+
+	type KeyOwnerProof = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		babe::AuthorityId,
+	)>>::Proof;
+
+	type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		babe::AuthorityId,
+	)>>::IdentificationTuple;
+
+	type HandleEquivocation = babe::EquivocationHandler<Offences, SubmitTransaction>;
 }
 
 impl indices::Trait for Runtime {
@@ -202,7 +248,10 @@ parameter_types! {
 impl authorship::Trait for Runtime {
 	type FindAuthor = session::FindAccountFromAuthorIndex<Self, Babe>;
 	type UncleGenerations = UncleGenerations;
-	type FilterUncle = ();
+	type FilterUncle = authorship::OnePerAuthorPerHeight<
+		session::FindAccountFromAuthorKey<Self, babe::AuthorityId, Babe>,
+		BlockNumber,
+	>;
 	type EventHandler = (Staking, ImOnline);
 }
 
@@ -252,6 +301,11 @@ parameter_types! {
 	pub const BondingDuration: staking::EraIndex = 24 * 28;
 	pub const SlashDeferDuration: staking::EraIndex = 24 * 7; // 1/4 the bonding duration.
 	pub const RewardCurve: &'static PiecewiseLinear<'static> = &REWARD_CURVE;
+	pub const MaxNominatorRewardedPerValidator: u32 = 64;
+	pub const HistoryDepth: staking::EraIndex = 84;
+	pub const OffchainElection: bool = true;
+	pub const ElectionSolutionDeposit: Balance = 10 * DOLLARS;
+	pub const ElectionLookahead: BlockNumber = EPOCH_DURATION_IN_BLOCKS / 4;
 }
 
 impl staking::Trait for Runtime {
@@ -269,6 +323,15 @@ impl staking::Trait for Runtime {
 	type SlashCancelOrigin = collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
 	type SessionInterface = Self;
 	type RewardCurve = RewardCurve;
+	type MaxNominatorRewardedPerValidator = MaxNominatorRewardedPerValidator;
+	type HistoryDepth = HistoryDepth;
+	type Call = Call;
+	type SubmitTransaction = SubmitTransaction;
+	type KeyType = ImOnlineId;
+	type OffchainElection = OffchainElection;
+	type ElectionSolutionDeposit = ElectionSolutionDeposit;
+	type NextNewSession = Babe;
+	type ElectionLookahead = ElectionLookahead;
 }
 
 parameter_types! {
@@ -311,11 +374,18 @@ impl democracy::Trait for Runtime {
 	type Slash = Treasury;
 }
 
+parameter_types! {
+	pub const CouncilMotionDuration: BlockNumber = 5 * DAYS;
+	pub const CouncilMaxProposals: u32 = 100;
+}
+
 type CouncilCollective = collective::Instance1;
 impl collective::Trait<CouncilCollective> for Runtime {
 	type Origin = Origin;
 	type Proposal = Call;
 	type Event = Event;
+	type MotionDuration = CouncilMotionDuration;
+	type MaxProposals = CouncilMaxProposals;
 }
 
 parameter_types! {
@@ -341,11 +411,18 @@ impl elections_phragmen::Trait for Runtime {
 	type ChangeMembers = Council;
 }
 
+parameter_types! {
+	pub const TechnicalMotionDuration: BlockNumber = 5 * DAYS;
+	pub const TechnicalMaxProposals: u32 = 100;
+}
+
 type TechnicalCollective = collective::Instance2;
 impl collective::Trait<TechnicalCollective> for Runtime {
 	type Origin = Origin;
 	type Proposal = Call;
 	type Event = Event;
+	type MotionDuration = TechnicalMotionDuration;
+	type MaxProposals = TechnicalMaxProposals;
 }
 
 impl membership::Trait<membership::Instance1> for Runtime {
@@ -354,6 +431,7 @@ impl membership::Trait<membership::Instance1> for Runtime {
 	type RemoveOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
 	type SwapOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
 	type ResetOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+	type PrimeOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
 	type MembershipInitialized = TechnicalCommittee;
 	type MembershipChanged = TechnicalCommittee;
 }
@@ -363,12 +441,29 @@ parameter_types! {
 	pub const ProposalBondMinimum: Balance = 1 * DOLLARS;
 	pub const SpendPeriod: BlockNumber = 1 * DAYS;
 	pub const Burn: Permill = Permill::from_percent(50);
+	pub const TipCountdown: BlockNumber = 1 * DAYS;
+	pub const TipFindersFee: Permill = Permill::from_percent(20);
+	pub const TipReportDepositBase: Balance = 1 * DOLLARS;
+	pub const DataDepositPerByte: Balance = 1 * CENTS;
+	pub const MaximumReasonLength: u32 = 16384;
+	pub const BountyDepositBase: Balance = 1 * DOLLARS;
+	pub const BountyDepositPayoutDelay: BlockNumber = 8 * DAYS;
+	pub const BountyValueMinimum: Balance = 10 * DOLLARS;
 }
 
 impl treasury::Trait for Runtime {
 	type Currency = Balances;
 	type ApproveOrigin = collective::EnsureMembers<_4, AccountId, CouncilCollective>;
 	type RejectOrigin = collective::EnsureMembers<_2, AccountId, CouncilCollective>;
+	type Tippers = Elections;
+	type TipCountdown = TipCountdown;
+	type TipFindersFee = TipFindersFee;
+	type TipReportDepositBase = TipReportDepositBase;
+	type DataDepositPerByte = DataDepositPerByte;
+	type MaximumReasonLength = MaximumReasonLength;
+	type BountyDepositBase = BountyDepositBase;
+	type BountyDepositPayoutDelay = BountyDepositPayoutDelay;
+	type BountyValueMinimum = BountyValueMinimum;
 	type Event = Event;
 	type ProposalRejection = ();
 	type ProposalBond = ProposalBond;
@@ -448,6 +543,21 @@ impl authority_discovery::Trait for Runtime {}
 
 impl grandpa::Trait for Runtime {
 	type Event = Event;
+	type Call = Call;
+
+	type KeyOwnerProofSystem = session::historical::Module<Self>;
+
+	type KeyOwnerProof = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		grandpa::AuthorityId,
+	)>>::Proof;
+
+	type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		grandpa::AuthorityId,
+	)>>::IdentificationTuple;
+
+	type HandleEquivocation = grandpa::EquivocationHandler<Offences, SubmitTransaction>;
 }
 
 parameter_types! {
@@ -477,6 +587,112 @@ impl nicks::Trait for Runtime {
 	type MaxLength = MaxLength;
 }
 
+parameter_types! {
+	// One storage item; value is size 4+4+16+32 bytes = 56 bytes.
+	pub const ProxyDepositBase: Balance = 30 * CENTS;
+	// Additional storage item size of 32 bytes.
+	pub const ProxyDepositFactor: Balance = 5 * CENTS;
+	pub const MaxProxies: u16 = 32;
+}
+
+/// The kinds of proxy an account may register.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+pub enum ProxyType {
+	/// May dispatch any call.
+	Any,
+	/// May dispatch any call that cannot move funds.
+	NonTransfer,
+	/// May only dispatch calls of the governance modules.
+	Governance,
+	/// May only dispatch calls of the staking and session modules.
+	Staking,
+}
+
+impl Default for ProxyType { fn default() -> Self { ProxyType::Any } }
+
+impl InstanceFilter<Call> for ProxyType {
+	fn filter(&self, c: &Call) -> bool {
+		match self {
+			ProxyType::Any => true,
+			// only calls which can't move funds out of the account are allowed. calls which
+			// dispatch other calls (utility, sudo, recovery and proxy) aren't, since the nested
+			// calls wouldn't be filtered.
+			ProxyType::NonTransfer => match c {
+				Call::System(..) | Call::Staking(..) | Call::Session(..) | Call::Democracy(..)
+					| Call::Council(..) | Call::TechnicalCommittee(..) | Call::Elections(..)
+					| Call::TechnicalMembership(..) | Call::Grandpa(..) | Call::Treasury(..)
+					| Call::Nicks(..) => true,
+				Call::Vesting(vesting::Call::vest(..)) | Call::Vesting(vesting::Call::vest_other(..))
+					=> true,
+				Call::Proxy(proxy::Call::proxy(..)) => false,
+				Call::Proxy(..) => true,
+				Call::Recovery(recovery::Call::as_recovered(..)) => false,
+				Call::Recovery(..) => true,
+				_ => false,
+			},
+			ProxyType::Governance => match c {
+				Call::Democracy(..) | Call::Council(..) | Call::TechnicalCommittee(..)
+					| Call::Elections(..) | Call::Treasury(..) => true,
+				_ => false,
+			},
+			ProxyType::Staking => match c {
+				Call::Staking(..) | Call::Session(..) => true,
+				_ => false,
+			},
+		}
+	}
+
+	fn is_superset(&self, o: &Self) -> bool {
+		match (self, o) {
+			(x, y) if x == y => true,
+			(ProxyType::Any, _) => true,
+			(_, ProxyType::Any) => false,
+			(ProxyType::NonTransfer, _) => true,
+			_ => false,
+		}
+	}
+}
+
+impl proxy::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type ProxyType = ProxyType;
+	type ProxyDepositBase = ProxyDepositBase;
+	type ProxyDepositFactor = ProxyDepositFactor;
+	type MaxProxies = MaxProxies;
+}
+
+parameter_types! {
+	pub const ConfigDepositBase: Balance = 5 * DOLLARS;
+	pub const FriendDepositFactor: Balance = 50 * CENTS;
+	pub const MaxFriends: u16 = 9;
+	pub const RecoveryDeposit: Balance = 5 * DOLLARS;
+}
+
+impl recovery::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type ConfigDepositBase = ConfigDepositBase;
+	type FriendDepositFactor = FriendDepositFactor;
+	type MaxFriends = MaxFriends;
+	type RecoveryDeposit = RecoveryDeposit;
+}
+
+parameter_types! {
+	pub const MinVestedTransfer: Balance = 100 * DOLLARS;
+	pub const MaxVestingSchedules: u32 = 28;
+}
+
+impl vesting::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BlockNumberToBalance = ConvertInto;
+	type MinVestedTransfer = MinVestedTransfer;
+	type MaxVestingSchedules = MaxVestingSchedules;
+}
+
 impl system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for Runtime {
 	type Public = <Signature as traits::Verify>::Signer;
 	type Signature = Signature;
@@ -514,14 +730,14 @@ construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic
 	{
 		System: system::{Module, Call, Storage, Config, Event},
-		Utility: utility::{Module, Call, Event},
-		Babe: babe::{Module, Call, Storage, Config, Inherent(Timestamp)},
+		Utility: utility::{Module, Call, Storage, Event<T>},
+		Babe: babe::{Module, Call, Storage, Config, Inherent(Timestamp), ValidateUnsigned},
 		Timestamp: timestamp::{Module, Call, Storage, Inherent},
 		Authorship: authorship::{Module, Call, Storage, Inherent},
 		Indices: indices,
 		Balances: balances::{default, Error},
 		TransactionPayment: transaction_payment::{Module, Storage},
-		Staking: staking::{default, OfflineWorker},
+		Staking: staking::{default, OfflineWorker, ValidateUnsigned},
 		Session: session::{Module, Call, Storage, Event, Config<T>},
 		Democracy: democracy::{Module, Call, Storage, Config, Event<T>},
 		Council: collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
@@ -529,7 +745,7 @@ construct_runtime!(
 		Elections: elections_phragmen::{Module, Call, Storage, Event<T>},
 		TechnicalMembership: membership::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
 		FinalityTracker: finality_tracker::{Module, Call, Inherent},
-		Grandpa: grandpa::{Module, Call, Storage, Config, Event},
+		Grandpa: grandpa::{Module, Call, Storage, Config, Event, ValidateUnsigned},
 		Treasury: treasury::{Module, Call, Storage, Config, Event<T>},
 		Contracts: contracts,
 		Sudo: sudo,
@@ -538,6 +754,10 @@ construct_runtime!(
 		Offences: offences::{Module, Call, Storage, Event},
 		RandomnessCollectiveFlip: randomness_collective_flip::{Module, Call, Storage},
 		Nicks: nicks::{Module, Call, Storage, Event<T>},
+		Scheduler: scheduler::{Module, Call, Storage, Event<T>},
+		Proxy: proxy::{Module, Call, Storage, Event},
+		Recovery: recovery::{Module, Call, Storage, Event<T>},
+		Vesting: vesting::{Module, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -629,6 +849,29 @@ impl_runtime_apis! {
 		fn grandpa_authorities() -> GrandpaAuthorityList {
 			Grandpa::grandpa_authorities()
 		}
+
+		fn generate_key_ownership_proof(
+			_set_id: fg_primitives::SetId,
+			authority_id: fg_primitives::AuthorityId,
+		) -> Option<fg_primitives::OpaqueKeyOwnershipProof> {
+			use codec::Encode;
+
+			session::historical::Module::<Runtime>::prove((fg_primitives::KEY_TYPE, authority_id))
+				.map(|p| p.encode())
+				.map(fg_primitives::OpaqueKeyOwnershipProof::new)
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: fg_primitives::EquivocationProof<
+				<Block as BlockT>::Hash,
+				NumberFor<Block>,
+			>,
+			key_owner_proof: fg_primitives::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			let key_owner_proof = key_owner_proof.decode()?;
+
+			Grandpa::submit_unsigned_equivocation_report(equivocation_proof, key_owner_proof)
+		}
 	}
 
 	impl babe_primitives::BabeApi<Block> for Runtime {
@@ -641,12 +884,32 @@ impl_runtime_apis! {
 			babe_primitives::BabeConfiguration {
 				slot_duration: Babe::slot_duration(),
 				epoch_length: EpochDuration::get(),
-				c: PRIMARY_PROBABILITY,
+				c: PrimaryProbability::get(),
 				genesis_authorities: Babe::authorities(),
 				randomness: Babe::randomness(),
-				secondary_slots: true,
+				secondary_slots: SecondarySlots::get(),
 			}
 		}
+
+		fn generate_key_ownership_proof(
+			_slot_number: babe_primitives::SlotNumber,
+			authority_id: babe_primitives::AuthorityId,
+		) -> Option<babe_primitives::OpaqueKeyOwnershipProof> {
+			use codec::Encode;
+
+			session::historical::Module::<Runtime>::prove((babe_primitives::KEY_TYPE, authority_id))
+				.map(|p| p.encode())
+				.map(babe_primitives::OpaqueKeyOwnershipProof::new)
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: babe_primitives::EquivocationProof<<Block as BlockT>::Header>,
+			key_owner_proof: babe_primitives::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			let key_owner_proof = key_owner_proof.decode()?;
+
+			Babe::submit_unsigned_equivocation_report(equivocation_proof, key_owner_proof)
+		}
 	}
 
 	impl authority_discovery_primitives::AuthorityDiscoveryApi<Block> for Runtime {
@@ -733,6 +996,44 @@ mod tests {
 		>,
 	{}
 
+	#[test]
+	fn non_transfer_proxy_cannot_move_funds() {
+		let account = AccountId::default();
+		let dest = Indices::unlookup(account.clone());
+		let transfer = Call::Balances(balances::Call::transfer(dest.clone(), 1));
+		let schedule = vesting::VestingInfo { locked: 10, per_block: 1, starting_block: 0 };
+
+		let moving_funds = vec![
+			transfer.clone(),
+			Call::Utility(utility::Call::batch(vec![transfer.clone()])),
+			Call::Vesting(vesting::Call::vested_transfer(dest.clone(), schedule)),
+			Call::Recovery(recovery::Call::as_recovered(account.clone(), Box::new(transfer.clone()))),
+			Call::Proxy(proxy::Call::proxy(account.clone(), None, Box::new(transfer.clone()))),
+			Call::Contracts(contracts::Call::call(dest, 1, 0, vec![])),
+			Call::Sudo(sudo::Call::sudo(Box::new(transfer.clone()))),
+		];
+
+		for call in &moving_funds {
+			assert!(ProxyType::Any.filter(call));
+			assert!(!ProxyType::NonTransfer.filter(call), "{:?} is allowed", call);
+		}
+
+		let not_moving_funds = vec![
+			Call::System(system::Call::remark(vec![])),
+			Call::Staking(staking::Call::chill()),
+			Call::Vesting(vesting::Call::vest()),
+			Call::Proxy(proxy::Call::add_proxy(account.clone(), ProxyType::Staking)),
+		];
+
+		for call in &not_moving_funds {
+			assert!(ProxyType::NonTransfer.filter(call), "{:?} is not allowed", call);
+		}
+
+		assert!(ProxyType::NonTransfer.is_superset(&ProxyType::Governance));
+		assert!(ProxyType::NonTransfer.is_superset(&ProxyType::Staking));
+		assert!(!ProxyType::NonTransfer.is_superset(&ProxyType::Any));
+	}
+
 	#[test]
 	fn validate_bounds() {
 		let x = SubmitTransaction::default();
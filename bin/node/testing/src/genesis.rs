@@ -49,7 +49,6 @@ pub fn config(support_changes_trie: bool, code: Option<&[u8]>) -> GenesisConfig
 				(eve(), 101 * DOLLARS),
 				(ferdie(), 100 * DOLLARS),
 			],
-			vesting: vec![],
 		}),
 		session: Some(SessionConfig {
 			keys: vec![
@@ -96,5 +95,6 @@ pub fn config(support_changes_trie: bool, code: Option<&[u8]>) -> GenesisConfig
 		membership_Instance1: Some(Default::default()),
 		sudo: Some(Default::default()),
 		treasury: Some(Default::default()),
+		vesting: Some(Default::default()),
 	}
 }
@@ -36,7 +36,8 @@ use network::{
 	self,
 	multiaddr::Protocol,
 	config::{
-		NetworkConfiguration, TransportConfig, NonReservedPeerMode, NodeKeyConfig, build_multiaddr
+		BandwidthLimits, NetworkConfiguration, TransportConfig, NonReservedPeerMode, NodeKeyConfig,
+		build_multiaddr,
 	},
 };
 use primitives::H256;
@@ -716,6 +717,14 @@ fn fill_network_configuration(
 	};
 
 	config.max_parallel_downloads = cli.max_parallel_downloads;
+	let kib_per_sec = |rate: Option<u64>| rate.map(|rate| rate.saturating_mul(1024));
+	config.bandwidth_limits = BandwidthLimits {
+		peer_download: kib_per_sec(cli.max_peer_download_rate),
+		peer_upload: kib_per_sec(cli.max_peer_upload_rate),
+		download: kib_per_sec(cli.max_download_rate),
+		upload: kib_per_sec(cli.max_upload_rate),
+	};
+	config.sync_mode = cli.sync.into();
 
 	Ok(())
 }
@@ -864,6 +873,8 @@ where
 
 	config.roles = role;
 	config.disable_grandpa = cli.no_grandpa;
+	config.grandpa_pause_voting = cli.grandpa_pause_voting;
+	config.grandpa_voting_rpc = cli.unsafe_grandpa_voting_rpc;
 
 	let client_id = config.client_id();
 	fill_network_configuration(
@@ -223,11 +223,61 @@ pub struct NetworkConfigurationParams {
 	#[structopt(long = "max-parallel-downloads", value_name = "COUNT", default_value = "5")]
 	pub max_parallel_downloads: u32,
 
+	/// Maximum download rate from all peers together, in KiB per second.
+	#[structopt(long = "max-download-rate", value_name = "KIB_PER_SEC")]
+	pub max_download_rate: Option<u64>,
+
+	/// Maximum upload rate to all peers together, in KiB per second.
+	#[structopt(long = "max-upload-rate", value_name = "KIB_PER_SEC")]
+	pub max_upload_rate: Option<u64>,
+
+	/// Maximum download rate from a single peer, in KiB per second.
+	#[structopt(long = "max-peer-download-rate", value_name = "KIB_PER_SEC")]
+	pub max_peer_download_rate: Option<u64>,
+
+	/// Maximum upload rate to a single peer, in KiB per second.
+	#[structopt(long = "max-peer-upload-rate", value_name = "KIB_PER_SEC")]
+	pub max_peer_upload_rate: Option<u64>,
+
+	/// Blockchain syncing mode.
+	///
+	/// - `Full`: Download and validate full blockchain history.
+	///
+	/// - `Fast`: Download blocks without executing them, then download the state of
+	///   a recent finalized block.
+	#[structopt(
+		long = "sync",
+		value_name = "SYNC_MODE",
+		possible_values = &SyncMode::variants(),
+		case_insensitive = true,
+		default_value = "Full"
+	)]
+	pub sync: SyncMode,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub node_key_params: NodeKeyParams
 }
 
+arg_enum! {
+	/// How the chain is synchronized with the network.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum SyncMode {
+		Full,
+		Fast,
+	}
+}
+
+impl Into<network::config::SyncMode> for SyncMode {
+	fn into(self) -> network::config::SyncMode {
+		match self {
+			SyncMode::Full => network::config::SyncMode::Full,
+			SyncMode::Fast => network::config::SyncMode::Fast,
+		}
+	}
+}
+
 arg_enum! {
 	#[allow(missing_docs)]
 	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -432,6 +482,23 @@ pub struct RunCmd {
 	#[structopt(long = "no-grandpa")]
 	pub no_grandpa: bool,
 
+	/// Start the GRANDPA voter with its votes paused.
+	///
+	/// The voter keeps running rounds but only votes on finalized blocks, until voting is
+	/// resumed through the `grandpa_resumeVoting` RPC.
+	#[structopt(long = "grandpa-pause-voting")]
+	pub grandpa_pause_voting: bool,
+
+	/// Expose the RPC methods pausing, holding and resuming the votes of the GRANDPA voter.
+	///
+	/// Anyone reaching them can stop the finality votes of the node, so they are only
+	/// available when the RPC servers listen on local interfaces.
+	#[structopt(
+		long = "unsafe-grandpa-voting-rpc",
+		conflicts_with_all = &[ "rpc-external", "ws-external" ]
+	)]
+	pub unsafe_grandpa_voting_rpc: bool,
+
 	/// Experimental: Run in light client mode.
 	#[structopt(long = "light")]
 	pub light: bool,
@@ -38,18 +38,18 @@ use consensus_common::{
 use consensus_common::import_queue::{
 	Verifier, BasicQueue, BoxBlockImport, BoxJustificationImport, BoxFinalityProofImport,
 };
-use client_api::backend::AuxStore;
+use client_api::{backend::AuxStore, ProvideUncles};
 use client::{
 	blockchain::ProvideCache, BlockOf
 };
 use sp_blockchain::{
-	Result as CResult, well_known_cache_keys::{self, Id as CacheKeyId},
+	HeaderBackend, Result as CResult, well_known_cache_keys::{self, Id as CacheKeyId},
 };
 
 use block_builder_api::BlockBuilder as BlockBuilderApi;
 
 use sp_runtime::{generic::{BlockId, OpaqueDigestItemId}, Justification};
-use sp_runtime::traits::{Block as BlockT, Header, DigestItemFor, ProvideRuntimeApi, Zero, Member};
+use sp_runtime::traits::{Block as BlockT, Header, DigestItemFor, NumberFor, ProvideRuntimeApi, Zero, Member};
 
 use primitives::crypto::Pair;
 use inherents::{InherentDataProviders, InherentData};
@@ -80,6 +80,7 @@ pub use aura_primitives::{
 	},
 };
 pub use consensus_common::SyncOracle;
+pub use slots::{BackoffAuthoringBlocksStrategy, BackoffAuthoringOnFinalizedHeadLagging};
 pub use digest::CompatibleDigestItem;
 
 mod digest;
@@ -143,7 +144,7 @@ impl SlotCompatible for AuraSlotCompatible {
 }
 
 /// Start the aura worker. The returned future should be run in a futures executor.
-pub fn start_aura<B, C, SC, E, I, P, SO, CAW, Error, H>(
+pub fn start_aura<B, C, SC, E, I, P, SO, CAW, BS, Error, H>(
 	slot_duration: SlotDuration,
 	client: Arc<C>,
 	select_chain: SC,
@@ -154,11 +155,13 @@ pub fn start_aura<B, C, SC, E, I, P, SO, CAW, Error, H>(
 	force_authoring: bool,
 	keystore: KeyStorePtr,
 	can_author_with: CAW,
+	backoff_authoring_blocks: Option<BS>,
 ) -> Result<impl futures01::Future<Item = (), Error = ()>, consensus_common::Error> where
 	B: BlockT<Header=H>,
-	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + AuxStore + Send + Sync,
+	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + ProvideUncles<B> + AuxStore + HeaderBackend<B>,
+	C: Send + Sync + 'static,
 	C::Api: AuraApi<B, AuthorityId<P>>,
-	SC: SelectChain<B>,
+	SC: SelectChain<B> + 'static,
 	E: Environment<B, Error=Error> + Send + Sync + 'static,
 	E::Proposer: Proposer<B, Error=Error>,
 	<E::Proposer as Proposer<B>>::Create: Unpin + Send,
@@ -170,6 +173,7 @@ pub fn start_aura<B, C, SC, E, I, P, SO, CAW, Error, H>(
 	Error: ::std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
 	SO: SyncOracle + Send + Sync + Clone,
 	CAW: CanAuthorWith<B> + Send,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync + 'static,
 {
 	let worker = AuraWorker {
 		client: client.clone(),
@@ -178,12 +182,22 @@ pub fn start_aura<B, C, SC, E, I, P, SO, CAW, Error, H>(
 		keystore,
 		sync_oracle: sync_oracle.clone(),
 		force_authoring,
+		backoff_authoring_blocks,
 		_key_type: PhantomData::<P>,
 	};
 	register_aura_inherent_data_provider(
 		&inherent_data_providers,
 		slot_duration.0.slot_duration()
 	)?;
+	uncles::register_filtered_uncles_inherent_data_provider(
+		client.clone(),
+		select_chain.clone(),
+		{
+			let client = client.clone();
+			move |uncle: &B::Header| is_valid_uncle::<B, C, P>(&*client, uncle)
+		},
+		&inherent_data_providers,
+	)?;
 	Ok(slots::start_slot_worker::<_, _, _, _, _, AuraSlotCompatible, _>(
 		slot_duration.0,
 		select_chain,
@@ -195,19 +209,20 @@ pub fn start_aura<B, C, SC, E, I, P, SO, CAW, Error, H>(
 	).map(|()| Ok::<(), ()>(())).compat())
 }
 
-struct AuraWorker<C, E, I, P, SO> {
+struct AuraWorker<C, E, I, P, SO, BS> {
 	client: Arc<C>,
 	block_import: Arc<Mutex<I>>,
 	env: E,
 	keystore: KeyStorePtr,
 	sync_oracle: SO,
 	force_authoring: bool,
+	backoff_authoring_blocks: Option<BS>,
 	_key_type: PhantomData<P>,
 }
 
-impl<H, B, C, E, I, P, Error, SO> slots::SimpleSlotWorker<B> for AuraWorker<C, E, I, P, SO> where
+impl<H, B, C, E, I, P, Error, SO, BS> slots::SimpleSlotWorker<B> for AuraWorker<C, E, I, P, SO, BS> where
 	B: BlockT<Header=H>,
-	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + Sync,
+	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + HeaderBackend<B> + Sync,
 	C::Api: AuraApi<B, AuthorityId<P>>,
 	E: Environment<B, Error=Error>,
 	E::Proposer: Proposer<B, Error=Error>,
@@ -219,6 +234,7 @@ impl<H, B, C, E, I, P, Error, SO> slots::SimpleSlotWorker<B> for AuraWorker<C, E
 	P::Signature: Member + Encode + Decode + Hash + Debug,
 	SO: SyncOracle + Send + Clone,
 	Error: ::std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>>,
 {
 	type EpochData = Vec<AuthorityId<P>>;
 	type Claim = P;
@@ -303,6 +319,24 @@ impl<H, B, C, E, I, P, Error, SO> slots::SimpleSlotWorker<B> for AuraWorker<C, E
 		})
 	}
 
+	fn should_backoff(&self, slot_number: u64, chain_head: &B::Header) -> bool {
+		let strategy = match self.backoff_authoring_blocks {
+			Some(ref strategy) => strategy,
+			None => return false,
+		};
+
+		match find_pre_digest::<B, P>(chain_head) {
+			Ok(chain_head_slot) => strategy.should_backoff(
+				*chain_head.number(),
+				chain_head_slot,
+				self.client.info().finalized_number,
+				slot_number,
+				self.logging_target(),
+			),
+			Err(_) => false,
+		}
+	}
+
 	fn proposing_remaining_duration(
 		&self,
 		head: &B::Header,
@@ -330,9 +364,9 @@ impl<H, B, C, E, I, P, Error, SO> slots::SimpleSlotWorker<B> for AuraWorker<C, E
 	}
 }
 
-impl<H, B: BlockT, C, E, I, P, Error, SO> SlotWorker<B> for AuraWorker<C, E, I, P, SO> where
+impl<H, B: BlockT, C, E, I, P, Error, SO, BS> SlotWorker<B> for AuraWorker<C, E, I, P, SO, BS> where
 	B: BlockT<Header=H>,
-	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + Sync + Send,
+	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + HeaderBackend<B> + Sync + Send,
 	C::Api: AuraApi<B, AuthorityId<P>>,
 	E: Environment<B, Error=Error> + Send + Sync,
 	E::Proposer: Proposer<B, Error=Error>,
@@ -344,6 +378,7 @@ impl<H, B: BlockT, C, E, I, P, Error, SO> SlotWorker<B> for AuraWorker<C, E, I,
 	P::Signature: Member + Encode + Decode + Hash + Debug,
 	SO: SyncOracle + Send + Sync + Clone,
 	Error: ::std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync,
 {
 	type OnSlot = Pin<Box<dyn Future<Output = Result<(), consensus_common::Error>> + Send>>;
 
@@ -399,6 +434,39 @@ fn find_pre_digest<B: BlockT, P: Pair>(header: &B::Header) -> Result<u64, Error<
 	pre_digest.ok_or_else(|| aura_err(Error::NoDigestFound))
 }
 
+/// Check that an uncle is sealed according to the Aura rules, i.e. by the authority owning its
+/// slot.
+fn is_valid_uncle<B, C, P>(client: &C, uncle: &B::Header) -> bool where
+	B: BlockT,
+	C: ProvideRuntimeApi + BlockOf + ProvideCache<B>,
+	C::Api: AuraApi<B, AuthorityId<P>>,
+	DigestItemFor<B>: CompatibleDigestItem<P>,
+	P: Pair,
+	P::Signature: Decode,
+	P::Public: Codec + PartialEq + Clone,
+{
+	let mut header = uncle.clone();
+	let signature = match header.digest_mut().pop().and_then(|seal| seal.as_aura_seal()) {
+		Some(signature) => signature,
+		None => return false,
+	};
+
+	let slot_num = match find_pre_digest::<B, P>(&header) {
+		Ok(slot_num) => slot_num,
+		Err(_) => return false,
+	};
+
+	let authorities = match authorities(client, &BlockId::Hash(*header.parent_hash())) {
+		Ok(authorities) => authorities,
+		Err(_) => return false,
+	};
+
+	match slot_author::<P>(slot_num, &authorities) {
+		Some(author) => P::verify(&signature, header.hash().as_ref(), author),
+		None => false,
+	}
+}
+
 /// check a header has been signed by the right key. If the slot is too far in the future, an error will be returned.
 /// if it's successful, returns the pre-header and the digest item containing the seal.
 ///
@@ -896,7 +964,7 @@ mod tests {
 				&inherent_data_providers, slot_duration.get()
 			).expect("Registers aura inherent data provider");
 
-			let aura = start_aura::<_, _, _, _, _, AuthorityPair, _, _, _, _>(
+			let aura = start_aura::<_, _, _, _, _, AuthorityPair, _, _, _, _, _>(
 				slot_duration,
 				client.clone(),
 				select_chain,
@@ -907,6 +975,7 @@ mod tests {
 				false,
 				keystore,
 				consensus_common::AlwaysCanAuthor,
+				Option::<()>::None,
 			).expect("Starts aura");
 
 			runtime.spawn(aura);
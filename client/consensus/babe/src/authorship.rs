@@ -17,7 +17,7 @@
 //! BABE authority selection and slot claiming.
 
 use merlin::Transcript;
-use babe_primitives::{AuthorityId, BabeAuthorityWeight, BABE_ENGINE_ID, BABE_VRF_PREFIX};
+use babe_primitives::{AuthorityId, BabeAuthorityWeight, BABE_VRF_PREFIX};
 use babe_primitives::{Epoch, SlotNumber, AuthorityPair, BabePreDigest, BabeConfiguration};
 use primitives::{U256, blake2_256};
 use codec::Encode;
@@ -82,17 +82,12 @@ pub(super) fn secondary_slot_author(
 	Some(&expected_author.0)
 }
 
-#[allow(deprecated)]
 pub(super) fn make_transcript(
 	randomness: &[u8],
 	slot_number: u64,
 	epoch: u64,
 ) -> Transcript {
-	let mut transcript = Transcript::new(&BABE_ENGINE_ID);
-	transcript.commit_bytes(b"slot number", &slot_number.to_le_bytes());
-	transcript.commit_bytes(b"current epoch", &epoch.to_le_bytes());
-	transcript.commit_bytes(b"chain randomness", randomness);
-	transcript
+	babe_primitives::make_transcript(randomness, slot_number, epoch)
 }
 
 
@@ -63,6 +63,7 @@ pub use babe_primitives::{
 	CompatibleDigestItem,
 };
 pub use consensus_common::SyncOracle;
+pub use slots::{BackoffAuthoringBlocksStrategy, BackoffAuthoringOnFinalizedHeadLagging};
 use std::{collections::HashMap, sync::Arc, u64, pin::Pin, time::{Instant, Duration}};
 use babe_primitives;
 use consensus_common::{ImportResult, CanAuthorWith};
@@ -71,7 +72,7 @@ use consensus_common::import_queue::{
 };
 use sp_runtime::{generic::{BlockId, OpaqueDigestItemId}, Justification};
 use sp_runtime::traits::{
-	Block as BlockT, Header, DigestItemFor, ProvideRuntimeApi,
+	Block as BlockT, Header, DigestItemFor, NumberFor, ProvideRuntimeApi,
 	Zero,
 };
 use keystore::KeyStorePtr;
@@ -113,6 +114,7 @@ mod aux_schema;
 mod verification;
 mod epoch_changes;
 mod authorship;
+pub mod rpc;
 #[cfg(test)]
 mod tests;
 pub use babe_primitives::{
@@ -241,7 +243,7 @@ impl std::ops::Deref for Config {
 }
 
 /// Parameters for BABE.
-pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW> {
+pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW, BS> {
 	/// The keystore that manages the keys of the node.
 	pub keystore: KeyStorePtr,
 
@@ -273,10 +275,13 @@ pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW> {
 
 	/// Checks if the current native implementation can author with a runtime at a given block.
 	pub can_author_with: CAW,
+
+	/// Strategy deciding whether to skip authoring in a slot, e.g. when finality lags behind.
+	pub backoff_authoring_blocks: Option<BS>,
 }
 
 /// Start the babe worker. The returned future should be run in a tokio runtime.
-pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
+pub fn start_babe<B, C, SC, E, I, SO, CAW, BS, Error>(BabeParams {
 	keystore,
 	client,
 	select_chain,
@@ -287,7 +292,8 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
 	force_authoring,
 	babe_link,
 	can_author_with,
-}: BabeParams<B, C, E, I, SO, SC, CAW>) -> Result<
+	backoff_authoring_blocks,
+}: BabeParams<B, C, E, I, SO, SC, CAW, BS>) -> Result<
 	impl futures01::Future<Item=(), Error=()>,
 	consensus_common::Error,
 > where
@@ -303,6 +309,7 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
 	Error: std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
 	SO: SyncOracle + Send + Sync + Clone,
 	CAW: CanAuthorWith<B> + Send,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync + 'static,
 {
 	let config = babe_link.config;
 	let worker = BabeWorker {
@@ -314,12 +321,19 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
 		keystore,
 		epoch_changes: babe_link.epoch_changes.clone(),
 		config: config.clone(),
+		backoff_authoring_blocks,
 	};
 
 	register_babe_inherent_data_provider(&inherent_data_providers, config.slot_duration())?;
-	uncles::register_uncles_inherent_data_provider(
+	uncles::register_filtered_uncles_inherent_data_provider(
 		client.clone(),
 		select_chain.clone(),
+		{
+			let client = client.clone();
+			let epoch_changes = babe_link.epoch_changes.clone();
+			let config = config.clone();
+			move |uncle: &B::Header| is_valid_uncle(&*client, &epoch_changes, &config, uncle)
+		},
 		&inherent_data_providers,
 	)?;
 
@@ -337,7 +351,7 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
 	Ok(slot_worker.map(|_| Ok::<(), ()>(())).compat())
 }
 
-struct BabeWorker<B: BlockT, C, E, I, SO> {
+struct BabeWorker<B: BlockT, C, E, I, SO, BS> {
 	client: Arc<C>,
 	block_import: Arc<Mutex<I>>,
 	env: E,
@@ -346,9 +360,10 @@ struct BabeWorker<B: BlockT, C, E, I, SO> {
 	keystore: KeyStorePtr,
 	epoch_changes: SharedEpochChanges<B>,
 	config: Config,
+	backoff_authoring_blocks: Option<BS>,
 }
 
-impl<B, C, E, I, Error, SO> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I, SO> where
+impl<B, C, E, I, Error, SO, BS> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I, SO, BS> where
 	B: BlockT<Hash=H256>,
 	C: ProvideRuntimeApi + ProvideCache<B> + HeaderBackend<B> + HeaderMetadata<B, Error=ClientError>,
 	C::Api: BabeApi<B>,
@@ -358,6 +373,7 @@ impl<B, C, E, I, Error, SO> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I
 	I: BlockImport<B> + Send + Sync + 'static,
 	SO: SyncOracle + Send + Clone,
 	Error: std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>>,
 {
 	type EpochData = Epoch;
 	type Claim = (BabePreDigest, AuthorityPair);
@@ -461,6 +477,24 @@ impl<B, C, E, I, Error, SO> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I
 		})
 	}
 
+	fn should_backoff(&self, slot_number: u64, chain_head: &B::Header) -> bool {
+		let strategy = match self.backoff_authoring_blocks {
+			Some(ref strategy) => strategy,
+			None => return false,
+		};
+
+		match find_pre_digest::<B>(chain_head) {
+			Ok(pre_digest) => strategy.should_backoff(
+				*chain_head.number(),
+				pre_digest.slot_number(),
+				self.client.info().finalized_number,
+				slot_number,
+				self.logging_target(),
+			),
+			Err(_) => false,
+		}
+	}
+
 	fn proposing_remaining_duration(
 		&self,
 		head: &B::Header,
@@ -488,7 +522,7 @@ impl<B, C, E, I, Error, SO> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I
 	}
 }
 
-impl<B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<B, C, E, I, SO> where
+impl<B, C, E, I, Error, SO, BS> SlotWorker<B> for BabeWorker<B, C, E, I, SO, BS> where
 	B: BlockT<Hash=H256>,
 	C: ProvideRuntimeApi + ProvideCache<B> + HeaderBackend<B> + HeaderMetadata<B, Error=ClientError> + Send + Sync,
 	C::Api: BabeApi<B>,
@@ -498,6 +532,7 @@ impl<B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<B, C, E, I, SO> where
 	I: BlockImport<B> + Send + Sync + 'static,
 	SO: SyncOracle + Send + Sync + Clone,
 	Error: std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync,
 {
 	type OnSlot = Pin<Box<dyn Future<Output = Result<(), consensus_common::Error>> + Send>>;
 
@@ -531,6 +566,57 @@ fn find_pre_digest<B: BlockT>(header: &B::Header) -> Result<BabePreDigest, Error
 	pre_digest.ok_or_else(|| babe_err(Error::NoPreRuntimeDigest))
 }
 
+/// Check that an uncle is sealed according to the BABE rules: its slot must have been claimed
+/// by its author in the epoch of the uncle, and the seal must be signed by that author.
+///
+/// The runtime only checks the seal signature, so the authors use this to leave out uncles whose
+/// slot claim is invalid.
+fn is_valid_uncle<B, C>(
+	client: &C,
+	epoch_changes: &SharedEpochChanges<B>,
+	config: &Config,
+	uncle: &B::Header,
+) -> bool where
+	B: BlockT<Hash=H256>,
+	C: HeaderMetadata<B, Error=ClientError>,
+{
+	let pre_digest = match find_pre_digest::<B>(uncle) {
+		Ok(pre_digest) => pre_digest,
+		Err(_) => return false,
+	};
+
+	let parent_hash = *uncle.parent_hash();
+	let parent_number = match client.header_metadata(parent_hash) {
+		Ok(parent) => parent.number,
+		Err(_) => return false,
+	};
+
+	let epoch = match epoch_changes.lock().epoch_for_child_of(
+		descendent_query(client),
+		&parent_hash,
+		parent_number,
+		pre_digest.slot_number(),
+		|slot| config.genesis_epoch(slot),
+	) {
+		Ok(Some(epoch)) => epoch,
+		_ => return false,
+	};
+
+	// uncles are past blocks, so their slot is never in the future.
+	let v_params = verification::VerificationParams {
+		header: uncle.clone(),
+		pre_digest: Some(pre_digest.clone()),
+		slot_now: pre_digest.slot_number(),
+		epoch: epoch.as_ref(),
+		config,
+	};
+
+	match verification::check_header::<B>(v_params) {
+		Ok(CheckedHeader::Checked(..)) => true,
+		_ => false,
+	}
+}
+
 /// Extract the BABE epoch change digest from the given header, if it exists.
 fn find_next_epoch_digest<B: BlockT>(header: &B::Header)
 	-> Result<Option<NextEpochDescriptor>, Error<B>>
@@ -575,6 +661,19 @@ pub struct BabeLink<Block: BlockT> {
 	epoch_changes: SharedEpochChanges<Block>,
 	config: Config,
 }
+
+impl<Block: BlockT> BabeLink<Block> {
+	/// Get the epoch changes of this link.
+	pub fn epoch_changes(&self) -> &SharedEpochChanges<Block> {
+		&self.epoch_changes
+	}
+
+	/// Get the config of this link.
+	pub fn config(&self) -> &Config {
+		&self.config
+	}
+}
+
 /// A verifier for Babe blocks.
 pub struct BabeVerifier<B, E, Block: BlockT, RA, PRA> {
 	client: Arc<Client<B, E, Block, RA>>,
@@ -612,6 +711,67 @@ impl<B, E, Block: BlockT, RA, PRA> BabeVerifier<B, E, Block, RA, PRA> {
 	}
 }
 
+impl<B, E, Block, RA, PRA> BabeVerifier<B, E, Block, RA, PRA> where
+	Block: BlockT,
+	B: Backend<Block, Blake2Hasher> + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + 'static + Clone + Send + Sync,
+	PRA: ProvideRuntimeApi,
+	PRA::Api: BabeApi<Block, Error = sp_blockchain::Error>,
+{
+	/// Submit a report for the given equivocation through the runtime, which
+	/// will push an unsigned extrinsic to the transaction pool. The key
+	/// ownership proof and the report are both created at the best block.
+	fn report_equivocation(
+		&self,
+		equivocation_proof: slots::EquivocationProof<Block::Header>,
+		offender: &AuthorityId,
+	) -> Result<(), Error<Block>> {
+		let best_id = BlockId::Hash(self.client.info().chain.best_hash);
+		let runtime_api = self.api.runtime_api();
+
+		// equivocation reporting is only available from version 2 of the
+		// runtime API onwards.
+		let has_equivocation_reporting = runtime_api
+			.has_api_with::<dyn BabeApi<Block, Error = sp_blockchain::Error>, _>(
+				&best_id,
+				|version| version >= 2,
+			)
+			.map_err(Error::Client)?;
+
+		if !has_equivocation_reporting {
+			debug!(target: "babe", "Runtime doesn't support equivocation reporting.");
+			return Ok(());
+		}
+
+		let slot_number = equivocation_proof.slot();
+		let key_owner_proof = match runtime_api
+			.generate_key_ownership_proof(&best_id, slot_number, offender.clone())
+			.map_err(Error::Client)?
+		{
+			Some(proof) => proof,
+			None => {
+				debug!(target: "babe", "Equivocation offender is not part of the authority set.");
+				return Ok(());
+			}
+		};
+
+		let equivocation_proof = babe_primitives::EquivocationProof {
+			offender: offender.clone(),
+			slot_number,
+			first_header: equivocation_proof.fst_header().clone(),
+			second_header: equivocation_proof.snd_header().clone(),
+		};
+
+		runtime_api
+			.submit_report_equivocation_unsigned_extrinsic(&best_id, equivocation_proof, key_owner_proof)
+			.map_err(Error::Client)?;
+
+		info!(target: "babe", "Submitted equivocation report for author {:?}", offender);
+
+		Ok(())
+	}
+}
+
 #[allow(dead_code)]
 fn median_algorithm(
 	median_required_blocks: u64,
@@ -744,6 +904,10 @@ impl<B, E, Block, RA, PRA> Verifier<Block> for BabeVerifier<B, E, Block, RA, PRA
 						equivocation_proof.fst_header().hash(),
 						equivocation_proof.snd_header().hash(),
 					);
+
+					if let Err(err) = self.report_equivocation(equivocation_proof, &author) {
+						warn!(target: "babe", "Error reporting equivocation: {}", err);
+					}
 				}
 
 				// if the body is passed through, we need to use the runtime
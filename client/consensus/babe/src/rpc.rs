@@ -0,0 +1,244 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! RPC interface for the BABE consensus algorithm.
+
+use std::{collections::HashMap, sync::Arc};
+use babe_primitives::{
+	AuthorityId, AuthorityPair, BabeConfiguration, BabePreDigest, Epoch, SlotNumber,
+};
+use consensus_common::SelectChain;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use keystore::KeyStorePtr;
+use primitives::{H256, Pair};
+use serde::{Deserialize, Serialize};
+use sp_blockchain::{HeaderBackend, HeaderMetadata, Error as ClientError};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use crate::{Config, SharedEpochChanges, authorship, epoch_changes::descendent_query};
+
+pub use self::gen_client::Client as BabeClient;
+
+/// Slots of the current epoch that can be claimed by a local authority key.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochAuthorship {
+	/// Slots claimed through the VRF.
+	pub primary: Vec<SlotNumber>,
+	/// Secondary slots assigned to the authority.
+	pub secondary: Vec<SlotNumber>,
+}
+
+/// BABE RPC API.
+#[rpc]
+pub trait BabeApi {
+	/// List, for each local authority key, the slots it can claim in the current epoch.
+	#[rpc(name = "babe_epochAuthorship")]
+	fn epoch_authorship(&self) -> Result<HashMap<AuthorityId, EpochAuthorship>>;
+}
+
+/// Implementation of the BABE RPC API.
+pub struct BabeRpcHandler<B: BlockT, C, SC> {
+	client: Arc<C>,
+	epoch_changes: SharedEpochChanges<B>,
+	keystore: KeyStorePtr,
+	config: Config,
+	select_chain: SC,
+}
+
+impl<B: BlockT, C, SC> BabeRpcHandler<B, C, SC> {
+	/// Create a new instance, typically with the epoch changes and config of the `BabeLink`.
+	pub fn new(
+		client: Arc<C>,
+		epoch_changes: SharedEpochChanges<B>,
+		keystore: KeyStorePtr,
+		config: Config,
+		select_chain: SC,
+	) -> Self {
+		BabeRpcHandler { client, epoch_changes, keystore, config, select_chain }
+	}
+}
+
+impl<B, C, SC> BabeApi for BabeRpcHandler<B, C, SC> where
+	B: BlockT<Hash=H256>,
+	C: HeaderBackend<B> + HeaderMetadata<B, Error=ClientError> + Send + Sync + 'static,
+	SC: SelectChain<B> + 'static,
+{
+	fn epoch_authorship(&self) -> Result<HashMap<AuthorityId, EpochAuthorship>> {
+		let header = self.select_chain.best_chain().map_err(internal_error)?;
+		let slot_number = slots::SignedDuration::default().slot_now(self.config.slot_duration);
+
+		let epoch = self.epoch_changes.lock().epoch_for_child_of(
+			descendent_query(&*self.client),
+			&header.hash(),
+			*header.number(),
+			slot_number,
+			|slot| self.config.genesis_epoch(slot),
+		)
+			.map_err(|e| internal_error(format!("{:?}", e)))?
+			.ok_or_else(|| internal_error("No epoch data for the best block"))?
+			.into_inner();
+
+		Ok(claimable_slots(&epoch, &self.config, &self.keystore))
+	}
+}
+
+/// List, for each authority of the epoch with a key in the keystore, the slots of the epoch
+/// it can claim.
+fn claimable_slots(
+	epoch: &Epoch,
+	config: &BabeConfiguration,
+	keystore: &KeyStorePtr,
+) -> HashMap<AuthorityId, EpochAuthorship> {
+	// local keys without any claimable slot are listed as well.
+	let mut claims: HashMap<AuthorityId, EpochAuthorship> = {
+		let keystore = keystore.read();
+		epoch.authorities.iter()
+			.filter(|(id, _)| keystore.key_pair::<AuthorityPair>(id).is_ok())
+			.map(|(id, _)| (id.clone(), Default::default()))
+			.collect()
+	};
+
+	for slot_number in epoch.start_slot..epoch.end_slot() {
+		let claim = authorship::claim_slot(slot_number, epoch, config, keystore);
+		if let Some((pre_digest, pair)) = claim {
+			let authorship = claims.entry(pair.public()).or_default();
+			match pre_digest {
+				BabePreDigest::Primary { .. } => authorship.primary.push(slot_number),
+				BabePreDigest::Secondary { .. } => authorship.secondary.push(slot_number),
+			}
+		}
+	}
+
+	claims
+}
+
+fn internal_error(e: impl ToString) -> RpcError {
+	RpcError {
+		code: ErrorCode::InternalError,
+		message: e.to_string(),
+		data: None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::{IoHandler, serde_json};
+	use keyring::Sr25519Keyring;
+	use test_client::{DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt};
+
+	fn keystore_with(seed: &str) -> (KeyStorePtr, tempfile::TempDir) {
+		let keystore_path = tempfile::tempdir().expect("Creates keystore path");
+		let keystore = keystore::Store::open(keystore_path.path(), None)
+			.expect("Creates keystore");
+		keystore.write().insert_ephemeral_from_seed::<AuthorityPair>(seed)
+			.expect("Generates authority pair");
+		(keystore, keystore_path)
+	}
+
+	fn config(c: (u64, u64), secondary_slots: bool) -> BabeConfiguration {
+		BabeConfiguration {
+			slot_duration: 1000,
+			epoch_length: 10,
+			c,
+			genesis_authorities: Vec::new(),
+			randomness: [0; 32],
+			secondary_slots,
+		}
+	}
+
+	#[test]
+	fn claimable_slots_of_local_keys_are_listed() {
+		let (keystore, _keystore_path) = keystore_with("//Alice");
+		let alice: AuthorityId = Sr25519Keyring::Alice.public().into();
+		let bob: AuthorityId = Sr25519Keyring::Bob.public().into();
+		let epoch = Epoch {
+			start_slot: 20,
+			authorities: vec![(alice.clone(), 1), (bob.clone(), 1)],
+			randomness: [0; 32],
+			epoch_index: 2,
+			duration: 10,
+		};
+
+		// every slot of the epoch is a primary slot of every authority.
+		let claims = claimable_slots(&epoch, &config((1, 1), true), &keystore);
+		assert_eq!(claims.len(), 1);
+		assert_eq!(claims[&alice], EpochAuthorship {
+			primary: (20..30).collect(),
+			secondary: Vec::new(),
+		});
+
+		// no primary slots, the secondary slots are shared between the authorities.
+		let claims = claimable_slots(&epoch, &config((0, 1), true), &keystore);
+		let secondary = (20..30)
+			.filter(|slot| {
+				authorship::secondary_slot_author(*slot, &epoch.authorities, epoch.randomness)
+					== Some(&alice)
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(claims.len(), 1);
+		assert_eq!(claims[&alice], EpochAuthorship { primary: Vec::new(), secondary });
+
+		// a local key without any slot to claim is listed as well.
+		let claims = claimable_slots(&epoch, &config((0, 1), false), &keystore);
+		assert_eq!(claims.len(), 1);
+		assert_eq!(claims[&alice], EpochAuthorship::default());
+
+		// keys that aren't authorities of the epoch aren't listed.
+		let (keystore, _keystore_path) = keystore_with("//Charlie");
+		assert!(claimable_slots(&epoch, &config((1, 1), true), &keystore).is_empty());
+	}
+
+	#[test]
+	fn epoch_authorship_rpc_lists_the_slots_of_the_current_epoch() {
+		let (client, select_chain) = TestClientBuilder::new().build_with_longest_chain();
+		let client = Arc::new(client);
+		let config = Config::get_or_compute(&*client).expect("BABE config exists at genesis");
+		let epoch_changes = crate::aux_schema::load_epoch_changes(&*client)
+			.expect("Loads epoch changes");
+		let (keystore, _keystore_path) = keystore_with("//Alice");
+
+		let handler = BabeRpcHandler::new(
+			client.clone(),
+			epoch_changes,
+			keystore.clone(),
+			config.clone(),
+			select_chain,
+		);
+		let mut io = IoHandler::new();
+		io.extend_with(BabeApi::to_delegate(handler));
+
+		// the genesis epoch starts at the current slot, so the call is retried
+		// until it doesn't happen across two slots.
+		let request = r#"{"jsonrpc":"2.0","method":"babe_epochAuthorship","params":[],"id":1}"#;
+		let slot_now = || slots::SignedDuration::default().slot_now(config.slot_duration);
+		let (slot_number, response) = loop {
+			let slot_number = slot_now();
+			let response = io.handle_request_sync(request).expect("Returns a response");
+			if slot_number == slot_now() {
+				break (slot_number, response);
+			}
+		};
+
+		let expected = claimable_slots(&config.genesis_epoch(slot_number), &config, &keystore);
+		let alice: AuthorityId = Sr25519Keyring::Alice.public().into();
+		assert!(expected.contains_key(&alice));
+
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["result"], serde_json::to_value(&expected).unwrap());
+	}
+}
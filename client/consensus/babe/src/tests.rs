@@ -404,6 +404,7 @@ fn run_one_test(
 			babe_link: data.link.clone(),
 			keystore,
 			can_author_with: consensus_common::AlwaysCanAuthor,
+			backoff_authoring_blocks: Option::<()>::None,
 		}).expect("Starts babe"));
 	}
 
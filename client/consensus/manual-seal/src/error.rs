@@ -0,0 +1,91 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Errors of the manual seal engine.
+
+use consensus_common::{Error as ConsensusError, ImportResult};
+use jsonrpc_core as rpc;
+
+/// Base code for all manual seal errors.
+const BASE_ERROR: i64 = 15000;
+/// The transaction pool is empty.
+const EMPTY_TRANSACTION_POOL: i64 = BASE_ERROR + 1;
+/// The block couldn't be imported.
+const BLOCK_IMPORT_FAILED: i64 = BASE_ERROR + 2;
+/// The parent block couldn't be found.
+const BLOCK_NOT_FOUND: i64 = BASE_ERROR + 3;
+/// The inherents couldn't be created.
+const INHERENTS_ERROR: i64 = BASE_ERROR + 4;
+/// The engine isn't running.
+const ENGINE_UNAVAILABLE: i64 = BASE_ERROR + 5;
+/// Any other error.
+const OTHER_ERROR: i64 = BASE_ERROR + 100;
+
+/// Manual seal errors.
+#[derive(Debug, derive_more::Display, derive_more::From)]
+pub enum Error {
+	/// The block was not imported.
+	#[display(fmt = "Block import failed: {:?}", _0)]
+	BlockImportError(ImportResult),
+	/// The transaction pool is empty and no empty block was requested.
+	#[display(fmt = "Transaction pool is empty, set create_empty to true to create a block anyway")]
+	EmptyTransactionPool,
+	/// The parent block couldn't be found.
+	#[display(fmt = "Block {} not found", _0)]
+	#[from(ignore)]
+	BlockNotFound(String),
+	/// Creating the inherents failed.
+	#[display(fmt = "Creating inherents failed: {}", _0)]
+	InherentError(inherents::Error),
+	/// Consensus error.
+	#[display(fmt = "Consensus error: {}", _0)]
+	ConsensusError(ConsensusError),
+	/// Blockchain error.
+	#[display(fmt = "Blockchain error: {}", _0)]
+	BlockchainError(sp_blockchain::Error),
+	/// The authorship task isn't running anymore.
+	#[display(fmt = "Consensus engine is not running")]
+	EngineUnavailable,
+	/// Any other error.
+	#[display(fmt = "{}", _0)]
+	#[from(ignore)]
+	Other(String),
+}
+
+impl Error {
+	fn code(&self) -> i64 {
+		match self {
+			Error::EmptyTransactionPool => EMPTY_TRANSACTION_POOL,
+			Error::BlockImportError(_) => BLOCK_IMPORT_FAILED,
+			Error::BlockNotFound(_) => BLOCK_NOT_FOUND,
+			Error::InherentError(_) => INHERENTS_ERROR,
+			Error::EngineUnavailable => ENGINE_UNAVAILABLE,
+			_ => OTHER_ERROR,
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for rpc::Error {
+	fn from(error: Error) -> Self {
+		rpc::Error {
+			code: rpc::ErrorCode::ServerError(error.code()),
+			message: error.to_string(),
+			data: None,
+		}
+	}
+}
@@ -0,0 +1,391 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Manual sealing engine, for development and testing.
+//!
+//! The engine never authors blocks on its own. With `run_manual_seal`, a block is sealed each
+//! time an [`EngineCommand`] is received, for example from the `engine_createBlock` RPC. With
+//! `run_instant_seal`, a block is sealed as soon as transactions enter the transaction pool.
+//!
+//! Blocks carry no seal and can be finalized as soon as they are imported. Blocks received from
+//! the network are imported without any check, so that the engine must not be used on a network
+//! with untrusted nodes.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use consensus_common::{
+	BlockImportParams, BlockOrigin, Environment, ForkChoiceStrategy, ImportResult, Proposer,
+	SelectChain,
+};
+use consensus_common::import_queue::{BasicQueue, BoxBlockImport, CacheKeyId, Verifier};
+use futures::prelude::*;
+use inherents::InherentDataProviders;
+use log::{debug, warn};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::Justification;
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use txpool_api::TransactionPool;
+
+mod error;
+pub mod rpc;
+
+pub use self::error::Error;
+pub use self::rpc::{CreatedBlock, EngineCommand};
+
+/// Maximum time spent building a block.
+const MAX_PROPOSAL_DURATION: Duration = Duration::from_secs(10);
+
+/// Verifier of the manual seal engine, accepting all the blocks.
+struct ManualSealVerifier;
+
+impl<B: BlockT> Verifier<B> for ManualSealVerifier {
+	fn verify(
+		&mut self,
+		origin: BlockOrigin,
+		header: B::Header,
+		justification: Option<Justification>,
+		body: Option<Vec<B::Extrinsic>>,
+	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+		let import_block = BlockImportParams {
+			origin,
+			header,
+			justification,
+			post_digests: Vec::new(),
+			body,
+			finalized: false,
+			auxiliary: Vec::new(),
+			fork_choice: ForkChoiceStrategy::LongestChain,
+			allow_missing_state: false,
+			import_existing: false,
+		};
+
+		Ok((import_block, None))
+	}
+}
+
+/// Import queue for the manual seal engine.
+pub fn import_queue<B: BlockT>(block_import: BoxBlockImport<B>) -> BasicQueue<B> {
+	BasicQueue::new(ManualSealVerifier, block_import, None, None)
+}
+
+/// Runs the authorship task of the manual seal engine, sealing a block for each command of
+/// `commands`. The task ends when `commands` is exhausted.
+pub async fn run_manual_seal<B, C, E, P, SC, CS>(
+	mut block_import: BoxBlockImport<B>,
+	mut env: E,
+	client: Arc<C>,
+	pool: Arc<P>,
+	mut commands: CS,
+	select_chain: SC,
+	inherent_data_providers: InherentDataProviders,
+) where
+	B: BlockT + 'static,
+	C: HeaderBackend<B>,
+	E: Environment<B>,
+	E::Error: std::fmt::Debug,
+	P: TransactionPool<Block = B>,
+	SC: SelectChain<B>,
+	CS: Stream<Item = EngineCommand<B::Hash>> + Unpin,
+{
+	while let Some(command) = commands.next().await {
+		match command {
+			EngineCommand::SealNewBlock { create_empty, finalize, parent_hash, sender } => {
+				let result = seal_new_block(
+					&mut block_import,
+					&mut env,
+					client.as_ref(),
+					pool.as_ref(),
+					&select_chain,
+					&inherent_data_providers,
+					create_empty,
+					finalize,
+					parent_hash,
+				).await;
+
+				match sender {
+					Some(sender) => {
+						let _ = sender.send(result);
+					},
+					None => if let Err(err) = result {
+						warn!(target: "manual-seal", "Failed to seal block: {}", err);
+					},
+				}
+			},
+		}
+	}
+}
+
+/// Runs the authorship task of the instant seal engine, sealing a block as soon as transactions
+/// are imported into the pool. With `finalize`, the blocks are finalized right away.
+pub async fn run_instant_seal<B, C, E, P, SC>(
+	block_import: BoxBlockImport<B>,
+	env: E,
+	client: Arc<C>,
+	pool: Arc<P>,
+	select_chain: SC,
+	inherent_data_providers: InherentDataProviders,
+	finalize: bool,
+) where
+	B: BlockT + 'static,
+	C: HeaderBackend<B>,
+	E: Environment<B>,
+	E::Error: std::fmt::Debug,
+	P: TransactionPool<Block = B>,
+	SC: SelectChain<B>,
+{
+	let commands = pool.import_notification_stream().map(move |_| EngineCommand::SealNewBlock {
+		create_empty: false,
+		finalize,
+		parent_hash: None,
+		sender: None,
+	});
+
+	run_manual_seal(
+		block_import,
+		env,
+		client,
+		pool,
+		commands,
+		select_chain,
+		inherent_data_providers,
+	).await
+}
+
+/// Builds a block on top of `parent_hash`, or of the best block, and imports it.
+async fn seal_new_block<B, C, E, P, SC>(
+	block_import: &mut BoxBlockImport<B>,
+	env: &mut E,
+	client: &C,
+	pool: &P,
+	select_chain: &SC,
+	inherent_data_providers: &InherentDataProviders,
+	create_empty: bool,
+	finalize: bool,
+	parent_hash: Option<B::Hash>,
+) -> Result<CreatedBlock<B::Hash>, Error> where
+	B: BlockT,
+	C: HeaderBackend<B>,
+	E: Environment<B>,
+	E::Error: std::fmt::Debug,
+	P: TransactionPool<Block = B>,
+	SC: SelectChain<B>,
+{
+	if !create_empty && pool.status().ready == 0 {
+		return Err(Error::EmptyTransactionPool);
+	}
+
+	let parent = match parent_hash {
+		Some(hash) => client.header(BlockId::Hash(hash))?
+			.ok_or_else(|| Error::BlockNotFound(format!("{}", hash)))?,
+		None => select_chain.best_chain()?,
+	};
+
+	let mut proposer = env.init(&parent)
+		.map_err(|err| Error::Other(format!("Initializing the proposer failed: {:?}", err)))?;
+	let inherent_data = inherent_data_providers.create_inherent_data()?;
+	let block = proposer.propose(inherent_data, Default::default(), MAX_PROPOSAL_DURATION)
+		.await
+		.map_err(|err| Error::Other(format!("Block proposing failed: {:?}", err)))?;
+
+	let (header, body) = block.deconstruct();
+	let hash = header.hash();
+	debug!(target: "manual-seal", "Sealing block #{} ({}) on {}", header.number(), hash, parent.hash());
+
+	let import_block = BlockImportParams {
+		origin: BlockOrigin::Own,
+		header,
+		justification: None,
+		post_digests: Vec::new(),
+		body: Some(body),
+		finalized: finalize,
+		auxiliary: Vec::new(),
+		fork_choice: ForkChoiceStrategy::LongestChain,
+		allow_missing_state: false,
+		import_existing: false,
+	};
+
+	match block_import.import_block(import_block, HashMap::new())? {
+		ImportResult::Imported(aux) => Ok(CreatedBlock { hash, is_new_best: aux.is_new_best, finalized: finalize }),
+		other => Err(other.into()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::thread;
+	use basic_authorship::ProposerFactory;
+	use futures::channel::{mpsc, oneshot};
+	use jsonrpc_core::IoHandler;
+	use test_client::{
+		AccountKeyring, DefaultTestClientBuilderExt, TestClient, TestClientBuilder,
+		TestClientBuilderExt, client::BlockchainEvents,
+		runtime::{Block, Extrinsic, Transfer},
+	};
+	use txpool::{BasicPool, FullChainApi};
+	use txpool_api::TransactionPool as _;
+	use crate::rpc::{ManualSeal, ManualSealApi};
+
+	type TestPool = BasicPool<FullChainApi<TestClient, Block>, Block>;
+
+	fn extrinsic(nonce: u64) -> Extrinsic {
+		Transfer {
+			amount: Default::default(),
+			nonce,
+			from: AccountKeyring::Alice.into(),
+			to: Default::default(),
+		}.into_signed_tx()
+	}
+
+	fn setup() -> (
+		Arc<TestClient>,
+		impl SelectChain<Block> + 'static,
+		Arc<TestPool>,
+		ProposerFactory<TestClient, TestPool>,
+	) {
+		let (client, select_chain) = TestClientBuilder::new().build_with_longest_chain();
+		let client = Arc::new(client);
+		let pool = Arc::new(
+			BasicPool::new(Default::default(), FullChainApi::new(client.clone()))
+		);
+		let env = ProposerFactory {
+			client: client.clone(),
+			transaction_pool: pool.clone(),
+		};
+		(client, select_chain, pool, env)
+	}
+
+	type Commands = mpsc::Sender<EngineCommand<<Block as BlockT>::Hash>>;
+
+	/// Runs the manual seal engine in the background, returning the channel to send it commands.
+	fn run_engine() -> (Arc<TestClient>, Arc<TestPool>, Commands) {
+		let (client, select_chain, pool, env) = setup();
+		let (commands, receiver) = mpsc::channel(16);
+		let block_import: BoxBlockImport<Block> = Box::new(client.clone());
+		let (engine_client, engine_pool) = (client.clone(), pool.clone());
+		thread::spawn(move || futures::executor::block_on(run_manual_seal(
+			block_import,
+			env,
+			engine_client,
+			engine_pool,
+			receiver,
+			select_chain,
+			InherentDataProviders::new(),
+		)));
+		(client, pool, commands)
+	}
+
+	fn create_block(
+		commands: &mut Commands,
+		create_empty: bool,
+		finalize: bool,
+	) -> Result<CreatedBlock<<Block as BlockT>::Hash>, Error> {
+		let (sender, receiver) = oneshot::channel();
+		futures::executor::block_on(async {
+			commands.send(EngineCommand::SealNewBlock {
+				create_empty,
+				finalize,
+				parent_hash: None,
+				sender: Some(sender),
+			}).await.unwrap();
+			receiver.await.unwrap()
+		})
+	}
+
+	#[test]
+	fn instant_seal_seals_a_block_per_transaction_import() {
+		let (client, select_chain, pool, env) = setup();
+		let block_import: BoxBlockImport<Block> = Box::new(client.clone());
+		let mut imported = client.import_notification_stream();
+		let (engine_client, engine_pool) = (client.clone(), pool.clone());
+		thread::spawn(move || futures::executor::block_on(run_instant_seal(
+			block_import,
+			env,
+			engine_client,
+			engine_pool,
+			select_chain,
+			InherentDataProviders::new(),
+			false,
+		)));
+
+		let submit = pool.submit_one(&BlockId::Number(0), extrinsic(0));
+		futures::executor::block_on(submit).unwrap();
+
+		let notification = futures::executor::block_on(imported.next()).unwrap();
+		assert_eq!(*notification.header.number(), 1);
+		assert!(notification.is_new_best);
+
+		let body = client.body(&BlockId::Hash(notification.hash)).unwrap().unwrap();
+		assert_eq!(body, vec![extrinsic(0)]);
+		assert_eq!(client.info().chain.finalized_number, 0);
+	}
+
+	#[test]
+	fn seals_blocks_on_command_and_finalizes_them() {
+		let (client, pool, mut commands) = run_engine();
+
+		// nothing to include.
+		match create_block(&mut commands, false, false) {
+			Err(Error::EmptyTransactionPool) => {},
+			other => panic!("unexpected result: {:?}", other),
+		}
+
+		let submit = pool.submit_one(&BlockId::Number(0), extrinsic(0));
+		futures::executor::block_on(submit).unwrap();
+		let created = create_block(&mut commands, false, false).unwrap();
+		let best_hash = client.info().chain.best_hash;
+		assert_eq!(created, CreatedBlock { hash: best_hash, is_new_best: true, finalized: false });
+		assert_eq!(client.info().chain.best_number, 1);
+		assert_eq!(client.info().chain.finalized_number, 0);
+
+		let created = create_block(&mut commands, true, true).unwrap();
+		let best_hash = client.info().chain.best_hash;
+		assert_eq!(created, CreatedBlock { hash: best_hash, is_new_best: true, finalized: true });
+		assert_eq!(client.info().chain.best_number, 2);
+		assert_eq!(client.info().chain.finalized_hash, created.hash);
+	}
+
+	#[test]
+	fn engine_create_block_rpc_seals_blocks() {
+		let (client, _pool, commands) = run_engine();
+		let mut io = IoHandler::new();
+		io.extend_with(ManualSealApi::to_delegate(ManualSeal::new(commands)));
+
+		let request = r#"{
+			"jsonrpc":"2.0","method":"engine_createBlock","params":[false,false,null],"id":1
+		}"#;
+		let response = concat!(
+			r#"{"jsonrpc":"2.0","error":{"code":15001,"message":"Transaction pool is empty, "#,
+			r#"set create_empty to true to create a block anyway"},"id":1}"#,
+		);
+		assert_eq!(io.handle_request_sync(request), Some(response.into()));
+
+		let request = r#"{
+			"jsonrpc":"2.0","method":"engine_createBlock","params":[true,true,null],"id":1
+		}"#;
+		let response = io.handle_request_sync(request);
+		let info = client.info().chain;
+		assert_eq!(info.best_number, 1);
+		assert_eq!(info.finalized_hash, info.best_hash);
+		assert_eq!(response, Some(format!(
+			concat!(
+				r#"{{"jsonrpc":"2.0","result":{{"hash":"{:?}","#,
+				r#""isNewBest":true,"finalized":true}},"id":1}}"#,
+			),
+			info.best_hash,
+		)));
+	}
+}
@@ -0,0 +1,105 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC interface of the manual seal engine.
+
+use futures::{FutureExt, SinkExt, TryFutureExt, channel::{mpsc, oneshot}};
+use jsonrpc_core::Error as RpcError;
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use crate::Error;
+
+pub use self::gen_client::Client as ManualSealClient;
+
+/// Future result of the RPC methods.
+pub type FutureResult<T> = Box<dyn jsonrpc_core::futures::Future<Item = T, Error = RpcError> + Send>;
+
+/// Command sent to the authorship task of the engine.
+pub enum EngineCommand<Hash> {
+	/// Seals a new block.
+	SealNewBlock {
+		/// Whether to create a block even if there is no transaction in the pool.
+		create_empty: bool,
+		/// Whether to finalize the block right away.
+		finalize: bool,
+		/// Block to build on. Defaults to the best block.
+		parent_hash: Option<Hash>,
+		/// Channel to send the outcome on.
+		sender: Option<oneshot::Sender<Result<CreatedBlock<Hash>, Error>>>,
+	},
+}
+
+/// A block created by the engine.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedBlock<Hash> {
+	/// Hash of the block.
+	pub hash: Hash,
+	/// Whether the block is the new best block.
+	pub is_new_best: bool,
+	/// Whether the block has been finalized.
+	pub finalized: bool,
+}
+
+/// Manual seal RPC API.
+#[rpc]
+pub trait ManualSealApi<Hash> {
+	/// Creates and imports a new block, on top of `parent_hash` or of the best block.
+	#[rpc(name = "engine_createBlock")]
+	fn create_block(
+		&self,
+		create_empty: bool,
+		finalize: bool,
+		parent_hash: Option<Hash>,
+	) -> FutureResult<CreatedBlock<Hash>>;
+}
+
+/// Implementation of the manual seal RPC API, forwarding the calls to the authorship task.
+pub struct ManualSeal<Hash> {
+	commands: mpsc::Sender<EngineCommand<Hash>>,
+}
+
+impl<Hash> ManualSeal<Hash> {
+	/// Creates a new instance sending the commands on `commands`, whose receiving end must be
+	/// passed to `run_manual_seal`.
+	pub fn new(commands: mpsc::Sender<EngineCommand<Hash>>) -> Self {
+		ManualSeal { commands }
+	}
+}
+
+impl<Hash: Send + 'static> ManualSealApi<Hash> for ManualSeal<Hash> {
+	fn create_block(
+		&self,
+		create_empty: bool,
+		finalize: bool,
+		parent_hash: Option<Hash>,
+	) -> FutureResult<CreatedBlock<Hash>> {
+		let mut commands = self.commands.clone();
+		let future = async move {
+			let (sender, receiver) = oneshot::channel();
+			let command = EngineCommand::SealNewBlock {
+				create_empty,
+				finalize,
+				parent_hash,
+				sender: Some(sender),
+			};
+			commands.send(command).await.map_err(|_| Error::EngineUnavailable)?;
+			receiver.await.map_err(|_| Error::EngineUnavailable)?
+		};
+
+		Box::new(future.boxed().map_err(RpcError::from).compat())
+	}
+}
@@ -0,0 +1,192 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Difficulty adjustment.
+//!
+//! The bundled algorithms take the difficulty of the next block from a [`DifficultySource`].
+//! [`MovingWindow`] adjusts it so that blocks are produced at a target rate, based on the
+//! timestamps of the last blocks of the chain, as given by the `TimestampApi` runtime API.
+
+use std::{marker::PhantomData, sync::Arc};
+use client_api::backend::AuxStore;
+use pow_primitives::TimestampApi;
+use primitives::{H256, U256};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, ProvideRuntimeApi, Zero};
+use crate::{Error, PowAux};
+
+/// Source of the difficulty of the next block.
+pub trait DifficultySource<B: BlockT>: Send + Sync {
+	/// Get the difficulty of the block built on `parent`.
+	fn difficulty(&self, parent: &BlockId<B>) -> Result<U256, Error<B>>;
+}
+
+/// A difficulty that never changes, mostly useful for testing.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedDifficulty(pub U256);
+
+impl<B: BlockT> DifficultySource<B> for FixedDifficulty {
+	fn difficulty(&self, _parent: &BlockId<B>) -> Result<U256, Error<B>> {
+		Ok(self.0)
+	}
+}
+
+/// Parameters of the moving window difficulty adjustment.
+#[derive(Clone, Copy, Debug)]
+pub struct MovingWindowParams {
+	/// Desired time between two blocks, in the unit of the runtime timestamps.
+	pub target_block_time: u64,
+	/// Number of blocks the timespan is measured on.
+	pub window: u32,
+	/// Maximum factor by which the difficulty changes from one block to the next.
+	pub clamp_factor: u64,
+	/// Difficulty of the blocks at the start of the chain, while the window isn't full.
+	pub initial_difficulty: U256,
+	/// Lowest difficulty.
+	pub min_difficulty: U256,
+}
+
+impl Default for MovingWindowParams {
+	fn default() -> Self {
+		MovingWindowParams {
+			target_block_time: 10_000,
+			window: 60,
+			clamp_factor: 2,
+			initial_difficulty: U256::from(1_000_000),
+			min_difficulty: U256::one(),
+		}
+	}
+}
+
+/// Difficulty adjusted on a moving window of the last blocks of the chain.
+///
+/// The difficulty of the next block is the average difficulty of the window, scaled by the ratio
+/// between the expected and the actual time it took to produce it. The difficulty of each block
+/// is read from the auxiliary storage of the PoW engine.
+pub struct MovingWindow<B, C> {
+	client: Arc<C>,
+	params: MovingWindowParams,
+	_marker: PhantomData<B>,
+}
+
+impl<B, C> MovingWindow<B, C> {
+	/// Create a new instance with the given parameters.
+	pub fn new(client: Arc<C>, params: MovingWindowParams) -> Self {
+		MovingWindow { client, params, _marker: PhantomData }
+	}
+}
+
+impl<B, C> Clone for MovingWindow<B, C> {
+	fn clone(&self) -> Self {
+		MovingWindow { client: self.client.clone(), params: self.params, _marker: PhantomData }
+	}
+}
+
+impl<B, C> DifficultySource<B> for MovingWindow<B, C> where
+	B: BlockT<Hash=H256>,
+	C: ProvideRuntimeApi + HeaderBackend<B> + AuxStore + Send + Sync,
+	C::Api: TimestampApi<B, u64, Error = sp_blockchain::Error>,
+{
+	fn difficulty(&self, parent: &BlockId<B>) -> Result<U256, Error<B>> {
+		let mut hash = self.client.expect_block_hash_from_id(parent).map_err(Error::Client)?;
+		let mut samples = Vec::with_capacity(self.params.window as usize + 1);
+
+		// the genesis block has no timestamp and is left out of the window.
+		while samples.len() <= self.params.window as usize {
+			let header = self.client.header(BlockId::Hash(hash))
+				.map_err(Error::Client)?
+				.ok_or_else(|| Error::Client(sp_blockchain::Error::UnknownBlock(format!("{}", hash))))?;
+			if header.number().is_zero() {
+				break;
+			}
+
+			let timestamp = self.client.runtime_api().timestamp(&BlockId::Hash(hash))
+				.map_err(Error::Client)?;
+			let aux = PowAux::<U256>::read::<_, B>(self.client.as_ref(), &hash)?;
+			samples.push((timestamp, aux.difficulty));
+			hash = *header.parent_hash();
+		}
+		samples.reverse();
+
+		Ok(next_difficulty(&samples, &self.params))
+	}
+}
+
+/// Compute the difficulty of the block following the given `(timestamp, difficulty)` samples,
+/// ordered from the oldest to the newest block.
+fn next_difficulty(samples: &[(u64, U256)], params: &MovingWindowParams) -> U256 {
+	let (first, last) = match (samples.first(), samples.last()) {
+		(Some(first), Some(last)) if samples.len() > params.window as usize => (first, last),
+		_ => return params.initial_difficulty,
+	};
+
+	let blocks = (samples.len() - 1) as u64;
+	let expected = params.target_block_time.saturating_mul(blocks).max(1);
+	let clamp_factor = params.clamp_factor.max(1);
+	let timespan = last.0.saturating_sub(first.0)
+		.max(expected / clamp_factor)
+		.min(expected.saturating_mul(clamp_factor))
+		.max(1);
+
+	let total = samples[1..].iter()
+		.fold(U256::zero(), |total, (_, difficulty)| total.saturating_add(*difficulty));
+	let average = total / U256::from(blocks);
+
+	let next = average.saturating_mul(U256::from(expected)) / U256::from(timespan);
+	next.max(params.min_difficulty)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn params() -> MovingWindowParams {
+		MovingWindowParams {
+			target_block_time: 10,
+			window: 4,
+			clamp_factor: 2,
+			initial_difficulty: U256::from(1000),
+			min_difficulty: U256::one(),
+		}
+	}
+
+	fn samples(interval: u64, difficulty: u64) -> Vec<(u64, U256)> {
+		(0..5).map(|i| (i * interval, U256::from(difficulty))).collect()
+	}
+
+	#[test]
+	fn initial_difficulty_until_window_is_full() {
+		assert_eq!(next_difficulty(&[], &params()), U256::from(1000));
+		assert_eq!(next_difficulty(&samples(10, 500)[..4], &params()), U256::from(1000));
+	}
+
+	#[test]
+	fn difficulty_follows_block_time() {
+		assert_eq!(next_difficulty(&samples(10, 500), &params()), U256::from(500));
+		// blocks twice too slow.
+		assert_eq!(next_difficulty(&samples(20, 500), &params()), U256::from(250));
+		// blocks 25% too fast.
+		assert_eq!(next_difficulty(&samples(8, 500), &params()), U256::from(625));
+	}
+
+	#[test]
+	fn difficulty_change_is_clamped() {
+		assert_eq!(next_difficulty(&samples(0, 500), &params()), U256::from(1000));
+		assert_eq!(next_difficulty(&samples(100, 500), &params()), U256::from(250));
+		assert_eq!(next_difficulty(&samples(100, 1), &params()), U256::one());
+	}
+}
@@ -0,0 +1,116 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hash-cash proof of work, mostly useful for testing.
+//!
+//! The work is the Blake2 hash of the pre-hash of the block and of a nonce. It is cheap to
+//! compute on specialized hardware, which makes it unsuitable for securing a public chain.
+
+use codec::{Encode, Decode};
+use primitives::{blake2_256, H256, U256};
+use pow_primitives::Seal;
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::Block as BlockT;
+use crate::{check_work, difficulty::DifficultySource, Error, PowAlgorithm};
+
+/// Seal of the hash-cash algorithm.
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HashcashSeal {
+	/// Nonce hashed with the pre-hash of the block.
+	pub nonce: u64,
+}
+
+/// Hash-cash algorithm, taking the difficulty from the given source.
+#[derive(Clone)]
+pub struct Hashcash<D> {
+	difficulty: D,
+}
+
+impl<D> Hashcash<D> {
+	/// Create a new instance.
+	pub fn new(difficulty: D) -> Self {
+		Hashcash { difficulty }
+	}
+}
+
+/// Compute the work of the given nonce.
+fn work(pre_hash: &H256, nonce: u64) -> H256 {
+	H256::from(blake2_256(&(pre_hash, nonce).encode()))
+}
+
+impl<B: BlockT<Hash=H256>, D: DifficultySource<B>> PowAlgorithm<B> for Hashcash<D> {
+	type Difficulty = U256;
+
+	fn difficulty(&self, parent: &BlockId<B>) -> Result<U256, Error<B>> {
+		self.difficulty.difficulty(parent)
+	}
+
+	fn verify(
+		&self,
+		_parent: &BlockId<B>,
+		pre_hash: &H256,
+		seal: &Seal,
+		difficulty: U256,
+	) -> Result<bool, Error<B>> {
+		let seal = match HashcashSeal::decode(&mut &seal[..]) {
+			Ok(seal) => seal,
+			Err(_) => return Ok(false),
+		};
+
+		Ok(check_work(&work(pre_hash, seal.nonce), difficulty))
+	}
+
+	fn mine(
+		&self,
+		_parent: &BlockId<B>,
+		pre_hash: &H256,
+		difficulty: U256,
+		round: u32,
+	) -> Result<Option<Seal>, Error<B>> {
+		let start = rand::random::<u64>();
+		let seal = (0..round as u64)
+			.map(|i| HashcashSeal { nonce: start.wrapping_add(i) })
+			.find(|seal| check_work(&work(pre_hash, seal.nonce), difficulty));
+
+		Ok(seal.map(|seal| seal.encode()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::difficulty::FixedDifficulty;
+	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	#[test]
+	fn mined_seal_is_valid() {
+		let algorithm = Hashcash::new(FixedDifficulty(U256::from(16)));
+		let parent = BlockId::<Block>::Number(0);
+		let pre_hash = H256::repeat_byte(7);
+		let difficulty = algorithm.difficulty(&parent).unwrap();
+
+		let seal = (0..100)
+			.filter_map(|_| algorithm.mine(&parent, &pre_hash, difficulty, 1000).unwrap())
+			.next()
+			.expect("a seal is found in 100_000 attempts with difficulty 16");
+
+		assert!(algorithm.verify(&parent, &pre_hash, &seal, difficulty).unwrap());
+		assert!(!algorithm.verify(&parent, &H256::repeat_byte(8), &seal, U256::max_value()).unwrap());
+		assert!(!algorithm.verify(&parent, &pre_hash, &vec![1, 2], difficulty).unwrap());
+	}
+}
@@ -19,7 +19,21 @@
 //! To use this engine, you can need to have a struct that implements
 //! `PowAlgorithm`. After that, pass an instance of the struct, along
 //! with other necessary client references to `import_queue` to setup
-//! the queue. Use the `start_mine` function for basic CPU mining.
+//! the queue. Use the `start_mine` function for basic CPU mining, and
+//! the `rpc` module to let external miners get work and submit seals.
+//!
+//! Two algorithms are provided: a hash-cash in the `hashcash` module,
+//! mostly useful for testing, and a memory-hard algorithm in the
+//! `memory_hard` module. Both take the difficulty of the next block
+//! from a `DifficultySource`, such as the moving window adjustment of
+//! the `difficulty` module.
+//!
+//! PoW alone only gives probabilistic finality. A finality gadget such as GRANDPA can be layered
+//! on top of it by wrapping its block import in a `PowBlockImport`, and passing it to the
+//! `import_queue` as the justification import. The `PowBlockImport` picks the best chain by total
+//! difficulty, but only among the chains that include the last finalized block, so that a heavier
+//! chain can never revert finality. The `PowSelectChain` should be given to the finality gadget,
+//! so that it only votes for blocks of the chain with the greatest total difficulty.
 //!
 //! The auxiliary storage for PoW engine only stores the total difficulty.
 //! For other storage requirements for particular PoW algorithm (such as
@@ -29,27 +43,47 @@
 //! as the storage, but it is not recommended as it won't work well with light
 //! clients.
 
+use std::collections::HashMap;
+use std::marker::PhantomData;
 use std::sync::Arc;
 use std::thread;
-use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use client_api::{BlockOf, backend::AuxStore};
-use sp_blockchain::{HeaderBackend, ProvideCache, well_known_cache_keys::Id as CacheKeyId};
+use sp_blockchain::{
+	HeaderBackend, HeaderMetadata, ProvideCache, well_known_cache_keys::Id as CacheKeyId,
+};
 use block_builder_api::BlockBuilder as BlockBuilderApi;
 use sp_runtime::{Justification, RuntimeString};
 use sp_runtime::generic::{BlockId, Digest, DigestItem};
-use sp_runtime::traits::{Block as BlockT, Header as HeaderT, ProvideRuntimeApi};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor, ProvideRuntimeApi};
 use sp_timestamp::{TimestampInherentData, InherentError as TIError};
 use pow_primitives::{Seal, TotalDifficulty, POW_ENGINE_ID};
-use primitives::H256;
+use primitives::{H256, U256};
 use inherents::{InherentDataProviders, InherentData};
 use consensus_common::{
-	BlockImportParams, BlockOrigin, ForkChoiceStrategy, SyncOracle, Environment, Proposer,
-	SelectChain, Error as ConsensusError, CanAuthorWith,
+	BlockImport, BlockCheckParams, BlockImportParams, BlockOrigin, ForkChoiceStrategy, ImportResult,
+	SyncOracle, Environment, Proposer, SelectChain, Error as ConsensusError, CanAuthorWith,
+};
+use consensus_common::import_queue::{
+	BoxBlockImport, BoxJustificationImport, BoxFinalityProofImport, BasicQueue, Verifier,
 };
-use consensus_common::import_queue::{BoxBlockImport, BasicQueue, Verifier};
 use codec::{Encode, Decode};
 use client_api;
 use log::*;
+use parking_lot::Mutex;
+
+pub mod difficulty;
+pub mod hashcash;
+pub mod memory_hard;
+pub mod rpc;
+mod worker;
+
+pub use worker::{MiningMetadata, MiningWorker};
+
+/// Interval at which the miners check for new work when there is none.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Interval at which the block being mined is rebuilt, to include new transactions.
+const REBUILD_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(derive_more::Display, Debug)]
 pub enum Error<B: BlockT> {
@@ -77,6 +111,8 @@ pub enum Error<B: BlockT> {
 	CreateInherents(inherents::Error),
 	#[display(fmt = "Checking inherents failed: {}", _0)]
 	CheckInherents(String),
+	#[display(fmt = "Block {:?} is missing its PoW auxiliary data", _0)]
+	MissingAux(B::Hash),
 	Client(sp_blockchain::Error),
 	Codec(codec::Error),
 	Environment(String),
@@ -147,6 +183,13 @@ pub trait PowAlgorithm<B: BlockT> {
 	) -> Result<Option<Seal>, Error<B>>;
 }
 
+/// Check that the work satisfies the given difficulty, i.e. that `work * difficulty` doesn't
+/// overflow when the work is read as a big-endian 256-bit number.
+pub fn check_work(work: &H256, difficulty: U256) -> bool {
+	let (_, overflowed) = U256::from_big_endian(work.as_bytes()).overflowing_mul(difficulty);
+	!overflowed
+}
+
 /// A verifier for PoW blocks.
 pub struct PowVerifier<B: BlockT<Hash=H256>, C, S, Algorithm> {
 	client: Arc<C>,
@@ -311,6 +354,171 @@ impl<B: BlockT<Hash=H256>, C, S, Algorithm> Verifier<B> for PowVerifier<B, C, S,
 	}
 }
 
+/// A block import for PoW, wrapping another block import, such as the GRANDPA one.
+///
+/// The fork choice is made here rather than in the verifier, which would race against other
+/// imports. A block becomes the new best block if its total difficulty is greater than the one of
+/// the current best block, and if it is a descendant of the last finalized block: a heavier chain
+/// can't revert the blocks finalized by a finality gadget layered on top of PoW.
+///
+/// The total difficulty of the block is read from the PoW auxiliary data that the verifier and the
+/// mining worker attach to the blocks they import.
+pub struct PowBlockImport<B: BlockT<Hash=H256>, I, C, Algorithm> {
+	inner: I,
+	client: Arc<C>,
+	_phantom: PhantomData<(B, Algorithm)>,
+}
+
+impl<B: BlockT<Hash=H256>, I: Clone, C, Algorithm> Clone for PowBlockImport<B, I, C, Algorithm> {
+	fn clone(&self) -> Self {
+		PowBlockImport {
+			inner: self.inner.clone(),
+			client: self.client.clone(),
+			_phantom: PhantomData,
+		}
+	}
+}
+
+impl<B: BlockT<Hash=H256>, I, C, Algorithm> PowBlockImport<B, I, C, Algorithm> {
+	/// Create a new block import wrapping `inner`.
+	pub fn new(inner: I, client: Arc<C>) -> Self {
+		PowBlockImport { inner, client, _phantom: PhantomData }
+	}
+}
+
+impl<B, I, C, Algorithm> BlockImport<B> for PowBlockImport<B, I, C, Algorithm> where
+	B: BlockT<Hash=H256>,
+	I: BlockImport<B> + Send + Sync,
+	I::Error: Into<ConsensusError>,
+	C: HeaderBackend<B> + HeaderMetadata<B, Error = sp_blockchain::Error> + AuxStore + Send + Sync,
+	Algorithm: PowAlgorithm<B> + Send + Sync,
+{
+	type Error = ConsensusError;
+
+	fn check_block(
+		&mut self,
+		block: BlockCheckParams<B>,
+	) -> Result<ImportResult, Self::Error> {
+		self.inner.check_block(block).map_err(Into::into)
+	}
+
+	fn import_block(
+		&mut self,
+		mut block: BlockImportParams<B>,
+		new_cache: HashMap<CacheKeyId, Vec<u8>>,
+	) -> Result<ImportResult, Self::Error> {
+		let hash = block.post_header().hash();
+		let parent_hash = *block.header.parent_hash();
+
+		let key = aux_key(&hash);
+		let aux = match block.auxiliary.iter().find(|(k, _)| k == &key) {
+			Some((_, Some(bytes))) => PowAux::<Algorithm::Difficulty>::decode(&mut &bytes[..])
+				.map_err(|e| ConsensusError::ClientImport(Error::<B>::Codec(e).to_string()))?,
+			_ => return Err(ConsensusError::ClientImport(Error::<B>::MissingAux(hash).to_string())),
+		};
+
+		let info = self.client.info();
+		let best_aux = PowAux::<Algorithm::Difficulty>::read::<_, B>(
+			self.client.as_ref(),
+			&info.best_hash,
+		).map_err(|e| ConsensusError::ChainLookup(e.to_string()))?;
+
+		// blocks that are not descendants of the last finalized block can be imported, since
+		// the client tolerates them as long as they are higher than it, but they must never
+		// become the best block.
+		let on_finalized_chain = parent_hash == info.finalized_hash ||
+			sp_blockchain::tree_route(self.client.as_ref(), info.finalized_hash, parent_hash)
+				.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
+				.retracted()
+				.is_empty();
+
+		block.fork_choice = ForkChoiceStrategy::Custom(
+			on_finalized_chain && aux.total_difficulty > best_aux.total_difficulty
+		);
+
+		self.inner.import_block(block, new_cache).map_err(Into::into)
+	}
+}
+
+/// A `SelectChain` for finality gadgets layered on top of PoW, wrapping another `SelectChain`,
+/// such as `LongestChain`.
+///
+/// The best chain is the one picked by the `PowBlockImport`, i.e. the one with the greatest total
+/// difficulty, and the finality target is always one of its blocks: a finality gadget would
+/// otherwise vote for the longest chain, which is not the best one if lighter blocks are mined
+/// on a fork. If the given target isn't a block of the best chain, only the target itself is
+/// returned.
+pub struct PowSelectChain<B: BlockT, C, S> {
+	client: Arc<C>,
+	inner: S,
+	_phantom: PhantomData<B>,
+}
+
+impl<B: BlockT, C, S: Clone> Clone for PowSelectChain<B, C, S> {
+	fn clone(&self) -> Self {
+		PowSelectChain {
+			client: self.client.clone(),
+			inner: self.inner.clone(),
+			_phantom: PhantomData,
+		}
+	}
+}
+
+impl<B: BlockT, C, S> PowSelectChain<B, C, S> {
+	/// Create a new `SelectChain` wrapping `inner`.
+	pub fn new(client: Arc<C>, inner: S) -> Self {
+		PowSelectChain { client, inner, _phantom: PhantomData }
+	}
+}
+
+impl<B, C, S> SelectChain<B> for PowSelectChain<B, C, S> where
+	B: BlockT,
+	C: HeaderBackend<B> + HeaderMetadata<B, Error = sp_blockchain::Error> + Send + Sync,
+	S: SelectChain<B>,
+{
+	fn leaves(&self) -> Result<Vec<B::Hash>, ConsensusError> {
+		self.inner.leaves()
+	}
+
+	fn best_chain(&self) -> Result<B::Header, ConsensusError> {
+		let best_hash = self.client.info().best_hash;
+		self.client.header(BlockId::Hash(best_hash))
+			.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
+			.ok_or_else(|| ConsensusError::ChainLookup(
+				format!("Missing best block {:?}", best_hash)
+			))
+	}
+
+	fn finality_target(
+		&self,
+		target_hash: B::Hash,
+		maybe_max_number: Option<NumberFor<B>>,
+	) -> Result<Option<B::Hash>, ConsensusError> {
+		let mut best = self.best_chain()?;
+
+		let on_best_chain = sp_blockchain::tree_route(self.client.as_ref(), target_hash, best.hash())
+			.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
+			.retracted()
+			.is_empty();
+		if !on_best_chain {
+			return Ok(Some(target_hash));
+		}
+
+		if let Some(max_number) = maybe_max_number {
+			while *best.number() > max_number && best.hash() != target_hash {
+				let parent_hash = *best.parent_hash();
+				best = self.client.header(BlockId::Hash(parent_hash))
+					.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
+					.ok_or_else(|| ConsensusError::ChainLookup(
+						format!("Missing ancestor {:?} of the best block", parent_hash)
+					))?;
+			}
+		}
+
+		Ok(Some(best.hash()))
+	}
+}
+
 /// Register the PoW inherent data provider, if not registered already.
 pub fn register_pow_inherent_data_provider(
 	inherent_data_providers: &InherentDataProviders,
@@ -329,8 +537,14 @@ pub fn register_pow_inherent_data_provider(
 pub type PowImportQueue<B> = BasicQueue<B>;
 
 /// Import queue for PoW engine.
+///
+/// When a finality gadget is used, `block_import` should be a `PowBlockImport` wrapping the
+/// block import of the gadget, and `justification_import` and `finality_proof_import` the
+/// imports of the gadget.
 pub fn import_queue<B, C, S, Algorithm>(
 	block_import: BoxBlockImport<B>,
+	justification_import: Option<BoxJustificationImport<B>>,
+	finality_proof_import: Option<BoxFinalityProofImport<B>>,
 	client: Arc<C>,
 	algorithm: Algorithm,
 	check_inherents_after: <<B as BlockT>::Header as HeaderT>::Number,
@@ -357,36 +571,41 @@ pub fn import_queue<B, C, S, Algorithm>(
 	Ok(BasicQueue::new(
 		verifier,
 		block_import,
-		None,
-		None
+		justification_import,
+		finality_proof_import,
 	))
 }
 
-/// Start the background mining thread for PoW. Note that because PoW mining
+/// Start the background mining threads for PoW. Note that because PoW mining
 /// is CPU-intensive, it is not possible to use an async future to define this.
 /// However, it's not recommended to use background threads in the rest of the
 /// codebase.
 ///
+/// One thread builds the blocks to mine, and `threads` threads mine them. The
+/// returned worker can be given to the `rpc` module so that external miners can
+/// mine as well, in which case `threads` can be zero.
+///
 /// `preruntime` is a parameter that allows a custom additional pre-runtime
 /// digest to be inserted for blocks being built. This can encode authorship
 /// information, or just be a graffiti. `round` is for number of rounds the
 /// CPU miner runs each time. This parameter should be tweaked so that each
 /// mining round is within sub-second time.
 pub fn start_mine<B: BlockT<Hash=H256>, C, Algorithm, E, SO, S, CAW>(
-	mut block_import: BoxBlockImport<B>,
+	block_import: BoxBlockImport<B>,
 	client: Arc<C>,
 	algorithm: Algorithm,
 	mut env: E,
 	preruntime: Option<Vec<u8>>,
 	round: u32,
+	threads: usize,
 	mut sync_oracle: SO,
 	build_time: std::time::Duration,
 	select_chain: Option<S>,
 	inherent_data_providers: inherents::InherentDataProviders,
 	can_author_with: CAW,
-) where
-	C: HeaderBackend<B> + AuxStore + 'static,
-	Algorithm: PowAlgorithm<B> + Send + Sync + 'static,
+) -> Arc<Mutex<MiningWorker<B, C, S, Algorithm>>> where
+	C: HeaderBackend<B> + AuxStore + Send + Sync + 'static,
+	Algorithm: PowAlgorithm<B> + Clone + Send + Sync + 'static,
 	E: Environment<B> + Send + Sync + 'static,
 	E::Error: std::fmt::Debug,
 	SO: SyncOracle + Send + Sync + 'static,
@@ -397,15 +616,39 @@ pub fn start_mine<B: BlockT<Hash=H256>, C, Algorithm, E, SO, S, CAW>(
 		warn!("Registering inherent data provider for timestamp failed");
 	}
 
+	let worker = Arc::new(Mutex::new(MiningWorker::new(
+		block_import,
+		client.clone(),
+		select_chain.clone(),
+		algorithm.clone(),
+	)));
+
+	for _ in 0..threads {
+		let worker = worker.clone();
+		let algorithm = algorithm.clone();
+		thread::spawn(move || {
+			loop {
+				match mine_loop(&worker, &algorithm, round) {
+					Ok(()) => (),
+					Err(e) => error!(
+						"Mining block failed with {:?}. Sleep for 1 second before restarting...",
+						e
+					),
+				}
+				std::thread::sleep(std::time::Duration::new(1, 0));
+			}
+		});
+	}
+
+	let build_worker = worker.clone();
 	thread::spawn(move || {
 		loop {
-			match mine_loop(
-				&mut block_import,
+			match build_loop(
+				&build_worker,
 				client.as_ref(),
 				&algorithm,
 				&mut env,
 				preruntime.as_ref(),
-				round,
 				&mut sync_oracle,
 				build_time.clone(),
 				select_chain.as_ref(),
@@ -414,22 +657,56 @@ pub fn start_mine<B: BlockT<Hash=H256>, C, Algorithm, E, SO, S, CAW>(
 			) {
 				Ok(()) => (),
 				Err(e) => error!(
-					"Mining block failed with {:?}. Sleep for 1 second before restarting...",
+					"Building block failed with {:?}. Sleep for 1 second before restarting...",
 					e
 				),
 			}
 			std::thread::sleep(std::time::Duration::new(1, 0));
 		}
 	});
+
+	worker
+}
+
+/// Mine the blocks of the worker, submitting the seals found.
+fn mine_loop<B: BlockT<Hash=H256>, C, S, Algorithm>(
+	worker: &Mutex<MiningWorker<B, C, S, Algorithm>>,
+	algorithm: &Algorithm,
+	round: u32,
+) -> Result<(), Error<B>> where
+	C: HeaderBackend<B> + AuxStore,
+	S: SelectChain<B>,
+	Algorithm: PowAlgorithm<B>,
+{
+	loop {
+		let metadata = match worker.lock().metadata() {
+			Some(metadata) => metadata,
+			None => {
+				std::thread::sleep(POLL_INTERVAL);
+				continue
+			},
+		};
+
+		let seal = algorithm.mine(
+			&BlockId::Hash(metadata.best_hash),
+			&metadata.pre_hash,
+			metadata.difficulty,
+			round,
+		)?;
+
+		if let Some(seal) = seal {
+			worker.lock().submit(&metadata.pre_hash, seal)?;
+		}
+	}
 }
 
-fn mine_loop<B: BlockT<Hash=H256>, C, Algorithm, E, SO, S, CAW>(
-	block_import: &mut BoxBlockImport<B>,
+/// Build the blocks to mine on top of the best block, and hand them to the worker.
+fn build_loop<B: BlockT<Hash=H256>, C, Algorithm, E, SO, S, CAW>(
+	worker: &Mutex<MiningWorker<B, C, S, Algorithm>>,
 	client: &C,
 	algorithm: &Algorithm,
 	env: &mut E,
 	preruntime: Option<&Vec<u8>>,
-	round: u32,
 	sync_oracle: &mut SO,
 	build_time: std::time::Duration,
 	select_chain: Option<&S>,
@@ -444,11 +721,12 @@ fn mine_loop<B: BlockT<Hash=H256>, C, Algorithm, E, SO, S, CAW>(
 	S: SelectChain<B>,
 	CAW: CanAuthorWith<B>,
 {
-	'outer: loop {
+	loop {
 		if sync_oracle.is_major_syncing() {
 			debug!(target: "pow", "Skipping proposal due to sync.");
+			worker.lock().set_build(None);
 			std::thread::sleep(std::time::Duration::new(1, 0));
-			continue 'outer
+			continue
 		}
 
 		let (best_hash, best_header) = match select_chain {
@@ -467,6 +745,14 @@ fn mine_loop<B: BlockT<Hash=H256>, C, Algorithm, E, SO, S, CAW>(
 			},
 		};
 
+		let up_to_date = worker.lock().built_on().map_or(false, |(built_on, built_at)| {
+			built_on == best_hash && built_at.elapsed() < REBUILD_INTERVAL
+		});
+		if up_to_date {
+			std::thread::sleep(POLL_INTERVAL);
+			continue
+		}
+
 		if let Err(err) = can_author_with.can_author_with(&BlockId::Hash(best_hash)) {
 			warn!(
 				target: "pow",
@@ -474,11 +760,11 @@ fn mine_loop<B: BlockT<Hash=H256>, C, Algorithm, E, SO, S, CAW>(
 				Probably a node update is required!",
 				err,
 			);
+			worker.lock().set_build(None);
 			std::thread::sleep(std::time::Duration::from_secs(1));
-			continue 'outer
+			continue
 		}
 
-		let mut aux = PowAux::read(client, &best_hash)?;
 		let mut proposer = env.init(&best_header)
 			.map_err(|e| Error::Environment(format!("{:?}", e)))?;
 
@@ -495,65 +781,140 @@ fn mine_loop<B: BlockT<Hash=H256>, C, Algorithm, E, SO, S, CAW>(
 		)).map_err(|e| Error::BlockProposingError(format!("{:?}", e)))?;
 
 		let (header, body) = block.deconstruct();
-		let (difficulty, seal) = {
-			let difficulty = algorithm.difficulty(
-				&BlockId::Hash(best_hash),
-			)?;
+		let difficulty = algorithm.difficulty(&BlockId::Hash(best_hash))?;
 
-			loop {
-				let seal = algorithm.mine(
-					&BlockId::Hash(best_hash),
-					&header.hash(),
-					difficulty,
-					round,
-				)?;
-
-				if let Some(seal) = seal {
-					break (difficulty, seal)
-				}
+		worker.lock().set_build(Some(worker::MiningBuild {
+			metadata: MiningMetadata {
+				best_hash,
+				pre_hash: header.hash(),
+				difficulty,
+			},
+			header,
+			body,
+			built_at: Instant::now(),
+		}));
+	}
+}
 
-				if best_hash != client.info().best_hash {
-					continue 'outer
-				}
-			}
-		};
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use test_client::{prelude::*, runtime::Block as TestBlock};
+	use crate::{difficulty::FixedDifficulty, hashcash::Hashcash};
+
+	type TestBlockImport = PowBlockImport<
+		TestBlock,
+		Arc<TestClient>,
+		TestClient,
+		Hashcash<FixedDifficulty>,
+	>;
+
+	fn import_with_difficulty(
+		block_import: &mut TestBlockImport,
+		client: &TestClient,
+		parent: H256,
+		fork: u8,
+		difficulty: u64,
+	) -> H256 {
+		let digest = Digest { logs: vec![DigestItem::Other(vec![fork])] };
+		let block = client.new_block_at(&BlockId::Hash(parent), digest).unwrap().bake().unwrap();
+		let hash = block.hash();
+		let (header, body) = block.deconstruct();
 
-		aux.difficulty = difficulty;
-		aux.total_difficulty.increment(difficulty);
-		let hash = {
-			let mut header = header.clone();
-			header.digest_mut().push(DigestItem::Seal(POW_ENGINE_ID, seal.clone()));
-			header.hash()
-		};
+		let mut aux = PowAux::<U256>::read::<_, TestBlock>(client, &parent).unwrap();
+		aux.difficulty = U256::from(difficulty);
+		aux.total_difficulty.increment(U256::from(difficulty));
 
-		let key = aux_key(&hash);
-		let best_hash = match select_chain {
-			Some(select_chain) => select_chain.best_chain()
-				.map_err(Error::BestHashSelectChain)?
-				.hash(),
-			None => client.info().best_hash,
+		let import = BlockImportParams {
+			origin: BlockOrigin::Own,
+			header,
+			justification: None,
+			post_digests: vec![],
+			body: Some(body),
+			finalized: false,
+			auxiliary: vec![(aux_key(&hash), Some(aux.encode()))],
+			fork_choice: ForkChoiceStrategy::Custom(true),
+			allow_missing_state: false,
+			import_existing: false,
 		};
-		let best_aux = PowAux::<Algorithm::Difficulty>::read(client, &best_hash)?;
+		block_import.import_block(import, Default::default()).unwrap();
 
-		// if the best block has changed in the meantime drop our proposal
-		if best_aux.total_difficulty > aux.total_difficulty {
-			continue 'outer
-		}
+		hash
+	}
 
-		let import_block = BlockImportParams {
+	#[test]
+	fn heavier_chains_do_not_revert_finality() {
+		let client = Arc::new(test_client::new());
+		let mut block_import = TestBlockImport::new(client.clone(), client.clone());
+		let genesis = client.info().chain.best_hash;
+
+		// the heaviest chain is the best one.
+		let a1 = import_with_difficulty(&mut block_import, &client, genesis, 0, 10);
+		let a2 = import_with_difficulty(&mut block_import, &client, a1, 0, 10);
+		let b2 = import_with_difficulty(&mut block_import, &client, a1, 1, 5);
+		assert_eq!(client.info().chain.best_hash, a2);
+
+		let b3 = import_with_difficulty(&mut block_import, &client, b2, 1, 10);
+		assert_eq!(client.info().chain.best_hash, b3);
+
+		// once a block is finalized, forks that don't include it are never the best chain,
+		// whatever their difficulty.
+		client.finalize_block(BlockId::Hash(a2), None).unwrap();
+		assert_eq!(client.info().chain.best_hash, a2);
+
+		let b4 = import_with_difficulty(&mut block_import, &client, b3, 1, 1000);
+		assert_eq!(client.info().chain.best_hash, a2);
+		assert!(client.header(&BlockId::Hash(b4)).unwrap().is_some());
+
+		let a3 = import_with_difficulty(&mut block_import, &client, a2, 0, 1);
+		assert_eq!(client.info().chain.best_hash, a3);
+	}
+
+	#[test]
+	fn finality_target_is_on_the_heaviest_chain() {
+		let (client, longest_chain) = TestClientBuilder::new().build_with_longest_chain();
+		let client = Arc::new(client);
+		let mut block_import = TestBlockImport::new(client.clone(), client.clone());
+		let select_chain = PowSelectChain::new(client.clone(), longest_chain.clone());
+		let genesis = client.info().chain.best_hash;
+
+		// the heavy chain is shorter than the light one.
+		let a1 = import_with_difficulty(&mut block_import, &client, genesis, 0, 10);
+		let a2 = import_with_difficulty(&mut block_import, &client, a1, 0, 10);
+		let b2 = import_with_difficulty(&mut block_import, &client, a1, 1, 1);
+		let b3 = import_with_difficulty(&mut block_import, &client, b2, 1, 1);
+		let b4 = import_with_difficulty(&mut block_import, &client, b3, 1, 1);
+		assert_eq!(longest_chain.finality_target(genesis, None).unwrap(), Some(b4));
+
+		assert_eq!(select_chain.best_chain().unwrap().hash(), a2);
+		assert_eq!(select_chain.finality_target(genesis, None).unwrap(), Some(a2));
+		assert_eq!(select_chain.finality_target(a1, None).unwrap(), Some(a2));
+		assert_eq!(select_chain.finality_target(genesis, Some(1)).unwrap(), Some(a1));
+
+		// a target on the light chain is never extended.
+		assert_eq!(select_chain.finality_target(b2, None).unwrap(), Some(b2));
+	}
+
+	#[test]
+	fn blocks_without_pow_aux_are_rejected() {
+		let client = Arc::new(test_client::new());
+		let mut block_import = TestBlockImport::new(client.clone(), client.clone());
+
+		let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+		let (header, body) = block.deconstruct();
+		let import = BlockImportParams {
 			origin: BlockOrigin::Own,
 			header,
 			justification: None,
-			post_digests: vec![DigestItem::Seal(POW_ENGINE_ID, seal)],
+			post_digests: vec![],
 			body: Some(body),
 			finalized: false,
-			auxiliary: vec![(key, Some(aux.encode()))],
+			auxiliary: vec![],
 			fork_choice: ForkChoiceStrategy::Custom(true),
 			allow_missing_state: false,
 			import_existing: false,
 		};
 
-		block_import.import_block(import_block, HashMap::default())
-			.map_err(|e| Error::BlockBuiltError(best_hash, e))?;
+		assert!(block_import.import_block(import, Default::default()).is_err());
 	}
 }
@@ -0,0 +1,250 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Memory-hard proof of work, in the style of Ethash.
+//!
+//! Each epoch of `epoch_length` blocks has a cache of pseudo-random items, generated from a seed
+//! that only depends on the epoch. The work of a nonce is computed by repeatedly mixing items of
+//! the cache picked from the intermediate results, so that miners need the whole cache in memory
+//! to compute it efficiently. Verifiers compute the work the same way.
+
+use std::sync::Arc;
+use codec::{Encode, Decode};
+use lru::LruCache;
+use parking_lot::Mutex;
+use primitives::{blake2_256, H256, U256};
+use pow_primitives::Seal;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::{Block as BlockT, UniqueSaturatedInto};
+use crate::{check_work, difficulty::DifficultySource, Error, PowAlgorithm};
+
+/// Default size of the cache, in bytes.
+pub const DEFAULT_CACHE_SIZE: usize = 16 * 1024 * 1024;
+/// Default number of blocks using the same cache.
+pub const DEFAULT_EPOCH_LENGTH: u64 = 4096;
+/// Number of rounds of the cache generation.
+const CACHE_ROUNDS: usize = 3;
+/// Number of cache items mixed into the work.
+const ACCESSES: usize = 64;
+/// Number of epochs whose cache is kept, so that blocks around epoch boundaries can be verified
+/// and mined without generating the caches again.
+const CACHED_EPOCHS: usize = 3;
+
+/// Seal of the memory-hard algorithm.
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryHardSeal {
+	/// Nonce mixed with the pre-hash of the block.
+	pub nonce: u64,
+}
+
+/// Memory-hard algorithm, taking the difficulty from the given source.
+pub struct MemoryHard<C, D> {
+	client: Arc<C>,
+	difficulty: D,
+	cache_items: usize,
+	epoch_length: u64,
+	caches: Arc<Mutex<LruCache<u64, Arc<Vec<H256>>>>>,
+}
+
+impl<C, D> MemoryHard<C, D> {
+	/// Create a new instance with the default cache size and epoch length.
+	pub fn new(client: Arc<C>, difficulty: D) -> Self {
+		Self::with_params(client, difficulty, DEFAULT_CACHE_SIZE, DEFAULT_EPOCH_LENGTH)
+	}
+
+	/// Create a new instance with a cache of `cache_size` bytes, renewed every `epoch_length`
+	/// blocks. All the nodes of a chain must use the same parameters.
+	pub fn with_params(client: Arc<C>, difficulty: D, cache_size: usize, epoch_length: u64) -> Self {
+		MemoryHard {
+			client,
+			difficulty,
+			cache_items: (cache_size / H256::len_bytes()).max(1),
+			epoch_length: epoch_length.max(1),
+			caches: Arc::new(Mutex::new(LruCache::new(CACHED_EPOCHS))),
+		}
+	}
+
+	/// Get the cache of the given epoch, generating it if needed.
+	fn cache(&self, epoch: u64) -> Arc<Vec<H256>> {
+		let mut caches = self.caches.lock();
+		if let Some(items) = caches.get(&epoch) {
+			return items.clone()
+		}
+
+		let items = Arc::new(generate_cache(&seed(epoch), self.cache_items));
+		caches.put(epoch, items.clone());
+		items
+	}
+}
+
+impl<C, D: Clone> Clone for MemoryHard<C, D> {
+	fn clone(&self) -> Self {
+		MemoryHard {
+			client: self.client.clone(),
+			difficulty: self.difficulty.clone(),
+			cache_items: self.cache_items,
+			epoch_length: self.epoch_length,
+			caches: self.caches.clone(),
+		}
+	}
+}
+
+/// Seed of the cache of the given epoch.
+fn seed(epoch: u64) -> H256 {
+	(0..epoch).fold(H256::zero(), |seed, _| H256::from(blake2_256(seed.as_bytes())))
+}
+
+/// Generate the cache items from the seed of an epoch.
+fn generate_cache(seed: &H256, len: usize) -> Vec<H256> {
+	let mut items = Vec::with_capacity(len);
+	items.push(H256::from(blake2_256(seed.as_bytes())));
+	for i in 1..len {
+		let item = H256::from(blake2_256(items[i - 1].as_bytes()));
+		items.push(item);
+	}
+
+	for _ in 0..CACHE_ROUNDS {
+		for i in 0..len {
+			let other = index(&items[i], len);
+			let previous = items[(i + len - 1) % len];
+			let mixed = xor(&previous, &items[other]);
+			items[i] = H256::from(blake2_256(mixed.as_bytes()));
+		}
+	}
+
+	items
+}
+
+/// Index of the cache item picked by the given hash.
+fn index(hash: &H256, len: usize) -> usize {
+	let mut bytes = [0u8; 4];
+	bytes.copy_from_slice(&hash[..4]);
+	u32::from_le_bytes(bytes) as usize % len
+}
+
+fn xor(a: &H256, b: &H256) -> H256 {
+	let mut out = H256::zero();
+	for (out, (a, b)) in out.as_bytes_mut().iter_mut().zip(a.as_bytes().iter().zip(b.as_bytes())) {
+		*out = a ^ b;
+	}
+	out
+}
+
+/// Compute the work of the given nonce.
+fn work(cache: &[H256], pre_hash: &H256, nonce: u64) -> H256 {
+	let mut mix = H256::from(blake2_256(&(pre_hash, nonce).encode()));
+	for access in 0..ACCESSES {
+		let item = &cache[(index(&mix, cache.len()) ^ access) % cache.len()];
+		mix = H256::from(blake2_256(&(mix, item).encode()));
+	}
+	mix
+}
+
+impl<B, C, D> PowAlgorithm<B> for MemoryHard<C, D> where
+	B: BlockT<Hash=H256>,
+	C: HeaderBackend<B>,
+	D: DifficultySource<B>,
+{
+	type Difficulty = U256;
+
+	fn difficulty(&self, parent: &BlockId<B>) -> Result<U256, Error<B>> {
+		self.difficulty.difficulty(parent)
+	}
+
+	fn verify(
+		&self,
+		parent: &BlockId<B>,
+		pre_hash: &H256,
+		seal: &Seal,
+		difficulty: U256,
+	) -> Result<bool, Error<B>> {
+		let seal = match MemoryHardSeal::decode(&mut &seal[..]) {
+			Ok(seal) => seal,
+			Err(_) => return Ok(false),
+		};
+
+		let cache = self.cache(self.epoch(parent)?);
+		Ok(check_work(&work(&cache, pre_hash, seal.nonce), difficulty))
+	}
+
+	fn mine(
+		&self,
+		parent: &BlockId<B>,
+		pre_hash: &H256,
+		difficulty: U256,
+		round: u32,
+	) -> Result<Option<Seal>, Error<B>> {
+		let cache = self.cache(self.epoch(parent)?);
+		let start = rand::random::<u64>();
+		let seal = (0..round as u64)
+			.map(|i| MemoryHardSeal { nonce: start.wrapping_add(i) })
+			.find(|seal| check_work(&work(&cache, pre_hash, seal.nonce), difficulty));
+
+		Ok(seal.map(|seal| seal.encode()))
+	}
+}
+
+impl<C, D> MemoryHard<C, D> {
+	/// Epoch of the block built on `parent`.
+	fn epoch<B: BlockT>(&self, parent: &BlockId<B>) -> Result<u64, Error<B>> where
+		C: HeaderBackend<B>,
+	{
+		let number: u64 = self.client.expect_block_number_from_id(parent)
+			.map_err(Error::Client)?
+			.unique_saturated_into();
+		Ok(number.saturating_add(1) / self.epoch_length)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn cache_depends_on_epoch() {
+		assert_eq!(generate_cache(&seed(1), 64), generate_cache(&seed(1), 64));
+		assert_ne!(generate_cache(&seed(1), 64), generate_cache(&seed(2), 64));
+	}
+
+	#[test]
+	fn work_depends_on_cache_and_nonce() {
+		let cache = generate_cache(&seed(0), 1024);
+		let other_cache = generate_cache(&seed(1), 1024);
+		let pre_hash = H256::repeat_byte(1);
+
+		assert_eq!(work(&cache, &pre_hash, 1), work(&cache, &pre_hash, 1));
+		assert_ne!(work(&cache, &pre_hash, 1), work(&cache, &pre_hash, 2));
+		assert_ne!(work(&cache, &pre_hash, 1), work(&other_cache, &pre_hash, 1));
+	}
+
+	#[test]
+	fn caches_of_recent_epochs_are_kept() {
+		let algorithm = MemoryHard::with_params(Arc::new(()), (), 1024, 1);
+		let previous = algorithm.cache(0);
+		let current = algorithm.cache(1);
+
+		// going back and forth across the epoch boundary doesn't generate the caches again.
+		assert!(Arc::ptr_eq(&previous, &algorithm.cache(0)));
+		assert!(Arc::ptr_eq(&current, &algorithm.cache(1)));
+
+		// the least recently used cache is dropped once more epochs are cached.
+		algorithm.cache(2);
+		algorithm.cache(3);
+		assert!(Arc::ptr_eq(&current, &algorithm.cache(1)));
+		assert!(!Arc::ptr_eq(&previous, &algorithm.cache(0)));
+	}
+}
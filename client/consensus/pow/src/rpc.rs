@@ -0,0 +1,92 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC interface for external miners.
+
+use std::sync::Arc;
+use client_api::backend::AuxStore;
+use consensus_common::SelectChain;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use parking_lot::Mutex;
+use primitives::{Bytes, H256};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Block as BlockT;
+use crate::{PowAlgorithm, worker::MiningWorker};
+
+pub use self::gen_client::Client as PowClient;
+
+/// Work to perform to seal the block being mined.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Work<Hash, Difficulty> {
+	/// Parent of the block.
+	pub parent_hash: Hash,
+	/// Hash of the block without its seal.
+	pub pre_hash: Hash,
+	/// Difficulty the seal must satisfy.
+	pub difficulty: Difficulty,
+}
+
+/// PoW RPC API, for mining with external miners.
+#[rpc]
+pub trait PowApi<Hash, Difficulty> {
+	/// Get the work to perform to seal the block being mined, if any.
+	#[rpc(name = "pow_getWork")]
+	fn get_work(&self) -> Result<Option<Work<Hash, Difficulty>>>;
+
+	/// Submit the seal of the block with the given pre-hash. Returns whether the block has been
+	/// sealed and imported.
+	#[rpc(name = "pow_submitWork")]
+	fn submit_work(&self, pre_hash: Hash, seal: Bytes) -> Result<bool>;
+}
+
+/// Implementation of the PoW RPC API on top of a mining worker.
+pub struct Pow<B: BlockT<Hash=H256>, C, S, Algorithm: PowAlgorithm<B>> {
+	worker: Arc<Mutex<MiningWorker<B, C, S, Algorithm>>>,
+}
+
+impl<B: BlockT<Hash=H256>, C, S, Algorithm: PowAlgorithm<B>> Pow<B, C, S, Algorithm> {
+	/// Create a new instance, serving the work of the worker returned by `start_mine`.
+	pub fn new(worker: Arc<Mutex<MiningWorker<B, C, S, Algorithm>>>) -> Self {
+		Pow { worker }
+	}
+}
+
+impl<B, C, S, Algorithm> PowApi<H256, Algorithm::Difficulty> for Pow<B, C, S, Algorithm> where
+	B: BlockT<Hash=H256>,
+	C: HeaderBackend<B> + AuxStore + Send + Sync + 'static,
+	S: SelectChain<B> + 'static,
+	Algorithm: PowAlgorithm<B> + Send + 'static,
+	Algorithm::Difficulty: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+	fn get_work(&self) -> Result<Option<Work<H256, Algorithm::Difficulty>>> {
+		Ok(self.worker.lock().metadata().map(|metadata| Work {
+			parent_hash: metadata.best_hash,
+			pre_hash: metadata.pre_hash,
+			difficulty: metadata.difficulty,
+		}))
+	}
+
+	fn submit_work(&self, pre_hash: H256, seal: Bytes) -> Result<bool> {
+		self.worker.lock().submit(&pre_hash, seal.0).map_err(|e| RpcError {
+			code: ErrorCode::InternalError,
+			message: e.to_string(),
+			data: None,
+		})
+	}
+}
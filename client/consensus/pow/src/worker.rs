@@ -0,0 +1,154 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Mining worker.
+//!
+//! The worker holds the block being mined. It is shared between the thread building the blocks,
+//! the local miner threads and the external miners, which get the work to perform with
+//! [`MiningWorker::metadata`] and report the seals they find with [`MiningWorker::submit`].
+
+use std::{collections::HashMap, sync::Arc, time::Instant};
+use client_api::backend::AuxStore;
+use consensus_common::{BlockImportParams, BlockOrigin, ForkChoiceStrategy, SelectChain};
+use consensus_common::import_queue::BoxBlockImport;
+use codec::Encode;
+use log::info;
+use pow_primitives::{Seal, TotalDifficulty, POW_ENGINE_ID};
+use primitives::H256;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::generic::{BlockId, DigestItem};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use crate::{aux_key, Error, PowAlgorithm, PowAux};
+
+/// Work to perform to seal the block being mined.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MiningMetadata<Difficulty> {
+	/// Best block when the block was built, i.e. its parent.
+	pub best_hash: H256,
+	/// Hash of the block without its seal.
+	pub pre_hash: H256,
+	/// Difficulty the seal must satisfy.
+	pub difficulty: Difficulty,
+}
+
+/// A block being mined.
+pub(crate) struct MiningBuild<B: BlockT, Difficulty> {
+	pub(crate) metadata: MiningMetadata<Difficulty>,
+	pub(crate) header: B::Header,
+	pub(crate) body: Vec<B::Extrinsic>,
+	pub(crate) built_at: Instant,
+}
+
+/// Holds the block being mined, and imports it once sealed.
+pub struct MiningWorker<B: BlockT<Hash=H256>, C, S, Algorithm: PowAlgorithm<B>> {
+	build: Option<MiningBuild<B, Algorithm::Difficulty>>,
+	block_import: BoxBlockImport<B>,
+	client: Arc<C>,
+	select_chain: Option<S>,
+	algorithm: Algorithm,
+}
+
+impl<B, C, S, Algorithm> MiningWorker<B, C, S, Algorithm> where
+	B: BlockT<Hash=H256>,
+	C: HeaderBackend<B> + AuxStore,
+	S: SelectChain<B>,
+	Algorithm: PowAlgorithm<B>,
+{
+	pub(crate) fn new(
+		block_import: BoxBlockImport<B>,
+		client: Arc<C>,
+		select_chain: Option<S>,
+		algorithm: Algorithm,
+	) -> Self {
+		MiningWorker { build: None, block_import, client, select_chain, algorithm }
+	}
+
+	/// Work to perform, if a block is being mined.
+	pub fn metadata(&self) -> Option<MiningMetadata<Algorithm::Difficulty>> {
+		self.build.as_ref().map(|build| build.metadata.clone())
+	}
+
+	/// Best block the block being mined was built on, and when it was built.
+	pub(crate) fn built_on(&self) -> Option<(H256, Instant)> {
+		self.build.as_ref().map(|build| (build.metadata.best_hash, build.built_at))
+	}
+
+	/// Replace the block being mined.
+	pub(crate) fn set_build(&mut self, build: Option<MiningBuild<B, Algorithm::Difficulty>>) {
+		self.build = build;
+	}
+
+	/// Submit a seal for the block with the given pre-hash.
+	///
+	/// Returns whether the block has been sealed and imported. Seals of blocks that are no longer
+	/// being mined, or that don't satisfy the difficulty, are ignored.
+	pub fn submit(&mut self, pre_hash: &H256, seal: Seal) -> Result<bool, Error<B>> {
+		let build = match self.build.take() {
+			Some(build) if build.metadata.pre_hash == *pre_hash => build,
+			other => {
+				self.build = other;
+				return Ok(false)
+			},
+		};
+
+		let MiningMetadata { best_hash: parent_hash, difficulty, .. } = build.metadata;
+		if !self.algorithm.verify(&BlockId::Hash(parent_hash), pre_hash, &seal, difficulty)? {
+			self.build = Some(build);
+			return Ok(false)
+		}
+
+		let mut aux = PowAux::<Algorithm::Difficulty>::read::<_, B>(self.client.as_ref(), &parent_hash)?;
+		aux.difficulty = difficulty;
+		aux.total_difficulty.increment(difficulty);
+		let seal = DigestItem::Seal(POW_ENGINE_ID, seal);
+		let hash = {
+			let mut header = build.header.clone();
+			header.digest_mut().push(seal.clone());
+			header.hash()
+		};
+
+		let best_hash = match self.select_chain.as_ref() {
+			Some(select_chain) => select_chain.best_chain()
+				.map_err(Error::BestHashSelectChain)?
+				.hash(),
+			None => self.client.info().best_hash,
+		};
+		let best_aux = PowAux::<Algorithm::Difficulty>::read::<_, B>(self.client.as_ref(), &best_hash)?;
+
+		// if the best block has changed in the meantime drop our proposal
+		if best_aux.total_difficulty > aux.total_difficulty {
+			return Ok(false)
+		}
+
+		info!(target: "pow", "Sealed block #{} ({})", build.header.number(), hash);
+		let import_block = BlockImportParams {
+			origin: BlockOrigin::Own,
+			header: build.header,
+			justification: None,
+			post_digests: vec![seal],
+			body: Some(build.body),
+			finalized: false,
+			auxiliary: vec![(aux_key(&hash), Some(aux.encode()))],
+			fork_choice: ForkChoiceStrategy::Custom(true),
+			allow_missing_state: false,
+			import_existing: false,
+		};
+
+		self.block_import.import_block(import_block, HashMap::default())
+			.map_err(|e| Error::BlockBuiltError(best_hash, e))?;
+		Ok(true)
+	}
+}
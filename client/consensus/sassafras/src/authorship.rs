@@ -0,0 +1,251 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Sassafras ticket generation and slot claiming.
+
+use codec::{Encode, Decode};
+use schnorrkel::{SignatureError, vrf::{VRFInOut, VRFOutput, VRFProof}};
+use sp_consensus_sassafras::{
+	AuthorityId, AuthorityIndex, AuthorityPair, Epoch, PreDigest, SassafrasConfiguration,
+	SlotNumber, Ticket, TicketProof, SASSAFRAS_TICKET_VRF_PREFIX, VRF_OUTPUT_LENGTH,
+	VRF_PROOF_LENGTH, make_ticket_transcript, ticket_threshold, ticket_value,
+};
+use primitives::Pair;
+use keystore::KeyStorePtr;
+
+/// The secret part of a ticket, i.e. its VRF proof. It is submitted on-chain
+/// along with the ticket and revealed again when claiming the slot the ticket
+/// was assigned to.
+#[derive(Clone, Encode, Decode)]
+pub(super) struct TicketSecret {
+	/// The index of the ticket owner in the epoch authorities.
+	pub authority_index: AuthorityIndex,
+	/// The attempt number used as VRF input.
+	pub attempt: u32,
+	/// The ticket VRF output.
+	pub vrf_output: [u8; VRF_OUTPUT_LENGTH],
+	/// The ticket VRF proof.
+	pub vrf_proof: [u8; VRF_PROOF_LENGTH],
+}
+
+impl TicketSecret {
+	/// The proof submitted on-chain for this ticket.
+	pub(super) fn proof(&self) -> TicketProof {
+		TicketProof {
+			attempt: self.attempt,
+			vrf_output: self.vrf_output,
+			vrf_proof: self.vrf_proof,
+		}
+	}
+}
+
+/// Derive the ticket from the given VRF input and output.
+pub(super) fn make_ticket(inout: &VRFInOut) -> Ticket {
+	inout.make_bytes::<Ticket>(SASSAFRAS_TICKET_VRF_PREFIX)
+}
+
+/// Returns the threshold under which tickets of the given epoch are winning.
+pub(super) fn epoch_ticket_threshold(epoch: &Epoch, config: &SassafrasConfiguration) -> u128 {
+	ticket_threshold(
+		config.redundancy_factor,
+		epoch.duration,
+		config.attempts_number,
+		epoch.authorities.len() as u32,
+	)
+}
+
+/// Verify that the given VRF output and proof were produced by the given
+/// authority for the given ticket attempt, returning the ticket.
+pub(super) fn verify_ticket(
+	author: &AuthorityId,
+	epoch: &Epoch,
+	attempt: u32,
+	vrf_output: &[u8; VRF_OUTPUT_LENGTH],
+	vrf_proof: &[u8; VRF_PROOF_LENGTH],
+) -> Result<Ticket, SignatureError> {
+	let transcript = make_ticket_transcript(&epoch.randomness, attempt, epoch.epoch_index);
+	let vrf_output = VRFOutput::from_bytes(&vrf_output[..])?;
+	let vrf_proof = VRFProof::from_bytes(&vrf_proof[..])?;
+
+	let (inout, _) = schnorrkel::PublicKey::from_bytes(author.as_slice())
+		.and_then(|p| p.vrf_verify(transcript, &vrf_output, &vrf_proof))?;
+
+	Ok(make_ticket(&inout))
+}
+
+fn get_keypair(q: &AuthorityPair) -> &schnorrkel::Keypair {
+	use primitives::crypto::IsWrappedBy;
+	primitives::sr25519::Pair::from_ref(q).as_ref()
+}
+
+/// Generate the winning tickets of all local authorities for the given epoch.
+/// Each authority tries `attempts_number` VRF evaluations and keeps the ones
+/// under the epoch ticket threshold.
+pub(super) fn generate_tickets(
+	epoch: &Epoch,
+	config: &SassafrasConfiguration,
+	keystore: &KeyStorePtr,
+) -> Vec<(Ticket, TicketSecret)> {
+	let threshold = epoch_ticket_threshold(epoch, config);
+	let keystore = keystore.read();
+	let mut tickets = Vec::new();
+
+	for (pair, authority_index) in epoch.authorities.iter()
+		.enumerate()
+		.flat_map(|(i, a)| {
+			keystore.key_pair::<AuthorityPair>(a).ok().map(|kp| (kp, i))
+		})
+	{
+		for attempt in 0..config.attempts_number {
+			let transcript = make_ticket_transcript(&epoch.randomness, attempt, epoch.epoch_index);
+
+			let ticket = get_keypair(&pair)
+				.vrf_sign_after_check(transcript, |inout| ticket_value(&make_ticket(inout)) < threshold)
+				.map(|s| {
+					let secret = TicketSecret {
+						authority_index: authority_index as AuthorityIndex,
+						attempt,
+						vrf_output: s.0.to_output().to_bytes(),
+						vrf_proof: s.1.to_bytes(),
+					};
+
+					(make_ticket(&s.0), secret)
+				});
+
+			tickets.extend(ticket);
+		}
+	}
+
+	tickets
+}
+
+/// Tries to claim the given slot number. If a ticket was assigned to the slot,
+/// it can only be claimed by revealing the secret of one of our tickets.
+/// Otherwise the slot is claimed by its round-robin author.
+pub(super) fn claim_slot<'a>(
+	slot_number: SlotNumber,
+	epoch: &Epoch,
+	slot_ticket: Option<Ticket>,
+	mut ticket_secret: impl FnMut(&Ticket) -> Option<&'a TicketSecret>,
+	keystore: &KeyStorePtr,
+) -> Option<(PreDigest, AuthorityPair)> {
+	let (pre_digest, author) = match slot_ticket {
+		Some(ticket) => {
+			let secret = ticket_secret(&ticket)?;
+			let author = epoch.authorities.get(secret.authority_index as usize)?;
+
+			let pre_digest = PreDigest::Primary {
+				authority_index: secret.authority_index,
+				slot_number,
+				ticket_attempt: secret.attempt,
+				ticket_vrf_output: secret.vrf_output,
+				ticket_vrf_proof: secret.vrf_proof,
+			};
+
+			(pre_digest, author)
+		},
+		None => {
+			let authority_index = epoch.secondary_slot_author(slot_number)?;
+			let author = &epoch.authorities[authority_index as usize];
+
+			(PreDigest::Secondary { authority_index, slot_number }, author)
+		},
+	};
+
+	let pair = keystore.read().key_pair::<AuthorityPair>(author).ok()?;
+	debug_assert_eq!(pair.public(), *author);
+
+	Some((pre_digest, pair))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use keystore::Store;
+
+	#[test]
+	fn generated_tickets_are_verifiable() {
+		let keystore_path = tempfile::tempdir().expect("Creates keystore path");
+		let keystore = Store::open(keystore_path.path(), None).expect("Creates keystore");
+		let pair: AuthorityPair = keystore.write().generate().expect("Generates authority pair");
+
+		let config = SassafrasConfiguration {
+			slot_duration: 1000,
+			epoch_duration: 10,
+			attempts_number: 4,
+			redundancy_factor: 1,
+		};
+
+		let epoch = Epoch {
+			epoch_index: 1,
+			start_slot: 10,
+			duration: 10,
+			authorities: vec![pair.public()],
+			randomness: [1; 32],
+		};
+
+		// with a single authority all attempts are winning.
+		let tickets = generate_tickets(&epoch, &config, &keystore);
+		assert_eq!(tickets.len(), 4);
+
+		for (ticket, secret) in &tickets {
+			assert_eq!(
+				verify_ticket(&pair.public(), &epoch, secret.attempt, &secret.vrf_output, &secret.vrf_proof)
+					.expect("ticket proof is valid"),
+				*ticket,
+			);
+
+			// the runtime verifies the submitted proofs the same way.
+			let proof = secret.proof();
+			assert_eq!(
+				sp_consensus_sassafras::verify_ticket(
+					&pair.public(),
+					&epoch.randomness,
+					epoch.epoch_index,
+					proof.attempt,
+					&proof.vrf_output,
+					&proof.vrf_proof,
+				),
+				Some(*ticket),
+			);
+
+			// the proof is bound to the attempt.
+			assert_ne!(
+				verify_ticket(&pair.public(), &epoch, secret.attempt + 1, &secret.vrf_output, &secret.vrf_proof)
+					.ok(),
+				Some(*ticket),
+			);
+		}
+
+		let (ticket, secret) = tickets[0].clone();
+		let (pre_digest, claimed) = claim_slot(
+			12,
+			&epoch,
+			Some(ticket),
+			|t| if *t == ticket { Some(&secret) } else { None },
+			&keystore,
+		).expect("slot with our ticket is claimable");
+
+		assert_eq!(claimed.public(), pair.public());
+		match pre_digest {
+			PreDigest::Primary { ticket_attempt, .. } => assert_eq!(ticket_attempt, secret.attempt),
+			PreDigest::Secondary { .. } => panic!("slot with a ticket is claimed as primary"),
+		}
+
+		assert!(claim_slot(12, &epoch, Some([0xff; 32]), |_| None, &keystore).is_none());
+	}
+}
@@ -0,0 +1,127 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Schema for the Sassafras ticket secrets in the aux-db.
+
+use codec::{Decode, Encode};
+
+use client_api::backend::AuxStore;
+use sp_blockchain::{Result as ClientResult, Error as ClientError};
+use sp_consensus_sassafras::Ticket;
+
+use super::authorship::TicketSecret;
+
+const SASSAFRAS_TICKET_SECRETS: &[u8] = b"sassafras_ticket_secrets";
+
+/// The secrets of the tickets generated by local authorities for an epoch.
+pub(crate) type EpochTicketSecrets = Vec<(Ticket, TicketSecret)>;
+
+fn ticket_secrets_key(epoch_index: u64) -> Vec<u8> {
+	(SASSAFRAS_TICKET_SECRETS, epoch_index).encode()
+}
+
+/// Load the secrets of the tickets generated for the given epoch, if any.
+pub(crate) fn load_ticket_secrets<B: AuxStore>(
+	backend: &B,
+	epoch_index: u64,
+) -> ClientResult<Option<EpochTicketSecrets>> {
+	let corrupt = |e: codec::Error| {
+		ClientError::Backend(format!("Sassafras DB is corrupted. Decode error: {}", e.what()))
+	};
+	match backend.get_aux(&ticket_secrets_key(epoch_index))? {
+		None => Ok(None),
+		Some(t) => EpochTicketSecrets::decode(&mut &t[..]).map(Some).map_err(corrupt),
+	}
+}
+
+/// Write the secrets of the tickets generated for the given epoch, removing
+/// the ones of the epoch before the previous one, which can't be claimed
+/// anymore.
+pub(crate) fn write_ticket_secrets<B: AuxStore>(
+	backend: &B,
+	epoch_index: u64,
+	ticket_secrets: &EpochTicketSecrets,
+) -> ClientResult<()> {
+	let key = ticket_secrets_key(epoch_index);
+	let encoded = ticket_secrets.encode();
+	let obsolete = epoch_index.checked_sub(2).map(ticket_secrets_key);
+
+	backend.insert_aux(
+		&[(&key[..], &encoded[..])],
+		obsolete.as_ref().map(|k| &k[..]).iter(),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashMap;
+	use parking_lot::Mutex;
+
+	#[derive(Default)]
+	struct TestAuxStore(Mutex<HashMap<Vec<u8>, Vec<u8>>>);
+
+	impl AuxStore for TestAuxStore {
+		fn insert_aux<
+			'a,
+			'b: 'a,
+			'c: 'a,
+			I: IntoIterator<Item=&'a(&'c [u8], &'c [u8])>,
+			D: IntoIterator<Item=&'a &'b [u8]>,
+		>(&self, insert: I, delete: D) -> ClientResult<()> {
+			let mut store = self.0.lock();
+			for (k, v) in insert {
+				store.insert(k.to_vec(), v.to_vec());
+			}
+			for k in delete {
+				store.remove(*k);
+			}
+			Ok(())
+		}
+
+		fn get_aux(&self, key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
+			Ok(self.0.lock().get(key).cloned())
+		}
+	}
+
+	fn secrets(attempt: u32) -> EpochTicketSecrets {
+		vec![([attempt as u8; 32], TicketSecret {
+			authority_index: 1,
+			attempt,
+			vrf_output: [2; 32],
+			vrf_proof: [3; 64],
+		})]
+	}
+
+	#[test]
+	fn ticket_secrets_are_persisted_and_pruned() {
+		let store = TestAuxStore::default();
+		assert!(load_ticket_secrets(&store, 1).unwrap().is_none());
+
+		write_ticket_secrets(&store, 1, &secrets(1)).unwrap();
+		write_ticket_secrets(&store, 2, &secrets(2)).unwrap();
+
+		let loaded = load_ticket_secrets(&store, 1).unwrap().expect("secrets were written");
+		assert_eq!(loaded[0].0, [1; 32]);
+		assert_eq!(loaded[0].1.attempt, 1);
+		assert_eq!(&loaded[0].1.vrf_proof[..], &[3; 64][..]);
+
+		// writing the secrets of epoch 3 prunes the ones of epoch 1.
+		write_ticket_secrets(&store, 3, &secrets(3)).unwrap();
+		assert!(load_ticket_secrets(&store, 1).unwrap().is_none());
+		assert!(load_ticket_secrets(&store, 2).unwrap().is_some());
+	}
+}
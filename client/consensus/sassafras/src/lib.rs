@@ -0,0 +1,767 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Sassafras consensus in substrate.
+//!
+//! Sassafras is a slot-based block production engine which assigns exactly
+//! one author to each slot, avoiding both the predictable leaders of Aura and
+//! the forks caused by multiple primary slot claims in BABE.
+//!
+//! During each epoch, authorities evaluate a VRF for a fixed number of
+//! attempts using the randomness of the *next* epoch. Outputs under a
+//! threshold are winning tickets, which are submitted on-chain through an
+//! unsigned extrinsic along with their VRF proofs, which the runtime verifies
+//! against the submitting authority. The runtime sorts the tickets and, once
+//! the next epoch starts, assigns the `i`-th ticket to the `i`-th slot of the
+//! epoch. The owner of a ticket claims its slot by revealing the ticket VRF
+//! proof in the block pre-digest. Slots without a ticket are claimed by a
+//! round-robin author.
+//!
+//! The secrets of generated tickets are persisted in the aux-db, so they can
+//! still be claimed after restarting the node, and they are submitted again
+//! after a restart in case the previous submission was lost.
+//!
+//! This is a prototype, with the following known limitations:
+//!
+//! - tickets are submitted with a plain VRF proof, which reveals their owner.
+//!   A proper implementation would use a ring VRF to keep the ticket owners
+//!   anonymous until they claim their slots.
+//! - a malicious authority can still submit its own winning tickets and leave
+//!   the slots they are assigned to empty, although it can't submit more than
+//!   `attempts_number` tickets per epoch.
+
+#![forbid(missing_docs, unsafe_code)]
+use std::{sync::Arc, time::Duration, thread, collections::{BTreeMap, HashMap}, pin::Pin};
+
+use consensus_common::{
+	self, BlockImport, Environment, Proposer, CanAuthorWith, ForkChoiceStrategy, BlockImportParams,
+	BlockOrigin, SelectChain, SlotData,
+};
+use consensus_common::import_queue::{
+	Verifier, BasicQueue, BoxBlockImport, BoxJustificationImport, BoxFinalityProofImport,
+};
+use client_api::backend::AuxStore;
+use sp_blockchain::{HeaderBackend, Result as ClientResult, well_known_cache_keys::Id as CacheKeyId};
+
+use block_builder_api::BlockBuilder as BlockBuilderApi;
+
+use sp_runtime::{generic::BlockId, Justification};
+use sp_runtime::traits::{Block as BlockT, Header, DigestItemFor, ProvideRuntimeApi, Zero};
+
+use primitives::Pair;
+use inherents::{InherentDataProviders, InherentData};
+
+use futures::prelude::*;
+use parking_lot::Mutex;
+use log::{debug, info, trace, warn};
+
+use sp_timestamp::{
+	TimestampInherentData, InherentType as TimestampInherent, InherentError as TIError
+};
+
+use sc_telemetry::{telemetry, CONSENSUS_TRACE, CONSENSUS_DEBUG, CONSENSUS_INFO};
+
+use slots::{CheckedHeader, SlotWorker, SlotInfo, SlotCompatible, check_equivocation};
+
+use keystore::KeyStorePtr;
+
+pub use sp_consensus_sassafras::{
+	SassafrasApi, SassafrasConfiguration, CompatibleDigestItem, Epoch, PreDigest, Ticket,
+	TicketProof, AuthorityId, AuthorityIndex, AuthorityPair, AuthoritySignature, SlotNumber,
+	SASSAFRAS_ENGINE_ID,
+	inherents::{SassafrasInherentData, InherentDataProvider, INHERENT_IDENTIFIER},
+};
+pub use consensus_common::SyncOracle;
+
+use authorship::TicketSecret;
+
+mod authorship;
+mod aux_schema;
+
+/// Sassafras configuration, fetched from the runtime.
+#[derive(Clone)]
+pub struct Config(slots::SlotDuration<SassafrasConfiguration>);
+
+impl Config {
+	/// Either fetch the slot duration from disk or compute it from the genesis
+	/// state.
+	pub fn get_or_compute<B: BlockT, C>(client: &C) -> ClientResult<Self> where
+		C: AuxStore + ProvideRuntimeApi, C::Api: SassafrasApi<B, Error = sp_blockchain::Error>,
+	{
+		trace!(target: "sassafras", "Getting slot duration");
+		match slots::SlotDuration::get_or_compute(client, |a, b| a.configuration(b)).map(Self) {
+			Ok(s) => Ok(s),
+			Err(s) => {
+				warn!(target: "sassafras", "Failed to get slot duration");
+				Err(s)
+			}
+		}
+	}
+}
+
+impl std::ops::Deref for Config {
+	type Target = SassafrasConfiguration;
+
+	fn deref(&self) -> &SassafrasConfiguration {
+		&*self.0
+	}
+}
+
+/// The secrets of the tickets generated by local authorities, indexed by
+/// the epoch they were generated for. This caches the secrets persisted in
+/// the aux-db.
+type TicketSecrets = Arc<Mutex<HashMap<u64, HashMap<Ticket, TicketSecret>>>>;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct SassafrasSlotCompatible;
+
+impl SlotCompatible for SassafrasSlotCompatible {
+	fn extract_timestamp_and_slot(
+		&self,
+		data: &InherentData
+	) -> Result<(TimestampInherent, SlotNumber, std::time::Duration), consensus_common::Error> {
+		data.timestamp_inherent_data()
+			.and_then(|t| data.sassafras_inherent_data().map(|a| (t, a)))
+			.map_err(Into::into)
+			.map_err(consensus_common::Error::InherentData)
+			.map(|(x, y)| (x, y, Default::default()))
+	}
+}
+
+/// Start the Sassafras worker. The returned future should be run in a futures executor.
+pub fn start_sassafras<B, C, SC, E, I, SO, CAW, Error>(
+	config: Config,
+	client: Arc<C>,
+	select_chain: SC,
+	block_import: I,
+	env: E,
+	sync_oracle: SO,
+	inherent_data_providers: InherentDataProviders,
+	force_authoring: bool,
+	keystore: KeyStorePtr,
+	can_author_with: CAW,
+) -> Result<impl futures01::Future<Item = (), Error = ()>, consensus_common::Error> where
+	B: BlockT,
+	C: ProvideRuntimeApi + HeaderBackend<B> + AuxStore + Send + Sync,
+	C::Api: SassafrasApi<B, Error = sp_blockchain::Error>,
+	SC: SelectChain<B>,
+	E: Environment<B, Error=Error> + Send + Sync + 'static,
+	E::Proposer: Proposer<B, Error=Error>,
+	<E::Proposer as Proposer<B>>::Create: Unpin + Send,
+	I: BlockImport<B> + Send + Sync + 'static,
+	Error: ::std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
+	SO: SyncOracle + Send + Sync + Clone,
+	CAW: CanAuthorWith<B> + Send,
+{
+	let worker = SassafrasWorker {
+		client: client.clone(),
+		block_import: Arc::new(Mutex::new(block_import)),
+		env,
+		keystore,
+		sync_oracle: sync_oracle.clone(),
+		force_authoring,
+		config: config.clone(),
+		ticket_secrets: Default::default(),
+	};
+	register_sassafras_inherent_data_provider(
+		&inherent_data_providers,
+		config.slot_duration(),
+	)?;
+	Ok(slots::start_slot_worker::<_, _, _, _, _, SassafrasSlotCompatible, _>(
+		config.0,
+		select_chain,
+		worker,
+		sync_oracle,
+		inherent_data_providers,
+		SassafrasSlotCompatible,
+		can_author_with,
+	).map(|()| Ok::<(), ()>(())).compat())
+}
+
+struct SassafrasWorker<C, E, I, SO> {
+	client: Arc<C>,
+	block_import: Arc<Mutex<I>>,
+	env: E,
+	keystore: KeyStorePtr,
+	sync_oracle: SO,
+	force_authoring: bool,
+	config: Config,
+	ticket_secrets: TicketSecrets,
+}
+
+impl<C, E, I, SO> SassafrasWorker<C, E, I, SO> {
+	/// Generate and submit our tickets for the epoch following the one of
+	/// the given block, unless we already did. Tickets generated before a
+	/// restart are loaded from disk and submitted again.
+	fn submit_tickets<B>(&self, parent: &B::Header) -> Result<(), sp_blockchain::Error> where
+		B: BlockT,
+		C: ProvideRuntimeApi + AuxStore,
+		C::Api: SassafrasApi<B, Error = sp_blockchain::Error>,
+	{
+		let at = BlockId::Hash(parent.hash());
+		let runtime_api = self.client.runtime_api();
+		let next_epoch = runtime_api.next_epoch(&at)?;
+
+		let tickets = {
+			let mut ticket_secrets = self.ticket_secrets.lock();
+			if ticket_secrets.contains_key(&next_epoch.epoch_index) {
+				return Ok(());
+			}
+
+			// we only need the secrets of the current and next epochs.
+			ticket_secrets.retain(|epoch_index, _| *epoch_index + 1 >= next_epoch.epoch_index);
+
+			let tickets = match aux_schema::load_ticket_secrets(
+				&*self.client,
+				next_epoch.epoch_index,
+			)? {
+				Some(tickets) => tickets,
+				None => {
+					let tickets = authorship::generate_tickets(
+						&next_epoch,
+						&self.config,
+						&self.keystore,
+					);
+					aux_schema::write_ticket_secrets(
+						&*self.client,
+						next_epoch.epoch_index,
+						&tickets,
+					)?;
+					tickets
+				},
+			};
+
+			ticket_secrets.insert(next_epoch.epoch_index, tickets.iter().cloned().collect());
+			tickets
+		};
+
+		// the ticket proofs are verified against the authority submitting
+		// them, and each authority has at most `attempts_number` tickets.
+		let mut authority_tickets = BTreeMap::<AuthorityIndex, Vec<TicketProof>>::new();
+		for (_, secret) in &tickets {
+			authority_tickets.entry(secret.authority_index).or_default().push(secret.proof());
+		}
+
+		for (authority_index, proofs) in authority_tickets {
+			if !runtime_api.submit_tickets_unsigned_extrinsic(&at, authority_index, proofs)? {
+				warn!(target: "sassafras", "Failed to submit tickets for epoch {}", next_epoch.epoch_index);
+			}
+		}
+
+		if !tickets.is_empty() {
+			info!(
+				target: "sassafras",
+				"Submitted {} tickets for epoch {}",
+				tickets.len(),
+				next_epoch.epoch_index,
+			);
+		}
+
+		Ok(())
+	}
+}
+
+impl<B, C, E, I, Error, SO> slots::SimpleSlotWorker<B> for SassafrasWorker<C, E, I, SO> where
+	B: BlockT,
+	C: ProvideRuntimeApi + HeaderBackend<B> + AuxStore + Sync,
+	C::Api: SassafrasApi<B, Error = sp_blockchain::Error>,
+	E: Environment<B, Error=Error>,
+	E::Proposer: Proposer<B, Error=Error>,
+	<E::Proposer as Proposer<B>>::Create: Unpin + Send,
+	I: BlockImport<B> + Send + Sync + 'static,
+	SO: SyncOracle + Send + Clone,
+	Error: ::std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
+{
+	type EpochData = Epoch;
+	type Claim = (PreDigest, AuthorityPair);
+	type SyncOracle = SO;
+	type Proposer = E::Proposer;
+	type BlockImport = I;
+
+	fn logging_target(&self) -> &'static str {
+		"sassafras"
+	}
+
+	fn block_import(&self) -> Arc<Mutex<Self::BlockImport>> {
+		self.block_import.clone()
+	}
+
+	fn epoch_data(&self, header: &B::Header, slot_number: u64) -> Result<Self::EpochData, consensus_common::Error> {
+		self.client.runtime_api()
+			.slot_epoch(&BlockId::Hash(header.hash()), slot_number)
+			.map_err(|e| consensus_common::Error::ChainLookup(format!("{:?}", e)))
+	}
+
+	fn authorities_len(&self, epoch_data: &Self::EpochData) -> usize {
+		epoch_data.authorities.len()
+	}
+
+	fn claim_slot(
+		&self,
+		header: &B::Header,
+		slot_number: u64,
+		epoch_data: &Self::EpochData,
+	) -> Option<Self::Claim> {
+		if let Err(e) = self.submit_tickets::<B>(header) {
+			warn!(target: "sassafras", "Failed to submit tickets: {:?}", e);
+		}
+
+		let slot_ticket = self.client.runtime_api()
+			.slot_ticket(&BlockId::Hash(header.hash()), slot_number)
+			.ok()?;
+
+		let mut ticket_secrets = self.ticket_secrets.lock();
+		if !ticket_secrets.contains_key(&epoch_data.epoch_index) {
+			// the tickets of the current epoch were generated during the
+			// previous one, possibly before a restart.
+			match aux_schema::load_ticket_secrets(&*self.client, epoch_data.epoch_index) {
+				Ok(Some(secrets)) => {
+					ticket_secrets.insert(epoch_data.epoch_index, secrets.into_iter().collect());
+				},
+				Ok(None) => {},
+				Err(e) => warn!(target: "sassafras", "Failed to load ticket secrets: {:?}", e),
+			}
+		}
+
+		let epoch_secrets = ticket_secrets.get(&epoch_data.epoch_index);
+
+		authorship::claim_slot(
+			slot_number,
+			epoch_data,
+			slot_ticket,
+			|ticket| epoch_secrets.and_then(|secrets| secrets.get(ticket)),
+			&self.keystore,
+		)
+	}
+
+	fn pre_digest_data(&self, _slot_number: u64, claim: &Self::Claim) -> Vec<sp_runtime::DigestItem<B::Hash>> {
+		vec![
+			<DigestItemFor<B> as CompatibleDigestItem>::sassafras_pre_digest(claim.0),
+		]
+	}
+
+	fn block_import_params(&self) -> Box<dyn Fn(
+		B::Header,
+		&B::Hash,
+		Vec<B::Extrinsic>,
+		Self::Claim,
+	) -> consensus_common::BlockImportParams<B> + Send> {
+		Box::new(|header, header_hash, body, (_, pair)| {
+			// sign the pre-sealed hash of the block and then
+			// add it to a digest item.
+			let signature = pair.sign(header_hash.as_ref());
+			let signature_digest_item = <DigestItemFor<B> as CompatibleDigestItem>::sassafras_seal(signature);
+
+			BlockImportParams {
+				origin: BlockOrigin::Own,
+				header,
+				justification: None,
+				post_digests: vec![signature_digest_item],
+				body: Some(body),
+				finalized: false,
+				auxiliary: Vec::new(),
+				fork_choice: ForkChoiceStrategy::LongestChain,
+				allow_missing_state: false,
+				import_existing: false,
+			}
+		})
+	}
+
+	fn force_authoring(&self) -> bool {
+		self.force_authoring
+	}
+
+	fn sync_oracle(&mut self) -> &mut Self::SyncOracle {
+		&mut self.sync_oracle
+	}
+
+	fn proposer(&mut self, block: &B::Header) -> Result<Self::Proposer, consensus_common::Error> {
+		self.env.init(block).map_err(|e| {
+			consensus_common::Error::ClientImport(format!("{:?}", e)).into()
+		})
+	}
+
+	fn proposing_remaining_duration(
+		&self,
+		head: &B::Header,
+		slot_info: &SlotInfo
+	) -> Option<std::time::Duration> {
+		// never give more than 20 times more lenience.
+		const BACKOFF_CAP: u64 = 20;
+
+		let slot_remaining = self.slot_remaining_duration(slot_info);
+		let parent_slot = match find_pre_digest::<B>(head) {
+			Err(_) => return Some(slot_remaining),
+			Ok(d) => d.slot_number(),
+		};
+
+		// we allow a lenience of the number of slots since the head of the
+		// chain was produced, minus 1 (since there is always a difference of at least 1)
+		//
+		// linear back-off.
+		// in normal cases we only attempt to issue blocks up to the end of the slot.
+		// when the chain has been stalled for a few slots, we give more lenience.
+		let slot_lenience = slot_info.number.saturating_sub(parent_slot + 1);
+		let slot_lenience = std::cmp::min(slot_lenience, BACKOFF_CAP);
+		let slot_lenience = Duration::from_secs(slot_lenience * slot_info.duration);
+		Some(slot_lenience + slot_remaining)
+	}
+}
+
+impl<B, C, E, I, Error, SO> SlotWorker<B> for SassafrasWorker<C, E, I, SO> where
+	B: BlockT,
+	C: ProvideRuntimeApi + HeaderBackend<B> + Sync + Send,
+	C::Api: SassafrasApi<B, Error = sp_blockchain::Error>,
+	E: Environment<B, Error=Error> + Send + Sync,
+	E::Proposer: Proposer<B, Error=Error>,
+	<E::Proposer as Proposer<B>>::Create: Unpin + Send + 'static,
+	I: BlockImport<B> + Send + Sync + 'static,
+	SO: SyncOracle + Send + Sync + Clone,
+	Error: ::std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
+{
+	type OnSlot = Pin<Box<dyn Future<Output = Result<(), consensus_common::Error>> + Send>>;
+
+	fn on_slot(&mut self, chain_head: B::Header, slot_info: SlotInfo) -> Self::OnSlot {
+		<Self as slots::SimpleSlotWorker<B>>::on_slot(self, chain_head, slot_info)
+	}
+}
+
+fn sassafras_err<B: BlockT>(error: Error<B>) -> Error<B> {
+	debug!(target: "sassafras", "{}", error);
+	error
+}
+
+#[derive(derive_more::Display)]
+enum Error<B: BlockT> {
+	#[display(fmt = "Multiple Sassafras pre-runtime headers")]
+	MultipleHeaders,
+	#[display(fmt = "No Sassafras pre-runtime digest found")]
+	NoDigestFound,
+	#[display(fmt = "Header {:?} is unsealed", _0)]
+	HeaderUnsealed(B::Hash),
+	#[display(fmt = "Header {:?} has a bad seal", _0)]
+	HeaderBadSeal(B::Hash),
+	#[display(fmt = "Slot author not found")]
+	SlotAuthorNotFound,
+	#[display(fmt = "Bad signature on {:?}", _0)]
+	BadSignature(B::Hash),
+	#[display(fmt = "Ticket VRF verification failed: {:?}", _0)]
+	TicketVerificationFailed(schnorrkel::SignatureError),
+	#[display(fmt = "Claimed ticket is not assigned to slot {}", _0)]
+	InvalidTicket(SlotNumber),
+	#[display(fmt = "Slot {} has a ticket and can't be claimed as a secondary slot", _0)]
+	UnexpectedSecondaryClaim(SlotNumber),
+	#[display(fmt = "Rejecting block too far in future")]
+	TooFarInFuture,
+	Client(sp_blockchain::Error),
+	DataProvider(String),
+	Runtime(String),
+}
+
+fn find_pre_digest<B: BlockT>(header: &B::Header) -> Result<PreDigest, Error<B>> {
+	// genesis block doesn't contain a pre digest so let's generate a
+	// dummy one to not break any invariants in the rest of the code
+	if header.number().is_zero() {
+		return Ok(PreDigest::Secondary {
+			slot_number: 0,
+			authority_index: 0,
+		});
+	}
+
+	let mut pre_digest: Option<_> = None;
+	for log in header.digest().logs() {
+		trace!(target: "sassafras", "Checking log {:?}", log);
+		match (log.as_sassafras_pre_digest(), pre_digest.is_some()) {
+			(Some(_), true) => Err(sassafras_err(Error::MultipleHeaders))?,
+			(None, _) => trace!(target: "sassafras", "Ignoring digest not meant for us"),
+			(s, false) => pre_digest = s,
+		}
+	}
+	pre_digest.ok_or_else(|| sassafras_err(Error::NoDigestFound))
+}
+
+/// Check a header has been signed by the author of its slot. For primary
+/// claims, the revealed ticket must be the ticket assigned to the slot and
+/// its VRF proof must be valid under the author's key. Secondary claims are
+/// only valid for slots without a ticket.
+///
+/// If the slot is too far in the future, the header is deferred. If it's
+/// successful, returns the pre-header and the digest item containing the seal.
+fn check_header<C, B: BlockT>(
+	client: &C,
+	slot_now: u64,
+	mut header: B::Header,
+	hash: B::Hash,
+) -> Result<CheckedHeader<B::Header, (u64, DigestItemFor<B>)>, Error<B>> where
+	C: ProvideRuntimeApi + AuxStore,
+	C::Api: SassafrasApi<B, Error = sp_blockchain::Error>,
+{
+	let seal = match header.digest_mut().pop() {
+		Some(x) => x,
+		None => return Err(Error::HeaderUnsealed(hash)),
+	};
+
+	let sig = seal.as_sassafras_seal().ok_or_else(|| {
+		sassafras_err(Error::HeaderBadSeal(hash))
+	})?;
+
+	let pre_digest = find_pre_digest::<B>(&header)?;
+	let slot_number = pre_digest.slot_number();
+
+	if slot_number > slot_now {
+		header.digest_mut().push(seal);
+		return Ok(CheckedHeader::Deferred(header, slot_number));
+	}
+
+	let parent_id = BlockId::Hash(*header.parent_hash());
+	let runtime_api = client.runtime_api();
+	let epoch = runtime_api.slot_epoch(&parent_id, slot_number).map_err(Error::Client)?;
+	let slot_ticket = runtime_api.slot_ticket(&parent_id, slot_number).map_err(Error::Client)?;
+
+	let author = epoch.authorities.get(pre_digest.authority_index() as usize)
+		.ok_or_else(|| sassafras_err(Error::SlotAuthorNotFound))?;
+
+	let pre_hash = header.hash();
+	if !AuthorityPair::verify(&sig, pre_hash.as_ref(), author) {
+		return Err(sassafras_err(Error::BadSignature(hash)));
+	}
+
+	match pre_digest {
+		PreDigest::Primary { ticket_attempt, ticket_vrf_output, ticket_vrf_proof, .. } => {
+			let ticket = authorship::verify_ticket(
+				author,
+				&epoch,
+				ticket_attempt,
+				&ticket_vrf_output,
+				&ticket_vrf_proof,
+			).map_err(|e| sassafras_err(Error::TicketVerificationFailed(e)))?;
+
+			if slot_ticket != Some(ticket) {
+				return Err(sassafras_err(Error::InvalidTicket(slot_number)));
+			}
+		},
+		PreDigest::Secondary { authority_index, .. } => {
+			if slot_ticket.is_some() {
+				return Err(sassafras_err(Error::UnexpectedSecondaryClaim(slot_number)));
+			}
+
+			if epoch.secondary_slot_author(slot_number) != Some(authority_index) {
+				return Err(sassafras_err(Error::SlotAuthorNotFound));
+			}
+		},
+	}
+
+	if let Some(equivocation_proof) = check_equivocation(
+		client,
+		slot_now,
+		slot_number,
+		&header,
+		author,
+	).map_err(Error::Client)? {
+		info!(
+			target: "sassafras",
+			"Slot author {:?} is equivocating at slot {} with headers {:?} and {:?}",
+			author,
+			slot_number,
+			equivocation_proof.fst_header().hash(),
+			equivocation_proof.snd_header().hash(),
+		);
+	}
+
+	Ok(CheckedHeader::Checked(header, (slot_number, seal)))
+}
+
+/// A verifier for Sassafras blocks.
+pub struct SassafrasVerifier<C> {
+	client: Arc<C>,
+	inherent_data_providers: inherents::InherentDataProviders,
+}
+
+impl<C> SassafrasVerifier<C> {
+	fn check_inherents<B: BlockT>(
+		&self,
+		block: B,
+		block_id: BlockId<B>,
+		inherent_data: InherentData,
+		timestamp_now: u64,
+	) -> Result<(), Error<B>>
+		where C: ProvideRuntimeApi, C::Api: BlockBuilderApi<B, Error = sp_blockchain::Error>
+	{
+		const MAX_TIMESTAMP_DRIFT_SECS: u64 = 60;
+
+		let inherent_res = self.client.runtime_api().check_inherents(
+			&block_id,
+			block,
+			inherent_data,
+		).map_err(Error::Client)?;
+
+		if !inherent_res.ok() {
+			inherent_res
+				.into_errors()
+				.try_for_each(|(i, e)| match TIError::try_from(&i, &e) {
+					Some(TIError::ValidAtTimestamp(timestamp)) => {
+						// halt import until timestamp is valid.
+						// reject when too far ahead.
+						if timestamp > timestamp_now + MAX_TIMESTAMP_DRIFT_SECS {
+							return Err(Error::TooFarInFuture);
+						}
+
+						let diff = timestamp.saturating_sub(timestamp_now);
+						info!(
+							target: "sassafras",
+							"halting for block {} seconds in the future",
+							diff
+						);
+						telemetry!(CONSENSUS_INFO; "sassafras.halting_for_future_block";
+							"diff" => ?diff
+						);
+						thread::sleep(Duration::from_secs(diff));
+						Ok(())
+					},
+					Some(TIError::Other(e)) => Err(Error::Runtime(e.into())),
+					None => Err(Error::DataProvider(
+						self.inherent_data_providers.error_to_string(&i, &e)
+					)),
+				})
+		} else {
+			Ok(())
+		}
+	}
+}
+
+impl<B: BlockT, C> Verifier<B> for SassafrasVerifier<C> where
+	C: ProvideRuntimeApi + Send + Sync + AuxStore,
+	C::Api: BlockBuilderApi<B, Error = sp_blockchain::Error>
+		+ SassafrasApi<B, Error = sp_blockchain::Error>,
+{
+	fn verify(
+		&mut self,
+		origin: BlockOrigin,
+		header: B::Header,
+		justification: Option<Justification>,
+		mut body: Option<Vec<B::Extrinsic>>,
+	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+		let mut inherent_data = self.inherent_data_providers
+			.create_inherent_data()
+			.map_err(|e| e.into_string())?;
+		let (timestamp_now, slot_now, _) = SassafrasSlotCompatible.extract_timestamp_and_slot(&inherent_data)
+			.map_err(|e| format!("Could not extract timestamp and slot: {:?}", e))?;
+		let hash = header.hash();
+		let parent_hash = *header.parent_hash();
+
+		// we add one to allow for some small drift.
+		let checked_header = check_header::<C, B>(
+			&self.client,
+			slot_now + 1,
+			header,
+			hash,
+		).map_err(|e| e.to_string())?;
+
+		match checked_header {
+			CheckedHeader::Checked(pre_header, (slot_number, seal)) => {
+				// if the body is passed through, we need to use the runtime
+				// to check that the internally-set timestamp in the inherents
+				// actually matches the slot set in the seal.
+				if let Some(inner_body) = body.take() {
+					inherent_data.sassafras_replace_inherent_data(slot_number);
+					let block = B::new(pre_header.clone(), inner_body);
+
+					self.check_inherents(
+						block.clone(),
+						BlockId::Hash(parent_hash),
+						inherent_data,
+						timestamp_now,
+					).map_err(|e| e.to_string())?;
+
+					let (_, inner_body) = block.deconstruct();
+					body = Some(inner_body);
+				}
+
+				trace!(target: "sassafras", "Checked {:?}; importing.", pre_header);
+				telemetry!(CONSENSUS_TRACE; "sassafras.checked_and_importing"; "pre_header" => ?pre_header);
+
+				let block_import_params = BlockImportParams {
+					origin,
+					header: pre_header,
+					post_digests: vec![seal],
+					body,
+					finalized: false,
+					justification,
+					auxiliary: Vec::new(),
+					fork_choice: ForkChoiceStrategy::LongestChain,
+					allow_missing_state: false,
+					import_existing: false,
+				};
+
+				Ok((block_import_params, None))
+			}
+			CheckedHeader::Deferred(a, b) => {
+				debug!(target: "sassafras", "Checking {:?} failed; {:?}, {:?}.", hash, a, b);
+				telemetry!(CONSENSUS_DEBUG; "sassafras.header_too_far_in_future";
+					"hash" => ?hash, "a" => ?a, "b" => ?b
+				);
+				Err(format!("Header {:?} rejected: too far in the future", hash))
+			}
+		}
+	}
+}
+
+/// The Sassafras import queue type.
+pub type SassafrasImportQueue<B> = BasicQueue<B>;
+
+/// Register the Sassafras inherent data provider, if not registered already.
+fn register_sassafras_inherent_data_provider(
+	inherent_data_providers: &InherentDataProviders,
+	slot_duration: u64,
+) -> Result<(), consensus_common::Error> {
+	if !inherent_data_providers.has_provider(&INHERENT_IDENTIFIER) {
+		inherent_data_providers
+			.register_provider(InherentDataProvider::new(slot_duration))
+			.map_err(Into::into)
+			.map_err(consensus_common::Error::InherentData)
+	} else {
+		Ok(())
+	}
+}
+
+/// Start an import queue for the Sassafras consensus algorithm.
+pub fn import_queue<B, C>(
+	config: Config,
+	block_import: BoxBlockImport<B>,
+	justification_import: Option<BoxJustificationImport<B>>,
+	finality_proof_import: Option<BoxFinalityProofImport<B>>,
+	client: Arc<C>,
+	inherent_data_providers: InherentDataProviders,
+) -> Result<SassafrasImportQueue<B>, consensus_common::Error> where
+	B: BlockT,
+	C: 'static + ProvideRuntimeApi + Send + Sync + AuxStore,
+	C::Api: BlockBuilderApi<B, Error = sp_blockchain::Error>
+		+ SassafrasApi<B, Error = sp_blockchain::Error>,
+{
+	register_sassafras_inherent_data_provider(&inherent_data_providers, config.slot_duration())?;
+
+	let verifier = SassafrasVerifier {
+		client,
+		inherent_data_providers,
+	};
+	Ok(BasicQueue::new(
+		verifier,
+		block_import,
+		justification_import,
+		finality_proof_import,
+	))
+}
@@ -28,7 +28,7 @@ mod aux_schema;
 
 pub use slots::{SignedDuration, SlotInfo};
 use slots::Slots;
-pub use aux_schema::{check_equivocation, MAX_SLOT_CAPACITY, PRUNING_BOUND};
+pub use aux_schema::{check_equivocation, EquivocationProof, MAX_SLOT_CAPACITY, PRUNING_BOUND};
 
 use codec::{Decode, Encode};
 use consensus_common::{BlockImport, Proposer, SyncOracle, SelectChain, CanAuthorWith, SlotData};
@@ -37,7 +37,9 @@ use futures_timer::Delay;
 use inherents::{InherentData, InherentDataProviders};
 use log::{debug, error, info, warn};
 use sp_runtime::generic::BlockId;
-use sp_runtime::traits::{ApiRef, Block as BlockT, Header, ProvideRuntimeApi};
+use sp_runtime::traits::{
+	ApiRef, Block as BlockT, Header, ProvideRuntimeApi, SimpleArithmetic, UniqueSaturatedInto, One,
+};
 use std::{fmt::Debug, ops::Deref, pin::Pin, sync::Arc, time::{Instant, Duration}};
 use sc_telemetry::{telemetry, CONSENSUS_DEBUG, CONSENSUS_WARN, CONSENSUS_INFO};
 use parking_lot::Mutex;
@@ -113,6 +115,12 @@ pub trait SimpleSlotWorker<B: BlockT> {
 	/// Returns a `Proposer` to author on top of the given block.
 	fn proposer(&mut self, block: &B::Header) -> Result<Self::Proposer, consensus_common::Error>;
 
+	/// Whether to skip authoring in the given claimed slot on top of the given chain head,
+	/// e.g. as decided by a `BackoffAuthoringBlocksStrategy`. Defaults to never skipping.
+	fn should_backoff(&self, _slot_number: u64, _chain_head: &B::Header) -> bool {
+		false
+	}
+
 	/// Remaining duration of the slot.
 	fn slot_remaining_duration(&self, slot_info: &SlotInfo) -> Duration {
 		let now = Instant::now();
@@ -187,6 +195,11 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			Some(claim) => claim,
 		};
 
+		// only slots we could author in are backed off from.
+		if self.should_backoff(slot_number, &chain_head) {
+			return Box::pin(future::ready(Ok(())));
+		}
+
 		debug!(
 			target: self.logging_target(), "Starting authorship at slot {}; timestamp = {}",
 			slot_number,
@@ -374,6 +387,88 @@ where
 		})
 }
 
+/// A strategy deciding whether to skip authoring a block in a slot.
+pub trait BackoffAuthoringBlocksStrategy<N> {
+	/// Returns whether authoring in `slot_now` should be skipped, given the chain head the
+	/// block would be built on and the slot it was authored in, and the last finalized block.
+	fn should_backoff(
+		&self,
+		chain_head_number: N,
+		chain_head_slot: u64,
+		finalized_number: N,
+		slot_now: u64,
+		logging_target: &str,
+	) -> bool;
+}
+
+/// Back off from authoring blocks as the distance between the best and the finalized block
+/// grows, so that the unfinalized chain doesn't grow without bound when finality stalls.
+///
+/// Past `unfinalized_slack` unfinalized blocks, one slot is skipped after the chain head's
+/// slot for every `authoring_bias` additional unfinalized blocks, up to `max_interval`
+/// skipped slots. Authors still build a block at least once every `max_interval` slots, so
+/// that finality gadgets relying on block production keep making progress.
+#[derive(Debug, Clone)]
+pub struct BackoffAuthoringOnFinalizedHeadLagging<N> {
+	/// Maximum number of slots to skip before authoring a block.
+	pub max_interval: N,
+	/// Number of unfinalized blocks tolerated before backing off.
+	pub unfinalized_slack: N,
+	/// Number of additional unfinalized blocks that make authors skip one more slot.
+	pub authoring_bias: N,
+}
+
+impl<N: SimpleArithmetic> Default for BackoffAuthoringOnFinalizedHeadLagging<N> {
+	fn default() -> Self {
+		BackoffAuthoringOnFinalizedHeadLagging {
+			// never wait more than 100 slots.
+			max_interval: 100u32.into(),
+			// don't back off until the finalized block is 50 blocks behind.
+			unfinalized_slack: 50u32.into(),
+			// each slot skipped for every two unfinalized blocks past the slack.
+			authoring_bias: 2u32.into(),
+		}
+	}
+}
+
+impl<N: SimpleArithmetic + Copy> BackoffAuthoringBlocksStrategy<N> for BackoffAuthoringOnFinalizedHeadLagging<N> {
+	fn should_backoff(
+		&self,
+		chain_head_number: N,
+		chain_head_slot: u64,
+		finalized_number: N,
+		slot_now: u64,
+		logging_target: &str,
+	) -> bool {
+		// this shouldn't happen: the chain head is from an earlier slot.
+		if slot_now <= chain_head_slot {
+			return false;
+		}
+
+		let unfinalized_block_length = chain_head_number.saturating_sub(finalized_number);
+		let interval = unfinalized_block_length.saturating_sub(self.unfinalized_slack)
+			/ self.authoring_bias.max(N::one());
+		let interval: u64 = interval.min(self.max_interval).unique_saturated_into();
+
+		// we're doing arithmetic between block and slot numbers.
+		if slot_now <= chain_head_slot.saturating_add(interval) {
+			debug!(
+				target: logging_target,
+				"Backing off claiming new slot for block authorship: finality is lagging."
+			);
+			return true;
+		}
+
+		false
+	}
+}
+
+impl<N> BackoffAuthoringBlocksStrategy<N> for () {
+	fn should_backoff(&self, _: N, _: u64, _: N, _: u64, _: &str) -> bool {
+		false
+	}
+}
+
 /// A header which has been checked
 pub enum CheckedHeader<H, S> {
 	/// A header which has slot in the future. this is the full header (not stripped)
@@ -454,3 +549,48 @@ impl<T: Clone> SlotDuration<T> {
 		self.0.clone()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{BackoffAuthoringBlocksStrategy, BackoffAuthoringOnFinalizedHeadLagging};
+
+	#[test]
+	fn should_not_backoff_within_slack() {
+		let strategy = BackoffAuthoringOnFinalizedHeadLagging::<u64>::default();
+
+		// 50 unfinalized blocks, the head was authored in the previous slot.
+		assert!(!strategy.should_backoff(150, 10, 100, 11, "slots"));
+		// same slot as the chain head is never a backoff.
+		assert!(!strategy.should_backoff(1000, 10, 0, 10, "slots"));
+	}
+
+	#[test]
+	fn backoff_grows_with_unfinalized_blocks() {
+		let strategy = BackoffAuthoringOnFinalizedHeadLagging::<u64>::default();
+
+		// 60 unfinalized blocks: (60 - 50) / 2 = 5 slots to skip.
+		assert!(strategy.should_backoff(160, 10, 100, 15, "slots"));
+		assert!(!strategy.should_backoff(160, 10, 100, 16, "slots"));
+
+		// 70 unfinalized blocks: 10 slots to skip.
+		assert!(strategy.should_backoff(170, 10, 100, 20, "slots"));
+		assert!(!strategy.should_backoff(170, 10, 100, 21, "slots"));
+	}
+
+	#[test]
+	fn backoff_is_bounded_by_max_interval() {
+		let strategy = BackoffAuthoringOnFinalizedHeadLagging::<u64> {
+			max_interval: 20,
+			unfinalized_slack: 0,
+			authoring_bias: 1,
+		};
+
+		assert!(strategy.should_backoff(20_000, 10, 0, 30, "slots"));
+		assert!(!strategy.should_backoff(20_000, 10, 0, 31, "slots"));
+	}
+
+	#[test]
+	fn unit_strategy_never_backs_off() {
+		assert!(!BackoffAuthoringBlocksStrategy::<u64>::should_backoff(&(), 20_000, 10, 0, 11, "slots"));
+	}
+}
@@ -39,6 +39,30 @@ pub fn register_uncles_inherent_data_provider<B, C, SC>(
 	B: BlockT,
 	C: ProvideUncles<B> + Send + Sync + 'static,
 	SC: SelectChain<B> + 'static,
+{
+	register_filtered_uncles_inherent_data_provider(
+		client,
+		select_chain,
+		|_: &B::Header| true,
+		inherent_data_providers,
+	)
+}
+
+/// Register uncles inherent data provider, if not registered already, only providing the
+/// uncles for which `filter` returns `true`.
+///
+/// Consensus engines use the filter to leave out the uncles that don't follow their seal rules,
+/// which the runtime may not be able to fully check.
+pub fn register_filtered_uncles_inherent_data_provider<B, C, SC, F>(
+	client: Arc<C>,
+	select_chain: SC,
+	filter: F,
+	inherent_data_providers: &InherentDataProviders,
+) -> Result<(), consensus_common::Error> where
+	B: BlockT,
+	C: ProvideUncles<B> + Send + Sync + 'static,
+	SC: SelectChain<B> + 'static,
+	F: Fn(&B::Header) -> bool + Send + Sync + 'static,
 {
 	if !inherent_data_providers.has_provider(&sp_authorship::INHERENT_IDENTIFIER) {
 		inherent_data_providers
@@ -52,7 +76,7 @@ pub fn register_uncles_inherent_data_provider<B, C, SC>(
 						}
 					};
 					match client.uncles(chain_head.hash(), MAX_UNCLE_GENERATIONS.into()) {
-						Ok(uncles) => uncles,
+						Ok(uncles) => uncles.into_iter().filter(|uncle| filter(uncle)).collect(),
 						Err(e) => {
 							warn!(target: "uncles", "Unable to get uncles: {:?}", e);
 							Vec::new()
@@ -64,4 +88,3 @@ pub fn register_uncles_inherent_data_provider<B, C, SC>(
 	}
 	Ok(())
 }
-
@@ -473,6 +473,7 @@ pub struct BlockImportOperation<Block: BlockT, H: Hasher> {
 	finalized_blocks: Vec<(BlockId<Block>, Option<Justification>)>,
 	set_head: Option<BlockId<Block>>,
 	commit_state: bool,
+	storage_reset: bool,
 }
 
 impl<Block: BlockT, H: Hasher> BlockImportOperation<Block, H> {
@@ -548,6 +549,7 @@ impl<Block> client_api::backend::BlockImportOperation<Block, Blake2Hasher>
 
 		self.db_updates = transaction;
 		self.commit_state = true;
+		self.storage_reset = true;
 		Ok(root)
 	}
 
@@ -1078,9 +1080,7 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			};
 
 			trace!(target: "db", "Canonicalize block #{} ({:?})", new_canonical, hash);
-			let commit = self.storage.state_db.canonicalize_block(&hash)
-				.map_err(|e: state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
-			apply_state_commit(transaction, commit);
+			self.canonicalize_state(transaction, &hash, new_canonical)?;
 		};
 
 		Ok(())
@@ -1116,6 +1116,8 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			let hash = pending_block.header.hash();
 			let parent_hash = *pending_block.header.parent_hash();
 			let number = pending_block.header.number().clone();
+			// the whole state of an already imported block, e.g. one downloaded by state sync.
+			let imported_state = operation.storage_reset && !number.is_zero();
 
 			// blocks are keyed by number + hash.
 			let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
@@ -1126,12 +1128,14 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 				(Default::default(), Default::default())
 			};
 
-			utils::insert_hash_to_key_mapping(
-				&mut transaction,
-				columns::KEY_LOOKUP,
-				number,
-				hash,
-			)?;
+			if !imported_state {
+				utils::insert_hash_to_key_mapping(
+					&mut transaction,
+					columns::KEY_LOOKUP,
+					number,
+					hash,
+				)?;
+			}
 
 			let header_metadata = CachedHeaderMetadata::from(&pending_block.header);
 			self.blockchain.insert_header_metadata(
@@ -1162,8 +1166,12 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 					}
 				}
 				let number_u64 = number.saturated_into::<u64>();
-				let commit = self.storage.state_db.insert_block(&hash, number_u64, &pending_block.header.parent_hash(), changeset)
-					.map_err(|e: state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
+				let commit = if imported_state {
+					// the whole state of a known block, which becomes the new canonical state.
+					self.storage.state_db.insert_canonical_block(&hash, number_u64, changeset)
+				} else {
+					self.storage.state_db.insert_block(&hash, number_u64, &pending_block.header.parent_hash(), changeset)
+				}.map_err(|e: state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
 				apply_state_commit(&mut transaction, commit);
 
 				// Check if need to finalize. Genesis is always finalized instantly.
@@ -1180,7 +1188,9 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			self.changes_tries_storage.commit(&mut transaction, changes_trie_updates);
 			let cache = operation.old_state.release(); // release state reference so that it can be finalized
 
-			if finalized {
+			if imported_state {
+				// the block keeps its place in the chain, only its state is written.
+			} else if finalized {
 				// TODO: ensure best chain contains this block.
 				self.ensure_sequential_finalization(header, Some(last_finalized_hash))?;
 				self.note_finalized(
@@ -1196,7 +1206,9 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 
 			debug!(target: "db", "DB Commit {:?} ({}), best = {}", hash, number, is_best);
 
-			let displaced_leaf = {
+			let displaced_leaf = if imported_state {
+				None
+			} else {
 				let mut leaves = self.blockchain.leaves.write();
 				let displaced_leaf = leaves.import(hash, number, parent_hash);
 				leaves.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
@@ -1204,9 +1216,11 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 				displaced_leaf
 			};
 
-			let mut children = children::read_children(&*self.storage.db, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash)?;
-			children.push(hash);
-			children::write_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash, children);
+			if !imported_state {
+				let mut children = children::read_children(&*self.storage.db, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash)?;
+				children.push(hash);
+				children::write_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash, children);
+			}
 
 			meta_updates.push((hash, number, pending_block.leaf_state.is_best(), finalized));
 
@@ -1278,6 +1292,24 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 	}
 
 
+	// canonicalizes the state of a block. blocks imported without state, e.g. during state sync,
+	// have nothing to canonicalize, but the state db still fails if that leaves blocks with state
+	// behind.
+	fn canonicalize_state(
+		&self,
+		transaction: &mut DBTransaction,
+		hash: &Block::Hash,
+		number: u64,
+	) -> ClientResult<()> {
+		let commit = if self.storage.state_db.is_pruned(hash, number) {
+			self.storage.state_db.canonicalize_stateless_block(hash)
+		} else {
+			self.storage.state_db.canonicalize_block(hash)
+		}.map_err(|e: state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
+		apply_state_commit(transaction, commit);
+		Ok(())
+	}
+
 	// write stuff to a transaction after a new block is finalized.
 	// this canonicalizes finalized blocks. Fails if called with a block which
 	// was not a child of the last finalized block.
@@ -1298,9 +1330,7 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			let lookup_key = utils::number_and_hash_to_lookup_key(f_num, f_hash.clone())?;
 			transaction.put(columns::META, meta_keys::FINALIZED_BLOCK, &lookup_key);
 
-			let commit = self.storage.state_db.canonicalize_block(&f_hash)
-				.map_err(|e: state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
-			apply_state_commit(transaction, commit);
+			self.canonicalize_state(transaction, &f_hash, f_num.saturated_into::<u64>())?;
 
 			let changes_trie_config = self.changes_trie_config(parent_hash)?;
 			if let Some(changes_trie_config) = changes_trie_config {
@@ -1378,6 +1408,7 @@ impl<Block> client_api::backend::Backend<Block, Blake2Hasher> for Backend<Block>
 			finalized_blocks: Vec::new(),
 			set_head: None,
 			commit_state: false,
+			storage_reset: false,
 		})
 	}
 
@@ -22,7 +22,8 @@ use grandpa::voter_set::VoterSet;
 use codec::{Encode, Decode};
 use log::{debug, info};
 use sc_telemetry::{telemetry, CONSENSUS_INFO};
-use fg_primitives::{AuthorityId, AuthorityList};
+use fg_primitives::{AuthorityId, AuthorityList, SetId};
+use sp_runtime::traits::Zero;
 
 use std::cmp::Ord;
 use std::fmt::Debug;
@@ -96,6 +97,8 @@ pub(crate) struct AuthoritySet<H, N> {
 	// Forced changes are enacted on block depth (not finality), for this reason
 	// only one forced change should exist per fork.
 	pub(crate) pending_forced_changes: Vec<PendingChange<H, N>>,
+	// History of the changes enacted so far, used to build warp sync proofs.
+	pub(crate) authority_set_changes: AuthoritySetChanges<N>,
 }
 
 impl<H, N> AuthoritySet<H, N>
@@ -103,12 +106,13 @@ where H: PartialEq,
 	  N: Ord,
 {
 	/// Get a genesis set with given authorities.
-	pub(crate) fn genesis(initial: AuthorityList) -> Self {
+	pub(crate) fn genesis(initial: AuthorityList) -> Self where N: Zero {
 		AuthoritySet {
 			current_authorities: initial,
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			authority_set_changes: AuthoritySetChanges::genesis(N::zero()),
 		}
 	}
 
@@ -269,11 +273,15 @@ where
 					_ => unreachable!("pending_forced_changes only contains forced changes; forced changes have delay kind Best; qed."),
 				};
 
+				let mut authority_set_changes = self.authority_set_changes.clone();
+				authority_set_changes.append_forced(self.set_id + 1, best_number.clone());
+
 				new_set = Some((median_last_finalized, AuthoritySet {
 					current_authorities: change.next_authorities.clone(),
 					set_id: self.set_id + 1,
 					pending_standard_changes: ForkTree::new(), // new set, new changes.
 					pending_forced_changes: Vec::new(),
+					authority_set_changes,
 				}));
 
 				break;
@@ -330,6 +338,7 @@ where
 						"block" => ?change.canon_height
 					);
 
+					self.authority_set_changes.append_standard(self.set_id, finalized_number.clone());
 					self.current_authorities = change.next_authorities;
 					self.set_id += 1;
 
@@ -373,6 +382,57 @@ where
 	}
 }
 
+/// History of the authority set changes, used to build warp sync proofs.
+///
+/// Only standard changes carry a justification, so the history starts at the
+/// last forced change, or at genesis if there was none.
+#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+pub(crate) struct AuthoritySetChanges<N> {
+	/// The first set of the history, with the number of the block it is active
+	/// from. `None` until a change is enacted when the history is not known,
+	/// e.g. after a migration from a version that didn't keep it.
+	pub(crate) start: Option<(SetId, N)>,
+	/// Standard changes enacted since, as the id of the set that finalized the
+	/// change and the number of the block enacting it.
+	pub(crate) changes: Vec<(SetId, N)>,
+}
+
+impl<N> AuthoritySetChanges<N> {
+	/// The history of a chain starting at the genesis block `number`.
+	pub(crate) fn genesis(number: N) -> Self {
+		AuthoritySetChanges {
+			start: Some((0, number)),
+			changes: Vec::new(),
+		}
+	}
+
+	/// A history that isn't known yet.
+	pub(crate) fn unknown() -> Self {
+		AuthoritySetChanges {
+			start: None,
+			changes: Vec::new(),
+		}
+	}
+
+	/// Record a standard change finalized by the set `set_id` at block `number`.
+	pub(crate) fn append_standard(&mut self, set_id: SetId, number: N) {
+		if self.start.is_none() {
+			// the change can't be proven without knowing `set_id`, start
+			// the history with the set it enacts.
+			self.start = Some((set_id + 1, number));
+		} else {
+			self.changes.push((set_id, number));
+		}
+	}
+
+	/// Record a forced change to the set `set_id` at block `number`. Forced
+	/// changes have no justification, so the history restarts with the new set.
+	pub(crate) fn append_forced(&mut self, set_id: SetId, number: N) {
+		self.start = Some((set_id, number));
+		self.changes.clear();
+	}
+}
+
 /// Kinds of delays for pending changes.
 #[derive(Debug, Clone, Encode, Decode, PartialEq)]
 pub(crate) enum DelayKind<N> {
@@ -452,6 +512,7 @@ mod tests {
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			authority_set_changes: AuthoritySetChanges::genesis(0),
 		};
 
 		let change_a = PendingChange {
@@ -519,6 +580,7 @@ mod tests {
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			authority_set_changes: AuthoritySetChanges::genesis(0),
 		};
 
 		let set_a = vec![(AuthorityId::from_slice(&[1; 32]), 5)];
@@ -584,6 +646,7 @@ mod tests {
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			authority_set_changes: AuthoritySetChanges::genesis(0),
 		};
 
 		let set_a = vec![(AuthorityId::from_slice(&[1; 32]), 5)];
@@ -641,6 +704,9 @@ mod tests {
 
 		assert_eq!(authorities.current_authorities, set_c);
 		assert_eq!(authorities.set_id, 2);
+
+		// both enacted changes are kept in the history
+		assert_eq!(authorities.authority_set_changes.changes, vec![(0, 15), (1, 40)]);
 	}
 
 	#[test]
@@ -650,6 +716,7 @@ mod tests {
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			authority_set_changes: AuthoritySetChanges::genesis(0),
 		};
 
 		let set_a = vec![(AuthorityId::from_slice(&[1; 32]), 5)];
@@ -716,6 +783,7 @@ mod tests {
 			set_id: 0,
 			pending_standard_changes: ForkTree::new(),
 			pending_forced_changes: Vec::new(),
+			authority_set_changes: AuthoritySetChanges::genesis(0),
 		};
 
 		let set_a = vec![(AuthorityId::from_slice(&[1; 32]), 5)];
@@ -781,7 +849,34 @@ mod tests {
 				set_id: 1,
 				pending_standard_changes: ForkTree::new(),
 				pending_forced_changes: Vec::new(),
+				// the history restarts with the forced change.
+				authority_set_changes: AuthoritySetChanges {
+					start: Some((1, 15)),
+					changes: Vec::new(),
+				},
 			})
 		);
 	}
+
+	#[test]
+	fn authority_set_changes_restart_on_forced_change() {
+		let mut changes = AuthoritySetChanges::genesis(0);
+		changes.append_standard(0, 15);
+		changes.append_standard(1, 40);
+		assert_eq!(changes.start, Some((0, 0)));
+		assert_eq!(changes.changes, vec![(0, 15), (1, 40)]);
+
+		// the standard changes before a forced change can't be used anymore.
+		changes.append_forced(3, 50);
+		changes.append_standard(3, 60);
+		assert_eq!(changes.start, Some((3, 50)));
+		assert_eq!(changes.changes, vec![(3, 60)]);
+
+		// an unknown history starts with the first set enacted.
+		let mut changes = AuthoritySetChanges::unknown();
+		changes.append_standard(5, 100);
+		changes.append_standard(6, 120);
+		assert_eq!(changes.start, Some((6, 100)));
+		assert_eq!(changes.changes, vec![(6, 120)]);
+	}
 }
@@ -27,11 +27,14 @@ use sp_runtime::traits::{Block as BlockT, NumberFor};
 use log::{info, warn};
 use fg_primitives::{AuthorityList, SetId, RoundNumber};
 
-use crate::authorities::{AuthoritySet, SharedAuthoritySet, PendingChange, DelayKind};
+use crate::authorities::{
+	AuthoritySet, AuthoritySetChanges, SharedAuthoritySet, PendingChange, DelayKind,
+};
 use crate::consensus_changes::{SharedConsensusChanges, ConsensusChanges};
 use crate::environment::{
 	CompletedRound, CompletedRounds, CurrentRounds, HasVoted, SharedVoterSetState, VoterSetState,
 };
+use crate::voting_rule::FinalizedSignals;
 use crate::NewAuthoritySet;
 
 const VERSION_KEY: &[u8] = b"grandpa_schema_version";
@@ -39,8 +42,9 @@ const SET_STATE_KEY: &[u8] = b"grandpa_completed_round";
 const CONCLUDED_ROUNDS: &[u8] = b"grandpa_concluded_rounds";
 const AUTHORITY_SET_KEY: &[u8] = b"grandpa_voters";
 const CONSENSUS_CHANGES_KEY: &[u8] = b"grandpa_consensus_changes";
+const SCHEDULED_PAUSES_KEY: &[u8] = b"grandpa_scheduled_pauses";
 
-const CURRENT_VERSION: u32 = 2;
+const CURRENT_VERSION: u32 = 4;
 
 /// The voter set state.
 #[derive(Debug, Clone, Encode, Decode)]
@@ -69,6 +73,63 @@ struct V0AuthoritySet<H, N> {
 	pending_changes: Vec<V0PendingChange<H, N>>,
 }
 
+#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+struct V2AuthoritySet<H, N> {
+	current_authorities: AuthorityList,
+	set_id: SetId,
+	pending_standard_changes: ForkTree<H, N, PendingChange<H, N>>,
+	pending_forced_changes: Vec<PendingChange<H, N>>,
+}
+
+impl<H, N> Into<AuthoritySet<H, N>> for V2AuthoritySet<H, N> {
+	fn into(self) -> AuthoritySet<H, N> {
+		AuthoritySet {
+			current_authorities: self.current_authorities,
+			set_id: self.set_id,
+			pending_standard_changes: self.pending_standard_changes,
+			pending_forced_changes: self.pending_forced_changes,
+			// changes enacted before the migration are unknown.
+			authority_set_changes: AuthoritySetChanges::unknown(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Encode, Decode, PartialEq)]
+struct V3AuthoritySet<H, N> {
+	current_authorities: AuthorityList,
+	set_id: SetId,
+	pending_standard_changes: ForkTree<H, N, PendingChange<H, N>>,
+	pending_forced_changes: Vec<PendingChange<H, N>>,
+	authority_set_changes: Vec<(SetId, N)>,
+}
+
+impl<H, N> V3AuthoritySet<H, N> {
+	fn into_authority_set(self, genesis_number: N) -> AuthoritySet<H, N> {
+		// the history is only complete if no forced change was enacted and it
+		// wasn't migrated from version 2.
+		let complete = self.authority_set_changes.len() as u64 == self.set_id &&
+			self.authority_set_changes.iter().enumerate()
+				.all(|(index, (set_id, _))| index as u64 == *set_id);
+
+		let authority_set_changes = if complete {
+			AuthoritySetChanges {
+				start: Some((0, genesis_number)),
+				changes: self.authority_set_changes,
+			}
+		} else {
+			AuthoritySetChanges::unknown()
+		};
+
+		AuthoritySet {
+			current_authorities: self.current_authorities,
+			set_id: self.set_id,
+			pending_standard_changes: self.pending_standard_changes,
+			pending_forced_changes: self.pending_forced_changes,
+			authority_set_changes,
+		}
+	}
+}
+
 impl<H, N> Into<AuthoritySet<H, N>> for V0AuthoritySet<H, N>
 where H: Clone + Debug + PartialEq,
 	  N: Clone + Debug + Ord,
@@ -101,7 +162,8 @@ where H: Clone + Debug + PartialEq,
 			current_authorities: self.current_authorities,
 			set_id: self.set_id,
 			pending_forced_changes: Vec::new(),
-			pending_standard_changes
+			pending_standard_changes,
+			authority_set_changes: AuthoritySetChanges::unknown(),
 		}
 	}
 }
@@ -195,10 +257,13 @@ fn migrate_from_version1<Block: BlockT, B, G>(
 		backend.insert_aux(&[(VERSION_KEY, s)], &[])
 	)?;
 
-	if let Some(set) = load_decode::<_, AuthoritySet<Block::Hash, NumberFor<Block>>>(
+	if let Some(set) = load_decode::<_, V2AuthoritySet<Block::Hash, NumberFor<Block>>>(
 		backend,
 		AUTHORITY_SET_KEY,
 	)? {
+		let set: AuthoritySet<Block::Hash, NumberFor<Block>> = set.into();
+		backend.insert_aux(&[(AUTHORITY_SET_KEY, set.encode().as_slice())], &[])?;
+
 		let set_id = set.current().0;
 
 		let completed_rounds = |number, state, base| CompletedRounds::new(
@@ -257,6 +322,47 @@ fn migrate_from_version1<Block: BlockT, B, G>(
 	Ok(None)
 }
 
+fn migrate_from_version2<Block: BlockT, B>(
+	backend: &B,
+) -> ClientResult<Option<AuthoritySet<Block::Hash, NumberFor<Block>>>> where B: AuxStore {
+	CURRENT_VERSION.using_encoded(|s|
+		backend.insert_aux(&[(VERSION_KEY, s)], &[])
+	)?;
+
+	if let Some(old_set) = load_decode::<_, V2AuthoritySet<Block::Hash, NumberFor<Block>>>(
+		backend,
+		AUTHORITY_SET_KEY,
+	)? {
+		let new_set: AuthoritySet<Block::Hash, NumberFor<Block>> = old_set.into();
+		backend.insert_aux(&[(AUTHORITY_SET_KEY, new_set.encode().as_slice())], &[])?;
+
+		return Ok(Some(new_set));
+	}
+
+	Ok(None)
+}
+
+fn migrate_from_version3<Block: BlockT, B>(
+	backend: &B,
+	genesis_number: NumberFor<Block>,
+) -> ClientResult<Option<AuthoritySet<Block::Hash, NumberFor<Block>>>> where B: AuxStore {
+	CURRENT_VERSION.using_encoded(|s|
+		backend.insert_aux(&[(VERSION_KEY, s)], &[])
+	)?;
+
+	if let Some(old_set) = load_decode::<_, V3AuthoritySet<Block::Hash, NumberFor<Block>>>(
+		backend,
+		AUTHORITY_SET_KEY,
+	)? {
+		let new_set = old_set.into_authority_set(genesis_number);
+		backend.insert_aux(&[(AUTHORITY_SET_KEY, new_set.encode().as_slice())], &[])?;
+
+		return Ok(Some(new_set));
+	}
+
+	Ok(None)
+}
+
 /// Load or initialize persistent data from backend.
 pub(crate) fn load_persistent<Block: BlockT, B, G>(
 	backend: &B,
@@ -294,11 +400,17 @@ pub(crate) fn load_persistent<Block: BlockT, B, G>(
 				});
 			}
 		},
-		Some(2) => {
-			if let Some(set) = load_decode::<_, AuthoritySet<Block::Hash, NumberFor<Block>>>(
-				backend,
-				AUTHORITY_SET_KEY,
-			)? {
+		Some(2) | Some(3) | Some(4) => {
+			let set = match version {
+				Some(2) => migrate_from_version2::<Block, _>(backend)?,
+				Some(3) => migrate_from_version3::<Block, _>(backend, genesis_number)?,
+				_ => load_decode::<_, AuthoritySet<Block::Hash, NumberFor<Block>>>(
+					backend,
+					AUTHORITY_SET_KEY,
+				)?,
+			};
+
+			if let Some(set) = set {
 				let set_state = match load_decode::<_, VoterSetState<Block>>(
 					backend,
 					SET_STATE_KEY,
@@ -406,6 +518,20 @@ pub(crate) fn write_voter_set_state<Block: BlockT, B: AuxStore>(
 	)
 }
 
+/// Load the finalized pause signals recorded by the `ScheduledPauses` voting rule.
+pub(crate) fn load_scheduled_pauses<B: AuxStore, N: Decode>(backend: &B)
+	-> ClientResult<Option<FinalizedSignals<N>>> {
+	load_decode(backend, SCHEDULED_PAUSES_KEY)
+}
+
+/// Write the finalized pause signals recorded by the `ScheduledPauses` voting rule.
+pub(crate) fn write_scheduled_pauses<B: AuxStore, N: Encode>(
+	backend: &B,
+	signals: &FinalizedSignals<N>,
+) -> ClientResult<()> {
+	backend.insert_aux(&[(SCHEDULED_PAUSES_KEY, signals.encode().as_slice())], &[])
+}
+
 /// Write concluded round.
 pub(crate) fn write_concluded_round<Block: BlockT, B: AuxStore>(
 	backend: &B,
@@ -430,6 +556,12 @@ pub(crate) fn update_consensus_changes<H, N, F, R>(
 	write_aux(&[(CONSENSUS_CHANGES_KEY, set.encode().as_slice())])
 }
 
+/// Load the authority set, if any was persisted yet.
+pub(crate) fn load_authority_set<B: AuxStore, H: Decode, N: Decode>(backend: &B)
+	-> ClientResult<Option<AuthoritySet<H, N>>> {
+	load_decode::<_, AuthoritySet<H, N>>(backend, AUTHORITY_SET_KEY)
+}
+
 #[cfg(test)]
 pub(crate) fn load_authorities<B: AuxStore, H: Decode, N: Decode>(backend: &B)
 	-> Option<AuthoritySet<H, N>> {
@@ -491,7 +623,7 @@ mod test {
 
 		assert_eq!(
 			load_decode::<_, u32>(&client, VERSION_KEY).unwrap(),
-			Some(2),
+			Some(4),
 		);
 
 		let PersistentData { authority_set, set_state, .. } = load_persistent::<test_client::runtime::Block, _, _>(
@@ -507,6 +639,7 @@ mod test {
 				current_authorities: authorities.clone(),
 				pending_standard_changes: ForkTree::new(),
 				pending_forced_changes: Vec::new(),
+				authority_set_changes: AuthoritySetChanges::unknown(),
 				set_id,
 			},
 		);
@@ -547,7 +680,7 @@ mod test {
 		};
 
 		{
-			let authority_set = AuthoritySet::<H256, u64> {
+			let authority_set = V2AuthoritySet::<H256, u64> {
 				current_authorities: authorities.clone(),
 				pending_standard_changes: ForkTree::new(),
 				pending_forced_changes: Vec::new(),
@@ -581,7 +714,7 @@ mod test {
 
 		assert_eq!(
 			load_decode::<_, u32>(&client, VERSION_KEY).unwrap(),
-			Some(2),
+			Some(4),
 		);
 
 		let PersistentData { authority_set, set_state, .. } = load_persistent::<test_client::runtime::Block, _, _>(
@@ -597,6 +730,7 @@ mod test {
 				current_authorities: authorities.clone(),
 				pending_standard_changes: ForkTree::new(),
 				pending_forced_changes: Vec::new(),
+				authority_set_changes: AuthoritySetChanges::unknown(),
 				set_id,
 			},
 		);
@@ -622,6 +756,117 @@ mod test {
 		);
 	}
 
+	#[test]
+	fn load_decode_from_v2_migrates_data_format() {
+		let client = test_client::new();
+
+		let authorities = vec![(AuthorityId::default(), 100)];
+		let set_id = 3;
+
+		{
+			let authority_set = V2AuthoritySet::<H256, u64> {
+				current_authorities: authorities.clone(),
+				pending_standard_changes: ForkTree::new(),
+				pending_forced_changes: Vec::new(),
+				set_id,
+			};
+
+			client.insert_aux(
+				&[
+					(AUTHORITY_SET_KEY, authority_set.encode().as_slice()),
+					(VERSION_KEY, 2u32.encode().as_slice()),
+				],
+				&[],
+			).unwrap();
+		}
+
+		// should perform the migration
+		load_persistent::<test_client::runtime::Block, _, _>(
+			&client,
+			H256::random(),
+			0,
+			|| unreachable!(),
+		).unwrap();
+
+		assert_eq!(
+			load_decode::<_, u32>(&client, VERSION_KEY).unwrap(),
+			Some(4),
+		);
+
+		let PersistentData { authority_set, .. } = load_persistent::<test_client::runtime::Block, _, _>(
+			&client,
+			H256::random(),
+			0,
+			|| unreachable!(),
+		).unwrap();
+
+		assert_eq!(
+			*authority_set.inner().read(),
+			AuthoritySet {
+				current_authorities: authorities.clone(),
+				pending_standard_changes: ForkTree::new(),
+				pending_forced_changes: Vec::new(),
+				authority_set_changes: AuthoritySetChanges::unknown(),
+				set_id,
+			},
+		);
+	}
+
+	#[test]
+	fn load_decode_from_v3_migrates_data_format() {
+		let authorities = vec![(AuthorityId::default(), 100)];
+
+		let migrate = |set_id, authority_set_changes| {
+			let client = test_client::new();
+			let authority_set = V3AuthoritySet::<H256, u64> {
+				current_authorities: authorities.clone(),
+				pending_standard_changes: ForkTree::new(),
+				pending_forced_changes: Vec::new(),
+				set_id,
+				authority_set_changes,
+			};
+
+			client.insert_aux(
+				&[
+					(AUTHORITY_SET_KEY, authority_set.encode().as_slice()),
+					(VERSION_KEY, 3u32.encode().as_slice()),
+				],
+				&[],
+			).unwrap();
+
+			// should perform the migration
+			let PersistentData { authority_set, .. } =
+				load_persistent::<test_client::runtime::Block, _, _>(
+					&client,
+					H256::random(),
+					0,
+					|| unreachable!(),
+				).unwrap();
+
+			assert_eq!(
+				load_decode::<_, u32>(&client, VERSION_KEY).unwrap(),
+				Some(4),
+			);
+
+			let set = authority_set.inner().read().clone();
+			assert_eq!(set.current_authorities, authorities);
+			assert_eq!(set.set_id, set_id);
+			set.authority_set_changes
+		};
+
+		// the complete history of a chain is kept from genesis.
+		assert_eq!(
+			migrate(2, vec![(0, 15), (1, 40)]),
+			AuthoritySetChanges { start: Some((0, 0)), changes: vec![(0, 15), (1, 40)] },
+		);
+
+		// the history is unknown after a forced change.
+		assert_eq!(
+			migrate(3, vec![(0, 15), (1, 40)]),
+			AuthoritySetChanges::unknown(),
+		);
+	}
+
 	#[test]
 	fn write_read_concluded_rounds() {
 		let client = test_client::new();
@@ -40,10 +40,10 @@ use grandpa::{
 	BlockNumberOps, Equivocation, Error as GrandpaError, round::State as RoundState,
 	voter, voter_set::VoterSet,
 };
-use primitives::{Blake2Hasher, H256, Pair};
+use primitives::{Blake2Hasher, ExecutionContext, H256, Pair};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{
-	Block as BlockT, Header as HeaderT, NumberFor, One, Zero,
+	Block as BlockT, Header as HeaderT, NumberFor, One, ProvideRuntimeApi, Zero,
 };
 use sc_telemetry::{telemetry, CONSENSUS_INFO};
 
@@ -57,9 +57,13 @@ use consensus_common::SelectChain;
 use crate::authorities::{AuthoritySet, SharedAuthoritySet};
 use crate::consensus_changes::SharedConsensusChanges;
 use crate::justification::GrandpaJustification;
+use crate::notification::GrandpaJustificationStream;
+use crate::round_state::SharedRoundState;
 use crate::until_imported::UntilVoteTargetImported;
-use crate::voting_rule::VotingRule;
-use fg_primitives::{AuthorityId, AuthoritySignature, SetId, RoundNumber};
+use crate::voting_rule::{SharedVotingHold, VotingRule};
+use fg_primitives::{
+	AuthorityId, AuthoritySignature, EquivocationProof, GrandpaApi, SetId, RoundNumber,
+};
 
 type HistoricalVotes<Block> = grandpa::HistoricalVotes<
 	<Block as BlockT>::Hash,
@@ -386,6 +390,9 @@ pub(crate) struct Environment<B, E, Block: BlockT, N: Network<Block>, RA, SC, VR
 	pub(crate) set_id: SetId,
 	pub(crate) voter_set_state: SharedVoterSetState<Block>,
 	pub(crate) voting_rule: VR,
+	pub(crate) round_state: SharedRoundState,
+	pub(crate) justification_stream: GrandpaJustificationStream,
+	pub(crate) voting_hold: SharedVotingHold<NumberFor<Block>>,
 }
 
 impl<B, E, Block: BlockT, N: Network<Block>, RA, SC, VR> Environment<B, E, Block, N, RA, SC, VR> {
@@ -458,7 +465,15 @@ where
 				let best_header = self.client.header(&BlockId::Hash(best_hash)).ok()?
 					.expect("Header known to exist after `best_containing` call; qed");
 
-				// check if our vote is currently being limited due to a pending change
+				// our votes might also be held by the operator or a scheduled
+				// pause, which is another mandatory voting rule. the hold
+				// never restricts votes below the base.
+				let limit = match (limit, self.voting_hold.limit(*base_header.number())) {
+					(Some(limit), Some(hold)) => Some(std::cmp::min(limit, hold)),
+					(limit, hold) => limit.or(hold),
+				};
+
+				// check if our vote is currently being limited due to a pending change or a hold
 				let limit = limit.filter(|limit| limit < best_header.number());
 				let target;
 
@@ -555,6 +570,65 @@ pub(crate) fn ancestry<B, Block: BlockT<Hash=H256>, E, RA>(
 	Ok(tree_route.retracted().iter().skip(1).map(|e| e.hash).collect())
 }
 
+impl<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR> Environment<B, E, Block, N, RA, SC, VR> where
+	B: Backend<Block, Blake2Hasher> + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + 'static + Send + Sync,
+	N: Network<Block>,
+	Client<B, E, Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: GrandpaApi<Block, Error = ClientError>,
+{
+	/// Report the given equivocation to the GRANDPA runtime module. This
+	/// generates a session membership proof for the offender and then submits
+	/// an unsigned extrinsic to report the equivocation. Both runtime calls
+	/// are made at the best block, at which the current set is expected to be
+	/// live on-chain.
+	fn report_equivocation(
+		&self,
+		equivocation: fg_primitives::Equivocation<Block::Hash, NumberFor<Block>>,
+	) -> Result<(), Error> {
+		let best_id = BlockId::Hash(self.client.info().chain.best_hash);
+
+		// equivocation reporting is only available from version 3 of the
+		// runtime API onwards.
+		let has_equivocation_reporting = self.client.runtime_version_at(&best_id)?
+			.has_api_with::<dyn GrandpaApi<Block, Error = ()>, _>(|version| version >= 3);
+
+		if !has_equivocation_reporting {
+			debug!(target: "afg", "Runtime doesn't support equivocation reporting.");
+			return Ok(());
+		}
+
+		// the extrinsic is submitted to the transaction pool, which is only
+		// reachable from an offchain context.
+		let runtime_api = self.client.runtime_api();
+		let key_owner_proof = match runtime_api.generate_key_ownership_proof_with_context(
+			&best_id,
+			ExecutionContext::OffchainCall(None),
+			self.set_id,
+			equivocation.offender().clone(),
+		)? {
+			Some(proof) => proof,
+			None => {
+				debug!(target: "afg", "Equivocation offender is not part of the authority set.");
+				return Ok(());
+			}
+		};
+
+		let equivocation_proof = EquivocationProof::new(self.set_id, equivocation);
+
+		runtime_api.submit_report_equivocation_unsigned_extrinsic_with_context(
+			&best_id,
+			ExecutionContext::OffchainCall(None),
+			equivocation_proof,
+			key_owner_proof,
+		)?;
+
+		info!(target: "afg", "Submitted equivocation report for set {}", self.set_id);
+
+		Ok(())
+	}
+}
+
 impl<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR>
 	voter::Environment<Block::Hash, NumberFor<Block>>
 for Environment<B, E, Block, N, RA, SC, VR>
@@ -568,6 +642,8 @@ where
 	SC: SelectChain<Block> + 'static,
 	VR: VotingRule<Block, Client<B, E, Block, RA>>,
 	NumberFor<Block>: BlockNumberOps,
+	Client<B, E, Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: GrandpaApi<Block, Error = ClientError>,
 {
 	type Timer = Box<dyn Future<Item = (), Error = Self::Error> + Send>;
 	type Id = AuthorityId;
@@ -614,15 +690,21 @@ where
 			has_voted,
 		);
 
+		self.round_state.start_round(self.set_id, round, self.voters.clone());
+
 		// schedule incoming messages from the network to be held until
 		// corresponding blocks are imported.
+		let round_state = self.round_state.clone();
+		let set_id = self.set_id;
 		let incoming = Box::new(UntilVoteTargetImported::new(
 			self.client.import_notification_stream(),
 			self.network.clone(),
 			self.client.clone(),
 			incoming,
 			"round",
-		).map_err(Into::into));
+		)
+			.inspect(move |signed| round_state.note_vote(set_id, round, &signed.id, &signed.message))
+			.map_err(Into::into));
 
 		// schedule network message cleanup when sink drops.
 		let outgoing = Box::new(outgoing.sink_map_err(Into::into));
@@ -683,6 +765,8 @@ where
 			None => return Ok(()),
 		};
 
+		self.round_state.note_vote(self.set_id, round, &local_id, &grandpa::Message::Prevote(prevote.clone()));
+
 		self.update_voter_set_state(|voter_set_state| {
 			let (completed_rounds, current_rounds) = voter_set_state.with_current_round(round)?;
 			let current_round = current_rounds.get(&round)
@@ -724,6 +808,8 @@ where
 			None => return Ok(()),
 		};
 
+		self.round_state.note_vote(self.set_id, round, &local_id, &grandpa::Message::Precommit(precommit.clone()));
+
 		self.update_voter_set_state(|voter_set_state| {
 			let (completed_rounds, current_rounds) = voter_set_state.with_current_round(round)?;
 			let current_round = current_rounds.get(&round)
@@ -892,6 +978,7 @@ where
 			&*self.client,
 			&self.authority_set,
 			&self.consensus_changes,
+			&self.justification_stream,
 			Some(self.config.justification_period.into()),
 			hash,
 			number,
@@ -915,7 +1002,9 @@ where
 		equivocation: ::grandpa::Equivocation<Self::Id, Prevote<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected prevote equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet; this could craft misbehavior reports of some kind.
+		if let Err(err) = self.report_equivocation(equivocation.into()) {
+			warn!(target: "afg", "Error reporting prevote equivocation: {:?}", err);
+		}
 	}
 
 	fn precommit_equivocation(
@@ -924,7 +1013,9 @@ where
 		equivocation: Equivocation<Self::Id, Precommit<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected precommit equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet
+		if let Err(err) = self.report_equivocation(equivocation.into()) {
+			warn!(target: "afg", "Error reporting precommit equivocation: {:?}", err);
+		}
 	}
 }
 
@@ -947,12 +1038,14 @@ impl<Block: BlockT> From<GrandpaJustification<Block>> for JustificationOrCommit<
 
 /// Finalize the given block and apply any authority set changes. If an
 /// authority set change is enacted then a justification is created (if not
-/// given) and stored with the block when finalizing it.
+/// given) and stored with the block when finalizing it. The justification is
+/// also built and sent to the justification stream if it has any subscriber.
 /// This method assumes that the block being finalized has already been imported.
 pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 	client: &Client<B, E, Block, RA>,
 	authority_set: &SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
 	consensus_changes: &SharedConsensusChanges<Block::Hash, NumberFor<Block>>,
+	justification_stream: &GrandpaJustificationStream,
 	justification_period: Option<NumberFor<Block>>,
 	hash: Block::Hash,
 	number: NumberFor<Block>,
@@ -993,6 +1086,9 @@ pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 		canonical_at_height(client, (hash, number), true, canon_number)
 	};
 
+	// justification to notify once the finalization is committed.
+	let mut notification = None;
+
 	let update_res: Result<_, Error> = client.lock_import_and_run(|import_op| {
 		let status = authority_set.apply_standard_changes(
 			hash,
@@ -1026,8 +1122,8 @@ pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 		// `N+1`. this assumption is required to make sure we store
 		// justifications for transition blocks which will be requested by
 		// syncing clients.
-		let justification = match justification_or_commit {
-			JustificationOrCommit::Justification(justification) => Some(justification.encode()),
+		let (justification, store_justification) = match justification_or_commit {
+			JustificationOrCommit::Justification(justification) => (Some(justification.encode()), true),
 			JustificationOrCommit::Commit((round_number, commit)) => {
 				let mut justification_required =
 					// justification is always required when block that enacts new authorities
@@ -1054,13 +1150,27 @@ pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 						commit,
 					)?;
 
-					Some(justification.encode())
+					(Some(justification.encode()), true)
+				} else if justification_stream.has_subscribers() {
+					// the justification is only built for the subscribers, finality
+					// must not fail because of it.
+					match GrandpaJustification::from_commit(client, round_number, commit) {
+						Ok(justification) => (Some(justification.encode()), false),
+						Err(e) => {
+							debug!(target: "afg", "Failed to build justification for subscribers \
+								of block {:?}: {:?}", (hash, number), e);
+							(None, false)
+						},
+					}
 				} else {
-					None
+					(None, false)
 				}
 			},
 		};
 
+		notification = justification.clone();
+		let justification = justification.filter(|_| store_justification);
+
 		debug!(target: "afg", "Finalizing blocks up to ({:?}, {})", number, hash);
 
 		// ideally some handle to a synchronization oracle would be used
@@ -1116,6 +1226,12 @@ pub(crate) fn finalize_block<B, Block: BlockT<Hash=H256>, E, RA>(
 		Ok(new_authorities.map(VoterCommand::ChangeAuthorities))
 	});
 
+	if update_res.is_ok() {
+		if let Some(justification) = notification {
+			justification_stream.notify(&justification);
+		}
+	}
+
 	match update_res {
 		Ok(Some(command)) => Err(CommandOrError::VoterCommand(command)),
 		Ok(None) => Ok(()),
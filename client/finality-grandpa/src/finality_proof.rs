@@ -55,6 +55,7 @@ use sc_telemetry::{telemetry, CONSENSUS_INFO};
 use fg_primitives::{AuthorityId, AuthorityList, VersionedAuthorityList, GRANDPA_AUTHORITIES_KEY};
 
 use crate::justification::GrandpaJustification;
+use crate::warp_proof::WarpSyncProof;
 
 /// Maximum number of fragments that we want to return in a single prove_finality call.
 const MAX_FRAGMENTS_IN_PROOF: usize = 8;
@@ -180,6 +181,23 @@ impl<B, Block> network::FinalityProofProvider<Block> for FinalityProofProvider<B
 			),
 		}
 	}
+
+	fn prove_warp_sync(&self, begin: Block::Hash) -> Result<Option<Vec<u8>>, ClientError> {
+		let authority_set = crate::aux_schema::load_authority_set::<_, Block::Hash, NumberFor<Block>>(
+			&*self.backend,
+		)?;
+		let authority_set = match authority_set {
+			Some(authority_set) => authority_set,
+			None => return Ok(None),
+		};
+
+		WarpSyncProof::<Block>::generate(
+			&*self.backend.blockchain(),
+			begin,
+			&authority_set.authority_set_changes,
+			authority_set.set_id,
+		).map(|proof| Some(proof.encode()))
+	}
 }
 
 /// The effects of block finality.
@@ -42,6 +42,7 @@ use crate::authorities::{AuthoritySet, SharedAuthoritySet, DelayKind, PendingCha
 use crate::consensus_changes::SharedConsensusChanges;
 use crate::environment::finalize_block;
 use crate::justification::GrandpaJustification;
+use crate::notification::GrandpaJustificationStream;
 
 /// A block-import handler for GRANDPA.
 ///
@@ -58,6 +59,7 @@ pub struct GrandpaBlockImport<B, E, Block: BlockT<Hash=H256>, RA, SC> {
 	authority_set: SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
 	send_voter_commands: mpsc::UnboundedSender<VoterCommand<Block::Hash, NumberFor<Block>>>,
 	consensus_changes: SharedConsensusChanges<Block::Hash, NumberFor<Block>>,
+	justification_stream: GrandpaJustificationStream,
 }
 
 impl<B, E, Block: BlockT<Hash=H256>, RA, SC: Clone> Clone for
@@ -70,6 +72,7 @@ impl<B, E, Block: BlockT<Hash=H256>, RA, SC: Clone> Clone for
 			authority_set: self.authority_set.clone(),
 			send_voter_commands: self.send_voter_commands.clone(),
 			consensus_changes: self.consensus_changes.clone(),
+			justification_stream: self.justification_stream.clone(),
 		}
 	}
 }
@@ -171,7 +174,7 @@ impl<'a, Block: 'a + BlockT> Drop for PendingSetChanges<'a, Block> {
 	}
 }
 
-fn find_scheduled_change<B: BlockT>(header: &B::Header)
+pub(crate) fn find_scheduled_change<B: BlockT>(header: &B::Header)
 	-> Option<ScheduledChange<NumberFor<B>>>
 {
 	let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
@@ -518,6 +521,7 @@ impl<B, E, Block: BlockT<Hash=H256>, RA, SC>
 		authority_set: SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
 		send_voter_commands: mpsc::UnboundedSender<VoterCommand<Block::Hash, NumberFor<Block>>>,
 		consensus_changes: SharedConsensusChanges<Block::Hash, NumberFor<Block>>,
+		justification_stream: GrandpaJustificationStream,
 	) -> GrandpaBlockImport<B, E, Block, RA, SC> {
 		GrandpaBlockImport {
 			inner,
@@ -525,6 +529,7 @@ impl<B, E, Block: BlockT<Hash=H256>, RA, SC>
 			authority_set,
 			send_voter_commands,
 			consensus_changes,
+			justification_stream,
 		}
 	}
 }
@@ -565,6 +570,7 @@ where
 			&*self.inner,
 			&self.authority_set,
 			&self.consensus_changes,
+			&self.justification_stream,
 			None,
 			hash,
 			number,
@@ -60,7 +60,7 @@ use sp_blockchain::{HeaderBackend, Error as ClientError};
 use client::Client;
 use codec::{Decode, Encode};
 use sp_runtime::generic::BlockId;
-use sp_runtime::traits::{NumberFor, Block as BlockT, DigestFor, Zero};
+use sp_runtime::traits::{NumberFor, Block as BlockT, DigestFor, ProvideRuntimeApi, Zero};
 use keystore::KeyStorePtr;
 use inherents::InherentDataProviders;
 use consensus_common::SelectChain;
@@ -86,25 +86,33 @@ mod finality_proof;
 mod import;
 mod justification;
 mod light_import;
+mod notification;
 mod observer;
+mod round_state;
+pub mod rpc;
 mod until_imported;
 mod voting_rule;
+mod warp_proof;
 
 pub use communication::Network;
 pub use finality_proof::FinalityProofProvider;
 pub use justification::GrandpaJustification;
 pub use light_import::light_block_import;
+pub use notification::GrandpaJustificationStream;
 pub use observer::run_grandpa_observer;
+pub use round_state::{Participation, ReportedRoundState, SharedRoundState};
 pub use voting_rule::{
-	BeforeBestBlock, ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRulesBuilder
+	BeforeBestBlock, FixedDelay, ScheduledPauses, SharedVotingHold, ThreeQuartersOfTheUnfinalizedChain,
+	VotingHold, VotingRule, VotingRulesBuilder,
 };
+pub use warp_proof::{AuthoritySetChangeProof, WarpSyncProof};
 
 use aux_schema::PersistentData;
 use environment::{Environment, VoterSetState};
 use import::GrandpaBlockImport;
 use until_imported::UntilGlobalMessageBlocksImported;
 use communication::NetworkBridge;
-use fg_primitives::{AuthorityList, AuthorityPair, AuthoritySignature, SetId};
+use fg_primitives::{AuthorityList, AuthorityPair, AuthoritySignature, GrandpaApi, SetId};
 
 // Re-export these two because it's just so damn convenient.
 pub use fg_primitives::{AuthorityId, ScheduledChange};
@@ -361,6 +369,26 @@ pub struct LinkHalf<B, E, Block: BlockT<Hash=H256>, RA, SC> {
 	select_chain: SC,
 	persistent_data: PersistentData<Block>,
 	voter_commands_rx: mpsc::UnboundedReceiver<VoterCommand<Block::Hash, NumberFor<Block>>>,
+	justification_stream: GrandpaJustificationStream,
+	round_state: SharedRoundState,
+	voting_hold: SharedVotingHold<NumberFor<Block>>,
+}
+
+impl<B, E, Block: BlockT<Hash=H256>, RA, SC> LinkHalf<B, E, Block, RA, SC> {
+	/// Get the stream of justifications of the blocks finalized by the voter or the block import.
+	pub fn justification_stream(&self) -> GrandpaJustificationStream {
+		self.justification_stream.clone()
+	}
+
+	/// Get the votes of the round the voter is running.
+	pub fn round_state(&self) -> SharedRoundState {
+		self.round_state.clone()
+	}
+
+	/// Get the handle used to pause, hold and resume the votes of the voter.
+	pub fn voting_hold(&self) -> SharedVotingHold<NumberFor<Block>> {
+		self.voting_hold.clone()
+	}
 }
 
 /// Provider for the Grandpa authority set configured on the genesis block.
@@ -429,6 +457,7 @@ where
 	)?;
 
 	let (voter_commands_tx, voter_commands_rx) = mpsc::unbounded();
+	let justification_stream = GrandpaJustificationStream::default();
 
 	Ok((
 		GrandpaBlockImport::new(
@@ -437,12 +466,16 @@ where
 			persistent_data.authority_set.clone(),
 			voter_commands_tx,
 			persistent_data.consensus_changes.clone(),
+			justification_stream.clone(),
 		),
 		LinkHalf {
 			client,
 			select_chain,
 			persistent_data,
 			voter_commands_rx,
+			justification_stream,
+			round_state: Default::default(),
+			voting_hold: Default::default(),
 		},
 	))
 }
@@ -556,6 +589,8 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X>(
 	DigestFor<Block>: Encode,
 	RA: Send + Sync + 'static,
 	X: futures03::Future<Output=()> + Clone + Send + Unpin + 'static,
+	Client<B, E, Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: GrandpaApi<Block, Error = ClientError>,
 {
 	let GrandpaParams {
 		config,
@@ -572,6 +607,9 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X>(
 		select_chain,
 		persistent_data,
 		voter_commands_rx,
+		justification_stream,
+		round_state,
+		voting_hold,
 	} = link;
 
 	let (network, network_startup) = NetworkBridge::new(
@@ -619,6 +657,9 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X>(
 		voting_rule,
 		persistent_data,
 		voter_commands_rx,
+		round_state,
+		justification_stream,
+		voting_hold,
 	);
 
 	let voter_work = voter_work
@@ -658,6 +699,8 @@ where
 	B: Backend<Block, Blake2Hasher> + 'static,
 	SC: SelectChain<Block> + 'static,
 	VR: VotingRule<Block, Client<B, E, Block, RA>> + Clone + 'static,
+	Client<B, E, Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: GrandpaApi<Block, Error = ClientError>,
 {
 	fn new(
 		client: Arc<Client<B, E, Block, RA>>,
@@ -667,6 +710,9 @@ where
 		voting_rule: VR,
 		persistent_data: PersistentData<Block>,
 		voter_commands_rx: mpsc::UnboundedReceiver<VoterCommand<Block::Hash, NumberFor<Block>>>,
+		round_state: SharedRoundState,
+		justification_stream: GrandpaJustificationStream,
+		voting_hold: SharedVotingHold<NumberFor<Block>>,
 	) -> Self {
 
 		let voters = persistent_data.authority_set.current_authorities();
@@ -681,6 +727,9 @@ where
 			authority_set: persistent_data.authority_set.clone(),
 			consensus_changes: persistent_data.consensus_changes.clone(),
 			voter_set_state: persistent_data.set_state.clone(),
+			round_state,
+			justification_stream,
+			voting_hold,
 		});
 
 		let mut work = VoterWork {
@@ -799,6 +848,9 @@ where
 					consensus_changes: self.env.consensus_changes.clone(),
 					network: self.env.network.clone(),
 					voting_rule: self.env.voting_rule.clone(),
+					round_state: self.env.round_state.clone(),
+					justification_stream: self.env.justification_stream.clone(),
+					voting_hold: self.env.voting_hold.clone(),
 				});
 
 				self.rebuild_voter();
@@ -834,6 +886,8 @@ where
 	B: Backend<Block, Blake2Hasher> + 'static,
 	SC: SelectChain<Block> + 'static,
 	VR: VotingRule<Block, Client<B, E, Block, RA>> + Clone + 'static,
+	Client<B, E, Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: GrandpaApi<Block, Error = ClientError>,
 {
 	type Item = ();
 	type Error = Error;
@@ -892,6 +946,8 @@ pub fn run_grandpa<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X>(
 	RA: Send + Sync + 'static,
 	VR: VotingRule<Block, Client<B, E, Block, RA>> + Clone + 'static,
 	X: futures03::Future<Output=()> + Clone + Send + Unpin + 'static,
+	Client<B, E, Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: GrandpaApi<Block, Error = ClientError>,
 {
 	run_grandpa_voter(grandpa_params)
 }
@@ -0,0 +1,50 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Notifications of the justifications of finalized blocks.
+
+use std::sync::Arc;
+use futures03::channel::mpsc;
+use parking_lot::Mutex;
+
+/// Stream of the encoded justifications of the blocks finalized by GRANDPA.
+///
+/// The voter, the observer and the block import all notify the blocks they finalize,
+/// whether the justification ends up being stored with the block or not.
+#[derive(Clone, Default)]
+pub struct GrandpaJustificationStream {
+	subscribers: Arc<Mutex<Vec<mpsc::UnboundedSender<Vec<u8>>>>>,
+}
+
+impl GrandpaJustificationStream {
+	/// Subscribe to the justifications of the blocks finalized from now on.
+	pub fn subscribe(&self) -> mpsc::UnboundedReceiver<Vec<u8>> {
+		let (sink, stream) = mpsc::unbounded();
+		self.subscribers.lock().push(sink);
+		stream
+	}
+
+	/// Whether anyone is listening. Justifications don't need to be built otherwise.
+	pub(crate) fn has_subscribers(&self) -> bool {
+		!self.subscribers.lock().is_empty()
+	}
+
+	/// Send an encoded justification to the subscribers, dropping the closed subscriptions.
+	pub(crate) fn notify(&self, justification: &[u8]) {
+		self.subscribers.lock().retain(|sink| sink.unbounded_send(justification.to_vec()).is_ok());
+	}
+}
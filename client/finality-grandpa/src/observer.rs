@@ -37,6 +37,7 @@ use crate::{
 use crate::authorities::SharedAuthoritySet;
 use crate::communication::NetworkBridge;
 use crate::consensus_changes::SharedConsensusChanges;
+use crate::notification::GrandpaJustificationStream;
 use fg_primitives::AuthorityId;
 
 struct ObserverChain<'a, Block: BlockT, B, E, RA>(&'a Client<B, E, Block, RA>);
@@ -61,6 +62,7 @@ fn grandpa_observer<B, E, Block: BlockT<Hash=H256>, RA, S, F>(
 	client: &Arc<Client<B, E, Block, RA>>,
 	authority_set: &SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
 	consensus_changes: &SharedConsensusChanges<Block::Hash, NumberFor<Block>>,
+	justification_stream: &GrandpaJustificationStream,
 	voters: &Arc<VoterSet<AuthorityId>>,
 	last_finalized_number: NumberFor<Block>,
 	commits: S,
@@ -78,6 +80,7 @@ fn grandpa_observer<B, E, Block: BlockT<Hash=H256>, RA, S, F>(
 {
 	let authority_set = authority_set.clone();
 	let consensus_changes = consensus_changes.clone();
+	let justification_stream = justification_stream.clone();
 	let client = client.clone();
 	let voters = voters.clone();
 
@@ -117,6 +120,7 @@ fn grandpa_observer<B, E, Block: BlockT<Hash=H256>, RA, S, F>(
 				&client,
 				&authority_set,
 				&consensus_changes,
+				&justification_stream,
 				None,
 				finalized_hash,
 				finalized_number,
@@ -170,6 +174,9 @@ pub fn run_grandpa_observer<B, E, Block: BlockT<Hash=H256>, N, RA, SC>(
 		select_chain: _,
 		persistent_data,
 		voter_commands_rx,
+		justification_stream,
+		round_state: _,
+		voting_hold: _,
 	} = link;
 
 	let (network, network_startup) = NetworkBridge::new(
@@ -184,7 +191,8 @@ pub fn run_grandpa_observer<B, E, Block: BlockT<Hash=H256>, N, RA, SC>(
 		network,
 		persistent_data,
 		config.keystore.clone(),
-		voter_commands_rx
+		voter_commands_rx,
+		justification_stream,
 	);
 
 	let observer_work = observer_work
@@ -209,6 +217,7 @@ struct ObserverWork<B: BlockT<Hash=H256>, N: Network<B>, E, Backend, RA> {
 	persistent_data: PersistentData<B>,
 	keystore: Option<keystore::KeyStorePtr>,
 	voter_commands_rx: mpsc::UnboundedReceiver<VoterCommand<B::Hash, NumberFor<B>>>,
+	justification_stream: GrandpaJustificationStream,
 }
 
 impl<B, N, E, Bk, RA> ObserverWork<B, N, E, Bk, RA>
@@ -227,6 +236,7 @@ where
 		persistent_data: PersistentData<B>,
 		keystore: Option<keystore::KeyStorePtr>,
 		voter_commands_rx: mpsc::UnboundedReceiver<VoterCommand<B::Hash, NumberFor<B>>>,
+		justification_stream: GrandpaJustificationStream,
 	) -> Self {
 
 		let mut work = ObserverWork {
@@ -238,6 +248,7 @@ where
 			persistent_data,
 			keystore,
 			voter_commands_rx,
+			justification_stream,
 		};
 		work.rebuild_observer();
 		work
@@ -281,6 +292,7 @@ where
 			&self.client,
 			&self.persistent_data.authority_set,
 			&self.persistent_data.consensus_changes,
+			&self.justification_stream,
 			&voters,
 			last_finalized_number,
 			global_in,
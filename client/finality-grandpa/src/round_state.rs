@@ -0,0 +1,202 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Tracking of the votes cast in the round the voter is currently running.
+//!
+//! The voter itself doesn't expose its internal round state, so the votes are
+//! recorded as they reach the voter from the network, and as the local voter
+//! casts its own. This is only meant for reporting (e.g. over RPC) and doesn't
+//! take part in any decision of the voter.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use grandpa::{Message, voter_set::VoterSet};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use fg_primitives::{AuthorityId, RoundNumber, SetId};
+
+/// Votes received in a single round.
+#[derive(Default)]
+struct RoundVotes {
+	prevotes: HashSet<AuthorityId>,
+	precommits: HashSet<AuthorityId>,
+}
+
+struct Inner {
+	set_id: SetId,
+	voters: Arc<VoterSet<AuthorityId>>,
+	rounds: BTreeMap<RoundNumber, RoundVotes>,
+}
+
+/// Participation of the voters in one vote type of a round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Participation {
+	/// Sum of the weights of the voters that have voted.
+	pub current_weight: u64,
+	/// Voters that have voted, with their weight.
+	pub voted: Vec<(AuthorityId, u64)>,
+	/// Voters that haven't voted yet, with their weight.
+	pub missing: Vec<(AuthorityId, u64)>,
+}
+
+/// State of the latest round the voter is running.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportedRoundState {
+	/// Id of the authority set voting.
+	pub set_id: SetId,
+	/// Number of the round.
+	pub round: RoundNumber,
+	/// Total weight of the authority set.
+	pub total_weight: u64,
+	/// Weight required for a supermajority.
+	pub threshold_weight: u64,
+	/// Prevotes cast in the round.
+	pub prevotes: Participation,
+	/// Precommits cast in the round.
+	pub precommits: Participation,
+}
+
+/// Votes of the rounds run by the voter, shared with the RPC.
+#[derive(Clone, Default)]
+pub struct SharedRoundState(Arc<RwLock<Option<Inner>>>);
+
+impl SharedRoundState {
+	/// Note that the voter started the given round.
+	///
+	/// Starting a round of a new authority set discards the votes of the previous one.
+	/// Only the votes of the two latest rounds are kept, since the voter only
+	/// completes the previous round in the background.
+	pub(crate) fn start_round(&self, set_id: SetId, round: RoundNumber, voters: Arc<VoterSet<AuthorityId>>) {
+		let mut inner = self.0.write();
+		if inner.as_ref().map_or(true, |inner| inner.set_id != set_id) {
+			*inner = Some(Inner { set_id, voters, rounds: BTreeMap::new() });
+		}
+
+		let inner = inner.as_mut().expect("set above if missing; qed");
+		inner.rounds.entry(round).or_default();
+		let oldest_kept = round.saturating_sub(1);
+		inner.rounds = inner.rounds.split_off(&oldest_kept);
+	}
+
+	/// Note a vote of the given round.
+	pub(crate) fn note_vote<H, N>(
+		&self,
+		set_id: SetId,
+		round: RoundNumber,
+		id: &AuthorityId,
+		message: &Message<H, N>,
+	) {
+		let mut inner = self.0.write();
+		let votes = match &mut *inner {
+			Some(ref mut inner) if inner.set_id == set_id => match inner.rounds.get_mut(&round) {
+				Some(votes) => votes,
+				None => return,
+			},
+			_ => return,
+		};
+
+		match message {
+			Message::Prevote(_) => { votes.prevotes.insert(id.clone()); },
+			Message::Precommit(_) => { votes.precommits.insert(id.clone()); },
+			Message::PrimaryPropose(_) => {},
+		}
+	}
+
+	/// Report the state of the latest round, if the voter has started any.
+	pub fn report(&self) -> Option<ReportedRoundState> {
+		let inner = self.0.read();
+		let inner = inner.as_ref()?;
+		let (round, votes) = inner.rounds.iter().next_back()?;
+
+		let participation = |voted: &HashSet<AuthorityId>| {
+			let (voted, missing): (Vec<_>, Vec<_>) = inner.voters.voters().iter()
+				.cloned()
+				.partition(|(id, _)| voted.contains(id));
+			Participation {
+				current_weight: voted.iter().map(|(_, weight)| weight).sum(),
+				voted,
+				missing,
+			}
+		};
+
+		Some(ReportedRoundState {
+			set_id: inner.set_id,
+			round: *round,
+			total_weight: inner.voters.total_weight(),
+			threshold_weight: inner.voters.threshold(),
+			prevotes: participation(&votes.prevotes),
+			precommits: participation(&votes.precommits),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use grandpa::{Prevote, Precommit};
+	use keyring::Ed25519Keyring;
+
+	fn voters() -> Arc<VoterSet<AuthorityId>> {
+		Arc::new(vec![
+			(Ed25519Keyring::Alice.public().into(), 1),
+			(Ed25519Keyring::Bob.public().into(), 1),
+			(Ed25519Keyring::Charlie.public().into(), 1),
+		].into_iter().collect())
+	}
+
+	#[test]
+	fn reports_latest_round_participation() {
+		let state = SharedRoundState::default();
+		assert!(state.report().is_none());
+
+		let alice: AuthorityId = Ed25519Keyring::Alice.public().into();
+		let bob: AuthorityId = Ed25519Keyring::Bob.public().into();
+
+		state.start_round(0, 1, voters());
+		state.note_vote(0, 1, &alice, &Message::Prevote(Prevote::new(1u32, 1u32)));
+		state.start_round(0, 2, voters());
+		state.note_vote(0, 2, &bob, &Message::Prevote(Prevote::new(1u32, 1u32)));
+		state.note_vote(0, 2, &bob, &Message::Precommit(Precommit::new(1u32, 1u32)));
+		// votes of other sets or of pruned rounds are ignored.
+		state.note_vote(1, 2, &alice, &Message::Prevote(Prevote::new(1u32, 1u32)));
+
+		let report = state.report().unwrap();
+		assert_eq!(report.round, 2);
+		assert_eq!(report.total_weight, 3);
+		assert_eq!(report.threshold_weight, 3);
+		assert_eq!(report.prevotes.current_weight, 1);
+		assert_eq!(report.prevotes.voted, vec![(bob.clone(), 1)]);
+		assert_eq!(report.prevotes.missing.len(), 2);
+		assert_eq!(report.precommits.voted, vec![(bob, 1)]);
+	}
+
+	#[test]
+	fn new_set_discards_previous_votes() {
+		let state = SharedRoundState::default();
+		let alice: AuthorityId = Ed25519Keyring::Alice.public().into();
+
+		state.start_round(0, 5, voters());
+		state.note_vote(0, 5, &alice, &Message::Prevote(Prevote::new(1u32, 1u32)));
+		state.start_round(1, 1, voters());
+
+		let report = state.report().unwrap();
+		assert_eq!((report.set_id, report.round), (1, 1));
+		assert_eq!(report.prevotes.current_weight, 0);
+	}
+}
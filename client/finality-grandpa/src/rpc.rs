@@ -0,0 +1,184 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! RPC interface for the GRANDPA finality gadget.
+
+use futures::{Future, Sink};
+use futures03::{StreamExt, TryStreamExt};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+use log::warn;
+use primitives::Bytes;
+use sc_rpc::{Metadata, Subscriptions};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::notification::GrandpaJustificationStream;
+use crate::round_state::{ReportedRoundState, SharedRoundState};
+use crate::voting_rule::{SharedVotingHold, VotingHold};
+
+pub use self::gen_client::Client as GrandpaClient;
+
+/// GRANDPA RPC API.
+#[rpc]
+pub trait GrandpaApi<Number> {
+	/// RPC metadata
+	type Metadata;
+
+	/// Get the set id, the round number and the participation of the voters in the
+	/// round the voter is currently running.
+	#[rpc(name = "grandpa_roundState")]
+	fn round_state(&self) -> Result<ReportedRoundState>;
+
+	/// Get the current hold on the votes of the voter.
+	#[rpc(name = "grandpa_votingHold")]
+	fn voting_hold(&self) -> Result<VotingHold<Number>>;
+
+	/// Subscribe to the encoded justifications of the blocks finalized from now on.
+	#[pubsub(
+		subscription = "grandpa_justifications",
+		subscribe,
+		name = "grandpa_subscribeJustifications"
+	)]
+	fn subscribe_justifications(&self, metadata: Self::Metadata, subscriber: Subscriber<Bytes>);
+
+	/// Unsubscribe from the justifications subscription.
+	#[pubsub(
+		subscription = "grandpa_justifications",
+		unsubscribe,
+		name = "grandpa_unsubscribeJustifications"
+	)]
+	fn unsubscribe_justifications(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool>;
+}
+
+/// Implementation of the GRANDPA RPC API.
+pub struct GrandpaRpcHandler<N> {
+	round_state: SharedRoundState,
+	justification_stream: GrandpaJustificationStream,
+	voting_hold: SharedVotingHold<N>,
+	subscriptions: Subscriptions,
+}
+
+impl<N> GrandpaRpcHandler<N> {
+	/// Create a new instance, typically with the round state, justification stream and
+	/// voting hold of the `LinkHalf` given to the voter.
+	pub fn new(
+		round_state: SharedRoundState,
+		justification_stream: GrandpaJustificationStream,
+		voting_hold: SharedVotingHold<N>,
+		subscriptions: Subscriptions,
+	) -> Self {
+		GrandpaRpcHandler { round_state, justification_stream, voting_hold, subscriptions }
+	}
+}
+
+impl<N> GrandpaApi<N> for GrandpaRpcHandler<N> where
+	N: Copy + Ord + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+	type Metadata = Metadata;
+
+	fn round_state(&self) -> Result<ReportedRoundState> {
+		self.round_state.report().ok_or_else(|| RpcError {
+			code: ErrorCode::InternalError,
+			message: "GRANDPA voter is not running any round".into(),
+			data: None,
+		})
+	}
+
+	fn voting_hold(&self) -> Result<VotingHold<N>> {
+		Ok(self.voting_hold.get())
+	}
+
+	fn subscribe_justifications(&self, _metadata: Self::Metadata, subscriber: Subscriber<Bytes>) {
+		let stream = self.justification_stream.subscribe()
+			.map(|justification| Ok::<_, ()>(Ok(Bytes(justification))))
+			.compat();
+
+		self.subscriptions.add(subscriber, |sink| {
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(stream)
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_justifications(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
+
+/// GRANDPA RPC API changing the votes of the voter.
+///
+/// Anyone reaching these methods can stop the votes of the voter, so they must
+/// only be exposed to the operator of the node.
+#[rpc(server)]
+pub trait GrandpaVotingApi<Number> {
+	/// Pause voting, i.e. only vote on blocks that are already finalized, until
+	/// voting is resumed. The voter keeps running rounds while paused.
+	#[rpc(name = "grandpa_pauseVoting")]
+	fn pause_voting(&self) -> Result<()>;
+
+	/// Don't vote beyond the given block number until voting is resumed.
+	#[rpc(name = "grandpa_holdVotingAt")]
+	fn hold_voting_at(&self, number: Number) -> Result<()>;
+
+	/// Resume voting, lifting any pause or hold, including holds at pauses
+	/// scheduled on-chain.
+	#[rpc(name = "grandpa_resumeVoting")]
+	fn resume_voting(&self) -> Result<()>;
+}
+
+/// Implementation of the GRANDPA RPC API changing the votes of the voter.
+pub struct GrandpaVotingRpcHandler<N> {
+	voting_hold: SharedVotingHold<N>,
+}
+
+impl<N> GrandpaVotingRpcHandler<N> {
+	/// Create a new instance, typically with the voting hold of the `LinkHalf`
+	/// given to the voter.
+	pub fn new(voting_hold: SharedVotingHold<N>) -> Self {
+		GrandpaVotingRpcHandler { voting_hold }
+	}
+}
+
+impl<N> GrandpaVotingApi<N> for GrandpaVotingRpcHandler<N> where
+	N: Copy + Ord + DeserializeOwned + Send + Sync + 'static,
+{
+	fn pause_voting(&self) -> Result<()> {
+		self.voting_hold.pause();
+		Ok(())
+	}
+
+	fn hold_voting_at(&self, number: N) -> Result<()> {
+		self.voting_hold.hold_at(number);
+		Ok(())
+	}
+
+	fn resume_voting(&self) -> Result<()> {
+		self.voting_hold.resume();
+		Ok(())
+	}
+}
@@ -37,7 +37,9 @@ use codec::Decode;
 use sp_runtime::traits::{ApiRef, ProvideRuntimeApi, Header as HeaderT};
 use sp_runtime::generic::{BlockId, DigestItem};
 use primitives::{NativeOrEncoded, ExecutionContext, crypto::Public};
-use fg_primitives::{GRANDPA_ENGINE_ID, AuthorityList, GrandpaApi};
+use fg_primitives::{
+	GRANDPA_ENGINE_ID, AuthorityList, GrandpaApi, EquivocationProof, OpaqueKeyOwnershipProof,
+};
 use state_machine::{backend::InMemory, prove_read, read_proof_check};
 use std::{pin::Pin, task};
 
@@ -274,6 +276,26 @@ impl GrandpaApi<Block> for RuntimeApi {
 	) -> Result<NativeOrEncoded<AuthorityList>> {
 		Ok(self.inner.genesis_authorities.clone()).map(NativeOrEncoded::Native)
 	}
+
+	fn GrandpaApi_generate_key_ownership_proof_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<(fg_primitives::SetId, AuthorityId)>,
+		_: Vec<u8>,
+	) -> Result<NativeOrEncoded<Option<OpaqueKeyOwnershipProof>>> {
+		Ok(NativeOrEncoded::Native(None))
+	}
+
+	fn GrandpaApi_submit_report_equivocation_unsigned_extrinsic_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<(EquivocationProof<Hash, BlockNumber>, OpaqueKeyOwnershipProof)>,
+		_: Vec<u8>,
+	) -> Result<NativeOrEncoded<Option<()>>> {
+		Ok(NativeOrEncoded::Native(None))
+	}
 }
 
 impl GenesisAuthoritySetProvider<Block> for TestApi {
@@ -478,6 +500,42 @@ fn finalize_3_voters_no_observers() {
 	);
 }
 
+#[test]
+fn finalized_blocks_justifications_are_sent_to_subscribers() {
+	let mut runtime = current_thread::Runtime::new().unwrap();
+	let peers = &[Ed25519Keyring::Alice];
+	let voters = make_ids(peers);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters.clone()), 1);
+	net.peer(0).push_blocks(20, false);
+
+	let mut justifications = net.peer(0).data.lock().as_ref()
+		.expect("link initialized at startup; qed")
+		.justification_stream()
+		.subscribe();
+
+	let net = Arc::new(Mutex::new(net));
+	run_to_completion(&mut runtime, 20, net.clone(), peers);
+
+	// the justification isn't required to be stored, but is built for the subscribers.
+	let client = net.lock().peer(0).client().clone();
+	assert!(client.justification(&BlockId::Number(20)).unwrap().is_none());
+
+	let mut last = None;
+	while let Ok(Some(justification)) = justifications.try_next() {
+		last = Some(justification);
+	}
+
+	let hash = client.as_full().unwrap().block_hash(20).unwrap().unwrap();
+	let voters = VoterSet::from_iter(voters);
+	GrandpaJustification::<Block>::decode_and_verify_finalizes(
+		&last.expect("a justification is sent for each finalized block; qed"),
+		(hash, 20),
+		0,
+		&voters,
+	).unwrap();
+}
+
 #[test]
 fn finalize_3_voters_1_full_observer() {
 	let mut runtime = current_thread::Runtime::new().unwrap();
@@ -887,6 +945,95 @@ fn finalizes_multiple_pending_changes_in_order() {
 	run_to_completion(&mut runtime, 30, net.clone(), all_peers);
 }
 
+#[test]
+fn warp_sync_proof_proves_authority_set_changes() {
+	let _ = env_logger::try_init();
+	let mut runtime = current_thread::Runtime::new().unwrap();
+
+	let peers_a = &[Ed25519Keyring::Alice, Ed25519Keyring::Bob, Ed25519Keyring::Charlie];
+	let peers_b = &[Ed25519Keyring::Dave, Ed25519Keyring::Eve, Ed25519Keyring::Ferdie];
+	let peers_c = &[Ed25519Keyring::Dave, Ed25519Keyring::Alice, Ed25519Keyring::Bob];
+
+	let all_peers = &[
+		Ed25519Keyring::Alice, Ed25519Keyring::Bob, Ed25519Keyring::Charlie,
+		Ed25519Keyring::Dave, Ed25519Keyring::Eve, Ed25519Keyring::Ferdie,
+	];
+	let genesis_voters = make_ids(peers_a);
+
+	let api = TestApi::new(genesis_voters);
+	let mut net = GrandpaTestNet::new(api, 6);
+
+	// at block 21 we do add a transition which is instant
+	net.peer(0).push_blocks(20, false);
+	net.peer(0).generate_blocks(1, BlockOrigin::File, |builder| {
+		let mut block = builder.bake().unwrap();
+		add_scheduled_change(&mut block, ScheduledChange {
+			next_authorities: make_ids(peers_b),
+			delay: 0,
+		});
+		block
+	});
+
+	// at block 26 we add another which is enacted at block 30
+	net.peer(0).push_blocks(4, false);
+	net.peer(0).generate_blocks(1, BlockOrigin::File, |builder| {
+		let mut block = builder.bake().unwrap();
+		add_scheduled_change(&mut block, ScheduledChange {
+			next_authorities: make_ids(peers_c),
+			delay: 4,
+		});
+		block
+	});
+	net.peer(0).push_blocks(4, false);
+
+	net.block_until_sync(&mut runtime);
+
+	let net = Arc::new(Mutex::new(net));
+	run_to_completion(&mut runtime, 30, net.clone(), all_peers);
+
+	let mut net = net.lock();
+	let backend = match net.peer(0).client() {
+		PeersClient::Full(_, backend) => backend.clone(),
+		PeersClient::Light(..) => unreachable!("only full clients are used in test"),
+	};
+	let provider = FinalityProofProvider::new(backend.clone(), Arc::new(net.test_config.clone()));
+	let genesis_hash = net.peer(0).client().info().chain.genesis_hash;
+
+	let proof = network::FinalityProofProvider::prove_warp_sync(&provider, genesis_hash).unwrap().unwrap();
+	let proof = WarpSyncProof::<Block>::decode(&mut &proof[..]).unwrap();
+
+	assert!(proof.is_finished());
+	assert_eq!(proof.last_header().map(|header| *header.number()), Some(30));
+
+	// starting from the genesis set, the proof leads to the current set
+	assert_eq!(proof.verify(0, make_ids(peers_a)).unwrap(), (2, make_ids(peers_c)));
+
+	// but it doesn't verify against any other set
+	assert!(proof.verify(0, make_ids(peers_b)).is_err());
+
+	// a node that only knows the history from the second set on, e.g. after a forced
+	// change, proves the changes from that set but not the ones before it.
+	let mut authority_set = crate::aux_schema::load_authority_set::<_, Hash, BlockNumber>(&*backend)
+		.unwrap()
+		.unwrap();
+	authority_set.authority_set_changes = authorities::AuthoritySetChanges {
+		start: Some((1, 21)),
+		changes: vec![(1, 30)],
+	};
+	crate::aux_schema::update_authority_set::<Block, _, _>(&authority_set, None, |insert| {
+		client_api::backend::AuxStore::insert_aux(&*backend, insert, &[])
+	}).unwrap();
+
+	assert!(network::FinalityProofProvider::prove_warp_sync(&provider, genesis_hash).is_err());
+
+	let hash_21 = net.peer(0).client().as_full().unwrap().hash(21).unwrap().unwrap();
+	let proof = network::FinalityProofProvider::prove_warp_sync(&provider, hash_21).unwrap().unwrap();
+	let proof = WarpSyncProof::<Block>::decode(&mut &proof[..]).unwrap();
+
+	assert!(proof.is_finished());
+	assert_eq!(proof.verify(1, make_ids(peers_b)).unwrap(), (2, make_ids(peers_c)));
+}
+
 #[test]
 fn force_change_to_new_set() {
 	let _ = env_logger::try_init();
@@ -1599,6 +1746,9 @@ fn grandpa_environment_respects_voting_rules() {
 			voters: Arc::new(authority_set.current_authorities()),
 			network,
 			voting_rule,
+			round_state: Default::default(),
+			justification_stream: Default::default(),
+			voting_hold: Default::default(),
 		}
 	};
 
@@ -1677,6 +1827,248 @@ fn grandpa_environment_respects_voting_rules() {
 	);
 }
 
+#[test]
+fn grandpa_environment_respects_fixed_delay_and_voting_hold() {
+	use grandpa::Chain;
+	use sc_network_test::TestClient;
+
+	let peers = &[Ed25519Keyring::Alice];
+	let voters = make_ids(peers);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters), 1);
+	let peer = net.peer(0);
+	let network_service = peer.network_service().clone();
+	let link = peer.data.lock().take().unwrap();
+
+	// create a voter environment with a given voting rule and voting hold
+	let environment = |
+		voting_rule: Box<dyn VotingRule<Block, TestClient>>,
+		voting_hold: SharedVotingHold<u64>,
+	| {
+		let PersistentData {
+			ref authority_set,
+			ref consensus_changes,
+			ref set_state,
+			..
+		} = link.persistent_data;
+
+		let config = Config {
+			gossip_duration: TEST_GOSSIP_DURATION,
+			justification_period: 32,
+			keystore: None,
+			name: None,
+			is_authority: true,
+			observer_enabled: true,
+		};
+
+		let (network, _) = NetworkBridge::new(
+			network_service.clone(),
+			config.clone(),
+			set_state.clone(),
+			Exit,
+		);
+
+		Environment {
+			authority_set: authority_set.clone(),
+			config: config.clone(),
+			consensus_changes: consensus_changes.clone(),
+			client: link.client.clone(),
+			select_chain: link.select_chain.clone(),
+			set_id: authority_set.set_id(),
+			voter_set_state: set_state.clone(),
+			voters: Arc::new(authority_set.current_authorities()),
+			network,
+			voting_rule,
+			round_state: Default::default(),
+			justification_stream: Default::default(),
+			voting_hold,
+		}
+	};
+
+	// add 20 blocks
+	peer.push_blocks(20, false);
+
+	let voting_hold = SharedVotingHold::default();
+	let fixed_delay_env = environment(Box::new(FixedDelay(5)), Default::default());
+	let held_env = environment(Box::new(()), voting_hold.clone());
+
+	let best_chain_containing = |env: &Environment<_, _, _, _, _, _, _>| {
+		env.best_chain_containing(peer.client().info().chain.finalized_hash).unwrap().1
+	};
+
+	// the fixed delay keeps the vote 5 blocks behind the best block.
+	assert_eq!(best_chain_containing(&fixed_delay_env), 15);
+
+	// without any hold we vote on the best block.
+	assert_eq!(best_chain_containing(&held_env), 20);
+
+	// holding at a block restricts the vote to it.
+	voting_hold.hold_at(12);
+	assert_eq!(best_chain_containing(&held_env), 12);
+
+	// pausing restricts the vote to the finalized block.
+	voting_hold.pause();
+	assert_eq!(best_chain_containing(&held_env), 0);
+
+	voting_hold.resume();
+	assert_eq!(best_chain_containing(&held_env), 20);
+
+	// we finalize block 18, the fixed delay can't restrict votes below it.
+	peer.client().finalize_block(BlockId::Number(18), None, false).unwrap();
+	assert_eq!(best_chain_containing(&fixed_delay_env), 18);
+
+	// and neither can a stale hold.
+	voting_hold.hold_at(12);
+	assert_eq!(best_chain_containing(&held_env), 18);
+}
+
+fn add_pause_signal(block: &mut Block, signal: fg_primitives::ConsensusLog<BlockNumber>) {
+	block.header.digest_mut().push(DigestItem::Consensus(GRANDPA_ENGINE_ID, signal.encode()));
+}
+
+#[test]
+fn scheduled_pauses_only_hold_votes_on_the_chain_signalling_them() {
+	use fg_primitives::ConsensusLog;
+
+	let peers = &[Ed25519Keyring::Alice];
+	let voters = make_ids(peers);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters), 1);
+	let peer = net.peer(0);
+	let client = peer.client().as_full().unwrap();
+
+	let voting_hold = SharedVotingHold::<BlockNumber>::default();
+	let rule = ScheduledPauses::new(voting_hold.clone());
+
+	// the vote of the rule on top of the given block, based on the finalized block.
+	let restrict_vote = |best: Hash| {
+		let header = |hash| client.header(&BlockId::Hash(hash)).unwrap().unwrap();
+		let base = header(client.info().chain.finalized_hash);
+		let best = header(best);
+		VotingRule::<Block, _>::restrict_vote(&rule, &*client, &base, &best, &best)
+			.map(|(_, number)| number)
+	};
+
+	// a chain pausing at block 14 from block 12, and a fork from block 11.
+	peer.push_blocks(11, false);
+	let pause_hash = peer.generate_blocks(1, BlockOrigin::File, |builder| {
+		let mut block = builder.bake().unwrap();
+		add_pause_signal(&mut block, ConsensusLog::Pause(2));
+		block
+	});
+	let best_hash = peer.push_blocks(4, false);
+	let fork_hash = peer.push_blocks_at(BlockId::Number(11), 4, false);
+
+	// the unfinalized pause only holds the votes on its chain, and isn't recorded.
+	assert_eq!(restrict_vote(best_hash), Some(14));
+	assert_eq!(restrict_vote(fork_hash), None);
+	assert_eq!(voting_hold.get(), VotingHold::default());
+
+	// once finalized, the pause is recorded and holds the votes.
+	peer.client().finalize_block(BlockId::Hash(pause_hash), None, false).unwrap();
+	assert_eq!(restrict_vote(best_hash), Some(14));
+	assert_eq!(voting_hold.get().at_block, Some(14));
+
+	// an enacted resume lifts the hold on its chain only, until it is finalized.
+	let resume_hash = peer.generate_blocks(1, BlockOrigin::File, |builder| {
+		let mut block = builder.bake().unwrap();
+		add_pause_signal(&mut block, ConsensusLog::Resume(1));
+		block
+	});
+	let best_hash = peer.push_blocks(1, false);
+	assert_eq!(restrict_vote(resume_hash), Some(14));
+	assert_eq!(restrict_vote(best_hash), None);
+	assert_eq!(voting_hold.get().at_block, Some(14));
+
+	peer.client().finalize_block(BlockId::Hash(resume_hash), None, false).unwrap();
+	assert_eq!(restrict_vote(best_hash), None);
+	assert_eq!(voting_hold.get(), VotingHold::default());
+}
+
+#[test]
+fn scheduled_pauses_keep_pauses_not_enacted_by_unfinalized_resumes() {
+	use fg_primitives::ConsensusLog;
+
+	let peers = &[Ed25519Keyring::Alice];
+	let voters = make_ids(peers);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters), 1);
+	let peer = net.peer(0);
+	let client = peer.client().as_full().unwrap();
+
+	let rule = ScheduledPauses::new(SharedVotingHold::<BlockNumber>::default());
+
+	// a chain pausing at block 11 from block 10, and resuming at block 17 from block 12.
+	peer.push_blocks(9, false);
+	peer.generate_blocks(1, BlockOrigin::File, |builder| {
+		let mut block = builder.bake().unwrap();
+		add_pause_signal(&mut block, ConsensusLog::Pause(1));
+		block
+	});
+	peer.push_blocks(1, false);
+	peer.generate_blocks(1, BlockOrigin::File, |builder| {
+		let mut block = builder.bake().unwrap();
+		add_pause_signal(&mut block, ConsensusLog::Resume(5));
+		block
+	});
+	let best_hash = peer.push_blocks(2, false);
+
+	// the resume isn't enacted at block 14, so the pause below it still holds the votes.
+	let header = |hash| client.header(&BlockId::Hash(hash)).unwrap().unwrap();
+	let base = header(client.info().chain.finalized_hash);
+	let best = header(best_hash);
+	assert_eq!(
+		VotingRule::<Block, _>::restrict_vote(&rule, &*client, &base, &best, &best)
+			.map(|(_, number)| number),
+		Some(11),
+	);
+}
+
+#[test]
+fn scheduled_pauses_are_restored_after_a_restart() {
+	use fg_primitives::ConsensusLog;
+
+	let peers = &[Ed25519Keyring::Alice];
+	let voters = make_ids(peers);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters), 1);
+	let peer = net.peer(0);
+	let client = peer.client().as_full().unwrap();
+
+	// the vote of the given rule on top of the best block.
+	let restrict_vote = |rule: &ScheduledPauses<BlockNumber>| {
+		let header = |hash| client.header(&BlockId::Hash(hash)).unwrap().unwrap();
+		let base = header(client.info().chain.finalized_hash);
+		let best = header(client.info().chain.best_hash);
+		VotingRule::<Block, _>::restrict_vote(rule, &*client, &base, &best, &best)
+			.map(|(_, number)| number)
+	};
+
+	// a pause at block 14 from block 12, checked by a running voter once finalized.
+	peer.push_blocks(11, false);
+	let pause_hash = peer.generate_blocks(1, BlockOrigin::File, |builder| {
+		let mut block = builder.bake().unwrap();
+		add_pause_signal(&mut block, ConsensusLog::Pause(2));
+		block
+	});
+	peer.push_blocks(4, false);
+	peer.client().finalize_block(BlockId::Hash(pause_hash), None, false).unwrap();
+	assert_eq!(restrict_vote(&ScheduledPauses::new(Default::default())), Some(14));
+
+	// the finalized pause still holds the votes of the restarted voter, even once
+	// the blocks finalized in the meantime are past the signal.
+	peer.client().finalize_block(BlockId::Number(13), None, false).unwrap();
+	let voting_hold = SharedVotingHold::default();
+	let rule = ScheduledPauses::new(voting_hold.clone());
+	assert_eq!(restrict_vote(&rule), Some(14));
+	assert_eq!(voting_hold.get().at_block, Some(14));
+
+	// a hold lifted by the operator isn't restored.
+	voting_hold.resume();
+	assert_eq!(restrict_vote(&rule), None);
+	assert_eq!(restrict_vote(&ScheduledPauses::new(Default::default())), None);
+}
+
 #[test]
 fn imports_justification_for_regular_blocks_on_import() {
 	// NOTE: this is a regression test since initially we would only import
@@ -1759,3 +2151,50 @@ fn imports_justification_for_regular_blocks_on_import() {
 		client.justification(&BlockId::Hash(block_hash)).unwrap().is_some(),
 	);
 }
+
+#[test]
+fn checks_equivocation_proofs() {
+	let voter = Ed25519Keyring::Alice;
+	let round = 1;
+	let set_id = 0;
+
+	type SignedPrevote = (grandpa::Prevote<Hash, BlockNumber>, AuthoritySignature);
+
+	let signed_prevote = |target_hash: Hash, target_number: BlockNumber| -> SignedPrevote {
+		let prevote = grandpa::Prevote { target_hash, target_number };
+		let msg = grandpa::Message::Prevote(prevote.clone());
+		let encoded = communication::localized_payload(round, set_id, &msg);
+		(prevote, voter.sign(&encoded[..]).into())
+	};
+
+	let equivocation_proof = |first: SignedPrevote, second: SignedPrevote| EquivocationProof::new(
+		set_id,
+		fg_primitives::Equivocation::Prevote(grandpa::Equivocation {
+			round_number: round,
+			identity: voter.public().into(),
+			first,
+			second,
+		}),
+	);
+
+	// votes for different blocks signed by the same voter are an equivocation
+	assert!(fg_primitives::check_equivocation_proof(equivocation_proof(
+		signed_prevote(Hash::repeat_byte(1), 1),
+		signed_prevote(Hash::repeat_byte(2), 1),
+	)));
+
+	// votes with the same target aren't an equivocation
+	assert!(!fg_primitives::check_equivocation_proof(equivocation_proof(
+		signed_prevote(Hash::repeat_byte(1), 1),
+		signed_prevote(Hash::repeat_byte(1), 1),
+	)));
+
+	// votes signed for a different round are rejected
+	let (prevote, _) = signed_prevote(Hash::repeat_byte(2), 1);
+	let msg = grandpa::Message::Prevote(prevote.clone());
+	let encoded = communication::localized_payload(round + 1, set_id, &msg);
+	assert!(!fg_primitives::check_equivocation_proof(equivocation_proof(
+		signed_prevote(Hash::repeat_byte(1), 1),
+		(prevote, voter.sign(&encoded[..]).into()),
+	)));
+}
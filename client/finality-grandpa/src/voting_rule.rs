@@ -22,10 +22,17 @@
 
 use std::sync::Arc;
 
-use client_api::blockchain::HeaderBackend;
-use sp_runtime::generic::BlockId;
+use client_api::{backend::AuxStore, blockchain::HeaderBackend};
+use codec::{Decode, Encode};
+use fg_primitives::{ConsensusLog, GRANDPA_ENGINE_ID};
+use log::warn;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use sp_runtime::generic::{BlockId, OpaqueDigestItemId};
 use sp_runtime::traits::{Block as BlockT, Header, NumberFor, One, Zero};
 
+use crate::aux_schema;
+
 /// A trait for custom voting rules in GRANDPA.
 pub trait VotingRule<Block, B>: Send + Sync where
 	Block: BlockT,
@@ -153,6 +160,315 @@ impl<Block, B> VotingRule<Block, B> for ThreeQuartersOfTheUnfinalizedChain where
 	}
 }
 
+/// A custom voting rule that keeps our vote at least a fixed number of blocks
+/// behind the best block, e.g. to give operators time to react to bad blocks
+/// before they are finalized.
+#[derive(Clone)]
+pub struct FixedDelay(pub u32);
+
+impl<Block, B> VotingRule<Block, B> for FixedDelay where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		base: &Block::Header,
+		best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		let delay = NumberFor::<Block>::from(self.0);
+
+		// we never vote below the base, if the delay would take us there then
+		// we vote on the base itself.
+		let target_number = if *best_target.number() > *base.number() + delay {
+			*best_target.number() - delay
+		} else {
+			*base.number()
+		};
+
+		// our current target is already lower than this rule would restrict
+		if target_number >= *current_target.number() {
+			return None;
+		}
+
+		walk_backwards(backend, current_target, target_number)
+	}
+}
+
+/// A hold on the votes of the local voter, set either by the operator (e.g.
+/// through RPC) or by a pause scheduled on-chain. The voter keeps running
+/// rounds while its votes are held, but never votes beyond the held block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VotingHold<N> {
+	/// Whether voting is paused, i.e. we only vote on the round base and no
+	/// new blocks are finalized by our votes.
+	pub paused: bool,
+	/// The block number beyond which we don't vote, if any.
+	pub at_block: Option<N>,
+}
+
+impl<N> Default for VotingHold<N> {
+	fn default() -> Self {
+		VotingHold {
+			paused: false,
+			at_block: None,
+		}
+	}
+}
+
+impl<N: Copy + Ord> VotingHold<N> {
+	fn limit(&self, base: N) -> Option<N> {
+		if self.paused {
+			return Some(base);
+		}
+
+		self.at_block.map(|number| std::cmp::max(number, base))
+	}
+}
+
+/// The voting hold of the local voter, shared between the voter, the voting
+/// rules and the RPC.
+#[derive(Clone)]
+pub struct SharedVotingHold<N>(Arc<RwLock<VotingHold<N>>>);
+
+impl<N> Default for SharedVotingHold<N> {
+	fn default() -> Self {
+		SharedVotingHold(Arc::new(RwLock::new(VotingHold::default())))
+	}
+}
+
+impl<N: Copy + Ord> SharedVotingHold<N> {
+	/// Get the current voting hold.
+	pub fn get(&self) -> VotingHold<N> {
+		self.0.read().clone()
+	}
+
+	/// Pause voting until `resume` is called.
+	pub fn pause(&self) {
+		self.0.write().paused = true;
+	}
+
+	/// Hold votes at the given block number until `resume` is called.
+	pub fn hold_at(&self, number: N) {
+		self.0.write().at_block = Some(number);
+	}
+
+	/// Resume voting, clearing both the pause and any held block.
+	pub fn resume(&self) {
+		*self.0.write() = VotingHold::default();
+	}
+
+	/// Clear the held block if it is the given one, leaving any pause or
+	/// a hold set in the meantime untouched.
+	fn release(&self, number: N) {
+		let mut hold = self.0.write();
+		if hold.at_block == Some(number) {
+			hold.at_block = None;
+		}
+	}
+
+	/// Returns the highest block number we can vote on given the round base,
+	/// or `None` if our votes aren't held. We always allow voting on the base.
+	pub(crate) fn limit(&self, base: N) -> Option<N> {
+		self.0.read().limit(base)
+	}
+}
+
+/// A custom voting rule that holds votes at the blocks where the runtime
+/// scheduled a pause of the authority set (e.g. by governance ahead of a
+/// risky chain upgrade), until the matching resume signal is enacted.
+///
+/// Signals of unfinalized blocks only hold the votes on the chain that carries
+/// them, as that chain may still be abandoned. Once a pause is finalized the
+/// held block is recorded in the given `SharedVotingHold`, so that the hold is
+/// kept after the signal itself leaves the unfinalized chain, and so that it
+/// can be lifted by the operator. The finalized signals are also persisted in
+/// the aux store of the backend, so that the hold survives a restart.
+pub struct ScheduledPauses<N> {
+	voting_hold: SharedVotingHold<N>,
+	finalized: Mutex<Option<FinalizedSignals<N>>>,
+}
+
+/// The signals of the finalized chain seen by `ScheduledPauses`.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub(crate) struct FinalizedSignals<N> {
+	/// The last finalized block checked for signals.
+	checked: Option<N>,
+	/// The block we hold at because of a finalized pause, if any.
+	pause_at: Option<N>,
+}
+
+impl<N> ScheduledPauses<N> {
+	/// Create a new rule recording finalized pauses in the given voting hold,
+	/// usually the one of the `LinkHalf` given to the voter.
+	pub fn new(voting_hold: SharedVotingHold<N>) -> Self {
+		ScheduledPauses {
+			voting_hold,
+			finalized: Mutex::new(None),
+		}
+	}
+}
+
+/// Returns the pause (`true`) or resume (`false`) signalled by the given
+/// header, along with the block number at which it is enacted.
+fn pause_signal<Block: BlockT>(header: &Block::Header) -> Option<(bool, NumberFor<Block>)> {
+	let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
+	header.digest().convert_first(|l| {
+		l.try_to::<ConsensusLog<NumberFor<Block>>>(id).and_then(|log| match log {
+			ConsensusLog::Pause(delay) => Some((true, *header.number() + delay)),
+			ConsensusLog::Resume(delay) => Some((false, *header.number() + delay)),
+			_ => None,
+		})
+	})
+}
+
+impl<Block, B> VotingRule<Block, B> for ScheduledPauses<NumberFor<Block>> where
+	Block: BlockT,
+	B: HeaderBackend<Block> + AuxStore,
+{
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		base: &Block::Header,
+		best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		let finalized_number = backend.info().finalized_number;
+
+		// the signals of the blocks finalized since the last call are final,
+		// they are recorded in the shared hold.
+		let finalized_pause = {
+			let mut finalized = self.finalized.lock();
+			if finalized.is_none() {
+				// signals recorded before a restart are restored, and the blocks
+				// finalized since then are checked below.
+				let signals = aux_schema::load_scheduled_pauses(backend)
+					.map_err(|e| warn!(target: "afg", "Failed to load scheduled pauses: {:?}", e))
+					.ok()?
+					.unwrap_or(FinalizedSignals { checked: None, pause_at: None });
+				if let Some(pause_at) = signals.pause_at {
+					self.voting_hold.hold_at(pause_at);
+				}
+				*finalized = Some(signals);
+			}
+			let finalized = finalized.as_mut().expect("restored above; qed");
+			let previous = finalized.clone();
+
+			// the operator lifted the hold of the finalized pause.
+			let pause_at = finalized.pause_at;
+			if pause_at.is_some() && self.voting_hold.get().at_block != pause_at {
+				finalized.pause_at = None;
+			}
+
+			let mut number = finalized.checked.map_or(finalized_number, |n| n + One::one());
+			while number <= finalized_number {
+				let header = backend.header(BlockId::Number(number)).ok()?
+					.expect("Finalized headers are known to exist; qed");
+
+				match pause_signal::<Block>(&header) {
+					Some((true, pause_at)) => {
+						self.voting_hold.hold_at(pause_at);
+						finalized.pause_at = Some(pause_at);
+					},
+					Some((false, _)) => {
+						// the runtime only allows resuming a paused authority set.
+						if let Some(pause_at) = finalized.pause_at.take() {
+							self.voting_hold.release(pause_at);
+						}
+					},
+					None => {},
+				}
+
+				number = number + One::one();
+			}
+			finalized.checked = Some(finalized_number);
+
+			if *finalized != previous {
+				if let Err(e) = aux_schema::write_scheduled_pauses(backend, &*finalized) {
+					warn!(target: "afg", "Failed to write scheduled pauses: {:?}", e);
+				}
+			}
+
+			finalized.pause_at
+		};
+
+		// the signals of the unfinalized chain we are voting on only apply to
+		// this vote, they are applied from the oldest one.
+		let mut signals = Vec::new();
+		let mut header = best_target.clone();
+		while *header.number() > finalized_number {
+			if let Some(signal) = pause_signal::<Block>(&header) {
+				signals.push(signal);
+			}
+
+			header = backend.header(BlockId::Hash(*header.parent_hash())).ok()?
+				.expect("Header known to exist due to the existence of one of its descendents; qed");
+		}
+
+		let mut hold = self.voting_hold.get();
+		let mut pause_at = finalized_pause;
+		for signal in signals.into_iter().rev() {
+			match signal {
+				(true, at) => {
+					hold.at_block = Some(at);
+					pause_at = Some(at);
+				},
+				// a resume that isn't enacted yet leaves the pause in place.
+				(false, resume_at) if resume_at <= *best_target.number() => {
+					// unless the operator set another hold in the meantime.
+					if pause_at.is_some() && hold.at_block == pause_at {
+						hold.at_block = None;
+					}
+					pause_at = None;
+				},
+				_ => {},
+			}
+		}
+
+		let target_number = hold.limit(*base.number())?;
+
+		// our current target is already lower than this rule would restrict
+		if target_number >= *current_target.number() {
+			return None;
+		}
+
+		walk_backwards(backend, current_target, target_number)
+	}
+}
+
+/// Walk backwards from the given header until the block with the given number
+/// is found, returning its hash and number.
+fn walk_backwards<Block, B>(
+	backend: &B,
+	from: &Block::Header,
+	target_number: NumberFor<Block>,
+) -> Option<(Block::Hash, NumberFor<Block>)> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	let mut target_header = from.clone();
+	let mut target_hash = from.hash();
+
+	loop {
+		if *target_header.number() < target_number {
+			unreachable!(
+				"we are traversing backwards from a known block; \
+				 blocks are stored contiguously; \
+				 qed"
+			);
+		}
+		if *target_header.number() == target_number {
+			return Some((target_hash, target_number));
+		}
+
+		target_hash = *target_header.parent_hash();
+		target_header = backend.header(BlockId::Hash(target_hash)).ok()?
+			.expect("Header known to exist due to the existence of one of its descendents; qed");
+	}
+}
+
 struct VotingRules<Block, B> {
 	rules: Arc<Vec<Box<dyn VotingRule<Block, B>>>>,
 }
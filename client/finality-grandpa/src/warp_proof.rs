@@ -0,0 +1,224 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! GRANDPA warp sync proof generation and check.
+//!
+//! A warp sync proof is the ordered list of the authority set changes enacted
+//! after a given finalized block. Each fragment holds the headers from the block
+//! that signaled the change up to the block that enacted it, together with the
+//! justification of the latter by the outgoing authority set. Starting from a
+//! known authority set (e.g. the genesis one), verifying the fragments in order
+//! yields the authority set that is currently finalizing the chain, without the
+//! need to download any of the headers in between.
+//!
+//! Forced changes don't come with a justification and can't be proven this way.
+//! The history of changes restarts at each forced change, so proofs can only start
+//! at blocks finalized by the set enacted by the last forced change or after it.
+
+use codec::{Encode, Decode};
+use grandpa::BlockNumberOps;
+use log::trace;
+use sp_blockchain::{Backend as BlockchainBackend, Error as ClientError, Result as ClientResult};
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::{NumberFor, Block as BlockT, Header as HeaderT};
+use primitives::H256;
+use fg_primitives::{AuthorityList, SetId};
+
+use crate::authorities::AuthoritySetChanges;
+use crate::import::find_scheduled_change;
+use crate::justification::GrandpaJustification;
+
+/// Maximum encoded size of a warp sync proof. Longer histories are served in several proofs.
+const MAX_WARP_SYNC_PROOF_SIZE: usize = 8 * 1024 * 1024;
+
+/// Proof of a single authority set change.
+#[derive(Encode, Decode)]
+pub struct AuthoritySetChangeProof<Block: BlockT> {
+	/// Headers from the block that signaled the change to the block that enacted it. Ordered.
+	pub headers: Vec<Block::Header>,
+	/// Justification of the last header by the authority set that is being replaced.
+	pub justification: GrandpaJustification<Block>,
+}
+
+/// Warp sync proof, proving the authority set changes enacted after some finalized block.
+#[derive(Encode, Decode)]
+pub struct WarpSyncProof<Block: BlockT> {
+	proofs: Vec<AuthoritySetChangeProof<Block>>,
+	is_finished: bool,
+}
+
+impl<Block: BlockT<Hash=H256>> WarpSyncProof<Block> {
+	/// Generate a proof of the authority set changes enacted after the finalized block `begin`.
+	///
+	/// `authority_set_changes` is the history of enacted changes, as kept by the
+	/// authority set, and `current_set_id` is the id of the current authority set.
+	pub(crate) fn generate<B: BlockchainBackend<Block>>(
+		blockchain: &B,
+		begin: Block::Hash,
+		authority_set_changes: &AuthoritySetChanges<NumberFor<Block>>,
+		current_set_id: SetId,
+	) -> ClientResult<Self> {
+		let (start_set_id, start_number) = match authority_set_changes.start {
+			Some(start) => start,
+			None => return Err(ClientError::Backend(
+				"Authority set change history is not known yet, unable to prove it".into()
+			)),
+		};
+
+		let consistent_history = start_set_id +
+			authority_set_changes.changes.len() as u64 == current_set_id &&
+			authority_set_changes.changes.iter().enumerate()
+				.all(|(index, (set_id, _))| start_set_id + index as u64 == *set_id);
+		if !consistent_history {
+			return Err(ClientError::Backend(
+				"Authority set change history is inconsistent, unable to prove it".into()
+			));
+		}
+
+		let begin_number = blockchain.expect_block_number_from_id(&BlockId::Hash(begin))?;
+		if begin_number > blockchain.info().finalized_number ||
+			blockchain.hash(begin_number)? != Some(begin)
+		{
+			return Err(ClientError::Backend("Warp sync proof must start at a finalized block".into()));
+		}
+
+		if begin_number < start_number {
+			return Err(ClientError::Backend(format!(
+				"Authority set changes before #{} are not known, unable to prove them",
+				start_number,
+			)));
+		}
+
+		let mut proofs = Vec::new();
+		let mut proofs_size = 0;
+		let mut is_finished = true;
+
+		let changes = authority_set_changes.changes.iter()
+			.filter(|(_, number)| *number > begin_number);
+		for (_, enacted_at) in changes {
+			let proof = prove_set_change(blockchain, *enacted_at)?;
+			proofs_size += proof.using_encoded(|encoded| encoded.len());
+			if proofs_size > MAX_WARP_SYNC_PROOF_SIZE && !proofs.is_empty() {
+				is_finished = false;
+				break;
+			}
+
+			proofs.push(proof);
+		}
+
+		trace!(
+			target: "finality",
+			"Built warp sync proof from {} with {} authority set changes (finished: {})",
+			begin,
+			proofs.len(),
+			is_finished,
+		);
+
+		Ok(WarpSyncProof { proofs, is_finished })
+	}
+
+	/// Whether the proof reaches the current authority set of the node that produced it.
+	///
+	/// Unfinished proofs should be continued with a request starting at `last_header`.
+	pub fn is_finished(&self) -> bool {
+		self.is_finished
+	}
+
+	/// Header of the block enacting the last proven change, if any.
+	pub fn last_header(&self) -> Option<&Block::Header> {
+		self.proofs.last().and_then(|proof| proof.headers.last())
+	}
+
+	/// Verify the proof against the authority set that was active at the block the proof
+	/// starts at, returning the authority set that results from all proven changes.
+	pub fn verify(
+		&self,
+		set_id: SetId,
+		authorities: AuthorityList,
+	) -> ClientResult<(SetId, AuthorityList)> where
+		NumberFor<Block>: BlockNumberOps,
+	{
+		let bad_proof = |msg: &str| ClientError::BadJustification(format!("invalid warp sync proof: {}", msg));
+
+		let mut current_set_id = set_id;
+		let mut current_authorities = authorities;
+		let mut last_enacted_at = None;
+
+		for proof in &self.proofs {
+			let (signal_header, enact_header) = match (proof.headers.first(), proof.headers.last()) {
+				(Some(first), Some(last)) => (first, last),
+				_ => return Err(bad_proof("no headers")),
+			};
+
+			if last_enacted_at.map_or(false, |number| *signal_header.number() < number) {
+				return Err(bad_proof("changes out of order"));
+			}
+
+			let linked = proof.headers.windows(2).all(|pair| *pair[1].parent_hash() == pair[0].hash());
+			if !linked {
+				return Err(bad_proof("headers are not a chain"));
+			}
+
+			let change = find_scheduled_change::<Block>(signal_header)
+				.filter(|change| *signal_header.number() + change.delay == *enact_header.number())
+				.ok_or_else(|| bad_proof("no authority set change enacted at the last header"))?;
+
+			let commit = &proof.justification.commit;
+			if (commit.target_hash, commit.target_number) != (enact_header.hash(), *enact_header.number()) {
+				return Err(bad_proof("justification doesn't target the last header"));
+			}
+
+			proof.justification.verify(current_set_id, &current_authorities.iter().cloned().collect())?;
+
+			current_set_id += 1;
+			current_authorities = change.next_authorities;
+			last_enacted_at = Some(*enact_header.number());
+		}
+
+		Ok((current_set_id, current_authorities))
+	}
+}
+
+/// Prove the authority set change enacted at the given finalized block.
+fn prove_set_change<Block: BlockT<Hash=H256>, B: BlockchainBackend<Block>>(
+	blockchain: &B,
+	enacted_at: NumberFor<Block>,
+) -> ClientResult<AuthoritySetChangeProof<Block>> {
+	let enact_header = blockchain.expect_header(BlockId::Number(enacted_at))?;
+	let justification = blockchain.justification(BlockId::Number(enacted_at))?
+		.ok_or_else(|| ClientError::Backend(
+			format!("Missing justification of authority set change block #{}", enacted_at)
+		))?;
+	let justification = GrandpaJustification::<Block>::decode(&mut &justification[..])
+		.map_err(|_| ClientError::JustificationDecode)?;
+
+	// walk back to the block that signaled the change.
+	let mut headers = vec![enact_header];
+	loop {
+		let header = headers.last().expect("headers is never empty; qed");
+		let signaled_here = find_scheduled_change::<Block>(header)
+			.map_or(false, |change| *header.number() + change.delay == enacted_at);
+		if signaled_here {
+			break;
+		}
+
+		let parent = blockchain.expect_header(BlockId::Hash(*header.parent_hash()))?;
+		headers.push(parent);
+	}
+	headers.reverse();
+
+	Ok(AuthoritySetChangeProof { headers, justification })
+}
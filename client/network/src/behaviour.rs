@@ -19,8 +19,9 @@ use crate::{
 	protocol::event::DhtEvent
 };
 use crate::{ExHashT, specialization::NetworkSpecialization};
-use crate::protocol::{CustomMessageOutcome, Protocol};
-use futures::prelude::*;
+use crate::protocol::{CustomMessageOutcome, Protocol, RequestProtocol};
+use crate::request_responses::{self, RequestFailure, RequestResponsesBehaviour, RequestResponsesEvent};
+use futures::{prelude::*, sync::oneshot};
 use libp2p::NetworkBehaviour;
 use libp2p::core::{Multiaddr, PeerId, PublicKey};
 use libp2p::kad::record;
@@ -42,7 +43,12 @@ pub struct Behaviour<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> {
 	debug_info: debug_info::DebugInfoBehaviour<Substream<StreamMuxerBox>>,
 	/// Discovers nodes of the network.
 	discovery: DiscoveryBehaviour<Substream<StreamMuxerBox>>,
+	/// Sends requests and answers them, over the request-response protocols.
+	request_responses: RequestResponsesBehaviour<Substream<StreamMuxerBox>>,
 
+	/// Requests sent by the substrate protocol, waiting for a response.
+	#[behaviour(ignore)]
+	pending_requests: Vec<(PeerId, RequestProtocol, oneshot::Receiver<Result<Vec<u8>, RequestFailure>>)>,
 	/// Queue of events to produce for the outside.
 	#[behaviour(ignore)]
 	events: Vec<BehaviourOut<B>>,
@@ -63,6 +69,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Behaviour<B, S, H> {
 		known_addresses: Vec<(PeerId, Multiaddr)>,
 		enable_mdns: bool,
 		allow_private_ipv4: bool,
+		request_response_protocols: Vec<request_responses::ProtocolConfig>,
 	) -> Self {
 		Behaviour {
 			substrate,
@@ -73,6 +80,8 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Behaviour<B, S, H> {
 				enable_mdns,
 				allow_private_ipv4
 			),
+			request_responses: RequestResponsesBehaviour::new(request_response_protocols),
+			pending_requests: Vec::new(),
 			events: Vec::new(),
 		}
 	}
@@ -115,6 +124,18 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Behaviour<B, S, H> {
 	pub fn put_value(&mut self, key: record::Key, value: Vec<u8>) {
 		self.discovery.put_value(key, value);
 	}
+
+	/// Sends a request to a peer over a request-response protocol. The response, or the reason
+	/// of the failure, is sent on `pending_response`.
+	pub fn send_request(
+		&mut self,
+		target: &PeerId,
+		protocol: &str,
+		request: Vec<u8>,
+		pending_response: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
+	) {
+		self.request_responses.send_request(target, protocol, request, pending_response)
+	}
 }
 
 impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> NetworkBehaviourEventProcess<void::Void> for
@@ -131,6 +152,24 @@ Behaviour<B, S, H> {
 	}
 }
 
+impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> NetworkBehaviourEventProcess<RequestResponsesEvent>
+	for Behaviour<B, S, H> {
+	fn inject_event(&mut self, event: RequestResponsesEvent) {
+		let RequestResponsesEvent::InboundRequest { protocol, request } = event;
+		let kind = match self.substrate.request_protocol(&protocol) {
+			Some(kind) => kind,
+			None => {
+				debug!(target: "sub-libp2p", "Dropped request on {} from {}: no handler", protocol, request.peer);
+				return
+			},
+		};
+		// Dropping `pending_response` without an answer closes the substream.
+		if let Some(response) = self.substrate.on_request(request.peer, kind, &request.payload) {
+			let _ = request.pending_response.send(response);
+		}
+	}
+}
+
 impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> NetworkBehaviourEventProcess<debug_info::DebugInfoEvent>
 	for Behaviour<B, S, H> {
 	fn inject_event(&mut self, event: debug_info::DebugInfoEvent) {
@@ -183,6 +222,33 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> NetworkBehaviourEventPr
 
 impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Behaviour<B, S, H> {
 	fn poll<TEv>(&mut self) -> Async<NetworkBehaviourAction<TEv, BehaviourOut<B>>> {
+		let requests = self.substrate.take_requests();
+		if !requests.is_empty() {
+			for (peer, protocol, request) in requests {
+				let (tx, rx) = oneshot::channel();
+				let name = self.substrate.request_protocol_name(protocol);
+				self.request_responses.send_request(&peer, &name, request, tx);
+				self.pending_requests.push((peer, protocol, rx));
+			}
+			// The request-response behaviour has already been polled.
+			futures::task::current().notify();
+		}
+
+		let mut index = 0;
+		while index < self.pending_requests.len() {
+			let response = match self.pending_requests[index].2.poll() {
+				Ok(Async::NotReady) => {
+					index += 1;
+					continue
+				},
+				Ok(Async::Ready(response)) => response,
+				Err(oneshot::Canceled) => Err(RequestFailure::ConnectionClosed),
+			};
+			let (peer, protocol, _) = self.pending_requests.swap_remove(index);
+			let outcome = self.substrate.on_request_response(peer, protocol, response);
+			self.events.push(BehaviourOut::SubstrateAction(outcome));
+		}
+
 		if !self.events.is_empty() {
 			return Async::Ready(NetworkBehaviourAction::GenerateEvent(self.events.remove(0)))
 		}
@@ -22,7 +22,7 @@ use client_api::{ChangesProof, StorageProof, ClientInfo, CallExecutor};
 use consensus::{BlockImport, BlockStatus, Error as ConsensusError};
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
 use sp_runtime::generic::{BlockId};
-use sp_runtime::Justification;
+use sp_runtime::{Justification, StorageOverlay, ChildrenStorageOverlay};
 use primitives::{H256, Blake2Hasher, storage::StorageKey};
 
 /// Local client abstraction for the network.
@@ -60,6 +60,36 @@ pub trait Client<Block: BlockT>: Send + Sync {
 		keys: &[Vec<u8>],
 	) -> Result<StorageProof, Error>;
 
+	/// Get a range of storage entries starting right after `start_key`, with a proof.
+	///
+	/// Returns the entries, whether the end of the trie was reached and the proof.
+	fn read_state_range(
+		&self,
+		block: &Block::Hash,
+		storage_key: Option<&[u8]>,
+		start_key: &[u8],
+		size_limit: usize,
+	) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool, StorageProof), Error>;
+
+	/// Check a range of storage entries against the given state root.
+	fn check_state_range(
+		&self,
+		root: Block::Hash,
+		proof: StorageProof,
+		storage_key: Option<&[u8]>,
+		start_key: &[u8],
+		entries: &[(Vec<u8>, Vec<u8>)],
+		complete: bool,
+	) -> Result<(), Error>;
+
+	/// Import the whole state of a known block.
+	fn import_state(
+		&self,
+		block: &Block::Hash,
+		top: StorageOverlay,
+		children: ChildrenStorageOverlay,
+	) -> Result<(), Error>;
+
 	/// Get method execution proof.
 	fn execution_proof(&self, block: &Block::Hash, method: &str, data: &[u8]) -> Result<(Vec<u8>, StorageProof), Error>;
 
@@ -82,12 +112,19 @@ pub trait Client<Block: BlockT>: Send + Sync {
 pub trait FinalityProofProvider<Block: BlockT>: Send + Sync {
 	/// Prove finality of the block.
 	fn prove_finality(&self, for_block: Block::Hash, request: &[u8]) -> Result<Option<Vec<u8>>, Error>;
+
+	/// Prove the authority set changes enacted after the given finalized block.
+	fn prove_warp_sync(&self, begin: Block::Hash) -> Result<Option<Vec<u8>>, Error>;
 }
 
 impl<Block: BlockT> FinalityProofProvider<Block> for () {
 	fn prove_finality(&self, _for_block: Block::Hash, _request: &[u8]) -> Result<Option<Vec<u8>>, Error> {
 		Ok(None)
 	}
+
+	fn prove_warp_sync(&self, _begin: Block::Hash) -> Result<Option<Vec<u8>>, Error> {
+		Ok(None)
+	}
 }
 
 impl<B, E, Block, RA> Client<Block> for SubstrateClient<B, E, Block, RA> where
@@ -145,6 +182,39 @@ impl<B, E, Block, RA> Client<Block> for SubstrateClient<B, E, Block, RA> where
 		(self as &SubstrateClient<B, E, Block, RA>).execution_proof(&BlockId::Hash(block.clone()), method, data)
 	}
 
+	fn read_state_range(
+		&self,
+		block: &Block::Hash,
+		storage_key: Option<&[u8]>,
+		start_key: &[u8],
+		size_limit: usize,
+	) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool, StorageProof), Error> {
+		(self as &SubstrateClient<B, E, Block, RA>)
+			.read_range_proof(&BlockId::Hash(block.clone()), storage_key, start_key, size_limit)
+	}
+
+	fn check_state_range(
+		&self,
+		root: Block::Hash,
+		proof: StorageProof,
+		storage_key: Option<&[u8]>,
+		start_key: &[u8],
+		entries: &[(Vec<u8>, Vec<u8>)],
+		complete: bool,
+	) -> Result<(), Error> {
+		(self as &SubstrateClient<B, E, Block, RA>)
+			.check_range_proof(root, proof, storage_key, start_key, entries, complete)
+	}
+
+	fn import_state(
+		&self,
+		block: &Block::Hash,
+		top: StorageOverlay,
+		children: ChildrenStorageOverlay,
+	) -> Result<(), Error> {
+		(self as &SubstrateClient<B, E, Block, RA>).import_state(block.clone(), top, children)
+	}
+
 	fn key_changes_proof(
 		&self,
 		first: Block::Hash,
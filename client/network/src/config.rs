@@ -20,6 +20,7 @@
 //! See the documentation of [`Params`].
 
 pub use crate::protocol::ProtocolConfig;
+pub use crate::request_responses::{IncomingRequest, ProtocolConfig as RequestResponseConfig};
 pub use libp2p::{identity, core::PublicKey, wasm_ext::ExtTransport, build_multiaddr};
 
 use crate::chain::{Client, FinalityProofProvider};
@@ -232,6 +233,32 @@ impl From<multiaddr::Error> for ParseErr {
 	}
 }
 
+/// How the chain is synchronized with the network.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SyncMode {
+	/// Download and execute all blocks.
+	Full,
+	/// Download headers and justifications, then the state of a recent finalized block,
+	/// and execute only the blocks after it.
+	Fast,
+}
+
+/// Limits on the rate at which data is exchanged with the network, in bytes per second.
+///
+/// `None` means unlimited. Limits apply to the decrypted traffic of a connection, which includes
+/// the overhead of multiplexing but not the one of encryption.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BandwidthLimits {
+	/// Maximum rate at which data is received from a single peer.
+	pub peer_download: Option<u64>,
+	/// Maximum rate at which data is sent to a single peer.
+	pub peer_upload: Option<u64>,
+	/// Maximum rate at which data is received from all peers together.
+	pub download: Option<u64>,
+	/// Maximum rate at which data is sent to all peers together.
+	pub upload: Option<u64>,
+}
+
 /// Network service configuration.
 #[derive(Clone, Debug)]
 pub struct NetworkConfiguration {
@@ -265,6 +292,12 @@ pub struct NetworkConfiguration {
 	pub transport: TransportConfig,
 	/// Maximum number of peers to ask the same blocks in parallel.
 	pub max_parallel_downloads: u32,
+	/// How the chain is synchronized with the network.
+	pub sync_mode: SyncMode,
+	/// Upload and download rate limits.
+	pub bandwidth_limits: BandwidthLimits,
+	/// Additional request-response protocols, usable through `NetworkService::request`.
+	pub request_response_protocols: Vec<RequestResponseConfig>,
 }
 
 impl Default for NetworkConfiguration {
@@ -289,6 +322,9 @@ impl Default for NetworkConfiguration {
 				wasm_external_transport: None,
 			},
 			max_parallel_downloads: 5,
+			sync_mode: SyncMode::Full,
+			bandwidth_limits: BandwidthLimits::default(),
+			request_response_protocols: Vec::new(),
 		}
 	}
 }
@@ -174,6 +174,7 @@ mod debug_info;
 mod discovery;
 mod on_demand_layer;
 mod protocol;
+mod request_responses;
 mod service;
 mod transport;
 mod utils;
@@ -189,6 +190,7 @@ pub use service::{
 pub use protocol::{PeerInfo, Context, ProtocolConfig, consensus_gossip, message, specialization};
 pub use protocol::event::{Event, DhtEvent};
 pub use protocol::sync::SyncState;
+pub use request_responses::RequestFailure;
 pub use libp2p::{Multiaddr, PeerId};
 #[doc(inline)]
 pub use libp2p::multiaddr;
@@ -239,6 +241,8 @@ pub struct NetworkState {
 	pub average_download_per_sec: u64,
 	/// Uploaded bytes per second averaged over the past few seconds.
 	pub average_upload_per_sec: u64,
+	/// Bytes of protocol messages exchanged since the node started, per protocol.
+	pub bandwidth_by_protocol: HashMap<String, NetworkStateBandwidth>,
 	/// State of the peerset manager.
 	pub peerset: serde_json::Value,
 }
@@ -261,6 +265,18 @@ pub struct NetworkStatePeer {
 	pub open: bool,
 	/// List of addresses known for this node.
 	pub known_addresses: HashSet<Multiaddr>,
+	/// Decrypted traffic with this node over the open connections, multiplexing included.
+	pub bandwidth: NetworkStateBandwidth,
+}
+
+/// Part of the `NetworkState` struct. Unstable.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStateBandwidth {
+	/// Total number of bytes received.
+	pub total_inbound: u64,
+	/// Total number of bytes sent.
+	pub total_outbound: u64,
 }
 
 /// Part of the `NetworkState` struct. Unstable.
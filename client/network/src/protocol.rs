@@ -18,7 +18,7 @@ use crate::{DiscoveryNetBehaviour, config::ProtocolId};
 use legacy_proto::{LegacyProto, LegacyProtoOut};
 use crate::utils::interval;
 use bytes::BytesMut;
-use futures::prelude::*;
+use futures::{prelude::*, sync::oneshot};
 use futures03::{StreamExt as _, TryStreamExt as _};
 use libp2p::{Multiaddr, PeerId};
 use libp2p::core::{ConnectedPoint, nodes::Substream, muxing::StreamMuxerBox};
@@ -43,9 +43,11 @@ use light_dispatch::{LightDispatch, LightDispatchNetwork, RequestData};
 use specialization::NetworkSpecialization;
 use sync::{ChainSync, SyncState};
 use crate::service::{TransactionPool, ExHashT};
-use crate::config::{BoxFinalityProofRequestBuilder, Roles};
+use crate::transport::{BandwidthProtocol, ProtocolBandwidthSinks};
+use crate::request_responses::{ProtocolConfig as RequestResponseConfig, RequestFailure};
+use crate::config::{BoxFinalityProofRequestBuilder, Roles, SyncMode};
 use rustc_hex::ToHex;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::fmt::Write;
 use std::{cmp, num::NonZeroUsize, time};
@@ -77,7 +79,11 @@ const MAX_KNOWN_BLOCKS: usize = 1024; // ~32kb per peer + LruHashSet overhead
 const MAX_KNOWN_EXTRINSICS: usize = 4096; // ~128kb per peer + overhead
 
 /// Current protocol version.
-pub(crate) const CURRENT_VERSION: u32 = 5;
+pub(crate) const CURRENT_VERSION: u32 = 8;
+/// Lowest version that answers state requests.
+const STATE_REQUEST_VERSION: u32 = 6;
+/// Lowest version that sends requests over the request-response protocols.
+const REQUEST_RESPONSE_VERSION: u32 = 8;
 /// Lowest version we support
 pub(crate) const MIN_VERSION: u32 = 3;
 
@@ -85,6 +91,17 @@ pub(crate) const MIN_VERSION: u32 = 3;
 const MAX_BLOCK_DATA_RESPONSE: u32 = 128;
 // Maximum allowed entries in `ConsensusBatch`
 const MAX_CONSENSUS_MESSAGES: usize = 256;
+// Maximum total size of the storage entries in `StateResponse`
+const MAX_STATE_RESPONSE_SIZE: usize = 2 * 1024 * 1024;
+/// Minimum interval between two warp sync requests of a peer. Proofs are up to 8MB, so more
+/// frequent requests are answered without a proof.
+const WARP_SYNC_REQUEST_INTERVAL: time::Duration = time::Duration::from_secs(10);
+/// Maximum size of a request sent over the request-response protocols.
+const MAX_REQUEST_SIZE: usize = 1024 * 1024;
+/// Maximum size of a response received over the request-response protocols.
+const MAX_RESPONSE_SIZE: usize = 16 * 1024 * 1024;
+/// Maximum number of requests of each protocol a peer can have us answering at the same time.
+const MAX_CONCURRENT_INBOUND_REQUESTS: usize = 20;
 /// When light node connects to the full node and the full node is behind light node
 /// for at least `LIGHT_MAXIMAL_BLOCKS_DIFFERENCE` blocks, we consider it unuseful
 /// and disconnect to free connection slot.
@@ -97,6 +114,8 @@ mod rep {
 	pub const CLOGGED_PEER: Rep = Rep::new(-(1 << 12), "Clogged message queue");
 	/// Reputation change when a peer doesn't respond in time to our messages.
 	pub const TIMEOUT: Rep = Rep::new(-(1 << 10), "Request timeout");
+	/// Reputation change when a peer refuses a request, e.g. because it is busy.
+	pub const REFUSED: Rep = Rep::new(-(1 << 8), "Request refused");
 	/// Reputation change when a peer sends us a status message while we already received one.
 	pub const UNEXPECTED_STATUS: Rep = Rep::new(-(1 << 20), "Unexpected status message");
 	/// Reputation change when we are a light client and a peer is behind us.
@@ -107,6 +126,8 @@ mod rep {
 	pub const BAD_EXTRINSIC: Rep = Rep::new(-(1 << 12), "Bad extrinsic");
 	/// We sent an RPC query to the given node, but it failed.
 	pub const RPC_FAILED: Rep = Rep::new(-(1 << 12), "Remote call failed");
+	/// Peer sent warp sync requests faster than we answer them.
+	pub const WARP_SYNC_FLOOD: Rep = Rep::new(-(1 << 10), "Too many warp sync requests");
 	/// We received a message that failed to decode.
 	pub const BAD_MESSAGE: Rep = Rep::new(-(1 << 12), "Bad message");
 	/// We received an unexpected response.
@@ -147,10 +168,121 @@ pub struct Protocol<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> {
 	transaction_pool: Arc<dyn TransactionPool<H, B>>,
 	/// When asked for a proof of finality, we use this struct to build one.
 	finality_proof_provider: Option<Arc<dyn FinalityProofProvider<B>>>,
+	/// Pending warp sync requests, with the channel the proof is to be sent to.
+	warp_sync_requests: HashMap<PeerId, (message::RequestId, oneshot::Sender<Option<Vec<u8>>>)>,
+	/// Request being answered for a request-response protocol. The response is stored here
+	/// instead of being sent over the legacy substream.
+	captured_response: Option<(PeerId, Option<Vec<u8>>)>,
+	protocol_id: ProtocolId,
 	/// Handles opening the unique substream and sending and receiving raw messages.
 	behaviour: LegacyProto<Substream<StreamMuxerBox>>,
 }
 
+/// Request-response protocols the requests of the legacy substream have been moved to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RequestProtocol {
+	/// Block requests.
+	Sync,
+	/// Light client requests.
+	Light,
+	/// Finality proof requests.
+	FinalityProof,
+}
+
+impl RequestProtocol {
+	/// All the request-response protocols.
+	pub const ALL: [RequestProtocol; 3] = [
+		RequestProtocol::Sync,
+		RequestProtocol::Light,
+		RequestProtocol::FinalityProof,
+	];
+
+	/// Returns the protocol the given request is sent over, or `None` if it isn't a request
+	/// that has been moved to a request-response protocol.
+	fn of_request<B: BlockT>(message: &Message<B>) -> Option<Self> {
+		match message {
+			GenericMessage::BlockRequest(_) => Some(RequestProtocol::Sync),
+			GenericMessage::RemoteCallRequest(_) |
+			GenericMessage::RemoteReadRequest(_) |
+			GenericMessage::RemoteReadChildRequest(_) |
+			GenericMessage::RemoteHeaderRequest(_) |
+			GenericMessage::RemoteChangesRequest(_) => Some(RequestProtocol::Light),
+			GenericMessage::FinalityProofRequest(_) => Some(RequestProtocol::FinalityProof),
+			_ => None,
+		}
+	}
+
+	/// Returns the protocol the given response is sent over, or `None` if it isn't a response
+	/// that has been moved to a request-response protocol.
+	fn of_response<B: BlockT>(message: &Message<B>) -> Option<Self> {
+		match message {
+			GenericMessage::BlockResponse(_) => Some(RequestProtocol::Sync),
+			GenericMessage::RemoteCallResponse(_) |
+			GenericMessage::RemoteReadResponse(_) |
+			GenericMessage::RemoteHeaderResponse(_) |
+			GenericMessage::RemoteChangesResponse(_) => Some(RequestProtocol::Light),
+			GenericMessage::FinalityProofResponse(_) => Some(RequestProtocol::FinalityProof),
+			_ => None,
+		}
+	}
+
+	/// Name of the protocol on the wire.
+	pub fn name(&self, protocol_id: &ProtocolId) -> String {
+		let id = String::from_utf8_lossy(protocol_id.as_bytes());
+		match self {
+			RequestProtocol::Sync => format!("/substrate/{}/sync/2", id),
+			RequestProtocol::Light => format!("/substrate/{}/light/2", id),
+			RequestProtocol::FinalityProof => format!("/substrate/{}/finality-proof/1", id),
+		}
+	}
+
+	/// Configuration of the protocol. Incoming requests are reported as events, to be answered
+	/// with [`Protocol::on_request`].
+	pub fn config(&self, protocol_id: &ProtocolId) -> RequestResponseConfig {
+		RequestResponseConfig {
+			name: self.name(protocol_id).into(),
+			max_request_size: MAX_REQUEST_SIZE,
+			max_response_size: MAX_RESPONSE_SIZE,
+			request_timeout: time::Duration::from_secs(REQUEST_TIMEOUT_SEC),
+			max_concurrent_inbound: MAX_CONCURRENT_INBOUND_REQUESTS,
+			inbound_queue: None,
+		}
+	}
+}
+
+/// Requests to send over the request-response protocols.
+#[derive(Default)]
+struct RequestsOut {
+	/// Peers that accept requests over the request-response protocols.
+	peers: HashSet<PeerId>,
+	queue: VecDeque<(PeerId, RequestProtocol, Vec<u8>)>,
+}
+
+impl RequestsOut {
+	/// Returns the protocol to send the message over, if the peer accepts it as a request.
+	fn protocol_for<B: BlockT>(&self, who: &PeerId, message: &Message<B>) -> Option<RequestProtocol> {
+		if self.peers.contains(who) {
+			RequestProtocol::of_request(message)
+		} else {
+			None
+		}
+	}
+
+	/// Sends a message, over a request-response protocol if possible and over the legacy
+	/// substream otherwise.
+	fn send_packet<B: BlockT>(
+		&mut self,
+		behaviour: &mut LegacyProto<Substream<StreamMuxerBox>>,
+		who: &PeerId,
+		message: Message<B>,
+	) {
+		match self.protocol_for(who, &message) {
+			Some(protocol) => self.queue.push_back((who.clone(), protocol, message.encode())),
+			None => behaviour.send_packet(who, message.encode()),
+		}
+	}
+}
+
 #[derive(Default)]
 struct PacketStats {
 	bytes_in: u64,
@@ -159,6 +291,18 @@ struct PacketStats {
 	count_out: u64,
 }
 
+/// Traffic of the protocol messages.
+#[derive(Default)]
+struct Stats {
+	/// Per message type.
+	packets: HashMap<&'static str, PacketStats>,
+	/// Per protocol.
+	protocols: ProtocolBandwidthSinks,
+}
+
+/// Engine id of GRANDPA, whose gossip is accounted for separately.
+const GRANDPA_ENGINE_ID: ConsensusEngineId = *b"FRNK";
+
 /// A peer that we are connected to
 /// and from whom we have not yet received a Status message.
 struct HandshakingPeer {
@@ -171,6 +315,10 @@ struct Peer<B: BlockT, H: ExHashT> {
 	info: PeerInfo<B>,
 	/// Current block request, if any.
 	block_request: Option<(time::Instant, message::BlockRequest<B>)>,
+	/// Current state request, if any.
+	state_request: Option<(time::Instant, message::RequestId)>,
+	/// When a warp sync request of the peer was last answered.
+	last_warp_sync_request: Option<time::Instant>,
 	/// Requests we are no longer insterested in.
 	obsolete_requests: HashMap<message::RequestId, time::Instant>,
 	/// Holds a set of transactions known to this peer.
@@ -196,6 +344,7 @@ pub struct PeerInfo<B: BlockT> {
 
 struct LightDispatchIn<'a> {
 	behaviour: &'a mut LegacyProto<Substream<StreamMuxerBox>>,
+	requests: &'a mut RequestsOut,
 	peerset: peerset::PeersetHandle,
 }
 
@@ -214,7 +363,7 @@ impl<'a, B: BlockT> LightDispatchNetwork<B> for LightDispatchIn<'a> {
 			block,
 		});
 
-		self.behaviour.send_packet(who, message.encode())
+		self.requests.send_packet(self.behaviour, who, message)
 	}
 
 	fn send_read_request(
@@ -230,7 +379,7 @@ impl<'a, B: BlockT> LightDispatchNetwork<B> for LightDispatchIn<'a> {
 			keys,
 		});
 
-		self.behaviour.send_packet(who, message.encode())
+		self.requests.send_packet(self.behaviour, who, message)
 	}
 
 	fn send_read_child_request(
@@ -248,7 +397,7 @@ impl<'a, B: BlockT> LightDispatchNetwork<B> for LightDispatchIn<'a> {
 			keys,
 		});
 
-		self.behaviour.send_packet(who, message.encode())
+		self.requests.send_packet(self.behaviour, who, message)
 	}
 
 	fn send_call_request(
@@ -266,7 +415,7 @@ impl<'a, B: BlockT> LightDispatchNetwork<B> for LightDispatchIn<'a> {
 			data,
 		});
 
-		self.behaviour.send_packet(who, message.encode())
+		self.requests.send_packet(self.behaviour, who, message)
 	}
 
 	fn send_changes_request(
@@ -290,7 +439,7 @@ impl<'a, B: BlockT> LightDispatchNetwork<B> for LightDispatchIn<'a> {
 			key,
 		});
 
-		self.behaviour.send_packet(who, message.encode())
+		self.requests.send_packet(self.behaviour, who, message)
 	}
 
 	fn send_body_request(
@@ -312,7 +461,7 @@ impl<'a, B: BlockT> LightDispatchNetwork<B> for LightDispatchIn<'a> {
 			max,
 		});
 
-		self.behaviour.send_packet(who, message.encode())
+		self.requests.send_packet(self.behaviour, who, message)
 	}
 }
 
@@ -401,7 +550,9 @@ impl<'a, B: BlockT + 'a, H: ExHashT + 'a> Context<B> for ProtocolContext<'a, B,
 struct ContextData<B: BlockT, H: ExHashT> {
 	// All connected peers
 	peers: HashMap<PeerId, Peer<B, H>>,
-	stats: HashMap<&'static str, PacketStats>,
+	stats: Stats,
+	/// Requests to send over the request-response protocols.
+	requests: RequestsOut,
 	pub chain: Arc<dyn Client<B>>,
 }
 
@@ -412,6 +563,8 @@ pub struct ProtocolConfig {
 	pub roles: Roles,
 	/// Maximum number of peers to ask the same blocks in parallel.
 	pub max_parallel_downloads: u32,
+	/// How the chain is synchronized with the network.
+	pub sync_mode: SyncMode,
 }
 
 impl Default for ProtocolConfig {
@@ -419,6 +572,7 @@ impl Default for ProtocolConfig {
 		ProtocolConfig {
 			roles: Roles::FULL,
 			max_parallel_downloads: 5,
+			sync_mode: SyncMode::Full,
 		}
 	}
 }
@@ -445,6 +599,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			finality_proof_request_builder,
 			block_announce_validator,
 			config.max_parallel_downloads,
+			config.sync_mode,
 		);
 
 		let important_peers = {
@@ -458,7 +613,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 
 		let (peerset, peerset_handle) = peerset::Peerset::from_config(peerset_config);
 		let versions = &((MIN_VERSION as u8)..=(CURRENT_VERSION as u8)).collect::<Vec<u8>>();
-		let behaviour = LegacyProto::new(protocol_id, versions, peerset);
+		let behaviour = LegacyProto::new(protocol_id.clone(), versions, peerset);
 
 		let protocol = Protocol {
 			tick_timeout: Box::new(interval(TICK_TIMEOUT).map(|v| Ok::<_, ()>(v)).compat()),
@@ -466,7 +621,8 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			config,
 			context_data: ContextData {
 				peers: HashMap::new(),
-				stats: HashMap::new(),
+				stats: Stats::default(),
+				requests: RequestsOut::default(),
 				chain,
 			},
 			light_dispatch: LightDispatch::new(checker),
@@ -478,7 +634,10 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			important_peers,
 			transaction_pool,
 			finality_proof_provider,
+			warp_sync_requests: HashMap::new(),
 			peerset_handle: peerset_handle.clone(),
+			captured_response: None,
+			protocol_id,
 			behaviour,
 		};
 
@@ -510,6 +669,11 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		self.behaviour.peerset_debug_info()
 	}
 
+	/// Returns the number of bytes of messages exchanged so far, per protocol.
+	pub fn protocol_bandwidth(&self) -> &ProtocolBandwidthSinks {
+		&self.context_data.stats.protocols
+	}
+
 	/// Returns the number of peers we're connected to.
 	pub fn num_connected_peers(&self) -> usize {
 		self.context_data.peers.values().count()
@@ -550,6 +714,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 	pub(crate) fn add_light_client_request(&mut self, rq: RequestData<B>) {
 		self.light_dispatch.add_request(LightDispatchIn {
 			behaviour: &mut self.behaviour,
+			requests: &mut self.context_data.requests,
 			peerset: self.peerset_handle.clone(),
 		}, rq);
 	}
@@ -609,9 +774,133 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			}
 		};
 
-		let mut stats = self.context_data.stats.entry(message.id()).or_default();
-		stats.bytes_in += data.len() as u64;
+		self.on_message(who, message, data.len())
+	}
+
+	/// Returns the request-response protocol with the given name, if it is one of ours.
+	pub fn request_protocol(&self, name: &str) -> Option<RequestProtocol> {
+		RequestProtocol::ALL.iter().find(|protocol| protocol.name(&self.protocol_id) == name).cloned()
+	}
+
+	/// Returns the name of the given request-response protocol.
+	pub fn request_protocol_name(&self, protocol: RequestProtocol) -> String {
+		protocol.name(&self.protocol_id)
+	}
+
+	/// Returns the requests to send over the request-response protocols, and clears the queue.
+	///
+	/// Their responses must be passed to [`Protocol::on_request_response`].
+	pub fn take_requests(&mut self) -> Vec<(PeerId, RequestProtocol, Vec<u8>)> {
+		self.context_data.requests.queue.drain(..).collect()
+	}
+
+	/// Handles a request received over a request-response protocol. Returns the response to send
+	/// back, if any.
+	pub fn on_request(&mut self, who: PeerId, protocol: RequestProtocol, request: &[u8]) -> Option<Vec<u8>> {
+		let message = match <Message<B> as Decode>::decode(&mut &request[..]) {
+			Ok(message) => message,
+			Err(err) => {
+				debug!(target: "sync", "Couldn't decode request sent by {}: {}", who, err.what());
+				self.peerset_handle.report_peer(who, rep::BAD_MESSAGE);
+				return None;
+			}
+		};
+		if RequestProtocol::of_request(&message) != Some(protocol) {
+			debug!(target: "sync", "Unexpected {} message sent by {} as a {:?} request", message.id(), who, protocol);
+			self.peerset_handle.report_peer(who, rep::BAD_MESSAGE);
+			return None;
+		}
+
+		self.captured_response = Some((who.clone(), None));
+		self.on_message(who, message, request.len());
+		self.captured_response.take().and_then(|(_, response)| response)
+	}
+
+	/// Handles the outcome of a request sent over a request-response protocol.
+	pub fn on_request_response(
+		&mut self,
+		who: PeerId,
+		protocol: RequestProtocol,
+		response: Result<Vec<u8>, RequestFailure>,
+	) -> CustomMessageOutcome<B> {
+		let response = match response {
+			Ok(response) => response,
+			Err(RequestFailure::NotConnected) | Err(RequestFailure::ConnectionClosed) => {
+				// The request is retried by the disconnection handlers.
+				trace!(target: "sync", "{:?} request to {} dropped: peer disconnected", protocol, who);
+				return CustomMessageOutcome::None;
+			},
+			Err(RequestFailure::Refused) => {
+				// Honest peers refuse requests beyond their limit of concurrent requests, the
+				// request is retried.
+				debug!(target: "sync", "{:?} request to {} refused", protocol, who);
+				self.peerset_handle.report_peer(who.clone(), rep::REFUSED);
+				self.on_request_refused(who, protocol);
+				return CustomMessageOutcome::None;
+			},
+			Err(err) => {
+				debug!(target: "sync", "{:?} request to {} failed: {}", protocol, who, err);
+				let reputation = match err {
+					RequestFailure::Timeout => rep::TIMEOUT,
+					_ => rep::BAD_PROTOCOL,
+				};
+				self.behaviour.disconnect_peer(&who);
+				self.peerset_handle.report_peer(who, reputation);
+				return CustomMessageOutcome::None;
+			},
+		};
+
+		let message = match <Message<B> as Decode>::decode(&mut &response[..]) {
+			Ok(message) => message,
+			Err(err) => {
+				debug!(target: "sync", "Couldn't decode response sent by {}: {}", who, err.what());
+				self.peerset_handle.report_peer(who, rep::BAD_MESSAGE);
+				return CustomMessageOutcome::None;
+			}
+		};
+		if RequestProtocol::of_response(&message) != Some(protocol) {
+			debug!(target: "sync", "Unexpected {} message sent by {} as a {:?} response", message.id(), who, protocol);
+			self.behaviour.disconnect_peer(&who);
+			self.peerset_handle.report_peer(who, rep::UNEXPECTED_RESPONSE);
+			return CustomMessageOutcome::None;
+		}
+
+		self.on_message(who, message, response.len())
+	}
+
+	/// Retries the request refused by the given peer, without disconnecting it.
+	fn on_request_refused(&mut self, who: PeerId, protocol: RequestProtocol) {
+		if protocol == RequestProtocol::Sync {
+			if let Some(peer) = self.context_data.peers.get_mut(&who) {
+				peer.block_request = None;
+			}
+		}
+		if protocol != RequestProtocol::Light {
+			if let Some(request) = self.sync.on_request_failed(&who) {
+				self.send_request(&who, GenericMessage::BlockRequest(request));
+			}
+		}
+		if protocol != RequestProtocol::FinalityProof {
+			// light clients send body requests over the sync protocol.
+			self.light_dispatch.on_request_failed(LightDispatchIn {
+				behaviour: &mut self.behaviour,
+				requests: &mut self.context_data.requests,
+				peerset: self.peerset_handle.clone(),
+			}, who);
+		}
+	}
+
+	fn on_message(
+		&mut self,
+		who: PeerId,
+		message: Message<B>,
+		len: usize,
+	) -> CustomMessageOutcome<B> {
+		let mut stats = self.context_data.stats.packets.entry(message.id()).or_default();
+		stats.bytes_in += len as u64;
 		stats.count_in += 1;
+		let protocols = &self.context_data.stats.protocols;
+		account_message(&message, len, |protocol, bytes| protocols.inject_inbound(protocol, bytes));
 
 		match message {
 			GenericMessage::Status(s) => self.on_status_message(who, s),
@@ -656,6 +945,14 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				return self.on_finality_proof_response(who, response),
 			GenericMessage::RemoteReadChildRequest(request) =>
 				self.on_remote_read_child_request(who, request),
+			GenericMessage::StateRequest(request) =>
+				self.on_state_request(who, request),
+			GenericMessage::StateResponse(response) =>
+				self.on_state_response(who, response),
+			GenericMessage::WarpSyncRequest(request) =>
+				self.on_warp_sync_request(who, request),
+			GenericMessage::WarpSyncResponse(response) =>
+				self.on_warp_sync_response(who, response),
 			GenericMessage::Consensus(msg) => {
 				self.consensus_gossip.on_incoming(
 					&mut ProtocolContext::new(&mut self.context_data, &mut self.behaviour, &self.peerset_handle),
@@ -685,12 +982,23 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			&mut self.behaviour,
 			&mut self.context_data.stats,
 			&mut self.context_data.peers,
+			&mut self.context_data.requests,
 			who,
 			message,
 		);
 	}
 
 	fn send_message(&mut self, who: &PeerId, message: Message<B>) {
+		if let Some((peer, response @ None)) = &mut self.captured_response {
+			if peer == who {
+				// Answer to a request received over a request-response protocol.
+				let encoded = message.encode();
+				note_outbound(&mut self.context_data.stats, &message, encoded.len());
+				*response = Some(encoded);
+				return;
+			}
+		}
+
 		send_message::<B>(
 			&mut self.behaviour,
 			&mut self.context_data.stats,
@@ -753,6 +1061,9 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		// lock all the the peer lists so that add/remove peer events are in order
 		let removed = {
 			self.handshaking_peers.remove(&peer);
+			self.warp_sync_requests.remove(&peer);
+			self.context_data.requests.peers.remove(&peer);
+			self.context_data.requests.queue.retain(|(who, _, _)| *who != peer);
 			self.context_data.peers.remove(&peer)
 		};
 		if let Some(peer_data) = removed {
@@ -764,6 +1075,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			self.specialization.on_disconnect(&mut context, peer.clone());
 			self.light_dispatch.on_disconnect(LightDispatchIn {
 				behaviour: &mut self.behaviour,
+				requests: &mut self.context_data.requests,
 				peerset: self.peerset_handle.clone(),
 			}, peer);
 		}
@@ -928,6 +1240,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		self.maintain_peers();
 		self.light_dispatch.maintain_peers(LightDispatchIn {
 			behaviour: &mut self.behaviour,
+			requests: &mut self.context_data.requests,
 			peerset: self.peerset_handle.clone(),
 		});
 	}
@@ -937,7 +1250,9 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		let mut aborting = Vec::new();
 		{
 			for (who, peer) in self.context_data.peers.iter() {
-				if peer.block_request.as_ref().map_or(false, |(t, _)| (tick - *t).as_secs() > REQUEST_TIMEOUT_SEC) {
+				if peer.block_request.as_ref().map_or(false, |(t, _)| (tick - *t).as_secs() > REQUEST_TIMEOUT_SEC) ||
+					peer.state_request.as_ref().map_or(false, |(t, _)| (tick - *t).as_secs() > REQUEST_TIMEOUT_SEC)
+				{
 					log!(
 						target: "sync",
 						if self.important_peers.contains(&who) { Level::Warn } else { Level::Trace },
@@ -1054,6 +1369,8 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			let peer = Peer {
 				info,
 				block_request: None,
+				state_request: None,
+				last_warp_sync_request: None,
 				known_extrinsics: LruHashSet::new(NonZeroUsize::new(MAX_KNOWN_EXTRINSICS)
 					.expect("Constant is nonzero")),
 				known_blocks: LruHashSet::new(NonZeroUsize::new(MAX_KNOWN_BLOCKS)
@@ -1062,6 +1379,9 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				obsolete_requests: HashMap::new(),
 			};
 			self.context_data.peers.insert(who.clone(), peer);
+			if status.version >= REQUEST_RESPONSE_VERSION {
+				self.context_data.requests.peers.insert(who.clone());
+			}
 
 			debug!(target: "sync", "Connected {}", who);
 			status.version
@@ -1070,10 +1390,11 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		let info = self.context_data.peers.get(&who).expect("We just inserted above; QED").info.clone();
 		self.light_dispatch.on_connect(LightDispatchIn {
 			behaviour: &mut self.behaviour,
+			requests: &mut self.context_data.requests,
 			peerset: self.peerset_handle.clone(),
 		}, who.clone(), status.roles, status.best_number);
 		if info.roles.is_full() {
-			match self.sync.new_peer(who.clone(), info.best_hash, info.best_number) {
+			match self.sync.new_peer(who.clone(), info.best_hash, info.best_number, protocol_version) {
 				Ok(None) => (),
 				Ok(Some(req)) => self.send_request(&who, GenericMessage::BlockRequest(req)),
 				Err(sync::BadPeer(id, repu)) => {
@@ -1255,6 +1576,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		}
 		self.light_dispatch.update_best_number(LightDispatchIn {
 			behaviour: &mut self.behaviour,
+			requests: &mut self.context_data.requests,
 			peerset: self.peerset_handle.clone(),
 		}, who.clone(), *announce.header.number());
 
@@ -1419,6 +1741,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 						&mut self.behaviour,
 						&mut self.context_data.stats,
 						&mut self.context_data.peers,
+						&mut self.context_data.requests,
 						&id,
 						msg
 					)
@@ -1460,6 +1783,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		trace!(target: "sync", "Remote call response {} from {}", response.id, who);
 		self.light_dispatch.on_remote_call_response(LightDispatchIn {
 			behaviour: &mut self.behaviour,
+			requests: &mut self.context_data.requests,
 			peerset: self.peerset_handle.clone(),
 		}, who, response);
 	}
@@ -1545,6 +1869,171 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		);
 	}
 
+	fn on_state_request(
+		&mut self,
+		who: PeerId,
+		request: message::StateRequest<B::Hash>,
+	) {
+		trace!(target: "sync", "State request {} from {} ({:?} after {} at {})",
+			request.id,
+			who,
+			request.storage_key.as_ref().map(|k| k.to_hex::<String>()),
+			request.start.to_hex::<String>(),
+			request.block,
+		);
+		let (entries, complete, proof) = match self.context_data.chain.read_state_range(
+			&request.block,
+			request.storage_key.as_ref().map(|k| k.as_slice()),
+			&request.start,
+			MAX_STATE_RESPONSE_SIZE,
+		) {
+			Ok((entries, complete, proof)) => (entries, complete, Some(proof)),
+			Err(error) => {
+				trace!(target: "sync", "State request {} from {} at {} failed with: {}",
+					request.id,
+					who,
+					request.block,
+					error
+				);
+				(Vec::new(), false, None)
+			}
+		};
+		self.send_message(
+			&who,
+			GenericMessage::StateResponse(message::StateResponse {
+				id: request.id,
+				entries,
+				complete,
+				proof,
+			}),
+		);
+	}
+
+	fn on_state_response(
+		&mut self,
+		who: PeerId,
+		response: message::StateResponse,
+	) {
+		trace!(target: "sync", "State response {} from {} ({} entries)", response.id, who, response.entries.len());
+		if let Some(ref mut peer) = self.context_data.peers.get_mut(&who) {
+			if peer.state_request.take().map_or(true, |(_, id)| id != response.id) {
+				trace!(target: "sync", "Unexpected state response packet from {} ({})", who, response.id);
+				self.peerset_handle.report_peer(who.clone(), rep::UNEXPECTED_RESPONSE);
+				self.behaviour.disconnect_peer(&who);
+				return;
+			}
+		}
+		if let Err(sync::BadPeer(id, repu)) = self.sync.on_state_data(&who, response) {
+			self.behaviour.disconnect_peer(&id);
+			self.peerset_handle.report_peer(id, repu);
+		}
+	}
+
+	/// Request a warp sync proof starting at the given finalized block from a peer.
+	///
+	/// The proof, or `None` if the peer can't provide it, is sent to `sender`. The sender is
+	/// dropped if the peer disconnects or if the request is superseded by another one.
+	pub fn request_warp_sync_proof(
+		&mut self,
+		who: PeerId,
+		begin: B::Hash,
+		sender: oneshot::Sender<Option<Vec<u8>>>,
+	) {
+		let id = match self.context_data.peers.get_mut(&who) {
+			Some(ref peer) if peer.info.protocol_version < 7 => {
+				trace!(target: "sync", "Peer {} doesn't support warp sync proofs", who);
+				return;
+			},
+			Some(peer) => {
+				let id = peer.next_request_id;
+				peer.next_request_id += 1;
+				id
+			},
+			None => {
+				trace!(target: "sync", "Warp sync proof requested from unknown peer {}", who);
+				return;
+			}
+		};
+
+		self.warp_sync_requests.insert(who.clone(), (id, sender));
+		self.send_message(
+			&who,
+			GenericMessage::WarpSyncRequest(message::WarpSyncRequest { id, begin }),
+		);
+	}
+
+	fn on_warp_sync_request(
+		&mut self,
+		who: PeerId,
+		request: message::WarpSyncRequest<B::Hash>,
+	) {
+		trace!(target: "sync", "Warp sync request {} from {} starting at {}", request.id, who, request.begin);
+		let now = time::Instant::now();
+		let too_frequent = match self.context_data.peers.get_mut(&who) {
+			Some(peer) => match peer.last_warp_sync_request {
+				Some(last) if now.duration_since(last) < WARP_SYNC_REQUEST_INTERVAL => true,
+				_ => {
+					peer.last_warp_sync_request = Some(now);
+					false
+				},
+			},
+			None => return,
+		};
+		if too_frequent {
+			trace!(target: "sync", "Warp sync request {} from {} is too frequent", request.id, who);
+			self.peerset_handle.report_peer(who.clone(), rep::WARP_SYNC_FLOOD);
+			self.send_message(
+				&who,
+				GenericMessage::WarpSyncResponse(message::WarpSyncResponse {
+					id: request.id,
+					proof: None,
+				}),
+			);
+			return;
+		}
+
+		let proof = self.finality_proof_provider.as_ref()
+			.ok_or_else(|| String::from("Finality provider is not configured"))
+			.and_then(|provider| provider.prove_warp_sync(request.begin).map_err(|e| e.to_string()));
+		let proof = match proof {
+			Ok(proof) => proof,
+			Err(error) => {
+				trace!(target: "sync", "Warp sync request {} from {} starting at {} failed with: {}",
+					request.id,
+					who,
+					request.begin,
+					error
+				);
+				None
+			},
+		};
+		self.send_message(
+			&who,
+			GenericMessage::WarpSyncResponse(message::WarpSyncResponse {
+				id: request.id,
+				proof,
+			}),
+		);
+	}
+
+	fn on_warp_sync_response(
+		&mut self,
+		who: PeerId,
+		response: message::WarpSyncResponse,
+	) {
+		trace!(target: "sync", "Warp sync response {} from {}", response.id, who);
+		match self.warp_sync_requests.remove(&who) {
+			Some((id, sender)) if id == response.id => {
+				let _ = sender.send(response.proof);
+			},
+			_ => {
+				trace!(target: "sync", "Unexpected warp sync response packet from {} ({})", who, response.id);
+				self.peerset_handle.report_peer(who.clone(), rep::UNEXPECTED_RESPONSE);
+				self.behaviour.disconnect_peer(&who);
+			},
+		}
+	}
+
 	fn on_remote_read_response(
 		&mut self,
 		who: PeerId,
@@ -1553,6 +2042,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		trace!(target: "sync", "Remote read response {} from {}", response.id, who);
 		self.light_dispatch.on_remote_read_response(LightDispatchIn {
 			behaviour: &mut self.behaviour,
+			requests: &mut self.context_data.requests,
 			peerset: self.peerset_handle.clone(),
 		}, who, response);
 	}
@@ -1594,6 +2084,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		trace!(target: "sync", "Remote header proof response {} from {}", response.id, who);
 		self.light_dispatch.on_remote_header_response(LightDispatchIn {
 			behaviour: &mut self.behaviour,
+			requests: &mut self.context_data.requests,
 			peerset: self.peerset_handle.clone(),
 		}, who, response);
 	}
@@ -1670,6 +2161,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		);
 		self.light_dispatch.on_remote_changes_response(LightDispatchIn {
 			behaviour: &mut self.behaviour,
+			requests: &mut self.context_data.requests,
 			peerset: self.peerset_handle.clone(),
 		}, who, response);
 	}
@@ -1731,13 +2223,14 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 	) {
 		self.light_dispatch.on_remote_body_response(LightDispatchIn {
 			behaviour: &mut self.behaviour,
+			requests: &mut self.context_data.requests,
 			peerset: self.peerset_handle.clone(),
 		}, peer, response);
 	}
 
 	fn format_stats(&self) -> String {
 		let mut out = String::new();
-		for (id, stats) in &self.context_data.stats {
+		for (id, stats) in &self.context_data.stats.packets {
 			let _ = writeln!(
 				&mut out,
 				"{}: In: {} bytes ({}), Out: {} bytes ({})",
@@ -1763,8 +2256,9 @@ pub enum CustomMessageOutcome<B: BlockT> {
 
 fn send_request<B: BlockT, H: ExHashT>(
 	behaviour: &mut LegacyProto<Substream<StreamMuxerBox>>,
-	stats: &mut HashMap<&'static str, PacketStats>,
+	stats: &mut Stats,
 	peers: &mut HashMap<PeerId, Peer<B, H>>,
+	requests: &mut RequestsOut,
 	who: &PeerId,
 	mut message: Message<B>,
 ) {
@@ -1779,22 +2273,89 @@ fn send_request<B: BlockT, H: ExHashT>(
 			peer.block_request = Some((time::Instant::now(), r.clone()));
 		}
 	}
-	send_message::<B>(behaviour, stats, who, message)
+	if let GenericMessage::StateRequest(ref mut r) = message {
+		if let Some(ref mut peer) = peers.get_mut(who) {
+			r.id = peer.next_request_id;
+			peer.next_request_id = peer.next_request_id + 1;
+			peer.state_request = Some((time::Instant::now(), r.id));
+		}
+	}
+	match requests.protocol_for(who, &message) {
+		Some(protocol) => {
+			let encoded = message.encode();
+			note_outbound(stats, &message, encoded.len());
+			requests.queue.push_back((who.clone(), protocol, encoded));
+		},
+		None => send_message::<B>(behaviour, stats, who, message),
+	}
 }
 
 fn send_message<B: BlockT>(
 	behaviour: &mut LegacyProto<Substream<StreamMuxerBox>>,
-	stats: &mut HashMap<&'static str, PacketStats>,
+	stats: &mut Stats,
 	who: &PeerId,
 	message: Message<B>,
 ) {
 	let encoded = message.encode();
-	let mut stats = stats.entry(message.id()).or_default();
-	stats.bytes_out += encoded.len() as u64;
-	stats.count_out += 1;
+	note_outbound(stats, &message, encoded.len());
 	behaviour.send_packet(who, encoded);
 }
 
+/// Accounts an outgoing message of `len` encoded bytes.
+fn note_outbound<B: BlockT>(stats: &mut Stats, message: &Message<B>, len: usize) {
+	let protocols = &stats.protocols;
+	account_message(message, len, |protocol, bytes| protocols.inject_outbound(protocol, bytes));
+	let mut stats = stats.packets.entry(message.id()).or_default();
+	stats.bytes_out += len as u64;
+	stats.count_out += 1;
+}
+
+/// Accounts the `len` bytes of an encoded message to the protocols it belongs to.
+fn account_message<B: BlockT>(message: &Message<B>, mut len: usize, mut inject: impl FnMut(BandwidthProtocol, usize)) {
+	let engine_protocol = |engine_id: &ConsensusEngineId| if *engine_id == GRANDPA_ENGINE_ID {
+		BandwidthProtocol::Grandpa
+	} else {
+		BandwidthProtocol::Consensus
+	};
+	let protocol = match message {
+		GenericMessage::Status(_) |
+		GenericMessage::BlockRequest(_) |
+		GenericMessage::BlockResponse(_) |
+		GenericMessage::BlockAnnounce(_) |
+		GenericMessage::FinalityProofRequest(_) |
+		GenericMessage::FinalityProofResponse(_) |
+		GenericMessage::StateRequest(_) |
+		GenericMessage::StateResponse(_) |
+		GenericMessage::WarpSyncRequest(_) |
+		GenericMessage::WarpSyncResponse(_) => BandwidthProtocol::Sync,
+		GenericMessage::RemoteCallRequest(_) |
+		GenericMessage::RemoteCallResponse(_) |
+		GenericMessage::RemoteReadRequest(_) |
+		GenericMessage::RemoteReadResponse(_) |
+		GenericMessage::RemoteHeaderRequest(_) |
+		GenericMessage::RemoteHeaderResponse(_) |
+		GenericMessage::RemoteChangesRequest(_) |
+		GenericMessage::RemoteChangesResponse(_) |
+		GenericMessage::RemoteReadChildRequest(_) => BandwidthProtocol::Light,
+		GenericMessage::Transactions(_) => BandwidthProtocol::Transactions,
+		GenericMessage::Consensus(message) => engine_protocol(&message.engine_id),
+		GenericMessage::ConsensusBatch(messages) => {
+			// Split the batch between the engines, the few remaining bytes go to the first one.
+			for message in messages.iter().skip(1) {
+				let size = cmp::min(message.data.len(), len);
+				len -= size;
+				inject(engine_protocol(&message.engine_id), size);
+			}
+			match messages.first() {
+				Some(message) => engine_protocol(&message.engine_id),
+				None => BandwidthProtocol::Consensus,
+			}
+		},
+		GenericMessage::ChainSpecific(_) => BandwidthProtocol::Other,
+	};
+	inject(protocol, len)
+}
+
 impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> NetworkBehaviour for
 Protocol<B, S, H> {
 	type ProtocolsHandler = <LegacyProto<Substream<StreamMuxerBox>> as NetworkBehaviour>::ProtocolsHandler;
@@ -1846,6 +2407,7 @@ Protocol<B, S, H> {
 				&mut self.behaviour,
 				&mut self.context_data.stats,
 				&mut self.context_data.peers,
+				&mut self.context_data.requests,
 				&id,
 				GenericMessage::BlockRequest(r)
 			)
@@ -1855,6 +2417,7 @@ Protocol<B, S, H> {
 				&mut self.behaviour,
 				&mut self.context_data.stats,
 				&mut self.context_data.peers,
+				&mut self.context_data.requests,
 				&id,
 				GenericMessage::BlockRequest(r)
 			)
@@ -1864,9 +2427,19 @@ Protocol<B, S, H> {
 				&mut self.behaviour,
 				&mut self.context_data.stats,
 				&mut self.context_data.peers,
+				&mut self.context_data.requests,
 				&id,
 				GenericMessage::FinalityProofRequest(r))
 		}
+		if let Some((id, r)) = self.sync.state_request() {
+			send_request(
+				&mut self.behaviour,
+				&mut self.context_data.stats,
+				&mut self.context_data.peers,
+				&mut self.context_data.requests,
+				&id,
+				GenericMessage::StateRequest(r))
+		}
 
 		let event = match self.behaviour.poll(params) {
 			Async::NotReady => return Async::NotReady,
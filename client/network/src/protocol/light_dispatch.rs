@@ -339,6 +339,17 @@ impl<B: BlockT> LightDispatch<B> where
 		self.dispatch(network);
 	}
 
+	/// Call this when a request to a node failed without it being disconnected, e.g. because it
+	/// refused the request while busy. The request is retried, with another node if possible.
+	pub fn on_request_failed(&mut self, network: impl LightDispatchNetwork<B>, peer: PeerId) {
+		if let Some(request) = self.active_peers.remove(&peer) {
+			self.idle_peers.push_back(peer);
+			self.pending_requests.push_front(request);
+		}
+
+		self.dispatch(network);
+	}
+
 	/// Must be called periodically in order to perform maintenance.
 	pub fn maintain_peers(&mut self, mut network: impl LightDispatchNetwork<B>) {
 		let now = Instant::now();
@@ -878,6 +889,32 @@ pub mod tests {
 		assert_disconnected_peer(&network_interface);
 	}
 
+	#[test]
+	fn retries_refused_request_with_another_peer() {
+		let mut light_dispatch = dummy(true);
+		let mut network_interface = DummyNetwork::default();
+		let peer0 = PeerId::random();
+		let peer1 = PeerId::random();
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, 1000);
+
+		light_dispatch.add_request(&mut network_interface, RequestData::RemoteCall(RemoteCallRequest {
+			block: Default::default(),
+			header: dummy_header(),
+			method: "test".into(),
+			call_data: vec![],
+			retry_count: None,
+		}, oneshot::channel().0));
+		assert_eq!(vec![peer0.clone()], light_dispatch.active_peers.keys().cloned().collect::<Vec<_>>());
+
+		// the refusing peer is kept, and the request is sent to the other one.
+		light_dispatch.on_request_failed(&mut network_interface, peer0.clone());
+		assert!(network_interface.disconnected_peers.is_empty());
+		assert_eq!(vec![peer0.clone()], light_dispatch.idle_peers.iter().cloned().collect::<Vec<_>>());
+		assert_eq!(vec![peer1.clone()], light_dispatch.active_peers.keys().cloned().collect::<Vec<_>>());
+		assert!(light_dispatch.pending_requests.is_empty());
+	}
+
 	#[test]
 	fn disconnects_from_peer_on_response_with_wrong_id() {
 		let mut light_dispatch = dummy(true);
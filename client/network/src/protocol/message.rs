@@ -24,7 +24,7 @@ pub use self::generic::{
 	RemoteHeaderRequest, RemoteHeaderResponse,
 	RemoteChangesRequest, RemoteChangesResponse,
 	FinalityProofRequest, FinalityProofResponse,
-	FromBlock, RemoteReadChildRequest,
+	FromBlock, RemoteReadChildRequest, StateRequest, WarpSyncRequest,
 };
 use client_api::StorageProof;
 
@@ -135,6 +135,29 @@ pub struct RemoteReadResponse {
 	pub proof: StorageProof,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+/// State response.
+pub struct StateResponse {
+	/// Id of a request this response was made for.
+	pub id: RequestId,
+	/// Storage entries following the requested start key, in key order.
+	pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+	/// Whether `entries` reach the end of the requested trie.
+	pub complete: bool,
+	/// Proof of the entries against the block state root, `None` if the state of the block is
+	/// not available, e.g. because it was pruned.
+	pub proof: Option<StorageProof>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+/// Warp sync response.
+pub struct WarpSyncResponse {
+	/// Id of a request this response was made for.
+	pub id: RequestId,
+	/// Encoded proof of the authority set changes, `None` if it can't be provided.
+	pub proof: Option<Vec<u8>>,
+}
+
 /// Generic types.
 pub mod generic {
 	use codec::{Encode, Decode, Input, Output};
@@ -143,7 +166,7 @@ pub mod generic {
 	use super::{
 		RemoteReadResponse, Transactions, Direction,
 		RequestId, BlockAttributes, RemoteCallResponse, ConsensusEngineId,
-		BlockState, StorageProof,
+		BlockState, StorageProof, StateResponse, WarpSyncResponse,
 	};
 	/// Consensus is mostly opaque to us
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
@@ -219,6 +242,14 @@ pub mod generic {
 		FinalityProofResponse(FinalityProofResponse<Hash>),
 		/// Batch of consensus protocol messages.
 		ConsensusBatch(Vec<ConsensusMessage>),
+		/// State request.
+		StateRequest(StateRequest<Hash>),
+		/// State response.
+		StateResponse(StateResponse),
+		/// Warp sync request.
+		WarpSyncRequest(WarpSyncRequest<Hash>),
+		/// Warp sync response.
+		WarpSyncResponse(WarpSyncResponse),
 		/// Chain-specific message.
 		#[codec(index = "255")]
 		ChainSpecific(Vec<u8>),
@@ -246,6 +277,10 @@ pub mod generic {
 				Message::FinalityProofRequest(_) => "FinalityProofRequest",
 				Message::FinalityProofResponse(_) => "FinalityProofResponse",
 				Message::ConsensusBatch(_) => "ConsensusBatch",
+				Message::StateRequest(_) => "StateRequest",
+				Message::StateResponse(_) => "StateResponse",
+				Message::WarpSyncRequest(_) => "WarpSyncRequest",
+				Message::WarpSyncResponse(_) => "WarpSyncResponse",
 				Message::ChainSpecific(_) => "ChainSpecific",
 			}
 		}
@@ -372,6 +407,28 @@ pub mod generic {
 		pub keys: Vec<Vec<u8>>,
 	}
 
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	/// Request for a range of storage entries at a given block.
+	pub struct StateRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Block at which to read the state.
+		pub block: H,
+		/// Child storage key of the trie to read, or `None` for the top trie.
+		pub storage_key: Option<Vec<u8>>,
+		/// Entries are returned starting right after this key. Empty to start at the beginning.
+		pub start: Vec<u8>,
+	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	/// Request for the finality proofs of the authority set changes following a block.
+	pub struct WarpSyncRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Finalized block the proof starts at.
+		pub begin: H,
+	}
+
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
 	/// Remote header request.
 	pub struct RemoteHeaderRequest<N> {
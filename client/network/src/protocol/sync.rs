@@ -27,7 +27,7 @@
 //! order to update it.
 //!
 
-use blocks::BlockCollection;
+use blocks::{BlockCollection, DownloadStats};
 use client_api::ClientInfo;
 use sp_blockchain::Error as ClientError;
 use consensus::{BlockOrigin, BlockStatus,
@@ -35,12 +35,14 @@ use consensus::{BlockOrigin, BlockStatus,
 	import_queue::{IncomingBlock, BlockImportResult, BlockImportError}
 };
 use crate::{
-	config::{Roles, BoxFinalityProofRequestBuilder},
+	config::{Roles, BoxFinalityProofRequestBuilder, SyncMode},
 	message::{self, generic::FinalityProofRequest, BlockAnnounce, BlockAttributes, BlockRequest, BlockResponse,
-	FinalityProofResponse},
+	FinalityProofResponse, StateRequest, StateResponse},
 };
 use either::Either;
 use extra_requests::ExtraRequests;
+use state::{StateSync, ImportResult as StateImportResult};
+use super::STATE_REQUEST_VERSION;
 use libp2p::PeerId;
 use log::{debug, trace, warn, info, error};
 use sp_runtime::{
@@ -48,14 +50,31 @@ use sp_runtime::{
 	generic::BlockId,
 	traits::{Block as BlockT, Header, NumberFor, Zero, One, CheckedSub, SaturatedConversion}
 };
-use std::{fmt, ops::Range, collections::{HashMap, HashSet, VecDeque}, sync::Arc};
+use std::{fmt, ops::Range, collections::{HashMap, HashSet, VecDeque}, sync::Arc, time::{Duration, Instant}};
 
 mod blocks;
 mod extra_requests;
+mod state;
 
 /// Maximum blocks to request in a single packet.
 const MAX_BLOCKS_TO_REQUEST: usize = 128;
 
+/// Minimum blocks to request in a single packet, however slow the peer is.
+const MIN_BLOCKS_TO_REQUEST: usize = 8;
+
+/// Blocks to request from a peer before its throughput is known.
+const INITIAL_BLOCKS_TO_REQUEST: usize = 32;
+
+/// Block requests are sized so that peers answer them in about this time.
+const TARGET_BLOCK_RESPONSE_TIME: Duration = Duration::from_secs(2);
+
+/// A block request taking this many times the usual latency of the peer is downloaded again
+/// from another peer.
+const SLOW_REQUEST_FACTOR: u32 = 3;
+
+/// Block requests are not considered slow before this time.
+const MIN_SLOW_REQUEST_TIME: Duration = Duration::from_secs(5);
+
 /// Maximum blocks to store in the import queue.
 const MAX_IMPORTING_BLOCKS: usize = 2048;
 
@@ -72,6 +91,12 @@ const MAJOR_SYNC_BLOCKS: u8 = 5;
 /// Number of recently announced blocks to track for each peer.
 const ANNOUNCE_HISTORY_SIZE: usize = 64;
 
+/// Maximum number of blocks the fast sync target can be behind the best block of our peers.
+///
+/// Pruning nodes only keep the state of the last finalized blocks (256 of them by default), the
+/// state of older blocks can't be downloaded from them.
+const MAX_STATE_SYNC_TARGET_AGE: u32 = 128;
+
 mod rep {
 	use peerset::ReputationChange as Rep;
 	/// Reputation change when a peer sent us a message that led to a
@@ -99,6 +124,9 @@ mod rep {
 
 	/// Reputation change when a peer sent us invlid ancestry result.
 	pub const UNKNOWN_ANCESTOR:Rep = Rep::new(-(1 << 16), "DB Error");
+
+	/// Reputation change for peers which send us a state response we fail to verify.
+	pub const BAD_STATE: Rep = Rep::new(-(1 << 16), "Bad state");
 }
 
 /// The main data structure which contains all the state for a chains
@@ -139,6 +167,22 @@ pub struct ChainSync<B: BlockT> {
 	block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>,
 	/// Maximum number of peers to ask the same blocks in parallel.
 	max_parallel_downloads: u32,
+	/// How the chain is synchronized. Switches from `Fast` to `Full` once the state is downloaded.
+	mode: SyncMode,
+	/// Headers downloaded during fast sync that are held back until a justified header follows them.
+	fast_sync_headers: Vec<IncomingBlock<B>>,
+	/// The last justified header queued for import during fast sync.
+	fast_sync_candidate: Option<(B::Hash, NumberFor<B>)>,
+	/// The block to download the state of, once header download is over. The state is only
+	/// downloaded once the client has imported and finalized it, i.e. verified its justification.
+	fast_sync_target: Option<(B::Hash, NumberFor<B>)>,
+	/// Set once headers are imported past the last justified one, which is too old to be a
+	/// target. The target is then the next block finalized within `MAX_STATE_SYNC_TARGET_AGE`.
+	fast_sync_await_finality: bool,
+	/// State download in progress.
+	state_sync: Option<StateSync<B>>,
+	/// Peers which told us they don't have the state of the fast sync target.
+	state_unavailable: HashSet<PeerId>,
 }
 
 /// All the data we have about a Peer that we are trying to sync with
@@ -156,7 +200,11 @@ pub struct PeerSync<B: BlockT> {
 	pub state: PeerSyncState<B>,
 	/// A queue of blocks that this peer has announced to us, should only
 	/// contain `ANNOUNCE_HISTORY_SIZE` entries.
-	pub recently_announced: VecDeque<B::Hash>
+	pub recently_announced: VecDeque<B::Hash>,
+	/// Observed block download performance, used to size and schedule block requests.
+	pub download_stats: DownloadStats,
+	/// The protocol version of the peer.
+	pub protocol_version: u32,
 }
 
 /// The sync status of a peer we are trying to sync with
@@ -193,7 +241,9 @@ pub enum PeerSyncState<B: BlockT> {
 	/// Downloading justification for given block hash.
 	DownloadingJustification(B::Hash),
 	/// Downloading finality proof for given block hash.
-	DownloadingFinalityProof(B::Hash)
+	DownloadingFinalityProof(B::Hash),
+	/// Downloading state.
+	DownloadingState,
 }
 
 impl<B: BlockT> PeerSyncState<B> {
@@ -295,10 +345,20 @@ impl<B: BlockT> ChainSync<B> {
 		request_builder: Option<BoxFinalityProofRequestBuilder<B>>,
 		block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>,
 		max_parallel_downloads: u32,
+		mode: SyncMode,
 	) -> Self {
 		let mut required_block_attributes = BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION;
 
-		if role.is_full() {
+		// Fast sync only starts from scratch, or resumes over headers imported without state.
+		let mode = match mode {
+			SyncMode::Fast if role.is_full() && (
+				info.chain.best_number.is_zero() ||
+				client.block_status(&BlockId::Hash(info.chain.best_hash)).ok() == Some(BlockStatus::InChainPruned)
+			) => SyncMode::Fast,
+			_ => SyncMode::Full,
+		};
+
+		if role.is_full() && mode == SyncMode::Full {
 			required_block_attributes |= BlockAttributes::BODY
 		}
 
@@ -319,6 +379,13 @@ impl<B: BlockT> ChainSync<B> {
 			is_idle: false,
 			block_announce_validator,
 			max_parallel_downloads,
+			mode,
+			fast_sync_headers: Vec::new(),
+			fast_sync_candidate: None,
+			fast_sync_target: None,
+			fast_sync_await_finality: false,
+			state_sync: None,
+			state_unavailable: HashSet::new(),
 		}
 	}
 
@@ -333,7 +400,10 @@ impl<B: BlockT> ChainSync<B> {
 	pub fn status(&self) -> Status<B> {
 		let best_seen = self.peers.values().max_by_key(|p| p.best_number).map(|p| p.best_number);
 		let sync_state =
-			if let Some(n) = best_seen {
+			if self.fast_sync_target.is_some() {
+				// Blocks can not be executed until the state is downloaded.
+				SyncState::Downloading
+			} else if let Some(n) = best_seen {
 				// A chain is classified as downloading if the provided best block is
 				// more than `MAJOR_SYNC_BLOCKS` behind the best queued block.
 				if n > self.best_queued_number && n - self.best_queued_number > MAJOR_SYNC_BLOCKS.into() {
@@ -356,9 +426,13 @@ impl<B: BlockT> ChainSync<B> {
 	/// Handle a new connected peer.
 	///
 	/// Call this method whenever we connect to a new peer.
-	pub fn new_peer(&mut self, who: PeerId, best_hash: B::Hash, best_number: NumberFor<B>)
-		-> Result<Option<BlockRequest<B>>, BadPeer>
-	{
+	pub fn new_peer(
+		&mut self,
+		who: PeerId,
+		best_hash: B::Hash,
+		best_number: NumberFor<B>,
+		protocol_version: u32,
+	) -> Result<Option<BlockRequest<B>>, BadPeer> {
 		// There is nothing sync can get from the node that has no blockchain data.
 		match self.block_status(&best_hash) {
 			Err(e) => {
@@ -389,7 +463,9 @@ impl<B: BlockT> ChainSync<B> {
 						best_hash,
 						best_number,
 						state: PeerSyncState::Available,
-						recently_announced: Default::default()
+						recently_announced: Default::default(),
+						download_stats: DownloadStats::new(INITIAL_BLOCKS_TO_REQUEST),
+						protocol_version,
 					});
 					return Ok(None)
 				}
@@ -403,6 +479,8 @@ impl<B: BlockT> ChainSync<B> {
 						best_number,
 						state: PeerSyncState::Available,
 						recently_announced: Default::default(),
+						download_stats: DownloadStats::new(INITIAL_BLOCKS_TO_REQUEST),
+						protocol_version,
 					});
 					self.is_idle = false;
 					return Ok(None)
@@ -424,7 +502,9 @@ impl<B: BlockT> ChainSync<B> {
 						common_best,
 						AncestorSearchState::ExponentialBackoff(One::one())
 					),
-					recently_announced: Default::default()
+					recently_announced: Default::default(),
+					download_stats: DownloadStats::new(INITIAL_BLOCKS_TO_REQUEST),
+					protocol_version,
 				});
 				self.is_idle = false;
 
@@ -438,6 +518,8 @@ impl<B: BlockT> ChainSync<B> {
 					best_number,
 					state: PeerSyncState::Available,
 					recently_announced: Default::default(),
+					download_stats: DownloadStats::new(INITIAL_BLOCKS_TO_REQUEST),
+					protocol_version,
 				});
 				self.is_idle = false;
 				Ok(None)
@@ -566,15 +648,50 @@ impl<B: BlockT> ChainSync<B> {
 	}
 
 	/// Get an iterator over all block requests of all peers.
+	///
+	/// The fastest peers are asked first, so that they get the ranges needed soonest, and each
+	/// request is sized after the throughput of the peer. Ranges that slow peers are taking too
+	/// long to deliver are requested again from available peers.
 	pub fn block_requests(&mut self) -> impl Iterator<Item = (PeerId, BlockRequest<B>)> + '_ {
-		if self.is_idle {
+		if self.fast_sync_target.is_some() {
+			return Either::Left(std::iter::empty())
+		}
+		// Pending requests may become slow, their range is then requested from another peer.
+		let awaiting_blocks = self.peers.values().any(|peer| match peer.state {
+			PeerSyncState::DownloadingNew(_) => true,
+			_ => false,
+		});
+		if self.is_idle && !awaiting_blocks {
 			return Either::Left(std::iter::empty())
 		}
-		if self.queue_blocks.len() > MAX_IMPORTING_BLOCKS {
+		// Downloads go on while blocks are imported, up to what the import queue can take.
+		let mut import_budget = MAX_IMPORTING_BLOCKS
+			.saturating_sub(self.queue_blocks.len())
+			.saturating_sub(self.blocks.pending_blocks());
+		if import_budget == 0 {
 			trace!(target: "sync", "Too many blocks in the queue.");
 			return Either::Left(std::iter::empty())
 		}
 		let major_sync = self.status().state == SyncState::Downloading;
+		let now = Instant::now();
+		let mut slow_ranges: Vec<NumberFor<B>> = self.peers.iter()
+			.filter_map(|(id, peer)| match peer.state {
+				PeerSyncState::DownloadingNew(start)
+					if peer.download_stats.is_slow(now, SLOW_REQUEST_FACTOR, MIN_SLOW_REQUEST_TIME) =>
+				{
+					trace!(target: "sync", "Peer {} is slow to send blocks from #{}", id, start);
+					Some(start)
+				},
+				_ => None,
+			})
+			.collect();
+		slow_ranges.sort();
+		let mut peer_ids: Vec<PeerId> = self.peers.keys().cloned().collect();
+		{
+			let speed = |id: &PeerId| self.peers[id].download_stats.blocks_per_sec();
+			peer_ids.sort_by(|a, b| speed(b).partial_cmp(&speed(a)).unwrap_or(std::cmp::Ordering::Equal));
+		}
+		let peers = &mut self.peers;
 		let blocks = &mut self.blocks;
 		let attrs = &self.required_block_attributes;
 		let fork_targets = &mut self.fork_targets;
@@ -584,13 +701,14 @@ impl<B: BlockT> ChainSync<B> {
 		let client = &self.client;
 		let queue = &self.queue_blocks;
 		let max_parallel = if major_sync { 1 } else { self.max_parallel_downloads };
-		let iter = self.peers.iter_mut().filter_map(move |(id, peer)| {
+		let iter = peer_ids.into_iter().filter_map(move |id| {
+			let peer = peers.get_mut(&id)?;
 			if !peer.state.is_available() {
 				trace!(target: "sync", "Peer {} is busy", id);
 				return None
 			}
 			if let Some((hash, req)) = fork_sync_request(
-				id,
+				&id,
 				fork_targets,
 				best_queued,
 				last_finalized,
@@ -604,16 +722,32 @@ impl<B: BlockT> ChainSync<B> {
 				trace!(target: "sync", "Downloading fork {:?} from {}", hash, id);
 				peer.state = PeerSyncState::DownloadingStale(hash);
 				have_requests = true;
-				Some((id.clone(), req))
+				Some((id, req))
+			} else if let Some((range, req)) = slow_range_request(
+				&id,
+				peer,
+				blocks,
+				attrs,
+				&mut slow_ranges,
+				last_finalized,
+			) {
+				trace!(target: "sync", "Requesting blocks {:?} from {} again", range, id);
+				peer.state = PeerSyncState::DownloadingNew(range.start);
+				peer.download_stats.on_request(now);
+				have_requests = true;
+				Some((id, req))
 			} else if let Some((range, req)) = peer_block_request(
-				id,
+				&id,
 				peer,
 				blocks,
 				attrs,
 				max_parallel,
-				last_finalized
+				last_finalized,
+				std::cmp::min(peer.download_stats.request_size(), import_budget),
 			) {
+				import_budget = import_budget.saturating_sub((range.end - range.start).saturated_into::<usize>());
 				peer.state = PeerSyncState::DownloadingNew(range.start);
+				peer.download_stats.on_request(now);
 				trace!(
 					target: "sync",
 					"New block request for {}, (best:{}, common:{}) {:?}",
@@ -623,7 +757,7 @@ impl<B: BlockT> ChainSync<B> {
 					req,
 				);
 				have_requests = true;
-				Some((id.clone(), req))
+				Some((id, req))
 			} else {
 				None
 			}
@@ -634,6 +768,26 @@ impl<B: BlockT> ChainSync<B> {
 		Either::Right(iter)
 	}
 
+	/// Get a state request, if any.
+	pub fn state_request(&mut self) -> Option<(PeerId, StateRequest<B::Hash>)> {
+		let state_sync = self.state_sync.as_ref()?;
+		if self.peers.values().any(|p| p.state == PeerSyncState::DownloadingState) {
+			// Only one pending state request is allowed.
+			return None
+		}
+		let target = state_sync.target_block_num();
+		let unavailable = &self.state_unavailable;
+		let (id, peer) = self.peers.iter_mut()
+			.find(|(id, peer)| {
+				peer.state.is_available() &&
+					can_serve_state(peer, target) &&
+					!unavailable.contains(id)
+			})?;
+		trace!(target: "sync", "New state request to {}", id);
+		peer.state = PeerSyncState::DownloadingState;
+		Some((id.clone(), state_sync.next_request()))
+	}
+
 	/// Handle a response from the remote to a block request that we made.
 	///
 	/// `request` must be the original request that triggered `response`.
@@ -643,6 +797,9 @@ impl<B: BlockT> ChainSync<B> {
 	pub fn on_block_data
 		(&mut self, who: PeerId, request: BlockRequest<B>, response: BlockResponse<B>) -> Result<OnBlockData<B>, BadPeer>
 	{
+		let fast_sync = self.mode == SyncMode::Fast;
+		let hold = fast_sync && !self.fast_sync_await_finality;
+		let mut hold_headers = false;
 		let new_blocks: Vec<IncomingBlock<B>> =
 			if let Some(peer) = self.peers.get_mut(&who) {
 				let mut blocks = response.blocks;
@@ -653,9 +810,23 @@ impl<B: BlockT> ChainSync<B> {
 				self.is_idle = false;
 				match &mut peer.state {
 					PeerSyncState::DownloadingNew(start_block) => {
+						peer.download_stats.on_response(
+							Instant::now(),
+							blocks.len(),
+							TARGET_BLOCK_RESPONSE_TIME,
+							MIN_BLOCKS_TO_REQUEST,
+							MAX_BLOCKS_TO_REQUEST,
+						);
 						self.blocks.clear_peer_download(&who);
-						self.blocks.insert(*start_block, blocks, who);
+						let queue_blocks = &self.queue_blocks;
+						if blocks.first().map_or(false, |b| queue_blocks.contains(&b.hash)) {
+							// Another peer delivered the range first.
+							trace!(target: "sync", "Ignored blocks from #{} already queued", start_block);
+						} else {
+							self.blocks.insert(*start_block, blocks, who);
+						}
 						peer.state = PeerSyncState::Available;
+						hold_headers = hold;
 						self.blocks
 							.drain(self.best_queued_number + One::one())
 							.into_iter()
@@ -666,7 +837,7 @@ impl<B: BlockT> ChainSync<B> {
 									body: block_data.block.body,
 									justification: block_data.block.justification,
 									origin: block_data.origin,
-									allow_missing_state: false,
+									allow_missing_state: fast_sync,
 									import_existing: false,
 								}
 							}).collect()
@@ -741,12 +912,19 @@ impl<B: BlockT> ChainSync<B> {
 
 					| PeerSyncState::Available
 					| PeerSyncState::DownloadingJustification(..)
-					| PeerSyncState::DownloadingFinalityProof(..) => Vec::new()
+					| PeerSyncState::DownloadingFinalityProof(..)
+					| PeerSyncState::DownloadingState => Vec::new()
 				}
 			} else {
 				Vec::new()
 			};
 
+		let new_blocks = if hold_headers {
+			self.on_fast_sync_headers(new_blocks)
+		} else {
+			new_blocks
+		};
+
 		let is_recent = new_blocks.first()
 			.map(|block| {
 				self.peers.iter().any(|(_, peer)| peer.recently_announced.contains(&block.hash))
@@ -770,6 +948,52 @@ impl<B: BlockT> ChainSync<B> {
 		Ok(OnBlockData::Import(origin, new_blocks))
 	}
 
+	/// Handle a response from the remote to a state request that we made.
+	pub fn on_state_data(&mut self, who: &PeerId, response: StateResponse) -> Result<(), BadPeer> {
+		if let Some(peer) = self.peers.get_mut(who) {
+			if let PeerSyncState::DownloadingState = peer.state {
+				peer.state = PeerSyncState::Available;
+			}
+		}
+
+		let import_result = if let Some(state_sync) = &mut self.state_sync {
+			trace!(
+				target: "sync",
+				"Importing state data from {} with {} entries ({} bytes so far)",
+				who,
+				response.entries.len(),
+				state_sync.imported_bytes(),
+			);
+			state_sync.import(response)
+		} else {
+			debug!(target: "sync", "Ignored obsolete state response from {}", who);
+			return Ok(())
+		};
+
+		match import_result {
+			StateImportResult::Import(hash, top, children) => {
+				info!("Importing downloaded state of block {}", hash);
+				match self.client.import_state(&hash, top, children) {
+					Ok(()) => self.switch_to_full_sync(),
+					Err(e) => {
+						warn!(target: "sync", "Error importing downloaded state of {}: {:?}", hash, e);
+						self.state_sync = None;
+						self.start_state_sync();
+					}
+				}
+				Ok(())
+			}
+			StateImportResult::Continue => Ok(()),
+			StateImportResult::Unavailable => {
+				debug!(target: "sync", "State of the fast sync target not available from {}", who);
+				self.state_unavailable.insert(who.clone());
+				self.on_state_unavailable();
+				Ok(())
+			}
+			StateImportResult::BadResponse => Err(BadPeer(who.clone(), rep::BAD_STATE)),
+		}
+	}
+
 	/// Handle a response from the remote to a justification request that we made.
 	///
 	/// `request` must be the original request that triggered `response`.
@@ -950,6 +1174,7 @@ impl<B: BlockT> ChainSync<B> {
 			self.queue_blocks.remove(&hash);
 		}
 
+		self.start_state_sync();
 		self.is_idle = false;
 		output.into_iter()
 	}
@@ -986,6 +1211,12 @@ impl<B: BlockT> ChainSync<B> {
 		if let Err(err) = r {
 			warn!(target: "sync", "Error cleaning up pending extra justification data requests: {:?}", err);
 		}
+
+		if self.fast_sync_await_finality && self.is_recent_state(number) {
+			info!("Downloading state of finalized block #{} ({})", number, hash);
+			self.fast_sync_await_finality = false;
+			self.set_fast_sync_target(*hash, number);
+		}
 	}
 
 	/// Called when a block has been queued for import.
@@ -1127,20 +1358,231 @@ impl<B: BlockT> ChainSync<B> {
 		OnBlockAnnounce::Nothing
 	}
 
+	/// Called with headers downloaded during fast sync, before they are queued for import.
+	///
+	/// Headers are only imported up to the last justified one, so that the state we download
+	/// later on is the state of a finalized block. Once the headers have caught up with the
+	/// network, the state download is started as soon as the justified header is imported and
+	/// finalized. Returns the headers to import.
+	fn on_fast_sync_headers(&mut self, blocks: Vec<IncomingBlock<B>>) -> Vec<IncomingBlock<B>> {
+		if self.fast_sync_target.is_some() {
+			// Late response, header download is over until the state is downloaded.
+			return Vec::new()
+		}
+		if let Some((hash, number)) = blocks.last().and_then(|b| b.header.as_ref().map(|h| (b.hash, *h.number()))) {
+			self.on_block_queued(&hash, number);
+		}
+
+		self.fast_sync_headers.extend(blocks);
+		let mut ready = match self.fast_sync_headers.iter().rposition(|b| b.justification.is_some()) {
+			Some(index) => {
+				let held = self.fast_sync_headers.split_off(index + 1);
+				std::mem::replace(&mut self.fast_sync_headers, held)
+			}
+			None => Vec::new(),
+		};
+		if let Some((hash, number)) = ready.last().and_then(|b| b.header.as_ref().map(|h| (b.hash, *h.number()))) {
+			self.fast_sync_candidate = Some((hash, number));
+		}
+
+		let caught_up = self.status().state == SyncState::Idle;
+		if caught_up || self.fast_sync_headers.len() >= MAX_IMPORTING_BLOCKS {
+			match self.fast_sync_candidate.take() {
+				Some((hash, number)) if self.is_recent_state(number) => {
+					info!("Header sync complete, downloading state of block #{} ({})", number, hash);
+					self.fast_sync_headers.clear();
+					self.set_fast_sync_target(hash, number);
+				}
+				Some((hash, number)) => {
+					// Peers have likely pruned the state of the last justified block. Headers are
+					// imported as they come until a recent enough block is finalized.
+					debug!(
+						target: "sync",
+						"Last justified block #{} ({}) too old, waiting for a newer finalized block",
+						number,
+						hash,
+					);
+					self.fast_sync_await_finality = true;
+					ready.extend(self.fast_sync_headers.drain(..));
+				}
+				None => {
+					info!("No finalized block to download the state of, switching to full sync");
+					self.fast_sync_headers.clear();
+					self.switch_to_full_sync();
+				}
+			}
+		}
+
+		ready
+	}
+
+	/// Whether the state of the block with the given number is likely to be kept by our peers.
+	fn is_recent_state(&self, number: NumberFor<B>) -> bool {
+		let best_seen = self.peers.values().map(|p| p.best_number).max().unwrap_or_else(Zero::zero);
+		number + MAX_STATE_SYNC_TARGET_AGE.into() >= best_seen
+	}
+
+	/// Stop downloading headers and download the state of the given block.
+	fn set_fast_sync_target(&mut self, hash: B::Hash, number: NumberFor<B>) {
+		self.fast_sync_target = Some((hash, number));
+		self.state_sync = None;
+		self.state_unavailable.clear();
+		self.blocks.clear();
+		self.start_state_sync();
+	}
+
+	/// Called when a peer doesn't have the state of the fast sync target.
+	///
+	/// Once no peer is left to download it from, the target has likely been pruned by all of
+	/// them. Headers are then downloaded again until a more recent block is finalized.
+	fn on_state_unavailable(&mut self) {
+		let target = match self.fast_sync_target {
+			Some((_, number)) => number,
+			None => return,
+		};
+		let unavailable = &self.state_unavailable;
+		let available = self.peers.iter()
+			.any(|(id, peer)| can_serve_state(peer, target) && !unavailable.contains(id));
+		if available {
+			return
+		}
+		info!("State of #{} not available from any peer, waiting for another finalized block", target);
+		self.fast_sync_target = None;
+		self.fast_sync_await_finality = true;
+		self.state_sync = None;
+		self.state_unavailable.clear();
+		self.is_idle = false;
+	}
+
+	/// Start downloading the state of the fast sync target, once its header has been imported.
+	///
+	/// The justification of the target is only verified on import, so the state downloaded is
+	/// the state of the last block finalized by the client once the target is imported. A target
+	/// imported without being finalized is dropped, and headers are downloaded again until a
+	/// recent block is finalized.
+	fn start_state_sync(&mut self) {
+		let (hash, number) = match self.fast_sync_target {
+			Some(target) if self.state_sync.is_none() && !self.queue_blocks.contains(&target.0) =>
+				target,
+			_ => return,
+		};
+		match self.client.header(&BlockId::Hash(hash)) {
+			Ok(Some(_)) => {}
+			Ok(None) => {
+				trace!(target: "sync", "Waiting for fast sync target {} to be imported", hash);
+				return
+			}
+			Err(e) => {
+				debug!(target: "sync", "Error reading fast sync target {}: {:?}", hash, e);
+				return
+			}
+		}
+
+		let info = self.client.info().chain;
+		if info.finalized_number < number {
+			debug!(
+				target: "sync",
+				"Fast sync target #{} ({}) imported but not finalized, waiting for another one",
+				number,
+				hash,
+			);
+			self.fast_sync_target = None;
+			self.fast_sync_await_finality = true;
+			self.reset_best_queued();
+			return
+		}
+
+		let (hash, number) = (info.finalized_hash, info.finalized_number);
+		match self.client.header(&BlockId::Hash(hash)) {
+			Ok(Some(header)) => {
+				debug!(target: "sync", "Starting state sync for #{} ({})", number, hash);
+				self.fast_sync_target = Some((hash, number));
+				self.state_sync = Some(StateSync::new(self.client.clone(), header));
+			}
+			Ok(None) => debug!(target: "sync", "Finalized block {} not found", hash),
+			Err(e) => debug!(target: "sync", "Error reading finalized block {}: {:?}", hash, e),
+		}
+	}
+
+	/// Stop fast sync and continue by downloading and executing full blocks on top of our best block.
+	fn switch_to_full_sync(&mut self) {
+		self.mode = SyncMode::Full;
+		if self.role.is_full() {
+			self.required_block_attributes |= BlockAttributes::BODY;
+		}
+		self.fast_sync_headers.clear();
+		self.fast_sync_candidate = None;
+		self.fast_sync_target = None;
+		self.fast_sync_await_finality = false;
+		self.state_sync = None;
+		self.state_unavailable.clear();
+		self.reset_best_queued();
+		debug!(target: "sync", "Switched to full sync at {} ({})", self.best_queued_number, self.best_queued_hash);
+	}
+
+	/// Drop the blocks being downloaded and download new blocks on top of our best block.
+	fn reset_best_queued(&mut self) {
+		self.blocks.clear();
+		let info = self.client.info();
+		self.best_queued_hash = info.chain.best_hash;
+		self.best_queued_number = info.chain.best_number;
+		for peer in self.peers.values_mut() {
+			if peer.common_number > self.best_queued_number {
+				peer.common_number = self.best_queued_number;
+			}
+			if let PeerSyncState::DownloadingNew(_) = peer.state {
+				peer.state = PeerSyncState::Available;
+				peer.download_stats.clear_request();
+			}
+		}
+		self.is_idle = false;
+	}
+
 	/// Call when a peer has disconnected.
 	pub fn peer_disconnected(&mut self, who: PeerId) {
 		self.blocks.clear_peer_download(&who);
 		self.peers.remove(&who);
+		self.state_unavailable.remove(&who);
 		self.extra_justifications.peer_disconnected(&who);
 		self.extra_finality_proofs.peer_disconnected(&who);
 		self.is_idle = false;
 	}
 
+	/// Call when a request to a peer failed without the peer being disconnected, e.g. because
+	/// it refused the request while busy.
+	///
+	/// Downloads are retried from any available peer. Returns the request to retry an ancestor
+	/// search with, as it can only be done with the same peer.
+	pub fn on_request_failed(&mut self, who: &PeerId) -> Option<BlockRequest<B>> {
+		let peer = self.peers.get_mut(who)?;
+		self.is_idle = false;
+		match peer.state {
+			PeerSyncState::AncestorSearch(num, _) => return Some(ancestry_request::<B>(num)),
+			PeerSyncState::DownloadingNew(_) | PeerSyncState::DownloadingStale(_) =>
+				self.blocks.clear_peer_download(who),
+			PeerSyncState::DownloadingJustification(_) => {
+				self.extra_justifications.on_response::<()>(who.clone(), None);
+			},
+			PeerSyncState::DownloadingFinalityProof(_) => {
+				self.extra_finality_proofs.on_response::<()>(who.clone(), None);
+			},
+			PeerSyncState::DownloadingState | PeerSyncState::Available => {},
+		}
+		peer.state = PeerSyncState::Available;
+		peer.download_stats.clear_request();
+		None
+	}
+
 	/// Restart the sync process.
 	fn restart<'a>(&'a mut self) -> impl Iterator<Item = Result<(PeerId, BlockRequest<B>), BadPeer>> + 'a
 	{
 		self.queue_blocks.clear();
 		self.blocks.clear();
+		self.fast_sync_headers.clear();
+		if self.state_sync.is_none() {
+			// Resume header download if the fast sync target failed to import.
+			self.fast_sync_target = None;
+		}
 		let info = self.client.info();
 		self.best_queued_hash = info.chain.best_hash;
 		self.best_queued_number = std::cmp::max(info.chain.best_number, self.best_imported_number);
@@ -1148,7 +1590,13 @@ impl<B: BlockT> ChainSync<B> {
 		debug!(target:"sync", "Restarted with {} ({})", self.best_queued_number, self.best_queued_hash);
 		let old_peers = std::mem::replace(&mut self.peers, HashMap::new());
 		old_peers.into_iter().filter_map(move |(id, p)| {
-			match self.new_peer(id.clone(), p.best_hash, p.best_number) {
+			let result = self.new_peer(id.clone(), p.best_hash, p.best_number, p.protocol_version);
+			if let Some(peer) = self.peers.get_mut(&id) {
+				// Keep what was learned about the peer, but not the dropped request.
+				peer.download_stats = p.download_stats;
+				peer.download_stats.clear_request();
+			}
+			match result {
 				Ok(None) => None,
 				Ok(Some(x)) => Some(Ok((id, x))),
 				Err(e) => Some(Err(e))
@@ -1175,6 +1623,11 @@ impl<B: BlockT> ChainSync<B> {
 	}
 }
 
+/// Whether the peer can be asked for the state of the block with the given number.
+fn can_serve_state<B: BlockT>(peer: &PeerSync<B>, number: NumberFor<B>) -> bool {
+	peer.protocol_version >= STATE_REQUEST_VERSION && peer.best_number >= number
+}
+
 /// Request the ancestry for a block. Sends a request for header and justification for the given
 /// block number. Used during ancestry search.
 fn ancestry_request<B: BlockT>(block: NumberFor<B>) -> BlockRequest<B> {
@@ -1248,7 +1701,7 @@ fn handle_ancestor_search_state<B: BlockT>(
 	}
 }
 
-/// Get a new block request for the peer if any.
+/// Get a new block request of at most `count` blocks for the peer if any.
 fn peer_block_request<B: BlockT>(
 	id: &PeerId,
 	peer: &PeerSync<B>,
@@ -1256,32 +1709,60 @@ fn peer_block_request<B: BlockT>(
 	attrs: &message::BlockAttributes,
 	max_parallel_downloads: u32,
 	finalized: NumberFor<B>,
+	count: usize,
 ) -> Option<(Range<NumberFor<B>>, BlockRequest<B>)> {
-	if peer.common_number < finalized {
+	if peer.common_number < finalized || count == 0 {
 		return None;
 	}
 	if let Some(range) = blocks.needed_blocks(
 		id.clone(),
-		MAX_BLOCKS_TO_REQUEST,
+		count,
 		peer.best_number,
 		peer.common_number,
 		max_parallel_downloads,
 		MAX_DOWNLOAD_AHEAD,
 	) {
-		let request = message::generic::BlockRequest {
-			id: 0,
-			fields: attrs.clone(),
-			from: message::FromBlock::Number(range.start),
-			to: None,
-			direction: message::Direction::Ascending,
-			max: Some((range.end - range.start).saturated_into::<u32>())
-		};
+		let request = range_request(attrs, &range);
 		Some((range, request))
 	} else {
 		None
 	}
 }
 
+/// Get a request for a range that a slow peer is taking too long to deliver, if any.
+///
+/// The range is removed from `slow_ranges` so that it is only requested again once.
+fn slow_range_request<B: BlockT>(
+	id: &PeerId,
+	peer: &PeerSync<B>,
+	blocks: &mut BlockCollection<B>,
+	attrs: &message::BlockAttributes,
+	slow_ranges: &mut Vec<NumberFor<B>>,
+	finalized: NumberFor<B>,
+) -> Option<(Range<NumberFor<B>>, BlockRequest<B>)> {
+	if peer.common_number < finalized {
+		return None;
+	}
+	let index = slow_ranges.iter().position(|start| *start <= peer.best_number)?;
+	let start = slow_ranges.remove(index);
+	// At most one more peer downloads the range besides the slow one.
+	let range = blocks.rerequest(id.clone(), start, peer.best_number, 2)?;
+	let request = range_request(attrs, &range);
+	Some((range, request))
+}
+
+/// Block request for the given range of blocks.
+fn range_request<B: BlockT>(attrs: &message::BlockAttributes, range: &Range<NumberFor<B>>) -> BlockRequest<B> {
+	message::generic::BlockRequest {
+		id: 0,
+		fields: attrs.clone(),
+		from: message::FromBlock::Number(range.start),
+		to: None,
+		direction: message::Direction::Ascending,
+		max: Some((range.end - range.start).saturated_into::<u32>())
+	}
+}
+
 /// Get pending fork sync targets for a peer.
 fn fork_sync_request<B: BlockT>(
 	id: &PeerId,
@@ -1322,3 +1803,280 @@ fn fork_sync_request<B: BlockT>(
 	}
 	None
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use super::message::generic::BlockData;
+	use consensus::block_validation::DefaultBlockAnnounceValidator;
+	use test_client::{ClientExt, runtime::Block};
+
+	fn build_blocks(count: usize) -> Vec<Block> {
+		let client = test_client::new();
+		(0..count).map(|_| {
+			let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+			client.import(BlockOrigin::Own, block.clone()).unwrap();
+			block
+		}).collect()
+	}
+
+	fn fast_sync(client: Arc<test_client::Client<test_client::Backend>>) -> ChainSync<Block> {
+		let info = client.info();
+		ChainSync::new(
+			Roles::FULL,
+			client.clone(),
+			&info,
+			None,
+			Box::new(DefaultBlockAnnounceValidator::new(client)),
+			1,
+			SyncMode::Fast,
+		)
+	}
+
+	fn add_peer(sync: &mut ChainSync<Block>, best: &Block, protocol_version: u32) -> PeerId {
+		let who = PeerId::random();
+		let hash = best.header.hash();
+		let request = sync.new_peer(who.clone(), hash, best.header.number, protocol_version).unwrap();
+		assert!(request.is_none());
+		who
+	}
+
+	/// Answers the next block request with the headers of `blocks`, the one numbered
+	/// `justified` carrying a justification. Returns the headers queued for import.
+	fn download_headers(sync: &mut ChainSync<Block>, blocks: &[Block], justified: u64) -> Vec<u64> {
+		let (who, request) = sync.block_requests().next().unwrap();
+		assert_eq!(request.fields, BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION);
+		let response = BlockResponse::<Block> {
+			id: request.id,
+			blocks: blocks.iter().map(|block| BlockData {
+				hash: block.header.hash(),
+				header: Some(block.header.clone()),
+				body: None,
+				receipt: None,
+				message_queue: None,
+				justification: Some(Vec::new()).filter(|_| block.header.number == justified),
+			}).collect(),
+		};
+		match sync.on_block_data(who, request, response).unwrap() {
+			OnBlockData::Import(_, blocks) => blocks.iter()
+				.map(|block| block.header.as_ref().unwrap().number)
+				.collect(),
+			OnBlockData::Request(..) => panic!("Unexpected request"),
+		}
+	}
+
+	/// Imports the blocks into the client of `sync` and reports them as processed. The one
+	/// numbered `justified` is finalized, as if its justification was valid.
+	fn import_blocks(
+		sync: &mut ChainSync<Block>,
+		client: &test_client::Client<test_client::Backend>,
+		blocks: &[Block],
+		justified: u64,
+	) {
+		let results = blocks.iter().map(|block| {
+			if block.header.number == justified {
+				client.import_justified(BlockOrigin::NetworkInitialSync, block.clone(), Vec::new())
+					.unwrap();
+			} else {
+				client.import(BlockOrigin::NetworkInitialSync, block.clone()).unwrap();
+			}
+			let result = BlockImportResult::ImportedUnknown(block.header.number, Default::default(), None);
+			(Ok(result), block.header.hash())
+		}).collect();
+		assert!(sync.on_blocks_processed(blocks.len(), blocks.len(), results).next().is_none());
+	}
+
+	fn unavailable() -> StateResponse {
+		StateResponse { id: 0, entries: Vec::new(), complete: false, proof: None }
+	}
+
+	#[test]
+	fn downloads_state_of_justified_block_from_peers_supporting_it() {
+		let blocks = build_blocks(3);
+		let client = Arc::new(test_client::new());
+		let mut sync = fast_sync(client.clone());
+		let _old = add_peer(&mut sync, &blocks[2], STATE_REQUEST_VERSION - 1);
+		let new = add_peer(&mut sync, &blocks[2], STATE_REQUEST_VERSION);
+
+		// headers are only imported up to the justified one.
+		assert_eq!(download_headers(&mut sync, &blocks, 2), vec![1, 2]);
+		assert_eq!(sync.fast_sync_target, Some((blocks[1].header.hash(), 2)));
+		assert!(sync.block_requests().next().is_none());
+
+		// the state is downloaded once the header of the target is imported.
+		assert!(sync.state_request().is_none());
+		import_blocks(&mut sync, &client, &blocks[..2], 2);
+		let (who, request) = sync.state_request().unwrap();
+		assert_eq!(who, new);
+		assert_eq!(request.block, blocks[1].header.hash());
+		assert!(request.start.is_empty());
+
+		// one request at a time.
+		assert!(sync.state_request().is_none());
+	}
+
+	#[test]
+	fn state_of_justified_block_is_not_downloaded_before_it_is_finalized() {
+		let blocks = build_blocks(3);
+		let client = Arc::new(test_client::new());
+		let mut sync = fast_sync(client.clone());
+		add_peer(&mut sync, &blocks[2], STATE_REQUEST_VERSION);
+
+		assert_eq!(download_headers(&mut sync, &blocks, 2), vec![1, 2]);
+		assert_eq!(sync.fast_sync_target, Some((blocks[1].header.hash(), 2)));
+
+		// the justification didn't finalize the target on import, its state is not downloaded.
+		import_blocks(&mut sync, &client, &blocks[..2], 0);
+		assert!(sync.state_request().is_none());
+		assert_eq!(sync.fast_sync_target, None);
+		assert!(sync.fast_sync_await_finality);
+		assert_eq!(sync.best_queued_number, 2);
+
+		// headers are downloaded again until a block is finalized.
+		assert!(sync.block_requests().next().is_some());
+		import_blocks(&mut sync, &client, &blocks[2..], 3);
+		sync.on_block_finalized(&blocks[2].header.hash(), 3);
+		assert_eq!(sync.state_request().unwrap().1.block, blocks[2].header.hash());
+	}
+
+	#[test]
+	fn unavailable_state_is_not_punished_and_moves_target() {
+		let blocks = build_blocks(3);
+		let client = Arc::new(test_client::new());
+		let mut sync = fast_sync(client.clone());
+		add_peer(&mut sync, &blocks[2], STATE_REQUEST_VERSION);
+		add_peer(&mut sync, &blocks[2], STATE_REQUEST_VERSION);
+
+		assert_eq!(download_headers(&mut sync, &blocks, 2), vec![1, 2]);
+		import_blocks(&mut sync, &client, &blocks[..2], 2);
+
+		let (who, _) = sync.state_request().unwrap();
+		assert!(sync.on_state_data(&who, unavailable()).is_ok());
+
+		// the state is requested from the other peer only.
+		let (other, _) = sync.state_request().unwrap();
+		assert_ne!(other, who);
+		assert!(sync.on_state_data(&other, unavailable()).is_ok());
+
+		// no peer has the state anymore, headers are downloaded again until a block is finalized.
+		assert_eq!(sync.fast_sync_target, None);
+		assert!(sync.state_request().is_none());
+		assert!(sync.fast_sync_await_finality);
+
+		import_blocks(&mut sync, &client, &blocks[2..], 3);
+		sync.on_block_finalized(&blocks[2].header.hash(), 3);
+		assert_eq!(sync.fast_sync_target, Some((blocks[2].header.hash(), 3)));
+		let (_, request) = sync.state_request().unwrap();
+		assert_eq!(request.block, blocks[2].header.hash());
+	}
+
+	#[test]
+	fn state_of_old_justified_block_is_not_downloaded() {
+		let count = MAX_STATE_SYNC_TARGET_AGE as usize + 3;
+		let blocks = build_blocks(count);
+		let client = Arc::new(test_client::new());
+		let mut sync = fast_sync(client.clone());
+		add_peer(&mut sync, &blocks[count - 1], STATE_REQUEST_VERSION);
+
+		// the last justified block is likely pruned by the peers, all headers are imported.
+		let queued = download_headers(&mut sync, &blocks, 1);
+		assert_eq!(queued, (1..=count as u64).collect::<Vec<_>>());
+		assert_eq!(sync.fast_sync_target, None);
+		import_blocks(&mut sync, &client, &blocks, 1);
+		assert!(sync.state_request().is_none());
+
+		// finalized blocks too far behind are no target either.
+		client.finalize_block(BlockId::Number(2), None).unwrap();
+		sync.on_block_finalized(&blocks[1].header.hash(), 2);
+		assert_eq!(sync.fast_sync_target, None);
+
+		let recent = &blocks[count - 2];
+		client.finalize_block(BlockId::Hash(recent.header.hash()), None).unwrap();
+		sync.on_block_finalized(&recent.header.hash(), recent.header.number);
+		assert_eq!(sync.fast_sync_target, Some((recent.header.hash(), recent.header.number)));
+		assert_eq!(sync.state_request().unwrap().1.block, recent.header.hash());
+	}
+
+	#[test]
+	fn range_of_slow_peer_is_requested_from_another_peer() {
+		let blocks = build_blocks(10);
+		let client = Arc::new(test_client::new());
+		let info = client.info();
+		let mut sync = ChainSync::new(
+			Roles::FULL,
+			client.clone(),
+			&info,
+			None,
+			Box::new(DefaultBlockAnnounceValidator::new(client)),
+			1,
+			SyncMode::Full,
+		);
+		let slow = add_peer(&mut sync, &blocks[9], STATE_REQUEST_VERSION);
+		let (who, request) = sync.block_requests().next().unwrap();
+		assert_eq!(who, slow);
+		assert_eq!(request.from, message::FromBlock::Number(1));
+
+		// the range is downloaded from a single peer at a time.
+		let other = add_peer(&mut sync, &blocks[9], STATE_REQUEST_VERSION);
+		assert!(sync.block_requests().next().is_none());
+
+		// once the request has taken too long, the range is requested from the other peer.
+		sync.peers.get_mut(&slow).unwrap().download_stats
+			.on_request(Instant::now() - MIN_SLOW_REQUEST_TIME * 2);
+		let (who, retry) = sync.block_requests().next().unwrap();
+		assert_eq!(who, other);
+		assert_eq!(retry.from, request.from);
+		assert_eq!(retry.max, request.max);
+		assert!(sync.block_requests().next().is_none());
+
+		// the blocks of whichever peer answers first are imported, the late ones are ignored.
+		let response = |request: &BlockRequest<Block>| BlockResponse::<Block> {
+			id: request.id,
+			blocks: blocks.iter().map(|block| BlockData {
+				hash: block.header.hash(),
+				header: Some(block.header.clone()),
+				body: Some(block.extrinsics.clone()),
+				receipt: None,
+				message_queue: None,
+				justification: None,
+			}).collect(),
+		};
+		let imported = |data: OnBlockData<Block>| match data {
+			OnBlockData::Import(_, blocks) => blocks.len(),
+			OnBlockData::Request(..) => panic!("Unexpected request"),
+		};
+		let data = sync.on_block_data(other, retry.clone(), response(&retry)).unwrap();
+		assert_eq!(imported(data), 10);
+		let data = sync.on_block_data(slow, request.clone(), response(&request)).unwrap();
+		assert_eq!(imported(data), 0);
+	}
+
+	#[test]
+	fn refused_block_request_is_retried() {
+		let blocks = build_blocks(10);
+		let client = Arc::new(test_client::new());
+		let info = client.info();
+		let mut sync = ChainSync::new(
+			Roles::FULL,
+			client.clone(),
+			&info,
+			None,
+			Box::new(DefaultBlockAnnounceValidator::new(client)),
+			1,
+			SyncMode::Full,
+		);
+		let peer = add_peer(&mut sync, &blocks[9], STATE_REQUEST_VERSION);
+
+		let (who, request) = sync.block_requests().next().unwrap();
+		assert_eq!(who, peer);
+		assert_eq!(request.from, message::FromBlock::Number(1));
+		assert!(sync.block_requests().next().is_none());
+
+		// the peer is kept, and the blocks are requested again.
+		assert!(sync.on_request_failed(&peer).is_none());
+		assert!(sync.peer_info(&peer).is_some());
+		let (who, retry) = sync.block_requests().next().unwrap();
+		assert_eq!(who, peer);
+		assert_eq!(retry.from, request.from);
+	}
+}
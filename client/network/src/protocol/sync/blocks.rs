@@ -17,6 +17,7 @@
 use std::mem;
 use std::cmp;
 use std::ops::Range;
+use std::time::{Duration, Instant};
 use std::collections::{HashMap, BTreeMap};
 use std::collections::hash_map::Entry;
 use log::trace;
@@ -51,6 +52,94 @@ impl<B: BlockT> BlockRangeState<B> {
 	}
 }
 
+/// Observed block download performance of a peer.
+///
+/// Block requests are sized so that the peer answers them in about `target_time`, and a request
+/// that takes much longer than the peer usually does is considered slow.
+#[derive(Debug, Clone)]
+pub struct DownloadStats {
+	/// Moving average of the time taken to answer a block request.
+	latency: Option<Duration>,
+	/// Moving average of the number of blocks received per second.
+	blocks_per_sec: Option<f64>,
+	/// Number of blocks to ask for in the next request.
+	request_size: usize,
+	/// When the pending block request was sent, if any.
+	request_started: Option<Instant>,
+}
+
+impl DownloadStats {
+	/// Create stats for a peer we know nothing about yet, starting with requests of `initial_size` blocks.
+	pub fn new(initial_size: usize) -> Self {
+		DownloadStats {
+			latency: None,
+			blocks_per_sec: None,
+			request_size: initial_size,
+			request_started: None,
+		}
+	}
+
+	/// Number of blocks to ask for in the next request.
+	pub fn request_size(&self) -> usize {
+		self.request_size
+	}
+
+	/// Number of blocks received per second, `0` until the peer has sent any.
+	pub fn blocks_per_sec(&self) -> f64 {
+		self.blocks_per_sec.unwrap_or(0.0)
+	}
+
+	/// Note that a block request has been sent to the peer.
+	pub fn on_request(&mut self, now: Instant) {
+		self.request_started = Some(now);
+	}
+
+	/// Note that the peer answered the pending block request with `count` blocks, and resize
+	/// the next request to be answered in about `target_time`, within `min_size..=max_size`.
+	pub fn on_response(
+		&mut self,
+		now: Instant,
+		count: usize,
+		target_time: Duration,
+		min_size: usize,
+		max_size: usize,
+	) {
+		let started = match self.request_started.take() {
+			Some(started) => started,
+			None => return,
+		};
+		let elapsed = now.duration_since(started);
+		self.latency = Some(match self.latency {
+			Some(latency) => (latency * 3 + elapsed) / 4,
+			None => elapsed,
+		});
+
+		// Empty responses tell nothing about throughput.
+		if count == 0 {
+			return;
+		}
+		let rate = count as f64 / cmp::max(elapsed, Duration::from_millis(1)).as_secs_f64();
+		let rate = match self.blocks_per_sec {
+			Some(average) => (average * 3.0 + rate) / 4.0,
+			None => rate,
+		};
+		self.blocks_per_sec = Some(rate);
+		self.request_size = cmp::min(cmp::max((rate * target_time.as_secs_f64()) as usize, min_size), max_size);
+	}
+
+	/// Whether the pending request has taken more than `factor` times the usual latency of the
+	/// peer, and at least `min_time`.
+	pub fn is_slow(&self, now: Instant, factor: u32, min_time: Duration) -> bool {
+		let expected = self.latency.map_or(min_time, |latency| cmp::max(latency * factor, min_time));
+		self.request_started.map_or(false, |started| now.duration_since(started) > expected)
+	}
+
+	/// Forget about the pending request, if any.
+	pub fn clear_request(&mut self) {
+		self.request_started = None;
+	}
+}
+
 /// A collection of blocks being downloaded.
 #[derive(Default)]
 pub struct BlockCollection<B: BlockT> {
@@ -186,6 +275,37 @@ impl<B: BlockT> BlockCollection<B> {
 		drained
 	}
 
+	/// Number of downloaded blocks that are waiting to be drained.
+	pub fn pending_blocks(&self) -> usize {
+		self.blocks.values().map(|range| match range {
+			BlockRangeState::Complete(blocks) => blocks.len(),
+			BlockRangeState::Downloading { .. } => 0,
+		}).sum()
+	}
+
+	/// Download again a range that is already being downloaded by another peer, for example because
+	/// the other peer is slow. Returns `None` if the range isn't pending, is already being downloaded
+	/// by `max_parallel` peers or starts past `peer_best`.
+	pub fn rerequest(
+		&mut self,
+		who: PeerId,
+		start: NumberFor<B>,
+		peer_best: NumberFor<B>,
+		max_parallel: u32,
+	) -> Option<Range<NumberFor<B>>> {
+		if start > peer_best {
+			return None;
+		}
+		match self.blocks.get_mut(&start) {
+			Some(&mut BlockRangeState::Downloading { len, ref mut downloading }) if *downloading < max_parallel => {
+				*downloading += 1;
+				self.peer_requests.insert(who, start);
+				Some(start .. cmp::min(start + len, peer_best + One::one()))
+			},
+			_ => None,
+		}
+	}
+
 	pub fn clear_peer_download(&mut self, who: &PeerId) {
 		match self.peer_requests.entry(who.clone()) {
 			Entry::Occupied(entry) => {
@@ -213,7 +333,8 @@ impl<B: BlockT> BlockCollection<B> {
 
 #[cfg(test)]
 mod test {
-	use super::{BlockCollection, BlockData, BlockRangeState};
+	use super::{BlockCollection, BlockData, BlockRangeState, DownloadStats};
+	use std::time::{Duration, Instant};
 	use crate::{message, PeerId};
 	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper};
 	use primitives::H256;
@@ -309,4 +430,54 @@ mod test {
 		assert_eq!(bc.needed_blocks(peer0.clone(), 128, 10000, 600, 1, 200), None); // too far ahead
 		assert_eq!(bc.needed_blocks(peer0.clone(), 128, 10000, 600, 1, 200000), Some(100 + 128 .. 100 + 128 + 128));
 	}
+
+	#[test]
+	fn rerequest_slow_range() {
+		let mut bc: BlockCollection<Block> = BlockCollection::new();
+		let peer0 = PeerId::random();
+		let peer1 = PeerId::random();
+		let peer2 = PeerId::random();
+
+		assert_eq!(bc.needed_blocks(peer0.clone(), 40, 150, 0, 1, 200), Some(1 .. 41));
+		assert_eq!(bc.rerequest(peer1.clone(), 1, 20, 2), Some(1 .. 21));
+		// Already downloaded by two peers.
+		assert_eq!(bc.rerequest(peer2.clone(), 1, 150, 2), None);
+		// Not a pending range.
+		assert_eq!(bc.rerequest(peer2.clone(), 41, 150, 2), None);
+
+		let blocks = generate_blocks(20);
+		bc.clear_peer_download(&peer1);
+		bc.insert(1, blocks[1..21].to_vec(), peer1.clone());
+		assert_eq!(bc.pending_blocks(), 20);
+		// The slow peer responding late doesn't remove the downloaded range.
+		bc.clear_peer_download(&peer0);
+		assert_eq!(bc.drain(1).len(), 20);
+		assert_eq!(bc.pending_blocks(), 0);
+	}
+
+	#[test]
+	fn download_stats_adapt_request_size() {
+		let mut stats = DownloadStats::new(32);
+		let start = Instant::now();
+		assert_eq!(stats.request_size(), 32);
+		assert!(!stats.is_slow(start + Duration::from_secs(100), 3, Duration::from_secs(5)));
+
+		// 32 blocks in one second: ask for two seconds worth of blocks next time.
+		stats.on_request(start);
+		stats.on_response(start + Duration::from_secs(1), 32, Duration::from_secs(2), 8, 128);
+		assert_eq!(stats.request_size(), 64);
+		assert_eq!(stats.blocks_per_sec(), 32.0);
+
+		// Sizes are kept within bounds.
+		stats.on_request(start);
+		stats.on_response(start + Duration::from_millis(10), 128, Duration::from_secs(2), 8, 128);
+		assert_eq!(stats.request_size(), 128);
+
+		// A request is slow once it takes several times the usual latency.
+		stats.on_request(start);
+		assert!(!stats.is_slow(start + Duration::from_secs(4), 3, Duration::from_secs(5)));
+		assert!(stats.is_slow(start + Duration::from_secs(6), 3, Duration::from_secs(5)));
+		stats.clear_request();
+		assert!(!stats.is_slow(start + Duration::from_secs(6), 3, Duration::from_secs(5)));
+	}
 }
@@ -283,6 +283,7 @@ impl<'a, B: BlockT> Matcher<'a, B> {
 #[cfg(test)]
 mod tests {
 	use crate::protocol::sync::PeerSync;
+	use crate::protocol::sync::blocks::DownloadStats;
 	use sp_blockchain::Error as ClientError;
 	use quickcheck::{Arbitrary, Gen, QuickCheck, StdThreadGen};
 	use rand::Rng;
@@ -489,7 +490,9 @@ mod tests {
 				best_hash: Hash::random(),
 				best_number: g.gen(),
 				state: ArbitraryPeerSyncState::arbitrary(g).0,
-				recently_announced: Default::default()
+				recently_announced: Default::default(),
+				download_stats: DownloadStats::new(32),
+				protocol_version: g.gen(),
 			};
 			ArbitraryPeerSync(ps)
 		}
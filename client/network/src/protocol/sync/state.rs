@@ -0,0 +1,146 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use crate::chain::Client;
+use crate::message::{self, StateResponse};
+use log::debug;
+use primitives::storage::well_known_keys;
+use sp_runtime::{StorageOverlay, ChildrenStorageOverlay};
+use sp_runtime::traits::{Block as BlockT, Header, NumberFor};
+
+/// Downloads the whole state of a single block, one proven range of storage entries at a time.
+///
+/// The top trie is downloaded first. Child tries found in it are downloaded afterwards.
+pub(crate) struct StateSync<B: BlockT> {
+	target_block: B::Hash,
+	target_header: B::Header,
+	/// Key of the last downloaded entry of the trie being downloaded.
+	last_key: Vec<u8>,
+	/// Child storage key of the trie being downloaded, `None` for the top trie.
+	child_key: Option<Vec<u8>>,
+	/// Child tries found in the top trie that are yet to be downloaded.
+	pending_children: Vec<Vec<u8>>,
+	top: StorageOverlay,
+	children: ChildrenStorageOverlay,
+	imported_bytes: u64,
+	client: Arc<dyn Client<B>>,
+}
+
+/// Result of [`StateSync::import`].
+pub(crate) enum ImportResult<B: BlockT> {
+	/// The state is complete and should be imported.
+	Import(B::Hash, StorageOverlay, ChildrenStorageOverlay),
+	/// More entries need to be downloaded.
+	Continue,
+	/// The peer doesn't have the state of the target block.
+	Unavailable,
+	/// The response is invalid.
+	BadResponse,
+}
+
+impl<B: BlockT> StateSync<B> {
+	/// Create a new instance, downloading the state of the block with the given header.
+	pub(crate) fn new(client: Arc<dyn Client<B>>, target: B::Header) -> Self {
+		StateSync {
+			target_block: target.hash(),
+			target_header: target,
+			last_key: Vec::new(),
+			child_key: None,
+			pending_children: Vec::new(),
+			top: Default::default(),
+			children: Default::default(),
+			imported_bytes: 0,
+			client,
+		}
+	}
+
+	/// Validate and import a state response.
+	pub(crate) fn import(&mut self, response: StateResponse) -> ImportResult<B> {
+		let proof = match response.proof {
+			Some(proof) => proof,
+			None => return ImportResult::Unavailable,
+		};
+		if response.entries.is_empty() && !response.complete {
+			debug!(target: "sync", "Bad state response: no entries");
+			return ImportResult::BadResponse;
+		}
+		if let Err(e) = self.client.check_state_range(
+			*self.target_header.state_root(),
+			proof,
+			self.child_key.as_ref().map(|k| k.as_slice()),
+			&self.last_key,
+			&response.entries,
+			response.complete,
+		) {
+			debug!(target: "sync", "Bad state response: {:?}", e);
+			return ImportResult::BadResponse;
+		}
+
+		if let Some((key, _)) = response.entries.last() {
+			self.last_key = key.clone();
+		}
+		for (key, value) in response.entries {
+			self.imported_bytes += (key.len() + value.len()) as u64;
+			match self.child_key {
+				Some(ref child_key) => {
+					self.children.entry(child_key.clone()).or_default().insert(key, value);
+				},
+				None if well_known_keys::is_child_storage_key(&key) => self.pending_children.push(key),
+				None => {
+					self.top.insert(key, value);
+				},
+			}
+		}
+
+		if response.complete {
+			self.last_key.clear();
+			self.child_key = self.pending_children.pop();
+			if self.child_key.is_none() {
+				debug!(
+					target: "sync",
+					"State sync of {} complete ({} bytes)",
+					self.target_block,
+					self.imported_bytes,
+				);
+				let top = std::mem::replace(&mut self.top, Default::default());
+				let children = std::mem::replace(&mut self.children, Default::default());
+				return ImportResult::Import(self.target_block, top, children);
+			}
+		}
+		ImportResult::Continue
+	}
+
+	/// Produce the next state request.
+	pub(crate) fn next_request(&self) -> message::StateRequest<B::Hash> {
+		message::generic::StateRequest {
+			id: 0,
+			block: self.target_block,
+			storage_key: self.child_key.clone(),
+			start: self.last_key.clone(),
+		}
+	}
+
+	/// Returns target block number.
+	pub(crate) fn target_block_num(&self) -> NumberFor<B> {
+		*self.target_header.number()
+	}
+
+	/// Returns the number of bytes of state downloaded so far.
+	pub(crate) fn imported_bytes(&self) -> u64 {
+		self.imported_bytes
+	}
+}
@@ -0,0 +1,664 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generic request-response protocols.
+//!
+//! Each protocol is identified by its name, and each request is sent on a new substream opened
+//! with that name. The request and the response are both a single length-prefixed message, after
+//! which the substream is closed.
+//!
+//! Protocols are registered with a [`ProtocolConfig`] that determines the maximum size of the
+//! requests and responses, how long to wait for a response, and how many inbound requests of
+//! each peer can be processed at the same time. Inbound requests are sent on the `inbound_queue`
+//! of the protocol, and requests beyond the concurrency limit of their peer or that don't fit in
+//! the queue are dropped, closing the substream.
+//!
+//! Protocols registered without an inbound queue only accept inbound requests that the network
+//! answers itself; requests for them are reported as [`RequestResponsesEvent::InboundRequest`].
+
+use futures::{prelude::*, sync::{mpsc, oneshot}};
+use futures03::TryFutureExt as _;
+use futures_timer::Delay;
+use libp2p::core::{ConnectedPoint, Multiaddr, PeerId};
+use libp2p::core::upgrade::{self, InboundUpgrade, OutboundUpgrade, UpgradeInfo, Negotiated, ReadOneError};
+use libp2p::swarm::{
+	KeepAlive, NetworkBehaviour, NetworkBehaviourAction, PollParameters, ProtocolsHandler,
+	ProtocolsHandlerEvent, ProtocolsHandlerUpgrErr, SubstreamProtocol,
+};
+use log::{debug, trace};
+use std::{borrow::Cow, collections::{HashMap, HashSet, VecDeque}, fmt, iter, marker::PhantomData};
+use std::time::Duration;
+use tokio_io::{AsyncRead, AsyncWrite};
+
+/// Configuration of a request-response protocol.
+#[derive(Debug, Clone)]
+pub struct ProtocolConfig {
+	/// Name of the protocol on the wire, e.g. `/my-chain/my-protocol/1`.
+	pub name: Cow<'static, str>,
+	/// Maximum size of a request, in bytes. Larger requests are refused.
+	pub max_request_size: usize,
+	/// Maximum size of a response, in bytes. Larger responses are refused.
+	pub max_response_size: usize,
+	/// How long to wait for the response to a request, negotiation of the substream included.
+	/// Also how long an inbound request is given to be answered.
+	pub request_timeout: Duration,
+	/// Maximum number of inbound requests of each peer being answered at the same time.
+	pub max_concurrent_inbound: usize,
+	/// Channel on which the inbound requests are sent. `None` to have them reported as
+	/// [`RequestResponsesEvent::InboundRequest`] instead.
+	pub inbound_queue: Option<mpsc::Sender<IncomingRequest>>,
+}
+
+/// A request received from a remote.
+#[derive(Debug)]
+pub struct IncomingRequest {
+	/// Who sent the request.
+	pub peer: PeerId,
+	/// The request, as sent by the remote.
+	pub payload: Vec<u8>,
+	/// Channel to send the response on. Dropping it refuses the request.
+	pub pending_response: oneshot::Sender<Vec<u8>>,
+}
+
+/// Reason why a request has failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestFailure {
+	/// We are not connected to the peer.
+	NotConnected,
+	/// The protocol isn't registered locally.
+	UnknownProtocol,
+	/// The request is larger than the maximum size of the protocol.
+	RequestTooLarge,
+	/// The remote doesn't support the protocol.
+	UnsupportedProtocol,
+	/// The remote didn't answer in time.
+	Timeout,
+	/// The connection was closed before the response was received.
+	ConnectionClosed,
+	/// The remote refused the request or sent an invalid response.
+	Refused,
+}
+
+impl fmt::Display for RequestFailure {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RequestFailure::NotConnected => write!(f, "not connected to the peer"),
+			RequestFailure::UnknownProtocol => write!(f, "unknown protocol"),
+			RequestFailure::RequestTooLarge => write!(f, "request too large"),
+			RequestFailure::UnsupportedProtocol => write!(f, "protocol not supported by the remote"),
+			RequestFailure::Timeout => write!(f, "request timeout"),
+			RequestFailure::ConnectionClosed => write!(f, "connection closed"),
+			RequestFailure::Refused => write!(f, "request refused"),
+		}
+	}
+}
+
+impl std::error::Error for RequestFailure {}
+
+/// Event generated by the [`RequestResponsesBehaviour`].
+#[derive(Debug)]
+pub enum RequestResponsesEvent {
+	/// A request has been received for a protocol registered without an inbound queue.
+	InboundRequest {
+		/// Name of the protocol.
+		protocol: Cow<'static, str>,
+		/// The request.
+		request: IncomingRequest,
+	},
+}
+
+/// Identifier of an outbound request, unique for the behaviour.
+type RequestId = u64;
+
+/// Future writing the response to an inbound request.
+type InboundResponse = Box<dyn Future<Item = (), Error = ()> + Send>;
+
+/// Implementation of `NetworkBehaviour` for the request-response protocols.
+pub struct RequestResponsesBehaviour<TSubstream> {
+	/// Registered protocols, by name.
+	protocols: HashMap<String, ProtocolConfig>,
+	/// Peers we are connected to.
+	connected: HashSet<PeerId>,
+	/// Outbound requests waiting for a response, with the peer they were sent to.
+	pending_requests: HashMap<RequestId, (PeerId, oneshot::Sender<Result<Vec<u8>, RequestFailure>>)>,
+	/// Id of the next outbound request.
+	next_request_id: RequestId,
+	/// Inbound requests being answered, per protocol, with the peer that sent each of them.
+	pending_responses: HashMap<String, Vec<(PeerId, InboundResponse)>>,
+	/// Actions to produce in `poll()`.
+	actions: VecDeque<NetworkBehaviourAction<HandlerIn, RequestResponsesEvent>>,
+	/// Marker to pin the generic.
+	marker: PhantomData<TSubstream>,
+}
+
+impl<TSubstream> RequestResponsesBehaviour<TSubstream> {
+	/// Creates a new behaviour for the given protocols.
+	pub fn new(protocols: impl IntoIterator<Item = ProtocolConfig>) -> Self {
+		RequestResponsesBehaviour {
+			protocols: protocols.into_iter().map(|config| (config.name.to_string(), config)).collect(),
+			connected: HashSet::new(),
+			pending_requests: HashMap::new(),
+			next_request_id: 0,
+			pending_responses: HashMap::new(),
+			actions: VecDeque::new(),
+			marker: PhantomData,
+		}
+	}
+
+	/// Sends a request to a peer. The response, or the reason of the failure, is sent on
+	/// `pending_response`.
+	pub fn send_request(
+		&mut self,
+		target: &PeerId,
+		protocol: &str,
+		request: Vec<u8>,
+		pending_response: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
+	) {
+		let config = match self.protocols.get(protocol) {
+			Some(config) => config,
+			None => {
+				let _ = pending_response.send(Err(RequestFailure::UnknownProtocol));
+				return
+			},
+		};
+		if !self.connected.contains(target) {
+			let _ = pending_response.send(Err(RequestFailure::NotConnected));
+			return
+		}
+		if request.len() > config.max_request_size {
+			let _ = pending_response.send(Err(RequestFailure::RequestTooLarge));
+			return
+		}
+
+		let id = self.next_request_id;
+		self.next_request_id += 1;
+		trace!(target: "sub-libp2p", "Request {} on {} to {}", id, protocol, target);
+		self.actions.push_back(NetworkBehaviourAction::SendEvent {
+			peer_id: target.clone(),
+			event: HandlerIn::Request {
+				id,
+				protocol: protocol.to_owned(),
+				request,
+				max_response_size: config.max_response_size,
+				timeout: config.request_timeout,
+			},
+		});
+		self.pending_requests.insert(id, (target.clone(), pending_response));
+	}
+
+	fn inbound_protocols(&self) -> InboundProtocols {
+		InboundProtocols {
+			protocols: self.protocols.values()
+				.map(|config| (config.name.to_string(), config.max_request_size))
+				.collect(),
+		}
+	}
+
+	/// Checks an inbound request against the limits of its protocol and dispatches it.
+	///
+	/// Returns the receiver of the response and how long it may take, or `None` if the request
+	/// is refused.
+	fn accept_inbound_request(
+		&mut self,
+		peer: &PeerId,
+		protocol: &str,
+		payload: Vec<u8>,
+	) -> Option<(oneshot::Receiver<Vec<u8>>, Duration)> {
+		let config = self.protocols.get_mut(protocol)?;
+		let in_progress = self.pending_responses.get(protocol)
+			.map_or(0, |pending| pending.iter().filter(|(p, _)| p == peer).count());
+		if in_progress >= config.max_concurrent_inbound {
+			debug!(target: "sub-libp2p", "Refused request on {} from {}: too many requests", protocol, peer);
+			return None
+		}
+
+		let (tx, rx) = oneshot::channel();
+		let request = IncomingRequest { peer: peer.clone(), payload, pending_response: tx };
+		match config.inbound_queue.as_mut() {
+			Some(queue) => if queue.try_send(request).is_err() {
+				debug!(target: "sub-libp2p", "Refused request on {} from {}: queue full", protocol, peer);
+				return None
+			},
+			None => self.actions.push_back(NetworkBehaviourAction::GenerateEvent(
+				RequestResponsesEvent::InboundRequest { protocol: config.name.clone(), request }
+			)),
+		}
+
+		Some((rx, config.request_timeout))
+	}
+
+	/// Drops the inbound requests that have been answered or have timed out.
+	fn poll_pending_responses(&mut self) {
+		for pending in self.pending_responses.values_mut() {
+			let mut index = 0;
+			while index < pending.len() {
+				match pending[index].1.poll() {
+					Ok(Async::NotReady) => index += 1,
+					Ok(Async::Ready(())) | Err(()) => {
+						pending.swap_remove(index);
+					},
+				}
+			}
+		}
+	}
+}
+
+impl<TSubstream> RequestResponsesBehaviour<TSubstream>
+where
+	TSubstream: AsyncRead + AsyncWrite + Send + 'static,
+{
+	fn on_inbound_request(
+		&mut self,
+		peer: PeerId,
+		protocol: String,
+		payload: Vec<u8>,
+		substream: Negotiated<TSubstream>,
+	) {
+		let (rx, timeout) = match self.accept_inbound_request(&peer, &protocol, payload) {
+			Some(accepted) => accepted,
+			None => return,
+		};
+
+		let timeout = Delay::new(timeout).compat().map_err(|_| ());
+		let response = rx
+			.map_err(|_| ())
+			.and_then(move |response| upgrade::write_one(substream, response).map(|_| ()).map_err(|_| ()))
+			.select(timeout)
+			.map(|_| ())
+			.map_err(|_| ());
+		self.pending_responses.entry(protocol).or_default().push((peer, Box::new(response)));
+	}
+}
+
+impl<TSubstream> NetworkBehaviour for RequestResponsesBehaviour<TSubstream>
+where
+	TSubstream: AsyncRead + AsyncWrite + Send + 'static,
+{
+	type ProtocolsHandler = RequestResponsesHandler<TSubstream>;
+	type OutEvent = RequestResponsesEvent;
+
+	fn new_handler(&mut self) -> Self::ProtocolsHandler {
+		RequestResponsesHandler::new(self.inbound_protocols())
+	}
+
+	fn addresses_of_peer(&mut self, _: &PeerId) -> Vec<Multiaddr> {
+		Vec::new()
+	}
+
+	fn inject_connected(&mut self, peer_id: PeerId, _: ConnectedPoint) {
+		self.connected.insert(peer_id);
+	}
+
+	fn inject_disconnected(&mut self, peer_id: &PeerId, _: ConnectedPoint) {
+		self.connected.remove(peer_id);
+		let closed = self.pending_requests.iter()
+			.filter(|(_, (peer, _))| peer == peer_id)
+			.map(|(id, _)| *id)
+			.collect::<Vec<_>>();
+		for id in closed {
+			if let Some((_, pending_response)) = self.pending_requests.remove(&id) {
+				let _ = pending_response.send(Err(RequestFailure::ConnectionClosed));
+			}
+		}
+	}
+
+	fn inject_node_event(&mut self, peer_id: PeerId, event: HandlerOut<TSubstream>) {
+		match event {
+			HandlerOut::Request { protocol, request, substream } =>
+				self.on_inbound_request(peer_id, protocol, request, substream),
+			HandlerOut::Response { id, result } => {
+				trace!(target: "sub-libp2p", "Response to request {} from {}: {:?}", id, peer_id, result.as_ref().map(|r| r.len()));
+				if let Some((_, pending_response)) = self.pending_requests.remove(&id) {
+					let _ = pending_response.send(result);
+				}
+			},
+		}
+	}
+
+	fn poll(
+		&mut self,
+		_: &mut impl PollParameters,
+	) -> Async<NetworkBehaviourAction<HandlerIn, Self::OutEvent>> {
+		self.poll_pending_responses();
+
+		if let Some(action) = self.actions.pop_front() {
+			return Async::Ready(action)
+		}
+
+		Async::NotReady
+	}
+}
+
+/// Event sent to the handler.
+#[derive(Debug)]
+pub enum HandlerIn {
+	/// Send a request on a new substream.
+	Request {
+		id: RequestId,
+		protocol: String,
+		request: Vec<u8>,
+		max_response_size: usize,
+		timeout: Duration,
+	},
+}
+
+/// Event produced by the handler.
+pub enum HandlerOut<TSubstream> {
+	/// The remote has sent a request. The response is to be written on the substream.
+	Request {
+		protocol: String,
+		request: Vec<u8>,
+		substream: Negotiated<TSubstream>,
+	},
+	/// Outcome of a request we sent.
+	Response {
+		id: RequestId,
+		result: Result<Vec<u8>, RequestFailure>,
+	},
+}
+
+/// Handler of the request-response protocols on a single connection.
+pub struct RequestResponsesHandler<TSubstream> {
+	/// Protocols accepted on inbound substreams.
+	inbound: InboundProtocols,
+	/// Requests to open a substream for.
+	pending_requests: VecDeque<(RequestId, OutboundRequest, Duration)>,
+	/// Number of requests sent and waiting for a response.
+	requests_in_progress: usize,
+	/// Events to produce in `poll()`.
+	events: VecDeque<HandlerOut<TSubstream>>,
+}
+
+impl<TSubstream> RequestResponsesHandler<TSubstream> {
+	fn new(inbound: InboundProtocols) -> Self {
+		RequestResponsesHandler {
+			inbound,
+			pending_requests: VecDeque::new(),
+			requests_in_progress: 0,
+			events: VecDeque::new(),
+		}
+	}
+}
+
+impl<TSubstream> ProtocolsHandler for RequestResponsesHandler<TSubstream>
+where
+	TSubstream: AsyncRead + AsyncWrite,
+{
+	type InEvent = HandlerIn;
+	type OutEvent = HandlerOut<TSubstream>;
+	type Error = void::Void;
+	type Substream = TSubstream;
+	type InboundProtocol = InboundProtocols;
+	type OutboundProtocol = OutboundRequest;
+	type OutboundOpenInfo = RequestId;
+
+	fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol> {
+		SubstreamProtocol::new(self.inbound.clone())
+	}
+
+	fn inject_fully_negotiated_inbound(&mut self, (protocol, request, substream): InboundOutput<TSubstream>) {
+		self.events.push_back(HandlerOut::Request { protocol, request, substream });
+	}
+
+	fn inject_fully_negotiated_outbound(&mut self, response: Vec<u8>, id: RequestId) {
+		self.requests_in_progress -= 1;
+		self.events.push_back(HandlerOut::Response { id, result: Ok(response) });
+	}
+
+	fn inject_event(&mut self, event: HandlerIn) {
+		let HandlerIn::Request { id, protocol, request, max_response_size, timeout } = event;
+		self.pending_requests.push_back((id, OutboundRequest { protocol, request, max_response_size }, timeout));
+	}
+
+	fn inject_dial_upgrade_error(&mut self, id: RequestId, error: ProtocolsHandlerUpgrErr<ReadOneError>) {
+		self.requests_in_progress -= 1;
+		let failure = match error {
+			ProtocolsHandlerUpgrErr::Timeout | ProtocolsHandlerUpgrErr::Timer => RequestFailure::Timeout,
+			ProtocolsHandlerUpgrErr::Upgrade(upgrade::UpgradeError::Select(_)) =>
+				RequestFailure::UnsupportedProtocol,
+			ProtocolsHandlerUpgrErr::Upgrade(upgrade::UpgradeError::Apply(_)) => RequestFailure::Refused,
+		};
+		self.events.push_back(HandlerOut::Response { id, result: Err(failure) });
+	}
+
+	fn connection_keep_alive(&self) -> KeepAlive {
+		if self.requests_in_progress > 0 || !self.pending_requests.is_empty() {
+			KeepAlive::Yes
+		} else {
+			KeepAlive::No
+		}
+	}
+
+	fn poll(&mut self) -> Poll<
+		ProtocolsHandlerEvent<Self::OutboundProtocol, Self::OutboundOpenInfo, Self::OutEvent>,
+		Self::Error
+	> {
+		if let Some(event) = self.events.pop_front() {
+			return Ok(Async::Ready(ProtocolsHandlerEvent::Custom(event)))
+		}
+
+		if let Some((id, request, timeout)) = self.pending_requests.pop_front() {
+			self.requests_in_progress += 1;
+			return Ok(Async::Ready(ProtocolsHandlerEvent::OutboundSubstreamRequest {
+				protocol: SubstreamProtocol::new(request).with_timeout(timeout),
+				info: id,
+			}))
+		}
+
+		Ok(Async::NotReady)
+	}
+}
+
+/// Output of the inbound upgrade: protocol name, request and substream to answer on.
+type InboundOutput<TSubstream> = (String, Vec<u8>, Negotiated<TSubstream>);
+
+/// Upgrade accepting requests of all the registered protocols.
+#[derive(Debug, Clone)]
+pub struct InboundProtocols {
+	/// Name and maximum request size of each protocol.
+	protocols: Vec<(String, usize)>,
+}
+
+impl UpgradeInfo for InboundProtocols {
+	type Info = String;
+	type InfoIter = Vec<String>;
+
+	fn protocol_info(&self) -> Self::InfoIter {
+		self.protocols.iter().map(|(name, _)| name.clone()).collect()
+	}
+}
+
+impl<TSubstream> InboundUpgrade<TSubstream> for InboundProtocols
+where
+	TSubstream: AsyncRead + AsyncWrite,
+{
+	type Output = InboundOutput<TSubstream>;
+	type Error = ReadOneError;
+	type Future = upgrade::ReadRespond<
+		Negotiated<TSubstream>,
+		String,
+		fn(Negotiated<TSubstream>, Vec<u8>, String) -> Result<InboundOutput<TSubstream>, ReadOneError>,
+	>;
+
+	fn upgrade_inbound(self, substream: Negotiated<TSubstream>, protocol: String) -> Self::Future {
+		let max_request_size = self.protocols.iter()
+			.find(|(name, _)| *name == protocol)
+			.map_or(0, |(_, max)| *max);
+		let then: fn(Negotiated<TSubstream>, Vec<u8>, String) -> Result<InboundOutput<TSubstream>, ReadOneError> =
+			|substream, request, protocol| Ok((protocol, request, substream));
+		upgrade::read_respond(substream, max_request_size, protocol, then)
+	}
+}
+
+/// Upgrade sending a request and reading the response.
+#[derive(Debug, Clone)]
+pub struct OutboundRequest {
+	protocol: String,
+	request: Vec<u8>,
+	max_response_size: usize,
+}
+
+impl UpgradeInfo for OutboundRequest {
+	type Info = String;
+	type InfoIter = iter::Once<String>;
+
+	fn protocol_info(&self) -> Self::InfoIter {
+		iter::once(self.protocol.clone())
+	}
+}
+
+impl<TSubstream> OutboundUpgrade<TSubstream> for OutboundRequest
+where
+	TSubstream: AsyncRead + AsyncWrite,
+{
+	type Output = Vec<u8>;
+	type Error = ReadOneError;
+	type Future = upgrade::RequestResponse<
+		Negotiated<TSubstream>,
+		(),
+		fn(Vec<u8>, ()) -> Result<Vec<u8>, ReadOneError>,
+	>;
+
+	fn upgrade_outbound(self, substream: Negotiated<TSubstream>, _: String) -> Self::Future {
+		let then: fn(Vec<u8>, ()) -> Result<Vec<u8>, ReadOneError> = |response, ()| Ok(response);
+		upgrade::request_response(substream, self.request, self.max_response_size, (), then)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::future;
+	use libp2p::core::{muxing::StreamMuxerBox, nodes::Substream};
+
+	type Behaviour = RequestResponsesBehaviour<Substream<StreamMuxerBox>>;
+
+	const PROTOCOL: &str = "/test/echo/1";
+
+	fn behaviour(max_concurrent_inbound: usize) -> Behaviour {
+		RequestResponsesBehaviour::new(iter::once(ProtocolConfig {
+			name: PROTOCOL.into(),
+			max_request_size: 1024,
+			max_response_size: 1024,
+			request_timeout: Duration::from_secs(10),
+			max_concurrent_inbound,
+			inbound_queue: None,
+		}))
+	}
+
+	fn connect(behaviour: &mut Behaviour) -> PeerId {
+		let peer = PeerId::random();
+		let address = "/memory/1".parse().unwrap();
+		behaviour.inject_connected(peer.clone(), ConnectedPoint::Dialer { address });
+		peer
+	}
+
+	fn send_request(
+		behaviour: &mut Behaviour,
+		peer: &PeerId,
+		protocol: &str,
+		request: Vec<u8>,
+	) -> oneshot::Receiver<Result<Vec<u8>, RequestFailure>> {
+		let (tx, rx) = oneshot::channel();
+		behaviour.send_request(peer, protocol, request, tx);
+		rx
+	}
+
+	/// Accepts an inbound request as `on_inbound_request` does, returning the request reported
+	/// to be answered.
+	fn receive_request(behaviour: &mut Behaviour, peer: &PeerId) -> Option<IncomingRequest> {
+		let (rx, _) = behaviour.accept_inbound_request(peer, PROTOCOL, b"ping".to_vec())?;
+		let response: InboundResponse = Box::new(rx.map(|_| ()).map_err(|_| ()));
+		behaviour.pending_responses.entry(PROTOCOL.to_owned()).or_default()
+			.push((peer.clone(), response));
+		match behaviour.actions.pop_back() {
+			Some(NetworkBehaviourAction::GenerateEvent(
+				RequestResponsesEvent::InboundRequest { request, .. }
+			)) => Some(request),
+			_ => panic!("accepted requests are reported"),
+		}
+	}
+
+	#[test]
+	fn invalid_requests_fail_without_being_sent() {
+		let mut behaviour = behaviour(1);
+		let peer = connect(&mut behaviour);
+
+		let rx = send_request(&mut behaviour, &peer, "/test/unknown/1", Vec::new());
+		assert_eq!(rx.wait().unwrap(), Err(RequestFailure::UnknownProtocol));
+
+		let rx = send_request(&mut behaviour, &PeerId::random(), PROTOCOL, Vec::new());
+		assert_eq!(rx.wait().unwrap(), Err(RequestFailure::NotConnected));
+
+		let rx = send_request(&mut behaviour, &peer, PROTOCOL, vec![0; 1025]);
+		assert_eq!(rx.wait().unwrap(), Err(RequestFailure::RequestTooLarge));
+
+		assert!(behaviour.actions.is_empty());
+	}
+
+	#[test]
+	fn responses_are_delivered_to_their_request() {
+		let mut behaviour = behaviour(1);
+		let peer = connect(&mut behaviour);
+
+		let first = send_request(&mut behaviour, &peer, PROTOCOL, b"first".to_vec());
+		let second = send_request(&mut behaviour, &peer, PROTOCOL, b"second".to_vec());
+		let ids = behaviour.actions.drain(..).map(|action| match action {
+			NetworkBehaviourAction::SendEvent { peer_id, event: HandlerIn::Request { id, .. } } => {
+				assert_eq!(peer_id, peer);
+				id
+			},
+			_ => panic!("requests are sent to the handler of the peer"),
+		}).collect::<Vec<_>>();
+		assert_eq!(ids.len(), 2);
+
+		behaviour.inject_node_event(peer.clone(), HandlerOut::Response {
+			id: ids[1],
+			result: Ok(b"pong".to_vec()),
+		});
+		assert_eq!(second.wait().unwrap(), Ok(b"pong".to_vec()));
+
+		// pending requests fail when the peer disconnects.
+		behaviour.inject_disconnected(&peer, ConnectedPoint::Dialer {
+			address: "/memory/1".parse().unwrap(),
+		});
+		assert_eq!(first.wait().unwrap(), Err(RequestFailure::ConnectionClosed));
+	}
+
+	#[test]
+	fn inbound_requests_are_limited_per_peer() {
+		future::lazy(|| {
+			let mut behaviour = behaviour(2);
+			let greedy = connect(&mut behaviour);
+			let other = connect(&mut behaviour);
+
+			let first = receive_request(&mut behaviour, &greedy).unwrap();
+			let _second = receive_request(&mut behaviour, &greedy).unwrap();
+			assert!(receive_request(&mut behaviour, &greedy).is_none());
+
+			// other peers are still answered.
+			assert!(receive_request(&mut behaviour, &other).is_some());
+
+			// answering a request frees a slot of the peer.
+			first.pending_response.send(b"pong".to_vec()).unwrap();
+			behaviour.poll_pending_responses();
+			assert!(receive_request(&mut behaviour, &greedy).is_some());
+			assert!(receive_request(&mut behaviour, &greedy).is_none());
+
+			Ok::<_, ()>(())
+		}).wait().unwrap();
+	}
+}
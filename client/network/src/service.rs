@@ -25,12 +25,12 @@
 //! The methods of the [`NetworkService`] are implemented by sending a message over a channel,
 //! which is then processed by [`NetworkWorker::poll`].
 
-use std::{collections::{HashMap, HashSet}, fs, marker::PhantomData, io, path::Path};
+use std::{borrow::Cow, collections::{HashMap, HashSet}, fs, marker::PhantomData, io, path::Path};
 use std::sync::{Arc, atomic::{AtomicBool, AtomicUsize, Ordering}};
 
 use consensus::import_queue::{ImportQueue, Link};
 use consensus::import_queue::{BlockImportResult, BlockImportError};
-use futures::{prelude::*, sync::mpsc};
+use futures::{prelude::*, sync::{mpsc, oneshot}};
 use futures03::TryFutureExt as _;
 use log::{warn, error, info};
 use libp2p::{PeerId, Multiaddr, kad::record};
@@ -41,11 +41,12 @@ use peerset::PeersetHandle;
 use sp_runtime::{traits::{Block as BlockT, NumberFor}, ConsensusEngineId};
 
 use crate::{behaviour::{Behaviour, BehaviourOut}, config::{parse_str_addr, parse_addr}};
-use crate::{NetworkState, NetworkStateNotConnectedPeer, NetworkStatePeer};
+use crate::{NetworkState, NetworkStateBandwidth, NetworkStateNotConnectedPeer, NetworkStatePeer};
 use crate::{transport, config::NonReservedPeerMode, ReputationChange};
 use crate::config::{Params, TransportConfig};
 use crate::error::Error;
-use crate::protocol::{self, Protocol, Context, CustomMessageOutcome, PeerInfo};
+use crate::protocol::{self, Protocol, Context, CustomMessageOutcome, PeerInfo, RequestProtocol};
+use crate::request_responses::RequestFailure;
 use crate::protocol::consensus_gossip::{ConsensusGossip, MessageRecipient as GossipMessageRecipient};
 use crate::protocol::{event::Event, light_dispatch::{AlwaysBadChecker, RequestData}};
 use crate::protocol::specialization::NetworkSpecialization;
@@ -111,6 +112,8 @@ pub struct NetworkService<B: BlockT + 'static, S: NetworkSpecialization<B>, H: E
 	local_peer_id: PeerId,
 	/// Bandwidth logging system. Can be queried to know the average bandwidth consumed.
 	bandwidth: Arc<transport::BandwidthSinks>,
+	/// Enforces the bandwidth limits and counts the traffic of each peer.
+	bandwidth_limiter: Arc<transport::BandwidthLimiter>,
 	/// Peerset manager (PSM); manages the reputation of nodes and indicates the network which
 	/// nodes it should be connected to or not.
 	peerset: PeersetHandle,
@@ -193,10 +196,15 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 
 		let num_connected = Arc::new(AtomicUsize::new(0));
 		let is_major_syncing = Arc::new(AtomicBool::new(false));
+		let request_response_protocols = RequestProtocol::ALL.iter()
+			.map(|protocol| protocol.config(&params.protocol_id))
+			.chain(params.network_config.request_response_protocols.iter().cloned())
+			.collect();
 		let (protocol, peerset_handle) = Protocol::new(
 			protocol::ProtocolConfig {
 				roles: params.roles,
 				max_parallel_downloads: params.network_config.max_parallel_downloads,
+				sync_mode: params.network_config.sync_mode,
 			},
 			params.chain,
 			params.on_demand.as_ref().map(|od| od.checker().clone())
@@ -211,7 +219,7 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 		)?;
 
 		// Build the swarm.
-		let (mut swarm, bandwidth) = {
+		let (mut swarm, bandwidth, bandwidth_limiter) = {
 			let user_agent = format!(
 				"{} ({})",
 				params.network_config.client_version,
@@ -230,16 +238,22 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 					TransportConfig::MemoryOnly => false,
 					TransportConfig::Normal { allow_private_ipv4, .. } => allow_private_ipv4,
 				},
+				request_response_protocols,
 			);
-			let (transport, bandwidth) = {
+			let (transport, bandwidth, bandwidth_limiter) = {
 				let (config_mem, config_wasm) = match params.network_config.transport {
 					TransportConfig::MemoryOnly => (true, None),
 					TransportConfig::Normal { wasm_external_transport, .. } =>
 						(false, wasm_external_transport)
 				};
-				transport::build_transport(local_identity, config_mem, config_wasm)
+				transport::build_transport(
+					local_identity,
+					config_mem,
+					config_wasm,
+					params.network_config.bandwidth_limits.clone(),
+				)
 			};
-			(Swarm::<B, S, H>::new(transport, behaviour, local_peer_id.clone()), bandwidth)
+			(Swarm::<B, S, H>::new(transport, behaviour, local_peer_id.clone()), bandwidth, bandwidth_limiter)
 		};
 
 		// Listen on multiaddresses.
@@ -258,6 +272,7 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 
 		let service = Arc::new(NetworkService {
 			bandwidth,
+			bandwidth_limiter,
 			external_addresses: external_addresses.clone(),
 			num_connected: num_connected.clone(),
 			is_major_syncing: is_major_syncing.clone(),
@@ -350,6 +365,7 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 
 		let connected_peers = {
 			let swarm = &mut *swarm;
+			let bandwidth_limiter = &self.service.bandwidth_limiter;
 			open.iter().filter_map(move |peer_id| {
 				let known_addresses = NetworkBehaviour::addresses_of_peer(&mut **swarm, peer_id)
 					.into_iter().collect();
@@ -362,6 +378,9 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 					return None
 				};
 
+				let (total_inbound, total_outbound) = bandwidth_limiter.peer_totals(peer_id)
+					.unwrap_or((0, 0));
+
 				Some((peer_id.to_base58(), NetworkStatePeer {
 					endpoint,
 					version_string: swarm.node(peer_id)
@@ -370,6 +389,7 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 					enabled: swarm.user_protocol().is_enabled(&peer_id),
 					open: swarm.user_protocol().is_open(&peer_id),
 					known_addresses,
+					bandwidth: NetworkStateBandwidth { total_inbound, total_outbound },
 				}))
 			}).collect()
 		};
@@ -397,6 +417,12 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 			average_upload_per_sec: self.service.bandwidth.average_upload_per_sec(),
 			connected_peers,
 			not_connected_peers,
+			bandwidth_by_protocol: transport::BandwidthProtocol::ALL.iter()
+				.map(|protocol| (protocol.name().to_owned(), NetworkStateBandwidth {
+					total_inbound: swarm.user_protocol().protocol_bandwidth().total_inbound(*protocol),
+					total_outbound: swarm.user_protocol().protocol_bandwidth().total_outbound(*protocol),
+				}))
+				.collect(),
 			peerset: swarm.user_protocol_mut().peerset_debug_info(),
 		}
 	}
@@ -506,6 +532,24 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkServic
 			.unbounded_send(ServerToWorkerMsg::PutValue(key, value));
 	}
 
+	/// Sends a request to a peer over a request-response protocol.
+	///
+	/// The protocol must have been registered in the `request_response_protocols` of the
+	/// network configuration. The returned receiver yields the response, or the reason why the
+	/// request has failed.
+	pub fn request(
+		&self,
+		target: PeerId,
+		protocol: impl Into<Cow<'static, str>>,
+		request: Vec<u8>,
+	) -> oneshot::Receiver<Result<Vec<u8>, RequestFailure>> {
+		let (tx, rx) = oneshot::channel();
+		let _ = self
+			.to_worker
+			.unbounded_send(ServerToWorkerMsg::Request(target, protocol.into(), request, tx));
+		rx
+	}
+
 	/// Connect to unreserved peers and allow unreserved peers to connect.
 	pub fn accept_unreserved_peers(&self) {
 		self.peerset.set_reserved_only(false);
@@ -543,6 +587,24 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkServic
 			.unbounded_send(ServerToWorkerMsg::SyncFork(peers, hash, number));
 	}
 
+	/// Request a proof of the GRANDPA authority set changes enacted after the finalized block
+	/// `begin` from the given peer.
+	///
+	/// The returned future resolves to `None` if the peer can't provide the proof, and fails
+	/// if the peer disconnects before answering. Peers answer at most one request every
+	/// 10 seconds with a proof.
+	pub fn request_warp_sync_proof(
+		&self,
+		peer: PeerId,
+		begin: B::Hash,
+	) -> oneshot::Receiver<Option<Vec<u8>>> {
+		let (sender, receiver) = oneshot::channel();
+		let _ = self
+			.to_worker
+			.unbounded_send(ServerToWorkerMsg::RequestWarpSyncProof(peer, begin, sender));
+		receiver
+	}
+
 	/// Modify a peerset priority group.
 	pub fn set_priority_group(&self, group_id: String, peers: HashSet<Multiaddr>) -> Result<(), String> {
 		let peers = peers.into_iter().map(|p| {
@@ -636,6 +698,8 @@ enum ServerToWorkerMsg<B: BlockT, S: NetworkSpecialization<B>> {
 	PutValue(record::Key, Vec<u8>),
 	AddKnownAddress(PeerId, Multiaddr),
 	SyncFork(Vec<PeerId>, B::Hash, NumberFor<B>),
+	RequestWarpSyncProof(PeerId, B::Hash, oneshot::Sender<Option<Vec<u8>>>),
+	Request(PeerId, Cow<'static, str>, Vec<u8>, oneshot::Sender<Result<Vec<u8>, RequestFailure>>),
 }
 
 /// Main network worker. Must be polled in order for the network to advance.
@@ -716,6 +780,10 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> Stream for Ne
 					self.network_service.add_known_address(peer_id, addr),
 				ServerToWorkerMsg::SyncFork(peer_ids, hash, number) =>
 					self.network_service.user_protocol_mut().set_sync_fork_request(peer_ids, &hash, number),
+				ServerToWorkerMsg::RequestWarpSyncProof(peer_id, begin, sender) =>
+					self.network_service.user_protocol_mut().request_warp_sync_proof(peer_id, begin, sender),
+				ServerToWorkerMsg::Request(target, protocol, request, sender) =>
+					self.network_service.send_request(&target, &protocol, request, sender),
 			}
 		}
 
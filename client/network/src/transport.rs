@@ -14,7 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
+use crate::config::BandwidthLimits;
 use futures::prelude::*;
+use futures03::{compat::Compat, TryFutureExt as _};
+use futures_timer::Delay;
 use libp2p::{
 	InboundUpgradeExt, OutboundUpgradeExt, PeerId, Transport,
 	mplex, identity, secio, yamux, bandwidth, wasm_ext
@@ -24,7 +27,10 @@ use libp2p::{tcp, dns, websocket, noise};
 #[cfg(not(target_os = "unknown"))]
 use libp2p::core::{either::EitherError, either::EitherOutput};
 use libp2p::core::{self, upgrade, transport::boxed::Boxed, transport::OptionalTransport, muxing::StreamMuxerBox};
-use std::{io, sync::Arc, time::Duration, usize};
+use parking_lot::Mutex;
+use std::{cmp, io, io::Read, io::Write, sync::{Arc, Weak}, time::{Duration, Instant}, usize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 pub use self::bandwidth::BandwidthSinks;
 
@@ -34,12 +40,14 @@ pub use self::bandwidth::BandwidthSinks;
 /// addresses with the format `/memory/...` are allowed.
 ///
 /// Returns a `BandwidthSinks` object that allows querying the average bandwidth produced by all
-/// the connections spawned with this transport.
+/// the connections spawned with this transport, and a `BandwidthLimiter` enforcing
+/// `bandwidth_limits` on them that can be queried for the traffic of each peer.
 pub fn build_transport(
 	keypair: identity::Keypair,
 	memory_only: bool,
-	wasm_external_transport: Option<wasm_ext::ExtTransport>
-) -> (Boxed<(PeerId, StreamMuxerBox), io::Error>, Arc<bandwidth::BandwidthSinks>) {
+	wasm_external_transport: Option<wasm_ext::ExtTransport>,
+	bandwidth_limits: BandwidthLimits,
+) -> (Boxed<(PeerId, StreamMuxerBox), io::Error>, Arc<bandwidth::BandwidthSinks>, Arc<BandwidthLimiter>) {
 	// Build configuration objects for encryption mechanisms.
 	#[cfg(not(target_os = "unknown"))]
 	let noise_config = {
@@ -113,6 +121,14 @@ pub fn build_transport(
 			.and_then(|(id, stream)| Ok((stream, id)))
 	});
 
+	// Rate limiting, once the remote is known. The limiter sees the decrypted stream, hence the
+	// overhead of encryption is neither limited nor counted.
+	let limiter = Arc::new(BandwidthLimiter::new(bandwidth_limits));
+	let limiter2 = limiter.clone();
+	let transport = transport.map(move |(stream, peer_id), _| {
+		(limiter2.limit_connection(stream, &peer_id), peer_id)
+	});
+
 	// Multiplexing
 	let transport = transport.and_then(move |(stream, peer_id), endpoint| {
 			let peer_id2 = peer_id.clone();
@@ -128,5 +144,382 @@ pub fn build_transport(
 		.map_err(|err| io::Error::new(io::ErrorKind::Other, err))
 		.boxed();
 
-	(transport, sinks)
+	(transport, sinks, limiter)
+}
+
+/// Protocols whose traffic is accounted for separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BandwidthProtocol {
+	/// Block, state and warp sync requests and announces.
+	Sync,
+	/// Light client requests.
+	Light,
+	/// GRANDPA gossip.
+	Grandpa,
+	/// Gossip of the other consensus engines.
+	Consensus,
+	/// Transactions propagation.
+	Transactions,
+	/// Chain-specific messages.
+	Other,
+}
+
+impl BandwidthProtocol {
+	/// All the protocols, in the order they are accounted in.
+	pub const ALL: [BandwidthProtocol; 6] = [
+		BandwidthProtocol::Sync,
+		BandwidthProtocol::Light,
+		BandwidthProtocol::Grandpa,
+		BandwidthProtocol::Consensus,
+		BandwidthProtocol::Transactions,
+		BandwidthProtocol::Other,
+	];
+
+	/// Name of the protocol, as reported in the network state and metrics.
+	pub fn name(&self) -> &'static str {
+		match self {
+			BandwidthProtocol::Sync => "sync",
+			BandwidthProtocol::Light => "light",
+			BandwidthProtocol::Grandpa => "grandpa",
+			BandwidthProtocol::Consensus => "consensus",
+			BandwidthProtocol::Transactions => "transactions",
+			BandwidthProtocol::Other => "other",
+		}
+	}
+
+	fn index(&self) -> usize {
+		*self as usize
+	}
+}
+
+/// Total number of bytes of protocol messages exchanged with all peers, per protocol.
+///
+/// Only the encoded messages are counted, not the overhead of the transport.
+#[derive(Debug, Default)]
+pub struct ProtocolBandwidthSinks {
+	inbound: [AtomicU64; 6],
+	outbound: [AtomicU64; 6],
+}
+
+impl ProtocolBandwidthSinks {
+	/// Notes that `bytes` have been received for the given protocol.
+	pub fn inject_inbound(&self, protocol: BandwidthProtocol, bytes: usize) {
+		self.inbound[protocol.index()].fetch_add(bytes as u64, Ordering::Relaxed);
+	}
+
+	/// Notes that `bytes` have been sent for the given protocol.
+	pub fn inject_outbound(&self, protocol: BandwidthProtocol, bytes: usize) {
+		self.outbound[protocol.index()].fetch_add(bytes as u64, Ordering::Relaxed);
+	}
+
+	/// Total number of bytes received for the given protocol.
+	pub fn total_inbound(&self, protocol: BandwidthProtocol) -> u64 {
+		self.inbound[protocol.index()].load(Ordering::Relaxed)
+	}
+
+	/// Total number of bytes sent for the given protocol.
+	pub fn total_outbound(&self, protocol: BandwidthProtocol) -> u64 {
+		self.outbound[protocol.index()].load(Ordering::Relaxed)
+	}
+}
+
+/// Direction of the traffic.
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+	Download,
+	Upload,
+}
+
+/// Token bucket allowing up to `rate` bytes per second, with bursts of up to one second.
+#[derive(Debug)]
+struct TokenBucket {
+	rate: u64,
+	available: u64,
+	last_refill: Instant,
+}
+
+impl TokenBucket {
+	fn new(rate: u64, now: Instant) -> Self {
+		let rate = cmp::max(rate, 1);
+		TokenBucket { rate, available: rate, last_refill: now }
+	}
+
+	/// Returns the number of bytes that can be transferred right now.
+	fn available(&mut self, now: Instant) -> u64 {
+		let elapsed = now.duration_since(self.last_refill).as_micros();
+		let added = elapsed * u128::from(self.rate) / 1_000_000;
+		if added > 0 {
+			self.available = cmp::min(self.available.saturating_add(added as u64), self.rate);
+			// Don't lose the time spent on the fraction of a byte that wasn't added.
+			self.last_refill = if self.available == self.rate {
+				now
+			} else {
+				self.last_refill + Duration::from_micros((added * 1_000_000 / u128::from(self.rate)) as u64)
+			};
+		}
+		self.available
+	}
+
+	fn consume(&mut self, bytes: usize) {
+		self.available = self.available.saturating_sub(bytes as u64);
+	}
+
+	/// Time after which enough bytes are available to be worth waking up for.
+	fn wait_time(&self) -> Duration {
+		let wanted = cmp::max(self.rate / 20, 1);
+		Duration::from_micros(wanted.saturating_mul(1_000_000) / self.rate)
+	}
+}
+
+/// Traffic of a single peer, shared between all the connections to it.
+#[derive(Debug)]
+struct PeerBandwidth {
+	inbound: AtomicU64,
+	outbound: AtomicU64,
+	download: Option<Mutex<TokenBucket>>,
+	upload: Option<Mutex<TokenBucket>>,
+}
+
+/// Enforces the bandwidth limits on the connections of the transport and counts the traffic of
+/// each peer.
+pub struct BandwidthLimiter {
+	limits: BandwidthLimits,
+	download: Option<Mutex<TokenBucket>>,
+	upload: Option<Mutex<TokenBucket>>,
+	peers: Mutex<HashMap<PeerId, Weak<PeerBandwidth>>>,
+}
+
+impl BandwidthLimiter {
+	/// Creates a limiter enforcing the given limits.
+	pub fn new(limits: BandwidthLimits) -> Self {
+		let now = Instant::now();
+		BandwidthLimiter {
+			download: limits.download.map(|rate| Mutex::new(TokenBucket::new(rate, now))),
+			upload: limits.upload.map(|rate| Mutex::new(TokenBucket::new(rate, now))),
+			limits,
+			peers: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Total number of bytes received from and sent to the given peer over the connections
+	/// currently open with it, or `None` if there is none.
+	pub fn peer_totals(&self, peer_id: &PeerId) -> Option<(u64, u64)> {
+		let peer = self.peers.lock().get(peer_id)?.upgrade()?;
+		Some((peer.inbound.load(Ordering::Relaxed), peer.outbound.load(Ordering::Relaxed)))
+	}
+
+	/// Wraps a connection to the given peer.
+	fn limit_connection<TInner>(self: &Arc<Self>, inner: TInner, peer_id: &PeerId) -> LimitedConnection<TInner> {
+		let mut peers = self.peers.lock();
+		let peer = match peers.get(peer_id).and_then(Weak::upgrade) {
+			Some(peer) => peer,
+			None => {
+				let now = Instant::now();
+				let peer = Arc::new(PeerBandwidth {
+					inbound: AtomicU64::new(0),
+					outbound: AtomicU64::new(0),
+					download: self.limits.peer_download.map(|rate| Mutex::new(TokenBucket::new(rate, now))),
+					upload: self.limits.peer_upload.map(|rate| Mutex::new(TokenBucket::new(rate, now))),
+				});
+				peers.retain(|_, peer| peer.upgrade().is_some());
+				peers.insert(peer_id.clone(), Arc::downgrade(&peer));
+				peer
+			},
+		};
+
+		LimitedConnection {
+			inner,
+			peer,
+			limiter: self.clone(),
+			read_delay: None,
+			write_delay: None,
+		}
+	}
+
+	/// Returns how many bytes can be transferred right now, or how long to wait before trying
+	/// again if none.
+	fn allowance(&self, peer: &PeerBandwidth, direction: Direction) -> Result<usize, Duration> {
+		let now = Instant::now();
+		let (global, peer) = match direction {
+			Direction::Download => (&self.download, &peer.download),
+			Direction::Upload => (&self.upload, &peer.upload),
+		};
+		let mut allowed = usize::MAX;
+		for bucket in global.iter().chain(peer.iter()) {
+			let mut bucket = bucket.lock();
+			match bucket.available(now) {
+				0 => return Err(bucket.wait_time()),
+				available => allowed = cmp::min(allowed, available as usize),
+			}
+		}
+		Ok(allowed)
+	}
+
+	/// Notes that `bytes` have been transferred.
+	fn consume(&self, peer: &PeerBandwidth, direction: Direction, bytes: usize) {
+		let (global, peer_bucket, total) = match direction {
+			Direction::Download => (&self.download, &peer.download, &peer.inbound),
+			Direction::Upload => (&self.upload, &peer.upload, &peer.outbound),
+		};
+		total.fetch_add(bytes as u64, Ordering::Relaxed);
+		for bucket in global.iter().chain(peer_bucket.iter()) {
+			bucket.lock().consume(bytes);
+		}
+	}
+}
+
+/// Wraps around an `AsyncRead + AsyncWrite` and delays reads and writes that would exceed the
+/// bandwidth limits.
+pub struct LimitedConnection<TInner> {
+	inner: TInner,
+	peer: Arc<PeerBandwidth>,
+	limiter: Arc<BandwidthLimiter>,
+	read_delay: Option<Compat<Delay>>,
+	write_delay: Option<Compat<Delay>>,
+}
+
+impl<TInner> LimitedConnection<TInner> {
+	/// Returns how many bytes can be transferred right now. If none, registers the current task
+	/// to be woken up once some can and returns `WouldBlock`.
+	fn poll_allowance(&mut self, direction: Direction) -> io::Result<usize> {
+		let delay = match direction {
+			Direction::Download => &mut self.read_delay,
+			Direction::Upload => &mut self.write_delay,
+		};
+		loop {
+			if let Some(timer) = delay.as_mut() {
+				if let Async::NotReady = timer.poll()? {
+					return Err(io::ErrorKind::WouldBlock.into())
+				}
+				*delay = None;
+			}
+			match self.limiter.allowance(&self.peer, direction) {
+				Ok(allowed) => return Ok(allowed),
+				Err(wait) => *delay = Some(Delay::new(wait).compat()),
+			}
+		}
+	}
+}
+
+impl<TInner: Read> Read for LimitedConnection<TInner> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let allowed = self.poll_allowance(Direction::Download)?;
+		let len = cmp::min(buf.len(), allowed);
+		let num_bytes = self.inner.read(&mut buf[..len])?;
+		self.limiter.consume(&self.peer, Direction::Download, num_bytes);
+		Ok(num_bytes)
+	}
+}
+
+impl<TInner: tokio_io::AsyncRead> tokio_io::AsyncRead for LimitedConnection<TInner> {
+	unsafe fn prepare_uninitialized_buffer(&self, buf: &mut [u8]) -> bool {
+		self.inner.prepare_uninitialized_buffer(buf)
+	}
+}
+
+impl<TInner: Write> Write for LimitedConnection<TInner> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let allowed = self.poll_allowance(Direction::Upload)?;
+		let len = cmp::min(buf.len(), allowed);
+		let num_bytes = self.inner.write(&buf[..len])?;
+		self.limiter.consume(&self.peer, Direction::Upload, num_bytes);
+		Ok(num_bytes)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
+
+impl<TInner: tokio_io::AsyncWrite> tokio_io::AsyncWrite for LimitedConnection<TInner> {
+	fn shutdown(&mut self) -> Poll<(), io::Error> {
+		self.inner.shutdown()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{BandwidthLimiter, TokenBucket};
+	use crate::config::BandwidthLimits;
+	use futures::{future, Future};
+	use libp2p::PeerId;
+	use std::{io::{self, Cursor, Read, Write}, sync::Arc, time::{Duration, Instant}};
+
+	#[test]
+	fn token_bucket_refills_at_rate() {
+		let start = Instant::now();
+		let mut bucket = TokenBucket::new(1000, start);
+		assert_eq!(bucket.available(start), 1000);
+
+		bucket.consume(1000);
+		assert_eq!(bucket.available(start), 0);
+		assert_eq!(bucket.wait_time(), Duration::from_millis(50));
+		assert_eq!(bucket.available(start + Duration::from_millis(250)), 250);
+
+		// Bursts are capped to one second worth of bytes.
+		assert_eq!(bucket.available(start + Duration::from_secs(10)), 1000);
+	}
+
+	#[test]
+	fn token_bucket_keeps_fractions() {
+		let start = Instant::now();
+		let mut bucket = TokenBucket::new(3, start);
+		bucket.consume(3);
+		// A third of a second adds one byte, two thirds add another one.
+		assert_eq!(bucket.available(start + Duration::from_millis(500)), 1);
+		assert_eq!(bucket.available(start + Duration::from_millis(700)), 2);
+	}
+
+	#[test]
+	fn limited_connection_throttles_reads_and_writes() {
+		let limiter = Arc::new(BandwidthLimiter::new(BandwidthLimits {
+			peer_download: Some(10),
+			upload: Some(5),
+			..Default::default()
+		}));
+		let mut connection = limiter.limit_connection(Cursor::new(vec![0; 100]), &PeerId::random());
+
+		// The delays are polled, so run in a task.
+		future::lazy(move || {
+			let mut buf = [0; 100];
+			assert_eq!(connection.read(&mut buf).unwrap(), 10);
+			assert_eq!(connection.read(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+			assert_eq!(connection.write(&buf).unwrap(), 5);
+			assert_eq!(connection.write(&buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+			Ok::<_, ()>(())
+		}).wait().unwrap();
+	}
+
+	#[test]
+	fn limits_and_traffic_are_shared_between_connections() {
+		let limiter = Arc::new(BandwidthLimiter::new(BandwidthLimits {
+			peer_download: Some(10),
+			download: Some(15),
+			..Default::default()
+		}));
+		let (alice, bob) = (PeerId::random(), PeerId::random());
+		let mut alice1 = limiter.limit_connection(Cursor::new(vec![0; 100]), &alice);
+		let mut alice2 = limiter.limit_connection(Cursor::new(vec![0; 100]), &alice);
+		let mut bob1 = limiter.limit_connection(Cursor::new(vec![0; 100]), &bob);
+
+		future::lazy(move || {
+			let mut buf = [0; 100];
+			// The connections to a peer share its limit...
+			assert_eq!(alice1.read(&mut buf[..4]).unwrap(), 4);
+			assert_eq!(alice2.read(&mut buf).unwrap(), 6);
+			assert_eq!(alice1.read(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+			// ...and all the peers share the global one.
+			assert_eq!(bob1.read(&mut buf).unwrap(), 5);
+			assert_eq!(bob1.read(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+			assert_eq!(alice1.write(&buf[..3]).unwrap(), 3);
+
+			assert_eq!(limiter.peer_totals(&alice), Some((10, 3)));
+			assert_eq!(limiter.peer_totals(&bob), Some((5, 0)));
+
+			// The traffic is only reported while a connection is open.
+			drop((alice1, alice2));
+			assert_eq!(limiter.peer_totals(&alice), None);
+			Ok::<_, ()>(())
+		}).wait().unwrap();
+	}
 }
@@ -328,6 +328,11 @@ impl<TBl, TRtApi, TCfg, TGen, TCSExt, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TNet
 		self.select_chain.as_ref()
 	}
 
+	/// Returns a reference to the keystore that was opened by this builder.
+	pub fn keystore(&self) -> &Arc<RwLock<Keystore>> {
+		&self.keystore
+	}
+
 	/// Defines which head-of-chain strategy to use.
 	pub fn with_opt_select_chain<USc>(
 		self,
@@ -915,7 +920,7 @@ ServiceBuilder<
 		let self_pid = get_current_pid().ok();
 		let (state_tx, state_rx) = mpsc::unbounded::<(NetworkStatus<_>, NetworkState)>();
 		network_status_sinks.lock().push(std::time::Duration::from_millis(5000), state_tx);
-		let tel_task = state_rx.for_each(move |(net_status, _)| {
+		let tel_task = state_rx.for_each(move |(net_status, net_state)| {
 			let info = client_.info();
 			let best_number = info.chain.best_number.saturated_into::<u64>();
 			let best_hash = info.chain.best_hash;
@@ -965,6 +970,7 @@ ServiceBuilder<
 				"bandwidth_upload" => bandwidth_upload,
 				"used_state_cache_size" => used_state_cache_size,
 			);
+			record_protocol_bandwidth(&net_state);
 
 			Ok(())
 		}).select(exit.clone().map(Ok).compat()).then(|_| Ok(()));
@@ -1151,3 +1157,21 @@ ServiceBuilder<
 		})
 	}
 }
+
+/// Records the traffic of each protocol since the node started in the metrics.
+#[cfg(not(target_os = "unknown"))]
+fn record_protocol_bandwidth(net_state: &network::NetworkState) {
+	let protocol_metrics = net_state.bandwidth_by_protocol.iter()
+		.flat_map(|(protocol, bandwidth)| vec![
+			(format!("bandwidth_total_download_{}", protocol), bandwidth.total_inbound as f32),
+			(format!("bandwidth_total_upload_{}", protocol), bandwidth.total_outbound as f32),
+		])
+		.collect::<Vec<_>>();
+	let _ = grafana_data_source::record_metrics_slice(
+		&protocol_metrics.iter().map(|(key, value)| (key.as_str(), *value)).collect::<Vec<_>>()
+	);
+}
+
+/// Records the traffic of each protocol since the node started in the metrics.
+#[cfg(target_os = "unknown")]
+fn record_protocol_bandwidth(_: &network::NetworkState) {}
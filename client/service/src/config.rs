@@ -92,6 +92,10 @@ pub struct Configuration<C, G, E = NoExtension> {
 	pub force_authoring: bool,
 	/// Disable GRANDPA when running in validator mode
 	pub disable_grandpa: bool,
+	/// Start the GRANDPA voter with its votes paused
+	pub grandpa_pause_voting: bool,
+	/// Expose the RPC methods changing the votes of the GRANDPA voter
+	pub grandpa_voting_rpc: bool,
 	/// Node keystore's password
 	pub keystore_password: Option<Protected<String>>,
 	/// Development key seed.
@@ -161,6 +165,8 @@ impl<C, G, E> Configuration<C, G, E> where
 			sentry_mode: false,
 			force_authoring: false,
 			disable_grandpa: false,
+			grandpa_pause_voting: false,
+			grandpa_voting_rpc: false,
 			keystore_password: None,
 			dev_key_seed: None,
 			tracing_targets: Default::default(),
@@ -164,6 +164,9 @@ fn node_config<G, E: Clone> (
 			wasm_external_transport: None,
 		},
 		max_parallel_downloads: NetworkConfiguration::default().max_parallel_downloads,
+		sync_mode: NetworkConfiguration::default().sync_mode,
+		bandwidth_limits: Default::default(),
+		request_response_protocols: Vec::new(),
 	};
 
 	Configuration {
@@ -200,6 +203,8 @@ fn node_config<G, E: Clone> (
 		sentry_mode: false,
 		force_authoring: false,
 		disable_grandpa: false,
+		grandpa_pause_voting: false,
+		grandpa_voting_rpc: false,
 		dev_key_seed: key_seed,
 		tracing_targets: None,
 		tracing_receiver: Default::default(),
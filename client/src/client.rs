@@ -33,7 +33,7 @@ use primitives::{
 };
 use sc_telemetry::{telemetry, SUBSTRATE_INFO};
 use sp_runtime::{
-	Justification, BuildStorage,
+	Justification, BuildStorage, StorageOverlay, ChildrenStorageOverlay,
 	generic::{BlockId, SignedBlock, DigestItem},
 	traits::{
 		Block as BlockT, Header as HeaderT, Zero, NumberFor,
@@ -42,7 +42,8 @@ use sp_runtime::{
 };
 use state_machine::{
 	DBValue, Backend as StateBackend, ChangesTrieAnchorBlockId, ExecutionStrategy, ExecutionManager,
-	prove_read, prove_child_read, ChangesTrieRootsStorage, ChangesTrieStorage,
+	prove_read, prove_child_read, prove_range_read_with_size, read_range_proof_check,
+	ChangesTrieRootsStorage, ChangesTrieStorage,
 	ChangesTrieTransaction, ChangesTrieConfigurationRange, key_changes, key_changes_proof,
 	OverlayedChanges, BackendTrustLevel, StorageProof, merge_storage_proofs,
 };
@@ -344,6 +345,60 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 				.map_err(Into::into))
 	}
 
+	/// Reads a range of storage entries at a given block, starting right after `start_key`,
+	/// returning the entries, whether the end of the storage was reached and the read proof.
+	///
+	/// Reads from the child trie at `storage_key` if given, otherwise from the top trie.
+	pub fn read_range_proof(
+		&self,
+		id: &BlockId<Block>,
+		storage_key: Option<&[u8]>,
+		start_key: &[u8],
+		size_limit: usize,
+	) -> sp_blockchain::Result<(Vec<(Vec<u8>, Vec<u8>)>, bool, StorageProof)> {
+		self.state_at(id)
+			.and_then(|state| prove_range_read_with_size(state, storage_key, size_limit, start_key)
+				.map(|(proof, entries, complete)| (entries, complete, proof))
+				.map_err(Into::into))
+	}
+
+	/// Checks a range of storage entries returned by `read_range_proof` against the given state root.
+	pub fn check_range_proof(
+		&self,
+		root: Block::Hash,
+		proof: StorageProof,
+		storage_key: Option<&[u8]>,
+		start_key: &[u8],
+		entries: &[(Vec<u8>, Vec<u8>)],
+		complete: bool,
+	) -> sp_blockchain::Result<()> {
+		read_range_proof_check::<Blake2Hasher>(root, proof, storage_key, start_key, entries, complete)
+			.map_err(Into::into)
+	}
+
+	/// Imports the whole state of an already known block, e.g. one downloaded by state sync.
+	///
+	/// The state root is checked against the header of the block before anything is written.
+	pub fn import_state(
+		&self,
+		hash: Block::Hash,
+		top: StorageOverlay,
+		children: ChildrenStorageOverlay,
+	) -> sp_blockchain::Result<()> {
+		let header = self.backend.blockchain().expect_header(BlockId::Hash(hash))?;
+		self.lock_import_and_run(|operation| {
+			self.backend.begin_state_operation(&mut operation.op, BlockId::Hash(Default::default()))?;
+			let state_root = operation.op.reset_storage(top, children)?;
+			if state_root != *header.state_root() {
+				warn!("Imported state of block {} does not match its state root", hash);
+				return Err(sp_blockchain::Error::InvalidStateRoot);
+			}
+			info!("Imported state of block #{} ({})", header.number(), hash);
+			operation.op.set_block_data(header, None, None, NewBlockState::Normal)?;
+			Ok(())
+		})
+	}
+
 	/// Execute a call to a contract on top of state in a block of given hash
 	/// AND returning execution proof.
 	///
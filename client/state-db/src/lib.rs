@@ -280,6 +280,33 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 		Ok(commit)
 	}
 
+	pub fn insert_canonical_block<E: fmt::Debug>(&mut self, hash: &BlockHash, number: u64, mut changeset: ChangeSet<Key>) -> Result<CommitSet<Key>, Error<E>> {
+		changeset.deleted.clear();
+		let mut commit = CommitSet {
+			data: changeset,
+			meta: Default::default(),
+		};
+		if self.mode == PruningMode::ArchiveAll {
+			return Ok(commit)
+		}
+		self.non_canonical.set_canonical(hash, number, &mut commit)?;
+		if let Some(ref mut pruning) = self.pruning {
+			pruning.reset(number, &mut commit);
+			pruning.note_canonical(&hash, &mut commit);
+		}
+		self.prune(&mut commit);
+		Ok(commit)
+	}
+
+	pub fn canonicalize_stateless_block<E: fmt::Debug>(&mut self, hash: &BlockHash) -> Result<CommitSet<Key>, Error<E>> {
+		if self.mode != PruningMode::ArchiveAll &&
+			(self.non_canonical.have_block(hash) || !self.non_canonical.top_level().is_empty())
+		{
+			return Err(Error::InvalidBlock)
+		}
+		Ok(CommitSet::default())
+	}
+
 	pub fn best_canonical(&self) -> Option<u64> {
 		return self.non_canonical.last_canonicalized_block_number()
 	}
@@ -423,6 +450,19 @@ impl<BlockHash: Hash, Key: Hash> StateDb<BlockHash, Key> {
 		self.db.write().canonicalize_block(hash)
 	}
 
+	/// Finalize a block imported without state, e.g. a header downloaded by state sync. There is
+	/// nothing to commit, but this fails if blocks with state are pending canonicalization at the
+	/// same height, as their state would never be canonicalized nor discarded.
+	pub fn canonicalize_stateless_block<E: fmt::Debug>(&self, hash: &BlockHash) -> Result<CommitSet<Key>, Error<E>> {
+		self.db.write().canonicalize_stateless_block(hash)
+	}
+
+	/// Add a block with a complete state and make it canonical right away, e.g. a block
+	/// whose state was downloaded as a whole. Expects no non-canonical blocks to be present.
+	pub fn insert_canonical_block<E: fmt::Debug>(&self, hash: &BlockHash, number: u64, changeset: ChangeSet<Key>) -> Result<CommitSet<Key>, Error<E>> {
+		self.db.write().insert_canonical_block(hash, number, changeset)
+	}
+
 	/// Prevents pruning of specified block and its descendants.
 	pub fn pin(&self, hash: &BlockHash) -> Result<(), PinError> {
 		self.db.write().pin(hash)
@@ -607,4 +647,60 @@ mod tests {
 		let state_db: Result<StateDb<H256, H256>, _> = StateDb::new(new_mode, &db);
 		assert!(state_db.is_err());
 	}
+
+	#[test]
+	fn inserts_canonical_block_state() {
+		let mut db = make_db(&[]);
+		let state_db = StateDb::new(PruningMode::Constrained(Constraints {
+			max_blocks: Some(1),
+			max_mem: None,
+		}), &db).unwrap();
+		db.commit(
+			&state_db
+			.insert_canonical_block::<io::Error>(
+				&H256::from_low_u64_be(10),
+				10,
+				make_changeset(&[1, 2], &[]),
+			)
+			.unwrap(),
+		);
+		state_db.apply_pending();
+		assert_eq!(state_db.best_canonical(), Some(10));
+		assert!(!state_db.is_pruned(&H256::from_low_u64_be(10), 10));
+
+		db.commit(
+			&state_db
+			.insert_block::<io::Error>(
+				&H256::from_low_u64_be(11),
+				11,
+				&H256::from_low_u64_be(10),
+				make_changeset(&[3], &[1]),
+			)
+			.unwrap(),
+		);
+		state_db.apply_pending();
+		db.commit(&state_db.canonicalize_block::<io::Error>(&H256::from_low_u64_be(11)).unwrap());
+		state_db.apply_pending();
+		assert!(state_db.is_pruned(&H256::from_low_u64_be(10), 10));
+		assert!(!state_db.is_pruned(&H256::from_low_u64_be(11), 11));
+		assert!(db.data_eq(&make_db(&[1, 2, 3])));
+	}
+
+	#[test]
+	fn canonicalizes_stateless_block_only_without_pending_state() {
+		// block 4 has state and is pending canonicalization.
+		let (_, state_db) = make_test_db(PruningMode::ArchiveCanonical);
+		assert!(
+			state_db.canonicalize_stateless_block::<io::Error>(&H256::from_low_u64_be(41)).is_err()
+		);
+
+		let mut db = make_db(&[]);
+		let state_db: StateDb<H256, H256> = StateDb::new(PruningMode::ArchiveCanonical, &db).unwrap();
+		db.commit(
+			&state_db
+			.canonicalize_stateless_block::<io::Error>(&H256::from_low_u64_be(1))
+			.unwrap(),
+		);
+		assert!(db.data_eq(&make_db(&[])));
+	}
 }
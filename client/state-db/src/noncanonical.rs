@@ -240,6 +240,22 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 		Ok(commit)
 	}
 
+	/// Mark a block as the last canonicalized one without inserting it into the overlay, e.g. a block
+	/// whose state is written as a whole. Expects the overlay to be empty.
+	pub fn set_canonical<E: fmt::Debug>(&mut self, hash: &BlockHash, number: u64, commit: &mut CommitSet<Key>) -> Result<(), Error<E>> {
+		if !self.levels.is_empty() || !self.pending_canonicalizations.is_empty() {
+			return Err(Error::InvalidBlock);
+		}
+		if number < self.front_block_number() {
+			return Err(Error::InvalidBlockNumber);
+		}
+		trace!(target: "state-db", "Setting canonical block #{} ({:?})", number, hash);
+		let last_canonicalized = (hash.clone(), number);
+		commit.meta.inserted.push((to_meta_key(LAST_CANONICAL, &()), last_canonicalized.encode()));
+		self.last_canonicalized = Some(last_canonicalized);
+		Ok(())
+	}
+
 	fn discard_journals(
 		&self,
 		level_index: usize,
@@ -153,6 +153,24 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 		}
 	}
 
+	/// Drop all blocks from the window and restart it at block `number`. Adds changes to `commit`.
+	///
+	/// Nodes pending deletion in the dropped blocks are left in the database, as they may still
+	/// be referenced by a state that is written alongside.
+	pub fn reset(&mut self, number: u64, commit: &mut CommitSet<Key>) {
+		trace!(target: "state-db", "Resetting pruning window at #{} ({} blocks dropped)", number, self.death_rows.len());
+		for row in self.death_rows.drain(..) {
+			commit.meta.deleted.push(row.journal_key);
+		}
+		self.death_index.clear();
+		self.pending_number = number;
+		self.pending_canonicalizations = 0;
+		self.pending_prunings = 0;
+		if number > 0 {
+			commit.meta.inserted.push((to_meta_key(LAST_PRUNED, &()), (number - 1).encode()));
+		}
+	}
+
 	/// Add a change set to the window. Creates a journal record and pushes it to `commit`
 	pub fn note_canonical(&mut self, hash: &BlockHash, commit: &mut CommitSet<Key>) {
 		trace!(target: "state-db", "Adding to pruning window: {:?} ({} inserted, {} deleted)", hash, commit.data.inserted.len(), commit.data.deleted.len());
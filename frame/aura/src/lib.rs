@@ -50,12 +50,13 @@ use pallet_timestamp;
 use sp_std::{result, prelude::*};
 use codec::{Encode, Decode};
 use support::{
-	decl_storage, decl_module, Parameter, traits::{Get, FindAuthor},
+	decl_storage, decl_module, Parameter, traits::{Get, FindAuthor, VerifySeal},
 	ConsensusEngineId,
 };
 use sp_runtime::{
 	RuntimeAppPublic,
-	traits::{SaturatedConversion, Saturating, Zero, Member, IsMember}, generic::DigestItem,
+	traits::{SaturatedConversion, Saturating, Zero, Member, IsMember, Header as HeaderT},
+	generic::DigestItem,
 };
 use sp_timestamp::OnTimestampSet;
 use inherents::{InherentIdentifier, InherentData, ProvideInherent, MakeFatalError};
@@ -79,6 +80,16 @@ decl_storage! {
 
 		/// The current authorities
 		pub Authorities get(fn authorities): Vec<T::AuthorityId>;
+
+		/// The slot of the block which enacted the current authorities, which own the slots
+		/// after it.
+		pub AuthoritiesSince get(fn authorities_since): u64;
+
+		/// The previous authorities, kept to verify uncles from the slots they owned.
+		pub PreviousAuthorities get(fn previous_authorities): Vec<T::AuthorityId>;
+
+		/// The slot of the block which enacted the previous authorities.
+		pub PreviousAuthoritiesSince get(fn previous_authorities_since): u64;
 	}
 	add_extra_genesis {
 		config(authorities): Vec<T::AuthorityId>;
@@ -92,7 +103,12 @@ decl_module! {
 
 impl<T: Trait> Module<T> {
 	fn change_authorities(new: Vec<T::AuthorityId>) {
+		// the new authorities own the slots after the current one.
+		let since = Self::current_slot().unwrap_or_default();
+		<PreviousAuthorities<T>>::put(<Authorities<T>>::get());
+		PreviousAuthoritiesSince::put(AuthoritiesSince::get());
 		<Authorities<T>>::put(&new);
+		AuthoritiesSince::put(since);
 
 		let log: DigestItem<T::Hash> = DigestItem::Consensus(
 			AURA_ENGINE_ID,
@@ -101,6 +117,16 @@ impl<T: Trait> Module<T> {
 		<system::Module<T>>::deposit_log(log.into());
 	}
 
+	// the slot of the current block, read from its pre-runtime digest.
+	fn current_slot() -> Option<u64> {
+		<system::Module<T>>::digest()
+			.logs
+			.iter()
+			.filter_map(|log| log.as_pre_runtime())
+			.find(|(id, _)| *id == AURA_ENGINE_ID)
+			.and_then(|(_, mut data)| u64::decode(&mut data).ok())
+	}
+
 	fn initialize_authorities(authorities: &[T::AuthorityId]) {
 		if !authorities.is_empty() {
 			assert!(<Authorities<T>>::get().is_empty(), "Authorities are already initialized!");
@@ -163,6 +189,54 @@ impl<T: Trait> FindAuthor<u32> for Module<T> {
 	}
 }
 
+/// Verifies the seal of uncles, which must be signed by the authority owning their slot in
+/// the set of that slot, which must be the current or the previous one, and returns the key
+/// of their author.
+impl<T: Trait> VerifySeal<T::Header, T::AuthorityId> for Module<T> {
+	fn verify_seal(header: &T::Header) -> Result<Option<T::AuthorityId>, &'static str> {
+		let mut header = header.clone();
+		let seal = header.digest_mut().pop().ok_or("Header unsealed")?;
+		let signature = seal.as_seal()
+			.filter(|(id, _)| *id == AURA_ENGINE_ID)
+			.ok_or("Header unsealed")
+			.and_then(|(_, signature)| {
+				<T::AuthorityId as RuntimeAppPublic>::Signature::decode(&mut &signature[..])
+					.map_err(|_| "Header with invalid Aura seal")
+			})?;
+
+		let slot_number = header.digest().logs().iter()
+			.filter_map(|d| d.as_pre_runtime())
+			.find(|(id, _)| *id == AURA_ENGINE_ID)
+			.and_then(|(_, mut data)| u64::decode(&mut data).ok())
+			.ok_or("Header without Aura pre-digest")?;
+
+		if Self::current_slot().map_or(true, |current| slot_number > current) {
+			return Err("Header from a future slot");
+		}
+
+		let authorities = if slot_number > Self::authorities_since() {
+			Self::authorities()
+		} else if slot_number > Self::previous_authorities_since() {
+			Self::previous_authorities()
+		} else {
+			return Err("Header from an unknown authority set");
+		};
+
+		if authorities.is_empty() {
+			return Err("No Aura authorities");
+		}
+		let author = &authorities[(slot_number % authorities.len() as u64) as usize];
+
+		// the seal signs the hash of the header without it.
+		let pre_hash = header.hash();
+		if !author.verify(&pre_hash, &signature) {
+			return Err("Header with invalid Aura seal");
+		}
+
+		Ok(Some(author.clone()))
+	}
+}
+
 impl<T: Trait> IsMember<T::AuthorityId> for Module<T> {
 	fn is_member(authority_id: &T::AuthorityId) -> bool {
 		Self::authorities()
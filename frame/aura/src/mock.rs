@@ -26,7 +26,7 @@ use sp_runtime::{
 };
 use support::{impl_outer_origin, parameter_types, weights::Weight};
 use sp_io;
-use primitives::H256;
+use primitives::{H256, Pair, ed25519};
 
 impl_outer_origin!{
 	pub enum Origin for Test {}
@@ -80,4 +80,10 @@ pub fn new_test_ext(authorities: Vec<u64>) -> sp_io::TestExternalities {
 	t.into()
 }
 
+/// The key pair of the given authority, for tests that need real signatures.
+pub fn authority_pair(authority: u64) -> ed25519::Pair {
+	ed25519::Pair::from_seed(&[authority as u8; 32])
+}
+
+pub type System = system::Module<Test>;
 pub type Aura = Module<Test>;
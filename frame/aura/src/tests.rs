@@ -18,7 +18,40 @@
 
 #![cfg(test)]
 
-use crate::mock::{Aura, new_test_ext};
+use codec::Encode;
+use primitives::{Pair, ed25519};
+use sp_consensus_aura::{AURA_ENGINE_ID, ed25519::AuthorityId};
+use sp_runtime::{testing::{Digest, DigestItem, Header}, traits::Header as HeaderT};
+use support::{StorageValue, traits::VerifySeal};
+use crate::{Authorities, mock::{Aura, System, Test, authority_pair, new_test_ext}};
+
+fn authority(authority: u64) -> AuthorityId {
+	AuthorityId::from(authority_pair(authority).public())
+}
+
+fn initialize_block(number: u64, slot_number: u64) {
+	let pre_digest = DigestItem::PreRuntime(AURA_ENGINE_ID, slot_number.encode());
+	System::initialize(
+		&number,
+		&Default::default(),
+		&Default::default(),
+		&Digest { logs: vec![pre_digest] },
+	);
+}
+
+fn sealed_uncle(slot_number: u64, signer: &ed25519::Pair) -> Header {
+	let pre_digest = DigestItem::PreRuntime(AURA_ENGINE_ID, slot_number.encode());
+	let mut header = Header::new(
+		1,
+		Default::default(),
+		Default::default(),
+		Default::default(),
+		Digest { logs: vec![pre_digest] },
+	);
+	let signature = signer.sign(header.hash().as_ref());
+	header.digest_mut().push(DigestItem::Seal(AURA_ENGINE_ID, signature.encode()));
+	header
+}
 
 #[test]
 fn initial_values() {
@@ -27,3 +60,53 @@ fn initial_values() {
 		assert_eq!(Aura::authorities().len(), 4);
 	});
 }
+
+#[test]
+fn uncle_seals_are_verified() {
+	new_test_ext(vec![]).execute_with(|| {
+		<Authorities<Test>>::put((0..4).map(authority).collect::<Vec<_>>());
+		initialize_block(1, 10);
+
+		// slot 10 is owned by the authority at index 10 % 4.
+		let uncle = sealed_uncle(10, &authority_pair(2));
+		assert_eq!(Aura::verify_seal(&uncle), Ok(Some(authority(2))));
+
+		let uncle = sealed_uncle(10, &authority_pair(1));
+		assert_eq!(Aura::verify_seal(&uncle), Err("Header with invalid Aura seal"));
+
+		let uncle = sealed_uncle(11, &authority_pair(3));
+		assert_eq!(Aura::verify_seal(&uncle), Err("Header from a future slot"));
+
+		let mut uncle = sealed_uncle(10, &authority_pair(2));
+		uncle.digest_mut().pop();
+		assert_eq!(Aura::verify_seal(&uncle), Err("Header unsealed"));
+	});
+}
+
+#[test]
+fn uncles_are_verified_against_the_set_of_their_slot() {
+	new_test_ext(vec![]).execute_with(|| {
+		<Authorities<Test>>::put(vec![authority(0), authority(1)]);
+		initialize_block(1, 10);
+		Aura::change_authorities(vec![authority(2), authority(3)]);
+		initialize_block(2, 12);
+
+		// slot 10 was still owned by the previous set.
+		let uncle = sealed_uncle(10, &authority_pair(0));
+		assert_eq!(Aura::verify_seal(&uncle), Ok(Some(authority(0))));
+
+		let uncle = sealed_uncle(11, &authority_pair(3));
+		assert_eq!(Aura::verify_seal(&uncle), Ok(Some(authority(3))));
+
+		let uncle = sealed_uncle(11, &authority_pair(1));
+		assert_eq!(Aura::verify_seal(&uncle), Err("Header with invalid Aura seal"));
+
+		Aura::change_authorities(vec![authority(4)]);
+
+		let uncle = sealed_uncle(10, &authority_pair(0));
+		assert_eq!(Aura::verify_seal(&uncle), Err("Header from an unknown authority set"));
+
+		let uncle = sealed_uncle(11, &authority_pair(3));
+		assert_eq!(Aura::verify_seal(&uncle), Ok(Some(authority(3))));
+	});
+}
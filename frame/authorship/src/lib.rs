@@ -236,10 +236,11 @@ impl<T: Trait> Module<T> {
 			let author = Self::verify_uncle(&uncle, prev_uncles, &mut acc)?;
 			let hash = uncle.hash();
 
-			T::EventHandler::note_uncle(
-				author.clone().unwrap_or_default(),
-				now - uncle.number().clone(),
-			);
+			// uncles whose author can't be identified are tracked, but nobody is rewarded
+			// for them.
+			if let Some(author) = author.clone() {
+				T::EventHandler::note_uncle(author, now - uncle.number().clone());
+			}
 			uncles.push(UncleEntryItem::Uncle(hash, author));
 		}
 
@@ -372,6 +373,7 @@ impl<T: Trait> ProvideInherent for Module<T> {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use std::cell::RefCell;
 	use primitives::H256;
 	use sp_runtime::{
 		traits::{BlakeTwo256, IdentityLookup}, testing::Header, generic::DigestItem, Perbill,
@@ -418,7 +420,7 @@ mod tests {
 		type FindAuthor = AuthorGiven;
 		type UncleGenerations = UncleGenerations;
 		type FilterUncle = SealVerify<VerifyBlock>;
-		type EventHandler = ();
+		type EventHandler = NoteUncles;
 	}
 
 	type System = system::Module<Test>;
@@ -442,6 +444,20 @@ mod tests {
 		}
 	}
 
+	thread_local! {
+		static NOTED_UNCLES: RefCell<Vec<(u64, u64)>> = RefCell::new(Vec::new());
+	}
+
+	pub struct NoteUncles;
+
+	impl EventHandler<u64, u64> for NoteUncles {
+		fn note_author(_author: u64) {}
+
+		fn note_uncle(author: u64, age: u64) {
+			NOTED_UNCLES.with(|n| n.borrow_mut().push((author, age)));
+		}
+	}
+
 	pub struct VerifyBlock;
 
 	impl VerifySeal<Header, u64> for VerifyBlock {
@@ -630,6 +646,40 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn notes_uncle_authors() {
+		new_test_ext().execute_with(|| {
+			let author_a = 69;
+			let author_b = 42;
+
+			let mut best_hash = seal_header(create_header(0, Default::default(), Default::default()), 999)
+				.hash();
+			let mut canon_hashes = vec![best_hash];
+			for number in 1..5 {
+				System::initialize(&number, &best_hash, &Default::default(), &Default::default());
+				best_hash = seal_header(System::finalize(), author_a).hash();
+				canon_hashes.push(best_hash);
+			}
+
+			System::initialize(&5, &best_hash, &Default::default(), &Default::default());
+
+			let uncle_b = seal_header(
+				create_header(3, canon_hashes[2], [1; 32].into()),
+				author_b,
+			);
+			let sibling_a = seal_header(
+				create_header(5, canon_hashes[4], [1; 32].into()),
+				author_a,
+			);
+			assert!(Authorship::verify_and_import_uncles(vec![uncle_b, sibling_a]).is_ok());
+
+			assert_eq!(
+				NOTED_UNCLES.with(|n| n.borrow().clone()),
+				vec![(author_b, 2), (author_a, 0)],
+			);
+		});
+	}
+
 	#[test]
 	fn sets_author_lazily() {
 		new_test_ext().execute_with(|| {
@@ -24,29 +24,38 @@ pub use timestamp;
 use sp_timestamp;
 
 use sp_std::{result, prelude::*};
-use support::{decl_storage, decl_module, traits::FindAuthor, traits::Get};
+use support::{decl_storage, decl_module, traits::{FindAuthor, VerifySeal}, traits::Get, Parameter};
+use support::{traits::KeyOwnerProofSystem, weights::SimpleDispatchInfo};
 use sp_timestamp::OnTimestampSet;
-use sp_runtime::{generic::DigestItem, ConsensusEngineId, Perbill};
-use sp_runtime::traits::{IsMember, SaturatedConversion, Saturating, RandomnessBeacon};
-use sp_staking::{
-	SessionIndex,
-	offence::{Offence, Kind},
+use sp_runtime::{generic::DigestItem, ConsensusEngineId, KeyTypeId, RuntimeAppPublic};
+use sp_runtime::traits::{
+	Header as HeaderT, IsMember, SaturatedConversion, Saturating, RandomnessBeacon,
 };
+use sp_runtime::transaction_validity::{
+	TransactionValidity, ValidTransaction, InvalidTransaction, TransactionPriority,
+};
+use session::{GetSessionNumber, GetValidatorCount};
+use system::ensure_none;
 
 use codec::{Encode, Decode};
 use inherents::{InherentIdentifier, InherentData, ProvideInherent, MakeFatalError};
 use babe_primitives::{
 	BABE_ENGINE_ID, ConsensusLog, BabeAuthorityWeight, NextEpochDescriptor, RawBabePreDigest,
-	SlotNumber, inherents::{INHERENT_IDENTIFIER, BabeInherentData}
+	SlotNumber, EquivocationProof, AuthoritySignature, verify_primary_vrf,
+	inherents::{INHERENT_IDENTIFIER, BabeInherentData}
 };
 pub use babe_primitives::{AuthorityId, VRF_OUTPUT_LENGTH, PUBLIC_KEY_LENGTH};
 
+mod equivocation;
+
 #[cfg(all(feature = "std", test))]
 mod tests;
 
 #[cfg(all(feature = "std", test))]
 mod mock;
 
+pub use equivocation::{BabeEquivocationOffence, EquivocationHandler, HandleEquivocation};
+
 pub trait Trait: timestamp::Trait {
 	/// The amount of time, in slots, that each epoch should last.
 	type EpochDuration: Get<SlotNumber>;
@@ -64,6 +73,40 @@ pub trait Trait: timestamp::Trait {
 	/// Typically, the `ExternalTrigger` type should be used. An internal trigger should only be used
 	/// when no other module is responsible for changing authority set.
 	type EpochChangeTrigger: EpochChangeTrigger;
+
+	/// The probability `c` of a slot being claimed by a primary VRF assignment, as
+	/// advertised to the client in the BABE configuration. Used to verify the slot
+	/// claims of uncles.
+	type PrimaryProbability: Get<(u64, u64)>;
+
+	/// Whether secondary slot assignments are enabled, as advertised to the client in
+	/// the BABE configuration. Used to verify the slot claims of uncles.
+	type SecondarySlots: Get<bool>;
+
+	/// A dispatchable call type.
+	type Call: From<Call<Self>>;
+
+	/// The proof of key ownership, used for validating equivocation reports.
+	/// The proof must include the session index and validator count of the
+	/// session at which the equivocation occurred.
+	type KeyOwnerProof: Parameter + GetSessionNumber + GetValidatorCount;
+
+	/// The identification of a key owner, used when reporting equivocations.
+	type KeyOwnerIdentification: Parameter;
+
+	/// A system for proving ownership of keys, i.e. that a given key was part
+	/// of a validator set, needed for validating equivocation reports.
+	type KeyOwnerProofSystem: KeyOwnerProofSystem<
+		(KeyTypeId, AuthorityId),
+		Proof = Self::KeyOwnerProof,
+		IdentificationTuple = Self::KeyOwnerIdentification,
+	>;
+
+	/// The equivocation handling subsystem, defines methods to report an
+	/// offence (after the equivocation has been validated) and for submitting
+	/// a transaction to report an equivocation (from an offchain context).
+	/// Set to `()` to disable equivocation reporting.
+	type HandleEquivocation: HandleEquivocation<Self>;
 }
 
 /// Trigger an epoch change, if any should take place.
@@ -111,6 +154,10 @@ decl_storage! {
 		/// Current epoch authorities.
 		pub Authorities get(fn authorities): Vec<(AuthorityId, BabeAuthorityWeight)>;
 
+		/// Authorities of the previous epoch, kept to verify uncles from that epoch.
+		pub PreviousAuthorities get(fn previous_authorities):
+			Vec<(AuthorityId, BabeAuthorityWeight)>;
+
 		/// The slot at which the first epoch actually started. This is 0
 		/// until the first block of the chain.
 		pub GenesisSlot get(fn genesis_slot): u64;
@@ -118,6 +165,12 @@ decl_storage! {
 		/// Current slot number.
 		pub CurrentSlot get(fn current_slot): u64;
 
+		/// The slot at which each epoch actually started, i.e. the slot of its first block.
+		///
+		/// Epochs can be shorter than `EpochDuration` when slots were skipped, so the epoch of a
+		/// slot can't be derived from the genesis slot alone.
+		pub EpochStartSlot get(fn epoch_start_slot): map u64 => Option<SlotNumber>;
+
 		/// The epoch randomness for the *current* epoch.
 		///
 		/// # Security
@@ -136,6 +189,9 @@ decl_storage! {
 		/// Next epoch randomness.
 		NextRandomness: [u8; 32 /* RANDOMNESS_LENGTH */];
 
+		/// Randomness of the previous epoch, kept to verify uncles from that epoch.
+		PreviousRandomness: [u8; 32 /* RANDOMNESS_LENGTH */];
+
 		/// Randomness under construction.
 		///
 		/// We make a tradeoff between storage accesses and list length.
@@ -188,7 +244,47 @@ decl_module! {
 				Self::deposit_vrf_output(&vrf_output);
 			}
 		}
+
+		/// Report authority equivocation/misbehavior. This method will verify
+		/// the equivocation proof and validate the given key ownership proof
+		/// against the extracted offender. If both are valid, the offence will
+		/// be reported.
+		///
+		/// This extrinsic must be called unsigned and both proofs are also
+		/// checked when validating the transaction (see `ValidateUnsigned`),
+		/// since it isn't paid for. No reporter is rewarded for the offence.
+		#[weight = SimpleDispatchInfo::FixedNormal(5_000_000)]
+		fn report_equivocation_unsigned(
+			origin,
+			equivocation_proof: EquivocationProof<T::Header>,
+			key_owner_proof: T::KeyOwnerProof,
+		) {
+			ensure_none(origin)?;
+
+			let offence = Self::check_equivocation_report(&equivocation_proof, key_owner_proof)?;
+
+			T::HandleEquivocation::report_offence(vec![], offence);
+		}
+	}
+}
+
+/// The index of the authority assigned to the given secondary slot, computed as in the
+/// client: the big-endian hash of the randomness and slot, modulo the number of
+/// authorities.
+fn secondary_slot_author(
+	slot_number: SlotNumber,
+	authorities: &[(AuthorityId, BabeAuthorityWeight)],
+	randomness: &[u8; RANDOMNESS_LENGTH],
+) -> Option<u32> {
+	if authorities.is_empty() {
+		return None;
 	}
+
+	let len = authorities.len() as u64;
+	let hash = (randomness, slot_number).using_encoded(sp_io::hashing::blake2_256);
+	let index = hash.iter().fold(0u64, |index, byte| (index * 256 + *byte as u64) % len);
+
+	Some(index as u32)
 }
 
 impl<T: Trait> RandomnessBeacon for Module<T> {
@@ -220,6 +316,89 @@ impl<T: Trait> FindAuthor<u32> for Module<T> {
 	}
 }
 
+/// Verifies the slot claim and seal of uncles against the authorities and randomness of
+/// the epoch their slot is in, which must be the current or the previous one, and returns
+/// the key of their author.
+///
+/// Primary claims are accepted below a threshold of `c * weight / total_weight`, which
+/// never exceeds the one of the client (`1 - (1 - c)^(weight / total_weight)`), so an
+/// honest uncle may be rejected but an unwon slot is never credited.
+impl<T: Trait> VerifySeal<T::Header, AuthorityId> for Module<T> {
+	fn verify_seal(header: &T::Header) -> Result<Option<AuthorityId>, &'static str> {
+		let mut header = header.clone();
+		let seal = header.digest_mut().pop().ok_or("Header unsealed")?;
+		let signature = seal.as_seal()
+			.filter(|(id, _)| *id == BABE_ENGINE_ID)
+			.ok_or("Header unsealed")
+			.and_then(|(_, signature)| AuthoritySignature::decode(&mut &signature[..])
+				.map_err(|_| "Header with invalid BABE seal"))?;
+
+		let pre_digest = header.digest().logs().iter()
+			.filter_map(|d| d.as_pre_runtime())
+			.find(|(id, _)| *id == BABE_ENGINE_ID)
+			.and_then(|(_, mut data)| RawBabePreDigest::decode(&mut data).ok())
+			.ok_or("Header without BABE pre-digest")?;
+
+		let slot_number = pre_digest.slot_number();
+		if slot_number > CurrentSlot::get() {
+			return Err("Header from a future slot");
+		}
+
+		let epoch_index = EpochIndex::get();
+		let starts_epoch = |index: u64| EpochStartSlot::get(index)
+			.map_or(false, |start| slot_number >= start);
+		let (epoch_index, authorities, randomness) = if starts_epoch(epoch_index) {
+			(epoch_index, Self::authorities(), Self::randomness())
+		} else if epoch_index > 0 && starts_epoch(epoch_index - 1) {
+			(epoch_index - 1, Self::previous_authorities(), PreviousRandomness::get())
+		} else {
+			return Err("Header from an unknown epoch");
+		};
+
+		let author = match pre_digest {
+			RawBabePreDigest::Primary { authority_index, vrf_output, vrf_proof, .. } => {
+				let (author, weight) = authorities.get(authority_index as usize)
+					.ok_or("Header author not in the epoch authority set")?;
+
+				let value = verify_primary_vrf(
+					author,
+					&randomness,
+					slot_number,
+					epoch_index,
+					&vrf_output,
+					&vrf_proof,
+				).ok_or("Header with invalid VRF proof")?;
+
+				if value >= Self::primary_threshold_bound(&authorities, *weight) {
+					return Err("Header VRF output above the primary threshold");
+				}
+
+				author
+			}
+			RawBabePreDigest::Secondary { authority_index, .. } => {
+				if !T::SecondarySlots::get() {
+					return Err("Header claims a secondary slot, which are disabled");
+				}
+
+				let expected = secondary_slot_author(slot_number, &authorities, &randomness);
+				if expected != Some(authority_index) {
+					return Err("Header author not assigned to the secondary slot");
+				}
+
+				&authorities[authority_index as usize].0
+			}
+		};
+
+		// the seal signs the hash of the header without it.
+		let pre_hash = header.hash();
+		if !author.verify(&pre_hash, &signature) {
+			return Err("Header with invalid BABE seal");
+		}
+
+		Ok(Some(author.clone()))
+	}
+}
+
 impl<T: Trait> IsMember<AuthorityId> for Module<T> {
 	fn is_member(authority_id: &AuthorityId) -> bool {
 		<Module<T>>::authorities()
@@ -240,50 +419,13 @@ impl<T: Trait> session::ShouldEndSession<T::BlockNumber> for Module<T> {
 	}
 }
 
-// TODO [slashing]: @marcio use this, remove the dead_code annotation.
-/// A BABE equivocation offence report.
-///
-/// When a validator released two or more blocks at the same slot.
-#[allow(dead_code)]
-struct BabeEquivocationOffence<FullIdentification> {
-	/// A babe slot number in which this incident happened.
-	slot: u64,
-	/// The session index in which the incident happened.
-	session_index: SessionIndex,
-	/// The size of the validator set at the time of the offence.
-	validator_set_count: u32,
-	/// The authority that produced the equivocation.
-	offender: FullIdentification,
-}
-
-impl<FullIdentification: Clone> Offence<FullIdentification> for BabeEquivocationOffence<FullIdentification> {
-	const ID: Kind = *b"babe:equivocatio";
-	type TimeSlot = u64;
-
-	fn offenders(&self) -> Vec<FullIdentification> {
-		vec![self.offender.clone()]
-	}
-
-	fn session_index(&self) -> SessionIndex {
-		self.session_index
-	}
-
-	fn validator_set_count(&self) -> u32 {
-		self.validator_set_count
-	}
-
-	fn time_slot(&self) -> Self::TimeSlot {
-		self.slot
-	}
-
-	fn slash_fraction(
-		offenders_count: u32,
-		validator_set_count: u32,
-	) -> Perbill {
-		// the formula is min((3k / n)^2, 1)
-		let x = Perbill::from_rational_approximation(3 * offenders_count, validator_set_count);
-		// _ ^ 2
-		x.square()
+impl<T: Trait> session::EstimateNextNewSession<T::BlockNumber> for Module<T> {
+	fn estimate_next_new_session(now: T::BlockNumber) -> Option<T::BlockNumber> {
+		// sessions change with epochs. this is a best effort guess, which assumes that a block
+		// is authored in each of the remaining slots of the current epoch.
+		let next_epoch_start = Self::current_epoch_start().saturating_add(T::EpochDuration::get());
+		next_epoch_start.checked_sub(CurrentSlot::get())
+			.map(|remaining_slots| now.saturating_add(remaining_slots.saturated_into()))
 	}
 }
 
@@ -334,6 +476,8 @@ impl<T: Trait> Module<T> {
 			.expect("epoch indices will never reach 2^64 before the death of the universe; qed");
 
 		EpochIndex::put(epoch_index);
+		EpochStartSlot::insert(epoch_index, CurrentSlot::get());
+		PreviousAuthorities::put(Authorities::take());
 		Authorities::put(authorities);
 
 		// Update epoch randomness.
@@ -344,6 +488,7 @@ impl<T: Trait> Module<T> {
 		// Returns randomness for the current epoch and computes the *next*
 		// epoch randomness.
 		let randomness = Self::randomness_change_epoch(next_epoch_index);
+		PreviousRandomness::put(Randomness::get());
 		Randomness::put(randomness);
 
 		// After we update the current epoch, we signal the *next* epoch change
@@ -358,6 +503,25 @@ impl<T: Trait> Module<T> {
 		Self::deposit_consensus(ConsensusLog::NextEpochData(next))
 	}
 
+	// a lower bound of the primary threshold of an authority with the given weight, i.e.
+	// `c * weight / total_weight` of the VRF output range.
+	fn primary_threshold_bound(
+		authorities: &[(AuthorityId, BabeAuthorityWeight)],
+		weight: BabeAuthorityWeight,
+	) -> u128 {
+		let (numerator, denominator) = T::PrimaryProbability::get();
+		let total_weight = authorities.iter()
+			.fold(0u128, |total, (_, weight)| total.saturating_add(*weight as u128));
+		if denominator == 0 || total_weight == 0 {
+			return 0;
+		}
+
+		(u128::max_value() / denominator as u128)
+			.saturating_mul(numerator.min(denominator) as u128)
+			/ total_weight
+			* weight as u128
+	}
+
 	// finds the start slot of the current epoch. only guaranteed to
 	// give correct results after `do_initialize` of the first block
 	// in the chain (as its result is based off of `GenesisSlot`).
@@ -410,6 +574,7 @@ impl<T: Trait> Module<T> {
 			// we need to adjust internal storage accordingly.
 			if GenesisSlot::get() == 0 {
 				GenesisSlot::put(digest.slot_number());
+				EpochStartSlot::insert(0, digest.slot_number());
 				debug_assert_ne!(GenesisSlot::get(), 0);
 
 				// deposit a log because this is the first block in epoch #0
@@ -460,6 +625,58 @@ impl<T: Trait> Module<T> {
 		this_randomness
 	}
 
+	/// Submits an extrinsic to report an equivocation. This method will create
+	/// an unsigned extrinsic with a call to `report_equivocation_unsigned` and
+	/// will push the transaction to the pool. Only useful in an offchain
+	/// context.
+	pub fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Header>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> Option<()> {
+		T::HandleEquivocation::submit_equivocation_report(equivocation_proof, key_owner_proof).ok()
+	}
+
+	/// Validate the given equivocation and key ownership proofs, returning
+	/// the offence that should be reported if both are valid.
+	fn check_equivocation_report(
+		equivocation_proof: &EquivocationProof<T::Header>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> result::Result<BabeEquivocationOffence<T::KeyOwnerIdentification>, &'static str> {
+		let slot = equivocation_proof.slot_number;
+		let session_index = key_owner_proof.session();
+		let validator_set_count = key_owner_proof.validator_count();
+
+		// validate the equivocation proof, i.e. that both headers are
+		// different, claim the given slot and are sealed by the offender.
+		if !babe_primitives::check_equivocation_proof(equivocation_proof.clone()) {
+			return Err("Invalid equivocation proof.");
+		}
+
+		// sessions are coupled to epochs, so the reported slot must be within
+		// the epoch of the session of the key ownership proof.
+		let epoch_index = session_index as u64;
+		let epoch_start = Self::epoch_start_slot(epoch_index)
+			.ok_or("Invalid key ownership proof session.")?;
+		let next_epoch_start = Self::epoch_start_slot(epoch_index + 1);
+
+		if slot < epoch_start || next_epoch_start.map_or(false, |next| slot >= next) {
+			return Err("Invalid key ownership proof session.");
+		}
+
+		// check the membership proof and extract the offender's identity.
+		let offender = T::KeyOwnerProofSystem::check_proof(
+			(babe_primitives::KEY_TYPE, equivocation_proof.offender.clone()),
+			key_owner_proof,
+		).ok_or("Invalid key ownership proof.")?;
+
+		Ok(BabeEquivocationOffence {
+			slot,
+			session_index,
+			validator_set_count,
+			offender,
+		})
+	}
+
 	fn initialize_authorities(authorities: &[(AuthorityId, BabeAuthorityWeight)]) {
 		if !authorities.is_empty() {
 			assert!(Authorities::get().is_empty(), "Authorities are already initialized!");
@@ -468,6 +685,33 @@ impl<T: Trait> Module<T> {
 	}
 }
 
+#[allow(deprecated)]
+impl<T: Trait> support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		if let Call::report_equivocation_unsigned(equivocation_proof, key_owner_proof) = call {
+			if Self::check_equivocation_report(equivocation_proof, key_owner_proof.clone()).is_err() {
+				return InvalidTransaction::BadProof.into();
+			}
+
+			Ok(ValidTransaction {
+				priority: TransactionPriority::max_value(),
+				requires: vec![],
+				// only one report for the same offender at the same slot.
+				provides: vec![(
+					&equivocation_proof.offender,
+					equivocation_proof.slot_number,
+				).encode()],
+				longevity: 64,
+				propagate: true,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+}
+
 impl<T: Trait> OnTimestampSet<T::Moment> for Module<T> {
 	fn on_timestamp_set(_moment: T::Moment) { }
 }
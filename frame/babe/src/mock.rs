@@ -17,7 +17,7 @@
 //! Test utilities
 #![allow(dead_code, unused_imports)]
 
-use super::{Trait, Module, GenesisConfig};
+use super::{Call, Trait, Module, GenesisConfig};
 use babe_primitives::AuthorityId;
 use sp_runtime::{
 	traits::IdentityLookup, Perbill, testing::{Header, UintAuthorityId}, impl_opaque_keys,
@@ -25,7 +25,7 @@ use sp_runtime::{
 use sp_version::RuntimeVersion;
 use support::{impl_outer_origin, parameter_types, weights::Weight};
 use sp_io;
-use primitives::{H256, Blake2Hasher};
+use primitives::{H256, Blake2Hasher, Pair, sr25519};
 
 impl_outer_origin!{
 	pub enum Origin for Test {}
@@ -45,6 +45,8 @@ parameter_types! {
 	pub const MinimumPeriod: u64 = 1;
 	pub const EpochDuration: u64 = 3;
 	pub const ExpectedBlockTime: u64 = 1;
+	pub const PrimaryProbability: (u64, u64) = (1, 1);
+	pub const SecondarySlots: bool = true;
 	pub const Version: RuntimeVersion = test_runtime::VERSION;
 	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(16);
 }
@@ -95,6 +97,13 @@ impl Trait for Test {
 	type EpochDuration = EpochDuration;
 	type ExpectedBlockTime = ExpectedBlockTime;
 	type EpochChangeTrigger = crate::ExternalTrigger;
+	type PrimaryProbability = PrimaryProbability;
+	type SecondarySlots = SecondarySlots;
+	type Call = Call<Test>;
+	type KeyOwnerProof = support::Void;
+	type KeyOwnerIdentification = support::Void;
+	type KeyOwnerProofSystem = ();
+	type HandleEquivocation = ();
 }
 
 pub fn new_test_ext(authorities: Vec<DummyValidatorId>) -> sp_io::TestExternalities {
@@ -105,5 +114,10 @@ pub fn new_test_ext(authorities: Vec<DummyValidatorId>) -> sp_io::TestExternalit
 	t.into()
 }
 
+/// The key pair of the given authority, for tests that need real signatures and VRFs.
+pub fn authority_pair(authority: DummyValidatorId) -> sr25519::Pair {
+	sr25519::Pair::from_seed(&[authority as u8; 32])
+}
+
 pub type System = system::Module<Test>;
 pub type Babe = Module<Test>;
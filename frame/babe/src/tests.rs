@@ -17,8 +17,9 @@
 //! Consensus extension module tests for BABE consensus.
 
 use super::*;
-use mock::{new_test_ext, Babe, Test};
-use sp_runtime::{traits::OnFinalize, testing::{Digest, DigestItem}};
+use mock::{new_test_ext, authority_pair, Babe, Test};
+use primitives::{Pair, sr25519};
+use sp_runtime::{traits::OnFinalize, testing::{Digest, DigestItem, Header}};
 use session::ShouldEndSession;
 
 const EMPTY_RANDOMNESS: [u8; 32] = [
@@ -116,6 +117,33 @@ fn first_block_epoch_zero_start() {
 	})
 }
 
+#[test]
+fn epoch_start_slots_are_recorded() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		let pre_digest = make_pre_digest(0, 100, [1; 32], [0xff; 64]);
+		System::initialize(&1, &Default::default(), &Default::default(), &pre_digest);
+		assert!(!Babe::should_end_session(1));
+		Babe::on_finalize(1);
+		System::finalize();
+
+		assert_eq!(Babe::epoch_start_slot(0), Some(100));
+		assert_eq!(Babe::epoch_start_slot(1), None);
+
+		// slots were skipped, so the second epoch starts later than the genesis
+		// slot and the epoch duration imply.
+		let pre_digest = make_pre_digest(0, 110, [1; 32], [0xff; 64]);
+		System::initialize(&2, &Default::default(), &Default::default(), &pre_digest);
+		assert!(Babe::should_end_session(2));
+		Babe::enact_epoch_change(Babe::authorities(), Babe::authorities());
+		Babe::on_finalize(2);
+		System::finalize();
+
+		assert_eq!(Babe::epoch_index(), 1);
+		assert_eq!(Babe::epoch_start_slot(0), Some(100));
+		assert_eq!(Babe::epoch_start_slot(1), Some(110));
+	})
+}
+
 #[test]
 fn authority_index() {
 	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
@@ -124,3 +152,188 @@ fn authority_index() {
 			"Trivially invalid authorities are ignored")
 	})
 }
+
+fn set_authorities(authorities: &[(u64, BabeAuthorityWeight)]) {
+	Authorities::put(authorities.iter()
+		.map(|(a, weight)| (AuthorityId::from(authority_pair(*a).public()), *weight))
+		.collect::<Vec<_>>());
+}
+
+fn primary_pre_digest(
+	authority: u64,
+	authority_index: babe_primitives::AuthorityIndex,
+	slot_number: SlotNumber,
+	epoch: u64,
+	randomness: [u8; RANDOMNESS_LENGTH],
+) -> DigestItem {
+	let pair = authority_pair(authority);
+	let keypair: &schnorrkel::Keypair = pair.as_ref();
+	let transcript = babe_primitives::make_transcript(&randomness, slot_number, epoch);
+	let (inout, proof, _) = keypair.vrf_sign(transcript);
+
+	let digest = RawBabePreDigest::Primary {
+		authority_index,
+		slot_number,
+		vrf_output: inout.to_output().to_bytes(),
+		vrf_proof: proof.to_bytes(),
+	};
+	DigestItem::PreRuntime(BABE_ENGINE_ID, digest.encode())
+}
+
+fn secondary_pre_digest(
+	authority_index: babe_primitives::AuthorityIndex,
+	slot_number: SlotNumber,
+) -> DigestItem {
+	let digest = RawBabePreDigest::Secondary { authority_index, slot_number };
+	DigestItem::PreRuntime(BABE_ENGINE_ID, digest.encode())
+}
+
+fn sealed_uncle(pre_digest: DigestItem, signer: &sr25519::Pair) -> Header {
+	let mut header = Header::new(
+		1,
+		Default::default(),
+		Default::default(),
+		Default::default(),
+		Digest { logs: vec![pre_digest] },
+	);
+	let signature = signer.sign(header.hash().as_ref());
+	header.digest_mut().push(DigestItem::Seal(BABE_ENGINE_ID, signature.encode()));
+	header
+}
+
+// imports block #1 at the given slot, which starts epoch #0.
+fn start_first_epoch(slot_number: SlotNumber) {
+	let pre_digest = make_pre_digest(0, slot_number, [1; 32], [0xff; 64]);
+	System::initialize(&1, &Default::default(), &Default::default(), &pre_digest);
+	assert!(!Babe::should_end_session(1));
+	Babe::on_finalize(1);
+	System::finalize();
+}
+
+#[test]
+fn uncle_primary_claims_are_verified() {
+	new_test_ext(vec![]).execute_with(|| {
+		// with `c = 1`, an authority owning all the weight wins every slot and one without
+		// weight never does.
+		set_authorities(&[(0, 1), (1, 0)]);
+		start_first_epoch(100);
+		let randomness = Babe::randomness();
+
+		let uncle = sealed_uncle(primary_pre_digest(0, 0, 100, 0, randomness), &authority_pair(0));
+		assert_eq!(
+			Babe::verify_seal(&uncle),
+			Ok(Some(AuthorityId::from(authority_pair(0).public()))),
+		);
+
+		// the VRF doesn't claim the slot of the uncle.
+		let mut pre_digest = primary_pre_digest(0, 0, 99, 0, randomness);
+		if let DigestItem::PreRuntime(_, data) = &mut pre_digest {
+			let mut digest = RawBabePreDigest::decode(&mut &data[..]).unwrap();
+			if let RawBabePreDigest::Primary { slot_number, .. } = &mut digest {
+				*slot_number = 100;
+			}
+			*data = digest.encode();
+		}
+		let uncle = sealed_uncle(pre_digest, &authority_pair(0));
+		assert_eq!(Babe::verify_seal(&uncle), Err("Header with invalid VRF proof"));
+
+		// the VRF of another authority.
+		let uncle = sealed_uncle(primary_pre_digest(1, 0, 100, 0, randomness), &authority_pair(0));
+		assert_eq!(Babe::verify_seal(&uncle), Err("Header with invalid VRF proof"));
+
+		// a valid VRF output which doesn't win the slot.
+		let uncle = sealed_uncle(primary_pre_digest(1, 1, 100, 0, randomness), &authority_pair(1));
+		assert_eq!(Babe::verify_seal(&uncle), Err("Header VRF output above the primary threshold"));
+
+		// sealed by another authority.
+		let uncle = sealed_uncle(primary_pre_digest(0, 0, 100, 0, randomness), &authority_pair(1));
+		assert_eq!(Babe::verify_seal(&uncle), Err("Header with invalid BABE seal"));
+
+		let uncle = sealed_uncle(primary_pre_digest(0, 0, 101, 0, randomness), &authority_pair(0));
+		assert_eq!(Babe::verify_seal(&uncle), Err("Header from a future slot"));
+
+		let uncle = sealed_uncle(primary_pre_digest(0, 0, 99, 0, randomness), &authority_pair(0));
+		assert_eq!(Babe::verify_seal(&uncle), Err("Header from an unknown epoch"));
+	})
+}
+
+#[test]
+fn uncle_secondary_claims_are_verified() {
+	new_test_ext(vec![]).execute_with(|| {
+		set_authorities(&[(0, 1), (1, 1), (2, 1), (3, 1)]);
+		start_first_epoch(100);
+
+		let expected = secondary_slot_author(100, &Babe::authorities(), &Babe::randomness())
+			.unwrap();
+		let other = (expected + 1) % 4;
+
+		let expected_pair = authority_pair(expected as u64);
+		let other_pair = authority_pair(other as u64);
+
+		let uncle = sealed_uncle(secondary_pre_digest(expected, 100), &expected_pair);
+		assert_eq!(
+			Babe::verify_seal(&uncle),
+			Ok(Some(AuthorityId::from(expected_pair.public()))),
+		);
+
+		let uncle = sealed_uncle(secondary_pre_digest(other, 100), &other_pair);
+		assert_eq!(
+			Babe::verify_seal(&uncle),
+			Err("Header author not assigned to the secondary slot"),
+		);
+
+		let uncle = sealed_uncle(secondary_pre_digest(expected, 100), &other_pair);
+		assert_eq!(Babe::verify_seal(&uncle), Err("Header with invalid BABE seal"));
+	})
+}
+
+#[test]
+fn uncles_are_verified_against_their_own_epoch() {
+	new_test_ext(vec![]).execute_with(|| {
+		set_authorities(&[(0, 1), (1, 0)]);
+		start_first_epoch(100);
+		let first_randomness = Babe::randomness();
+
+		let pre_digest = make_pre_digest(0, 110, [1; 32], [0xff; 64]);
+		System::initialize(&2, &Default::default(), &Default::default(), &pre_digest);
+		assert!(Babe::should_end_session(2));
+		let next_authorities = vec![(AuthorityId::from(authority_pair(2).public()), 1)];
+		Babe::enact_epoch_change(next_authorities.clone(), next_authorities);
+		Babe::on_finalize(2);
+		System::finalize();
+		assert_eq!(Babe::epoch_index(), 1);
+
+		// an uncle of the previous epoch is checked against its authorities and randomness.
+		let uncle = sealed_uncle(
+			primary_pre_digest(0, 0, 105, 0, first_randomness),
+			&authority_pair(0),
+		);
+		assert_eq!(
+			Babe::verify_seal(&uncle),
+			Ok(Some(AuthorityId::from(authority_pair(0).public()))),
+		);
+
+		// ...and not against the current ones.
+		let uncle = sealed_uncle(
+			primary_pre_digest(2, 0, 105, 0, first_randomness),
+			&authority_pair(2),
+		);
+		assert_eq!(Babe::verify_seal(&uncle), Err("Header with invalid VRF proof"));
+
+		let uncle = sealed_uncle(
+			primary_pre_digest(2, 0, 110, 1, Babe::randomness()),
+			&authority_pair(2),
+		);
+		assert_eq!(
+			Babe::verify_seal(&uncle),
+			Ok(Some(AuthorityId::from(authority_pair(2).public()))),
+		);
+
+		// an uncle of the current epoch claimed with the previous epoch's VRF inputs.
+		let uncle = sealed_uncle(
+			primary_pre_digest(2, 0, 110, 0, first_randomness),
+			&authority_pair(2),
+		);
+		assert_eq!(Babe::verify_seal(&uncle), Err("Header with invalid VRF proof"));
+	})
+}
@@ -67,8 +67,6 @@
 //! simply dropped, it should automatically maintain any book-keeping such as total issuance.)
 //! - **Lock:** A freeze on a specified amount of an account's free balance until a specified block number. Multiple
 //! locks always operate over the same funds, so they "overlay" rather than "stack".
-//! - **Vesting:** Similar to a lock, this is another, but independent, liquidity restriction that reduces linearly
-//! over time.
 //!
 //! ### Implementations
 //!
@@ -94,10 +92,6 @@
 //! - `transfer` - Transfer some liquid free balance to another account.
 //! - `set_balance` - Set the balances of a given account. The origin of this call must be root.
 //!
-//! ### Public Functions
-//!
-//! - `vesting_balance` - Get the amount that is currently being vested and cannot be transferred out of this account.
-//!
 //! ## Usage
 //!
 //! The following examples show how to use the Balances module in your custom module.
@@ -167,7 +161,7 @@ use support::{
 	traits::{
 		UpdateBalanceOutcome, Currency, OnFreeBalanceZero, OnUnbalanced, TryDrop,
 		WithdrawReason, WithdrawReasons, LockIdentifier, LockableCurrency, ExistenceRequirement,
-		Imbalance, SignedImbalance, ReservableCurrency, Get, VestingCurrency,
+		Imbalance, SignedImbalance, ReservableCurrency, Get,
 	},
 	weights::SimpleDispatchInfo,
 	dispatch::Result,
@@ -176,11 +170,12 @@ use sp_runtime::{
 	RuntimeDebug,
 	traits::{
 		Zero, SimpleArithmetic, StaticLookup, Member, CheckedAdd, CheckedSub, MaybeSerializeDeserialize,
-		Saturating, Bounded,
+		Bounded,
 	},
 };
 use system::{IsDeadAccount, OnNewAccount, ensure_signed, ensure_root};
 
+mod migration;
 #[cfg(test)]
 mod mock;
 #[cfg(test)]
@@ -191,7 +186,7 @@ pub use self::imbalances::{PositiveImbalance, NegativeImbalance};
 pub trait Subtrait<I: Instance = DefaultInstance>: system::Trait {
 	/// The balance of an account.
 	type Balance: Parameter + Member + SimpleArithmetic + Codec + Default + Copy +
-		MaybeSerializeDeserialize + Debug + From<Self::BlockNumber>;
+		MaybeSerializeDeserialize + Debug;
 
 	/// A function that is invoked when the free-balance has fallen below the existential deposit and
 	/// has been reduced to zero.
@@ -215,7 +210,7 @@ pub trait Subtrait<I: Instance = DefaultInstance>: system::Trait {
 pub trait Trait<I: Instance = DefaultInstance>: system::Trait {
 	/// The balance of an account.
 	type Balance: Parameter + Member + SimpleArithmetic + Codec + Default + Copy +
-		MaybeSerializeDeserialize + Debug + From<Self::BlockNumber>;
+		MaybeSerializeDeserialize + Debug;
 
 	/// A function that is invoked when the free-balance has fallen below the existential deposit and
 	/// has been reduced to zero.
@@ -269,34 +264,6 @@ decl_event!(
 	}
 );
 
-/// Struct to encode the vesting schedule of an individual account.
-#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
-pub struct VestingSchedule<Balance, BlockNumber> {
-	/// Locked amount at genesis.
-	pub locked: Balance,
-	/// Amount that gets unlocked every block after `starting_block`.
-	pub per_block: Balance,
-	/// Starting block for unlocking(vesting).
-	pub starting_block: BlockNumber,
-}
-
-impl<Balance: SimpleArithmetic + Copy, BlockNumber: SimpleArithmetic + Copy> VestingSchedule<Balance, BlockNumber> {
-	/// Amount locked at block `n`.
-	pub fn locked_at(&self, n: BlockNumber) -> Balance
-		where Balance: From<BlockNumber>
-	{
-		// Number of blocks that count toward vesting
-		// Saturating to 0 when n < starting_block
-		let vested_block_count = n.saturating_sub(self.starting_block);
-		// Return amount that is still locked in vesting
-		if let Some(x) = Balance::from(vested_block_count).checked_mul(&self.per_block) {
-			self.locked.max(x) - x
-		} else {
-			Zero::zero()
-		}
-	}
-}
-
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
 pub struct BalanceLock<Balance, BlockNumber> {
 	pub id: LockIdentifier,
@@ -312,33 +279,6 @@ decl_storage! {
 			config.balances.iter().fold(Zero::zero(), |acc: T::Balance, &(_, n)| acc + n)
 		}): T::Balance;
 
-		/// Information regarding the vesting of a given account.
-		pub Vesting get(fn vesting) build(|config: &GenesisConfig<T, I>| {
-			// Generate initial vesting configuration
-			// * who - Account which we are generating vesting configuration for
-			// * begin - Block when the account will start to vest
-			// * length - Number of blocks from `begin` until fully vested
-			// * liquid - Number of units which can be spent before vesting begins
-			config.vesting.iter().filter_map(|&(ref who, begin, length, liquid)| {
-				let length = <T::Balance as From<T::BlockNumber>>::from(length);
-
-				config.balances.iter()
-					.find(|&&(ref w, _)| w == who)
-					.map(|&(_, balance)| {
-						// Total genesis `balance` minus `liquid` equals funds locked for vesting
-						let locked = balance.saturating_sub(liquid);
-						// Number of units unlocked per block after `begin`
-						let per_block = locked / length.max(sp_runtime::traits::One::one());
-
-						(who.clone(), VestingSchedule {
-							locked: locked,
-							per_block: per_block,
-							starting_block: begin
-						})
-					})
-			}).collect::<Vec<_>>()
-		}): map T::AccountId => Option<VestingSchedule<T::Balance, T::BlockNumber>>;
-
 		/// The 'free' balance of a given account.
 		///
 		/// This is the only balance that matters in terms of most operations on tokens. It
@@ -369,11 +309,12 @@ decl_storage! {
 
 		/// Any liquidity locks on some account balances.
 		pub Locks get(fn locks): map T::AccountId => Vec<BalanceLock<T::Balance, T::BlockNumber>>;
+
+		/// The version of storage for upgrade.
+		StorageVersion build(|_| migration::CURRENT_VERSION): u32;
 	}
 	add_extra_genesis {
 		config(balances): Vec<(T::AccountId, T::Balance)>;
-		config(vesting): Vec<(T::AccountId, T::BlockNumber, T::BlockNumber, T::Balance)>;
-		// ^^ begin, length, amount liquid at genesis
 		build(|config: &GenesisConfig<T, I>| {
 			for (_, balance) in &config.balances {
 				assert!(
@@ -398,6 +339,10 @@ decl_module! {
 
 		fn deposit_event() = default;
 
+		fn on_initialize() {
+			migration::perform_migrations::<T, I>();
+		}
+
 		/// Transfer some liquid free balance to another account.
 		///
 		/// `transfer` will set the `FreeBalance` of the sender and receiver.
@@ -868,11 +813,6 @@ where
 		reasons: WithdrawReasons,
 		new_balance: T::Balance,
 	) -> Result {
-		if reasons.intersects(WithdrawReason::Reserve | WithdrawReason::Transfer)
-			&& Self::vesting_balance(who) > new_balance
-		{
-			return Err("vesting balance too high to send value");
-		}
 		let locks = Self::locks(who);
 		if locks.is_empty() {
 			return Ok(())
@@ -1201,50 +1141,6 @@ where
 	}
 }
 
-impl<T: Trait<I>, I: Instance> VestingCurrency<T::AccountId> for Module<T, I>
-where
-	T::Balance: MaybeSerializeDeserialize + Debug
-{
-	type Moment = T::BlockNumber;
-
-	/// Get the amount that is currently being vested and cannot be transferred out of this account.
-	fn vesting_balance(who: &T::AccountId) -> T::Balance {
-		if let Some(v) = Self::vesting(who) {
-			Self::free_balance(who)
-				.min(v.locked_at(<system::Module<T>>::block_number()))
-		} else {
-			Zero::zero()
-		}
-	}
-
-	/// Adds a vesting schedule to a given account.
-	///
-	/// If there already exists a vesting schedule for the given account, an `Err` is returned
-	/// and nothing is updated.
-	fn add_vesting_schedule(
-		who: &T::AccountId,
-		locked: T::Balance,
-		per_block: T::Balance,
-		starting_block: T::BlockNumber
-	) -> Result {
-		if <Vesting<T, I>>::exists(who) {
-			return Err("A vesting schedule already exists for this account.");
-		}
-		let vesting_schedule = VestingSchedule {
-			locked,
-			per_block,
-			starting_block
-		};
-		<Vesting<T, I>>::insert(who, vesting_schedule);
-		Ok(())
-	}
-
-	/// Remove a vesting schedule for a given account.
-	fn remove_vesting_schedule(who: &T::AccountId) {
-		<Vesting<T, I>>::remove(who);
-	}
-}
-
 impl<T: Trait<I>, I: Instance> IsDeadAccount<T::AccountId> for Module<T, I>
 where
 	T::Balance: MaybeSerializeDeserialize + Debug
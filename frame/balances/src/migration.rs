@@ -0,0 +1,140 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage migrations for pallet-balances.
+
+/// Indicator of a version of a storage layout.
+pub type VersionNumber = u32;
+
+// the current expected version of the storage
+pub const CURRENT_VERSION: VersionNumber = 1;
+
+#[cfg(any(test, feature = "migrate"))]
+pub(crate) mod inner {
+	use crate::{Store, Trait, Instance, Module, Locks, BalanceLock};
+	use codec::{Encode, Decode};
+	use support::{
+		StorageValue, StoragePrefixedMap,
+		storage::unhashed,
+		traits::{WithdrawReason, LockIdentifier},
+	};
+	use sp_runtime::traits::{Zero, Bounded, Saturating, UniqueSaturatedInto, UniqueSaturatedFrom};
+	use sp_std::{prelude::*, marker::PhantomData};
+	use super::{CURRENT_VERSION, VersionNumber};
+
+	// the minimum supported version of the migration logic.
+	const MIN_SUPPORTED_VERSION: VersionNumber = 0;
+
+	/// The identifier of the lock of the vesting module.
+	pub(crate) const VESTING_ID: LockIdentifier = *b"vesting ";
+
+	/// The vesting schedule of version 0.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, sp_runtime::RuntimeDebug)]
+	pub(crate) struct OldVestingSchedule<Balance, BlockNumber> {
+		pub(crate) locked: Balance,
+		pub(crate) per_block: Balance,
+		pub(crate) starting_block: BlockNumber,
+	}
+
+	/// `Vesting` of version 0: the vesting schedule of each account.
+	pub(crate) struct OldVesting<T, I>(PhantomData<(T, I)>);
+
+	impl<T: Trait<I>, I: Instance> StoragePrefixedMap<OldVestingSchedule<T::Balance, T::BlockNumber>>
+		for OldVesting<T, I>
+	{
+		fn module_prefix() -> &'static [u8] { I::PREFIX.as_bytes() }
+		fn storage_prefix() -> &'static [u8] { b"Vesting" }
+	}
+
+	// migrate storage from v0 to v1.
+	//
+	// vesting moved to the vesting module, which enforces it with a lock instead of a check of
+	// this module. the unvested amount of each schedule is locked, and the schedules are removed
+	// once the vesting module copied them in its own migration.
+	//
+	// the accounts of the old map can't be recovered from its keys, but it uses the same hasher
+	// as `Locks`, so each lock is set under the same hashed key.
+	pub fn to_v1<T: Trait<I>, I: Instance>(version: &mut VersionNumber) {
+		if *version != 0 { return }
+		*version += 1;
+
+		let now = <system::Module<T>>::block_number();
+		let old_prefix = OldVesting::<T, I>::final_prefix();
+		let locks_prefix = <Locks<T, I> as StoragePrefixedMap<_>>::final_prefix();
+
+		let mut previous_key = old_prefix.to_vec();
+		while let Some(key) = sp_io::storage::next_key(&previous_key)
+			.filter(|key| key.starts_with(&old_prefix))
+		{
+			previous_key = key.clone();
+			let hashed_account = &key[old_prefix.len()..];
+
+			let schedule: OldVestingSchedule<T::Balance, T::BlockNumber> =
+				match unhashed::take(&key) {
+					Some(schedule) => schedule,
+					None => continue,
+				};
+			let vested_blocks: u128 = now.saturating_sub(schedule.starting_block)
+				.unique_saturated_into();
+			let vested = T::Balance::unique_saturated_from(vested_blocks)
+				.saturating_mul(schedule.per_block);
+			let locked = schedule.locked.saturating_sub(vested);
+			if locked.is_zero() {
+				continue
+			}
+
+			let locks_key = [&locks_prefix[..], hashed_account].concat();
+			let mut locks: Vec<BalanceLock<T::Balance, T::BlockNumber>> =
+				unhashed::get_or_default(&locks_key);
+			locks.retain(|lock| lock.id != VESTING_ID);
+			locks.push(BalanceLock {
+				id: VESTING_ID,
+				amount: locked,
+				until: T::BlockNumber::max_value(),
+				reasons: WithdrawReason::Transfer | WithdrawReason::Reserve,
+			});
+			unhashed::put(&locks_key, &locks);
+		}
+
+		support::print("Finished migrating Balances storage to v1.");
+	}
+
+	pub(crate) fn perform_migrations<T: Trait<I>, I: Instance>() {
+		<Module<T, I> as Store>::StorageVersion::mutate(|version| {
+			if *version < MIN_SUPPORTED_VERSION {
+				support::print("Cannot migrate balances storage because version is less than\
+					minimum.");
+				support::print(*version);
+				return
+			}
+
+			if *version == CURRENT_VERSION { return }
+
+			to_v1::<T, I>(version);
+		});
+	}
+}
+
+#[cfg(not(any(test, feature = "migrate")))]
+mod inner {
+	pub(super) fn perform_migrations<T, I>() { }
+}
+
+/// Perform all necessary storage migrations to get storage into the expected state for current
+/// logic. No-op if fully upgraded.
+pub(crate) fn perform_migrations<T: crate::Trait<I>, I: crate::Instance>() {
+	inner::perform_migrations::<T, I>();
+}
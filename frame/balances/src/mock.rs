@@ -105,7 +105,6 @@ pub struct ExtBuilder {
 	transfer_fee: u64,
 	creation_fee: u64,
 	monied: bool,
-	vesting: bool,
 }
 impl Default for ExtBuilder {
 	fn default() -> Self {
@@ -114,7 +113,6 @@ impl Default for ExtBuilder {
 			transfer_fee: 0,
 			creation_fee: 0,
 			monied: false,
-			vesting: false,
 		}
 	}
 }
@@ -139,10 +137,6 @@ impl ExtBuilder {
 		}
 		self
 	}
-	pub fn vesting(mut self, vesting: bool) -> Self {
-		self.vesting = vesting;
-		self
-	}
 	pub fn set_associated_consts(&self) {
 		EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = self.existential_deposit);
 		TRANSFER_FEE.with(|v| *v.borrow_mut() = self.transfer_fee);
@@ -163,15 +157,6 @@ impl ExtBuilder {
 			} else {
 				vec![]
 			},
-			vesting: if self.vesting && self.monied {
-				vec![
-					(1, 0, 10, 5 * self.existential_deposit),
-					(2, 10, 20, 0),
-					(12, 10, 20, 5 * self.existential_deposit)
-				]
-			} else {
-				vec![]
-			},
 		}.assimilate_storage(&mut t).unwrap();
 		t.into()
 	}
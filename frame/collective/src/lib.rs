@@ -19,6 +19,10 @@
 //!
 //! The membership can be provided in one of two ways: either directly, using the Root-dispatchable
 //! function `set_members`, or indirectly, through implementing the `ChangeMembers`
+//!
+//! A motion which has not been decided by the votes cast within `MotionDuration` blocks of being
+//! proposed can be closed by anyone. Members who did not vote are then counted as voting the
+//! same as the prime member, if there is one and it voted aye, and as nays otherwise.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![recursion_limit="128"]
@@ -27,14 +31,19 @@ use sp_std::{prelude::*, result};
 use primitives::u32_trait::Value as U32;
 use sp_runtime::RuntimeDebug;
 use sp_runtime::traits::{Hash, EnsureOrigin};
-use support::weights::SimpleDispatchInfo;
+use support::weights::{
+	SimpleDispatchInfo, GetDispatchInfo, Weight, WeighData, ClassifyDispatch, DispatchClass,
+	PaysFee,
+};
 use support::{
 	dispatch::{Dispatchable, Parameter}, codec::{Encode, Decode},
-	traits::{ChangeMembers, InitializeMembers}, decl_module, decl_event,
+	traits::{ChangeMembers, InitializeMembers, Get}, decl_module, decl_event,
 	decl_storage, ensure,
 };
 use system::{self, ensure_signed, ensure_root};
 
+mod migration;
+
 /// Simple index type for proposal counting.
 pub type ProposalIndex = u32;
 
@@ -49,10 +58,16 @@ pub trait Trait<I=DefaultInstance>: system::Trait {
 	type Origin: From<RawOrigin<Self::AccountId, I>>;
 
 	/// The outer call dispatch type.
-	type Proposal: Parameter + Dispatchable<Origin=<Self as Trait<I>>::Origin>;
+	type Proposal: Parameter + Dispatchable<Origin=<Self as Trait<I>>::Origin> + GetDispatchInfo;
 
 	/// The outer event type.
 	type Event: From<Event<Self, I>> + Into<<Self as system::Trait>::Event>;
+
+	/// The number of blocks after which a motion can be closed by anyone.
+	type MotionDuration: Get<Self::BlockNumber>;
+
+	/// The maximum number of motions that can be open at the same time.
+	type MaxProposals: Get<ProposalIndex>;
 }
 
 /// Origin for the collective module.
@@ -71,7 +86,7 @@ pub type Origin<T, I=DefaultInstance> = RawOrigin<<T as system::Trait>::AccountI
 
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
 /// Info for keeping track of a motion being voted on.
-pub struct Votes<AccountId> {
+pub struct Votes<AccountId, BlockNumber> {
 	/// The proposal's unique index.
 	index: ProposalIndex,
 	/// The number of approval votes that are needed to pass the motion.
@@ -80,8 +95,27 @@ pub struct Votes<AccountId> {
 	ayes: Vec<AccountId>,
 	/// The current set of voters that rejected it.
 	nays: Vec<AccountId>,
+	/// The block from which the motion can be closed.
+	end: BlockNumber,
+}
+
+/// The weight of `close`: a fixed cost on top of the bound given for the weight of the proposal.
+pub struct WeightForClose;
+
+impl<H, N> WeighData<(&H, &N, &Weight)> for WeightForClose {
+	fn weigh_data(&self, (_, _, proposal_weight_bound): (&H, &N, &Weight)) -> Weight {
+		proposal_weight_bound.saturating_add(200_000)
+	}
 }
 
+impl<T> ClassifyDispatch<T> for WeightForClose {
+	fn classify_dispatch(&self, _: T) -> DispatchClass {
+		DispatchClass::Operational
+	}
+}
+
+impl PaysFee for WeightForClose {}
+
 decl_storage! {
 	trait Store for Module<T: Trait<I>, I: Instance=DefaultInstance> as Collective {
 		/// The hashes of the active proposals.
@@ -89,11 +123,16 @@ decl_storage! {
 		/// Actual proposal for a given hash, if it's current.
 		pub ProposalOf get(fn proposal_of): map T::Hash => Option<<T as Trait<I>>::Proposal>;
 		/// Votes on a given proposal, if it is ongoing.
-		pub Voting get(fn voting): map T::Hash => Option<Votes<T::AccountId>>;
+		pub Voting get(fn voting): map T::Hash => Option<Votes<T::AccountId, T::BlockNumber>>;
 		/// Proposals so far.
 		pub ProposalCount get(fn proposal_count): u32;
 		/// The current members of the collective. This is stored sorted (just by value).
 		pub Members get(fn members): Vec<T::AccountId>;
+		/// The member whose vote is the default for those who abstain, if any.
+		pub Prime get(fn prime): Option<T::AccountId>;
+
+		/// The version of storage for upgrade.
+		StorageVersion build(|_| migration::CURRENT_VERSION): u32;
 	}
 	add_extra_genesis {
 		config(phantom): sp_std::marker::PhantomData<I>;
@@ -121,6 +160,9 @@ decl_event!(
 		Executed(Hash, bool),
 		/// A single member did some action; `bool` is true if returned without error.
 		MemberExecuted(Hash, bool),
+		/// A motion was closed after its voting period, with the given final tally (yes votes and
+		/// no votes given respectively as `MemberCount`).
+		Closed(Hash, MemberCount, MemberCount),
 	}
 );
 
@@ -131,19 +173,27 @@ decl_module! {
 	pub struct Module<T: Trait<I>, I: Instance=DefaultInstance> for enum Call where origin: <T as system::Trait>::Origin {
 		fn deposit_event() = default;
 
-		/// Set the collective's membership manually to `new_members`. Be nice to the chain and
-		/// provide it pre-sorted.
+		fn on_initialize() {
+			migration::perform_migrations::<T, I>();
+		}
+
+		/// Set the collective's membership manually to `new_members`, and its prime member to
+		/// `prime`. Be nice to the chain and provide it pre-sorted.
 		///
 		/// Requires root origin.
 		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
-		fn set_members(origin, new_members: Vec<T::AccountId>) {
+		fn set_members(origin, new_members: Vec<T::AccountId>, prime: Option<T::AccountId>) {
 			ensure_root(origin)?;
 			let mut new_members = new_members;
 			new_members.sort();
+			if let Some(p) = &prime {
+				ensure!(new_members.binary_search(p).is_ok(), "prime not a member");
+			}
 			<Members<T, I>>::mutate(|m| {
 				<Self as ChangeMembers<T::AccountId>>::set_members_sorted(&new_members[..], m);
 				*m = new_members;
 			});
+			<Self as ChangeMembers<T::AccountId>>::set_prime(prime);
 		}
 
 		/// Dispatch a proposal from a member using the `Member` origin.
@@ -177,11 +227,19 @@ decl_module! {
 				let ok = proposal.dispatch(RawOrigin::Members(1, seats).into()).is_ok();
 				Self::deposit_event(RawEvent::Executed(proposal_hash, ok));
 			} else {
+				let mut proposals = Self::proposals();
+				ensure!(
+					(proposals.len() as ProposalIndex) < T::MaxProposals::get(),
+					"too many proposals",
+				);
+				proposals.push(proposal_hash);
+				<Proposals<T, I>>::put(proposals);
+
 				let index = Self::proposal_count();
 				<ProposalCount<I>>::mutate(|i| *i += 1);
-				<Proposals<T, I>>::mutate(|proposals| proposals.push(proposal_hash));
 				<ProposalOf<T, I>>::insert(proposal_hash, *proposal);
-				let votes = Votes { index, threshold, ayes: vec![who.clone()], nays: vec![] };
+				let end = <system::Module<T>>::block_number() + T::MotionDuration::get();
+				let votes = Votes { index, threshold, ayes: vec![who.clone()], nays: vec![], end };
 				<Voting<T, I>>::insert(proposal_hash, votes);
 
 				Self::deposit_event(RawEvent::Proposed(who, index, proposal_hash, threshold));
@@ -230,29 +288,79 @@ decl_module! {
 			let seats = Self::members().len() as MemberCount;
 			let approved = yes_votes >= voting.threshold;
 			let disapproved = seats.saturating_sub(no_votes) < voting.threshold;
-			if approved || disapproved {
-				if approved {
-					Self::deposit_event(RawEvent::Approved(proposal));
-
-					// execute motion, assuming it exists.
-					if let Some(p) = <ProposalOf<T, I>>::take(&proposal) {
-						let origin = RawOrigin::Members(voting.threshold, seats).into();
-						let ok = p.dispatch(origin).is_ok();
-						Self::deposit_event(RawEvent::Executed(proposal, ok));
-					}
-				} else {
-					// disapproved
-					Self::deposit_event(RawEvent::Disapproved(proposal));
-				}
-
-				// remove vote
-				<Voting<T, I>>::remove(&proposal);
-				<Proposals<T, I>>::mutate(|proposals| proposals.retain(|h| h != &proposal));
+			if approved {
+				Self::do_approve_proposal(seats, voting, proposal);
+			} else if disapproved {
+				Self::do_disapprove_proposal(proposal);
 			} else {
 				// update voting
 				<Voting<T, I>>::insert(&proposal, voting);
 			}
 		}
+
+		/// Close a motion whose voting period has ended, counting the members who did not vote as
+		/// voting the same as the prime member if it voted aye, and as nays otherwise.
+		///
+		/// If the motion is approved, it is dispatched; `proposal_weight_bound` must be at least
+		/// the weight of the proposal, and is charged on top of the weight of closing.
+		///
+		/// May be called by any signed account.
+		///
+		/// # <weight>
+		/// - Bounded storage reads and writes.
+		/// - Argument `proposal_weight_bound` has bearing on weight.
+		/// # </weight>
+		#[weight = WeightForClose]
+		fn close(
+			origin,
+			proposal: T::Hash,
+			#[compact] index: ProposalIndex,
+			#[compact] proposal_weight_bound: Weight,
+		) {
+			let _ = ensure_signed(origin)?;
+
+			let voting = Self::voting(&proposal).ok_or("proposal must exist")?;
+			ensure!(voting.index == index, "mismatched index");
+			ensure!(<system::Module<T>>::block_number() >= voting.end, "motion has not ended yet");
+
+			// abstentions default to aye only if there is a prime and it voted aye.
+			let default = Self::prime().map_or(false, |who| voting.ayes.iter().any(|a| a == &who));
+
+			let mut yes_votes = voting.ayes.len() as MemberCount;
+			let mut no_votes = voting.nays.len() as MemberCount;
+			let seats = Self::members().len() as MemberCount;
+			let abstentions = seats.saturating_sub(yes_votes + no_votes);
+			if default {
+				yes_votes += abstentions;
+			} else {
+				no_votes += abstentions;
+			}
+			let approved = yes_votes >= voting.threshold;
+			if approved {
+				if let Some(p) = Self::proposal_of(&proposal) {
+					let weight = p.get_dispatch_info().weight;
+					ensure!(weight <= proposal_weight_bound, "proposal weight exceeds the bound");
+				}
+			}
+
+			Self::deposit_event(RawEvent::Closed(proposal, yes_votes, no_votes));
+			if approved {
+				Self::do_approve_proposal(seats, voting, proposal);
+			} else {
+				Self::do_disapprove_proposal(proposal);
+			}
+		}
+
+		/// Disapprove a proposal, close, and remove it from the system, regardless of its current
+		/// state.
+		///
+		/// Requires root origin.
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
+		fn disapprove_proposal(origin, proposal_hash: T::Hash) {
+			ensure_root(origin)?;
+			ensure!(<Voting<T, I>>::exists(&proposal_hash), "proposal must exist");
+			Self::do_disapprove_proposal(proposal_hash);
+		}
 	}
 }
 
@@ -260,6 +368,37 @@ impl<T: Trait<I>, I: Instance> Module<T, I> {
 	pub fn is_member(who: &T::AccountId) -> bool {
 		Self::members().contains(who)
 	}
+
+	/// Dispatch an approved motion, then remove it.
+	fn do_approve_proposal(
+		seats: MemberCount,
+		voting: Votes<T::AccountId, T::BlockNumber>,
+		proposal_hash: T::Hash,
+	) {
+		Self::deposit_event(RawEvent::Approved(proposal_hash));
+
+		// execute motion, assuming it exists.
+		if let Some(p) = <ProposalOf<T, I>>::take(&proposal_hash) {
+			let origin = RawOrigin::Members(voting.threshold, seats).into();
+			let ok = p.dispatch(origin).is_ok();
+			Self::deposit_event(RawEvent::Executed(proposal_hash, ok));
+		}
+
+		Self::remove_proposal(proposal_hash);
+	}
+
+	/// Remove a disapproved motion.
+	fn do_disapprove_proposal(proposal_hash: T::Hash) {
+		Self::deposit_event(RawEvent::Disapproved(proposal_hash));
+		<ProposalOf<T, I>>::remove(&proposal_hash);
+		Self::remove_proposal(proposal_hash);
+	}
+
+	/// Remove the votes on a motion and drop it from the active proposals.
+	fn remove_proposal(proposal_hash: T::Hash) {
+		<Voting<T, I>>::remove(&proposal_hash);
+		<Proposals<T, I>>::mutate(|proposals| proposals.retain(|h| h != &proposal_hash));
+	}
 }
 
 impl<T: Trait<I>, I: Instance> ChangeMembers<T::AccountId> for Module<T, I> {
@@ -281,6 +420,17 @@ impl<T: Trait<I>, I: Instance> ChangeMembers<T::AccountId> for Module<T, I> {
 			);
 		}
 		<Members<T, I>>::put(new);
+		// an outgoing prime must be replaced through `set_prime`.
+		if Self::prime().map_or(false, |p| outgoing.binary_search(&p).is_ok()) {
+			<Prime<T, I>>::kill();
+		}
+	}
+
+	fn set_prime(prime: Option<T::AccountId>) {
+		match prime {
+			Some(who) => <Prime<T, I>>::put(who),
+			None => <Prime<T, I>>::kill(),
+		}
 	}
 }
 
@@ -393,6 +543,8 @@ mod tests {
 		pub const MaximumBlockWeight: Weight = 1024;
 		pub const MaximumBlockLength: u32 = 2 * 1024;
 		pub const AvailableBlockRatio: Perbill = Perbill::one();
+		pub const MotionDuration: u64 = 3;
+		pub const MaxProposals: u32 = 3;
 	}
 	impl system::Trait for Test {
 		type Origin = Origin;
@@ -415,11 +567,15 @@ mod tests {
 		type Origin = Origin;
 		type Proposal = Call;
 		type Event = Event;
+		type MotionDuration = MotionDuration;
+		type MaxProposals = MaxProposals;
 	}
 	impl Trait for Test {
 		type Origin = Origin;
 		type Proposal = Call;
 		type Event = Event;
+		type MotionDuration = MotionDuration;
+		type MaxProposals = MaxProposals;
 	}
 
 	pub type Block = sp_runtime::generic::Block<Header, UncheckedExtrinsic>;
@@ -460,6 +616,10 @@ mod tests {
 		Call::System(system::Call::remark(value.encode()))
 	}
 
+	fn record(event: Event) -> EventRecord<Event, H256> {
+		EventRecord { phase: Phase::Finalization, event, topics: vec![] }
+	}
+
 	#[test]
 	fn removal_of_old_voters_votes_works() {
 		make_ext().execute_with(|| {
@@ -470,12 +630,12 @@ mod tests {
 			assert_ok!(Collective::vote(Origin::signed(2), hash.clone(), 0, true));
 			assert_eq!(
 				Collective::voting(&hash),
-				Some(Votes { index: 0, threshold: 3, ayes: vec![1, 2], nays: vec![] })
+				Some(Votes { index: 0, threshold: 3, ayes: vec![1, 2], nays: vec![], end: 4 })
 			);
 			Collective::change_members_sorted(&[4], &[1], &[2, 3, 4]);
 			assert_eq!(
 				Collective::voting(&hash),
-				Some(Votes { index: 0, threshold: 3, ayes: vec![2], nays: vec![] })
+				Some(Votes { index: 0, threshold: 3, ayes: vec![2], nays: vec![], end: 4 })
 			);
 
 			let proposal = make_proposal(69);
@@ -484,12 +644,12 @@ mod tests {
 			assert_ok!(Collective::vote(Origin::signed(3), hash.clone(), 1, false));
 			assert_eq!(
 				Collective::voting(&hash),
-				Some(Votes { index: 1, threshold: 2, ayes: vec![2], nays: vec![3] })
+				Some(Votes { index: 1, threshold: 2, ayes: vec![2], nays: vec![3], end: 4 })
 			);
 			Collective::change_members_sorted(&[], &[3], &[2, 4]);
 			assert_eq!(
 				Collective::voting(&hash),
-				Some(Votes { index: 1, threshold: 2, ayes: vec![2], nays: vec![] })
+				Some(Votes { index: 1, threshold: 2, ayes: vec![2], nays: vec![], end: 4 })
 			);
 		});
 	}
@@ -504,12 +664,12 @@ mod tests {
 			assert_ok!(Collective::vote(Origin::signed(2), hash.clone(), 0, true));
 			assert_eq!(
 				Collective::voting(&hash),
-				Some(Votes { index: 0, threshold: 3, ayes: vec![1, 2], nays: vec![] })
+				Some(Votes { index: 0, threshold: 3, ayes: vec![1, 2], nays: vec![], end: 4 })
 			);
-			assert_ok!(Collective::set_members(Origin::ROOT, vec![2, 3, 4]));
+			assert_ok!(Collective::set_members(Origin::ROOT, vec![2, 3, 4], None));
 			assert_eq!(
 				Collective::voting(&hash),
-				Some(Votes { index: 0, threshold: 3, ayes: vec![2], nays: vec![] })
+				Some(Votes { index: 0, threshold: 3, ayes: vec![2], nays: vec![], end: 4 })
 			);
 
 			let proposal = make_proposal(69);
@@ -518,12 +678,12 @@ mod tests {
 			assert_ok!(Collective::vote(Origin::signed(3), hash.clone(), 1, false));
 			assert_eq!(
 				Collective::voting(&hash),
-				Some(Votes { index: 1, threshold: 2, ayes: vec![2], nays: vec![3] })
+				Some(Votes { index: 1, threshold: 2, ayes: vec![2], nays: vec![3], end: 4 })
 			);
-			assert_ok!(Collective::set_members(Origin::ROOT, vec![2, 4]));
+			assert_ok!(Collective::set_members(Origin::ROOT, vec![2, 4], None));
 			assert_eq!(
 				Collective::voting(&hash),
-				Some(Votes { index: 1, threshold: 2, ayes: vec![2], nays: vec![] })
+				Some(Votes { index: 1, threshold: 2, ayes: vec![2], nays: vec![], end: 4 })
 			);
 		});
 	}
@@ -539,7 +699,7 @@ mod tests {
 			assert_eq!(Collective::proposal_of(&hash), Some(proposal));
 			assert_eq!(
 				Collective::voting(&hash),
-				Some(Votes { index: 0, threshold: 3, ayes: vec![1], nays: vec![] })
+				Some(Votes { index: 0, threshold: 3, ayes: vec![1], nays: vec![], end: 4 })
 			);
 
 			assert_eq!(System::events(), vec![
@@ -606,7 +766,7 @@ mod tests {
 			assert_ok!(Collective::propose(Origin::signed(1), 2, Box::new(proposal.clone())));
 			assert_eq!(
 				Collective::voting(&hash),
-				Some(Votes { index: 0, threshold: 2, ayes: vec![1], nays: vec![] })
+				Some(Votes { index: 0, threshold: 2, ayes: vec![1], nays: vec![], end: 4 })
 			);
 			assert_noop!(
 				Collective::vote(Origin::signed(1), hash.clone(), 0, true),
@@ -615,7 +775,7 @@ mod tests {
 			assert_ok!(Collective::vote(Origin::signed(1), hash.clone(), 0, false));
 			assert_eq!(
 				Collective::voting(&hash),
-				Some(Votes { index: 0, threshold: 2, ayes: vec![], nays: vec![1] })
+				Some(Votes { index: 0, threshold: 2, ayes: vec![], nays: vec![1], end: 4 })
 			);
 			assert_noop!(
 				Collective::vote(Origin::signed(1), hash.clone(), 0, false),
@@ -740,4 +900,141 @@ mod tests {
 			]);
 		});
 	}
+
+	#[test]
+	fn close_works() {
+		make_ext().execute_with(|| {
+			System::set_block_number(1);
+			let proposal = make_proposal(42);
+			let hash: H256 = proposal.blake2_256().into();
+			assert_ok!(Collective::propose(Origin::signed(1), 3, Box::new(proposal.clone())));
+			assert_ok!(Collective::vote(Origin::signed(2), hash.clone(), 0, true));
+
+			System::set_block_number(3);
+			assert_noop!(
+				Collective::close(Origin::signed(4), hash.clone(), 0, 0),
+				"motion has not ended yet",
+			);
+			assert_noop!(
+				Collective::close(Origin::signed(4), hash.clone(), 1, 0),
+				"mismatched index",
+			);
+
+			// without a prime, the abstention counts as a nay.
+			System::set_block_number(4);
+			assert_ok!(Collective::close(Origin::signed(4), hash.clone(), 0, 0));
+			assert_eq!(Collective::proposals(), Vec::<H256>::new());
+			assert_eq!(Collective::proposal_of(&hash), None);
+			assert_eq!(Collective::voting(&hash), None);
+
+			assert_eq!(System::events(), vec![
+				record(Event::collective_Instance1(RawEvent::Proposed(1, 0, hash.clone(), 3))),
+				record(Event::collective_Instance1(RawEvent::Voted(2, hash.clone(), true, 2, 0))),
+				record(Event::collective_Instance1(RawEvent::Closed(hash.clone(), 2, 1))),
+				record(Event::collective_Instance1(RawEvent::Disapproved(hash.clone()))),
+			]);
+		});
+	}
+
+	#[test]
+	fn close_with_prime_works() {
+		make_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Collective::set_members(Origin::ROOT, vec![1, 2, 3], Some(3)));
+			let proposal = make_proposal(42);
+			let hash: H256 = proposal.blake2_256().into();
+			assert_ok!(Collective::propose(Origin::signed(1), 3, Box::new(proposal.clone())));
+			assert_ok!(Collective::vote(Origin::signed(3), hash.clone(), 0, true));
+
+			// the prime voted aye, so the abstention counts as an aye.
+			System::set_block_number(4);
+			assert_noop!(
+				Collective::close(Origin::signed(4), hash.clone(), 0, 9_999),
+				"proposal weight exceeds the bound",
+			);
+			assert_ok!(Collective::close(Origin::signed(4), hash.clone(), 0, 10_000));
+			assert_eq!(Collective::proposals(), Vec::<H256>::new());
+			assert_eq!(Collective::proposal_of(&hash), None);
+
+			assert_eq!(System::events(), vec![
+				record(Event::collective_Instance1(RawEvent::Proposed(1, 0, hash.clone(), 3))),
+				record(Event::collective_Instance1(RawEvent::Voted(3, hash.clone(), true, 2, 0))),
+				record(Event::collective_Instance1(RawEvent::Closed(hash.clone(), 3, 0))),
+				record(Event::collective_Instance1(RawEvent::Approved(hash.clone()))),
+				record(Event::collective_Instance1(RawEvent::Executed(hash.clone(), false))),
+			]);
+		});
+	}
+
+	#[test]
+	fn prime_is_reset_when_it_leaves_membership() {
+		make_ext().execute_with(|| {
+			assert_noop!(
+				Collective::set_members(Origin::ROOT, vec![1, 2, 3], Some(4)),
+				"prime not a member",
+			);
+			assert_ok!(Collective::set_members(Origin::ROOT, vec![1, 2, 3], Some(3)));
+			assert_eq!(Collective::prime(), Some(3));
+			Collective::change_members_sorted(&[4], &[1], &[2, 3, 4]);
+			assert_eq!(Collective::prime(), Some(3));
+			Collective::change_members_sorted(&[1], &[3], &[1, 2, 4]);
+			assert_eq!(Collective::prime(), None);
+		});
+	}
+
+	#[test]
+	fn limit_active_proposals() {
+		make_ext().execute_with(|| {
+			System::set_block_number(1);
+			for i in 0..MaxProposals::get() {
+				let proposal = make_proposal(i as u64);
+				assert_ok!(Collective::propose(Origin::signed(1), 3, Box::new(proposal)));
+			}
+			let proposal = make_proposal(MaxProposals::get() as u64);
+			assert_noop!(
+				Collective::propose(Origin::signed(1), 3, Box::new(proposal)),
+				"too many proposals",
+			);
+		});
+	}
+
+	#[test]
+	fn disapprove_proposal_works() {
+		make_ext().execute_with(|| {
+			System::set_block_number(1);
+			let proposal = make_proposal(42);
+			let hash: H256 = proposal.blake2_256().into();
+			assert_ok!(Collective::propose(Origin::signed(1), 2, Box::new(proposal.clone())));
+			assert_noop!(
+				Collective::disapprove_proposal(Origin::ROOT, H256::zero()),
+				"proposal must exist",
+			);
+			assert_ok!(Collective::disapprove_proposal(Origin::ROOT, hash.clone()));
+			assert_eq!(Collective::proposals(), Vec::<H256>::new());
+			assert_eq!(Collective::proposal_of(&hash), None);
+			assert_eq!(Collective::voting(&hash), None);
+		});
+	}
+
+	#[test]
+	fn migration_to_v1_works() {
+		use support::{StorageValue, StorageMap};
+		use migration::inner::OldVoting;
+
+		make_ext().execute_with(|| {
+			System::set_block_number(1);
+			let proposal = make_proposal(42);
+			let hash = BlakeTwo256::hash_of(&proposal);
+			<Collective as Store>::StorageVersion::put(0);
+			<Collective as Store>::Proposals::put(vec![hash]);
+			OldVoting::<Test, Instance1>::insert(&hash, (0, 2, vec![1], vec![2]));
+
+			migration::perform_migrations::<Test, Instance1>();
+			assert_eq!(<Collective as Store>::StorageVersion::get(), 1);
+			assert_eq!(
+				Collective::voting(&hash),
+				Some(Votes { index: 0, threshold: 2, ayes: vec![1], nays: vec![2], end: 4 })
+			);
+		});
+	}
 }
@@ -0,0 +1,103 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage migrations for pallet-collective.
+
+/// Indicator of a version of a storage layout.
+pub type VersionNumber = u32;
+
+// the current expected version of the storage
+pub const CURRENT_VERSION: VersionNumber = 1;
+
+#[cfg(any(test, feature = "migrate"))]
+pub(crate) mod inner {
+	use crate::{Store, Module, Trait, Instance, Votes, ProposalIndex, MemberCount};
+	use support::{
+		StorageValue, StorageMap, Blake2_256,
+		storage::{self, generator},
+		traits::Get,
+	};
+	use sp_std::{prelude::*, marker::PhantomData};
+	use super::{CURRENT_VERSION, VersionNumber};
+
+	// the minimum supported version of the migration logic.
+	const MIN_SUPPORTED_VERSION: VersionNumber = 0;
+
+	/// The votes of a motion in version 0: `(index, threshold, ayes, nays)`.
+	pub(crate) type OldVotes<AccountId> =
+		(ProposalIndex, MemberCount, Vec<AccountId>, Vec<AccountId>);
+
+	/// `Voting` of version 0.
+	pub(crate) struct OldVoting<T, I>(PhantomData<(T, I)>);
+
+	impl<T: Trait<I>, I: Instance> generator::StorageMap<T::Hash, OldVotes<T::AccountId>>
+		for OldVoting<T, I>
+	{
+		type Query = Option<OldVotes<T::AccountId>>;
+		type Hasher = Blake2_256;
+		fn module_prefix() -> &'static [u8] { I::PREFIX.as_bytes() }
+		fn storage_prefix() -> &'static [u8] { b"Voting" }
+		fn from_optional_value_to_query(v: Option<OldVotes<T::AccountId>>) -> Self::Query { v }
+		fn from_query_to_optional_value(v: Self::Query) -> Option<OldVotes<T::AccountId>> { v }
+	}
+
+	// migrate storage from v0 to v1.
+	//
+	// motions now end `MotionDuration` blocks after being proposed, after which they can be
+	// closed. this gives the motions in progress a full `MotionDuration` from now.
+	pub fn to_v1<T: Trait<I>, I: Instance>(version: &mut VersionNumber) {
+		if *version != 0 { return }
+		*version += 1;
+
+		let end = <system::Module<T>>::block_number() + T::MotionDuration::get();
+		for hash in <Module<T, I> as Store>::Proposals::get().iter() {
+			if let Some((index, threshold, ayes, nays)) =
+				<OldVoting<T, I> as storage::StorageMap<_, _>>::get(hash)
+			{
+				let votes = Votes { index, threshold, ayes, nays, end };
+				<Module<T, I> as Store>::Voting::insert(hash, votes);
+			}
+		}
+
+		support::print("Finished migrating Collective storage to v1.");
+	}
+
+	pub(crate) fn perform_migrations<T: Trait<I>, I: Instance>() {
+		<Module<T, I> as Store>::StorageVersion::mutate(|version| {
+			if *version < MIN_SUPPORTED_VERSION {
+				support::print("Cannot migrate collective storage because version is less than\
+					minimum.");
+				support::print(*version);
+				return
+			}
+
+			if *version == CURRENT_VERSION { return }
+
+			to_v1::<T, I>(version);
+		});
+	}
+}
+
+#[cfg(not(any(test, feature = "migrate")))]
+mod inner {
+	pub(super) fn perform_migrations<T, I>() { }
+}
+
+/// Perform all necessary storage migrations to get storage into the expected state for current
+/// logic. No-op if fully upgraded.
+pub(crate) fn perform_migrations<T: crate::Trait<I>, I: crate::Instance>() {
+	inner::perform_migrations::<T, I>();
+}
@@ -19,12 +19,9 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use sp_std::prelude::*;
-use sp_std::{result, convert::TryFrom};
-use sp_runtime::{
-	RuntimeDebug,
-	traits::{Zero, Bounded, CheckedMul, CheckedDiv, EnsureOrigin, Hash, Dispatchable, Saturating},
-};
-use codec::{Ref, Encode, Decode, Input, Output, Error};
+use sp_std::result;
+use sp_runtime::traits::{Zero, Bounded, EnsureOrigin, Hash, Dispatchable, Saturating};
+use codec::{Ref, Decode};
 use support::{
 	decl_module, decl_storage, decl_event, ensure,
 	dispatch,
@@ -38,9 +35,21 @@ use support::{
 use system::{ensure_signed, ensure_root};
 
 mod vote_threshold;
+mod vote;
+mod conviction;
+mod types;
+mod migration;
 pub use vote_threshold::{Approved, VoteThreshold};
+pub use vote::{Vote, AccountVote, Voting, Delegations, PriorLock};
+pub use conviction::Conviction;
+pub use types::{ReferendumInfo, ReferendumStatus, Tally};
+
+/// The identifier of the voting locks. It differs from the one of storage version 0, whose
+/// conviction locks expire on their own and must not be overwritten.
+const DEMOCRACY_ID: LockIdentifier = *b"democ_v1";
 
-const DEMOCRACY_ID: LockIdentifier = *b"democrac";
+/// The maximum number of referenda an account may be voting on at the same time.
+const MAX_VOTES: usize = 100;
 
 /// A proposal index.
 pub type PropIndex = u32;
@@ -48,131 +57,6 @@ pub type PropIndex = u32;
 /// A referendum index.
 pub type ReferendumIndex = u32;
 
-/// A value denoting the strength of conviction of a vote.
-#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug)]
-pub enum Conviction {
-	/// 0.1x votes, unlocked.
-	None,
-	/// 1x votes, locked for an enactment period following a successful vote.
-	Locked1x,
-	/// 2x votes, locked for 2x enactment periods following a successful vote.
-	Locked2x,
-	/// 3x votes, locked for 4x...
-	Locked3x,
-	/// 4x votes, locked for 8x...
-	Locked4x,
-	/// 5x votes, locked for 16x...
-	Locked5x,
-	/// 6x votes, locked for 32x...
-	Locked6x,
-}
-
-impl Default for Conviction {
-	fn default() -> Self {
-		Conviction::None
-	}
-}
-
-impl From<Conviction> for u8 {
-	fn from(c: Conviction) -> u8 {
-		match c {
-			Conviction::None => 0,
-			Conviction::Locked1x => 1,
-			Conviction::Locked2x => 2,
-			Conviction::Locked3x => 3,
-			Conviction::Locked4x => 4,
-			Conviction::Locked5x => 5,
-			Conviction::Locked6x => 6,
-		}
-	}
-}
-
-impl TryFrom<u8> for Conviction {
-	type Error = ();
-	fn try_from(i: u8) -> result::Result<Conviction, ()> {
-		Ok(match i {
-			0 => Conviction::None,
-			1 => Conviction::Locked1x,
-			2 => Conviction::Locked2x,
-			3 => Conviction::Locked3x,
-			4 => Conviction::Locked4x,
-			5 => Conviction::Locked5x,
-			6 => Conviction::Locked6x,
-			_ => return Err(()),
-		})
-	}
-}
-
-impl Conviction {
-	/// The amount of time (in number of periods) that our conviction implies a successful voter's
-	/// balance should be locked for.
-	fn lock_periods(self) -> u32 {
-		match self {
-			Conviction::None => 0,
-			Conviction::Locked1x => 1,
-			Conviction::Locked2x => 2,
-			Conviction::Locked3x => 4,
-			Conviction::Locked4x => 8,
-			Conviction::Locked5x => 16,
-			Conviction::Locked6x => 32,
-		}
-	}
-
-	/// The votes of a voter of the given `balance` with our conviction.
-	fn votes<
-		B: From<u8> + Zero + Copy + CheckedMul + CheckedDiv + Bounded
-	>(self, balance: B) -> (B, B) {
-		match self {
-			Conviction::None => {
-				let r = balance.checked_div(&10u8.into()).unwrap_or_else(Zero::zero);
-				(r, r)
-			}
-			x => (
-				balance.checked_mul(&u8::from(x).into()).unwrap_or_else(B::max_value),
-				balance,
-			)
-		}
-	}
-}
-
-impl Bounded for Conviction {
-	fn min_value() -> Self {
-		Conviction::None
-	}
-
-	fn max_value() -> Self {
-		Conviction::Locked6x
-	}
-}
-
-const MAX_RECURSION_LIMIT: u32 = 16;
-
-/// A number of lock periods, plus a vote, one way or the other.
-#[derive(Copy, Clone, Eq, PartialEq, Default, RuntimeDebug)]
-pub struct Vote {
-	pub aye: bool,
-	pub conviction: Conviction,
-}
-
-impl Encode for Vote {
-	fn encode_to<T: Output>(&self, output: &mut T) {
-		output.push_byte(u8::from(self.conviction) | if self.aye { 0b1000_0000 } else { 0 });
-	}
-}
-
-impl codec::EncodeLike for Vote {}
-
-impl Decode for Vote {
-	fn decode<I: Input>(input: &mut I) -> core::result::Result<Self, Error> {
-		let b = input.read_byte()?;
-		Ok(Vote {
-			aye: (b & 0b1000_0000) == 0b1000_0000,
-			conviction: Conviction::try_from(b & 0b0111_1111)
-				.map_err(|_| Error::from("Invalid conviction"))?,
-		})
-	}
-}
-
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 type NegativeImbalanceOf<T> =
 <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
@@ -237,29 +121,13 @@ pub trait Trait: system::Trait + Sized {
 	type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
 }
 
-/// Info regarding an ongoing referendum.
-#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
-pub struct ReferendumInfo<BlockNumber: Parameter, Hash: Parameter> {
-	/// When voting on this referendum will end.
-	end: BlockNumber,
-	/// The hash of the proposal being voted on.
-	proposal_hash: Hash,
-	/// The thresholding mechanism to determine whether it passed.
-	threshold: VoteThreshold,
-	/// The delay (in blocks) to wait after a successful referendum before deploying.
-	delay: BlockNumber,
-}
-
-impl<BlockNumber: Parameter, Hash: Parameter> ReferendumInfo<BlockNumber, Hash> {
-	/// Create a new instance.
-	pub fn new(
-		end: BlockNumber,
-		proposal_hash: Hash,
-		threshold: VoteThreshold,
-		delay: BlockNumber
-	) -> Self {
-		ReferendumInfo { end, proposal_hash, threshold, delay }
-	}
+/// Whether a vote may be removed by anyone, or only once it no longer needs a lock.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum UnvoteScope {
+	/// Permitted to do everything.
+	Any,
+	/// Permitted to remove only votes which are no longer locking any balance.
+	OnlyExpired,
 }
 
 decl_storage! {
@@ -281,26 +149,19 @@ decl_storage! {
 		pub LowestUnbaked get(fn lowest_unbaked) build(|_| 0 as ReferendumIndex): ReferendumIndex;
 		/// Information concerning any given referendum.
 		pub ReferendumInfoOf get(fn referendum_info):
-			map ReferendumIndex => Option<(ReferendumInfo<T::BlockNumber, T::Hash>)>;
+			map ReferendumIndex => Option<ReferendumInfo<T::BlockNumber, T::Hash, BalanceOf<T>>>;
 		/// Queue of successful referenda to be dispatched. Stored ordered by block number.
 		pub DispatchQueue get(fn dispatch_queue): Vec<(T::BlockNumber, T::Hash, ReferendumIndex)>;
 
-		/// Get the voters for the current proposal.
-		pub VotersFor get(fn voters_for): map ReferendumIndex => Vec<T::AccountId>;
-
-		/// Get the vote in a given referendum of a particular voter. The result is meaningful only
-		/// if `voters_for` includes the voter when called with the referendum (you'll get the
-		/// default `Vote` value otherwise). If you don't want to check `voters_for`, then you can
-		/// also check for simple existence with `VoteOf::exists` first.
-		pub VoteOf get(fn vote_of): map (ReferendumIndex, T::AccountId) => Vote;
+		/// The voting activity of an account: either its votes, or the account it is delegating
+		/// to. Also records the votes delegated to it and any lock left from past votes.
+		pub VotingOf get(fn voting_of):
+			map T::AccountId => Voting<BalanceOf<T>, T::AccountId, T::BlockNumber>;
 
 		/// Who is able to vote for whom. Value is the fund-holding account, key is the
 		/// vote-transaction-sending account.
 		pub Proxy get(fn proxy): map T::AccountId => Option<T::AccountId>;
 
-		/// Get the account (and lock periods) to which another account is delegating vote.
-		pub Delegations get(fn delegations): linked_map T::AccountId => (T::AccountId, Conviction);
-
 		/// True if the last referendum tabled was submitted externally. False if it was a public
 		/// proposal.
 		pub LastTabledWasExternal: bool;
@@ -317,6 +178,9 @@ decl_storage! {
 
 		/// Record of all proposals that have been subject to emergency cancellation.
 		pub Cancellations: map T::Hash => bool;
+
+		/// The version of storage for upgrade.
+		StorageVersion build(|_| migration::CURRENT_VERSION): u32;
 	}
 }
 
@@ -359,6 +223,8 @@ decl_event!(
 		PreimageMissing(Hash, ReferendumIndex),
 		/// A registered preimage was removed and the deposit collected by the reaper (last item).
 		PreimageReaped(Hash, AccountId, Balance, AccountId),
+		/// An account has been unlocked successfully.
+		Unlocked(AccountId),
 	}
 );
 
@@ -434,36 +300,43 @@ decl_module! {
 			<DepositOf<T>>::insert(proposal, deposit);
 		}
 
-		/// Vote in a referendum. If `vote.is_aye()`, the vote is to enact the proposal;
-		/// otherwise it is a vote to keep the status quo.
+		/// Vote in a referendum, either in one direction with some conviction, or split between
+		/// both directions without conviction. A previous vote in the same referendum is replaced.
+		///
+		/// The balance of the vote is locked for as long as the referendum is ongoing, and then
+		/// for the lock period of the conviction if the vote was on the winning side. The same
+		/// balance may be used to vote in several referenda.
+		///
+		/// The dispatch origin of this call must be _Signed_ and the account must not be
+		/// delegating.
 		///
 		/// # <weight>
-		/// - O(1).
-		/// - One DB change, one DB entry.
+		/// - O(R) where R is the number of referenda the voter is voting on.
+		/// - Two DB changes, one DB entry.
 		/// # </weight>
 		#[weight = SimpleDispatchInfo::FixedNormal(200_000)]
 		fn vote(origin,
 			#[compact] ref_index: ReferendumIndex,
-			vote: Vote
+			vote: AccountVote<BalanceOf<T>>,
 		) -> dispatch::Result {
 			let who = ensure_signed(origin)?;
-			Self::do_vote(who, ref_index, vote)
+			Self::try_vote(&who, ref_index, vote)
 		}
 
-		/// Vote in a referendum on behalf of a stash. If `vote.is_aye()`, the vote is to enact
-		/// the proposal;  otherwise it is a vote to keep the status quo.
+		/// Vote in a referendum on behalf of a stash. Same as `vote`, with the balance of the
+		/// stash.
 		///
 		/// # <weight>
-		/// - O(1).
-		/// - One DB change, one DB entry.
+		/// - O(R) where R is the number of referenda the stash is voting on.
+		/// - Two DB changes, one DB entry.
 		/// # </weight>
 		#[weight = SimpleDispatchInfo::FixedNormal(200_000)]
 		fn proxy_vote(origin,
 			#[compact] ref_index: ReferendumIndex,
-			vote: Vote
+			vote: AccountVote<BalanceOf<T>>,
 		) -> dispatch::Result {
 			let who = Self::proxy(ensure_signed(origin)?).ok_or("not a proxy")?;
-			Self::do_vote(who, ref_index, vote)
+			Self::try_vote(&who, ref_index, vote)
 		}
 
 		/// Schedule an emergency cancellation of a referendum. Cannot happen twice to the same
@@ -472,8 +345,8 @@ decl_module! {
 		fn emergency_cancel(origin, ref_index: ReferendumIndex) {
 			T::CancellationOrigin::ensure_origin(origin)?;
 
-			let info = Self::referendum_info(ref_index).ok_or("unknown index")?;
-			let h = info.proposal_hash;
+			let status = Self::referendum_status(ref_index).map_err(|_| "unknown index")?;
+			let h = status.proposal_hash;
 			ensure!(!<Cancellations<T>>::exists(h), "cannot cancel the same proposal twice");
 
 			<Cancellations<T>>::insert(h, true);
@@ -588,6 +461,8 @@ decl_module! {
 		}
 
 		fn on_initialize(n: T::BlockNumber) {
+			migration::perform_migrations::<T>();
+
 			if let Err(e) = Self::begin_block(n) {
 				sp_runtime::print(e);
 			}
@@ -628,47 +503,93 @@ decl_module! {
 			<Proxy<T>>::remove(proxy);
 		}
 
-		/// Delegate vote.
+		/// Delegate `balance` of the sending account to `to`, with the given `conviction`. The
+		/// delegated votes are counted in every referendum `to` votes on in one direction.
+		/// Delegations are not passed on if `to` is delegating itself.
+		///
+		/// The balance is locked for as long as it is delegated, and then for the lock period of
+		/// the conviction. An existing delegation is replaced.
+		///
+		/// The dispatch origin of this call must be _Signed_, and the account must not be voting
+		/// directly: its votes must be removed first with `remove_vote`.
 		///
 		/// # <weight>
-		/// - One extra DB entry.
+		/// - O(R) where R is the number of referenda `to` is voting on.
+		/// - Up to three DB changes.
 		/// # </weight>
 		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
-		pub fn delegate(origin, to: T::AccountId, conviction: Conviction) {
+		pub fn delegate(
+			origin,
+			to: T::AccountId,
+			conviction: Conviction,
+			balance: BalanceOf<T>,
+		) {
 			let who = ensure_signed(origin)?;
-			<Delegations<T>>::insert(&who, (&to, conviction));
-			// Currency is locked indefinitely as long as it's delegated.
-			T::Currency::extend_lock(
-				DEMOCRACY_ID,
-				&who,
-				Bounded::max_value(),
-				T::BlockNumber::max_value(),
-				WithdrawReason::Transfer.into()
-			);
-			Self::deposit_event(RawEvent::Delegated(who, to));
+			Self::try_delegate(who, to, conviction, balance)?;
 		}
 
-		/// Undelegate vote.
+		/// Undelegate vote. The delegated balance stays locked for the lock period of the
+		/// conviction of the delegation.
 		///
 		/// # <weight>
-		/// - O(1).
+		/// - O(R) where R is the number of referenda the target is voting on.
+		/// - Two DB changes.
 		/// # </weight>
 		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
 		fn undelegate(origin) {
 			let who = ensure_signed(origin)?;
-			ensure!(<Delegations<T>>::exists(&who), "not delegated");
-			let (_, conviction) = <Delegations<T>>::take(&who);
-			// Indefinite lock is reduced to the maximum voting lock that could be possible.
-			let now = <system::Module<T>>::block_number();
-			let locked_until = now + T::EnactmentPeriod::get() * conviction.lock_periods().into();
-			T::Currency::set_lock(
-				DEMOCRACY_ID,
-				&who,
-				Bounded::max_value(),
-				locked_until,
-				WithdrawReason::Transfer.into()
-			);
-			Self::deposit_event(RawEvent::Undelegated(who));
+			Self::try_undelegate(who)?;
+		}
+
+		/// Unlock the balance of `target` which no longer needs to be locked by its votes and
+		/// delegations.
+		///
+		/// The dispatch origin of this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(R) where R is the number of referenda the target is voting on.
+		/// - One DB change.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+		fn unlock(origin, target: T::AccountId) {
+			ensure_signed(origin)?;
+			Self::update_lock(&target);
+			Self::deposit_event(RawEvent::Unlocked(target));
+		}
+
+		/// Remove the vote of the sending account in a referendum.
+		///
+		/// If the referendum is ongoing, the vote is removed from the tally. If it ended, and the
+		/// vote was on the winning side, the balance stays locked until the end of the lock
+		/// period of the conviction. It can then be unlocked with `unlock`.
+		///
+		/// The dispatch origin of this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(R + log R) where R is the number of referenda the account is voting on.
+		/// - Up to two DB changes.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+		fn remove_vote(origin, index: ReferendumIndex) {
+			let who = ensure_signed(origin)?;
+			Self::try_remove_vote(&who, index, UnvoteScope::Any)?;
+		}
+
+		/// Remove the vote of `target` in a referendum which ended or was cancelled, if the vote
+		/// is no longer locking any balance.
+		///
+		/// The dispatch origin of this call must be _Signed_. If it is `target`, this is the same
+		/// as `remove_vote`.
+		///
+		/// # <weight>
+		/// - O(R + log R) where R is the number of referenda the target is voting on.
+		/// - One DB change.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+		fn remove_other_vote(origin, target: T::AccountId, index: ReferendumIndex) {
+			let who = ensure_signed(origin)?;
+			let scope = if target == who { UnvoteScope::Any } else { UnvoteScope::OnlyExpired };
+			Self::try_remove_vote(&target, index, scope)?;
 		}
 
 		/// Veto and blacklist the proposal hash. Must be from Root origin.
@@ -750,105 +671,39 @@ impl<T: Trait> Module<T> {
 
 	/// Return true if `ref_index` is an on-going referendum.
 	pub fn is_active_referendum(ref_index: ReferendumIndex) -> bool {
-		<ReferendumInfoOf<T>>::exists(ref_index)
+		Self::referendum_status(ref_index).is_ok()
+	}
+
+	/// Get the status of an ongoing referendum.
+	pub fn referendum_status(ref_index: ReferendumIndex)
+		-> result::Result<ReferendumStatus<T::BlockNumber, T::Hash, BalanceOf<T>>, &'static str>
+	{
+		match Self::referendum_info(ref_index) {
+			Some(ReferendumInfo::Ongoing(status)) => Ok(status),
+			_ => Err("referendum not ongoing"),
+		}
 	}
 
 	/// Get all referenda currently active.
 	pub fn active_referenda()
-		-> Vec<(ReferendumIndex, ReferendumInfo<T::BlockNumber, T::Hash>)>
+		-> Vec<(ReferendumIndex, ReferendumStatus<T::BlockNumber, T::Hash, BalanceOf<T>>)>
 	{
 		let next = Self::lowest_unbaked();
 		let last = Self::referendum_count();
 		(next..last).into_iter()
-			.filter_map(|i| Self::referendum_info(i).map(|info| (i, info)))
+			.filter_map(|i| Self::referendum_status(i).ok().map(|status| (i, status)))
 			.collect()
 	}
 
 	/// Get all referenda ready for tally at block `n`.
 	pub fn maturing_referenda_at(
 		n: T::BlockNumber
-	) -> Vec<(ReferendumIndex, ReferendumInfo<T::BlockNumber, T::Hash>)> {
-		let next = Self::lowest_unbaked();
-		let last = Self::referendum_count();
-		(next..last).into_iter()
-			.filter_map(|i| Self::referendum_info(i).map(|info| (i, info)))
-			.filter(|&(_, ref info)| info.end == n)
+	) -> Vec<(ReferendumIndex, ReferendumStatus<T::BlockNumber, T::Hash, BalanceOf<T>>)> {
+		Self::active_referenda().into_iter()
+			.filter(|(_, status)| status.end == n)
 			.collect()
 	}
 
-	/// Get the voters for the current proposal.
-	pub fn tally(ref_index: ReferendumIndex) -> (BalanceOf<T>, BalanceOf<T>, BalanceOf<T>) {
-		let (approve, against, capital):
-			(BalanceOf<T>, BalanceOf<T>, BalanceOf<T>) = Self::voters_for(ref_index)
-				.iter()
-				.map(|voter| (
-					T::Currency::total_balance(voter), Self::vote_of((ref_index, voter.clone()))
-				))
-				.map(|(balance, Vote { aye, conviction })| {
-					let (votes, turnout) = conviction.votes(balance);
-					if aye {
-						(votes, Zero::zero(), turnout)
-					} else {
-						(Zero::zero(), votes, turnout)
-					}
-				}).fold(
-					(Zero::zero(), Zero::zero(), Zero::zero()),
-					|(a, b, c), (d, e, f)| (a + d, b + e, c + f)
-				);
-		let (del_approve, del_against, del_capital) = Self::tally_delegation(ref_index);
-		(approve + del_approve, against + del_against, capital + del_capital)
-	}
-
-	/// Get the delegated voters for the current proposal.
-	/// I think this goes into a worker once https://github.com/paritytech/substrate/issues/1458 is
-	/// done.
-	fn tally_delegation(ref_index: ReferendumIndex) -> (BalanceOf<T>, BalanceOf<T>, BalanceOf<T>) {
-		Self::voters_for(ref_index).iter().fold(
-			(Zero::zero(), Zero::zero(), Zero::zero()),
-			|(approve_acc, against_acc, turnout_acc), voter| {
-				let Vote { aye, conviction } = Self::vote_of((ref_index, voter.clone()));
-				let (votes, turnout) = Self::delegated_votes(
-					ref_index,
-					voter.clone(),
-					conviction,
-					MAX_RECURSION_LIMIT
-				);
-				if aye {
-					(approve_acc + votes, against_acc, turnout_acc + turnout)
-				} else {
-					(approve_acc, against_acc + votes, turnout_acc + turnout)
-				}
-			}
-		)
-	}
-
-	fn delegated_votes(
-		ref_index: ReferendumIndex,
-		to: T::AccountId,
-		parent_conviction: Conviction,
-		recursion_limit: u32,
-	) -> (BalanceOf<T>, BalanceOf<T>) {
-		if recursion_limit == 0 { return (Zero::zero(), Zero::zero()); }
-		<Delegations<T>>::enumerate()
-			.filter(|(delegator, (delegate, _))|
-				*delegate == to && !<VoteOf<T>>::exists(&(ref_index, delegator.clone()))
-			).fold(
-				(Zero::zero(), Zero::zero()),
-				|(votes_acc, turnout_acc), (delegator, (_delegate, max_conviction))| {
-					let conviction = Conviction::min(parent_conviction, max_conviction);
-					let balance = T::Currency::total_balance(&delegator);
-					let (votes, turnout) = conviction.votes(balance);
-					let (del_votes, del_turnout) = Self::delegated_votes(
-						ref_index,
-						delegator,
-						conviction,
-						recursion_limit - 1
-					);
-					(votes_acc + votes + del_votes, turnout_acc + turnout + del_turnout)
-				}
-			)
-	}
-
 	// Exposed mutables.
 
 	#[cfg(feature = "std")]
@@ -879,15 +734,233 @@ impl<T: Trait> Module<T> {
 	// private.
 
 	/// Actually enact a vote, if legit.
-	fn do_vote(who: T::AccountId, ref_index: ReferendumIndex, vote: Vote) -> dispatch::Result {
-		ensure!(Self::is_active_referendum(ref_index), "vote given for invalid referendum.");
-		if !<VoteOf<T>>::exists((ref_index, &who)) {
-			<VotersFor<T>>::append_or_insert(ref_index, &[&who][..]);
+	fn try_vote(
+		who: &T::AccountId,
+		ref_index: ReferendumIndex,
+		vote: AccountVote<BalanceOf<T>>,
+	) -> dispatch::Result {
+		let mut status = Self::referendum_status(ref_index)?;
+		ensure!(vote.balance() <= T::Currency::free_balance(who), "insufficient funds");
+
+		let mut voting = Self::voting_of(who);
+		if let Voting::Direct { votes, delegations, .. } = &mut voting {
+			match votes.binary_search_by_key(&ref_index, |i| i.0) {
+				Ok(i) => {
+					// Shouldn't be possible to fail, but we handle it gracefully.
+					status.tally.remove(votes[i].1).ok_or("tally underflow")?;
+					if let Some(approve) = votes[i].1.as_standard() {
+						status.tally.reduce(approve, *delegations);
+					}
+					votes[i].1 = vote;
+				}
+				Err(i) => {
+					ensure!(votes.len() < MAX_VOTES, "too many votes");
+					votes.insert(i, (ref_index, vote));
+				}
+			}
+			// Shouldn't be possible to fail, but we handle it gracefully.
+			status.tally.add(vote).ok_or("tally overflow")?;
+			if let Some(approve) = vote.as_standard() {
+				status.tally.increase(approve, *delegations);
+			}
+		} else {
+			Err("account is delegating")?;
+		}
+
+		// Extend the lock to `balance` (rather than setting it) since we don't know what other
+		// votes are in place.
+		Self::extend_lock(who, vote.balance());
+		<VotingOf<T>>::insert(who, voting);
+		<ReferendumInfoOf<T>>::insert(ref_index, ReferendumInfo::Ongoing(status));
+		Ok(())
+	}
+
+	/// Remove the vote of `who` in a referendum.
+	///
+	/// If the referendum ended and the vote was on the winning side, the balance must stay locked
+	/// until the end of the lock period: this becomes a prior lock of the account, and the vote can
+	/// only be removed by the account itself before then.
+	fn try_remove_vote(
+		who: &T::AccountId,
+		ref_index: ReferendumIndex,
+		scope: UnvoteScope,
+	) -> dispatch::Result {
+		let mut voting = Self::voting_of(who);
+		if let Voting::Direct { votes, delegations, prior } = &mut voting {
+			let i = votes.binary_search_by_key(&ref_index, |i| i.0).map_err(|_| "vote not found")?;
+			match Self::referendum_info(ref_index) {
+				Some(ReferendumInfo::Ongoing(mut status)) => {
+					ensure!(scope == UnvoteScope::Any, "no permission");
+					// Shouldn't be possible to fail, but we handle it gracefully.
+					status.tally.remove(votes[i].1).ok_or("tally underflow")?;
+					if let Some(approve) = votes[i].1.as_standard() {
+						status.tally.reduce(approve, *delegations);
+					}
+					<ReferendumInfoOf<T>>::insert(ref_index, ReferendumInfo::Ongoing(status));
+				}
+				Some(ReferendumInfo::Finished { end, approved }) => {
+					if let Some((lock_periods, balance)) = votes[i].1.locked_if(approved) {
+						let unlock_at = end + T::EnactmentPeriod::get() * lock_periods.into();
+						let now = <system::Module<T>>::block_number();
+						if now < unlock_at {
+							ensure!(scope == UnvoteScope::Any, "no permission");
+							prior.accumulate(unlock_at, balance);
+						}
+					}
+				}
+				// Referendum was cancelled.
+				None => {}
+			}
+			votes.remove(i);
+		} else {
+			Err("vote not found")?;
+		}
+		<VotingOf<T>>::insert(who, voting);
+		Ok(())
+	}
+
+	/// Add the given delegations to `who`, and to the tally of the ongoing referenda it is
+	/// voting on.
+	fn increase_upstream_delegation(who: &T::AccountId, amount: Delegations<BalanceOf<T>>) {
+		<VotingOf<T>>::mutate(who, |voting| match voting {
+			// We don't support second level delegating, so we don't need to do anything more.
+			Voting::Delegating { delegations, .. } =>
+				*delegations = delegations.saturating_add(amount),
+			Voting::Direct { votes, delegations, .. } => {
+				*delegations = delegations.saturating_add(amount);
+				for &(ref_index, account_vote) in votes.iter() {
+					if let AccountVote::Standard { vote, .. } = account_vote {
+						<ReferendumInfoOf<T>>::mutate(ref_index, |maybe_info|
+							if let Some(ReferendumInfo::Ongoing(status)) = maybe_info {
+								status.tally.increase(vote.aye, amount);
+							}
+						);
+					}
+				}
+			}
+		})
+	}
+
+	/// Remove the given delegations from `who`, and from the tally of the ongoing referenda it is
+	/// voting on.
+	fn reduce_upstream_delegation(who: &T::AccountId, amount: Delegations<BalanceOf<T>>) {
+		<VotingOf<T>>::mutate(who, |voting| match voting {
+			// We don't support second level delegating, so we don't need to do anything more.
+			Voting::Delegating { delegations, .. } =>
+				*delegations = delegations.saturating_sub(amount),
+			Voting::Direct { votes, delegations, .. } => {
+				*delegations = delegations.saturating_sub(amount);
+				for &(ref_index, account_vote) in votes.iter() {
+					if let AccountVote::Standard { vote, .. } = account_vote {
+						<ReferendumInfoOf<T>>::mutate(ref_index, |maybe_info|
+							if let Some(ReferendumInfo::Ongoing(status)) = maybe_info {
+								status.tally.reduce(vote.aye, amount);
+							}
+						);
+					}
+				}
+			}
+		})
+	}
+
+	/// Delegate `balance` of `who` to `target`, replacing any existing delegation.
+	fn try_delegate(
+		who: T::AccountId,
+		target: T::AccountId,
+		conviction: Conviction,
+		balance: BalanceOf<T>,
+	) -> dispatch::Result {
+		ensure!(who != target, "cannot delegate to self");
+		ensure!(balance <= T::Currency::free_balance(&who), "insufficient funds");
+
+		let (delegations, prior) = match Self::voting_of(&who) {
+			Voting::Delegating {
+				balance: old_balance,
+				target: old_target,
+				conviction: old_conviction,
+				delegations,
+				mut prior,
+			} => {
+				// remove any delegation votes to our current target.
+				Self::reduce_upstream_delegation(&old_target, old_conviction.votes(old_balance));
+				let now = <system::Module<T>>::block_number();
+				let lock_periods = old_conviction.lock_periods().into();
+				prior.accumulate(now + T::EnactmentPeriod::get() * lock_periods, old_balance);
+				(delegations, prior)
+			}
+			Voting::Direct { votes, delegations, prior } => {
+				ensure!(votes.is_empty(), "account is voting");
+				(delegations, prior)
+			}
+		};
+
+		Self::increase_upstream_delegation(&target, conviction.votes(balance));
+		// Extend the lock to `balance` (rather than setting it) since we don't know what other
+		// votes are in place.
+		Self::extend_lock(&who, balance);
+		<VotingOf<T>>::insert(&who, Voting::Delegating {
+			balance,
+			target: target.clone(),
+			conviction,
+			delegations,
+			prior,
+		});
+		Self::deposit_event(RawEvent::Delegated(who, target));
+		Ok(())
+	}
+
+	/// Stop the delegation of `who`. The delegated balance stays locked for the lock period of
+	/// the conviction.
+	fn try_undelegate(who: T::AccountId) -> dispatch::Result {
+		match Self::voting_of(&who) {
+			Voting::Delegating { balance, target, conviction, delegations, mut prior } => {
+				// remove any delegation votes to our current target.
+				Self::reduce_upstream_delegation(&target, conviction.votes(balance));
+				let now = <system::Module<T>>::block_number();
+				let lock_periods = conviction.lock_periods().into();
+				prior.accumulate(now + T::EnactmentPeriod::get() * lock_periods, balance);
+				<VotingOf<T>>::insert(&who, Voting::Direct { votes: vec![], delegations, prior });
+			}
+			Voting::Direct { .. } => Err("not delegated")?,
 		}
-		<VoteOf<T>>::insert((ref_index, &who), vote);
+		Self::deposit_event(RawEvent::Undelegated(who));
 		Ok(())
 	}
 
+	/// Lock `amount` of the balance of `who`, on top of what is already locked.
+	fn extend_lock(who: &T::AccountId, amount: BalanceOf<T>) {
+		T::Currency::extend_lock(
+			DEMOCRACY_ID,
+			who,
+			amount,
+			T::BlockNumber::max_value(),
+			WithdrawReason::Transfer.into(),
+		);
+	}
+
+	/// Reduce the lock on `who` to what its votes, delegation and unexpired prior locks need.
+	fn update_lock(who: &T::AccountId) {
+		let mut voting = Self::voting_of(who);
+		voting.rejig(<system::Module<T>>::block_number());
+		let lock_needed = voting.locked_balance();
+		if lock_needed.is_zero() {
+			T::Currency::remove_lock(DEMOCRACY_ID, who);
+		} else {
+			T::Currency::set_lock(
+				DEMOCRACY_ID,
+				who,
+				lock_needed,
+				T::BlockNumber::max_value(),
+				WithdrawReason::Transfer.into(),
+			);
+		}
+		if voting == Default::default() {
+			<VotingOf<T>>::remove(who);
+		} else {
+			<VotingOf<T>>::insert(who, voting);
+		}
+	}
+
 	/// Start a referendum
 	fn inject_referendum(
 		end: T::BlockNumber,
@@ -897,16 +970,20 @@ impl<T: Trait> Module<T> {
 	) -> ReferendumIndex {
 		let ref_index = Self::referendum_count();
 		ReferendumCount::put(ref_index + 1);
-		let item = ReferendumInfo { end, proposal_hash, threshold, delay };
+		let item = ReferendumInfo::new(end, proposal_hash, threshold, delay);
 		<ReferendumInfoOf<T>>::insert(ref_index, item);
 		Self::deposit_event(RawEvent::Started(ref_index, threshold));
 		ref_index
 	}
 
-	/// Remove all info on a referendum.
+	/// Remove all info on a referendum. The votes in it can then be removed freely.
 	fn clear_referendum(ref_index: ReferendumIndex) {
 		<ReferendumInfoOf<T>>::remove(ref_index);
+		Self::note_referendum_done(ref_index);
+	}
 
+	/// Move `LowestUnbaked` past `ref_index`, which is no longer ongoing.
+	fn note_referendum_done(ref_index: ReferendumIndex) {
 		LowestUnbaked::mutate(|i| if *i == ref_index {
 			*i += 1;
 			let end = ReferendumCount::get();
@@ -914,10 +991,6 @@ impl<T: Trait> Module<T> {
 				*i += 1;
 			}
 		});
-		<VotersFor<T>>::remove(ref_index);
-		for v in Self::voters_for(ref_index) {
-			<VoteOf<T>>::remove((ref_index, v));
-		}
 	}
 
 	/// Enact a proposal from a referendum.
@@ -1002,42 +1075,27 @@ impl<T: Trait> Module<T> {
 	fn bake_referendum(
 		now: T::BlockNumber,
 		index: ReferendumIndex,
-		info: ReferendumInfo<T::BlockNumber, T::Hash>
+		status: ReferendumStatus<T::BlockNumber, T::Hash, BalanceOf<T>>,
 	) -> dispatch::Result {
-		let (approve, against, capital) = Self::tally(index);
 		let total_issuance = T::Currency::total_issuance();
-		let approved = info.threshold.approved(approve, against, capital, total_issuance);
-
-		// Logic defined in https://www.slideshare.net/gavofyork/governance-in-polkadot-poc3
-		// Essentially, we extend the lock-period of the coins behind the winning votes to be the
-		// vote strength times the public delay period from now.
-		for (a, Vote { conviction, .. }) in Self::voters_for(index).into_iter()
-			.map(|a| (a.clone(), Self::vote_of((index, a))))
-			// ^^^ defensive only: all items come from `voters`; for an item to be in `voters`
-			// there must be a vote registered; qed
-			.filter(|&(_, vote)| vote.aye == approved)  // Just the winning coins
-		{
-			// now plus: the base lock period multiplied by the number of periods this voter
-			// offered to lock should they win...
-			let locked_until = now + T::EnactmentPeriod::get() * conviction.lock_periods().into();
-			// ...extend their bondage until at least then.
-			T::Currency::extend_lock(
-				DEMOCRACY_ID,
-				&a,
-				Bounded::max_value(),
-				locked_until,
-				WithdrawReason::Transfer.into()
-			);
-		}
-
-		Self::clear_referendum(index);
+		let approved = status.threshold.approved(
+			status.tally.ayes,
+			status.tally.nays,
+			status.tally.turnout,
+			total_issuance,
+		);
+
+		// The votes are kept: the voters lock their balance for as long as needed when removing
+		// them.
+		<ReferendumInfoOf<T>>::insert(index, ReferendumInfo::Finished { end: now, approved });
+		Self::note_referendum_done(index);
 
 		if approved {
 			Self::deposit_event(RawEvent::Passed(index));
-			if info.delay.is_zero() {
-				let _ = Self::enact_proposal(info.proposal_hash, index);
+			if status.delay.is_zero() {
+				let _ = Self::enact_proposal(status.proposal_hash, index);
 			} else {
-				let item = (now + info.delay,info.proposal_hash, index);
+				let item = (now + status.delay, status.proposal_hash, index);
 				<DispatchQueue<T>>::mutate(|queue| {
 					let pos = queue.binary_search_by_key(&item.0, |x| x.0).unwrap_or_else(|e| e);
 					queue.insert(pos, item);
@@ -1088,13 +1146,14 @@ impl<T: Trait> OnFreeBalanceZero<T::AccountId> for Module<T> {
 mod tests {
 	use super::*;
 	use std::cell::RefCell;
+	use codec::Encode;
 	use support::{
 		impl_outer_origin, impl_outer_dispatch, assert_noop, assert_ok, parameter_types,
 		traits::Contains,
 		weights::Weight,
 	};
 	use primitives::H256;
-	use sp_runtime::{traits::{BlakeTwo256, IdentityLookup, Bounded}, testing::Header, Perbill};
+	use sp_runtime::{traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill};
 	use balances::BalanceLock;
 	use system::EnsureSignedBy;
 
@@ -1103,6 +1162,35 @@ mod tests {
 	const BIG_AYE: Vote = Vote{ aye: true, conviction: Conviction::Locked1x };
 	const BIG_NAY: Vote = Vote{ aye: false, conviction: Conviction::Locked1x };
 
+	fn aye(who: u64) -> AccountVote<u64> {
+		AccountVote::Standard { vote: AYE, balance: Balances::free_balance(&who) }
+	}
+
+	fn nay(who: u64) -> AccountVote<u64> {
+		AccountVote::Standard { vote: NAY, balance: Balances::free_balance(&who) }
+	}
+
+	fn big_aye(who: u64) -> AccountVote<u64> {
+		AccountVote::Standard { vote: BIG_AYE, balance: Balances::free_balance(&who) }
+	}
+
+	fn big_nay(who: u64) -> AccountVote<u64> {
+		AccountVote::Standard { vote: BIG_NAY, balance: Balances::free_balance(&who) }
+	}
+
+	fn locked_vote(aye: bool, conviction: Conviction, balance: u64) -> AccountVote<u64> {
+		AccountVote::Standard { vote: Vote { aye, conviction }, balance }
+	}
+
+	fn democracy_lock(amount: u64) -> Vec<BalanceLock<u64, u64>> {
+		vec![BalanceLock {
+			id: DEMOCRACY_ID,
+			amount,
+			until: u64::max_value(),
+			reasons: WithdrawReason::Transfer.into(),
+		}]
+	}
+
 	impl_outer_origin! {
 		pub enum Origin for Test {}
 	}
@@ -1174,6 +1262,9 @@ mod tests {
 		fn contains(n: &u64) -> bool {
 			*n >= 1 && *n <= 5
 		}
+		fn sorted_members() -> Vec<u64> {
+			vec![1, 2, 3, 4, 5]
+		}
 	}
 	thread_local! {
 		static PREIMAGE_BYTE_DEPOSIT: RefCell<u64> = RefCell::new(0);
@@ -1206,7 +1297,6 @@ mod tests {
 		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 		balances::GenesisConfig::<Test>{
 			balances: vec![(1, 10), (2, 20), (3, 30), (4, 40), (5, 50), (6, 60)],
-			vesting: vec![],
 		}.assimilate_storage(&mut t).unwrap();
 		GenesisConfig::default().assimilate_storage(&mut t).unwrap();
 		sp_io::TestExternalities::new(t)
@@ -1216,6 +1306,17 @@ mod tests {
 	type Balances = balances::Module<Test>;
 	type Democracy = Module<Test>;
 
+	fn tally(r: ReferendumIndex) -> Tally<u64> {
+		Democracy::referendum_status(r).unwrap().tally
+	}
+
+	fn votes_of(who: u64) -> Vec<(ReferendumIndex, AccountVote<u64>)> {
+		match Democracy::voting_of(who) {
+			Voting::Direct { votes, .. } => votes,
+			Voting::Delegating { .. } => vec![],
+		}
+	}
+
 	#[test]
 	fn params_should_work() {
 		new_test_ext().execute_with(|| {
@@ -1280,7 +1381,7 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				0
 			);
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
 
 			next_block();
 			next_block();
@@ -1307,7 +1408,7 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				0
 			);
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
 
 			assert_eq!(Balances::reserved_balance(6), 12);
 
@@ -1383,7 +1484,7 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				1
 			);
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
 
 			assert_noop!(
 				Democracy::note_imminent_preimage(Origin::signed(7), set_balance_proposal(2)),
@@ -1407,7 +1508,7 @@ mod tests {
 			System::set_block_number(1);
 			let h = set_balance_proposal_hash_and_note(2);
 			let r = Democracy::inject_referendum(3, h, VoteThreshold::SuperMajorityApprove, 1);
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
 			next_block();
 			next_block();
 			// now imminent.
@@ -1429,12 +1530,13 @@ mod tests {
 
 			// both waiting: external goes first.
 			assert_eq!(
-				Democracy::referendum_info(0),
-				Some(ReferendumInfo {
+				Democracy::referendum_status(0),
+				Ok(ReferendumStatus {
 					end: 4,
 					proposal_hash: set_balance_proposal_hash_and_note(1),
 					threshold: VoteThreshold::SuperMajorityApprove,
-					delay: 2
+					delay: 2,
+					tally: Tally { ayes: 0, nays: 0, turnout: 0 },
 				})
 			);
 			// replenish external
@@ -1447,12 +1549,13 @@ mod tests {
 
 			// both waiting: public goes next.
 			assert_eq!(
-				Democracy::referendum_info(1),
-				Some(ReferendumInfo {
+				Democracy::referendum_status(1),
+				Ok(ReferendumStatus {
 					end: 6,
 					proposal_hash: set_balance_proposal_hash_and_note(2),
 					threshold: VoteThreshold::SuperMajorityApprove,
-					delay: 2
+					delay: 2,
+					tally: Tally { ayes: 0, nays: 0, turnout: 0 },
 				})
 			);
 			// don't replenish public
@@ -1461,12 +1564,13 @@ mod tests {
 
 			// it's external "turn" again, though since public is empty that doesn't really matter
 			assert_eq!(
-				Democracy::referendum_info(2),
-				Some(ReferendumInfo {
+				Democracy::referendum_status(2),
+				Ok(ReferendumStatus {
 					end: 8,
 					proposal_hash: set_balance_proposal_hash_and_note(3),
 					threshold: VoteThreshold::SuperMajorityApprove,
-					delay: 2
+					delay: 2,
+					tally: Tally { ayes: 0, nays: 0, turnout: 0 },
 				})
 			);
 			// replenish external
@@ -1479,12 +1583,13 @@ mod tests {
 
 			// external goes again because there's no public waiting.
 			assert_eq!(
-				Democracy::referendum_info(3),
-				Some(ReferendumInfo {
+				Democracy::referendum_status(3),
+				Ok(ReferendumStatus {
 					end: 10,
 					proposal_hash: set_balance_proposal_hash_and_note(5),
 					threshold: VoteThreshold::SuperMajorityApprove,
-					delay: 2
+					delay: 2,
+					tally: Tally { ayes: 0, nays: 0, turnout: 0 },
 				})
 			);
 			// replenish both
@@ -1498,12 +1603,13 @@ mod tests {
 
 			// public goes now since external went last time.
 			assert_eq!(
-				Democracy::referendum_info(4),
-				Some(ReferendumInfo {
+				Democracy::referendum_status(4),
+				Ok(ReferendumStatus {
 					end: 12,
 					proposal_hash: set_balance_proposal_hash_and_note(4),
 					threshold: VoteThreshold::SuperMajorityApprove,
-					delay: 2
+					delay: 2,
+					tally: Tally { ayes: 0, nays: 0, turnout: 0 },
 				})
 			);
 			// replenish public again
@@ -1516,12 +1622,13 @@ mod tests {
 
 			// public goes again now since there's no external waiting.
 			assert_eq!(
-				Democracy::referendum_info(5),
-				Some(ReferendumInfo {
+				Democracy::referendum_status(5),
+				Ok(ReferendumStatus {
 					end: 14,
 					proposal_hash: set_balance_proposal_hash_and_note(6),
 					threshold: VoteThreshold::SuperMajorityApprove,
-					delay: 2
+					delay: 2,
+					tally: Tally { ayes: 0, nays: 0, turnout: 0 },
 				})
 			);
 		});
@@ -1538,11 +1645,11 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				2
 			);
-			assert!(Democracy::referendum_info(r).is_some());
+			assert!(Democracy::referendum_status(r).is_ok());
 
 			assert_noop!(Democracy::emergency_cancel(Origin::signed(3), r), "Invalid origin");
 			assert_ok!(Democracy::emergency_cancel(Origin::signed(4), r));
-			assert!(Democracy::referendum_info(r).is_none());
+			assert!(Democracy::referendum_status(r).is_err());
 
 			// some time later...
 
@@ -1552,7 +1659,7 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				2
 			);
-			assert!(Democracy::referendum_info(r).is_some());
+			assert!(Democracy::referendum_status(r).is_ok());
 			assert_noop!(Democracy::emergency_cancel(Origin::signed(4), r), "cannot cancel the same proposal twice");
 		});
 	}
@@ -1635,12 +1742,13 @@ mod tests {
 			), "proposal already made");
 			fast_forward_to(2);
 			assert_eq!(
-				Democracy::referendum_info(0),
-				Some(ReferendumInfo {
+				Democracy::referendum_status(0),
+				Ok(ReferendumStatus {
 					end: 4,
 					proposal_hash: set_balance_proposal_hash(2),
 					threshold: VoteThreshold::SuperMajorityApprove,
-					delay: 2
+					delay: 2,
+					tally: Tally { ayes: 0, nays: 0, turnout: 0 },
 				})
 			);
 		});
@@ -1660,12 +1768,13 @@ mod tests {
 			));
 			fast_forward_to(2);
 			assert_eq!(
-				Democracy::referendum_info(0),
-				Some(ReferendumInfo {
+				Democracy::referendum_status(0),
+				Ok(ReferendumStatus {
 					end: 4,
 					proposal_hash: set_balance_proposal_hash(2),
 					threshold: VoteThreshold::SimpleMajority,
 					delay: 2,
+					tally: Tally { ayes: 0, nays: 0, turnout: 0 },
 				})
 			);
 		});
@@ -1685,12 +1794,13 @@ mod tests {
 			));
 			fast_forward_to(2);
 			assert_eq!(
-				Democracy::referendum_info(0),
-				Some(ReferendumInfo {
+				Democracy::referendum_status(0),
+				Ok(ReferendumStatus {
 					end: 4,
 					proposal_hash: set_balance_proposal_hash(2),
 					threshold: VoteThreshold::SuperMajorityAgainst,
 					delay: 2,
+					tally: Tally { ayes: 0, nays: 0, turnout: 0 },
 				})
 			);
 		});
@@ -1709,12 +1819,13 @@ mod tests {
 			assert_noop!(Democracy::fast_track(Origin::signed(1), h, 3, 2), "Invalid origin");
 			assert_ok!(Democracy::fast_track(Origin::signed(5), h, 0, 0));
 			assert_eq!(
-				Democracy::referendum_info(0),
-				Some(ReferendumInfo {
+				Democracy::referendum_status(0),
+				Ok(ReferendumStatus {
 					end: 1,
 					proposal_hash: set_balance_proposal_hash_and_note(2),
 					threshold: VoteThreshold::SimpleMajority,
 					delay: 0,
+					tally: Tally { ayes: 0, nays: 0, turnout: 0 },
 				})
 			);
 		});
@@ -1754,37 +1865,40 @@ mod tests {
 		new_test_ext().execute_with(|| {
 			System::set_block_number(0);
 			assert_ok!(propose_set_balance_and_note(1, 2, 1));
-			assert!(Democracy::referendum_info(0).is_none());
+			assert!(Democracy::referendum_status(0).is_err());
 
 			// start of 2 => next referendum scheduled.
 			fast_forward_to(2);
 
 			let r = 0;
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
 
 			assert_eq!(Democracy::referendum_count(), 1);
 			assert_eq!(
-				Democracy::referendum_info(0),
-				Some(ReferendumInfo {
+				Democracy::referendum_status(0),
+				Ok(ReferendumStatus {
 					end: 4,
 					proposal_hash: set_balance_proposal_hash_and_note(2),
 					threshold: VoteThreshold::SuperMajorityApprove,
-					delay: 2
+					delay: 2,
+					tally: Tally { ayes: 1, nays: 0, turnout: 1 },
 				})
 			);
-			assert_eq!(Democracy::voters_for(r), vec![1]);
-			assert_eq!(Democracy::vote_of((r, 1)), AYE);
-			assert_eq!(Democracy::tally(r), (1, 0, 1));
+			assert_eq!(votes_of(1), vec![(r, aye(1))]);
 
 			fast_forward_to(3);
 
 			// referendum still running
-			assert!(Democracy::referendum_info(0).is_some());
+			assert!(Democracy::referendum_status(0).is_ok());
 
 			// referendum runs during 2 and 3, ends @ start of 4.
 			fast_forward_to(4);
 
-			assert!(Democracy::referendum_info(0).is_none());
+			assert!(Democracy::referendum_status(0).is_err());
+			assert_eq!(
+				Democracy::referendum_info(0),
+				Some(ReferendumInfo::Finished { end: 4, approved: true }),
+			);
 			assert_eq!(Democracy::dispatch_queue(), vec![
 				(6, set_balance_proposal_hash_and_note(2), 0)
 			]);
@@ -1805,7 +1919,7 @@ mod tests {
 			// start of 2 => next referendum scheduled.
 			fast_forward_to(2);
 
-			assert_ok!(Democracy::vote(Origin::signed(1), 0, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), 0, aye(1)));
 
 			fast_forward_to(4);
 
@@ -1858,11 +1972,10 @@ mod tests {
 			fast_forward_to(2);
 			let r = 0;
 			assert_ok!(Democracy::set_proxy(Origin::signed(1), 10));
-			assert_ok!(Democracy::proxy_vote(Origin::signed(10), r, AYE));
+			assert_ok!(Democracy::proxy_vote(Origin::signed(10), r, aye(1)));
 
-			assert_eq!(Democracy::voters_for(r), vec![1]);
-			assert_eq!(Democracy::vote_of((r, 1)), AYE);
-			assert_eq!(Democracy::tally(r), (1, 0, 1));
+			assert_eq!(votes_of(1), vec![(r, aye(1))]);
+			assert_eq!(tally(r), Tally { ayes: 1, nays: 0, turnout: 1 });
 
 			fast_forward_to(6);
 			assert_eq!(Balances::free_balance(&42), 2);
@@ -1879,14 +1992,13 @@ mod tests {
 			fast_forward_to(2);
 
 			// Delegate vote.
-			assert_ok!(Democracy::delegate(Origin::signed(2), 1, Conviction::max_value()));
+			assert_ok!(Democracy::delegate(Origin::signed(2), 1, Conviction::None, 20));
 
 			let r = 0;
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
-			assert_eq!(Democracy::voters_for(r), vec![1]);
-			assert_eq!(Democracy::vote_of((r, 1)), AYE);
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
+			assert_eq!(votes_of(1), vec![(r, aye(1))]);
 			// Delegated vote is counted.
-			assert_eq!(Democracy::tally(r), (3, 0, 3));
+			assert_eq!(tally(r), Tally { ayes: 3, nays: 0, turnout: 3 });
 
 			fast_forward_to(6);
 
@@ -1895,7 +2007,7 @@ mod tests {
 	}
 
 	#[test]
-	fn single_proposal_should_work_with_cyclic_delegation() {
+	fn single_proposal_should_work_with_chained_delegation() {
 		new_test_ext().execute_with(|| {
 			System::set_block_number(0);
 
@@ -1903,16 +2015,14 @@ mod tests {
 
 			fast_forward_to(2);
 
-			// Check behavior with cycle.
-			assert_ok!(Democracy::delegate(Origin::signed(2), 1, Conviction::max_value()));
-			assert_ok!(Democracy::delegate(Origin::signed(3), 2, Conviction::max_value()));
-			assert_ok!(Democracy::delegate(Origin::signed(1), 3, Conviction::max_value()));
+			// Delegations are not transitive.
+			assert_ok!(Democracy::delegate(Origin::signed(2), 1, Conviction::None, 20));
+			assert_ok!(Democracy::delegate(Origin::signed(3), 2, Conviction::None, 30));
 			let r = 0;
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
-			assert_eq!(Democracy::voters_for(r), vec![1]);
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
 
-			// Delegated vote is counted.
-			assert_eq!(Democracy::tally(r), (6, 0, 6));
+			// Only the direct delegation is counted.
+			assert_eq!(tally(r), Tally { ayes: 3, nays: 0, turnout: 3 });
 
 			fast_forward_to(6);
 
@@ -1921,7 +2031,7 @@ mod tests {
 	}
 
 	#[test]
-	/// If transactor already voted, delegated vote is overwriten.
+	/// A voting account cannot delegate.
 	fn single_proposal_should_work_with_vote_and_delegation() {
 		new_test_ext().execute_with(|| {
 			System::set_block_number(0);
@@ -1931,15 +2041,18 @@ mod tests {
 			fast_forward_to(2);
 
 			let r = 0;
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
 			// Vote.
-			assert_ok!(Democracy::vote(Origin::signed(2), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(2), r, aye(2)));
 			// Delegate vote.
-			assert_ok!(Democracy::delegate(Origin::signed(2), 1, Conviction::max_value()));
-			assert_eq!(Democracy::voters_for(r), vec![1, 2]);
-			assert_eq!(Democracy::vote_of((r, 1)), AYE);
+			assert_noop!(
+				Democracy::delegate(Origin::signed(2), 1, Conviction::None, 20),
+				"account is voting",
+			);
+			assert_eq!(votes_of(1), vec![(r, aye(1))]);
+			assert_eq!(votes_of(2), vec![(r, aye(2))]);
 			// Delegated vote is not counted.
-			assert_eq!(Democracy::tally(r), (3, 0, 3));
+			assert_eq!(tally(r), Tally { ayes: 3, nays: 0, turnout: 3 });
 
 			fast_forward_to(6);
 
@@ -1955,19 +2068,18 @@ mod tests {
 			assert_ok!(propose_set_balance_and_note(1, 2, 1));
 
 			// Delegate and undelegate vote.
-			assert_ok!(Democracy::delegate(Origin::signed(2), 1, Conviction::max_value()));
+			assert_ok!(Democracy::delegate(Origin::signed(2), 1, Conviction::None, 20));
 			assert_ok!(Democracy::undelegate(Origin::signed(2)));
 
 			fast_forward_to(2);
 			let r = 0;
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
 
 			assert_eq!(Democracy::referendum_count(), 1);
-			assert_eq!(Democracy::voters_for(r), vec![1]);
-			assert_eq!(Democracy::vote_of((r, 1)), AYE);
+			assert_eq!(votes_of(1), vec![(r, aye(1))]);
 
 			// Delegated vote is not counted.
-			assert_eq!(Democracy::tally(r), (1, 0, 1));
+			assert_eq!(tally(r), Tally { ayes: 1, nays: 0, turnout: 1 });
 
 			fast_forward_to(6);
 
@@ -1976,7 +2088,7 @@ mod tests {
 	}
 
 	#[test]
-	/// If transactor voted, delegated vote is overwriten.
+	/// A delegating account cannot vote.
 	fn single_proposal_should_work_with_delegation_and_vote() {
 		new_test_ext().execute_with(|| {
 			System::set_block_number(0);
@@ -1986,20 +2098,19 @@ mod tests {
 			fast_forward_to(2);
 			let r = 0;
 
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
 
 			// Delegate vote.
-			assert_ok!(Democracy::delegate(Origin::signed(2), 1, Conviction::max_value()));
+			assert_ok!(Democracy::delegate(Origin::signed(2), 1, Conviction::None, 20));
 
 			// Vote.
-			assert_ok!(Democracy::vote(Origin::signed(2), r, AYE));
+			assert_noop!(Democracy::vote(Origin::signed(2), r, aye(2)), "account is delegating");
 
 			assert_eq!(Democracy::referendum_count(), 1);
-			assert_eq!(Democracy::voters_for(r), vec![1, 2]);
-			assert_eq!(Democracy::vote_of((r, 1)), AYE);
+			assert_eq!(votes_of(1), vec![(r, aye(1))]);
 
-			// Delegated vote is not counted.
-			assert_eq!(Democracy::tally(r), (3, 0, 3));
+			// Delegated vote is counted.
+			assert_eq!(tally(r), Tally { ayes: 3, nays: 0, turnout: 3 });
 
 			fast_forward_to(6);
 
@@ -2071,11 +2182,11 @@ mod tests {
 			assert_ok!(propose_set_balance_and_note(1, 4, 4));
 			assert_ok!(propose_set_balance_and_note(1, 3, 3));
 			fast_forward_to(2);
-			assert_ok!(Democracy::vote(Origin::signed(1), 0, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), 0, aye(1)));
 			fast_forward_to(4);
-			assert_ok!(Democracy::vote(Origin::signed(1), 1, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), 1, aye(1)));
 			fast_forward_to(6);
-			assert_ok!(Democracy::vote(Origin::signed(1), 2, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), 2, aye(1)));
 		});
 	}
 
@@ -2096,18 +2207,16 @@ mod tests {
 				0
 			);
 
-			assert_ok!(Democracy::vote(Origin::signed(1), r2, AYE));
-			assert_eq!(Democracy::voters_for(r2), vec![1]);
-			assert_eq!(Democracy::vote_of((r2, 1)), AYE);
-			assert_eq!(Democracy::tally(r2), (1, 0, 1));
+			assert_ok!(Democracy::vote(Origin::signed(1), r2, aye(1)));
+			assert_eq!(votes_of(1), vec![(r2, aye(1))]);
+			assert_eq!(tally(r2), Tally { ayes: 1, nays: 0, turnout: 1 });
 
 			next_block();
 			assert_eq!(Balances::free_balance(&42), 2);
 
-			assert_ok!(Democracy::vote(Origin::signed(1), r1, AYE));
-			assert_eq!(Democracy::voters_for(r1), vec![1]);
-			assert_eq!(Democracy::vote_of((r1, 1)), AYE);
-			assert_eq!(Democracy::tally(r1), (1, 0, 1));
+			assert_ok!(Democracy::vote(Origin::signed(1), r1, aye(1)));
+			assert_eq!(votes_of(1), vec![(r1, aye(1)), (r2, aye(1))]);
+			assert_eq!(tally(r1), Tally { ayes: 1, nays: 0, turnout: 1 });
 
 			next_block();
 			assert_eq!(Balances::free_balance(&42), 3);
@@ -2124,11 +2233,10 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				0
 			);
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
 
-			assert_eq!(Democracy::voters_for(r), vec![1]);
-			assert_eq!(Democracy::vote_of((r, 1)), AYE);
-			assert_eq!(Democracy::tally(r), (1, 0, 1));
+			assert_eq!(votes_of(1), vec![(r, aye(1))]);
+			assert_eq!(tally(r), Tally { ayes: 1, nays: 0, turnout: 1 });
 
 			next_block();
 			next_block();
@@ -2147,7 +2255,7 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				0
 			);
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
 			assert_ok!(Democracy::cancel_referendum(Origin::ROOT, r.into()));
 
 			next_block();
@@ -2167,11 +2275,10 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				0
 			);
-			assert_ok!(Democracy::vote(Origin::signed(1), r, NAY));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, nay(1)));
 
-			assert_eq!(Democracy::voters_for(r), vec![1]);
-			assert_eq!(Democracy::vote_of((r, 1)), NAY);
-			assert_eq!(Democracy::tally(r), (0, 1, 1));
+			assert_eq!(votes_of(1), vec![(r, nay(1))]);
+			assert_eq!(tally(r), Tally { ayes: 0, nays: 1, turnout: 1 });
 
 			next_block();
 			next_block();
@@ -2191,14 +2298,14 @@ mod tests {
 				0
 			);
 
-			assert_ok!(Democracy::vote(Origin::signed(1), r, BIG_AYE));
-			assert_ok!(Democracy::vote(Origin::signed(2), r, BIG_NAY));
-			assert_ok!(Democracy::vote(Origin::signed(3), r, BIG_NAY));
-			assert_ok!(Democracy::vote(Origin::signed(4), r, BIG_AYE));
-			assert_ok!(Democracy::vote(Origin::signed(5), r, BIG_NAY));
-			assert_ok!(Democracy::vote(Origin::signed(6), r, BIG_AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, big_aye(1)));
+			assert_ok!(Democracy::vote(Origin::signed(2), r, big_nay(2)));
+			assert_ok!(Democracy::vote(Origin::signed(3), r, big_nay(3)));
+			assert_ok!(Democracy::vote(Origin::signed(4), r, big_aye(4)));
+			assert_ok!(Democracy::vote(Origin::signed(5), r, big_nay(5)));
+			assert_ok!(Democracy::vote(Origin::signed(6), r, big_aye(6)));
 
-			assert_eq!(Democracy::tally(r), (110, 100, 210));
+			assert_eq!(tally(r), Tally { ayes: 110, nays: 100, turnout: 210 });
 
 			next_block();
 			next_block();
@@ -2217,14 +2324,14 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				1
 			);
-			assert_ok!(Democracy::vote(Origin::signed(1), r, AYE));
-			assert_ok!(Democracy::vote(Origin::signed(2), r, AYE));
-			assert_ok!(Democracy::vote(Origin::signed(3), r, AYE));
-			assert_ok!(Democracy::vote(Origin::signed(4), r, AYE));
-			assert_ok!(Democracy::vote(Origin::signed(5), r, AYE));
-			assert_ok!(Democracy::vote(Origin::signed(6), r, AYE));
+			assert_ok!(Democracy::vote(Origin::signed(1), r, aye(1)));
+			assert_ok!(Democracy::vote(Origin::signed(2), r, aye(2)));
+			assert_ok!(Democracy::vote(Origin::signed(3), r, aye(3)));
+			assert_ok!(Democracy::vote(Origin::signed(4), r, aye(4)));
+			assert_ok!(Democracy::vote(Origin::signed(5), r, aye(5)));
+			assert_ok!(Democracy::vote(Origin::signed(6), r, aye(6)));
 
-			assert_eq!(Democracy::tally(r), (21, 0, 21));
+			assert_eq!(tally(r), Tally { ayes: 21, nays: 0, turnout: 21 });
 
 			next_block();
 			assert_eq!(Balances::free_balance(&42), 0);
@@ -2245,10 +2352,10 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				0
 			);
-			assert_ok!(Democracy::vote(Origin::signed(5), r, BIG_NAY));
-			assert_ok!(Democracy::vote(Origin::signed(6), r, BIG_AYE));
+			assert_ok!(Democracy::vote(Origin::signed(5), r, big_nay(5)));
+			assert_ok!(Democracy::vote(Origin::signed(6), r, big_aye(6)));
 
-			assert_eq!(Democracy::tally(r), (60, 50, 110));
+			assert_eq!(tally(r), Tally { ayes: 60, nays: 50, turnout: 110 });
 
 			next_block();
 			next_block();
@@ -2270,11 +2377,11 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				0
 			);
-			assert_ok!(Democracy::vote(Origin::signed(4), r, BIG_AYE));
-			assert_ok!(Democracy::vote(Origin::signed(5), r, BIG_NAY));
-			assert_ok!(Democracy::vote(Origin::signed(6), r, BIG_AYE));
+			assert_ok!(Democracy::vote(Origin::signed(4), r, big_aye(4)));
+			assert_ok!(Democracy::vote(Origin::signed(5), r, big_nay(5)));
+			assert_ok!(Democracy::vote(Origin::signed(6), r, big_aye(6)));
 
-			assert_eq!(Democracy::tally(r), (100, 50, 150));
+			assert_eq!(tally(r), Tally { ayes: 100, nays: 50, turnout: 150 });
 
 			next_block();
 			next_block();
@@ -2293,53 +2400,67 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				0
 			);
-			assert_ok!(Democracy::vote(Origin::signed(1), r, Vote {
-				aye: false,
-				conviction: Conviction::Locked5x
-			}));
-			assert_ok!(Democracy::vote(Origin::signed(2), r, Vote {
-				aye: true,
-				conviction: Conviction::Locked4x
-			}));
-			assert_ok!(Democracy::vote(Origin::signed(3), r, Vote {
-				aye: true,
-				conviction: Conviction::Locked3x
-			}));
-			assert_ok!(Democracy::vote(Origin::signed(4), r, Vote {
-				aye: true,
-				conviction: Conviction::Locked2x
-			}));
-			assert_ok!(Democracy::vote(Origin::signed(5), r, Vote {
-				aye: false,
-				conviction: Conviction::Locked1x
-			}));
-
-			assert_eq!(Democracy::tally(r), (250, 100, 150));
+			assert_ok!(Democracy::vote(
+				Origin::signed(1), r, locked_vote(false, Conviction::Locked5x, 10)
+			));
+			assert_ok!(Democracy::vote(
+				Origin::signed(2), r, locked_vote(true, Conviction::Locked4x, 20)
+			));
+			assert_ok!(Democracy::vote(
+				Origin::signed(3), r, locked_vote(true, Conviction::Locked3x, 30)
+			));
+			assert_ok!(Democracy::vote(
+				Origin::signed(4), r, locked_vote(true, Conviction::Locked2x, 40)
+			));
+			assert_ok!(Democracy::vote(
+				Origin::signed(5), r, locked_vote(false, Conviction::Locked1x, 50)
+			));
+
+			assert_eq!(tally(r), Tally { ayes: 250, nays: 100, turnout: 150 });
+
+			// All balances are locked while the referendum is ongoing.
+			for i in 1..=5 {
+				assert_eq!(Balances::locks(i), democracy_lock(i * 10));
+			}
 
 			fast_forward_to(2);
+			assert_eq!(Balances::free_balance(&42), 2);
 
+			// Losing votes can be removed by anyone, and unlocked straight away.
+			assert_ok!(Democracy::remove_vote(Origin::signed(1), r));
+			assert_ok!(Democracy::unlock(Origin::signed(1), 1));
 			assert_eq!(Balances::locks(1), vec![]);
-			assert_eq!(Balances::locks(2), vec![BalanceLock {
-				id: DEMOCRACY_ID,
-				amount: u64::max_value(),
-				until: 18,
-				reasons: WithdrawReason::Transfer.into()
-			}]);
-			assert_eq!(Balances::locks(3), vec![BalanceLock {
-				id: DEMOCRACY_ID,
-				amount: u64::max_value(),
-				until: 10,
-				reasons: WithdrawReason::Transfer.into()
-			}]);
-			assert_eq!(Balances::locks(4), vec![BalanceLock {
-				id: DEMOCRACY_ID,
-				amount: u64::max_value(),
-				until: 6,
-				reasons: WithdrawReason::Transfer.into()
-			}]);
+			assert_ok!(Democracy::remove_other_vote(Origin::signed(1), 5, r));
+			assert_ok!(Democracy::unlock(Origin::signed(1), 5));
 			assert_eq!(Balances::locks(5), vec![]);
 
-			assert_eq!(Balances::free_balance(&42), 2);
+			// Winning votes stay locked for their lock period.
+			assert_noop!(Democracy::remove_other_vote(Origin::signed(1), 2, r), "no permission");
+			assert_ok!(Democracy::remove_vote(Origin::signed(2), r));
+			assert_ok!(Democracy::unlock(Origin::signed(2), 2));
+			assert_eq!(Balances::locks(2), democracy_lock(20));
+
+			fast_forward_to(5);
+			assert_noop!(Democracy::remove_other_vote(Origin::signed(1), 4, r), "no permission");
+			fast_forward_to(6);
+			assert_ok!(Democracy::remove_other_vote(Origin::signed(1), 4, r));
+			assert_ok!(Democracy::unlock(Origin::signed(1), 4));
+			assert_eq!(Balances::locks(4), vec![]);
+
+			fast_forward_to(9);
+			assert_noop!(Democracy::remove_other_vote(Origin::signed(1), 3, r), "no permission");
+			fast_forward_to(10);
+			assert_ok!(Democracy::remove_other_vote(Origin::signed(1), 3, r));
+			assert_ok!(Democracy::unlock(Origin::signed(1), 3));
+			assert_eq!(Balances::locks(3), vec![]);
+
+			fast_forward_to(17);
+			assert_ok!(Democracy::unlock(Origin::signed(1), 2));
+			assert_eq!(Balances::locks(2), democracy_lock(20));
+			fast_forward_to(18);
+			assert_ok!(Democracy::unlock(Origin::signed(1), 2));
+			assert_eq!(Balances::locks(2), vec![]);
+			assert_eq!(Democracy::voting_of(2), Voting::default());
 		});
 	}
 
@@ -2353,30 +2474,212 @@ mod tests {
 				VoteThreshold::SuperMajorityApprove,
 				0
 			);
-			assert_ok!(Democracy::vote(Origin::signed(1), r, Vote {
-				aye: false,
-				conviction: Conviction::Locked5x
-			}));
-			assert_ok!(Democracy::vote(Origin::signed(2), r, Vote {
-				aye: true,
-				conviction: Conviction::Locked4x
-			}));
-			assert_ok!(Democracy::vote(Origin::signed(3), r, Vote {
-				aye: true,
-				conviction: Conviction::Locked3x
-			}));
-			assert_ok!(Democracy::delegate(Origin::signed(4), 2, Conviction::Locked2x));
-			assert_ok!(Democracy::vote(Origin::signed(5), r, Vote {
-				aye: false,
-				conviction: Conviction::Locked1x
-			}));
-
-			assert_eq!(Democracy::tally(r), (250, 100, 150));
+			assert_ok!(Democracy::vote(
+				Origin::signed(1), r, locked_vote(false, Conviction::Locked5x, 10)
+			));
+			assert_ok!(Democracy::vote(
+				Origin::signed(2), r, locked_vote(true, Conviction::Locked4x, 20)
+			));
+			assert_ok!(Democracy::vote(
+				Origin::signed(3), r, locked_vote(true, Conviction::Locked3x, 30)
+			));
+			assert_ok!(Democracy::delegate(Origin::signed(4), 2, Conviction::Locked2x, 40));
+			assert_ok!(Democracy::vote(
+				Origin::signed(5), r, locked_vote(false, Conviction::Locked1x, 50)
+			));
+
+			assert_eq!(tally(r), Tally { ayes: 250, nays: 100, turnout: 150 });
+			assert_eq!(Balances::locks(4), democracy_lock(40));
 
 			next_block();
 			next_block();
 
 			assert_eq!(Balances::free_balance(&42), 2);
+
+			// The delegated balance stays locked for the conviction's lock period.
+			assert_ok!(Democracy::undelegate(Origin::signed(4)));
+			assert_ok!(Democracy::unlock(Origin::signed(4), 4));
+			assert_eq!(Balances::locks(4), democracy_lock(40));
+			fast_forward_to(7);
+			assert_ok!(Democracy::unlock(Origin::signed(4), 4));
+			assert_eq!(Balances::locks(4), vec![]);
+		});
+	}
+
+	#[test]
+	fn delegation_follows_the_vote_of_the_target() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let r = Democracy::inject_referendum(
+				2,
+				set_balance_proposal_hash_and_note(2),
+				VoteThreshold::SuperMajorityApprove,
+				0
+			);
+			assert_ok!(Democracy::vote(
+				Origin::signed(2), r, locked_vote(true, Conviction::Locked1x, 20)
+			));
+			assert_ok!(Democracy::delegate(Origin::signed(4), 2, Conviction::Locked2x, 40));
+			assert_eq!(tally(r), Tally { ayes: 100, nays: 0, turnout: 60 });
+
+			// The target changes its mind: the delegated votes move with it.
+			assert_ok!(Democracy::vote(
+				Origin::signed(2), r, locked_vote(false, Conviction::Locked1x, 20)
+			));
+			assert_eq!(tally(r), Tally { ayes: 0, nays: 100, turnout: 60 });
+
+			// Redelegating with a different balance and conviction replaces the delegation.
+			assert_ok!(Democracy::delegate(Origin::signed(4), 2, Conviction::Locked1x, 10));
+			assert_eq!(tally(r), Tally { ayes: 0, nays: 30, turnout: 30 });
+
+			assert_ok!(Democracy::undelegate(Origin::signed(4)));
+			assert_eq!(tally(r), Tally { ayes: 0, nays: 20, turnout: 20 });
+			assert_noop!(Democracy::undelegate(Origin::signed(4)), "not delegated");
+		});
+	}
+
+	#[test]
+	fn split_votes_should_work() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let r = Democracy::inject_referendum(
+				2,
+				set_balance_proposal_hash_and_note(2),
+				VoteThreshold::SuperMajorityApprove,
+				0
+			);
+			let split = AccountVote::Split { aye: 40, nay: 20 };
+			assert_noop!(
+				Democracy::vote(Origin::signed(5), r, split),
+				"insufficient funds",
+			);
+			let split = AccountVote::Split { aye: 30, nay: 20 };
+			assert_ok!(Democracy::vote(Origin::signed(5), r, split));
+			assert_eq!(tally(r), Tally { ayes: 3, nays: 2, turnout: 5 });
+			assert_eq!(Balances::locks(5), democracy_lock(50));
+
+			// Split votes are not affected by delegations.
+			assert_ok!(Democracy::delegate(Origin::signed(4), 5, Conviction::Locked1x, 40));
+			assert_eq!(tally(r), Tally { ayes: 3, nays: 2, turnout: 5 });
+
+			// Split votes never keep the balance locked after the referendum.
+			fast_forward_to(3);
+			assert_ok!(Democracy::remove_other_vote(Origin::signed(1), 5, r));
+			assert_ok!(Democracy::unlock(Origin::signed(1), 5));
+			assert_eq!(Balances::locks(5), vec![]);
+		});
+	}
+
+	#[test]
+	fn voting_on_many_referenda_should_lock_the_maximum() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let r1 = Democracy::inject_referendum(
+				2,
+				set_balance_proposal_hash_and_note(2),
+				VoteThreshold::SuperMajorityApprove,
+				0
+			);
+			let r2 = Democracy::inject_referendum(
+				2,
+				set_balance_proposal_hash_and_note(3),
+				VoteThreshold::SuperMajorityApprove,
+				0
+			);
+			assert_ok!(Democracy::vote(
+				Origin::signed(5), r1, locked_vote(true, Conviction::None, 30)
+			));
+			assert_ok!(Democracy::vote(
+				Origin::signed(5), r2, locked_vote(false, Conviction::None, 50)
+			));
+			assert_eq!(tally(r1), Tally { ayes: 3, nays: 0, turnout: 3 });
+			assert_eq!(tally(r2), Tally { ayes: 0, nays: 5, turnout: 5 });
+			assert_eq!(Balances::locks(5), democracy_lock(50));
+
+			// Removing the bigger vote from an ongoing referendum updates its tally and the lock.
+			assert_ok!(Democracy::remove_vote(Origin::signed(5), r2));
+			assert_eq!(tally(r2), Tally { ayes: 0, nays: 0, turnout: 0 });
+			assert_ok!(Democracy::unlock(Origin::signed(5), 5));
+			assert_eq!(Balances::locks(5), democracy_lock(30));
+			assert_noop!(Democracy::remove_vote(Origin::signed(5), r2), "vote not found");
+		});
+	}
+
+	#[test]
+	fn migration_to_v1_works() {
+		use support::{StorageValue, StorageMap, StorageLinkedMap};
+		use migration::inner::{
+			LEGACY_DEMOCRACY_ID, OldReferendumInfoOf, OldVotersFor, OldVoteOf, OldDelegations,
+		};
+
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let legacy_lock = |until| BalanceLock {
+				id: LEGACY_DEMOCRACY_ID,
+				amount: u64::max_value(),
+				until,
+				reasons: WithdrawReason::Transfer.into(),
+			};
+
+			<Democracy as Store>::StorageVersion::put(0);
+			ReferendumCount::put(1);
+			let h = set_balance_proposal_hash(2);
+			OldReferendumInfoOf::<Test>::insert(0, (2, h, VoteThreshold::SuperMajorityApprove, 0));
+			OldVotersFor::<Test>::insert(0, vec![1, 2]);
+			OldVoteOf::<Test>::insert((0, 1), BIG_AYE);
+			OldVoteOf::<Test>::insert((0, 2), NAY);
+			// 3 delegates to 1, 2 both votes and delegates.
+			OldDelegations::<Test>::insert(3, (1, Conviction::Locked1x));
+			OldDelegations::<Test>::insert(2, (1, Conviction::Locked2x));
+			for who in &[2, 3] {
+				Balances::set_lock(
+					LEGACY_DEMOCRACY_ID,
+					who,
+					u64::max_value(),
+					u64::max_value(),
+					WithdrawReason::Transfer.into(),
+				);
+			}
+			// 6 voted with conviction in a past referendum.
+			Balances::set_lock(
+				LEGACY_DEMOCRACY_ID,
+				&6,
+				u64::max_value(),
+				10,
+				WithdrawReason::Transfer.into(),
+			);
+
+			migration::perform_migrations::<Test>();
+			assert_eq!(<Democracy as Store>::StorageVersion::get(), 1);
+
+			assert_eq!(votes_of(1), vec![(0, big_aye(1))]);
+			assert_eq!(votes_of(2), vec![(0, nay(2))]);
+			assert_eq!(Democracy::voting_of(3), Voting::Delegating {
+				balance: 30,
+				target: 1,
+				conviction: Conviction::Locked1x,
+				delegations: Default::default(),
+				prior: Default::default(),
+			});
+			assert_eq!(tally(0), Tally { ayes: 10 + 30, nays: 2, turnout: 10 + 2 + 30 });
+
+			// The delegation of 2 is ended: its balance stays locked for the conviction.
+			assert_eq!(Democracy::voting_of(2).locked_balance(), 20);
+			assert_eq!(Balances::locks(1), democracy_lock(10));
+			assert_eq!(Balances::locks(2), democracy_lock(20));
+			assert_eq!(Balances::locks(3), democracy_lock(30));
+			assert_eq!(Balances::locks(6), vec![legacy_lock(10)]);
+
+			assert!(!OldVotersFor::<Test>::exists(0));
+			assert!(!OldVoteOf::<Test>::exists((0, 1)));
+			assert_eq!(OldDelegations::<Test>::enumerate().count(), 0);
+
+			fast_forward_to(2);
+			assert_eq!(
+				Democracy::referendum_info(0),
+				Some(ReferendumInfo::Finished { end: 2, approved: true }),
+			);
 		});
 	}
 }
+
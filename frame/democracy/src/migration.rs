@@ -0,0 +1,251 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage migrations for pallet-democracy.
+
+/// Indicator of a version of a storage layout.
+pub type VersionNumber = u32;
+
+// the current expected version of the storage
+pub const CURRENT_VERSION: VersionNumber = 1;
+
+#[cfg(any(test, feature = "migrate"))]
+pub(crate) mod inner {
+	use crate::{
+		Store, Module, Trait, ReferendumIndex, ReferendumInfo, ReferendumStatus, Tally,
+		Vote, AccountVote, Voting, Conviction, VoteThreshold,
+	};
+	use support::{
+		StorageValue, StorageMap, StoragePrefixedMap, Blake2_256,
+		storage::{self, generator::{self, LinkedMapKeyFormat}},
+		traits::{Currency, LockableCurrency, LockIdentifier, Get},
+	};
+	use sp_std::{prelude::*, marker::PhantomData, collections::btree_map::BTreeMap};
+	use super::{CURRENT_VERSION, VersionNumber};
+
+	// the minimum supported version of the migration logic.
+	const MIN_SUPPORTED_VERSION: VersionNumber = 0;
+
+	/// The identifier of the locks of version 0. These locks expire on their own, so they are
+	/// left in place for the conviction locks of past votes to be honoured.
+	pub(crate) const LEGACY_DEMOCRACY_ID: LockIdentifier = *b"democrac";
+
+	/// `ReferendumInfoOf` of version 0: `(end, proposal_hash, threshold, delay)`.
+	pub(crate) struct OldReferendumInfoOf<T>(PhantomData<T>);
+
+	impl<T: Trait> generator::StorageMap<
+		ReferendumIndex,
+		(T::BlockNumber, T::Hash, VoteThreshold, T::BlockNumber),
+	> for OldReferendumInfoOf<T> {
+		type Query = Option<(T::BlockNumber, T::Hash, VoteThreshold, T::BlockNumber)>;
+		type Hasher = Blake2_256;
+		fn module_prefix() -> &'static [u8] { b"Democracy" }
+		fn storage_prefix() -> &'static [u8] { b"ReferendumInfoOf" }
+		fn from_optional_value_to_query(
+			v: Option<(T::BlockNumber, T::Hash, VoteThreshold, T::BlockNumber)>,
+		) -> Self::Query { v }
+		fn from_query_to_optional_value(
+			v: Self::Query,
+		) -> Option<(T::BlockNumber, T::Hash, VoteThreshold, T::BlockNumber)> { v }
+	}
+
+	/// `VotersFor` of version 0: the voters of each referendum.
+	pub(crate) struct OldVotersFor<T>(PhantomData<T>);
+
+	impl<T: Trait> generator::StorageMap<ReferendumIndex, Vec<T::AccountId>> for OldVotersFor<T> {
+		type Query = Vec<T::AccountId>;
+		type Hasher = Blake2_256;
+		fn module_prefix() -> &'static [u8] { b"Democracy" }
+		fn storage_prefix() -> &'static [u8] { b"VotersFor" }
+		fn from_optional_value_to_query(v: Option<Vec<T::AccountId>>) -> Self::Query {
+			v.unwrap_or_default()
+		}
+		fn from_query_to_optional_value(v: Self::Query) -> Option<Vec<T::AccountId>> { Some(v) }
+	}
+
+	impl<T: Trait> StoragePrefixedMap<Vec<T::AccountId>> for OldVotersFor<T> {
+		fn module_prefix() -> &'static [u8] { b"Democracy" }
+		fn storage_prefix() -> &'static [u8] { b"VotersFor" }
+	}
+
+	/// `VoteOf` of version 0: the vote of an account in a referendum.
+	pub(crate) struct OldVoteOf<T>(PhantomData<T>);
+
+	impl<T: Trait> generator::StorageMap<(ReferendumIndex, T::AccountId), Vote> for OldVoteOf<T> {
+		type Query = Option<Vote>;
+		type Hasher = Blake2_256;
+		fn module_prefix() -> &'static [u8] { b"Democracy" }
+		fn storage_prefix() -> &'static [u8] { b"VoteOf" }
+		fn from_optional_value_to_query(v: Option<Vote>) -> Self::Query { v }
+		fn from_query_to_optional_value(v: Self::Query) -> Option<Vote> { v }
+	}
+
+	impl<T: Trait> StoragePrefixedMap<Vote> for OldVoteOf<T> {
+		fn module_prefix() -> &'static [u8] { b"Democracy" }
+		fn storage_prefix() -> &'static [u8] { b"VoteOf" }
+	}
+
+	/// `Delegations` of version 0: the account (and conviction) an account is delegating to.
+	pub(crate) struct OldDelegations<T>(PhantomData<T>);
+
+	impl<T: Trait> generator::StorageLinkedMap<T::AccountId, (T::AccountId, Conviction)>
+		for OldDelegations<T>
+	{
+		type Query = Option<(T::AccountId, Conviction)>;
+		type KeyFormat = Self;
+		fn from_optional_value_to_query(v: Option<(T::AccountId, Conviction)>) -> Self::Query {
+			v
+		}
+		fn from_query_to_optional_value(v: Self::Query) -> Option<(T::AccountId, Conviction)> {
+			v
+		}
+	}
+
+	impl<T> LinkedMapKeyFormat for OldDelegations<T> {
+		type Hasher = Blake2_256;
+		fn module_prefix() -> &'static [u8] { b"Democracy" }
+		fn storage_prefix() -> &'static [u8] { b"Delegations" }
+		fn head_prefix() -> &'static [u8] { b"HeadOfDelegations" }
+	}
+
+	// migrate storage from v0 to v1.
+	//
+	// votes are now recorded per account with their balance, and tallied as they are cast. this
+	// converts the votes of ongoing referenda from `VotersFor` and `VoteOf` into `VotingOf`,
+	// with the free balance of the voter, and tallies them into the new `ReferendumInfoOf`.
+	//
+	// delegations from `Delegations` become `Voting::Delegating` of the free balance of the
+	// delegator, counted in the tally of the referenda the target votes on. an account can no
+	// longer both vote and delegate: if it voted in an ongoing referendum, its delegation is
+	// ended as `undelegate` would do.
+	//
+	// the conviction locks of past votes were set with an expiry under `LEGACY_DEMOCRACY_ID`,
+	// which is no longer touched, so they are honoured until they expire. the indefinite lock of
+	// a delegation is replaced by the lock of the new delegation, or of the undelegation.
+	pub fn to_v1<T: Trait>(version: &mut VersionNumber) {
+		if *version != 0 { return }
+		*version += 1;
+
+		let now = <system::Module<T>>::block_number();
+		let mut votes_of = BTreeMap::<T::AccountId, Vec<_>>::new();
+
+		let first = <Module<T> as Store>::LowestUnbaked::get();
+		let last = <Module<T> as Store>::ReferendumCount::get();
+		for index in first..last {
+			let (end, proposal_hash, threshold, delay) =
+				match <OldReferendumInfoOf<T> as storage::StorageMap<_, _>>::get(index) {
+					Some(info) => info,
+					None => continue,
+				};
+			let mut tally = Tally::default();
+			for voter in <OldVotersFor<T> as storage::StorageMap<_, _>>::get(index) {
+				let key = (index, &voter);
+				let vote = match <OldVoteOf<T> as storage::StorageMap<_, _>>::get(key) {
+					Some(vote) => vote,
+					None => continue,
+				};
+				let balance = T::Currency::free_balance(&voter);
+				let vote = AccountVote::Standard { vote, balance };
+				// Shouldn't be possible to fail, as the votes are bounded by the total issuance.
+				let _ = tally.add(vote);
+				votes_of.entry(voter).or_insert_with(Vec::new).push((index, vote));
+			}
+			let status = ReferendumStatus { end, proposal_hash, threshold, delay, tally };
+			<Module<T> as Store>::ReferendumInfoOf::insert(index, ReferendumInfo::Ongoing(status));
+		}
+		OldVotersFor::<T>::remove_all();
+		OldVoteOf::<T>::remove_all();
+
+		let mut accounts = votes_of.keys().cloned().collect::<Vec<_>>();
+		for (who, votes) in votes_of.into_iter() {
+			// votes are pushed in increasing referendum index.
+			<Module<T> as Store>::VotingOf::insert(&who, Voting::Direct {
+				votes,
+				delegations: Default::default(),
+				prior: Default::default(),
+			});
+		}
+
+		let delegations = <OldDelegations<T> as storage::StorageLinkedMap<_, _>>::enumerate()
+			.collect::<Vec<_>>();
+		let mut delegated = Vec::new();
+		for (who, (target, conviction)) in delegations.into_iter() {
+			<OldDelegations<T> as storage::StorageLinkedMap<_, _>>::remove(&who);
+			T::Currency::remove_lock(LEGACY_DEMOCRACY_ID, &who);
+			let balance = T::Currency::free_balance(&who);
+			let voting = <Module<T> as Store>::VotingOf::get(&who);
+			match voting {
+				Voting::Direct { votes, delegations, mut prior } => {
+					if votes.is_empty() && who != target {
+						<Module<T> as Store>::VotingOf::insert(&who, Voting::Delegating {
+							balance,
+							target: target.clone(),
+							conviction,
+							delegations,
+							prior,
+						});
+						delegated.push((target, conviction.votes(balance)));
+					} else {
+						let lock_periods = conviction.lock_periods().into();
+						prior.accumulate(now + T::EnactmentPeriod::get() * lock_periods, balance);
+						<Module<T> as Store>::VotingOf::insert(
+							&who,
+							Voting::Direct { votes, delegations, prior },
+						);
+					}
+				}
+				// delegations are unique per account.
+				Voting::Delegating { .. } => {}
+			}
+			accounts.push(who);
+		}
+		for (target, amount) in delegated.into_iter() {
+			<Module<T>>::increase_upstream_delegation(&target, amount);
+		}
+
+		for who in accounts.iter() {
+			<Module<T>>::update_lock(who);
+		}
+
+		support::print("Finished migrating Democracy storage to v1.");
+	}
+
+	pub(crate) fn perform_migrations<T: Trait>() {
+		<Module<T> as Store>::StorageVersion::mutate(|version| {
+			if *version < MIN_SUPPORTED_VERSION {
+				support::print("Cannot migrate democracy storage because version is less than\
+					minimum.");
+				support::print(*version);
+				return
+			}
+
+			if *version == CURRENT_VERSION { return }
+
+			to_v1::<T>(version);
+		});
+	}
+}
+
+#[cfg(not(any(test, feature = "migrate")))]
+mod inner {
+	pub(super) fn perform_migrations<T>() { }
+}
+
+/// Perform all necessary storage migrations to get storage into the expected state for current
+/// logic. No-op if fully upgraded.
+pub(crate) fn perform_migrations<T: crate::Trait>() {
+	inner::perform_migrations::<T>();
+}
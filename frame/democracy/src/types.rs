@@ -0,0 +1,148 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Miscellaneous additional datatypes.
+
+use codec::{Encode, Decode};
+use sp_runtime::{
+	RuntimeDebug,
+	traits::{Zero, Bounded, CheckedAdd, CheckedSub, CheckedMul, CheckedDiv, Saturating},
+};
+use crate::{Conviction, VoteThreshold, AccountVote, Delegations};
+
+/// The tally of the votes in a referendum.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Tally<Balance> {
+	/// The number of aye votes, expressed in terms of post-conviction lock-vote.
+	pub ayes: Balance,
+	/// The number of nay votes, expressed in terms of post-conviction lock-vote.
+	pub nays: Balance,
+	/// The amount of funds currently expressing its opinion. Pre-conviction.
+	pub turnout: Balance,
+}
+
+impl<
+	Balance: From<u8> + Zero + Copy + CheckedAdd + CheckedSub + CheckedMul + CheckedDiv + Bounded
+		+ Saturating
+> Tally<Balance> {
+	/// Add an account's vote into the tally. `None` on overflow.
+	pub fn add(&mut self, vote: AccountVote<Balance>) -> Option<()> {
+		match vote {
+			AccountVote::Standard { vote, balance } => {
+				let Delegations { votes, capital } = vote.conviction.votes(balance);
+				self.turnout = self.turnout.checked_add(&capital)?;
+				if vote.aye {
+					self.ayes = self.ayes.checked_add(&votes)?;
+				} else {
+					self.nays = self.nays.checked_add(&votes)?;
+				}
+			}
+			AccountVote::Split { aye, nay } => {
+				let aye = Conviction::None.votes(aye);
+				let nay = Conviction::None.votes(nay);
+				self.turnout = self.turnout.checked_add(&aye.capital)?.checked_add(&nay.capital)?;
+				self.ayes = self.ayes.checked_add(&aye.votes)?;
+				self.nays = self.nays.checked_add(&nay.votes)?;
+			}
+		}
+		Some(())
+	}
+
+	/// Remove an account's vote from the tally. `None` on underflow.
+	pub fn remove(&mut self, vote: AccountVote<Balance>) -> Option<()> {
+		match vote {
+			AccountVote::Standard { vote, balance } => {
+				let Delegations { votes, capital } = vote.conviction.votes(balance);
+				self.turnout = self.turnout.checked_sub(&capital)?;
+				if vote.aye {
+					self.ayes = self.ayes.checked_sub(&votes)?;
+				} else {
+					self.nays = self.nays.checked_sub(&votes)?;
+				}
+			}
+			AccountVote::Split { aye, nay } => {
+				let aye = Conviction::None.votes(aye);
+				let nay = Conviction::None.votes(nay);
+				self.turnout = self.turnout.checked_sub(&aye.capital)?.checked_sub(&nay.capital)?;
+				self.ayes = self.ayes.checked_sub(&aye.votes)?;
+				self.nays = self.nays.checked_sub(&nay.votes)?;
+			}
+		}
+		Some(())
+	}
+
+	/// Add the delegations of an account voting `approve` into the tally.
+	pub fn increase(&mut self, approve: bool, delegations: Delegations<Balance>) {
+		self.turnout = self.turnout.saturating_add(delegations.capital);
+		if approve {
+			self.ayes = self.ayes.saturating_add(delegations.votes);
+		} else {
+			self.nays = self.nays.saturating_add(delegations.votes);
+		}
+	}
+
+	/// Remove the delegations of an account voting `approve` from the tally.
+	pub fn reduce(&mut self, approve: bool, delegations: Delegations<Balance>) {
+		self.turnout = self.turnout.saturating_sub(delegations.capital);
+		if approve {
+			self.ayes = self.ayes.saturating_sub(delegations.votes);
+		} else {
+			self.nays = self.nays.saturating_sub(delegations.votes);
+		}
+	}
+}
+
+/// Info regarding an ongoing referendum.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ReferendumStatus<BlockNumber, Hash, Balance> {
+	/// When voting on this referendum will end.
+	pub end: BlockNumber,
+	/// The hash of the proposal being voted on.
+	pub proposal_hash: Hash,
+	/// The thresholding mechanism to determine whether it passed.
+	pub threshold: VoteThreshold,
+	/// The delay (in blocks) to wait after a successful referendum before deploying.
+	pub delay: BlockNumber,
+	/// The current tally of votes in this referendum.
+	pub tally: Tally<Balance>,
+}
+
+/// Info regarding a referendum, present or past.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum ReferendumInfo<BlockNumber, Hash, Balance> {
+	/// Referendum is happening.
+	Ongoing(ReferendumStatus<BlockNumber, Hash, Balance>),
+	/// Referendum finished at `end`, and has been `approved` or rejected.
+	Finished {
+		/// Whether the proposal was approved.
+		approved: bool,
+		/// The block at which the referendum ended.
+		end: BlockNumber,
+	},
+}
+
+impl<BlockNumber, Hash, Balance: Default> ReferendumInfo<BlockNumber, Hash, Balance> {
+	/// Create a new instance.
+	pub fn new(
+		end: BlockNumber,
+		proposal_hash: Hash,
+		threshold: VoteThreshold,
+		delay: BlockNumber,
+	) -> Self {
+		let s = ReferendumStatus { end, proposal_hash, threshold, delay, tally: Tally::default() };
+		ReferendumInfo::Ongoing(s)
+	}
+}
@@ -0,0 +1,215 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The vote datatype.
+
+use sp_std::{prelude::*, result, convert::TryFrom};
+use codec::{Encode, EncodeLike, Decode, Output, Input};
+use sp_runtime::{RuntimeDebug, traits::{Saturating, Zero}};
+use crate::{Conviction, ReferendumIndex};
+
+/// A number of lock periods, plus a vote, one way or the other.
+#[derive(Copy, Clone, Eq, PartialEq, Default, RuntimeDebug)]
+pub struct Vote {
+	/// Whether the vote is in favour of the proposal.
+	pub aye: bool,
+	/// The conviction behind the vote.
+	pub conviction: Conviction,
+}
+
+impl Encode for Vote {
+	fn encode_to<T: Output>(&self, output: &mut T) {
+		output.push_byte(u8::from(self.conviction) | if self.aye { 0b1000_0000 } else { 0 });
+	}
+}
+
+impl EncodeLike for Vote {}
+
+impl Decode for Vote {
+	fn decode<I: Input>(input: &mut I) -> result::Result<Self, codec::Error> {
+		let b = input.read_byte()?;
+		Ok(Vote {
+			aye: (b & 0b1000_0000) == 0b1000_0000,
+			conviction: Conviction::try_from(b & 0b0111_1111)
+				.map_err(|_| codec::Error::from("Invalid conviction"))?,
+		})
+	}
+}
+
+/// A vote for a referendum of a particular account.
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq, RuntimeDebug)]
+pub enum AccountVote<Balance> {
+	/// A standard vote, one-way (approve or reject) with a given amount of conviction.
+	Standard {
+		/// The direction and conviction of the vote.
+		vote: Vote,
+		/// The balance behind the vote, locked for as long as the vote may count.
+		balance: Balance,
+	},
+	/// A split vote with balances given for both ways, and with no conviction. Useful for
+	/// custodial accounts voting on behalf of many users.
+	Split {
+		/// The balance voting in favour of the proposal.
+		aye: Balance,
+		/// The balance voting against the proposal.
+		nay: Balance,
+	},
+}
+
+impl<Balance: Saturating> AccountVote<Balance> {
+	/// The number of lock periods and the balance to lock after the referendum ended, if it
+	/// passed iff `approved` is `true`. `None` if the vote does not need a lock.
+	pub fn locked_if(self, approved: bool) -> Option<(u32, Balance)> {
+		match self {
+			AccountVote::Standard { vote, balance } if vote.aye == approved =>
+				Some((vote.conviction.lock_periods(), balance)),
+			_ => None,
+		}
+	}
+
+	/// The total balance involved in this vote.
+	pub fn balance(self) -> Balance {
+		match self {
+			AccountVote::Standard { balance, .. } => balance,
+			AccountVote::Split { aye, nay } => aye.saturating_add(nay),
+		}
+	}
+
+	/// Whether this is an aye vote if it is a standard one, `None` if it is split.
+	pub fn as_standard(self) -> Option<bool> {
+		match self {
+			AccountVote::Standard { vote, .. } => Some(vote.aye),
+			_ => None,
+		}
+	}
+}
+
+/// The amount of votes and capital delegated to an account.
+#[derive(Encode, Decode, Default, Copy, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct Delegations<Balance> {
+	/// The number of votes (this is post-conviction).
+	pub votes: Balance,
+	/// The amount of raw capital, used for the turnout.
+	pub capital: Balance,
+}
+
+impl<Balance: Saturating> Delegations<Balance> {
+	/// Add the given delegations.
+	pub fn saturating_add(self, other: Self) -> Self {
+		Self {
+			votes: self.votes.saturating_add(other.votes),
+			capital: self.capital.saturating_add(other.capital),
+		}
+	}
+
+	/// Remove the given delegations.
+	pub fn saturating_sub(self, other: Self) -> Self {
+		Self {
+			votes: self.votes.saturating_sub(other.votes),
+			capital: self.capital.saturating_sub(other.capital),
+		}
+	}
+}
+
+/// A lock left over from past votes or delegations: the balance must stay locked until the block
+/// number.
+#[derive(Encode, Decode, Default, Copy, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct PriorLock<BlockNumber, Balance>(BlockNumber, Balance);
+
+impl<BlockNumber: Ord + Copy + Zero, Balance: Ord + Copy + Zero> PriorLock<BlockNumber, Balance> {
+	/// Merge another lock, keeping the longest duration and the highest balance.
+	pub fn accumulate(&mut self, until: BlockNumber, amount: Balance) {
+		self.0 = self.0.max(until);
+		self.1 = self.1.max(amount);
+	}
+
+	/// The balance which is locked.
+	pub fn locked(&self) -> Balance {
+		self.1
+	}
+
+	/// Drop the lock if it has expired at `now`.
+	pub fn rejig(&mut self, now: BlockNumber) {
+		if now >= self.0 {
+			self.0 = Zero::zero();
+			self.1 = Zero::zero();
+		}
+	}
+}
+
+/// The voting activity of an account.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub enum Voting<Balance, AccountId, BlockNumber> {
+	/// The account is voting directly.
+	Direct {
+		/// The current votes of the account, ordered by referendum index.
+		votes: Vec<(ReferendumIndex, AccountVote<Balance>)>,
+		/// The votes delegated to this account.
+		delegations: Delegations<Balance>,
+		/// Any lock left over from votes which were removed.
+		prior: PriorLock<BlockNumber, Balance>,
+	},
+	/// The account is delegating its vote to `target`.
+	Delegating {
+		/// The delegated balance, locked for as long as the delegation lasts.
+		balance: Balance,
+		/// The account voting on behalf of this one.
+		target: AccountId,
+		/// The conviction of the delegated votes.
+		conviction: Conviction,
+		/// The votes delegated to this account. They are not passed on to `target`.
+		delegations: Delegations<Balance>,
+		/// Any lock left over from votes or delegations which were removed.
+		prior: PriorLock<BlockNumber, Balance>,
+	},
+}
+
+impl<Balance: Default, AccountId, BlockNumber: Zero> Default
+	for Voting<Balance, AccountId, BlockNumber>
+{
+	fn default() -> Self {
+		Voting::Direct {
+			votes: Vec::new(),
+			delegations: Default::default(),
+			prior: PriorLock(Zero::zero(), Default::default()),
+		}
+	}
+}
+
+impl<
+	Balance: Saturating + Ord + Zero + Copy,
+	BlockNumber: Ord + Copy + Zero,
+	AccountId,
+> Voting<Balance, AccountId, BlockNumber> {
+	/// Drop the prior lock if it has expired at `now`.
+	pub fn rejig(&mut self, now: BlockNumber) {
+		let prior = match self {
+			Voting::Direct { prior, .. } => prior,
+			Voting::Delegating { prior, .. } => prior,
+		};
+		prior.rejig(now);
+	}
+
+	/// The amount of this account's balance that must currently be locked due to voting.
+	pub fn locked_balance(&self) -> Balance {
+		match self {
+			Voting::Direct { votes, prior, .. } => votes.iter()
+				.map(|i| i.1.balance())
+				.fold(prior.locked(), |a, i| a.max(i)),
+			Voting::Delegating { balance, prior, .. } => (*balance).max(prior.locked()),
+		}
+	}
+}
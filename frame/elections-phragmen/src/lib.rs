@@ -88,7 +88,7 @@ use support::{
 	decl_storage, decl_event, ensure, decl_module, dispatch, weights::SimpleDispatchInfo,
 	traits::{
 		Currency, Get, LockableCurrency, LockIdentifier, ReservableCurrency, WithdrawReasons,
-		ChangeMembers, OnUnbalanced, WithdrawReason
+		ChangeMembers, OnUnbalanced, WithdrawReason, Contains,
 	}
 };
 use phragmen::ExtendedBalance;
@@ -653,6 +653,9 @@ impl<T: Trait> Module<T> {
 			let split_point = desired_seats.min(new_set_with_stake.len());
 			let mut new_members = (&new_set_with_stake[..split_point]).to_vec();
 
+			// the prime member is the one elected first, i.e. the one with the most approval stake.
+			let prime = new_members.first().map(|(m, _)| m.clone());
+
 			// save the runners up as-is. They are sorted based on desirability.
 			// sort and save the members.
 			new_members.sort_by(|i, j| i.0.cmp(&j.0));
@@ -684,6 +687,7 @@ impl<T: Trait> Module<T> {
 				&outgoing.clone(),
 				&new_members_ids,
 			);
+			T::ChangeMembers::set_prime(prime);
 
 			// outgoing candidates lose their bond.
 			let mut to_burn_bond = outgoing.to_vec();
@@ -731,6 +735,16 @@ impl<T: Trait> Module<T> {
 	}
 }
 
+impl<T: Trait> Contains<T::AccountId> for Module<T> {
+	fn contains(who: &T::AccountId) -> bool {
+		Self::is_member(who)
+	}
+
+	fn sorted_members() -> Vec<T::AccountId> {
+		Self::members_ids()
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -820,6 +834,7 @@ mod tests {
 
 	thread_local! {
 		pub static MEMBERS: RefCell<Vec<u64>> = RefCell::new(vec![]);
+		pub static PRIME: RefCell<Option<u64>> = RefCell::new(None);
 	}
 
 	pub struct TestChangeMembers;
@@ -855,6 +870,10 @@ mod tests {
 
 			MEMBERS.with(|m| *m.borrow_mut() = new.to_vec());
 		}
+
+		fn set_prime(who: Option<u64>) {
+			PRIME.with(|p| *p.borrow_mut() = who);
+		}
 	}
 
 	/// Simple structure that exposes how u64 currency can be represented as... u64.
@@ -943,7 +962,6 @@ mod tests {
 						(5, 50 * self.balance_factor),
 						(6, 60 * self.balance_factor)
 					],
-					vesting: vec![],
 				}),
 			}.build_storage().unwrap().into()
 		}
@@ -1457,6 +1475,25 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn prime_is_the_first_elected_member() {
+		ExtBuilder::default().build().execute_with(|| {
+			assert_ok!(Elections::submit_candidacy(Origin::signed(5)));
+			assert_ok!(Elections::submit_candidacy(Origin::signed(4)));
+			assert_ok!(Elections::submit_candidacy(Origin::signed(3)));
+
+			assert_ok!(Elections::vote(Origin::signed(2), vec![5], 20));
+			assert_ok!(Elections::vote(Origin::signed(4), vec![4], 15));
+			assert_ok!(Elections::vote(Origin::signed(3), vec![3], 30));
+
+			System::set_block_number(5);
+			assert_ok!(Elections::end_block(System::block_number()));
+
+			assert_eq!(Elections::members(), vec![(3, 30), (5, 20)]);
+			assert_eq!(PRIME.with(|p| *p.borrow()), Some(3));
+		});
+	}
+
 	#[test]
 	fn defunct_voter_will_be_counted() {
 		ExtBuilder::default().build().execute_with(|| {
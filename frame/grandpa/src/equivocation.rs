@@ -0,0 +1,171 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! An opt-in utility module for reporting equivocations.
+//!
+//! This module defines an offence type for GRANDPA equivocations
+//! and some utility traits to wire together:
+//! - a system for reporting offences;
+//! - a system for submitting unsigned transactions;
+//!
+//! These can be used in an offchain context in order to submit equivocation
+//! reporting extrinsics (from the client that's running the GRANDPA protocol).
+//! And in a runtime context, so that the GRANDPA module can validate the
+//! equivocation proofs in the extrinsic and report the offences.
+
+use sp_std::prelude::*;
+use codec::{Decode, Encode};
+use sp_finality_grandpa::{EquivocationProof, RoundNumber, SetId};
+use sp_runtime::Perbill;
+use sp_staking::{
+	offence::{Kind, Offence, ReportOffence},
+	SessionIndex,
+};
+use system::offchain::SubmitUnsignedTransaction;
+
+use crate::{Call, Trait};
+
+/// A trait with utility methods for handling equivocation reports in GRANDPA.
+/// The offence type is generic, and the trait provides methods to report an
+/// offence and to submit an equivocation report extrinsic.
+pub trait HandleEquivocation<T: Trait> {
+	/// Report an offence proved by the given reporters.
+	fn report_offence(
+		reporters: Vec<T::AccountId>,
+		offence: GrandpaEquivocationOffence<T::KeyOwnerIdentification>,
+	);
+
+	/// Create and dispatch an equivocation report extrinsic.
+	fn submit_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> Result<(), ()>;
+}
+
+impl<T: Trait> HandleEquivocation<T> for () {
+	fn report_offence(
+		_reporters: Vec<T::AccountId>,
+		_offence: GrandpaEquivocationOffence<T::KeyOwnerIdentification>,
+	) {}
+
+	fn submit_equivocation_report(
+		_equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		_key_owner_proof: T::KeyOwnerProof,
+	) -> Result<(), ()> {
+		// equivocation reporting is disabled.
+		Err(())
+	}
+}
+
+/// Generic equivocation handler. This type implements `HandleEquivocation`
+/// using existing subsystems that are part of frame (type bounds described
+/// below) and will dispatch to them directly, it's only purpose is to wire all
+/// subsystems together.
+pub struct EquivocationHandler<R, S> {
+	_phantom: sp_std::marker::PhantomData<(R, S)>,
+}
+
+impl<R, S> Default for EquivocationHandler<R, S> {
+	fn default() -> Self {
+		Self {
+			_phantom: Default::default(),
+		}
+	}
+}
+
+impl<T, R, S> HandleEquivocation<T> for EquivocationHandler<R, S> where
+	T: Trait,
+	// A system for reporting offences after valid equivocation reports are
+	// processed.
+	R: ReportOffence<
+		T::AccountId,
+		T::KeyOwnerIdentification,
+		GrandpaEquivocationOffence<T::KeyOwnerIdentification>,
+	>,
+	// A transaction submitter. Used for submitting equivocation reports.
+	S: SubmitUnsignedTransaction<T, <T as Trait>::Call>,
+{
+	fn report_offence(
+		reporters: Vec<T::AccountId>,
+		offence: GrandpaEquivocationOffence<T::KeyOwnerIdentification>,
+	) {
+		R::report_offence(reporters, offence);
+	}
+
+	fn submit_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> Result<(), ()> {
+		let call = Call::report_equivocation_unsigned(equivocation_proof, key_owner_proof);
+		S::submit_unsigned(call)
+	}
+}
+
+/// A round number and set id which point on the time of an offence.
+#[derive(Copy, Clone, PartialOrd, Ord, Eq, PartialEq, Encode, Decode)]
+pub struct GrandpaTimeSlot {
+	// The order of these matters for `derive(Ord)`.
+	/// Grandpa Set ID.
+	pub set_id: SetId,
+	/// Round number.
+	pub round: RoundNumber,
+}
+
+/// A grandpa equivocation offence report.
+pub struct GrandpaEquivocationOffence<FullIdentification> {
+	/// Time slot at which this incident happened.
+	pub time_slot: GrandpaTimeSlot,
+	/// The session index in which the incident happened.
+	pub session_index: SessionIndex,
+	/// The size of the validator set at the time of the offence.
+	pub validator_set_count: u32,
+	/// The authority which produced this equivocation.
+	pub offender: FullIdentification,
+}
+
+impl<FullIdentification: Clone> Offence<FullIdentification>
+	for GrandpaEquivocationOffence<FullIdentification>
+{
+	const ID: Kind = *b"grandpa:equivoca";
+	type TimeSlot = GrandpaTimeSlot;
+
+	fn offenders(&self) -> Vec<FullIdentification> {
+		vec![self.offender.clone()]
+	}
+
+	fn session_index(&self) -> SessionIndex {
+		self.session_index
+	}
+
+	fn validator_set_count(&self) -> u32 {
+		self.validator_set_count
+	}
+
+	fn time_slot(&self) -> Self::TimeSlot {
+		self.time_slot
+	}
+
+	fn slash_fraction(
+		offenders_count: u32,
+		validator_set_count: u32,
+	) -> Perbill {
+		// the formula is min((3k / n)^2, 1)
+		let x = Perbill::from_rational_approximation(3 * offenders_count, validator_set_count);
+		// _ ^ 2
+		x.square()
+	}
+}
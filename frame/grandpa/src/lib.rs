@@ -19,8 +19,9 @@
 //! This manages the GRANDPA authority set ready for the native code.
 //! These authorities are only for GRANDPA finality, not for consensus overall.
 //!
-//! In the future, it will also handle misbehavior reports, and on-chain
-//! finality notifications.
+//! It also handles equivocation reports, i.e. proofs that a voter cast
+//! conflicting votes in the same round, which are submitted as unsigned
+//! extrinsics and reported to the offences module once validated.
 //!
 //! For full integration with GRANDPA, the `GrandpaApi` should be implemented.
 //! The necessary items are re-exported via the `fg_primitives` crate.
@@ -32,26 +33,59 @@ pub use sp_finality_grandpa as fg_primitives;
 
 use sp_std::prelude::*;
 use codec::{self as codec, Encode, Decode, Error};
-use support::{decl_event, decl_storage, decl_module, dispatch, storage};
+use support::{decl_event, decl_storage, decl_module, dispatch, storage, Parameter};
+use support::{traits::KeyOwnerProofSystem, weights::SimpleDispatchInfo};
 use sp_runtime::{
-	generic::{DigestItem, OpaqueDigestItemId}, traits::Zero, Perbill,
-};
-use sp_staking::{
-	SessionIndex,
-	offence::{Offence, Kind},
+	generic::{DigestItem, OpaqueDigestItemId}, traits::Zero, KeyTypeId,
+	transaction_validity::{
+		TransactionValidity, ValidTransaction, InvalidTransaction, TransactionPriority,
+	},
 };
+use sp_staking::SessionIndex;
 use fg_primitives::{
-	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID, ScheduledChange, ConsensusLog, SetId, RoundNumber,
+	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID, ScheduledChange, ConsensusLog, SetId,
+	EquivocationProof,
 };
 pub use fg_primitives::{AuthorityId, AuthorityList, AuthorityWeight, VersionedAuthorityList};
-use system::{ensure_signed, DigestOf};
+use session::{GetSessionNumber, GetValidatorCount};
+use system::{ensure_none, ensure_signed, DigestOf};
 
+mod equivocation;
 mod mock;
 mod tests;
 
+pub use equivocation::{
+	EquivocationHandler, GrandpaEquivocationOffence, GrandpaTimeSlot, HandleEquivocation,
+};
+
 pub trait Trait: system::Trait {
 	/// The event type of this module.
 	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+
+	/// A dispatchable call type.
+	type Call: From<Call<Self>>;
+
+	/// The proof of key ownership, used for validating equivocation reports.
+	/// The proof must include the session index and validator count of the
+	/// session at which the equivocation occurred.
+	type KeyOwnerProof: Parameter + GetSessionNumber + GetValidatorCount;
+
+	/// The identification of a key owner, used when reporting equivocations.
+	type KeyOwnerIdentification: Parameter;
+
+	/// A system for proving ownership of keys, i.e. that a given key was part
+	/// of a validator set, needed for validating equivocation reports.
+	type KeyOwnerProofSystem: KeyOwnerProofSystem<
+		(KeyTypeId, AuthorityId),
+		Proof = Self::KeyOwnerProof,
+		IdentificationTuple = Self::KeyOwnerIdentification,
+	>;
+
+	/// The equivocation handling subsystem, defines methods to report an
+	/// offence (after the equivocation has been validated) and for submitting
+	/// a transaction to report an equivocation (from an offchain context).
+	/// Set to `()` to disable equivocation reporting.
+	type HandleEquivocation: HandleEquivocation<Self>;
 }
 
 /// A stored pending change, old format.
@@ -178,6 +212,27 @@ decl_module! {
 			// FIXME: https://github.com/paritytech/substrate/issues/1112
 		}
 
+		/// Report voter equivocation/misbehavior. This method will verify the
+		/// equivocation proof and validate the given key ownership proof
+		/// against the extracted offender. If both are valid, the offence
+		/// will be reported.
+		///
+		/// This extrinsic must be called unsigned and both proofs are also
+		/// checked when validating the transaction (see `ValidateUnsigned`),
+		/// since it isn't paid for. No reporter is rewarded for the offence.
+		#[weight = SimpleDispatchInfo::FixedNormal(5_000_000)]
+		fn report_equivocation_unsigned(
+			origin,
+			equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+			key_owner_proof: T::KeyOwnerProof,
+		) {
+			ensure_none(origin)?;
+
+			let offence = Self::check_equivocation_report(&equivocation_proof, key_owner_proof)?;
+
+			T::HandleEquivocation::report_offence(vec![], offence);
+		}
+
 		fn on_initialize() {
 			#[cfg(feature = "migrate-authorities")]
 			Self::migrate_authorities();
@@ -340,6 +395,67 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Submits an extrinsic to report an equivocation. This method will create
+	/// an unsigned extrinsic with a call to `report_equivocation_unsigned` and
+	/// will push the transaction to the pool. Only useful in an offchain
+	/// context.
+	pub fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> Option<()> {
+		T::HandleEquivocation::submit_equivocation_report(equivocation_proof, key_owner_proof).ok()
+	}
+
+	/// Validate the given equivocation and key ownership proofs, returning
+	/// the offence that should be reported if both are valid.
+	fn check_equivocation_report(
+		equivocation_proof: &EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> Result<GrandpaEquivocationOffence<T::KeyOwnerIdentification>, &'static str> {
+		let set_id = equivocation_proof.set_id();
+		let round = equivocation_proof.round();
+		let session_index = key_owner_proof.session();
+		let validator_set_count = key_owner_proof.validator_count();
+
+		// validate the equivocation proof, i.e. that both votes target
+		// different blocks and are correctly signed.
+		if !fg_primitives::check_equivocation_proof(equivocation_proof.clone()) {
+			return Err("Invalid equivocation proof.");
+		}
+
+		// fetch the session index of the previous set, on the genesis set
+		// there is no previous set.
+		let previous_set_id_session_index = if set_id == 0 {
+			None
+		} else {
+			Some(Self::session_for_set(set_id - 1).ok_or("Invalid equivocation set id.")?)
+		};
+
+		let set_id_session_index = Self::session_for_set(set_id)
+			.ok_or("Invalid equivocation set id.")?;
+
+		// check that the session of the key ownership proof is within the
+		// bounds of the set id reported in the equivocation.
+		if session_index > set_id_session_index ||
+			previous_set_id_session_index.map_or(false, |previous| session_index <= previous)
+		{
+			return Err("Invalid key ownership proof session.");
+		}
+
+		// check the membership proof and extract the offender's identity.
+		let offender = T::KeyOwnerProofSystem::check_proof(
+			(fg_primitives::KEY_TYPE, equivocation_proof.offender().clone()),
+			key_owner_proof,
+		).ok_or("Invalid key ownership proof.")?;
+
+		Ok(GrandpaEquivocationOffence {
+			time_slot: GrandpaTimeSlot { set_id, round },
+			session_index,
+			validator_set_count,
+			offender,
+		})
+	}
+
 	/// Deposit one of this module's logs.
 	fn deposit_log(log: ConsensusLog<T::BlockNumber>) {
 		let log: DigestItem<T::Hash> = DigestItem::Consensus(GRANDPA_ENGINE_ID, log.encode());
@@ -456,55 +572,30 @@ impl<T: Trait> finality_tracker::OnFinalizationStalled<T::BlockNumber> for Modul
 	}
 }
 
-/// A round number and set id which point on the time of an offence.
-#[derive(Copy, Clone, PartialOrd, Ord, Eq, PartialEq, Encode, Decode)]
-struct GrandpaTimeSlot {
-	// The order of these matters for `derive(Ord)`.
-	set_id: SetId,
-	round: RoundNumber,
-}
-
-// TODO [slashing]: Integrate this.
-/// A grandpa equivocation offence report.
-#[allow(dead_code)]
-struct GrandpaEquivocationOffence<FullIdentification> {
-	/// Time slot at which this incident happened.
-	time_slot: GrandpaTimeSlot,
-	/// The session index in which the incident happened.
-	session_index: SessionIndex,
-	/// The size of the validator set at the time of the offence.
-	validator_set_count: u32,
-	/// The authority which produced this equivocation.
-	offender: FullIdentification,
-}
-
-impl<FullIdentification: Clone> Offence<FullIdentification> for GrandpaEquivocationOffence<FullIdentification> {
-	const ID: Kind = *b"grandpa:equivoca";
-	type TimeSlot = GrandpaTimeSlot;
-
-	fn offenders(&self) -> Vec<FullIdentification> {
-		vec![self.offender.clone()]
-	}
+#[allow(deprecated)]
+impl<T: Trait> support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
 
-	fn session_index(&self) -> SessionIndex {
-		self.session_index
-	}
-
-	fn validator_set_count(&self) -> u32 {
-		self.validator_set_count
-	}
-
-	fn time_slot(&self) -> Self::TimeSlot {
-		self.time_slot
-	}
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		if let Call::report_equivocation_unsigned(equivocation_proof, key_owner_proof) = call {
+			if Self::check_equivocation_report(equivocation_proof, key_owner_proof.clone()).is_err() {
+				return InvalidTransaction::BadProof.into();
+			}
 
-	fn slash_fraction(
-		offenders_count: u32,
-		validator_set_count: u32,
-	) -> Perbill {
-		// the formula is min((3k / n)^2, 1)
-		let x = Perbill::from_rational_approximation(3 * offenders_count, validator_set_count);
-		// _ ^ 2
-		x.square()
+			Ok(ValidTransaction {
+				priority: TransactionPriority::max_value(),
+				requires: vec![],
+				// only one report for the same offender at the same round and set id.
+				provides: vec![(
+					equivocation_proof.offender(),
+					equivocation_proof.set_id(),
+					equivocation_proof.round(),
+				).encode()],
+				longevity: 64,
+				propagate: true,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
 	}
 }
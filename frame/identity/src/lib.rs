@@ -866,7 +866,6 @@ mod tests {
 				(20, 100),
 				(30, 100),
 			],
-			vesting: vec![],
 		}.assimilate_storage(&mut t).unwrap();
 		t.into()
 	}
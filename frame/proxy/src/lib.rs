@@ -0,0 +1,654 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Proxy Module
+//!
+//! - [`proxy::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! A module allowing accounts to give permission to other accounts to dispatch types of calls
+//! from their signed origin. Each delegate is registered with a proxy type, which filters the
+//! calls it may dispatch on behalf of the real account; the kinds of proxy are defined by the
+//! runtime.
+//!
+//! Registering delegates reserves a deposit from the real account: `ProxyDepositBase` for
+//! having any, plus `ProxyDepositFactor` for each of them.
+//!
+//! Anonymous accounts may also be created: these have no private key, and are only controllable
+//! through the proxy that spawned them. The deposit for the first proxy of an anonymous account
+//! is reserved from its spawner, and returned to it once the account has no proxy left.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `proxy` - Dispatch a call on behalf of a real account, through one of its proxies.
+//! * `add_proxy` - Register a delegate of the sender.
+//! * `remove_proxy` - Unregister a delegate of the sender.
+//! * `remove_proxies` - Unregister all the delegates of the sender.
+//! * `anonymous` - Spawn an anonymous account, with the sender as its proxy.
+//! * `kill_anonymous` - Remove an anonymous account, called through its proxy.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_io::hashing::blake2_256;
+use sp_runtime::{DispatchError, traits::{Dispatchable, Member, Saturating, Zero}};
+use support::{
+	decl_module, decl_event, decl_storage, ensure, Parameter, IsSubType,
+	traits::{Currency, ReservableCurrency, Get, InstanceFilter},
+	weights::{GetDispatchInfo, SimpleDispatchInfo},
+};
+use system::ensure_signed;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+
+	/// The overarching call type.
+	type Call: Parameter + Dispatchable<Origin=<Self as system::Trait>::Origin>
+		+ GetDispatchInfo + IsSubType<Module<Self>, Self>;
+
+	/// The currency mechanism.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// A kind of proxy; specified with the proxy and passed in to the `InstanceFilter` to decide
+	/// whether a given call may be proxied under this type. The default value is the one allowing
+	/// any call, which alone may remove all the proxies of an account.
+	type ProxyType: Parameter + Member + Ord + PartialOrd + InstanceFilter<<Self as Trait>::Call>
+		+ Default;
+
+	/// The base amount of currency needed to reserve for having proxies.
+	type ProxyDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount of currency needed per proxy added.
+	type ProxyDepositFactor: Get<BalanceOf<Self>>;
+
+	/// The maximum amount of proxies allowed for a single account.
+	type MaxProxies: Get<u16>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Proxy {
+		/// The set of account proxies. Maps the account which has delegated to the accounts
+		/// which are being delegated to, together with the amount held on deposit.
+		pub Proxies get(fn proxies): map T::AccountId => (Vec<(T::AccountId, T::ProxyType)>, BalanceOf<T>);
+
+		/// The spawner of each anonymous account, together with the part of the deposit of the
+		/// anonymous account which is reserved from the spawner rather than from the account.
+		pub Anonymous get(fn anonymous_spawner):
+			map T::AccountId => Option<(T::AccountId, BalanceOf<T>)>;
+	}
+}
+
+decl_event! {
+	/// Events type.
+	pub enum Event {
+		/// A proxy was executed correctly, with the given result.
+		ProxyExecuted(Result<(), DispatchError>),
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The base amount of currency needed to reserve for having proxies.
+		const ProxyDepositBase: BalanceOf<T> = T::ProxyDepositBase::get();
+
+		/// The amount of currency needed per proxy added.
+		const ProxyDepositFactor: BalanceOf<T> = T::ProxyDepositFactor::get();
+
+		/// The maximum amount of proxies allowed for a single account.
+		const MaxProxies: u16 = T::MaxProxies::get();
+
+		/// Deposit one of this module's events by using the default implementation.
+		fn deposit_event() = default;
+
+		/// Dispatch the given `call` from an account that the sender is authorised for through
+		/// `add_proxy`.
+		///
+		/// - `real`: The account that the proxy will make a call on behalf of.
+		/// - `force_proxy_type`: Specify the exact proxy type to be used and checked for this call.
+		/// - `call`: The call to be made by the `real` account.
+		///
+		/// The call must be allowed by the proxy type, and may only change the proxies of `real`
+		/// within the permissions of the proxy type.
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		fn proxy(origin,
+			real: T::AccountId,
+			force_proxy_type: Option<T::ProxyType>,
+			call: Box<<T as Trait>::Call>,
+		) {
+			let who = ensure_signed(origin)?;
+			let (_, proxy_type) = Proxies::<T>::get(&real).0.into_iter()
+				.find(|x| x.0 == who && force_proxy_type.as_ref().map_or(true, |y| &x.1 == y))
+				.ok_or("not a proxy")?;
+
+			// a proxy may not grant more permissions than it has, nor remove all the proxies of
+			// the account unless it allows any call.
+			match call.is_sub_type() {
+				Some(Call::add_proxy(_, pt)) | Some(Call::remove_proxy(_, pt))
+					if !proxy_type.is_superset(pt) => Err("proxy type may not be escalated")?,
+				Some(Call::remove_proxies()) | Some(Call::kill_anonymous(..))
+					if proxy_type != T::ProxyType::default() => Err("proxy type may not be escalated")?,
+				_ => (),
+			}
+			ensure!(proxy_type.filter(&call), "call not allowed for this proxy type");
+
+			let e = call.dispatch(system::RawOrigin::Signed(real).into());
+			Self::deposit_event(Event::ProxyExecuted(e.map_err(Into::into)));
+		}
+
+		/// Register a proxy account for the sender that is able to make calls on its behalf.
+		///
+		/// The deposit of the sender is increased to cover the new proxy.
+		///
+		/// - `proxy`: The account that the `caller` would like to make a proxy.
+		/// - `proxy_type`: The permissions allowed for this proxy account.
+		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		fn add_proxy(origin, proxy: T::AccountId, proxy_type: T::ProxyType) {
+			let who = ensure_signed(origin)?;
+			let (mut proxies, old_deposit) = Proxies::<T>::get(&who);
+			ensure!(proxies.len() < T::MaxProxies::get() as usize, "too many proxies");
+			let typed_proxy = (proxy, proxy_type);
+			let i = proxies.binary_search(&typed_proxy).err().ok_or("proxy already registered")?;
+			proxies.insert(i, typed_proxy);
+			let new_deposit = T::ProxyDepositBase::get()
+				+ T::ProxyDepositFactor::get() * (proxies.len() as u32).into();
+			Self::adjust_deposit(&who, old_deposit, new_deposit)?;
+			Proxies::<T>::insert(&who, (proxies, new_deposit));
+		}
+
+		/// Unregister a proxy account for the sender.
+		///
+		/// The deposit of the sender is decreased accordingly, and returned in full once the last
+		/// proxy is removed.
+		///
+		/// - `proxy`: The account that the `caller` would like to remove as a proxy.
+		/// - `proxy_type`: The permissions currently enabled for the removed proxy account.
+		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		fn remove_proxy(origin, proxy: T::AccountId, proxy_type: T::ProxyType) {
+			let who = ensure_signed(origin)?;
+			let (mut proxies, old_deposit) = Proxies::<T>::get(&who);
+			let typed_proxy = (proxy, proxy_type);
+			let i = proxies.binary_search(&typed_proxy).ok().ok_or("proxy not found")?;
+			proxies.remove(i);
+			let new_deposit = if proxies.is_empty() {
+				Zero::zero()
+			} else {
+				T::ProxyDepositBase::get() + T::ProxyDepositFactor::get() * (proxies.len() as u32).into()
+			};
+			Self::adjust_deposit(&who, old_deposit, new_deposit)?;
+			if proxies.is_empty() {
+				Proxies::<T>::remove(&who);
+				Self::release_spawner_deposit(&who);
+			} else {
+				Proxies::<T>::insert(&who, (proxies, new_deposit));
+			}
+		}
+
+		/// Unregister all proxy accounts for the sender, returning its deposit.
+		///
+		/// WARNING: This may be called on accounts created by `anonymous`, however if done, then
+		/// the fees unreserved from the account will be inaccessible. **All access to this account
+		/// will be lost.** The part of the deposit reserved from the spawner is returned to it.
+		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		fn remove_proxies(origin) {
+			let who = ensure_signed(origin)?;
+			Self::remove_all_proxies(&who)?;
+		}
+
+		/// Spawn a fresh new account that is guaranteed to be otherwise inaccessible, and
+		/// initialize it with a proxy of `proxy_type` for the sender.
+		///
+		/// The deposit for the proxy is reserved from the sender.
+		///
+		/// - `proxy_type`: The type of the proxy that the sender will be registered as over the
+		/// new account. This will almost always be the most permissive `ProxyType` possible to
+		/// allow for maximum flexibility.
+		/// - `index`: A disambiguation index, in case this is called multiple times in the same
+		/// transaction (e.g. with a batch). Unless you're using a batch you probably just want to
+		/// use `0`.
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		fn anonymous(origin, proxy_type: T::ProxyType, index: u16) {
+			let who = ensure_signed(origin)?;
+
+			let anonymous = Self::anonymous_account(&who, &proxy_type, index, None);
+			ensure!(!Proxies::<T>::exists(&anonymous), "anonymous account already exists");
+			let deposit = T::ProxyDepositBase::get() + T::ProxyDepositFactor::get();
+			T::Currency::reserve(&who, deposit)?;
+			Anonymous::<T>::insert(&anonymous, (who.clone(), deposit));
+			Proxies::<T>::insert(&anonymous, (vec![(who, proxy_type)], deposit));
+		}
+
+		/// Removes a previously spawned anonymous proxy, returning its deposit to the spawner.
+		/// The deposit of the proxies added later through the anonymous account is returned to
+		/// the anonymous account.
+		///
+		/// WARNING: **All access to this account will be lost.** Any funds held in it will be
+		/// inaccessible.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the anonymous account, i.e.
+		/// through `proxy`, and the parameters must match those given when it was spawned.
+		///
+		/// - `spawner`: The account that originally called `anonymous` to create this account.
+		/// - `proxy_type`: The proxy type originally passed to `anonymous`.
+		/// - `index`: The disambiguation index originally passed to `anonymous`.
+		/// - `height`: The height of the chain when the call to `anonymous` was processed.
+		/// - `ext_index`: The extrinsic index in which the call to `anonymous` was processed.
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		fn kill_anonymous(origin,
+			spawner: T::AccountId,
+			proxy_type: T::ProxyType,
+			index: u16,
+			#[compact] height: T::BlockNumber,
+			#[compact] ext_index: u32,
+		) {
+			let who = ensure_signed(origin)?;
+
+			let when = (height, ext_index);
+			let anonymous = Self::anonymous_account(&spawner, &proxy_type, index, Some(when));
+			ensure!(anonymous == who, "not the anonymous account");
+
+			Self::remove_all_proxies(&who)?;
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Calculate the address of an anonymous account.
+	///
+	/// - `who`: The spawner account.
+	/// - `proxy_type`: The type of the proxy that the sender will be registered as over the
+	/// new account.
+	/// - `index`: A disambiguation index, in case this is called multiple times in the same
+	/// transaction (e.g. with a batch).
+	/// - `maybe_when`: The block height and extrinsic index of when the anonymous account was
+	/// created. `None` to use the current block height and extrinsic index.
+	pub fn anonymous_account(
+		who: &T::AccountId,
+		proxy_type: &T::ProxyType,
+		index: u16,
+		maybe_when: Option<(T::BlockNumber, u32)>,
+	) -> T::AccountId {
+		let (height, ext_index) = maybe_when.unwrap_or_else(|| (
+			<system::Module<T>>::block_number(),
+			<system::Module<T>>::extrinsic_index().unwrap_or_default(),
+		));
+		let entropy = (b"modlpy/proxy____", who, height, ext_index, proxy_type, index)
+			.using_encoded(blake2_256);
+		T::AccountId::decode(&mut &entropy[..]).unwrap_or_default()
+	}
+
+	/// Reserve or unreserve from `who` the change of the deposit of its proxies from `old` to
+	/// `new`, leaving out the part held by the spawner if `who` is an anonymous account.
+	fn adjust_deposit(
+		who: &T::AccountId,
+		old: BalanceOf<T>,
+		new: BalanceOf<T>,
+	) -> Result<(), &'static str> {
+		let held_by_spawner = Anonymous::<T>::get(who)
+			.map_or_else(Zero::zero, |(_, deposit)| deposit);
+		let old = old.saturating_sub(held_by_spawner);
+		let new = new.saturating_sub(held_by_spawner);
+		if new > old {
+			T::Currency::reserve(who, new - old)?;
+		} else if new < old {
+			T::Currency::unreserve(who, old - new);
+		}
+		Ok(())
+	}
+
+	/// Return to the spawner of the anonymous account `who`, if any, the deposit it holds for it.
+	fn release_spawner_deposit(who: &T::AccountId) {
+		if let Some((spawner, deposit)) = Anonymous::<T>::take(who) {
+			T::Currency::unreserve(&spawner, deposit);
+		}
+	}
+
+	/// Remove all the proxies of `who`, returning each part of their deposit to the account it
+	/// was reserved from.
+	fn remove_all_proxies(who: &T::AccountId) -> Result<(), &'static str> {
+		let (_, old_deposit) = Proxies::<T>::take(who);
+		Self::adjust_deposit(who, old_deposit, Zero::zero())?;
+		Self::release_spawner_deposit(who);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types, impl_outer_dispatch,
+		weights::Weight,
+	};
+	use primitives::H256;
+	use sp_runtime::{Perbill, RuntimeDebug, traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			balances::Balances,
+			proxy::Proxy,
+		}
+	}
+
+	// For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = Call;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 0;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const ProxyDepositBase: u64 = 1;
+		pub const ProxyDepositFactor: u64 = 1;
+		pub const MaxProxies: u16 = 4;
+	}
+	#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+	pub enum ProxyType {
+		Any,
+		NonTransfer,
+		JustTransfer,
+	}
+	impl Default for ProxyType { fn default() -> Self { ProxyType::Any } }
+	impl InstanceFilter<Call> for ProxyType {
+		fn filter(&self, c: &Call) -> bool {
+			match self {
+				ProxyType::Any => true,
+				ProxyType::NonTransfer => match c {
+					Call::Balances(..) => false,
+					_ => true,
+				},
+				ProxyType::JustTransfer => match c {
+					Call::Balances(balances::Call::transfer(..)) => true,
+					_ => false,
+				},
+			}
+		}
+		fn is_superset(&self, o: &Self) -> bool {
+			self == &ProxyType::Any || self == o
+		}
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Call = Call;
+		type Currency = Balances;
+		type ProxyType = ProxyType;
+		type ProxyDepositBase = ProxyDepositBase;
+		type ProxyDepositFactor = ProxyDepositFactor;
+		type MaxProxies = MaxProxies;
+	}
+	type System = system::Module<Test>;
+	type Balances = balances::Module<Test>;
+	type Proxy = Module<Test>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 10), (2, 10), (3, 10), (4, 10), (5, 2)],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn transfer_call(dest: u64, value: u64) -> Box<Call> {
+		Box::new(Call::Balances(balances::Call::transfer(dest, value)))
+	}
+
+	#[test]
+	fn add_remove_proxies_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any));
+			assert_noop!(
+				Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any),
+				"proxy already registered",
+			);
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::JustTransfer));
+			assert_eq!(Balances::reserved_balance(1), 3);
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::Any));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 4, ProxyType::Any));
+			assert_eq!(Balances::reserved_balance(1), 5);
+			assert_noop!(
+				Proxy::add_proxy(Origin::signed(1), 4, ProxyType::NonTransfer),
+				"too many proxies",
+			);
+
+			assert_noop!(
+				Proxy::remove_proxy(Origin::signed(1), 3, ProxyType::JustTransfer),
+				"proxy not found",
+			);
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 4, ProxyType::Any));
+			assert_eq!(Balances::reserved_balance(1), 4);
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 3, ProxyType::Any));
+			assert_eq!(Balances::reserved_balance(1), 3);
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 2, ProxyType::Any));
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 2, ProxyType::JustTransfer));
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert!(!Proxies::<Test>::exists(&1));
+		});
+	}
+
+	#[test]
+	fn cannot_add_proxy_without_balance() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(5), 3, ProxyType::Any));
+			assert_eq!(Balances::reserved_balance(5), 2);
+			assert!(Proxy::add_proxy(Origin::signed(5), 4, ProxyType::Any).is_err());
+			assert_eq!(Proxy::proxies(&5).0, vec![(3, ProxyType::Any)]);
+		});
+	}
+
+	#[test]
+	fn remove_proxies_returns_the_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::NonTransfer));
+			assert_eq!(Balances::reserved_balance(1), 3);
+			assert_ok!(Proxy::remove_proxies(Origin::signed(1)));
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::free_balance(1), 10);
+			assert!(!Proxies::<Test>::exists(&1));
+		});
+	}
+
+	#[test]
+	fn filtering_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::JustTransfer));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 4, ProxyType::NonTransfer));
+
+			assert_noop!(Proxy::proxy(Origin::signed(5), 1, None, transfer_call(6, 1)), "not a proxy");
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, None, transfer_call(6, 1)));
+			assert_ok!(Proxy::proxy(Origin::signed(3), 1, None, transfer_call(6, 1)));
+			assert_noop!(
+				Proxy::proxy(Origin::signed(4), 1, None, transfer_call(6, 1)),
+				"call not allowed for this proxy type",
+			);
+			assert_eq!(Balances::free_balance(6), 2);
+
+			assert_noop!(
+				Proxy::proxy(Origin::signed(2), 1, Some(ProxyType::NonTransfer), transfer_call(6, 1)),
+				"not a proxy",
+			);
+		});
+	}
+
+	#[test]
+	fn proxy_type_cannot_be_escalated() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 4, ProxyType::NonTransfer));
+
+			let add_any = Box::new(Call::Proxy(super::Call::add_proxy(4, ProxyType::Any)));
+			assert_noop!(
+				Proxy::proxy(Origin::signed(4), 1, None, add_any),
+				"proxy type may not be escalated",
+			);
+			let remove_any = Box::new(Call::Proxy(super::Call::remove_proxy(2, ProxyType::Any)));
+			assert_noop!(
+				Proxy::proxy(Origin::signed(4), 1, None, remove_any),
+				"proxy type may not be escalated",
+			);
+			let remove_all = Box::new(Call::Proxy(super::Call::remove_proxies()));
+			assert_noop!(
+				Proxy::proxy(Origin::signed(4), 1, None, remove_all.clone()),
+				"proxy type may not be escalated",
+			);
+
+			// a non-transfer proxy may still add proxies of its own type.
+			let add_non_transfer = Box::new(Call::Proxy(super::Call::add_proxy(3, ProxyType::NonTransfer)));
+			assert_ok!(Proxy::proxy(Origin::signed(4), 1, None, add_non_transfer));
+			assert_eq!(Proxy::proxies(&1).0.len(), 3);
+
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, None, remove_all));
+			assert!(!Proxies::<Test>::exists(&1));
+		});
+	}
+
+	#[test]
+	fn anonymous_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Proxy::anonymous(Origin::signed(1), ProxyType::Any, 0));
+			let anon = Proxy::anonymous_account(&1, &ProxyType::Any, 0, None);
+			assert_eq!(Proxy::proxies(&anon).0, vec![(1, ProxyType::Any)]);
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_noop!(
+				Proxy::anonymous(Origin::signed(1), ProxyType::Any, 0),
+				"anonymous account already exists",
+			);
+
+			assert_ok!(Proxy::anonymous(Origin::signed(1), ProxyType::Any, 1));
+			let anon2 = Proxy::anonymous_account(&1, &ProxyType::Any, 1, None);
+			assert_ok!(Proxy::anonymous(Origin::signed(2), ProxyType::Any, 0));
+
+			assert_ok!(Balances::transfer(Origin::signed(3), anon, 5));
+			assert_ok!(Proxy::proxy(Origin::signed(1), anon, None, transfer_call(6, 1)));
+			assert_eq!(Balances::free_balance(6), 1);
+
+			assert_noop!(
+				Proxy::kill_anonymous(Origin::signed(anon2), 1, ProxyType::Any, 0, 1, 0),
+				"not the anonymous account",
+			);
+			let kill = Box::new(Call::Proxy(super::Call::kill_anonymous(1, ProxyType::Any, 0, 1, 0)));
+			assert_eq!(Balances::reserved_balance(1), 4);
+			assert_ok!(Proxy::proxy(Origin::signed(1), anon, None, kill));
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_noop!(
+				Proxy::proxy(Origin::signed(1), anon, None, transfer_call(6, 1)),
+				"not a proxy",
+			);
+		});
+	}
+
+	#[test]
+	fn kill_anonymous_returns_each_deposit_to_its_depositor() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Proxy::anonymous(Origin::signed(1), ProxyType::Any, 0));
+			let anon = Proxy::anonymous_account(&1, &ProxyType::Any, 0, None);
+			assert_ok!(Balances::transfer(Origin::signed(3), anon, 5));
+
+			// the second proxy is paid for by the anonymous account.
+			let add = Box::new(Call::Proxy(super::Call::add_proxy(2, ProxyType::Any)));
+			assert_ok!(Proxy::proxy(Origin::signed(1), anon, None, add));
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_eq!(Balances::reserved_balance(anon), 1);
+
+			let kill = Box::new(Call::Proxy(super::Call::kill_anonymous(1, ProxyType::Any, 0, 1, 0)));
+			assert_ok!(Proxy::proxy(Origin::signed(2), anon, None, kill));
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::reserved_balance(anon), 0);
+			assert!(!Proxies::<Test>::exists(&anon));
+			assert!(!Anonymous::<Test>::exists(&anon));
+		});
+	}
+
+	#[test]
+	fn remove_proxies_of_anonymous_returns_the_deposit_of_the_spawner() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Proxy::anonymous(Origin::signed(1), ProxyType::Any, 0));
+			let anon = Proxy::anonymous_account(&1, &ProxyType::Any, 0, None);
+			assert_ok!(Balances::transfer(Origin::signed(3), anon, 5));
+
+			let add = Box::new(Call::Proxy(super::Call::add_proxy(2, ProxyType::Any)));
+			assert_ok!(Proxy::proxy(Origin::signed(1), anon, None, add));
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_eq!(Balances::reserved_balance(anon), 1);
+
+			let remove_all = Box::new(Call::Proxy(super::Call::remove_proxies()));
+			assert_ok!(Proxy::proxy(Origin::signed(1), anon, None, remove_all));
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::reserved_balance(anon), 0);
+			assert_eq!(Balances::free_balance(anon), 5);
+			assert!(!Anonymous::<Test>::exists(&anon));
+		});
+	}
+}
@@ -0,0 +1,358 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the recovery module.
+
+#![cfg(test)]
+
+use super::*;
+use crate::mock::*;
+use support::{assert_ok, assert_noop};
+
+#[test]
+fn basic_setup_works() {
+	new_test_ext().execute_with(|| {
+		// Nothing in storage to start
+		assert_eq!(Recovery::recovered_account(&1), None);
+		assert_eq!(Recovery::active_recovery(&1, &2), None);
+		assert_eq!(Recovery::recovery_config(&1), None);
+		// Everyone should have starting balance of 100
+		assert_eq!(Balances::free_balance(1), 100);
+	});
+}
+
+#[test]
+fn set_recovered_works() {
+	new_test_ext().execute_with(|| {
+		// Not accessible by a normal user
+		assert_noop!(Recovery::set_recovered(Origin::signed(1), 5, 1), "RequireRootOrigin");
+		// Root can set a recovered account though
+		assert_ok!(Recovery::set_recovered(Origin::ROOT, 5, 1));
+		// Account 1 should now be able to make a call through account 5
+		let call = Box::new(Call::Balances(BalancesCall::transfer(1, 100)));
+		assert_ok!(Recovery::as_recovered(Origin::signed(1), 5, call));
+		// Account 1 has successfully drained the funds from account 5
+		assert_eq!(Balances::free_balance(1), 200);
+		assert_eq!(Balances::free_balance(5), 0);
+	});
+}
+
+#[test]
+fn recovery_life_cycle_works() {
+	new_test_ext().execute_with(|| {
+		let friends = vec![2, 3, 4];
+		let threshold = 3;
+		let delay_period = 10;
+		// Account 5 sets up a recovery configuration on their account
+		assert_ok!(Recovery::create_recovery(Origin::signed(5), friends, threshold, delay_period));
+		// Some time has passed, and the user lost their keys!
+		run_to_block(10);
+		// Using account 1, the user begins the recovery process to recover the lost account
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(1), 5));
+		// Off chain, the user contacts their friends and asks them to vouch for the recovery attempt
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(2), 5, 1));
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(3), 5, 1));
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(4), 5, 1));
+		// We met the threshold, lets try to recover the account...?
+		assert_noop!(Recovery::claim_recovery(Origin::signed(1), 5), "recovery is still delayed");
+		// We need to wait at least the delay_period number of blocks before we can recover
+		run_to_block(20);
+		assert_ok!(Recovery::claim_recovery(Origin::signed(1), 5));
+		// Account 1 can use account 5 to close the active recovery process, claiming the deposited
+		// funds used to initiate the recovery process into account 5.
+		let call = Box::new(Call::Recovery(RecoveryCall::close_recovery(1)));
+		assert_ok!(Recovery::as_recovered(Origin::signed(1), 5, call));
+		// Account 1 can then use account 5 to remove the recovery configuration, claiming the
+		// deposited funds used to create the recovery configuration into account 5.
+		let call = Box::new(Call::Recovery(RecoveryCall::remove_recovery()));
+		assert_ok!(Recovery::as_recovered(Origin::signed(1), 5, call));
+		// Account 1 should now be able to make a call through account 5 to get all of their funds
+		assert_eq!(Balances::free_balance(5), 110);
+		let call = Box::new(Call::Balances(BalancesCall::transfer(1, 110)));
+		assert_ok!(Recovery::as_recovered(Origin::signed(1), 5, call));
+		// All funds have been fully recovered!
+		assert_eq!(Balances::free_balance(1), 200);
+		assert_eq!(Balances::free_balance(5), 0);
+		// All storage items are removed from the module
+		assert!(!<ActiveRecoveries<Test>>::exists(&5, &1));
+		assert!(!<Recoverable<Test>>::exists(&5));
+		// The rescuer can finally drop the link to the recovered account
+		assert_ok!(Recovery::cancel_recovered(Origin::signed(1), 5));
+		assert!(!<Recovered<Test>>::exists(&5));
+		let call = Box::new(Call::Balances(BalancesCall::transfer(1, 1)));
+		assert_noop!(Recovery::as_recovered(Origin::signed(1), 5, call), "not allowed");
+	});
+}
+
+#[test]
+fn malicious_recovery_fails() {
+	new_test_ext().execute_with(|| {
+		let friends = vec![2, 3, 4];
+		let threshold = 3;
+		let delay_period = 10;
+		// Account 5 sets up a recovery configuration on their account
+		assert_ok!(Recovery::create_recovery(Origin::signed(5), friends, threshold, delay_period));
+		// Some time has passed, and account 1 wants to try and attack this account!
+		run_to_block(10);
+		// Using account 1, the malicious user begins the recovery process on account 5
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(1), 5));
+		// Off chain, the user **tricks** their friends and asks them to vouch for the recovery
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(2), 5, 1));
+		// shame on you
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(3), 5, 1));
+		// shame on you
+		// We don't have enough votes yet to recover the account
+		run_to_block(20);
+		assert_noop!(Recovery::claim_recovery(Origin::signed(1), 5), "not enough friends vouched");
+		// Account 5 notices the recovery attempt and closes it, taking the deposit of account 1
+		assert_ok!(Recovery::close_recovery(Origin::signed(5), 1));
+		assert_eq!(Balances::free_balance(1), 90);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		// 100 - 13 reserved by the configuration + 10 from account 1
+		assert_eq!(Balances::free_balance(5), 97);
+		// The recovery cannot be claimed any more
+		assert_noop!(Recovery::vouch_recovery(Origin::signed(4), 5, 1), "recovery not started");
+		assert_noop!(Recovery::claim_recovery(Origin::signed(1), 5), "recovery not started");
+		// Account 5 can then remove the recovery configuration
+		assert_ok!(Recovery::remove_recovery(Origin::signed(5)));
+		assert_eq!(Balances::free_balance(5), 110);
+	});
+}
+
+#[test]
+fn create_recovery_handles_basic_errors() {
+	new_test_ext().execute_with(|| {
+		// No friends
+		assert_noop!(
+			Recovery::create_recovery(Origin::signed(5), vec![], 1, 0),
+			"friends list must not be empty",
+		);
+		// Zero threshold
+		assert_noop!(
+			Recovery::create_recovery(Origin::signed(5), vec![2], 0, 0),
+			"threshold must be at least one",
+		);
+		// Threshold greater than friends length
+		assert_noop!(
+			Recovery::create_recovery(Origin::signed(5), vec![2, 3, 4], 4, 0),
+			"threshold higher than the number of friends",
+		);
+		// Too many friends
+		assert_noop!(
+			Recovery::create_recovery(Origin::signed(5), vec![1, 2, 3, 4], 4, 0),
+			"too many friends",
+		);
+		// Unsorted friends
+		assert_noop!(
+			Recovery::create_recovery(Origin::signed(5), vec![3, 2, 4], 3, 0),
+			"friends list must be sorted and unique",
+		);
+		// Duplicate friends
+		assert_noop!(
+			Recovery::create_recovery(Origin::signed(5), vec![2, 2, 4], 3, 0),
+			"friends list must be sorted and unique",
+		);
+		// Already configured
+		assert_ok!(Recovery::create_recovery(Origin::signed(5), vec![2, 3, 4], 3, 10));
+		assert_noop!(
+			Recovery::create_recovery(Origin::signed(5), vec![2, 3, 4], 3, 10),
+			"account already recoverable",
+		);
+	});
+}
+
+#[test]
+fn create_recovery_works() {
+	new_test_ext().execute_with(|| {
+		let friends = vec![2, 3, 4];
+		let threshold = 3;
+		let delay_period = 10;
+		// Account 5 sets up a recovery configuration on their account
+		assert_ok!(Recovery::create_recovery(Origin::signed(5), friends.clone(), threshold, delay_period));
+		// Deposit is taken, and scales with the number of friends they pick
+		// Base 10 + 1 per friends = 13 total reserved
+		assert_eq!(Balances::reserved_balance(5), 13);
+		// Recovery configuration is correctly stored
+		let recovery_config = RecoveryConfig {
+			delay_period,
+			deposit: 13,
+			friends: friends.clone(),
+			threshold,
+		};
+		assert_eq!(Recovery::recovery_config(5), Some(recovery_config));
+	});
+}
+
+#[test]
+fn initiate_recovery_handles_basic_errors() {
+	new_test_ext().execute_with(|| {
+		// No recovery process set up for the account
+		assert_noop!(Recovery::initiate_recovery(Origin::signed(1), 5), "account not recoverable");
+		// Create a recovery process for next test
+		assert_ok!(Recovery::create_recovery(Origin::signed(5), vec![2, 3, 4], 3, 10));
+		// Same user cannot recover same account twice
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(1), 5));
+		assert_noop!(Recovery::initiate_recovery(Origin::signed(1), 5), "recovery already started");
+		// No double deposit
+		assert_eq!(Balances::reserved_balance(1), 10);
+	});
+}
+
+#[test]
+fn initiate_recovery_works() {
+	new_test_ext().execute_with(|| {
+		// Create a recovery process for the test
+		assert_ok!(Recovery::create_recovery(Origin::signed(5), vec![2, 3, 4], 3, 10));
+		// Recovery can be initiated
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(1), 5));
+		// Deposit is reserved
+		assert_eq!(Balances::reserved_balance(1), 10);
+		// Recovery status object is created correctly
+		let recovery_status = ActiveRecovery {
+			created: 0,
+			deposit: 10,
+			friends: vec![],
+		};
+		assert_eq!(<ActiveRecoveries<Test>>::get(&5, &1), Some(recovery_status));
+		// Multiple users can attempt to recover the same account
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(2), 5));
+	});
+}
+
+#[test]
+fn vouch_recovery_handles_basic_errors() {
+	new_test_ext().execute_with(|| {
+		// Cannot vouch for non-recoverable account
+		assert_noop!(Recovery::vouch_recovery(Origin::signed(2), 5, 1), "account not recoverable");
+		// Create a recovery process for next tests
+		assert_ok!(Recovery::create_recovery(Origin::signed(5), vec![2, 3, 4], 3, 10));
+		// Cannot vouch a recovery process that has not started
+		assert_noop!(Recovery::vouch_recovery(Origin::signed(2), 5, 1), "recovery not started");
+		// Initiate a recovery process
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(1), 5));
+		// Cannot vouch if you are not a friend
+		assert_noop!(Recovery::vouch_recovery(Origin::signed(22), 5, 1), "not a friend");
+		// Cannot vouch twice
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(2), 5, 1));
+		assert_noop!(Recovery::vouch_recovery(Origin::signed(2), 5, 1), "already vouched");
+	});
+}
+
+#[test]
+fn vouch_recovery_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Recovery::create_recovery(Origin::signed(5), vec![2, 3, 4], 3, 10));
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(1), 5));
+		// Vouching works
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(2), 5, 1));
+		// Handles out of order vouches
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(4), 5, 1));
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(3), 5, 1));
+		// Final recovery status object is updated correctly
+		let recovery_status = ActiveRecovery {
+			created: 0,
+			deposit: 10,
+			friends: vec![2, 3, 4],
+		};
+		assert_eq!(<ActiveRecoveries<Test>>::get(&5, &1), Some(recovery_status));
+	});
+}
+
+#[test]
+fn claim_recovery_handles_basic_errors() {
+	new_test_ext().execute_with(|| {
+		// Cannot claim a non-recoverable account
+		assert_noop!(Recovery::claim_recovery(Origin::signed(1), 5), "account not recoverable");
+		// Create a recovery process for the test
+		assert_ok!(Recovery::create_recovery(Origin::signed(5), vec![2, 3, 4], 3, 10));
+		// Cannot claim an account which has not started the recovery process
+		assert_noop!(Recovery::claim_recovery(Origin::signed(1), 5), "recovery not started");
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(1), 5));
+		// Cannot claim an account which has not passed the delay period
+		assert_noop!(Recovery::claim_recovery(Origin::signed(1), 5), "recovery is still delayed");
+		run_to_block(11);
+		// Cannot claim an account which has not passed the threshold number of votes
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(2), 5, 1));
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(3), 5, 1));
+		// Only 2/3 is not good enough
+		assert_noop!(Recovery::claim_recovery(Origin::signed(1), 5), "not enough friends vouched");
+	});
+}
+
+#[test]
+fn claim_recovery_works() {
+	new_test_ext().execute_with(|| {
+		// Create, initiate, and vouch recovery process for the test
+		assert_ok!(Recovery::create_recovery(Origin::signed(5), vec![2, 3, 4], 2, 10));
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(1), 5));
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(2), 5, 1));
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(3), 5, 1));
+
+		run_to_block(11);
+
+		// Account can be recovered.
+		assert_ok!(Recovery::claim_recovery(Origin::signed(1), 5));
+		// Recovered storage item is correctly created
+		assert_eq!(<Recovered<Test>>::get(&5), Some(1));
+
+		// Account 4 cannot claim the same account while account 1 holds it
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(4), 5));
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(2), 5, 4));
+		assert_ok!(Recovery::vouch_recovery(Origin::signed(3), 5, 4));
+		run_to_block(21);
+		assert_noop!(Recovery::claim_recovery(Origin::signed(4), 5), "account already recovered");
+	});
+}
+
+#[test]
+fn close_recovery_handles_basic_errors() {
+	new_test_ext().execute_with(|| {
+		// Cannot close a non-active recovery
+		assert_noop!(Recovery::close_recovery(Origin::signed(5), 1), "recovery not started");
+	});
+}
+
+#[test]
+fn remove_recovery_works() {
+	new_test_ext().execute_with(|| {
+		// Cannot remove an unrecoverable account
+		assert_noop!(Recovery::remove_recovery(Origin::signed(5)), "account not recoverable");
+		// Create and initiate a recovery process for the test
+		assert_ok!(Recovery::create_recovery(Origin::signed(5), vec![2, 3, 4], 3, 10));
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(1), 5));
+		assert_ok!(Recovery::initiate_recovery(Origin::signed(2), 5));
+		// Cannot remove a recovery when there are active recoveries.
+		assert_noop!(Recovery::remove_recovery(Origin::signed(5)), "recovery still active");
+		assert_ok!(Recovery::close_recovery(Origin::signed(5), 1));
+		// Still need to remove one more!
+		assert_noop!(Recovery::remove_recovery(Origin::signed(5)), "recovery still active");
+		assert_ok!(Recovery::close_recovery(Origin::signed(5), 2));
+		// Finally removed
+		assert_ok!(Recovery::remove_recovery(Origin::signed(5)));
+		assert_eq!(Balances::reserved_balance(5), 0);
+	});
+}
+
+#[test]
+fn cancel_recovered_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Recovery::set_recovered(Origin::ROOT, 5, 1));
+		// Only the rescuer can drop the link
+		assert_noop!(Recovery::cancel_recovered(Origin::signed(2), 5), "not allowed");
+		assert_ok!(Recovery::cancel_recovered(Origin::signed(1), 5));
+		assert_eq!(Recovery::recovered_account(&5), None);
+	});
+}
@@ -0,0 +1,562 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Consensus extension module for Sassafras consensus. Collects the tickets
+//! submitted by authorities for the next epoch, assigns them to the slots of
+//! the epoch once it starts and manages epoch transitions.
+//!
+//! Epochs are purely time-based: the epoch of a block is derived from its
+//! slot and the slot of the first block of the chain. Tickets are sorted by
+//! value and the `i`-th ticket is assigned to the `i`-th slot of the epoch,
+//! slots left without a ticket are assigned to authorities in a round-robin
+//! fashion.
+//!
+//! Tickets are submitted by the next epoch authorities together with their
+//! VRF proofs, which are verified against the submitting authority before the
+//! tickets are accepted. Since the VRF is deterministic, each authority can
+//! produce at most `AttemptsNumber` distinct tickets per epoch.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![forbid(unused_must_use, unsafe_code, unused_variables)]
+#![deny(unused_imports)]
+pub use timestamp;
+
+use sp_std::{result, prelude::*};
+use support::{decl_storage, decl_module, traits::FindAuthor, traits::Get};
+use sp_timestamp::OnTimestampSet;
+use sp_runtime::{ConsensusEngineId, traits::{IsMember, SaturatedConversion, Saturating}};
+use sp_runtime::transaction_validity::{
+	TransactionValidity, ValidTransaction, InvalidTransaction, TransactionPriority,
+};
+use system::{ensure_none, offchain::SubmitUnsignedTransaction};
+
+use codec::{Encode, Decode};
+use inherents::{InherentIdentifier, InherentData, ProvideInherent, MakeFatalError};
+use sassafras_primitives::{
+	SASSAFRAS_ENGINE_ID, AuthorityIndex, PreDigest, SassafrasConfiguration, Epoch, SlotNumber,
+	Ticket, TicketProof, inherents::{INHERENT_IDENTIFIER, SassafrasInherentData},
+};
+pub use sassafras_primitives::{AuthorityId, VRF_OUTPUT_LENGTH};
+
+#[cfg(all(feature = "std", test))]
+mod tests;
+
+#[cfg(all(feature = "std", test))]
+mod mock;
+
+pub trait Trait: timestamp::Trait {
+	/// The amount of time, in slots, that each epoch should last.
+	type EpochDuration: Get<SlotNumber>;
+
+	/// The number of tickets each authority tries to generate per epoch.
+	/// This is also the maximum number of tickets accepted in a single
+	/// submission.
+	type AttemptsNumber: Get<u32>;
+
+	/// The expected number of winning tickets per slot. Used to compute the
+	/// threshold under which a ticket is winning.
+	type RedundancyFactor: Get<u32>;
+
+	/// A dispatchable call type.
+	type Call: From<Call<Self>>;
+
+	/// A transaction submitter, used to submit tickets from an offchain
+	/// context.
+	type SubmitTransaction: SubmitUnsignedTransaction<Self, <Self as Trait>::Call>;
+}
+
+/// The length of the Sassafras randomness
+pub const RANDOMNESS_LENGTH: usize = 32;
+
+type MaybeVrf = Option<[u8; 32 /* VRF_OUTPUT_LENGTH */]>;
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Sassafras {
+		/// Current epoch index.
+		pub EpochIndex get(fn epoch_index): u64;
+
+		/// Current epoch authorities.
+		pub Authorities get(fn authorities): Vec<AuthorityId>;
+
+		/// Next epoch authorities, i.e. the authorities that tickets are
+		/// currently being collected from.
+		pub NextAuthorities get(fn next_authorities): Vec<AuthorityId>;
+
+		/// The slot at which the first epoch actually started. This is 0
+		/// until the first block of the chain.
+		pub GenesisSlot get(fn genesis_slot): u64;
+
+		/// Current slot number.
+		pub CurrentSlot get(fn current_slot): u64;
+
+		/// The epoch randomness for the *current* epoch, used as input for the
+		/// ticket VRFs of the epoch.
+		// NOTE: the following fields don't use the constants to define the
+		// array size because the metadata API currently doesn't resolve the
+		// variable to its underlying value.
+		pub Randomness get(fn randomness): [u8; 32 /* RANDOMNESS_LENGTH */];
+
+		/// Next epoch randomness.
+		pub NextRandomness get(fn next_randomness): [u8; 32 /* RANDOMNESS_LENGTH */];
+
+		/// Accumulator of the VRF outputs revealed in the current epoch, used
+		/// to compute the randomness of the epoch after next.
+		RandomnessAccumulator: [u8; 32 /* RANDOMNESS_LENGTH */];
+
+		/// The sorted tickets assigned to the slots of the current epoch.
+		pub Tickets get(fn tickets): Vec<Ticket>;
+
+		/// The sorted tickets submitted so far for the next epoch. Only the
+		/// lowest `EpochDuration` tickets are kept.
+		pub NextTickets get(fn next_tickets): Vec<Ticket>;
+
+		/// Temporary value (cleared at block finalization) which is `Some`
+		/// if per-block initialization has already been called for current block.
+		Initialized get(fn initialized): Option<MaybeVrf>;
+	}
+	add_extra_genesis {
+		config(authorities): Vec<AuthorityId>;
+		build(|config| Module::<T>::initialize_authorities(&config.authorities))
+	}
+}
+
+decl_module! {
+	/// The Sassafras module
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The number of **slots** that an epoch takes. We couple sessions to
+		/// epochs, i.e. we start a new session once the new epoch begins.
+		const EpochDuration: u64 = T::EpochDuration::get();
+
+		/// The number of tickets each authority tries to generate per epoch.
+		const AttemptsNumber: u32 = T::AttemptsNumber::get();
+
+		/// The expected number of winning tickets per slot.
+		const RedundancyFactor: u32 = T::RedundancyFactor::get();
+
+		/// Initialization
+		fn on_initialize(now: T::BlockNumber) {
+			Self::do_initialize(now);
+		}
+
+		/// Block finalization
+		fn on_finalize() {
+			// the epoch change has already happened at this point, so the
+			// accumulator only contains outputs from the current epoch.
+			if let Some(Some(vrf_output)) = Initialized::take() {
+				Self::deposit_vrf_output(&vrf_output);
+			}
+		}
+
+		/// Submit the tickets of the given next epoch authority. The ticket
+		/// proofs are verified against the authority key, and the resulting
+		/// tickets are inserted into the sorted list of next epoch tickets,
+		/// only the lowest `EpochDuration` tickets are kept.
+		///
+		/// This extrinsic must be called unsigned, the ticket proofs are also
+		/// checked when validating the transaction (see `ValidateUnsigned`).
+		fn submit_tickets(origin, authority_index: AuthorityIndex, tickets: Vec<TicketProof>) {
+			ensure_none(origin)?;
+
+			let tickets = Self::check_tickets(authority_index, &tickets)
+				.map_err(|_| "Invalid ticket proof")?;
+			let max_tickets = T::EpochDuration::get() as usize;
+
+			NextTickets::mutate(|next_tickets| {
+				for ticket in tickets {
+					if let Err(index) = next_tickets.binary_search(&ticket) {
+						next_tickets.insert(index, ticket);
+					}
+				}
+
+				next_tickets.truncate(max_tickets);
+			});
+		}
+	}
+}
+
+impl<T: Trait> FindAuthor<u32> for Module<T> {
+	fn find_author<'a, I>(digests: I) -> Option<u32> where
+		I: 'a + IntoIterator<Item=(ConsensusEngineId, &'a [u8])>
+	{
+		for (id, mut data) in digests.into_iter() {
+			if id == SASSAFRAS_ENGINE_ID {
+				let pre_digest = PreDigest::decode(&mut data).ok()?;
+				return Some(pre_digest.authority_index());
+			}
+		}
+
+		return None;
+	}
+}
+
+impl<T: Trait> IsMember<AuthorityId> for Module<T> {
+	fn is_member(authority_id: &AuthorityId) -> bool {
+		<Module<T>>::authorities()
+			.iter()
+			.any(|id| id == authority_id)
+	}
+}
+
+impl<T: Trait> session::ShouldEndSession<T::BlockNumber> for Module<T> {
+	fn should_end_session(now: T::BlockNumber) -> bool {
+		// the session module calls `should_end_session` from its own
+		// `on_initialize`, which runs before ours. the epoch change is enacted
+		// during initialization, so we only need to check whether it happened
+		// in this block.
+		let epoch_index = EpochIndex::get();
+		Self::do_initialize(now);
+
+		EpochIndex::get() != epoch_index
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Determine the Sassafras slot duration based on the Timestamp module configuration.
+	pub fn slot_duration() -> T::Moment {
+		// we double the minimum block-period so each author can always propose within
+		// the majority of their slot.
+		<T as timestamp::Trait>::MinimumPeriod::get().saturating_mul(2.into())
+	}
+
+	/// Return the Sassafras configuration.
+	pub fn configuration() -> SassafrasConfiguration {
+		SassafrasConfiguration {
+			slot_duration: Self::slot_duration().saturated_into::<u64>(),
+			epoch_duration: T::EpochDuration::get(),
+			attempts_number: T::AttemptsNumber::get(),
+			redundancy_factor: T::RedundancyFactor::get(),
+		}
+	}
+
+	/// Returns the epoch a block authored at the given slot on top of the
+	/// current block would belong to. Epochs which would start at or after
+	/// the next epoch use the next epoch authorities and randomness, since no
+	/// block can change them in between.
+	pub fn slot_epoch(slot_number: SlotNumber) -> Epoch {
+		let epoch_index = Self::slot_epoch_index(slot_number);
+
+		let (authorities, randomness) = if epoch_index > EpochIndex::get() {
+			(NextAuthorities::get(), NextRandomness::get())
+		} else {
+			(Authorities::get(), Randomness::get())
+		};
+
+		Epoch {
+			epoch_index,
+			start_slot: Self::epoch_start(epoch_index, slot_number),
+			duration: T::EpochDuration::get(),
+			authorities,
+			randomness,
+		}
+	}
+
+	/// Returns the epoch for which tickets are currently being collected.
+	pub fn next_epoch() -> Epoch {
+		let epoch_index = EpochIndex::get().saturating_add(1);
+
+		Epoch {
+			epoch_index,
+			start_slot: Self::epoch_start(epoch_index, CurrentSlot::get()),
+			duration: T::EpochDuration::get(),
+			authorities: NextAuthorities::get(),
+			randomness: NextRandomness::get(),
+		}
+	}
+
+	/// Returns the ticket assigned to the given slot, if any, for a block
+	/// authored on top of the current block.
+	pub fn slot_ticket(slot_number: SlotNumber) -> Option<Ticket> {
+		let epoch_index = Self::slot_epoch_index(slot_number);
+		let current_epoch_index = EpochIndex::get();
+
+		let tickets = if epoch_index == current_epoch_index {
+			Tickets::get()
+		} else if epoch_index == current_epoch_index.saturating_add(1) {
+			NextTickets::get()
+		} else {
+			// tickets of skipped epochs are discarded.
+			return None;
+		};
+
+		let offset = slot_number.saturating_sub(Self::epoch_start(epoch_index, slot_number));
+		tickets.get(offset as usize).cloned()
+	}
+
+	/// Submits an extrinsic with the given ticket proofs of a next epoch
+	/// authority. This method will create an unsigned extrinsic with a call
+	/// to `submit_tickets` and will push the transaction to the pool. Only
+	/// useful in an offchain context.
+	pub fn submit_tickets_unsigned(
+		authority_index: AuthorityIndex,
+		tickets: Vec<TicketProof>,
+	) -> bool {
+		let call = Call::submit_tickets(authority_index, tickets);
+		T::SubmitTransaction::submit_unsigned(call).is_ok()
+	}
+
+	/// Verifies the given ticket proofs of a next epoch authority, returning
+	/// the tickets if all of them are valid winning tickets of the next epoch.
+	fn check_tickets(
+		authority_index: AuthorityIndex,
+		tickets: &[TicketProof],
+	) -> result::Result<Vec<Ticket>, InvalidTransaction> {
+		let attempts = T::AttemptsNumber::get();
+		if tickets.is_empty() || tickets.len() > attempts as usize {
+			return Err(InvalidTransaction::Custom(0));
+		}
+
+		let authorities = NextAuthorities::get();
+		let author = authorities.get(authority_index as usize)
+			.ok_or(InvalidTransaction::BadProof)?;
+
+		let threshold = sassafras_primitives::ticket_threshold(
+			T::RedundancyFactor::get(),
+			T::EpochDuration::get(),
+			attempts,
+			authorities.len() as u32,
+		);
+
+		let epoch_index = EpochIndex::get().saturating_add(1);
+		let randomness = NextRandomness::get();
+
+		tickets.iter().map(|proof| {
+			if proof.attempt >= attempts {
+				return Err(InvalidTransaction::BadProof);
+			}
+
+			let ticket = sassafras_primitives::verify_ticket(
+				author,
+				&randomness,
+				epoch_index,
+				proof.attempt,
+				&proof.vrf_output,
+				&proof.vrf_proof,
+			).ok_or(InvalidTransaction::BadProof)?;
+
+			if sassafras_primitives::ticket_value(&ticket) >= threshold {
+				return Err(InvalidTransaction::BadProof);
+			}
+
+			Ok(ticket)
+		}).collect()
+	}
+
+	/// DANGEROUS: Enact an epoch change. The next epoch authorities,
+	/// randomness and tickets become current, unless some epochs were
+	/// skipped in which case the tickets are discarded.
+	fn enact_epoch_change(epoch_index: u64) {
+		let previous_epoch_index = EpochIndex::get();
+		EpochIndex::put(epoch_index);
+
+		Authorities::put(NextAuthorities::get());
+
+		if epoch_index == previous_epoch_index.saturating_add(1) {
+			Tickets::put(NextTickets::take());
+		} else {
+			Tickets::kill();
+			NextTickets::kill();
+		}
+
+		let randomness = NextRandomness::get();
+		let next_randomness = compute_randomness(
+			randomness,
+			epoch_index,
+			RandomnessAccumulator::take(),
+		);
+
+		Randomness::put(randomness);
+		NextRandomness::put(next_randomness);
+	}
+
+	// returns the index of the epoch the given slot belongs to. before the
+	// first block of the chain, the slot is assumed to start epoch 0.
+	fn slot_epoch_index(slot_number: SlotNumber) -> u64 {
+		let genesis_slot = GenesisSlot::get();
+		if genesis_slot == 0 {
+			return 0;
+		}
+
+		slot_number.saturating_sub(genesis_slot) / T::EpochDuration::get()
+	}
+
+	// returns the start slot of the given epoch, falling back to the given
+	// slot before the first block of the chain.
+	fn epoch_start(epoch_index: u64, slot_number: SlotNumber) -> SlotNumber {
+		let genesis_slot = GenesisSlot::get();
+		if genesis_slot == 0 {
+			return slot_number;
+		}
+
+		epoch_index.saturating_mul(T::EpochDuration::get()).saturating_add(genesis_slot)
+	}
+
+	fn deposit_vrf_output(vrf_output: &[u8; VRF_OUTPUT_LENGTH]) {
+		RandomnessAccumulator::mutate(|accumulator| {
+			let mut s = Vec::with_capacity(RANDOMNESS_LENGTH + VRF_OUTPUT_LENGTH);
+			s.extend_from_slice(&accumulator[..]);
+			s.extend_from_slice(&vrf_output[..]);
+
+			*accumulator = sp_io::hashing::blake2_256(&s);
+		});
+	}
+
+	fn do_initialize(_now: T::BlockNumber) {
+		// since do_initialize can be called twice (if session module is present)
+		// => let's ensure that we only modify the storage once per block
+		let initialized = Self::initialized().is_some();
+		if initialized {
+			return;
+		}
+
+		let maybe_pre_digest = <system::Module<T>>::digest()
+			.logs
+			.iter()
+			.filter_map(|s| s.as_pre_runtime())
+			.filter_map(|(id, mut data)| if id == SASSAFRAS_ENGINE_ID {
+				PreDigest::decode(&mut data).ok()
+			} else {
+				None
+			})
+			.next();
+
+		let maybe_vrf = maybe_pre_digest.and_then(|digest| {
+			// on the first non-zero block (i.e. block #1)
+			// this is where the first epoch (epoch #0) actually starts.
+			if GenesisSlot::get() == 0 {
+				GenesisSlot::put(digest.slot_number());
+				debug_assert_ne!(GenesisSlot::get(), 0);
+			}
+
+			CurrentSlot::put(digest.slot_number());
+
+			let epoch_index = Self::slot_epoch_index(digest.slot_number());
+			if epoch_index > EpochIndex::get() {
+				Self::enact_epoch_change(epoch_index);
+			}
+
+			if let PreDigest::Primary { ticket_vrf_output, .. } = digest {
+				// the ticket VRF output is only revealed when the slot is
+				// claimed, so it can be used as a source of randomness.
+				Some(ticket_vrf_output)
+			} else {
+				None
+			}
+		});
+
+		Initialized::put(maybe_vrf);
+	}
+
+	fn initialize_authorities(authorities: &[AuthorityId]) {
+		if !authorities.is_empty() {
+			assert!(Authorities::get().is_empty(), "Authorities are already initialized!");
+			Authorities::put(authorities);
+			NextAuthorities::put(authorities);
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl<T: Trait> support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		if let Call::submit_tickets(authority_index, tickets) = call {
+			let tickets = Self::check_tickets(*authority_index, tickets)?;
+
+			Ok(ValidTransaction {
+				priority: TransactionPriority::max_value(),
+				requires: vec![],
+				provides: vec![(EpochIndex::get(), authority_index, tickets).encode()],
+				longevity: T::EpochDuration::get(),
+				propagate: true,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+}
+
+impl<T: Trait> OnTimestampSet<T::Moment> for Module<T> {
+	fn on_timestamp_set(_moment: T::Moment) { }
+}
+
+impl<T: Trait> sp_runtime::BoundToRuntimeAppPublic for Module<T> {
+	type Public = AuthorityId;
+}
+
+impl<T: Trait> session::OneSessionHandler<T::AccountId> for Module<T> {
+	type Key = AuthorityId;
+
+	fn on_genesis_session<'a, I: 'a>(validators: I)
+		where I: Iterator<Item=(&'a T::AccountId, AuthorityId)>
+	{
+		let authorities = validators.map(|(_, k)| k).collect::<Vec<_>>();
+		Self::initialize_authorities(&authorities);
+	}
+
+	fn on_new_session<'a, I: 'a>(_changed: bool, _validators: I, queued_validators: I)
+		where I: Iterator<Item=(&'a T::AccountId, AuthorityId)>
+	{
+		// the current authorities have already been rotated when enacting
+		// the epoch change, tickets are now collected from the queued ones.
+		let next_authorities = queued_validators.map(|(_account, k)| k).collect::<Vec<_>>();
+		NextAuthorities::put(next_authorities);
+	}
+
+	fn on_disabled(_i: usize) { }
+}
+
+// compute randomness for a new epoch from the randomness of the epoch it
+// replaces and the accumulated VRF outputs of the prior epoch.
+fn compute_randomness(
+	last_epoch_randomness: [u8; RANDOMNESS_LENGTH],
+	epoch_index: u64,
+	accumulator: [u8; RANDOMNESS_LENGTH],
+) -> [u8; RANDOMNESS_LENGTH] {
+	let mut s = Vec::with_capacity(2 * RANDOMNESS_LENGTH + 8);
+	s.extend_from_slice(&last_epoch_randomness);
+	s.extend_from_slice(&epoch_index.to_le_bytes());
+	s.extend_from_slice(&accumulator);
+
+	sp_io::hashing::blake2_256(&s)
+}
+
+impl<T: Trait> ProvideInherent for Module<T> {
+	type Call = timestamp::Call<T>;
+	type Error = MakeFatalError<inherents::Error>;
+	const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+	fn create_inherent(_: &InherentData) -> Option<Self::Call> {
+		None
+	}
+
+	fn check_inherent(call: &Self::Call, data: &InherentData) -> result::Result<(), Self::Error> {
+		let timestamp = match call {
+			timestamp::Call::set(ref timestamp) => timestamp.clone(),
+			_ => return Ok(()),
+		};
+
+		let timestamp_based_slot = (timestamp / Self::slot_duration()).saturated_into::<u64>();
+		let seal_slot = data.sassafras_inherent_data()?;
+
+		if timestamp_based_slot == seal_slot {
+			Ok(())
+		} else {
+			Err(inherents::Error::from("timestamp set in block doesn't match slot in seal").into())
+		}
+	}
+}
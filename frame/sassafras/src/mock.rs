@@ -0,0 +1,121 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Test utilities
+#![allow(dead_code, unused_imports)]
+
+use std::cell::RefCell;
+use super::{Trait, Module, GenesisConfig, AuthorityId};
+use sp_runtime::{
+	traits::IdentityLookup, Perbill, testing::{Header, TestXt},
+};
+use sp_version::RuntimeVersion;
+use support::{
+	impl_outer_origin, impl_outer_dispatch, parameter_types, weights::Weight, traits::Get,
+};
+use sp_io;
+use primitives::{H256, Pair, sr25519};
+
+impl_outer_origin!{
+	pub enum Origin for Test {}
+}
+
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		sassafras::Sassafras,
+	}
+}
+
+type DummyValidatorId = u64;
+
+thread_local! {
+	pub(crate) static REDUNDANCY_FACTOR: RefCell<u32> = RefCell::new(4);
+}
+
+pub struct RedundancyFactor;
+impl Get<u32> for RedundancyFactor {
+	fn get() -> u32 { REDUNDANCY_FACTOR.with(|v| *v.borrow()) }
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+	pub const MinimumPeriod: u64 = 1;
+	pub const EpochDuration: u64 = 3;
+	pub const AttemptsNumber: u32 = 2;
+	pub const Version: RuntimeVersion = test_runtime::VERSION;
+}
+
+impl system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Call = Call;
+	type Hash = H256;
+	type Version = Version;
+	type Hashing = sp_runtime::traits::BlakeTwo256;
+	type AccountId = DummyValidatorId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type MaximumBlockLength = MaximumBlockLength;
+}
+
+impl timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = Sassafras;
+	type MinimumPeriod = MinimumPeriod;
+}
+
+/// An extrinsic type used for tests.
+pub type Extrinsic = TestXt<Call, ()>;
+type SubmitTransaction = system::offchain::TransactionSubmitter<(), Call, Extrinsic>;
+
+impl Trait for Test {
+	type EpochDuration = EpochDuration;
+	type AttemptsNumber = AttemptsNumber;
+	type RedundancyFactor = RedundancyFactor;
+	type Call = Call;
+	type SubmitTransaction = SubmitTransaction;
+}
+
+/// The key pair of the given authority, used to generate ticket proofs.
+pub fn authority_pair(authority: DummyValidatorId) -> sr25519::Pair {
+	sr25519::Pair::from_seed(&[authority as u8; 32])
+}
+
+pub fn new_test_ext(authorities: Vec<DummyValidatorId>) -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	GenesisConfig {
+		authorities: authorities.into_iter()
+			.map(|a| AuthorityId::from(authority_pair(a).public()))
+			.collect(),
+	}.assimilate_storage::<Test>(&mut t).unwrap();
+	t.into()
+}
+
+pub type System = system::Module<Test>;
+pub type Sassafras = Module<Test>;
@@ -0,0 +1,244 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Consensus extension module tests for Sassafras consensus.
+
+use super::*;
+use mock::{new_test_ext, authority_pair, Sassafras, System, REDUNDANCY_FACTOR};
+use sp_runtime::{traits::{OnInitialize, OnFinalize}, testing::{Digest, DigestItem}};
+use session::ShouldEndSession;
+use support::unsigned::ValidateUnsigned;
+
+fn make_secondary_pre_digest(
+	authority_index: sassafras_primitives::AuthorityIndex,
+	slot_number: SlotNumber,
+) -> Digest {
+	let digest_data = PreDigest::Secondary {
+		authority_index,
+		slot_number,
+	};
+	let log = DigestItem::PreRuntime(SASSAFRAS_ENGINE_ID, digest_data.encode());
+	Digest { logs: vec![log] }
+}
+
+// generates the proof of the given ticket attempt of an authority for the
+// epoch tickets are currently collected for.
+fn ticket_proof(authority: u64, attempt: u32) -> (TicketProof, Ticket) {
+	let epoch = Sassafras::next_epoch();
+	let pair = authority_pair(authority);
+	let keypair: &schnorrkel::Keypair = pair.as_ref();
+
+	let transcript = sassafras_primitives::make_ticket_transcript(
+		&epoch.randomness,
+		attempt,
+		epoch.epoch_index,
+	);
+	let (inout, proof, _) = keypair.vrf_sign(transcript);
+
+	let ticket_proof = TicketProof {
+		attempt,
+		vrf_output: inout.to_output().to_bytes(),
+		vrf_proof: proof.to_bytes(),
+	};
+
+	(ticket_proof, inout.make_bytes(sassafras_primitives::SASSAFRAS_TICKET_VRF_PREFIX))
+}
+
+fn submit_tickets(authority: u64) -> Vec<Ticket> {
+	let (proofs, tickets): (Vec<_>, Vec<_>) = (0..2).map(|a| ticket_proof(authority, a)).unzip();
+	assert!(Sassafras::submit_tickets(
+		system::RawOrigin::None.into(),
+		authority as AuthorityIndex,
+		proofs,
+	).is_ok());
+
+	tickets
+}
+
+fn sorted(mut tickets: Vec<Ticket>) -> Vec<Ticket> {
+	tickets.sort();
+	tickets
+}
+
+fn progress_to_block(number: u64, slot_number: SlotNumber) {
+	let pre_digest = make_secondary_pre_digest(0, slot_number);
+	System::initialize(&number, &Default::default(), &Default::default(), &pre_digest);
+	Sassafras::on_initialize(number);
+	Sassafras::on_finalize(number);
+	System::finalize();
+}
+
+#[test]
+fn initial_values() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		assert_eq!(Sassafras::authorities().len(), 4);
+		assert_eq!(Sassafras::authorities(), Sassafras::next_authorities());
+	})
+}
+
+#[test]
+fn first_block_epoch_zero_start() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		let genesis_slot = 100;
+
+		assert_eq!(Sassafras::genesis_slot(), 0);
+		let pre_digest = make_secondary_pre_digest(0, genesis_slot);
+		System::initialize(&1, &Default::default(), &Default::default(), &pre_digest);
+
+		assert!(!Sassafras::should_end_session(1));
+		assert_eq!(Sassafras::genesis_slot(), genesis_slot);
+		assert_eq!(Sassafras::current_slot(), genesis_slot);
+		assert_eq!(Sassafras::epoch_index(), 0);
+		assert_eq!(Sassafras::slot_epoch(genesis_slot + 2).epoch_index, 0);
+		assert_eq!(Sassafras::slot_epoch(genesis_slot + 3).epoch_index, 1);
+		assert_eq!(Sassafras::next_epoch().start_slot, genesis_slot + 3);
+	})
+}
+
+#[test]
+fn submitted_tickets_are_sorted_and_truncated() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		let mut tickets = submit_tickets(0);
+		assert_eq!(Sassafras::next_tickets(), sorted(tickets.clone()));
+
+		// resubmitting the same tickets doesn't insert them twice.
+		submit_tickets(0);
+		assert_eq!(Sassafras::next_tickets(), sorted(tickets.clone()));
+
+		tickets.extend(submit_tickets(1));
+		let mut expected = sorted(tickets);
+		expected.truncate(3);
+		assert_eq!(Sassafras::next_tickets(), expected);
+	})
+}
+
+#[test]
+fn forged_tickets_are_rejected() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		let (proof, _) = ticket_proof(0, 0);
+		assert!(Sassafras::validate_unsigned(&Call::submit_tickets(0, vec![proof])).is_ok());
+
+		// empty submissions and submissions with more than `AttemptsNumber`
+		// tickets are invalid.
+		assert!(Sassafras::validate_unsigned(&Call::submit_tickets(0, vec![])).is_err());
+		assert!(Sassafras::validate_unsigned(
+			&Call::submit_tickets(0, vec![proof, proof, proof])
+		).is_err());
+
+		// the proof is bound to the authority that generated it.
+		assert!(Sassafras::validate_unsigned(&Call::submit_tickets(1, vec![proof])).is_err());
+		assert!(Sassafras::validate_unsigned(&Call::submit_tickets(4, vec![proof])).is_err());
+		assert!(
+			Sassafras::submit_tickets(system::RawOrigin::None.into(), 1, vec![proof]).is_err()
+		);
+
+		// and to the attempt number.
+		let mut forged = proof;
+		forged.attempt = 1;
+		assert!(Sassafras::validate_unsigned(&Call::submit_tickets(0, vec![forged])).is_err());
+
+		let mut forged = proof;
+		forged.vrf_output = [1; 32];
+		assert!(Sassafras::validate_unsigned(&Call::submit_tickets(0, vec![forged])).is_err());
+
+		// attempts beyond `AttemptsNumber` are rejected even with a valid proof.
+		let (proof, _) = ticket_proof(0, 2);
+		assert!(Sassafras::validate_unsigned(&Call::submit_tickets(0, vec![proof])).is_err());
+
+		assert!(Sassafras::next_tickets().is_empty());
+	})
+}
+
+#[test]
+fn tickets_above_threshold_are_rejected() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		REDUNDANCY_FACTOR.with(|v| *v.borrow_mut() = 1);
+
+		let threshold = sassafras_primitives::ticket_threshold(1, 3, 2, 4);
+		for authority in 0..4 {
+			for attempt in 0..2 {
+				let (proof, ticket) = ticket_proof(authority, attempt);
+				let winning = sassafras_primitives::ticket_value(&ticket) < threshold;
+
+				assert_eq!(
+					Sassafras::validate_unsigned(
+						&Call::submit_tickets(authority as AuthorityIndex, vec![proof])
+					).is_ok(),
+					winning,
+				);
+			}
+		}
+
+		REDUNDANCY_FACTOR.with(|v| *v.borrow_mut() = 4);
+	})
+}
+
+#[test]
+fn tickets_are_assigned_to_slots_of_next_epoch() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		progress_to_block(1, 100);
+
+		let tickets = sorted(submit_tickets(0));
+
+		// tickets of the next epoch are already visible when building on top
+		// of the last block of the current epoch.
+		assert_eq!(Sassafras::slot_ticket(100), None);
+		assert_eq!(Sassafras::slot_ticket(103), Some(tickets[0]));
+		assert_eq!(Sassafras::slot_ticket(104), Some(tickets[1]));
+		assert_eq!(Sassafras::slot_ticket(105), None);
+
+		let next_randomness = Sassafras::next_randomness();
+		progress_to_block(2, 103);
+
+		assert_eq!(Sassafras::epoch_index(), 1);
+		assert_eq!(Sassafras::randomness(), next_randomness);
+		assert_eq!(Sassafras::tickets(), tickets);
+		assert!(Sassafras::next_tickets().is_empty());
+		assert_eq!(Sassafras::slot_ticket(104), Some(tickets[1]));
+	})
+}
+
+#[test]
+fn tickets_are_discarded_when_epochs_are_skipped() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		progress_to_block(1, 100);
+
+		submit_tickets(0);
+		assert_eq!(Sassafras::slot_ticket(106), None);
+
+		progress_to_block(2, 106);
+
+		assert_eq!(Sassafras::epoch_index(), 2);
+		assert!(Sassafras::tickets().is_empty());
+		assert!(Sassafras::next_tickets().is_empty());
+	})
+}
+
+#[test]
+fn authority_index() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		assert_eq!(
+			Sassafras::find_author((&[(SASSAFRAS_ENGINE_ID, &[][..])]).into_iter().cloned()), None,
+			"Trivially invalid authorities are ignored");
+
+		let pre_digest = PreDigest::Secondary { authority_index: 2, slot_number: 100 }.encode();
+		assert_eq!(
+			Sassafras::find_author((&[(SASSAFRAS_ENGINE_ID, &pre_digest[..])]).into_iter().cloned()),
+			Some(2),
+		);
+	})
+}
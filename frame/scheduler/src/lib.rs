@@ -0,0 +1,588 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Scheduler Module
+//!
+//! - [`scheduler::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The scheduler dispatches calls at the beginning of a given future block, optionally repeating
+//! them every given number of blocks for a given number of times. Each scheduled task carries the
+//! origin it is dispatched with and a priority: at every block, the due tasks are dispatched from
+//! the highest priority to the lowest until `MaximumWeight` is used up; the remaining tasks are
+//! postponed to the next block. Tasks with a priority of `HARD_DEADLINE` or higher are always
+//! dispatched on the block they ask for.
+//!
+//! Tasks may be named, in which case they can be cancelled by their name rather than by their
+//! address in the agenda.
+//!
+//! Other modules schedule tasks through the [`Anon`] and [`Named`] traits, with any system
+//! origin; the dispatchable functions are root-only and dispatch the tasks as root.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `schedule` - Schedule a call, dispatched as root.
+//! * `cancel` - Cancel a scheduled task by its address.
+//! * `schedule_named` - Schedule a named call, dispatched as root.
+//! * `cancel_named` - Cancel a scheduled task by its name.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+//! [`Anon`]: ../frame_support/traits/schedule/trait.Anon.html
+//! [`Named`]: ../frame_support/traits/schedule/trait.Named.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::{RuntimeDebug, DispatchError, traits::{Zero, One, Dispatchable}};
+use support::{
+	decl_module, decl_event, decl_storage, ensure, Parameter,
+	traits::{Get, schedule::{self, Period, Priority, HARD_DEADLINE}},
+	weights::{GetDispatchInfo, SimpleDispatchInfo, Weight},
+};
+use system::ensure_root;
+
+/// Our module's configuration trait. All our types and constants go in here. If the
+/// module is dependent on specific other modules, then their configuration traits
+/// should be added to our implied traits list.
+///
+/// `system::Trait` should always be included in our implied traits.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The aggregated call type.
+	type Call: Parameter + Dispatchable<Origin=<Self as system::Trait>::Origin> + GetDispatchInfo;
+
+	/// The maximum weight that may be scheduled per block for any dispatchables of less priority
+	/// than `HARD_DEADLINE`.
+	type MaximumWeight: Get<Weight>;
+}
+
+/// The location of a scheduled task that can be used to remove it.
+pub type TaskAddress<BlockNumber> = (BlockNumber, u32);
+
+/// Information regarding an item to be executed in the future.
+#[derive(Clone, RuntimeDebug, Encode, Decode)]
+pub struct Scheduled<Call, BlockNumber, AccountId> {
+	/// The unique identity for this task, if there is one.
+	pub maybe_id: Option<Vec<u8>>,
+	/// This task's priority.
+	pub priority: Priority,
+	/// The call to be dispatched.
+	pub call: Call,
+	/// If the call is periodic, then this points to the information concerning that.
+	pub maybe_periodic: Option<Period<BlockNumber>>,
+	/// The origin to dispatch the call with.
+	pub origin: system::RawOrigin<AccountId>,
+}
+
+type ScheduledOf<T> = Scheduled<
+	<T as Trait>::Call,
+	<T as system::Trait>::BlockNumber,
+	<T as system::Trait>::AccountId,
+>;
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Scheduler {
+		/// Items to be executed, indexed by the block number that they should be executed on.
+		pub Agenda get(fn agenda): map T::BlockNumber => Vec<Option<ScheduledOf<T>>>;
+
+		/// Lookup from identity to the block number and index of the task.
+		Lookup: map Vec<u8> => Option<TaskAddress<T::BlockNumber>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where BlockNumber = <T as system::Trait>::BlockNumber {
+		/// A task was scheduled at the given address.
+		Scheduled(BlockNumber, u32),
+		/// The task at the given address was cancelled.
+		Canceled(BlockNumber, u32),
+		/// The task at the given address, with its name if any, was dispatched.
+		Dispatched(TaskAddress<BlockNumber>, Option<Vec<u8>>, Result<(), DispatchError>),
+	}
+);
+
+decl_module! {
+	/// Scheduler module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The maximum weight of the tasks of less priority than `HARD_DEADLINE` dispatched per
+		/// block.
+		const MaximumWeight: Weight = T::MaximumWeight::get();
+
+		fn deposit_event() = default;
+
+		/// Anonymously schedule a task, dispatched as root.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn schedule(origin,
+			when: T::BlockNumber,
+			maybe_periodic: Option<Period<T::BlockNumber>>,
+			priority: Priority,
+			call: Box<<T as Trait>::Call>,
+		) {
+			ensure_root(origin)?;
+			Self::do_schedule(None, when, maybe_periodic, priority, system::RawOrigin::Root, *call)?;
+		}
+
+		/// Cancel an anonymously scheduled task.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn cancel(origin, when: T::BlockNumber, index: u32) {
+			ensure_root(origin)?;
+			Self::do_cancel((when, index))?;
+		}
+
+		/// Schedule a named task, dispatched as root.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn schedule_named(origin,
+			id: Vec<u8>,
+			when: T::BlockNumber,
+			maybe_periodic: Option<Period<T::BlockNumber>>,
+			priority: Priority,
+			call: Box<<T as Trait>::Call>,
+		) {
+			ensure_root(origin)?;
+			Self::do_schedule(Some(id), when, maybe_periodic, priority, system::RawOrigin::Root, *call)?;
+		}
+
+		/// Cancel a named scheduled task.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn cancel_named(origin, id: Vec<u8>) {
+			ensure_root(origin)?;
+			Self::do_cancel_named(id)?;
+		}
+
+		fn on_initialize(now: T::BlockNumber) {
+			let limit = T::MaximumWeight::get();
+			let mut queued = Agenda::<T>::take(now).into_iter()
+				.enumerate()
+				.filter_map(|(index, s)| s.map(|inner| (index as u32, inner)))
+				.collect::<Vec<_>>();
+			queued.sort_by_key(|(_, s)| s.priority);
+
+			let mut cumulative_weight: Weight = 0;
+			let mut postponed = Vec::new();
+			for (order, (index, mut s)) in queued.into_iter().enumerate() {
+				let weight = s.call.get_dispatch_info().weight;
+				// the first task and those with a hard deadline are always dispatched, so that the
+				// agenda always makes progress.
+				if order > 0
+					&& s.priority > HARD_DEADLINE
+					&& cumulative_weight.saturating_add(weight) > limit
+				{
+					postponed.push(s);
+					continue;
+				}
+				cumulative_weight = cumulative_weight.saturating_add(weight);
+
+				let result = s.call.clone().dispatch(s.origin.clone().into());
+				let maybe_id = s.maybe_id.clone();
+
+				if let Some((period, count)) = s.maybe_periodic {
+					s.maybe_periodic = if count > 1 {
+						Some((period, count - 1))
+					} else {
+						None
+					};
+					Self::place(now + period, s);
+				} else if let Some(ref id) = s.maybe_id {
+					Lookup::<T>::remove(id);
+				}
+
+				Self::deposit_event(RawEvent::Dispatched(
+					(now, index),
+					maybe_id,
+					result.map_err(Into::into),
+				));
+			}
+
+			for s in postponed {
+				Self::place(now + One::one(), s);
+			}
+
+			<system::Module<T>>::register_extra_weight_unchecked(cumulative_weight);
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Append `s` to the agenda of block `when`, keeping the lookup of its name up to date, and
+	/// return its new address.
+	fn place(when: T::BlockNumber, s: ScheduledOf<T>) -> TaskAddress<T::BlockNumber> {
+		let maybe_id = s.maybe_id.clone();
+		let index = Agenda::<T>::mutate(when, |agenda| {
+			agenda.push(Some(s));
+			agenda.len() as u32 - 1
+		});
+		if let Some(id) = maybe_id {
+			Lookup::<T>::insert(id, (when, index));
+		}
+		(when, index)
+	}
+
+	fn do_schedule(
+		maybe_id: Option<Vec<u8>>,
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		origin: system::RawOrigin<T::AccountId>,
+		call: <T as Trait>::Call,
+	) -> Result<TaskAddress<T::BlockNumber>, &'static str> {
+		ensure!(when > <system::Module<T>>::block_number(), "target block must be in the future");
+		if let Some(ref id) = maybe_id {
+			ensure!(!Lookup::<T>::exists(id), "a task with this name is already scheduled");
+		}
+
+		// a period of zero or a single execution is no period at all; the first execution is
+		// counted in `count`.
+		let maybe_periodic = maybe_periodic
+			.filter(|p| p.1 > 1 && !p.0.is_zero())
+			.map(|(period, count)| (period, count - 1));
+
+		let address = Self::place(when, Scheduled { maybe_id, priority, call, maybe_periodic, origin });
+		Self::deposit_event(RawEvent::Scheduled(address.0, address.1));
+		Ok(address)
+	}
+
+	fn do_cancel((when, index): TaskAddress<T::BlockNumber>) -> Result<(), &'static str> {
+		let scheduled = Agenda::<T>::mutate(when, |agenda| {
+			agenda.get_mut(index as usize).and_then(Option::take)
+		});
+		let s = scheduled.ok_or("scheduled task not found")?;
+		if let Some(id) = s.maybe_id {
+			Lookup::<T>::remove(id);
+		}
+		Self::deposit_event(RawEvent::Canceled(when, index));
+		Ok(())
+	}
+
+	fn do_cancel_named(id: Vec<u8>) -> Result<(), &'static str> {
+		let address = Lookup::<T>::get(&id).ok_or("scheduled task not found")?;
+		Self::do_cancel(address)
+	}
+}
+
+impl<T: Trait> schedule::Anon<T::BlockNumber, <T as Trait>::Call, system::RawOrigin<T::AccountId>>
+	for Module<T>
+{
+	type Address = TaskAddress<T::BlockNumber>;
+
+	fn schedule(
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		origin: system::RawOrigin<T::AccountId>,
+		call: <T as Trait>::Call,
+	) -> Result<Self::Address, &'static str> {
+		Self::do_schedule(None, when, maybe_periodic, priority, origin, call)
+	}
+
+	fn cancel(address: Self::Address) -> Result<(), &'static str> {
+		Self::do_cancel(address)
+	}
+}
+
+impl<T: Trait> schedule::Named<T::BlockNumber, <T as Trait>::Call, system::RawOrigin<T::AccountId>>
+	for Module<T>
+{
+	type Address = TaskAddress<T::BlockNumber>;
+
+	fn schedule_named(
+		id: Vec<u8>,
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		origin: system::RawOrigin<T::AccountId>,
+		call: <T as Trait>::Call,
+	) -> Result<Self::Address, &'static str> {
+		Self::do_schedule(Some(id), when, maybe_periodic, priority, origin, call)
+	}
+
+	fn cancel_named(id: Vec<u8>) -> Result<(), &'static str> {
+		Self::do_cancel_named(id)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::cell::RefCell;
+	use support::{
+		assert_ok, assert_noop, impl_outer_origin, impl_outer_dispatch, parameter_types,
+		traits::schedule::{Anon, Named, LOWEST_PRIORITY},
+	};
+	use primitives::H256;
+	use sp_runtime::{Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup, OnInitialize}};
+
+	mod logger {
+		use super::*;
+
+		thread_local! {
+			static LOG: RefCell<Vec<u32>> = RefCell::new(Vec::new());
+		}
+		pub fn log() -> Vec<u32> {
+			LOG.with(|log| log.borrow().clone())
+		}
+
+		pub trait Trait: system::Trait {}
+
+		decl_module! {
+			pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+				#[weight = SimpleDispatchInfo::FixedNormal(100)]
+				fn log(origin, i: u32) {
+					ensure_root(origin)?;
+					LOG.with(|log| log.borrow_mut().push(i));
+				}
+
+				#[weight = SimpleDispatchInfo::FixedNormal(600)]
+				fn log_heavy(origin, i: u32) {
+					ensure_root(origin)?;
+					LOG.with(|log| log.borrow_mut().push(i));
+				}
+			}
+		}
+	}
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			logger::Logger,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 2_000;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+	}
+	impl logger::Trait for Test {}
+	parameter_types! {
+		pub const MaximumWeight: Weight = 1_000;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Call = Call;
+		type MaximumWeight = MaximumWeight;
+	}
+	type System = system::Module<Test>;
+	type Logger = logger::Module<Test>;
+	type Scheduler = Module<Test>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		t.into()
+	}
+
+	fn run_to_block(n: u64) {
+		while System::block_number() < n {
+			System::set_block_number(System::block_number() + 1);
+			Scheduler::on_initialize(System::block_number());
+		}
+	}
+
+	fn log_call(i: u32) -> Call {
+		Call::Logger(logger::Call::log(i))
+	}
+
+	fn heavy_call(i: u32) -> Call {
+		Call::Logger(logger::Call::log_heavy(i))
+	}
+
+	#[test]
+	fn basic_scheduling_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 127, Box::new(log_call(42))));
+			run_to_block(3);
+			assert!(logger::log().is_empty());
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42]);
+			run_to_block(100);
+			assert_eq!(logger::log(), vec![42]);
+		});
+	}
+
+	#[test]
+	fn scheduling_is_root_only_and_in_the_future() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				Scheduler::schedule(Origin::signed(1), 4, None, 127, Box::new(log_call(42))),
+				"RequireRootOrigin",
+			);
+			run_to_block(2);
+			assert_noop!(
+				Scheduler::schedule(Origin::ROOT, 2, None, 127, Box::new(log_call(42))),
+				"target block must be in the future",
+			);
+		});
+	}
+
+	#[test]
+	fn tasks_are_dispatched_with_their_origin() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(<Scheduler as Anon<_, _, _>>::schedule(
+				4, None, 127, system::RawOrigin::Signed(1), log_call(1),
+			));
+			assert_ok!(<Scheduler as Anon<_, _, _>>::schedule(
+				4, None, 127, system::RawOrigin::Root, log_call(2),
+			));
+			run_to_block(4);
+			// `log` is root only.
+			assert_eq!(logger::log(), vec![2]);
+		});
+	}
+
+	#[test]
+	fn periodic_scheduling_works() {
+		new_test_ext().execute_with(|| {
+			// at #4, every 3 blocks, 3 times.
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, Some((3, 3)), 127, Box::new(log_call(42))));
+			run_to_block(3);
+			assert!(logger::log().is_empty());
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42]);
+			run_to_block(6);
+			assert_eq!(logger::log(), vec![42]);
+			run_to_block(7);
+			assert_eq!(logger::log(), vec![42, 42]);
+			run_to_block(10);
+			assert_eq!(logger::log(), vec![42, 42, 42]);
+			run_to_block(100);
+			assert_eq!(logger::log(), vec![42, 42, 42]);
+		});
+	}
+
+	#[test]
+	fn cancel_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 127, Box::new(log_call(69))));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 127, Box::new(log_call(42))));
+			assert_ok!(Scheduler::cancel(Origin::ROOT, 4, 0));
+			assert_noop!(Scheduler::cancel(Origin::ROOT, 4, 0), "scheduled task not found");
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42]);
+		});
+	}
+
+	#[test]
+	fn named_scheduling_and_cancel_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule_named(
+				Origin::ROOT, b"payout".to_vec(), 4, Some((2, 10)), 127, Box::new(log_call(69)),
+			));
+			assert_noop!(
+				Scheduler::schedule_named(
+					Origin::ROOT, b"payout".to_vec(), 5, None, 127, Box::new(log_call(42)),
+				),
+				"a task with this name is already scheduled",
+			);
+			run_to_block(6);
+			assert_eq!(logger::log(), vec![69, 69]);
+
+			// the named task can be cancelled after it was rescheduled.
+			assert_ok!(<Scheduler as Named<_, _, _>>::cancel_named(b"payout".to_vec()));
+			run_to_block(100);
+			assert_eq!(logger::log(), vec![69, 69]);
+			assert_noop!(
+				Scheduler::cancel_named(Origin::ROOT, b"payout".to_vec()),
+				"scheduled task not found",
+			);
+		});
+	}
+
+	#[test]
+	fn named_one_off_task_is_forgotten_once_dispatched() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule_named(
+				Origin::ROOT, b"once".to_vec(), 4, None, 127, Box::new(log_call(69)),
+			));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![69]);
+			// the name can be reused.
+			assert_ok!(Scheduler::schedule_named(
+				Origin::ROOT, b"once".to_vec(), 5, None, 127, Box::new(log_call(42)),
+			));
+			run_to_block(5);
+			assert_eq!(logger::log(), vec![69, 42]);
+		});
+	}
+
+	#[test]
+	fn scheduler_respects_priority_ordering() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 1, Box::new(log_call(42))));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 0, Box::new(log_call(69))));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![69, 42]);
+		});
+	}
+
+	#[test]
+	fn scheduler_respects_weight_limits() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 127, Box::new(heavy_call(42))));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 127, Box::new(heavy_call(69))));
+			run_to_block(4);
+			// 69 is postponed since the two calls don't fit in a block.
+			assert_eq!(logger::log(), vec![42]);
+			run_to_block(5);
+			assert_eq!(logger::log(), vec![42, 69]);
+		});
+	}
+
+	#[test]
+	fn scheduler_always_dispatches_hard_deadlines() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 63, Box::new(heavy_call(42))));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 63, Box::new(heavy_call(69))));
+			assert_ok!(Scheduler::schedule(
+				Origin::ROOT, 4, None, LOWEST_PRIORITY, Box::new(log_call(2600)),
+			));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42, 69]);
+			run_to_block(5);
+			assert_eq!(logger::log(), vec![42, 69, 2600]);
+		});
+	}
+}
@@ -27,13 +27,13 @@
 
 use sp_std::prelude::*;
 use codec::{Encode, Decode};
-use sp_runtime::KeyTypeId;
+use sp_runtime::{KeyTypeId, RuntimeDebug};
 use sp_runtime::traits::{Convert, OpaqueKeys, Hash as HashT};
 use support::{decl_module, decl_storage};
 use support::{Parameter, print};
 use sp_trie::{MemoryDB, Trie, TrieMut, Recorder, EMPTY_PREFIX};
 use sp_trie::trie_types::{TrieDBMut, TrieDB};
-use super::{SessionIndex, Module as SessionModule};
+use super::{SessionIndex, Module as SessionModule, GetSessionNumber, GetValidatorCount};
 
 type ValidatorCount = u32;
 
@@ -267,10 +267,23 @@ impl<T: Trait> ProvingTrie<T> {
 }
 
 /// Proof of ownership of a specific key.
-#[derive(Encode, Decode, Clone)]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
 pub struct Proof {
 	session: SessionIndex,
 	trie_nodes: Vec<Vec<u8>>,
+	validator_count: ValidatorCount,
+}
+
+impl GetSessionNumber for Proof {
+	fn session(&self) -> SessionIndex {
+		self.session
+	}
+}
+
+impl GetValidatorCount for Proof {
+	fn validator_count(&self) -> ValidatorCount {
+		self.validator_count
+	}
 }
 
 impl<T: Trait, D: AsRef<[u8]>> support::traits::KeyOwnerProofSystem<(KeyTypeId, D)>
@@ -283,11 +296,14 @@ impl<T: Trait, D: AsRef<[u8]>> support::traits::KeyOwnerProofSystem<(KeyTypeId,
 		let session = <SessionModule<T>>::current_index();
 		let trie = ProvingTrie::<T>::generate_for(session).ok()?;
 
+		let validator_count = <SessionModule<T>>::validators().len() as ValidatorCount;
+
 		let (id, data) = key;
 
 		trie.prove(id, data.as_ref()).map(|trie_nodes| Proof {
 			session,
 			trie_nodes,
+			validator_count,
 		})
 	}
 
@@ -295,11 +311,21 @@ impl<T: Trait, D: AsRef<[u8]>> support::traits::KeyOwnerProofSystem<(KeyTypeId,
 		let (id, data) = key;
 
 		if proof.session == <SessionModule<T>>::current_index() {
+			// the validator count is used to compute slashes, so it must be the real one.
+			let count = <SessionModule<T>>::validators().len() as ValidatorCount;
+			if proof.validator_count != count {
+				return None;
+			}
+
 			<SessionModule<T>>::key_owner(id, data.as_ref()).and_then(|owner|
 				T::FullIdentificationOf::convert(owner.clone()).map(move |id| (owner, id))
 			)
 		} else {
-			let (root, _) = <HistoricalSessions<T>>::get(&proof.session)?;
+			let (root, count) = <HistoricalSessions<T>>::get(&proof.session)?;
+			if proof.validator_count != count {
+				return None;
+			}
+
 			let trie = ProvingTrie::<T>::from_nodes(root, &proof.trie_nodes);
 
 			trie.query(id, data.as_ref())
@@ -371,6 +397,37 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn proof_with_wrong_validator_count_is_rejected() {
+		new_test_ext().execute_with(|| {
+			set_next_validators(vec![1, 2]);
+			force_new_session();
+
+			System::set_block_number(1);
+			Session::on_initialize(1);
+
+			let encoded_key_1 = UintAuthorityId(1).encode();
+			let proof = Historical::prove((DUMMY, &encoded_key_1[..])).unwrap();
+			assert_eq!(proof.validator_count, 2);
+
+			let mut forged = proof.clone();
+			forged.validator_count = 1;
+
+			// rejected in the same session.
+			assert!(Historical::check_proof((DUMMY, &encoded_key_1[..]), forged.clone()).is_none());
+
+			set_next_validators(vec![1, 2, 4]);
+			force_new_session();
+
+			System::set_block_number(2);
+			Session::on_initialize(2);
+
+			// and against the historical root.
+			assert!(Historical::check_proof((DUMMY, &encoded_key_1[..]), proof.clone()).is_some());
+			assert!(Historical::check_proof((DUMMY, &encoded_key_1[..]), forged).is_none());
+		});
+	}
+
 	#[test]
 	fn prune_up_to_works() {
 		new_test_ext().execute_with(|| {
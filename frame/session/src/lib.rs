@@ -123,10 +123,12 @@ use sp_std::{prelude::*, marker::PhantomData, ops::{Sub, Rem}};
 use codec::Decode;
 use sp_runtime::{KeyTypeId, Perbill, RuntimeAppPublic, BoundToRuntimeAppPublic};
 use support::weights::SimpleDispatchInfo;
-use sp_runtime::traits::{Convert, Zero, Member, OpaqueKeys};
+use sp_runtime::traits::{Convert, Zero, Member, OpaqueKeys, SimpleArithmetic};
 use sp_staking::SessionIndex;
 use support::{dispatch, ConsensusEngineId, decl_module, decl_event, decl_storage};
-use support::{ensure, traits::{OnFreeBalanceZero, Get, FindAuthor, ValidatorRegistration}, Parameter};
+use support::{
+	ensure, traits::{OnFreeBalanceZero, Get, FindAuthor, ValidatorRegistration, VerifySeal}, Parameter,
+};
 use system::{self, ensure_signed};
 
 #[cfg(test)]
@@ -162,6 +164,34 @@ impl<
 	}
 }
 
+/// Estimates at which block the next session will start.
+pub trait EstimateNextNewSession<BlockNumber> {
+	/// Return the block number at which the next session is expected to start, if known.
+	fn estimate_next_new_session(now: BlockNumber) -> Option<BlockNumber>;
+}
+
+impl<
+	BlockNumber: SimpleArithmetic + Clone,
+	Period: Get<BlockNumber>,
+	Offset: Get<BlockNumber>,
+> EstimateNextNewSession<BlockNumber> for PeriodicSessions<Period, Offset> {
+	fn estimate_next_new_session(now: BlockNumber) -> Option<BlockNumber> {
+		let offset = Offset::get();
+		let period = Period::get();
+		Some(if now >= offset {
+			let blocks_since_last_session = (now.clone() - offset) % period.clone();
+			if blocks_since_last_session > Zero::zero() {
+				now.saturating_add(period.saturating_sub(blocks_since_last_session))
+			} else {
+				// the session rotates at `now`, assume it already did.
+				now.saturating_add(period)
+			}
+		} else {
+			offset
+		})
+	}
+}
+
 /// An event handler for when the session is ending.
 /// TODO [slashing] consider renaming to OnSessionStarting
 pub trait OnSessionEnding<ValidatorId> {
@@ -333,6 +363,32 @@ impl<V> SelectInitialValidators<V> for () {
 	}
 }
 
+/// Something that can tell at which session a given key ownership proof was
+/// generated.
+pub trait GetSessionNumber {
+	/// The session index the proof refers to.
+	fn session(&self) -> SessionIndex;
+}
+
+/// Something that can tell the size of the validator set at the session a
+/// given key ownership proof was generated.
+pub trait GetValidatorCount {
+	/// The number of validators in the session the proof refers to.
+	fn validator_count(&self) -> u32;
+}
+
+impl GetSessionNumber for support::Void {
+	fn session(&self) -> SessionIndex {
+		match *self {}
+	}
+}
+
+impl GetValidatorCount for support::Void {
+	fn validator_count(&self) -> u32 {
+		match *self {}
+	}
+}
+
 impl<T: Trait> ValidatorRegistration<T::ValidatorId> for Module<T> {
 	fn is_registered(id: &T::ValidatorId) -> bool {
 		Self::load_keys(id).is_some()
@@ -668,7 +724,8 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
-	fn load_keys(v: &T::ValidatorId) -> Option<T::Keys> {
+	/// The latest session keys registered by a validator, if any.
+	pub fn load_keys(v: &T::ValidatorId) -> Option<T::Keys> {
 		<NextKeys<T>>::get(DEDUP_KEY_PREFIX, v)
 	}
 
@@ -717,6 +774,28 @@ impl<T: Trait, Inner: FindAuthor<u32>> FindAuthor<T::ValidatorId>
 	}
 }
 
+/// Wraps the seal-verifying logic for consensus engines that recover the session key
+/// of an author, possibly of a past session. This then transforms it into the
+/// registering account-ID of that session key.
+pub struct FindAccountFromAuthorKey<T, Key, Inner>(PhantomData<(T, Key, Inner)>);
+
+impl<T, Header, Key, Inner> VerifySeal<Header, T::ValidatorId>
+	for FindAccountFromAuthorKey<T, Key, Inner>
+where
+	T: Trait,
+	Key: RuntimeAppPublic + AsRef<[u8]>,
+	Inner: VerifySeal<Header, Key>,
+{
+	fn verify_seal(header: &Header) -> Result<Option<T::ValidatorId>, &'static str> {
+		let key = match Inner::verify_seal(header)? {
+			Some(key) => key,
+			None => return Ok(None),
+		};
+
+		Ok(<Module<T>>::key_owner(Key::ID, key.as_ref()))
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -985,6 +1064,12 @@ mod tests {
 		}
 
 		assert!(P::should_end_session(13));
+
+		assert_eq!(P::estimate_next_new_session(0), Some(3));
+		assert_eq!(P::estimate_next_new_session(3), Some(13));
+		assert_eq!(P::estimate_next_new_session(4), Some(13));
+		assert_eq!(P::estimate_next_new_session(12), Some(13));
+		assert_eq!(P::estimate_next_new_session(13), Some(23));
 	}
 
 	#[test]
@@ -1017,4 +1102,26 @@ mod tests {
 			assert_eq!(Session::disable_index(3), true);
 		});
 	}
+
+	pub struct SealedByHeader;
+
+	impl VerifySeal<u64, UintAuthorityId> for SealedByHeader {
+		fn verify_seal(header: &u64) -> Result<Option<UintAuthorityId>, &'static str> {
+			Ok(Some(UintAuthorityId(*header)))
+		}
+	}
+
+	#[test]
+	fn seal_author_keys_are_mapped_to_their_owner() {
+		new_test_ext().execute_with(|| {
+			type FindAccount = FindAccountFromAuthorKey<Test, UintAuthorityId, SealedByHeader>;
+
+			assert_eq!(FindAccount::verify_seal(&2), Ok(Some(2)));
+
+			// nobody owns the key anymore once it's rotated.
+			assert_ok!(Session::set_keys(Origin::signed(2), UintAuthorityId(42).into(), vec![]));
+			assert_eq!(FindAccount::verify_seal(&2), Ok(None));
+			assert_eq!(FindAccount::verify_seal(&42), Ok(Some(2)));
+		});
+	}
 }
@@ -169,11 +169,11 @@
 //!
 //! ### Reward Calculation
 //!
-//! Validators and nominators are rewarded at the end of each era. The total reward of an era is
-//! calculated using the era duration and the staking rate (the total amount of tokens staked by
-//! nominators and validators, divided by the total token supply). It aims to incentivise toward a
-//! defined staking rate. The full specification can be found
-//! [here](https://research.web3.foundation/en/latest/polkadot/Token%20Economics.html#inflation-model).
+//! The total reward of an era is fixed at the end of that era and stored; validators and
+//! nominators then claim it lazily. It is calculated using the era duration and the staking rate
+//! (the total amount of tokens staked by nominators and validators, divided by the total token
+//! supply). It aims to incentivise toward a defined staking rate. The full specification can be
+//! found [here](https://research.web3.foundation/en/latest/polkadot/Token%20Economics.html#inflation-model).
 //!
 //! Total reward is split among validators and their nominators depending on the number of points
 //! they received during the era. Points are added to a validator using
@@ -184,6 +184,13 @@
 //! [`authorship::EventHandler`](../pallet_authorship/trait.EventHandler.html) to add reward points
 //! to block producer and block producer of referenced uncles.
 //!
+//! Rewards are not paid out automatically. Once an era has ended, anyone can call
+//! [`payout_stakers`](./enum.Call.html#variant.payout_stakers) to pay out a given validator and
+//! its nominators for that era. Only the `MaxNominatorRewardedPerValidator` biggest nominators of
+//! each validator are rewarded, which bounds the cost of a single payout. Rewards can be claimed
+//! for the last `HistoryDepth` eras; older eras are pruned together with their exposures and
+//! reward points.
+//!
 //! The validator and its nominator split their reward as following:
 //!
 //! The validator can declare an amount, named
@@ -233,6 +240,21 @@
 //! values until the total difference among votes of a particular nominator are less than a
 //! threshold.
 //!
+//! #### Off-chain election
+//!
+//! Running the election inside the runtime limits the number of nominators that can be
+//! supported. `ElectionLookahead` blocks before the era ends, a snapshot of the candidates and
+//! voters is stored and the election window opens. During this window, validators compute the
+//! election in their off-chain worker and submit it, along with its score, as an unsigned
+//! transaction signed with their session key; anyone can also submit a solution with a signed
+//! [`submit_election_solution`](./enum.Call.html#variant.submit_election_solution). The assignments
+//! are submitted in a [`CompactAssignments`](./offchain_election/struct.CompactAssignments.html)
+//! form which refers to stakers by their index in the snapshot.
+//!
+//! Every solution is checked for feasibility and its score is recomputed on-chain; only a solution
+//! which is better than the one already queued is kept. At the end of the era the queued solution
+//! is used, and the on-chain election remains the fallback if no solution was submitted.
+//!
 //! ## GenesisConfig
 //!
 //! The Staking module depends on the [`GenesisConfig`](./struct.GenesisConfig.html).
@@ -254,26 +276,33 @@ mod migration;
 mod slashing;
 
 pub mod inflation;
+pub mod offchain_election;
 
-use sp_std::{prelude::*, result};
+use sp_std::{prelude::*, result, collections::btree_map::BTreeMap};
 use codec::{HasCompact, Encode, Decode};
 use support::{
-	decl_module, decl_event, decl_storage, ensure,
-	weights::SimpleDispatchInfo,
+	decl_module, decl_event, decl_storage, ensure, debug, Parameter,
+	weights::{SimpleDispatchInfo, WeighData, ClassifyDispatch, DispatchClass, PaysFee, Weight},
 	traits::{
-		Currency, OnFreeBalanceZero, LockIdentifier, LockableCurrency,
+		Currency, OnFreeBalanceZero, LockIdentifier, LockableCurrency, ReservableCurrency,
 		WithdrawReasons, OnUnbalanced, Imbalance, Get, Time
 	}
 };
-use session::{historical::OnSessionEnding, SelectInitialValidators};
+use session::{historical::OnSessionEnding, EstimateNextNewSession, SelectInitialValidators};
 use sp_runtime::{
 	Perbill,
 	RuntimeDebug,
+	RuntimeAppPublic,
+	KeyTypeId,
 	curve::PiecewiseLinear,
 	traits::{
 		Convert, Zero, One, StaticLookup, CheckedSub, Saturating, Bounded, SaturatedConversion,
-		SimpleArithmetic, EnsureOrigin,
-	}
+		SimpleArithmetic, EnsureOrigin, Member, OpaqueKeys,
+	},
+	transaction_validity::{
+		TransactionValidity, ValidTransaction, InvalidTransaction, TransactionPriority,
+		TransactionLongevity,
+	},
 };
 use sp_staking::{
 	SessionIndex,
@@ -281,9 +310,13 @@ use sp_staking::{
 };
 #[cfg(feature = "std")]
 use sp_runtime::{Serialize, Deserialize};
-use system::{ensure_signed, ensure_root};
+use system::{ensure_signed, ensure_root, ensure_none, offchain::SubmitUnsignedTransaction};
 
-use phragmen::{ExtendedBalance, PhragmenStakedAssignment};
+use phragmen::{
+	ExtendedBalance, PhragmenStakedAssignment, PhragmenScore, SupportMap, build_support_map,
+	evaluate_support, is_score_better,
+};
+use offchain_election::CompactAssignments;
 
 const DEFAULT_MINIMUM_VALIDATOR_COUNT: u32 = 4;
 const MAX_NOMINATIONS: usize = 16;
@@ -296,24 +329,40 @@ pub type EraIndex = u32;
 /// Counter for the number of "reward" points earned by a given validator.
 pub type Points = u32;
 
-/// Reward points of an era. Used to split era total payout between validators.
+/// Index of a validator candidate in `SnapshotValidators`.
+pub type ValidatorIndex = u16;
+
+/// Index of a voter in `SnapshotNominators`.
+pub type NominatorIndex = u32;
+
+/// Reward points of an era, indexed by the elected validator set.
+///
+/// Deprecated: replaced by `EraRewardPoints`, only kept to migrate `CurrentEraPointsEarned`.
 #[derive(Encode, Decode, Default)]
 pub struct EraPoints {
 	/// Total number of points. Equals the sum of reward points for each validator.
-	total: Points,
+	pub total: Points,
 	/// The reward points earned by a given validator. The index of this vec corresponds to the
 	/// index into the current validator set.
-	individual: Vec<Points>,
+	pub individual: Vec<Points>,
+}
+
+/// Reward points of an era. Used to split era total payout between validators.
+#[derive(PartialEq, Encode, Decode, Default, RuntimeDebug)]
+pub struct EraRewardPoints<AccountId: Ord> {
+	/// Total number of points. Equals the sum of reward points for each validator.
+	pub total: Points,
+	/// The reward points earned by a given validator.
+	pub individual: BTreeMap<AccountId, Points>,
 }
 
-impl EraPoints {
-	/// Add the reward to the validator at the given index. Index must be valid
-	/// (i.e. `index < current_elected.len()`).
-	fn add_points_to_index(&mut self, index: u32, points: u32) {
+impl<AccountId: Ord> EraRewardPoints<AccountId> {
+	/// Add the reward to the given validator.
+	fn add_points(&mut self, validator: AccountId, points: Points) {
 		if let Some(new_total) = self.total.checked_add(points) {
 			self.total = new_total;
-			self.individual.resize((index as usize + 1).max(self.individual.len()), 0);
-			self.individual[index as usize] += points; // Addition is less than total
+			// Addition is less than total
+			*self.individual.entry(validator).or_default() += points;
 		}
 	}
 }
@@ -542,6 +591,10 @@ pub trait SessionInterface<AccountId>: system::Trait {
 	fn validators() -> Vec<AccountId>;
 	/// Prune historical session tries up to but not including the given index.
 	fn prune_historical_up_to(up_to: SessionIndex);
+	/// Get the session key of the given type registered by a validator.
+	fn validator_key<K: Decode>(validator: &AccountId, id: KeyTypeId) -> Option<K>;
+	/// Get the index of the current session.
+	fn current_index() -> SessionIndex;
 }
 
 impl<T: Trait> SessionInterface<<T as system::Trait>::AccountId> for T where
@@ -566,11 +619,23 @@ impl<T: Trait> SessionInterface<<T as system::Trait>::AccountId> for T where
 	fn prune_historical_up_to(up_to: SessionIndex) {
 		<session::historical::Module<T>>::prune_up_to(up_to);
 	}
+
+	fn validator_key<K: Decode>(
+		validator: &<T as system::Trait>::AccountId,
+		id: KeyTypeId,
+	) -> Option<K> {
+		<session::Module<T>>::load_keys(validator).and_then(|keys| keys.get(id))
+	}
+
+	fn current_index() -> SessionIndex {
+		<session::Module<T>>::current_index()
+	}
 }
 
 pub trait Trait: system::Trait {
-	/// The staking balance.
-	type Currency: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber>;
+	/// The staking balance, which also holds the deposits of signed election solutions.
+	type Currency: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber>
+		+ ReservableCurrency<Self::AccountId>;
 
 	/// Time used for computing era duration.
 	type Time: Time;
@@ -613,6 +678,40 @@ pub trait Trait: system::Trait {
 
 	/// The NPoS reward curve to use.
 	type RewardCurve: Get<&'static PiecewiseLinear<'static>>;
+
+	/// The maximum number of nominators rewarded for each validator.
+	///
+	/// For each validator only the `MaxNominatorRewardedPerValidator` biggest stakers can claim
+	/// their reward. This bounds the cost of `payout_stakers`.
+	type MaxNominatorRewardedPerValidator: Get<u32>;
+
+	/// Number of past eras for which exposures, reward points and rewards are kept, and hence
+	/// for which rewards can still be claimed.
+	type HistoryDepth: Get<EraIndex>;
+
+	/// A dispatchable call type, used to submit election solutions from the off-chain worker.
+	type Call: From<Call<Self>>;
+
+	/// A transaction submitter for the unsigned election solutions.
+	type SubmitTransaction: SubmitUnsignedTransaction<Self, <Self as Trait>::Call>;
+
+	/// The session key type used by validators to sign the election solutions they submit.
+	type KeyType: RuntimeAppPublic + Member + Parameter + Default;
+
+	/// Whether the election window is opened at the end of each era. If not, no solution can be
+	/// submitted and the election is always computed on-chain.
+	type OffchainElection: Get<bool>;
+
+	/// Something that can estimate at which block the next session starts, and hence when the
+	/// current era ends.
+	type NextNewSession: EstimateNextNewSession<Self::BlockNumber>;
+
+	/// The number of blocks before the end of the era during which the election window is open.
+	type ElectionLookahead: Get<Self::BlockNumber>;
+
+	/// The deposit reserved by a signed election solution. It is returned once the solution is
+	/// used or replaced by a better one, and slashed if the solution is invalid.
+	type ElectionSolutionDeposit: Get<BalanceOf<Self>>;
 }
 
 /// Mode of era-forcing.
@@ -633,6 +732,92 @@ impl Default for Forcing {
 	fn default() -> Self { Forcing::NotForcing }
 }
 
+/// Status of the election window.
+#[derive(Copy, Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub enum ElectionStatus {
+	/// No solution can be submitted.
+	Closed,
+	/// The snapshot has been taken and solutions for the next era can be submitted.
+	Open,
+}
+
+impl Default for ElectionStatus {
+	fn default() -> Self { ElectionStatus::Closed }
+}
+
+/// How an election result has been computed.
+#[derive(Copy, Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub enum ElectionCompute {
+	/// Computed on-chain, at the end of the era.
+	OnChain,
+	/// Submitted by a signed transaction.
+	Signed,
+	/// Submitted off-chain by a validator, as an unsigned transaction.
+	Unsigned,
+}
+
+/// The result of an election, ready to be enacted.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct ElectionResult<AccountId, Balance: HasCompact> {
+	/// The elected validators.
+	pub elected_stashes: Vec<AccountId>,
+	/// The exposure of each elected validator.
+	pub exposures: Vec<(AccountId, Exposure<AccountId, Balance>)>,
+	/// How the result has been computed.
+	pub compute: ElectionCompute,
+}
+
+/// The weight of an election solution submission: a fixed cost, plus the cost of checking each
+/// winner, voter and edge of the solution.
+pub struct WeightForSubmitSolution;
+
+impl WeightForSubmitSolution {
+	fn weigh(winners: &[ValidatorIndex], compact: &CompactAssignments) -> Weight {
+		let voters = compact.votes.len() as Weight;
+		let edges = compact.votes.iter()
+			.fold(0 as Weight, |edges, (_, targets)| edges.saturating_add(targets.len() as Weight));
+
+		// every voter needs its nominations and ledger to be read, every edge the slashing spans
+		// of its target.
+		(5_000_000 as Weight)
+			.saturating_add((winners.len() as Weight).saturating_mul(100_000))
+			.saturating_add(voters.saturating_mul(500_000))
+			.saturating_add(edges.saturating_mul(200_000))
+	}
+}
+
+impl WeighData<(&Vec<ValidatorIndex>, &CompactAssignments, &EraIndex, &PhragmenScore)>
+	for WeightForSubmitSolution
+{
+	fn weigh_data(
+		&self,
+		(winners, compact, _, _):
+			(&Vec<ValidatorIndex>, &CompactAssignments, &EraIndex, &PhragmenScore),
+	) -> Weight {
+		Self::weigh(winners, compact)
+	}
+}
+
+impl<S> WeighData<(&Vec<ValidatorIndex>, &CompactAssignments, &EraIndex, &PhragmenScore, &u32, &S)>
+	for WeightForSubmitSolution
+{
+	fn weigh_data(
+		&self,
+		(winners, compact, _, _, _, _):
+			(&Vec<ValidatorIndex>, &CompactAssignments, &EraIndex, &PhragmenScore, &u32, &S),
+	) -> Weight {
+		Self::weigh(winners, compact)
+	}
+}
+
+impl<T> ClassifyDispatch<T> for WeightForSubmitSolution {
+	fn classify_dispatch(&self, _: T) -> DispatchClass {
+		DispatchClass::Normal
+	}
+}
+
+impl PaysFee for WeightForSubmitSolution {}
+
 decl_storage! {
 	trait Store for Module<T: Trait> as Staking {
 
@@ -684,7 +869,36 @@ decl_storage! {
 		pub CurrentEraStartSessionIndex get(fn current_era_start_session_index): SessionIndex;
 
 		/// Rewards for the current era. Using indices of current elected set.
-		CurrentEraPointsEarned get(fn current_era_reward): EraPoints;
+		///
+		/// DEPRECATED: replaced by `ErasRewardPoints`, only read by the storage migration.
+		CurrentEraPointsEarned: EraPoints;
+
+		/// Clipped exposure of the elected validators at era.
+		///
+		/// This is similar to `Stakers` but only the `T::MaxNominatorRewardedPerValidator`
+		/// biggest nominators are kept, and `total` is recomputed accordingly. It is used to
+		/// pay out rewards.
+		///
+		/// This is keyed first by the era index and then by the validator stash. Only the last
+		/// `T::HistoryDepth` eras are kept.
+		pub ErasStakersClipped get(fn eras_stakers_clipped):
+			double_map EraIndex, twox_128(T::AccountId) => Exposure<T::AccountId, BalanceOf<T>>;
+
+		/// The preferences of the elected validators at era, keyed like `ErasStakersClipped`.
+		pub ErasValidatorPrefs get(fn eras_validator_prefs):
+			double_map EraIndex, twox_128(T::AccountId) => ValidatorPrefs;
+
+		/// The total validator payout of the last `T::HistoryDepth` eras.
+		///
+		/// Eras that haven't finished yet or have been pruned don't have a payout.
+		pub ErasValidatorReward get(fn eras_validator_reward): map EraIndex => Option<BalanceOf<T>>;
+
+		/// Reward points of the validators for the last `T::HistoryDepth` eras.
+		pub ErasRewardPoints get(fn eras_reward_points): map EraIndex => EraRewardPoints<T::AccountId>;
+
+		/// Whether the rewards of a validator and its nominators have been paid out for an era.
+		ErasRewardsClaimed get(fn eras_rewards_claimed):
+			double_map EraIndex, twox_128(T::AccountId) => bool;
 
 		/// The amount of balance actively at stake for each validator slot, currently.
 		///
@@ -731,6 +945,26 @@ decl_storage! {
 		/// The earliest era for which we have a pending, unapplied slash.
 		EarliestUnappliedSlash: Option<EraIndex>;
 
+		/// Snapshot of the validator candidates, taken when the election window opens.
+		pub SnapshotValidators get(fn snapshot_validators): Option<Vec<T::AccountId>>;
+
+		/// Snapshot of the voters, taken when the election window opens. The validator
+		/// candidates come first, since they vote for themselves, followed by the nominators.
+		pub SnapshotNominators get(fn snapshot_nominators): Option<Vec<T::AccountId>>;
+
+		/// The best election result submitted during the election window, if any.
+		pub QueuedElected get(fn queued_elected):
+			Option<ElectionResult<T::AccountId, BalanceOf<T>>>;
+
+		/// The score of `QueuedElected`.
+		pub QueuedScore get(fn queued_score): Option<PhragmenScore>;
+
+		/// The submitter of `QueuedElected` and its deposit, if it was submitted signed.
+		pub QueuedSubmitter get(fn queued_submitter): Option<(T::AccountId, BalanceOf<T>)>;
+
+		/// Whether election solutions can currently be submitted.
+		pub EraElectionStatus get(fn era_election_status): ElectionStatus;
+
 		/// The version of storage for upgrade.
 		StorageVersion: u32;
 	}
@@ -772,14 +1006,22 @@ decl_storage! {
 
 decl_event!(
 	pub enum Event<T> where Balance = BalanceOf<T>, <T as system::Trait>::AccountId {
-		/// All validators have been rewarded by the first balance; the second is the remainder
-		/// from the maximum amount of reward.
-		Reward(Balance, Balance),
+		/// The payout of an era has been set aside for the validators to claim; the first balance
+		/// is the validator payout, the second is the remainder from the maximum amount of reward.
+		EraPayout(EraIndex, Balance, Balance),
+		/// A staker (validator or nominator) has been rewarded by the given amount.
+		Reward(AccountId, Balance),
 		/// One validator (and its nominators) has been slashed by the given amount.
 		Slash(AccountId, Balance),
 		/// An old slashing report from a prior era was discarded because it could
 		/// not be processed.
 		OldSlashingReportDiscarded(SessionIndex),
+		/// A new set of validators has been elected, computed in the given way.
+		StakingElection(ElectionCompute),
+		/// A better election solution has been submitted and queued for the next era.
+		SolutionStored(ElectionCompute),
+		/// An invalid signed election solution has been submitted, and its deposit slashed.
+		BadSolutionSlashed(AccountId, Balance),
 	}
 );
 
@@ -791,10 +1033,33 @@ decl_module! {
 		/// Number of eras that staked funds must remain bonded for.
 		const BondingDuration: EraIndex = T::BondingDuration::get();
 
+		/// The maximum number of nominators rewarded for each validator.
+		const MaxNominatorRewardedPerValidator: u32 = T::MaxNominatorRewardedPerValidator::get();
+
+		/// Number of past eras for which rewards can still be claimed.
+		const HistoryDepth: EraIndex = T::HistoryDepth::get();
+
+		/// The number of blocks before the end of the era during which the election window is
+		/// open.
+		const ElectionLookahead: T::BlockNumber = T::ElectionLookahead::get();
+
 		fn deposit_event() = default;
 
-		fn on_initialize() {
+		fn on_initialize(now: T::BlockNumber) {
 			Self::ensure_storage_upgraded();
+
+			// open the election window `ElectionLookahead` blocks before the era ends.
+			if T::OffchainElection::get()
+				&& Self::era_election_status() == ElectionStatus::Closed
+				&& Self::is_current_session_final()
+			{
+				let remaining = T::NextNewSession::estimate_next_new_session(now)
+					.and_then(|next_session| next_session.checked_sub(&now))
+					.unwrap_or_else(Zero::zero);
+				if !remaining.is_zero() && remaining <= T::ElectionLookahead::get() {
+					Self::open_election_window();
+				}
+			}
 		}
 
 		fn on_finalize() {
@@ -804,6 +1069,21 @@ decl_module! {
 			}
 		}
 
+		// Runs after every block.
+		fn offchain_worker(_now: T::BlockNumber) {
+			debug::RuntimeLogger::init();
+
+			if offchain_election::should_compute::<T>() {
+				if let Err(e) = offchain_election::compute_and_submit_election::<T>() {
+					debug::native::debug!(
+						target: "staking",
+						"Election solution not submitted: {:?}",
+						e,
+					);
+				}
+			}
+		}
+
 		/// Take the origin account as a stash and lock up `value` of its balance. `controller` will
 		/// be the account that controls it.
 		///
@@ -811,6 +1091,8 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the stash account.
 		///
+		/// Not allowed while the election window is open, since it would change the election.
+		///
 		/// # <weight>
 		/// - Independent of the arguments. Moderate complexity.
 		/// - O(1).
@@ -826,6 +1108,7 @@ decl_module! {
 			payee: RewardDestination
 		) {
 			let stash = ensure_signed(origin)?;
+			Self::ensure_election_window_closed()?;
 
 			if <Bonded<T>>::exists(&stash) {
 				return Err("stash already bonded")
@@ -862,6 +1145,8 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the stash, not the controller.
 		///
+		/// Not allowed while the election window is open, since it would change the election.
+		///
 		/// # <weight>
 		/// - Independent of the arguments. Insignificant complexity.
 		/// - O(1).
@@ -870,6 +1155,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
 		fn bond_extra(origin, #[compact] max_additional: BalanceOf<T>) {
 			let stash = ensure_signed(origin)?;
+			Self::ensure_election_window_closed()?;
 
 			let controller = Self::bonded(&stash).ok_or("not a stash")?;
 			let mut ledger = Self::ledger(&controller).ok_or("not a controller")?;
@@ -897,6 +1183,8 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
 		///
+		/// Not allowed while the election window is open, since it would change the election.
+		///
 		/// See also [`Call::withdraw_unbonded`].
 		///
 		/// # <weight>
@@ -910,6 +1198,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(400_000)]
 		fn unbond(origin, #[compact] value: BalanceOf<T>) {
 			let controller = ensure_signed(origin)?;
+			Self::ensure_election_window_closed()?;
 			let mut ledger = Self::ledger(&controller).ok_or("not a controller")?;
 			ensure!(
 				ledger.unlocking.len() < MAX_UNLOCKING_CHUNKS,
@@ -976,6 +1265,8 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
 		///
+		/// Not allowed while the election window is open, since it would change the election.
+		///
 		/// # <weight>
 		/// - Independent of the arguments. Insignificant complexity.
 		/// - Contains a limited number of reads.
@@ -986,6 +1277,7 @@ decl_module! {
 			Self::ensure_storage_upgraded();
 
 			let controller = ensure_signed(origin)?;
+			Self::ensure_election_window_closed()?;
 			let ledger = Self::ledger(&controller).ok_or("not a controller")?;
 			let stash = &ledger.stash;
 			<Nominators<T>>::remove(stash);
@@ -998,6 +1290,8 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
 		///
+		/// Not allowed while the election window is open, since it would change the election.
+		///
 		/// # <weight>
 		/// - The transaction's complexity is proportional to the size of `targets`,
 		/// which is capped at `MAX_NOMINATIONS`.
@@ -1008,6 +1302,7 @@ decl_module! {
 			Self::ensure_storage_upgraded();
 
 			let controller = ensure_signed(origin)?;
+			Self::ensure_election_window_closed()?;
 			let ledger = Self::ledger(&controller).ok_or("not a controller")?;
 			let stash = &ledger.stash;
 			ensure!(!targets.is_empty(), "targets cannot be empty");
@@ -1032,6 +1327,8 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
 		///
+		/// Not allowed while the election window is open, since it would change the election.
+		///
 		/// # <weight>
 		/// - Independent of the arguments. Insignificant complexity.
 		/// - Contains one read.
@@ -1040,6 +1337,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
 		fn chill(origin) {
 			let controller = ensure_signed(origin)?;
+			Self::ensure_election_window_closed()?;
 			let ledger = Self::ledger(&controller).ok_or("not a controller")?;
 			Self::chill_stash(&ledger.stash);
 		}
@@ -1185,6 +1483,90 @@ decl_module! {
 
 			<Self as Store>::UnappliedSlashes::insert(&era, &unapplied);
 		}
+
+		/// Pay out the reward of a validator and its nominators for a past era.
+		///
+		/// Only the `MaxNominatorRewardedPerValidator` biggest nominators of the validator are
+		/// rewarded. `era` must be one of the last `HistoryDepth` eras that have ended, and the
+		/// reward of a validator can only be paid out once per era.
+		///
+		/// The dispatch origin for this call must be _Signed_. Any account can call this function,
+		/// even if it is not one of the stakers.
+		///
+		/// # <weight>
+		/// - Time complexity: O(MaxNominatorRewardedPerValidator).
+		/// - Contains a limited number of reads and writes.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		fn payout_stakers(origin, validator_stash: T::AccountId, era: EraIndex) {
+			ensure_signed(origin)?;
+			Self::do_payout_stakers(validator_stash, era)?;
+		}
+
+		/// Submit the result of the election of the next era.
+		///
+		/// `winners` and `compact` refer to the stakers by their index in `SnapshotValidators`
+		/// and `SnapshotNominators`. The solution must be for the election ending the current
+		/// `era`, be feasible, match the claimed `score`, and be better than the queued solution.
+		///
+		/// The dispatch origin for this call must be _Signed_, and the election window must be
+		/// open. `T::ElectionSolutionDeposit` is reserved from the origin; it is returned once the
+		/// solution is used or replaced by a better one, and slashed if the solution is invalid.
+		///
+		/// # <weight>
+		/// - Time complexity: O(E) where E is the number of edges in `compact`, plus the
+		///   building of the support map.
+		/// - Reads the snapshot and the nominations of every voter in `compact`.
+		/// # </weight>
+		#[weight = WeightForSubmitSolution]
+		fn submit_election_solution(
+			origin,
+			winners: Vec<ValidatorIndex>,
+			compact: CompactAssignments,
+			era: EraIndex,
+			score: PhragmenScore,
+		) {
+			let who = ensure_signed(origin)?;
+			Self::pre_dispatch_checks(score, era)?;
+
+			let deposit = T::ElectionSolutionDeposit::get();
+			T::Currency::reserve(&who, deposit)
+				.map_err(|_| "not enough free balance for the solution deposit")?;
+
+			let compute = ElectionCompute::Signed;
+			if Self::check_and_replace_solution(winners, compact, era, score, compute).is_ok() {
+				<QueuedSubmitter<T>>::put((who, deposit));
+			} else {
+				let (imbalance, _) = T::Currency::slash_reserved(&who, deposit);
+				T::Slash::on_unbalanced(imbalance);
+				Self::deposit_event(RawEvent::BadSolutionSlashed(who, deposit));
+			}
+		}
+
+		/// Unsigned version of `submit_election_solution`, submitted by the off-chain worker of
+		/// a validator.
+		///
+		/// `signature` is the signature of `(winners, compact, era, score)` by the session key of
+		/// the validator at `validator_index` in the current session. It is checked in
+		/// `validate_unsigned`.
+		///
+		/// # <weight>
+		/// - Same as `submit_election_solution`.
+		/// # </weight>
+		#[weight = WeightForSubmitSolution]
+		fn submit_election_solution_unsigned(
+			origin,
+			winners: Vec<ValidatorIndex>,
+			compact: CompactAssignments,
+			era: EraIndex,
+			score: PhragmenScore,
+			// the signature is checked in `validate_unsigned`.
+			_validator_index: u32,
+			_signature: <T::KeyType as RuntimeAppPublic>::Signature,
+		) {
+			ensure_none(origin)?;
+			Self::check_and_replace_solution(winners, compact, era, score, ElectionCompute::Unsigned)?;
+		}
 	}
 }
 
@@ -1248,31 +1630,58 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
-	/// Reward a given validator by a specific amount. Add the reward to the validator's, and its
-	/// nominators' balance, pro-rata based on their exposure, after having removed the validator's
-	/// pre-payout cut.
-	fn reward_validator(stash: &T::AccountId, reward: BalanceOf<T>) -> PositiveImbalanceOf<T> {
-		let off_the_table = Self::validators(stash).commission * reward;
-		let reward = reward.saturating_sub(off_the_table);
-		let mut imbalance = <PositiveImbalanceOf<T>>::zero();
-		let validator_cut = if reward.is_zero() {
-			Zero::zero()
-		} else {
-			let exposure = Self::stakers(stash);
-			let total = exposure.total.max(One::one());
+	/// Pay out the reward of a validator and its nominators for the given era.
+	fn do_payout_stakers(
+		validator_stash: T::AccountId,
+		era: EraIndex,
+	) -> result::Result<(), &'static str> {
+		// An era without a payout has either not ended yet or has been pruned.
+		let era_payout = <ErasValidatorReward<T>>::get(&era).ok_or("invalid era to reward")?;
+		ensure!(
+			!<ErasRewardsClaimed<T>>::get(&era, &validator_stash),
+			"reward already claimed",
+		);
+		<ErasRewardsClaimed<T>>::insert(&era, &validator_stash, true);
 
-			for i in &exposure.others {
-				let per_u64 = Perbill::from_rational_approximation(i.value, total);
-				imbalance.maybe_subsume(Self::make_payout(&i.who, per_u64 * reward));
+		let era_reward_points = <ErasRewardPoints<T>>::get(&era);
+		let validator_reward_points = era_reward_points.individual.get(&validator_stash)
+			.cloned()
+			.unwrap_or_default();
+
+		// Nothing to pay out if the validator has not earned any points.
+		if validator_reward_points.is_zero() {
+			return Ok(())
+		}
+
+		let reward = Perbill::from_rational_approximation(
+			validator_reward_points,
+			era_reward_points.total,
+		) * era_payout;
+
+		let off_the_table = Self::eras_validator_prefs(&era, &validator_stash).commission * reward;
+		let reward = reward.saturating_sub(off_the_table);
+		let mut total_imbalance = <PositiveImbalanceOf<T>>::zero();
+		let exposure = Self::eras_stakers_clipped(&era, &validator_stash);
+		let total = exposure.total.max(One::one());
+
+		for i in &exposure.others {
+			let per_u64 = Perbill::from_rational_approximation(i.value, total);
+			if let Some(imbalance) = Self::make_payout(&i.who, per_u64 * reward) {
+				Self::deposit_event(RawEvent::Reward(i.who.clone(), imbalance.peek()));
+				total_imbalance.subsume(imbalance);
 			}
+		}
 
-			let per_u64 = Perbill::from_rational_approximation(exposure.own, total);
-			per_u64 * reward
-		};
+		let per_u64 = Perbill::from_rational_approximation(exposure.own, total);
+		let validator_cut = per_u64 * reward;
+		if let Some(imbalance) = Self::make_payout(&validator_stash, validator_cut + off_the_table) {
+			Self::deposit_event(RawEvent::Reward(validator_stash, imbalance.peek()));
+			total_imbalance.subsume(imbalance);
+		}
 
-		imbalance.maybe_subsume(Self::make_payout(stash, validator_cut + off_the_table));
+		T::Reward::on_unbalanced(total_imbalance);
 
-		imbalance
+		Ok(())
 	}
 
 	/// Session has just ended. Provide the validator set for the next session if it's an era-end, along
@@ -1300,8 +1709,8 @@ impl<T: Trait> Module<T> {
 	/// NOTE: This always happens immediately before a session change to ensure that new validators
 	/// get a chance to set their session keys.
 	fn new_era(start_session_index: SessionIndex) -> Option<Vec<T::AccountId>> {
-		// Payout
-		let points = CurrentEraPointsEarned::take();
+		// Set aside the payout of the ending era, to be claimed with `payout_stakers`.
+		let ending_era = Self::current_era();
 		let now = T::Time::now();
 		let previous_era_start = <CurrentEraStart<T>>::mutate(|v| {
 			sp_std::mem::replace(v, now)
@@ -1321,22 +1730,17 @@ impl<T: Trait> Module<T> {
 				era_duration.saturated_into::<u64>(),
 			);
 
-			let mut total_imbalance = <PositiveImbalanceOf<T>>::zero();
-
-			for (v, p) in validators.iter().zip(points.individual.into_iter()) {
-				if p != 0 {
-					let reward = Perbill::from_rational_approximation(p, points.total) * total_payout;
-					total_imbalance.subsume(Self::reward_validator(v, reward));
-				}
-			}
-
-			// assert!(total_imbalance.peek() == total_payout)
-			let total_payout = total_imbalance.peek();
+			// Nobody can claim a payout if no points were earned during the era.
+			let total_payout = if <ErasRewardPoints<T>>::get(&ending_era).total.is_zero() {
+				Zero::zero()
+			} else {
+				total_payout
+			};
 
 			let rest = max_payout.saturating_sub(total_payout);
-			Self::deposit_event(RawEvent::Reward(total_payout, rest));
+			Self::deposit_event(RawEvent::EraPayout(ending_era, total_payout, rest));
 
-			T::Reward::on_unbalanced(total_imbalance);
+			<ErasValidatorReward<T>>::insert(&ending_era, total_payout);
 			T::RewardRemainder::on_unbalanced(T::Currency::issue(rest));
 		}
 
@@ -1370,6 +1774,11 @@ impl<T: Trait> Module<T> {
 			}
 		});
 
+		// Clear the information of the era which falls out of the history depth.
+		if let Some(old_era) = current_era.checked_sub(T::HistoryDepth::get() + 1) {
+			Self::clear_era_information(old_era);
+		}
+
 		// Reassign all Stakers.
 		let (_slot_stake, maybe_new_validators) = Self::select_validators();
 		Self::apply_unapplied_slashes(current_era);
@@ -1377,6 +1786,15 @@ impl<T: Trait> Module<T> {
 		maybe_new_validators
 	}
 
+	/// Remove all the data kept about an era which fell out of the history depth.
+	fn clear_era_information(era: EraIndex) {
+		<ErasStakersClipped<T>>::remove_prefix(&era);
+		<ErasValidatorPrefs<T>>::remove_prefix(&era);
+		<ErasRewardsClaimed<T>>::remove_prefix(&era);
+		<ErasValidatorReward<T>>::remove(&era);
+		<ErasRewardPoints<T>>::remove(&era);
+	}
+
 	/// Apply previously-unapplied slashes on the beginning of a new era, after a delay.
 	fn apply_unapplied_slashes(current_era: EraIndex) {
 		let slash_defer_duration = T::SlashDeferDuration::get();
@@ -1395,93 +1813,18 @@ impl<T: Trait> Module<T> {
 
 	/// Select a new validator set from the assembled stakers and their role preferences.
 	///
+	/// The best solution submitted during the election window is used if there is one, otherwise
+	/// the election is computed on-chain.
+	///
 	/// Returns the new `SlotStake` value and a set of newly selected _stash_ IDs.
 	///
 	/// Assumes storage is coherent with the declaration.
 	fn select_validators() -> (BalanceOf<T>, Option<Vec<T::AccountId>>) {
-		let mut all_nominators: Vec<(T::AccountId, Vec<T::AccountId>)> = Vec::new();
-		let all_validator_candidates_iter = <Validators<T>>::enumerate();
-		let all_validators = all_validator_candidates_iter.map(|(who, _pref)| {
-			let self_vote = (who.clone(), vec![who.clone()]);
-			all_nominators.push(self_vote);
-			who
-		}).collect::<Vec<T::AccountId>>();
-
-		let nominator_votes = <Nominators<T>>::enumerate().map(|(nominator, nominations)| {
-			let Nominations { submitted_in, mut targets, suppressed: _ } = nominations;
-
-			// Filter out nomination targets which were nominated before the most recent
-			// slashing span.
-			targets.retain(|stash| {
-				<Self as Store>::SlashingSpans::get(&stash).map_or(
-					true,
-					|spans| submitted_in >= spans.last_start(),
-				)
-			});
-
-			(nominator, targets)
-		});
-		all_nominators.extend(nominator_votes);
-
-		let maybe_phragmen_result = phragmen::elect::<_, _, _, T::CurrencyToVote>(
-			Self::validator_count() as usize,
-			Self::minimum_validator_count().max(1) as usize,
-			all_validators,
-			all_nominators,
-			Self::slashable_balance_of,
-		);
-
-		if let Some(phragmen_result) = maybe_phragmen_result {
-			let elected_stashes = phragmen_result.winners.iter()
-				.map(|(s, _)| s.clone())
-				.collect::<Vec<T::AccountId>>();
-			let assignments = phragmen_result.assignments;
-
-			let to_votes = |b: BalanceOf<T>|
-				<T::CurrencyToVote as Convert<BalanceOf<T>, u64>>::convert(b) as ExtendedBalance;
-			let to_balance = |e: ExtendedBalance|
-				<T::CurrencyToVote as Convert<ExtendedBalance, BalanceOf<T>>>::convert(e);
-
-			let mut supports = phragmen::build_support_map::<_, _, _, T::CurrencyToVote>(
-				&elected_stashes,
-				&assignments,
-				Self::slashable_balance_of,
-			);
-
-			if cfg!(feature = "equalize") {
-				let mut staked_assignments
-					: Vec<(T::AccountId, Vec<PhragmenStakedAssignment<T::AccountId>>)>
-					= Vec::with_capacity(assignments.len());
-				for (n, assignment) in assignments.iter() {
-					let mut staked_assignment
-						: Vec<PhragmenStakedAssignment<T::AccountId>>
-						= Vec::with_capacity(assignment.len());
-
-					// If this is a self vote, then we don't need to equalise it at all. While the
-					// staking system does not allow nomination and validation at the same time,
-					// this must always be 100% support.
-					if assignment.len() == 1 && assignment[0].0 == *n {
-						continue;
-					}
-					for (c, per_thing) in assignment.iter() {
-						let nominator_stake = to_votes(Self::slashable_balance_of(n));
-						let other_stake = *per_thing * nominator_stake;
-						staked_assignment.push((c.clone(), other_stake));
-					}
-					staked_assignments.push((n.clone(), staked_assignment));
-				}
-
-				let tolerance = 0_u128;
-				let iterations = 2_usize;
-				phragmen::equalize::<_, _, T::CurrencyToVote, _>(
-					staked_assignments,
-					&mut supports,
-					tolerance,
-					iterations,
-					Self::slashable_balance_of,
-				);
-			}
+		let current_era = Self::current_era();
+		let maybe_election_result = <QueuedElected<T>>::take().or_else(Self::do_phragmen);
+		Self::close_election_window();
 
+		if let Some(ElectionResult { elected_stashes, exposures, compute }) = maybe_election_result {
 			// Clear Stakers.
 			for v in Self::current_elected().iter() {
 				<Stakers<T>>::remove(v);
@@ -1489,24 +1832,12 @@ impl<T: Trait> Module<T> {
 
 			// Populate Stakers and figure out the minimum stake behind a slot.
 			let mut slot_stake = BalanceOf::<T>::max_value();
-			for (c, s) in supports.into_iter() {
-				// build `struct exposure` from `support`
-				let exposure = Exposure {
-					own: to_balance(s.own),
-					// This might reasonably saturate and we cannot do much about it. The sum of
-					// someone's stake might exceed the balance type if they have the maximum amount
-					// of balance and receive some support. This is super unlikely to happen, yet
-					// we simulate it in some tests.
-					total: to_balance(s.total),
-					others: s.others
-						.into_iter()
-						.map(|(who, value)| IndividualExposure { who, value: to_balance(value) })
-						.collect::<Vec<IndividualExposure<_, _>>>(),
-				};
+			for (c, exposure) in exposures.into_iter() {
 				if exposure.total < slot_stake {
 					slot_stake = exposure.total;
 				}
 				<Stakers<T>>::insert(&c, exposure.clone());
+				Self::store_era_exposure(current_era, &c, exposure);
 			}
 
 			// Update slot stake.
@@ -1515,6 +1846,8 @@ impl<T: Trait> Module<T> {
 			// Set the new validator set in sessions.
 			<CurrentElected<T>>::put(&elected_stashes);
 
+			Self::deposit_event(RawEvent::StakingElection(compute));
+
 			// In order to keep the property required by `n_session_ending`
 			// that we must return the new validator set even if it's the same as the old,
 			// as long as any underlying economic conditions have changed, we don't attempt
@@ -1531,6 +1864,297 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Run the phragmen election on-chain, over all the current candidates and voters.
+	///
+	/// Returns `None` if not enough candidates could be elected.
+	fn do_phragmen() -> Option<ElectionResult<T::AccountId, BalanceOf<T>>> {
+		let mut all_nominators: Vec<(T::AccountId, Vec<T::AccountId>)> = Vec::new();
+		let all_validator_candidates_iter = <Validators<T>>::enumerate();
+		let all_validators = all_validator_candidates_iter.map(|(who, _pref)| {
+			let self_vote = (who.clone(), vec![who.clone()]);
+			all_nominators.push(self_vote);
+			who
+		}).collect::<Vec<T::AccountId>>();
+
+		let nominator_votes = <Nominators<T>>::enumerate().map(|(nominator, nominations)| {
+			(nominator, Self::filter_nominations(nominations))
+		});
+		all_nominators.extend(nominator_votes);
+
+		let phragmen_result = phragmen::elect::<_, _, _, T::CurrencyToVote>(
+			Self::validator_count() as usize,
+			Self::minimum_validator_count().max(1) as usize,
+			all_validators,
+			all_nominators,
+			Self::slashable_balance_of,
+		)?;
+
+		let elected_stashes = phragmen_result.winners.iter()
+			.map(|(s, _)| s.clone())
+			.collect::<Vec<T::AccountId>>();
+		let assignments = phragmen_result.assignments;
+
+		let to_votes = |b: BalanceOf<T>|
+			<T::CurrencyToVote as Convert<BalanceOf<T>, u64>>::convert(b) as ExtendedBalance;
+
+		let mut supports = build_support_map::<_, _, _, T::CurrencyToVote>(
+			&elected_stashes,
+			&assignments,
+			Self::slashable_balance_of,
+		);
+
+		if cfg!(feature = "equalize") {
+			let mut staked_assignments
+				: Vec<(T::AccountId, Vec<PhragmenStakedAssignment<T::AccountId>>)>
+				= Vec::with_capacity(assignments.len());
+			for (n, assignment) in assignments.iter() {
+				let mut staked_assignment
+					: Vec<PhragmenStakedAssignment<T::AccountId>>
+					= Vec::with_capacity(assignment.len());
+
+				// If this is a self vote, then we don't need to equalise it at all. While the
+				// staking system does not allow nomination and validation at the same time,
+				// this must always be 100% support.
+				if assignment.len() == 1 && assignment[0].0 == *n {
+					continue;
+				}
+				for (c, per_thing) in assignment.iter() {
+					let nominator_stake = to_votes(Self::slashable_balance_of(n));
+					let other_stake = *per_thing * nominator_stake;
+					staked_assignment.push((c.clone(), other_stake));
+				}
+				staked_assignments.push((n.clone(), staked_assignment));
+			}
+
+			let tolerance = 0_u128;
+			let iterations = 2_usize;
+			phragmen::equalize::<_, _, T::CurrencyToVote, _>(
+				staked_assignments,
+				&mut supports,
+				tolerance,
+				iterations,
+				Self::slashable_balance_of,
+			);
+		}
+
+		Some(ElectionResult {
+			elected_stashes,
+			exposures: Self::exposures_from_supports(supports),
+			compute: ElectionCompute::OnChain,
+		})
+	}
+
+	/// Build the exposures of the elected validators from their phragmen support.
+	fn exposures_from_supports(
+		supports: SupportMap<T::AccountId>,
+	) -> Vec<(T::AccountId, Exposure<T::AccountId, BalanceOf<T>>)> {
+		let to_balance = |e: ExtendedBalance|
+			<T::CurrencyToVote as Convert<ExtendedBalance, BalanceOf<T>>>::convert(e);
+
+		supports.into_iter().map(|(c, s)| {
+			// build `struct exposure` from `support`
+			let exposure = Exposure {
+				own: to_balance(s.own),
+				// This might reasonably saturate and we cannot do much about it. The sum of
+				// someone's stake might exceed the balance type if they have the maximum amount
+				// of balance and receive some support. This is super unlikely to happen, yet
+				// we simulate it in some tests.
+				total: to_balance(s.total),
+				others: s.others
+					.into_iter()
+					.map(|(who, value)| IndividualExposure { who, value: to_balance(value) })
+					.collect::<Vec<IndividualExposure<_, _>>>(),
+			};
+			(c, exposure)
+		}).collect()
+	}
+
+	/// The targets of the given nominations, without the ones which were nominated before the
+	/// most recent slashing span of the target.
+	fn filter_nominations(nominations: Nominations<T::AccountId>) -> Vec<T::AccountId> {
+		let Nominations { submitted_in, mut targets, suppressed: _ } = nominations;
+
+		targets.retain(|stash| {
+			<Self as Store>::SlashingSpans::get(&stash).map_or(
+				true,
+				|spans| submitted_in >= spans.last_start(),
+			)
+		});
+
+		targets
+	}
+
+	/// The candidates a voter may support in the election: itself for a validator, its valid
+	/// nominations for a nominator.
+	fn targets_of(who: &T::AccountId) -> Vec<T::AccountId> {
+		if <Validators<T>>::exists(who) {
+			vec![who.clone()]
+		} else {
+			Self::nominators(who).map(Self::filter_nominations).unwrap_or_default()
+		}
+	}
+
+	/// Whether the era ends with the current session, i.e. the next call to `new_session` starts
+	/// a new era.
+	fn is_current_session_final() -> bool {
+		// `new_session` is called with the index of the session after the current one.
+		let next_session = T::SessionInterface::current_index() + 1;
+		let era_length = next_session.saturating_sub(Self::current_era_start_session_index());
+		match ForceEra::get() {
+			Forcing::ForceNew | Forcing::ForceAlways => true,
+			Forcing::NotForcing => era_length >= T::SessionsPerEra::get(),
+			Forcing::ForceNone => false,
+		}
+	}
+
+	/// Take a snapshot of the candidates and voters, and open the election window.
+	fn open_election_window() {
+		let validators = <Validators<T>>::enumerate()
+			.map(|(who, _pref)| who)
+			.collect::<Vec<T::AccountId>>();
+		let mut nominators = validators.clone();
+		nominators.extend(<Nominators<T>>::enumerate().map(|(who, _nominations)| who));
+
+		<SnapshotValidators<T>>::put(validators);
+		<SnapshotNominators<T>>::put(nominators);
+		EraElectionStatus::put(ElectionStatus::Open);
+	}
+
+	/// Close the election window and remove the snapshot.
+	fn close_election_window() {
+		EraElectionStatus::put(ElectionStatus::Closed);
+		<SnapshotValidators<T>>::kill();
+		<SnapshotNominators<T>>::kill();
+		<QueuedElected<T>>::kill();
+		QueuedScore::kill();
+		Self::release_queued_deposit();
+	}
+
+	/// Return the deposit of the submitter of the queued solution, if it was submitted signed.
+	fn release_queued_deposit() {
+		if let Some((who, deposit)) = <QueuedSubmitter<T>>::take() {
+			T::Currency::unreserve(&who, deposit);
+		}
+	}
+
+	/// Staking calls which change the candidates, the voters or their stake are rejected while
+	/// the election window is open: the submitted solutions are checked against the current
+	/// state, which must match the snapshot.
+	fn ensure_election_window_closed() -> result::Result<(), &'static str> {
+		ensure!(
+			Self::era_election_status() == ElectionStatus::Closed,
+			"call not allowed while the election window is open",
+		);
+		Ok(())
+	}
+
+	/// Checks which can be done before decoding a solution: the election window must be open for
+	/// the given era, and the score must be better than the one of the queued solution.
+	fn pre_dispatch_checks(score: PhragmenScore, era: EraIndex) -> result::Result<(), &'static str> {
+		ensure!(Self::era_election_status() == ElectionStatus::Open, "election window is closed");
+		ensure!(era == Self::current_era(), "solution is for another era");
+		if let Some(queued_score) = Self::queued_score() {
+			ensure!(is_score_better(score, queued_score), "solution is not better than the queued one");
+		}
+		Ok(())
+	}
+
+	/// Check that a submitted solution is feasible and has the claimed score, and queue it as
+	/// the result of the next election.
+	fn check_and_replace_solution(
+		winners: Vec<ValidatorIndex>,
+		compact: CompactAssignments,
+		era: EraIndex,
+		claimed_score: PhragmenScore,
+		compute: ElectionCompute,
+	) -> result::Result<(), &'static str> {
+		Self::pre_dispatch_checks(claimed_score, era)?;
+
+		let snapshot_validators = Self::snapshot_validators().ok_or("snapshot unavailable")?;
+		let snapshot_nominators = Self::snapshot_nominators().ok_or("snapshot unavailable")?;
+
+		// The number of winners must be the one the on-chain election would give.
+		let desired_winners = Self::validator_count().min(snapshot_validators.len() as u32);
+		ensure!(winners.len() as u32 == desired_winners, "wrong number of winners");
+		ensure!(
+			desired_winners >= Self::minimum_validator_count().max(1),
+			"not enough winners",
+		);
+
+		let winners = winners.into_iter()
+			.map(|index| snapshot_validators.get(index as usize).cloned())
+			.collect::<Option<Vec<T::AccountId>>>()
+			.ok_or("invalid winner index")?;
+		let mut sorted_winners = winners.clone();
+		sorted_winners.sort();
+		sorted_winners.dedup();
+		ensure!(sorted_winners.len() == winners.len(), "duplicate winner");
+
+		let assignments = compact.into_assignments(
+			|index| snapshot_nominators.get(index as usize).cloned(),
+			|index| snapshot_validators.get(index as usize).cloned(),
+		).ok_or("invalid assignment index")?;
+
+		// Each voter must appear once.
+		let mut voters = assignments.iter().map(|(who, _)| who.clone()).collect::<Vec<_>>();
+		voters.sort();
+		voters.dedup();
+		ensure!(voters.len() == assignments.len(), "duplicate voter");
+
+		for (who, distribution) in assignments.iter() {
+			// A voter may only support winners it has voted for, with at most its whole stake.
+			let targets = Self::targets_of(who);
+			let mut total_share: u64 = 0;
+			for (target, share) in distribution.iter() {
+				ensure!(targets.contains(target), "invalid vote");
+				ensure!(sorted_winners.binary_search(target).is_ok(), "vote for a non-winner");
+				// A validator always backs itself with its whole stake.
+				ensure!(target != who || *share == Perbill::one(), "invalid self vote");
+				total_share += share.deconstruct() as u64;
+			}
+			ensure!(total_share <= Perbill::accuracy() as u64, "invalid vote share");
+		}
+
+		// Recompute the score of the solution.
+		let supports = build_support_map::<_, _, _, T::CurrencyToVote>(
+			&winners,
+			&assignments,
+			Self::slashable_balance_of,
+		);
+		ensure!(evaluate_support(&supports) == claimed_score, "wrong score");
+
+		Self::release_queued_deposit();
+		<QueuedElected<T>>::put(ElectionResult {
+			elected_stashes: winners,
+			exposures: Self::exposures_from_supports(supports),
+			compute,
+		});
+		QueuedScore::put(claimed_score);
+		Self::deposit_event(RawEvent::SolutionStored(compute));
+
+		Ok(())
+	}
+
+	/// Record the exposure and preferences of an elected validator for the given era.
+	///
+	/// Only the `T::MaxNominatorRewardedPerValidator` biggest nominators are kept in the
+	/// clipped exposure.
+	fn store_era_exposure(
+		era: EraIndex,
+		stash: &T::AccountId,
+		mut exposure: Exposure<T::AccountId, BalanceOf<T>>,
+	) {
+		let max_nominators = T::MaxNominatorRewardedPerValidator::get() as usize;
+		if exposure.others.len() > max_nominators {
+			exposure.others.sort_unstable_by(|a, b| b.value.cmp(&a.value));
+			exposure.others.truncate(max_nominators);
+			exposure.total = exposure.others.iter()
+				.fold(exposure.own, |total, i| total.saturating_add(i.value));
+		}
+		<ErasStakersClipped<T>>::insert(&era, stash, exposure);
+		<ErasValidatorPrefs<T>>::insert(&era, stash, Self::validators(stash));
+	}
+
 	/// Remove all associated data of a stash account from the staking system.
 	///
 	/// Assumes storage is upgraded before calling.
@@ -1556,19 +2180,17 @@ impl<T: Trait> Module<T> {
 	/// For each element in the iterator the given number of points in u32 is added to the
 	/// validator, thus duplicates are handled.
 	///
-	/// At the end of the era each the total payout will be distributed among validator
-	/// relatively to their points.
+	/// The total payout of the era is shared among validators relatively to their points, once
+	/// claimed with `payout_stakers`.
 	///
 	/// COMPLEXITY: Complexity is `number_of_validator_to_reward x current_elected_len`.
 	/// If you need to reward lots of validator consider using `reward_by_indices`.
 	pub fn reward_by_ids(validators_points: impl IntoIterator<Item = (T::AccountId, u32)>) {
-		CurrentEraPointsEarned::mutate(|rewards| {
-			let current_elected = <Module<T>>::current_elected();
+		let current_elected = <Module<T>>::current_elected();
+		<ErasRewardPoints<T>>::mutate(Self::current_era(), |rewards| {
 			for (validator, points) in validators_points.into_iter() {
-				if let Some(index) = current_elected.iter()
-					.position(|elected| *elected == validator)
-				{
-					rewards.add_points_to_index(index as u32, points);
+				if current_elected.contains(&validator) {
+					rewards.add_points(validator, points);
 				}
 			}
 		});
@@ -1579,13 +2201,11 @@ impl<T: Trait> Module<T> {
 	/// For each element in the iterator the given number of points in u32 is added to the
 	/// validator, thus duplicates are handled.
 	pub fn reward_by_indices(validators_points: impl IntoIterator<Item = (u32, u32)>) {
-		// TODO: This can be optimised once #3302 is implemented.
-		let current_elected_len = <Module<T>>::current_elected().len() as u32;
-
-		CurrentEraPointsEarned::mutate(|rewards| {
+		let current_elected = <Module<T>>::current_elected();
+		<ErasRewardPoints<T>>::mutate(Self::current_era(), |rewards| {
 			for (validator_index, points) in validators_points.into_iter() {
-				if validator_index < current_elected_len {
-					rewards.add_points_to_index(validator_index, points);
+				if let Some(validator) = current_elected.get(validator_index as usize) {
+					rewards.add_points(validator.clone(), points);
 				}
 			}
 		});
@@ -1776,3 +2396,60 @@ impl<T, Reporter, Offender, R, O> ReportOffence<Reporter, Offender, O>
 		}
 	}
 }
+
+#[allow(deprecated)]
+impl<T: Trait> support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		if let Call::submit_election_solution_unsigned(
+			winners,
+			compact,
+			era,
+			score,
+			validator_index,
+			signature,
+		) = call {
+			// discard solutions which could not be queued anyway.
+			if Self::pre_dispatch_checks(*score, *era).is_err() {
+				return InvalidTransaction::Stale.into();
+			}
+
+			// the submitter must be a current validator.
+			let key = match T::SessionInterface::validators()
+				.get(*validator_index as usize)
+				.and_then(|v| T::SessionInterface::validator_key::<T::KeyType>(v, T::KeyType::ID))
+			{
+				Some(key) => key,
+				None => return InvalidTransaction::BadProof.into(),
+			};
+
+			// check signature (this is expensive so we do it last).
+			let signature_valid = (winners, compact, era, score).using_encoded(|encoded| {
+				key.verify(&encoded, &signature)
+			});
+
+			if !signature_valid {
+				return InvalidTransaction::BadProof.into();
+			}
+
+			// the solution can't be submitted once the election window is closed.
+			let now = <system::Module<T>>::block_number();
+			let longevity = T::NextNewSession::estimate_next_new_session(now)
+				.map(|next_session| next_session.saturating_sub(now))
+				.unwrap_or_else(T::ElectionLookahead::get)
+				.saturated_into::<TransactionLongevity>();
+
+			Ok(ValidTransaction {
+				// better solutions go first.
+				priority: score[0].saturated_into::<TransactionPriority>(),
+				requires: vec![],
+				provides: vec![("StakingOffchain", era).encode()],
+				longevity,
+				propagate: true,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+}
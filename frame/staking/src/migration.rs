@@ -20,12 +20,12 @@
 pub type VersionNumber = u32;
 
 // the current expected version of the storage
-pub const CURRENT_VERSION: VersionNumber = 1;
+pub const CURRENT_VERSION: VersionNumber = 2;
 
 #[cfg(any(test, feature = "migrate"))]
 mod inner {
-	use crate::{Store, Module, Trait};
-	use support::{StorageLinkedMap, StorageValue};
+	use crate::{Store, Module, Trait, EraRewardPoints};
+	use support::{StorageLinkedMap, StorageValue, StorageMap};
 	use sp_std::vec::Vec;
 	use super::{CURRENT_VERSION, VersionNumber};
 
@@ -60,6 +60,33 @@ mod inner {
 		support::print("Finished migrating Staking storage to v1.");
 	}
 
+	// migrate storage from v1 to v2.
+	//
+	// rewards are now claimed lazily per era: this records the exposures and preferences of the
+	// current validator set in `ErasStakersClipped` and `ErasValidatorPrefs`, and converts the
+	// index-based `CurrentEraPointsEarned` into `ErasRewardPoints` for the current era.
+	pub fn to_v2<T: Trait>(version: &mut VersionNumber) {
+		if *version != 1 { return }
+		*version += 1;
+
+		let current_era = <Module<T>>::current_era();
+		let current_elected = <Module<T> as Store>::CurrentElected::get();
+
+		for stash in current_elected.iter() {
+			let exposure = <Module<T> as Store>::Stakers::get(stash);
+			<Module<T>>::store_era_exposure(current_era, stash, exposure);
+		}
+
+		let points = <Module<T> as Store>::CurrentEraPointsEarned::take();
+		let mut era_points = EraRewardPoints::default();
+		for (stash, p) in current_elected.into_iter().zip(points.individual.into_iter()) {
+			era_points.add_points(stash, p);
+		}
+		<Module<T> as Store>::ErasRewardPoints::insert(&current_era, era_points);
+
+		support::print("Finished migrating Staking storage to v2.");
+	}
+
 	pub(super) fn perform_migrations<T: Trait>() {
 		<Module<T> as Store>::StorageVersion::mutate(|version| {
 			if *version < MIN_SUPPORTED_VERSION {
@@ -72,6 +99,7 @@ mod inner {
 			if *version == CURRENT_VERSION { return }
 
 			to_v1::<T>(version);
+			to_v2::<T>(version);
 		});
 	}
 }
@@ -20,12 +20,12 @@ use std::{collections::HashSet, cell::RefCell};
 use sp_runtime::{Perbill, KeyTypeId};
 use sp_runtime::curve::PiecewiseLinear;
 use sp_runtime::traits::{IdentityLookup, Convert, OpaqueKeys, OnInitialize, SaturatedConversion};
-use sp_runtime::testing::{Header, UintAuthorityId};
+use sp_runtime::testing::{Header, UintAuthorityId, TestXt};
 use sp_staking::{SessionIndex, offence::{OffenceDetails, OnOffenceHandler}};
 use primitives::{H256, crypto::key_types};
 use sp_io;
 use support::{
-	assert_ok, impl_outer_origin, parameter_types, StorageLinkedMap, StorageValue,
+	assert_ok, impl_outer_origin, impl_outer_dispatch, parameter_types, StorageLinkedMap, StorageValue,
 	traits::{Currency, Get, FindAuthor},
 	weights::Weight,
 };
@@ -52,6 +52,9 @@ thread_local! {
 	static SESSION: RefCell<(Vec<AccountId>, HashSet<AccountId>)> = RefCell::new(Default::default());
 	static EXISTENTIAL_DEPOSIT: RefCell<u64> = RefCell::new(0);
 	static SLASH_DEFER_DURATION: RefCell<EraIndex> = RefCell::new(0);
+	static OFFCHAIN_ELECTION: RefCell<bool> = RefCell::new(false);
+	static SESSIONS_PER_ERA: RefCell<SessionIndex> = RefCell::new(3);
+	static SESSION_PERIOD: RefCell<BlockNumber> = RefCell::new(1);
 }
 
 pub struct TestSessionHandler;
@@ -98,10 +101,41 @@ impl Get<EraIndex> for SlashDeferDuration {
 	}
 }
 
+pub struct OffchainElection;
+impl Get<bool> for OffchainElection {
+	fn get() -> bool {
+		OFFCHAIN_ELECTION.with(|v| *v.borrow())
+	}
+}
+
+pub struct SessionsPerEra;
+impl Get<SessionIndex> for SessionsPerEra {
+	fn get() -> SessionIndex {
+		SESSIONS_PER_ERA.with(|v| *v.borrow())
+	}
+}
+
+pub struct Period;
+impl Get<BlockNumber> for Period {
+	fn get() -> BlockNumber {
+		SESSION_PERIOD.with(|v| *v.borrow())
+	}
+}
+
 impl_outer_origin!{
 	pub enum Origin for Test {}
 }
 
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		staking::Staking,
+	}
+}
+
+/// An extrinsic type used for tests.
+pub type Extrinsic = TestXt<Call, ()>;
+type SubmitTransaction = system::offchain::TransactionSubmitter<(), Call, Extrinsic>;
+
 /// Author of block is always 11
 pub struct Author11;
 impl FindAuthor<u64> for Author11 {
@@ -154,7 +188,6 @@ impl balances::Trait for Test {
 	type CreationFee = CreationFee;
 }
 parameter_types! {
-	pub const Period: BlockNumber = 1;
 	pub const Offset: BlockNumber = 0;
 	pub const UncleGenerations: u64 = 0;
 	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(25);
@@ -200,9 +233,12 @@ pallet_staking_reward_curve::build! {
 	);
 }
 parameter_types! {
-	pub const SessionsPerEra: SessionIndex = 3;
 	pub const BondingDuration: EraIndex = 3;
 	pub const RewardCurve: &'static PiecewiseLinear<'static> = &I_NPOS;
+	pub const MaxNominatorRewardedPerValidator: u32 = 64;
+	pub const HistoryDepth: EraIndex = 10;
+	pub const ElectionSolutionDeposit: Balance = 10;
+	pub const ElectionLookahead: BlockNumber = 3;
 }
 impl Trait for Test {
 	type Currency = balances::Module<Self>;
@@ -218,6 +254,15 @@ impl Trait for Test {
 	type BondingDuration = BondingDuration;
 	type SessionInterface = Self;
 	type RewardCurve = RewardCurve;
+	type MaxNominatorRewardedPerValidator = MaxNominatorRewardedPerValidator;
+	type HistoryDepth = HistoryDepth;
+	type Call = Call;
+	type SubmitTransaction = SubmitTransaction;
+	type KeyType = UintAuthorityId;
+	type OffchainElection = OffchainElection;
+	type ElectionSolutionDeposit = ElectionSolutionDeposit;
+	type NextNewSession = session::PeriodicSessions<Period, Offset>;
+	type ElectionLookahead = ElectionLookahead;
 }
 
 pub struct ExtBuilder {
@@ -230,6 +275,9 @@ pub struct ExtBuilder {
 	fair: bool,
 	num_validators: Option<u32>,
 	invulnerables: Vec<u64>,
+	offchain_election: bool,
+	sessions_per_era: SessionIndex,
+	session_period: BlockNumber,
 }
 
 impl Default for ExtBuilder {
@@ -244,6 +292,9 @@ impl Default for ExtBuilder {
 			fair: true,
 			num_validators: None,
 			invulnerables: vec![],
+			offchain_election: false,
+			sessions_per_era: 3,
+			session_period: 1,
 		}
 	}
 }
@@ -285,9 +336,24 @@ impl ExtBuilder {
 		self.invulnerables = invulnerables;
 		self
 	}
+	pub fn offchain_election(mut self, offchain_election: bool) -> Self {
+		self.offchain_election = offchain_election;
+		self
+	}
+	pub fn sessions_per_era(mut self, sessions_per_era: SessionIndex) -> Self {
+		self.sessions_per_era = sessions_per_era;
+		self
+	}
+	pub fn session_period(mut self, session_period: BlockNumber) -> Self {
+		self.session_period = session_period;
+		self
+	}
 	pub fn set_associated_consts(&self) {
 		EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = self.existential_deposit);
 		SLASH_DEFER_DURATION.with(|v| *v.borrow_mut() = self.slash_defer_duration);
+		OFFCHAIN_ELECTION.with(|v| *v.borrow_mut() = self.offchain_election);
+		SESSIONS_PER_ERA.with(|v| *v.borrow_mut() = self.sessions_per_era);
+		SESSION_PERIOD.with(|v| *v.borrow_mut() = self.session_period);
 	}
 	pub fn build(self) -> sp_io::TestExternalities {
 		self.set_associated_consts();
@@ -322,7 +388,6 @@ impl ExtBuilder {
 					// This allow us to have a total_payout different from 0.
 					(999, 1_000_000_000_000),
 			],
-			vesting: vec![],
 		}.assimilate_storage(&mut storage);
 
 		let stake_21 = if self.fair { 1000 } else { 2000 };
@@ -449,16 +514,27 @@ pub fn start_session(session_index: SessionIndex) {
 		System::set_block_number((i + 1).into());
 		Timestamp::set_timestamp(System::block_number() * 1000);
 		Session::on_initialize(System::block_number());
+		Staking::on_initialize(System::block_number());
 	}
 
 	assert_eq!(Session::current_index(), session_index);
 }
 
 pub fn start_era(era_index: EraIndex) {
-	start_session((era_index * 3).into());
+	start_session((era_index * SessionsPerEra::get()).into());
 	assert_eq!(Staking::current_era(), era_index);
 }
 
+/// Initialize the blocks up to and including `n`, which may span several sessions.
+pub fn run_to_block(n: BlockNumber) {
+	for b in System::block_number() + 1..=n {
+		System::set_block_number(b);
+		Timestamp::set_timestamp(b * 1000);
+		Session::on_initialize(b);
+		Staking::on_initialize(b);
+	}
+}
+
 pub fn current_total_payout_for_duration(duration: u64) -> u64 {
 	inflation::compute_total_payout(
 		<Test as Trait>::RewardCurve::get(),
@@ -476,6 +552,17 @@ pub fn reward_all_elected() {
 	<Module<Test>>::reward_by_ids(rewards)
 }
 
+/// Pay out the rewards of all the validators that earned points in the given era.
+pub fn make_all_reward_payment(era: EraIndex) {
+	let validators_with_reward = <Module<Test>>::eras_reward_points(era).individual.keys()
+		.cloned()
+		.collect::<Vec<_>>();
+
+	for validator in validators_with_reward.into_iter() {
+		assert_ok!(Staking::payout_stakers(Origin::signed(1337), validator, era));
+	}
+}
+
 pub fn validator_controllers() -> Vec<AccountId> {
 	Session::validators().into_iter().map(|s| Staking::bonded(&s).expect("no controller for validator")).collect()
 }
@@ -0,0 +1,204 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers for the off-chain phragmen election.
+//!
+//! While the election window is open, validators compute the next election in their off-chain
+//! worker, from the snapshot of candidates and voters stored on-chain, and submit the result as
+//! an unsigned transaction. The assignments are submitted in a compact form, where voters and
+//! candidates are referred to by their index in the snapshot.
+
+use crate::{
+	Call, Module, Trait, SessionInterface, EraIndex, ValidatorIndex, NominatorIndex, ElectionStatus,
+};
+use codec::{Encode, Decode};
+use phragmen::{PhragmenAssignment, PhragmenScore, build_support_map, evaluate_support};
+use primitives::offchain::StorageKind;
+use sp_runtime::{Perbill, RuntimeAppPublic, RuntimeDebug};
+use sp_std::{prelude::*, collections::btree_map::BTreeMap};
+use system::offchain::SubmitUnsignedTransaction;
+
+/// The local storage key of the last era for which this node submitted a solution.
+const OFFCHAIN_HEAD_DB: &[u8] = b"parity/staking-election/";
+
+/// Error which may occur while computing or submitting an off-chain election.
+#[derive(RuntimeDebug)]
+pub(crate) enum OffchainElectionError {
+	/// A solution was already submitted for this era.
+	AlreadySubmitted,
+	/// None of the local keys belongs to a current validator.
+	NoSigningKey,
+	/// The snapshot of the election is not available.
+	SnapshotUnavailable,
+	/// Phragmen did not elect enough validators.
+	ElectionFailed,
+	/// An account of the election result is missing from the snapshot.
+	InvalidIndex,
+	/// Signing the solution failed.
+	FailedSigning,
+	/// Submitting the transaction to the pool failed.
+	PoolSubmissionFailed,
+}
+
+/// The assignments of an election result, with voters and candidates referred to by their index
+/// in `SnapshotNominators` and `SnapshotValidators` respectively.
+#[derive(PartialEq, Eq, Clone, Default, Encode, Decode, RuntimeDebug)]
+pub struct CompactAssignments {
+	/// For each voter, the candidates it supports and the share of its stake behind each.
+	pub votes: Vec<(NominatorIndex, Vec<(ValidatorIndex, Perbill)>)>,
+}
+
+impl CompactAssignments {
+	/// Compact the given assignments. Returns `None` if an account has no index.
+	pub fn from_assignments<AccountId, FN, FV>(
+		assignments: Vec<(AccountId, Vec<PhragmenAssignment<AccountId>>)>,
+		nominator_index: FN,
+		validator_index: FV,
+	) -> Option<Self> where
+		for<'r> FN: Fn(&'r AccountId) -> Option<NominatorIndex>,
+		for<'r> FV: Fn(&'r AccountId) -> Option<ValidatorIndex>,
+	{
+		let mut votes = Vec::with_capacity(assignments.len());
+		for (who, distribution) in assignments {
+			let mut targets = Vec::with_capacity(distribution.len());
+			for (target, share) in distribution {
+				targets.push((validator_index(&target)?, share));
+			}
+			votes.push((nominator_index(&who)?, targets));
+		}
+		Some(Self { votes })
+	}
+
+	/// Expand the compact assignments back into accounts. Returns `None` if an index is unknown.
+	pub fn into_assignments<AccountId, FN, FV>(
+		self,
+		nominator_at: FN,
+		validator_at: FV,
+	) -> Option<Vec<(AccountId, Vec<PhragmenAssignment<AccountId>>)>> where
+		FN: Fn(NominatorIndex) -> Option<AccountId>,
+		FV: Fn(ValidatorIndex) -> Option<AccountId>,
+	{
+		self.votes.into_iter()
+			.map(|(who, distribution)| {
+				let distribution = distribution.into_iter()
+					.map(|(target, share)| validator_at(target).map(|target| (target, share)))
+					.collect::<Option<Vec<_>>>()?;
+				nominator_at(who).map(|who| (who, distribution))
+			})
+			.collect()
+	}
+}
+
+/// Compute the election from the snapshot and submit it as an unsigned transaction, signed with
+/// the first local key that belongs to a current validator.
+///
+/// Only one solution is submitted per era and per node.
+pub(crate) fn compute_and_submit_election<T: Trait>() -> Result<(), OffchainElectionError> {
+	let era = <Module<T>>::current_era();
+	let last_submitted = sp_io::offchain::local_storage_get(
+		StorageKind::PERSISTENT,
+		OFFCHAIN_HEAD_DB,
+	);
+	if last_submitted.and_then(|e| EraIndex::decode(&mut &e[..]).ok()) == Some(era) {
+		return Err(OffchainElectionError::AlreadySubmitted)
+	}
+
+	let local_keys = T::KeyType::all();
+	let (validator_index, key) = T::SessionInterface::validators()
+		.into_iter()
+		.enumerate()
+		.filter_map(|(index, validator)| {
+			T::SessionInterface::validator_key::<T::KeyType>(&validator, T::KeyType::ID)
+				.filter(|key| local_keys.contains(key))
+				.map(|key| (index as u32, key))
+		})
+		.next()
+		.ok_or(OffchainElectionError::NoSigningKey)?;
+
+	let (winners, compact, score) = prepare_submission::<T>()?;
+
+	let signature = (&winners, &compact, era, score)
+		.using_encoded(|payload| key.sign(&payload))
+		.ok_or(OffchainElectionError::FailedSigning)?;
+	let call = Call::submit_election_solution_unsigned(
+		winners,
+		compact,
+		era,
+		score,
+		validator_index,
+		signature,
+	);
+	T::SubmitTransaction::submit_unsigned(call)
+		.map_err(|_| OffchainElectionError::PoolSubmissionFailed)?;
+
+	sp_io::offchain::local_storage_set(StorageKind::PERSISTENT, OFFCHAIN_HEAD_DB, &era.encode());
+	Ok(())
+}
+
+/// Run phragmen over the snapshot and return the winners, the compact assignments and the score
+/// of the result, ready to be submitted.
+pub(crate) fn prepare_submission<T: Trait>()
+	-> Result<(Vec<ValidatorIndex>, CompactAssignments, PhragmenScore), OffchainElectionError>
+{
+	let snapshot_validators = <Module<T>>::snapshot_validators()
+		.ok_or(OffchainElectionError::SnapshotUnavailable)?;
+	let snapshot_nominators = <Module<T>>::snapshot_nominators()
+		.ok_or(OffchainElectionError::SnapshotUnavailable)?;
+
+	let voters = snapshot_nominators.iter()
+		.map(|who| (who.clone(), <Module<T>>::targets_of(who)))
+		.collect::<Vec<_>>();
+	let result = phragmen::elect::<_, _, _, T::CurrencyToVote>(
+		<Module<T>>::validator_count() as usize,
+		<Module<T>>::minimum_validator_count().max(1) as usize,
+		snapshot_validators.clone(),
+		voters,
+		<Module<T>>::slashable_balance_of,
+	).ok_or(OffchainElectionError::ElectionFailed)?;
+
+	let winners = result.winners.into_iter().map(|(who, _)| who).collect::<Vec<_>>();
+	let supports = build_support_map::<_, _, _, T::CurrencyToVote>(
+		&winners,
+		&result.assignments,
+		<Module<T>>::slashable_balance_of,
+	);
+	let score = evaluate_support(&supports);
+
+	let validator_indices = snapshot_validators.iter()
+		.enumerate()
+		.map(|(index, who)| (who.clone(), index as ValidatorIndex))
+		.collect::<BTreeMap<_, _>>();
+	let nominator_indices = snapshot_nominators.iter()
+		.enumerate()
+		.map(|(index, who)| (who.clone(), index as NominatorIndex))
+		.collect::<BTreeMap<_, _>>();
+	let winners = winners.iter()
+		.map(|who| validator_indices.get(who).cloned())
+		.collect::<Option<Vec<_>>>()
+		.ok_or(OffchainElectionError::InvalidIndex)?;
+	let compact = CompactAssignments::from_assignments(
+		result.assignments,
+		|who| nominator_indices.get(who).cloned(),
+		|who| validator_indices.get(who).cloned(),
+	).ok_or(OffchainElectionError::InvalidIndex)?;
+
+	Ok((winners, compact, score))
+}
+
+/// Returns `true` if the off-chain worker of this block should try to submit a solution.
+pub(crate) fn should_compute<T: Trait>() -> bool {
+	<Module<T>>::era_election_status() == ElectionStatus::Open && sp_io::offchain::is_validator()
+}
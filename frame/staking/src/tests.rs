@@ -152,7 +152,7 @@ fn change_controller_works() {
 fn rewards_should_work() {
 	// should check that:
 	// * rewards get recorded per session
-	// * rewards get paid per Era
+	// * rewards get claimable per Era
 	// * Check that nominators are also rewarded
 	ExtBuilder::default().nominate(false).build().execute_with(|| {
 		// Init some balances
@@ -174,11 +174,13 @@ fn rewards_should_work() {
 		//
 		// Equal division indicates that the reward will be equally divided among validator and
 		// nominator.
-		<Stakers<Test>>::insert(&11, Exposure {
+		let exposure = Exposure {
 			own: 500,
 			total: 1000,
 			others: vec![IndividualExposure {who: 2, value: 500 }]
-		});
+		};
+		<Stakers<Test>>::insert(&11, exposure.clone());
+		<ErasStakersClipped<Test>>::insert(0, &11, exposure);
 
 		<Payee<Test>>::insert(&2, RewardDestination::Stash);
 		assert_eq!(Staking::payee(2), RewardDestination::Stash);
@@ -220,6 +222,11 @@ fn rewards_should_work() {
 		assert_eq!(Staking::current_era(), 1);
 		assert_eq!(Session::current_index(), 3);
 
+		// Rewards are not paid out until they are claimed
+		assert_eq!(Balances::total_balance(&2), init_balance_2);
+		assert_eq!(Balances::total_balance(&10), init_balance_10);
+		make_all_reward_payment(0);
+
 		// 11 validator has 2/3 of the total rewards and half half for it and its nominator
 		assert_eq_error_rate!(Balances::total_balance(&2), init_balance_2 + total_payout / 3, 1);
 		assert_eq_error_rate!(Balances::total_balance(&10), init_balance_10 + total_payout / 3, 1);
@@ -249,6 +256,7 @@ fn multi_era_reward_should_work() {
 		start_session(3);
 
 		assert_eq!(Staking::current_era(), 1);
+		make_all_reward_payment(0);
 		assert_eq!(Balances::total_balance(&10), init_balance_10 + total_payout_0);
 
 		start_session(4);
@@ -261,6 +269,7 @@ fn multi_era_reward_should_work() {
 		start_session(5);
 
 		// pay time
+		make_all_reward_payment(1);
 		assert_eq!(Balances::total_balance(&10), init_balance_10 + total_payout_0 + total_payout_1);
 	});
 }
@@ -468,6 +477,7 @@ fn nominating_and_rewards_should_work() {
 			assert_eq_uvec!(validator_controllers(), vec![20, 10]);
 
 			// OLD validators must have already received some rewards.
+			make_all_reward_payment(0);
 			assert_eq!(Balances::total_balance(&40), 1 + total_payout_0 / 2);
 			assert_eq!(Balances::total_balance(&30), 1 + total_payout_0 / 2);
 
@@ -541,6 +551,7 @@ fn nominating_and_rewards_should_work() {
 
 			// nothing else will happen, era ends and rewards are paid again,
 			// it is expected that nominators will also be paid. See below
+			make_all_reward_payment(1);
 
 			let payout_for_10 = total_payout_1 / 3;
 			let payout_for_20 = 2 * total_payout_1 / 3;
@@ -633,6 +644,7 @@ fn nominators_also_get_slashed() {
 
 		// new era, pay rewards,
 		start_era(1);
+		make_all_reward_payment(0);
 
 		// Nominator stash didn't collect any.
 		assert_eq!(Balances::total_balance(&2), initial_balance);
@@ -892,6 +904,7 @@ fn reward_destination_works() {
 		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
 
 		start_era(1);
+		make_all_reward_payment(0);
 
 		// Check that RewardDestination is Staked (default)
 		assert_eq!(Staking::payee(&11), RewardDestination::Staked);
@@ -914,6 +927,7 @@ fn reward_destination_works() {
 		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
 
 		start_era(2);
+		make_all_reward_payment(1);
 
 		// Check that RewardDestination is Stash
 		assert_eq!(Staking::payee(&11), RewardDestination::Stash);
@@ -941,6 +955,7 @@ fn reward_destination_works() {
 		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
 
 		start_era(3);
+		make_all_reward_payment(2);
 
 		// Check that RewardDestination is Controller
 		assert_eq!(Staking::payee(&11), RewardDestination::Controller);
@@ -975,15 +990,19 @@ fn validator_payment_prefs_work() {
 		let _ = Balances::make_free_balance_be(&2, 500);
 
 		// add a dummy nominator.
-		<Stakers<Test>>::insert(&11, Exposure {
+		let exposure = Exposure {
 			own: 500, // equal division indicates that the reward will be equally divided among validator and nominator.
 			total: 1000,
 			others: vec![IndividualExposure {who: 2, value: 500 }]
-		});
+		};
+		<Stakers<Test>>::insert(&11, exposure.clone());
+		<ErasStakersClipped<Test>>::insert(0, &11, exposure);
 		<Payee<Test>>::insert(&2, RewardDestination::Stash);
-		<Validators<Test>>::insert(&11, ValidatorPrefs {
+		let prefs = ValidatorPrefs {
 			commission: Perbill::from_percent(50),
-		});
+		};
+		<Validators<Test>>::insert(&11, prefs.clone());
+		<ErasValidatorPrefs<Test>>::insert(0, &11, prefs);
 
 		// Compute total payout now for whole duration as other parameter won't change
 		let total_payout_0 = current_total_payout_for_duration(3000);
@@ -991,6 +1010,7 @@ fn validator_payment_prefs_work() {
 		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
 
 		start_era(1);
+		make_all_reward_payment(0);
 
 		// whats left to be shared is the sum of 3 rounds minus the validator's cut.
 		let shared_cut = total_payout_0 / 2;
@@ -1197,8 +1217,10 @@ fn slot_stake_is_least_staked_validator_and_exposure_defines_maximum_punishment(
 		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
 		<Module<Test>>::reward_by_ids(vec![(21, 1)]);
 
-		// New era --> rewards are paid --> stakes are changed
+		// New era --> rewards are paid --> stakes are changed in the following era
 		start_era(1);
+		make_all_reward_payment(0);
+		start_era(2);
 
 		// -- new balances + reward
 		assert_eq!(Staking::stakers(&11).total, 1000 + total_payout_0 / 2);
@@ -1481,6 +1503,7 @@ fn bond_with_little_staked_value_bounded_by_slot_stake() {
 			assert!(total_payout_0 > 100); // Test is meaningfull if reward something
 			reward_all_elected();
 			start_era(1);
+			make_all_reward_payment(0);
 
 			// 2 is elected.
 			// and fucks up the slot stake.
@@ -1496,6 +1519,7 @@ fn bond_with_little_staked_value_bounded_by_slot_stake() {
 			assert!(total_payout_1 > 100); // Test is meaningfull if reward something
 			reward_all_elected();
 			start_era(2);
+			make_all_reward_payment(1);
 
 			assert_eq_uvec!(validator_controllers(), vec![20, 10, 2]);
 			assert_eq!(Staking::slot_stake(), 1);
@@ -1651,10 +1675,12 @@ fn reward_validator_slashing_validator_doesnt_overflow() {
 
 		// Set staker
 		let _ = Balances::make_free_balance_be(&11, stake);
-		<Stakers<Test>>::insert(&11, Exposure { total: stake, own: stake, others: vec![] });
+		<ErasStakersClipped<Test>>::insert(0, &11, Exposure { total: stake, own: stake, others: vec![] });
 
 		// Check reward
-		let _ = Staking::reward_validator(&11, reward_slash);
+		<ErasValidatorReward<Test>>::insert(0, reward_slash);
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		assert_ok!(Staking::payout_stakers(Origin::signed(1337), 11, 0));
 		assert_eq!(Balances::total_balance(&11), stake * 2);
 
 		// Set staker
@@ -1705,8 +1731,13 @@ fn reward_from_authorship_event_handler_works() {
 
 		// 21 is rewarded as an uncle producer
 		// 11 is rewarded as a block producer and uncle referencer and uncle producer
-		assert_eq!(CurrentEraPointsEarned::get().individual, vec![1, 20 + 2 * 3 + 1]);
-		assert_eq!(CurrentEraPointsEarned::get().total, 28);
+		assert_eq!(
+			Staking::eras_reward_points(Staking::current_era()),
+			EraRewardPoints {
+				total: 28,
+				individual: vec![(11, 20 + 2 * 3 + 1), (21, 1)].into_iter().collect(),
+			},
+		);
 	})
 }
 
@@ -1731,8 +1762,13 @@ fn add_reward_points_fns_works() {
 			(11, 1),
 		]);
 
-		assert_eq!(CurrentEraPointsEarned::get().individual, vec![2, 4]);
-		assert_eq!(CurrentEraPointsEarned::get().total, 6);
+		assert_eq!(
+			Staking::eras_reward_points(Staking::current_era()),
+			EraRewardPoints {
+				total: 6,
+				individual: vec![(11, 4), (21, 2)].into_iter().collect(),
+			},
+		);
 	})
 }
 
@@ -2527,3 +2563,458 @@ fn version_initialized() {
 		assert_eq!(<Staking as Store>::StorageVersion::get(), crate::migration::CURRENT_VERSION);
 	});
 }
+
+#[test]
+fn payout_stakers_only_works_once_per_ended_era() {
+	ExtBuilder::default().nominate(false).build().execute_with(|| {
+		let init_balance_11 = Balances::total_balance(&11);
+		let total_payout_0 = current_total_payout_for_duration(3000);
+		assert!(total_payout_0 > 100); // Test is meaningfull if reward something
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+
+		// The era has not ended yet.
+		assert_noop!(
+			Staking::payout_stakers(Origin::signed(1337), 11, 0),
+			"invalid era to reward",
+		);
+
+		start_era(1);
+		assert_eq!(Staking::eras_validator_reward(0), Some(total_payout_0));
+		assert_eq!(Balances::total_balance(&11), init_balance_11);
+
+		// Anyone can trigger the payout.
+		assert_ok!(Staking::payout_stakers(Origin::signed(1337), 11, 0));
+		assert_eq!(Balances::total_balance(&11), init_balance_11 + total_payout_0);
+
+		assert_noop!(
+			Staking::payout_stakers(Origin::signed(1337), 11, 0),
+			"reward already claimed",
+		);
+
+		// A validator without points gets nothing, but the call succeeds.
+		let init_balance_21 = Balances::total_balance(&21);
+		assert_ok!(Staking::payout_stakers(Origin::signed(1337), 21, 0));
+		assert_eq!(Balances::total_balance(&21), init_balance_21);
+	});
+}
+
+#[test]
+fn era_information_is_pruned_after_history_depth() {
+	ExtBuilder::default().nominate(false).build().execute_with(|| {
+		let history_depth = <Test as Trait>::HistoryDepth::get();
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+
+		start_era(history_depth);
+		assert!(Staking::eras_validator_reward(0).is_some());
+		assert_eq!(Staking::eras_stakers_clipped(0, 11).own, 1000);
+		assert_eq!(Staking::eras_reward_points(0).total, 1);
+
+		start_era(history_depth + 1);
+		assert_eq!(Staking::eras_validator_reward(0), None);
+		assert_eq!(Staking::eras_stakers_clipped(0, 11), Exposure::default());
+		assert_eq!(Staking::eras_reward_points(0), EraRewardPoints::default());
+		assert_noop!(
+			Staking::payout_stakers(Origin::signed(1337), 11, 0),
+			"invalid era to reward",
+		);
+
+		// The following eras are kept.
+		assert!(Staking::eras_validator_reward(1).is_some());
+		assert_eq!(Staking::eras_stakers_clipped(1, 11).own, 1000);
+	});
+}
+
+#[test]
+fn era_exposure_only_keeps_the_biggest_nominators() {
+	ExtBuilder::default().build().execute_with(|| {
+		let max = <Test as Trait>::MaxNominatorRewardedPerValidator::get() as u64;
+		let others = (0..max + 10)
+			.map(|i| IndividualExposure { who: 1000 + i, value: i + 1 })
+			.collect::<Vec<_>>();
+		let total = others.iter().fold(1000, |total, i| total + i.value);
+
+		Staking::store_era_exposure(0, &11, Exposure { own: 1000, total, others });
+
+		let clipped = Staking::eras_stakers_clipped(0, 11);
+		assert_eq!(clipped.others.len() as u64, max);
+		assert!(clipped.others.iter().all(|i| i.value > 10));
+		assert_eq!(clipped.total, clipped.others.iter().fold(1000, |total, i| total + i.value));
+		assert_eq!(Staking::eras_validator_prefs(0, 11), Staking::validators(11));
+	});
+}
+
+#[test]
+fn migration_to_v2_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		<ErasStakersClipped<Test>>::remove_prefix(&0);
+		<ErasValidatorPrefs<Test>>::remove_prefix(&0);
+		CurrentEraPointsEarned::put(EraPoints { total: 3, individual: vec![1, 2] });
+		<Staking as Store>::StorageVersion::put(1);
+
+		crate::migration::perform_migrations::<Test>();
+
+		assert_eq!(<Staking as Store>::StorageVersion::get(), 2);
+		assert_eq!(
+			Staking::eras_reward_points(0),
+			EraRewardPoints {
+				total: 3,
+				individual: vec![(11, 2), (21, 1)].into_iter().collect(),
+			},
+		);
+		assert_eq!(Staking::eras_stakers_clipped(0, 11), Staking::stakers(11));
+		assert_eq!(Staking::eras_stakers_clipped(0, 21), Staking::stakers(21));
+		assert_eq!(Staking::eras_validator_prefs(0, 11), Staking::validators(11));
+	});
+}
+
+/// Build the compact assignments of the given votes, referring to the current snapshot.
+fn compact_of(votes: Vec<(AccountId, Vec<(AccountId, Perbill)>)>) -> CompactAssignments {
+	let snapshot_validators = Staking::snapshot_validators().unwrap();
+	let snapshot_nominators = Staking::snapshot_nominators().unwrap();
+	CompactAssignments::from_assignments(
+		votes,
+		|who| snapshot_nominators.iter().position(|n| n == who).map(|i| i as NominatorIndex),
+		|who| snapshot_validators.iter().position(|v| v == who).map(|i| i as ValidatorIndex),
+	).unwrap()
+}
+
+/// The indices of the given validators in the current snapshot.
+fn winners_of(winners: Vec<AccountId>) -> Vec<ValidatorIndex> {
+	let snapshot_validators = Staking::snapshot_validators().unwrap();
+	winners.iter()
+		.map(|who| snapshot_validators.iter().position(|v| v == who).unwrap() as ValidatorIndex)
+		.collect()
+}
+
+/// A feasible solution electing 11 and 31, worse than the one computed by phragmen.
+fn weak_solution() -> (Vec<ValidatorIndex>, CompactAssignments, PhragmenScore) {
+	let winners = winners_of(vec![11, 31]);
+	let compact = compact_of(vec![
+		(11, vec![(11, Perbill::one())]),
+		(31, vec![(31, Perbill::one())]),
+		(101, vec![(11, Perbill::one())]),
+	]);
+	// 11 is backed by itself and 101, 31 only by itself.
+	(winners, compact, [1, 1501, 1500 * 1500 + 1])
+}
+
+#[test]
+fn election_window_opens_in_last_session_of_era() {
+	ExtBuilder::default().offchain_election(true).build().execute_with(|| {
+		start_session(0);
+		assert_eq!(Staking::era_election_status(), ElectionStatus::Closed);
+		assert_eq!(Staking::snapshot_validators(), None);
+
+		// The next session is the last one of the era.
+		start_session(1);
+		assert_eq!(Staking::era_election_status(), ElectionStatus::Open);
+		let mut snapshot_validators = Staking::snapshot_validators().unwrap();
+		snapshot_validators.sort();
+		assert_eq!(snapshot_validators, vec![11, 21, 31]);
+		let snapshot_nominators = Staking::snapshot_nominators().unwrap();
+		assert_eq!(snapshot_nominators.len(), 4);
+		assert_eq!(snapshot_nominators[3], 101);
+
+		start_era(1);
+		assert_eq!(Staking::era_election_status(), ElectionStatus::Closed);
+		assert_eq!(Staking::snapshot_validators(), None);
+		assert_eq!(Staking::snapshot_nominators(), None);
+		assert_eq_uvec!(Staking::current_elected(), vec![11, 21]);
+	});
+}
+
+#[test]
+fn election_window_opens_lookahead_blocks_before_the_era_ends() {
+	ExtBuilder::default()
+		.offchain_election(true)
+		.sessions_per_era(1)
+		.session_period(10)
+		.build()
+		.execute_with(|| {
+			// every session is the last one of its era, the first one ends at block 10.
+			run_to_block(6);
+			assert_eq!(Staking::era_election_status(), ElectionStatus::Closed);
+			assert_eq!(Staking::snapshot_validators(), None);
+
+			run_to_block(7);
+			assert_eq!(Staking::era_election_status(), ElectionStatus::Open);
+			assert!(Staking::snapshot_validators().is_some());
+			assert_noop!(
+				Staking::chill(Origin::signed(10)),
+				"call not allowed while the election window is open",
+			);
+
+			run_to_block(10);
+			assert_eq!(Staking::current_era(), 1);
+			assert_eq!(Staking::era_election_status(), ElectionStatus::Closed);
+			assert_eq!(Staking::snapshot_validators(), None);
+
+			run_to_block(16);
+			assert_eq!(Staking::era_election_status(), ElectionStatus::Closed);
+			assert_ok!(Staking::chill(Origin::signed(10)));
+
+			run_to_block(17);
+			assert_eq!(Staking::era_election_status(), ElectionStatus::Open);
+
+			run_to_block(20);
+			assert_eq!(Staking::current_era(), 2);
+			assert_eq!(Staking::era_election_status(), ElectionStatus::Closed);
+		});
+}
+
+#[test]
+fn signed_solution_is_used_for_next_era() {
+	ExtBuilder::default().offchain_election(true).build().execute_with(|| {
+		start_session(1);
+
+		let (winners, compact, score) = weak_solution();
+		assert_ok!(Staking::submit_election_solution(Origin::signed(4), winners, compact, 0, score));
+		assert_eq!(Staking::queued_score(), Some(score));
+		assert_eq!(Staking::queued_elected().unwrap().compute, ElectionCompute::Signed);
+
+		start_era(1);
+		assert_eq_uvec!(Staking::current_elected(), vec![11, 31]);
+		assert_eq!(Staking::stakers(31).total, 1);
+		assert_eq!(Staking::stakers(11).total, 1500);
+		assert_eq!(Staking::eras_stakers_clipped(1, 11).total, 1500);
+		assert_eq!(Staking::queued_elected(), None);
+		assert_eq!(Staking::queued_score(), None);
+	});
+}
+
+#[test]
+fn only_better_solutions_replace_the_queued_one() {
+	ExtBuilder::default().offchain_election(true).build().execute_with(|| {
+		start_session(1);
+
+		let (weak_winners, weak_compact, weak_score) = weak_solution();
+		assert_ok!(Staking::submit_election_solution(
+			Origin::signed(4),
+			weak_winners.clone(),
+			weak_compact.clone(),
+			0,
+			weak_score,
+		));
+
+		let (winners, compact, score) = offchain_election::prepare_submission::<Test>().unwrap();
+		assert!(is_score_better(score, weak_score));
+		assert_ok!(Staking::submit_election_solution(Origin::signed(4), winners, compact, 0, score));
+		assert_eq!(Staking::queued_score(), Some(score));
+
+		assert_noop!(
+			Staking::submit_election_solution(
+				Origin::signed(4),
+				weak_winners,
+				weak_compact,
+				0,
+				weak_score,
+			),
+			"solution is not better than the queued one",
+		);
+
+		start_era(1);
+		assert_eq_uvec!(Staking::current_elected(), vec![11, 21]);
+	});
+}
+
+#[test]
+fn invalid_solutions_are_rejected() {
+	ExtBuilder::default().offchain_election(true).build().execute_with(|| {
+		let (winners, compact, score) = (vec![0, 1], CompactAssignments::default(), [0; 3]);
+		assert_noop!(
+			Staking::submit_election_solution(Origin::signed(4), winners, compact, 0, score),
+			"election window is closed",
+		);
+
+		start_session(1);
+		let (winners, compact, score) = weak_solution();
+		// signed invalid solutions are slashed rather than rejected, so check the solutions
+		// directly.
+		let submit = |winners: Vec<ValidatorIndex>, compact: CompactAssignments, era, score| {
+			let compute = ElectionCompute::Signed;
+			Staking::check_and_replace_solution(winners, compact, era, score, compute)
+		};
+		assert_noop!(
+			submit(winners.clone(), compact.clone(), 1, score),
+			"solution is for another era",
+		);
+		assert_noop!(submit(winners.clone(), compact.clone(), 0, [1, 1502, 0]), "wrong score");
+		assert_noop!(
+			submit(vec![winners[0]], compact.clone(), 0, score),
+			"wrong number of winners",
+		);
+		assert_noop!(
+			submit(vec![winners[0], 100], compact.clone(), 0, score),
+			"invalid winner index",
+		);
+		assert_noop!(submit(vec![winners[0]; 2], compact, 0, score), "duplicate winner");
+
+		// 101 does not nominate 31.
+		let compact = compact_of(vec![
+			(11, vec![(11, Perbill::one())]),
+			(31, vec![(31, Perbill::one())]),
+			(101, vec![(31, Perbill::one())]),
+		]);
+		assert_noop!(submit(winners.clone(), compact, 0, score), "invalid vote");
+
+		// 21 is not elected.
+		let compact = compact_of(vec![
+			(11, vec![(11, Perbill::one())]),
+			(31, vec![(31, Perbill::one())]),
+			(101, vec![(21, Perbill::one())]),
+		]);
+		assert_noop!(submit(winners, compact, 0, score), "vote for a non-winner");
+
+		// 101 backs 11 and 21 with more than its stake.
+		let compact = compact_of(vec![
+			(11, vec![(11, Perbill::one())]),
+			(21, vec![(21, Perbill::one())]),
+			(101, vec![(11, Perbill::one()), (21, Perbill::from_percent(10))]),
+		]);
+		assert_noop!(submit(winners_of(vec![11, 21]), compact, 0, score), "invalid vote share");
+	});
+}
+
+#[test]
+#[allow(deprecated)] // Allow ValidateUnsigned
+fn offchain_worker_submits_unsigned_solution() {
+	use primitives::offchain::{
+		OffchainExt,
+		TransactionPoolExt,
+		testing::{TestOffchainExt, TestTransactionPoolExt},
+	};
+	use support::unsigned::ValidateUnsigned;
+	use sp_runtime::testing::UintAuthorityId;
+
+	let mut ext = ExtBuilder::default().offchain_election(true).build();
+	let (offchain, _state) = TestOffchainExt::new();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	ext.register_extension(OffchainExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+
+	ext.execute_with(|| {
+		start_session(1);
+
+		// No local key belongs to a validator.
+		UintAuthorityId::set_all_keys(vec![1]);
+		assert!(offchain_election::compute_and_submit_election::<Test>().is_err());
+		assert!(pool_state.read().transactions.is_empty());
+
+		UintAuthorityId::set_all_keys(vec![21]);
+		assert!(offchain_election::compute_and_submit_election::<Test>().is_ok());
+		// Only one solution is submitted per era.
+		assert!(offchain_election::compute_and_submit_election::<Test>().is_err());
+
+		let transaction = pool_state.write().transactions.pop().unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+		let ex: Extrinsic = Decode::decode(&mut &*transaction).unwrap();
+		let call = match ex.1 {
+			mock::Call::Staking(call) => call,
+			_ => panic!("unexpected call"),
+		};
+		// the solution is only valid until the election window closes, with the next session.
+		assert_eq!(Staking::validate_unsigned(&call).map(|v| v.longevity), Ok(1));
+
+		let (winners, compact, era, score, validator_index, signature) = match call {
+			crate::Call::submit_election_solution_unsigned(w, c, e, s, i, sig) =>
+				(w, c, e, s, i, sig),
+			_ => panic!("unexpected call"),
+		};
+		assert_eq!(Staking::validate_unsigned(
+			&crate::Call::submit_election_solution_unsigned(
+				winners.clone(),
+				compact.clone(),
+				era,
+				score,
+				validator_index,
+				signature.wrapping_add(1),
+			),
+		), InvalidTransaction::BadProof.into());
+
+		assert_ok!(Staking::submit_election_solution_unsigned(
+			Origin::NONE,
+			winners,
+			compact,
+			era,
+			score,
+			validator_index,
+			signature,
+		));
+		assert_eq!(Staking::queued_elected().unwrap().compute, ElectionCompute::Unsigned);
+
+		start_era(1);
+		assert_eq_uvec!(Staking::current_elected(), vec![11, 21]);
+	});
+}
+
+#[test]
+fn election_window_is_not_opened_without_offchain_election() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_session(1);
+		assert_eq!(Staking::era_election_status(), ElectionStatus::Closed);
+		assert_eq!(Staking::snapshot_validators(), None);
+	});
+}
+
+#[test]
+fn staking_calls_are_rejected_while_election_window_is_open() {
+	ExtBuilder::default().offchain_election(true).build().execute_with(|| {
+		start_session(1);
+		assert_eq!(Staking::era_election_status(), ElectionStatus::Open);
+
+		let error = "call not allowed while the election window is open";
+		assert_noop!(Staking::bond(Origin::signed(1), 2, 5, RewardDestination::Controller), error);
+		assert_noop!(Staking::bond_extra(Origin::signed(11), 10), error);
+		assert_noop!(Staking::unbond(Origin::signed(10), 10), error);
+		assert_noop!(Staking::validate(Origin::signed(100), ValidatorPrefs::default()), error);
+		assert_noop!(Staking::nominate(Origin::signed(10), vec![21]), error);
+		assert_noop!(Staking::chill(Origin::signed(10)), error);
+
+		start_era(1);
+		assert_eq!(Staking::era_election_status(), ElectionStatus::Closed);
+		assert_ok!(Staking::chill(Origin::signed(10)));
+	});
+}
+
+#[test]
+fn signed_solution_deposit_is_returned_or_slashed() {
+	ExtBuilder::default().offchain_election(true).build().execute_with(|| {
+		start_session(1);
+		let deposit = ElectionSolutionDeposit::get();
+
+		let (weak_winners, weak_compact, weak_score) = weak_solution();
+		assert_ok!(Staking::submit_election_solution(
+			Origin::signed(4),
+			weak_winners.clone(),
+			weak_compact.clone(),
+			0,
+			weak_score,
+		));
+		assert_eq!(Balances::reserved_balance(&4), deposit);
+		assert_eq!(Staking::queued_submitter(), Some((4, deposit)));
+
+		// an invalid solution is not queued, and its deposit is slashed.
+		let free_balance = Balances::free_balance(&3);
+		assert_ok!(Staking::submit_election_solution(
+			Origin::signed(3),
+			weak_winners,
+			weak_compact,
+			0,
+			[1, 1502, 1500 * 1500 + 1],
+		));
+		assert_eq!(Balances::free_balance(&3), free_balance - deposit);
+		assert_eq!(Balances::reserved_balance(&3), 0);
+		assert_eq!(Staking::queued_score(), Some(weak_score));
+
+		// the deposit of a replaced solution is returned.
+		let (winners, compact, score) = offchain_election::prepare_submission::<Test>().unwrap();
+		assert_ok!(Staking::submit_election_solution(Origin::signed(2), winners, compact, 0, score));
+		assert_eq!(Balances::reserved_balance(&4), 0);
+		assert_eq!(Balances::reserved_balance(&2), deposit);
+
+		// so is the one of the solution which is used.
+		start_era(1);
+		assert_eq!(Balances::reserved_balance(&2), 0);
+		assert_eq!(Staking::queued_submitter(), None);
+	});
+}
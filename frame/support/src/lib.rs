@@ -192,6 +192,20 @@ macro_rules! assert_ok {
 #[derive(Clone, Eq, PartialEq, RuntimeDebug)]
 pub enum Void {}
 
+impl codec::Encode for Void {
+	fn encode_to<T: codec::Output>(&self, _dest: &mut T) {
+		match *self {}
+	}
+}
+
+impl codec::EncodeLike for Void {}
+
+impl codec::Decode for Void {
+	fn decode<I: codec::Input>(_input: &mut I) -> Result<Self, codec::Error> {
+		Err("`Void` cannot be instantiated".into())
+	}
+}
+
 #[cfg(feature = "std")]
 #[doc(hidden)]
 pub use serde::{Serialize, Deserialize};
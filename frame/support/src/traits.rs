@@ -56,12 +56,37 @@ impl<T: Default> Get<T> for () {
 pub trait Contains<T> {
 	/// Return `true` if this "contains" the given value `t`.
 	fn contains(t: &T) -> bool;
+
+	/// Get a vector of all members in the set, ordered.
+	fn sorted_members() -> Vec<T>;
+
+	/// Get the number of items in the set.
+	fn count() -> usize { Self::sorted_members().len() }
 }
 
 impl<V: PartialEq, T: Get<V>> Contains<V> for T {
 	fn contains(t: &V) -> bool {
 		&Self::get() == t
 	}
+
+	fn sorted_members() -> Vec<V> {
+		vec![Self::get()]
+	}
+}
+
+/// Something that can decide whether a value, typically a call, is allowed by one instance of
+/// the implementing type, e.g. by one kind of proxy.
+pub trait InstanceFilter<T>: Sized + Send + Sync {
+	/// Determine if a given value should be allowed through the filter (returns `true`) or not.
+	fn filter(&self, _: &T) -> bool;
+
+	/// Determines whether `self` matches at least everything that `_o` does.
+	fn is_superset(&self, _o: &Self) -> bool { false }
+}
+
+impl<T> InstanceFilter<T> for () {
+	fn filter(&self, _: &T) -> bool { true }
+	fn is_superset(&self, _o: &Self) -> bool { true }
 }
 
 /// The account with the given id was killed.
@@ -124,6 +149,21 @@ pub trait KeyOwnerProofSystem<Key> {
 	fn check_proof(key: Key, proof: Self::Proof) -> Option<Self::IdentificationTuple>;
 }
 
+impl<Key> KeyOwnerProofSystem<Key> for () {
+	// The proof and identification tuples are the bottom type, which guarantees
+	// that this implementation can never be called with a proof nor return one.
+	type Proof = crate::Void;
+	type IdentificationTuple = crate::Void;
+
+	fn prove(_key: Key) -> Option<Self::Proof> {
+		None
+	}
+
+	fn check_proof(_key: Key, _proof: Self::Proof) -> Option<Self::IdentificationTuple> {
+		None
+	}
+}
+
 /// Handler for when some currency "account" decreased in balance for
 /// some reason.
 ///
@@ -602,26 +642,33 @@ pub trait LockableCurrency<AccountId>: Currency<AccountId> {
 	);
 }
 
-/// A currency whose accounts can have balances which vest over time.
-pub trait VestingCurrency<AccountId>: Currency<AccountId> {
+/// A vesting schedule over a currency. This allows a particular currency to have vesting limits
+/// applied to it.
+pub trait VestingSchedule<AccountId> {
 	/// The quantity used to denote time; usually just a `BlockNumber`.
 	type Moment;
 
+	/// The currency that this schedule applies to.
+	type Currency: Currency<AccountId>;
+
 	/// Get the amount that is currently being vested and cannot be transferred out of this account.
-	fn vesting_balance(who: &AccountId) -> Self::Balance;
+	/// Returns `None` if the account has no vesting schedule.
+	fn vesting_balance(who: &AccountId) -> Option<<Self::Currency as Currency<AccountId>>::Balance>;
 
 	/// Adds a vesting schedule to a given account.
 	///
-	/// If there already exists a vesting schedule for the given account, an `Err` is returned
+	/// If the account already has the maximum number of vesting schedules, an `Err` is returned
 	/// and nothing is updated.
+	///
+	/// NOTE: This doesn't alter the free balance of the account.
 	fn add_vesting_schedule(
 		who: &AccountId,
-		locked: Self::Balance,
-		per_block: Self::Balance,
+		locked: <Self::Currency as Currency<AccountId>>::Balance,
+		per_block: <Self::Currency as Currency<AccountId>>::Balance,
 		starting_block: Self::Moment,
 	) -> result::Result<(), &'static str>;
 
-	/// Remove a vesting schedule for a given account.
+	/// Remove all the vesting schedules of a given account, unlocking its funds.
 	fn remove_vesting_schedule(who: &AccountId);
 }
 
@@ -732,12 +779,16 @@ pub trait ChangeMembers<AccountId: Clone + Ord> {
 		}
 		(incoming, outgoing)
 	}
+
+	/// Set the prime member, whose vote is the default for members who abstain.
+	fn set_prime(_prime: Option<AccountId>) {}
 }
 
 impl<T: Clone + Ord> ChangeMembers<T> for () {
 	fn change_members(_: &[T], _: &[T], _: Vec<T>) {}
 	fn change_members_sorted(_: &[T], _: &[T], _: &[T]) {}
 	fn set_members_sorted(_: &[T], _: &[T]) {}
+	fn set_prime(_: Option<T>) {}
 }
 
 /// Trait for type that can handle the initialization of account IDs at genesis.
@@ -777,3 +828,75 @@ pub trait ValidatorRegistration<ValidatorId> {
 	/// module
 	fn is_registered(id: &ValidatorId) -> bool;
 }
+
+/// Traits for dispatching calls at a future block.
+pub mod schedule {
+	use super::*;
+
+	/// Information relating to the period of a scheduled task. First item is the length of the
+	/// period and the second is the number of times it should be executed in total before the task
+	/// is considered finished and removed.
+	pub type Period<BlockNumber> = (BlockNumber, u32);
+
+	/// Priority with which a call is scheduled. It's just a linear amount with lowest values meaning
+	/// higher priority.
+	pub type Priority = u8;
+
+	/// The highest priority. We invert the value so that normal sorting will place the highest
+	/// priority at the beginning of the list.
+	pub const HIGHEST_PRIORITY: Priority = 0;
+	/// Anything of this value or lower will definitely be scheduled on the block that they ask for,
+	/// even if it breaches the `MaximumWeight` limitation.
+	pub const HARD_DEADLINE: Priority = 63;
+	/// The lowest priority. Most stuff should be around here.
+	pub const LOWEST_PRIORITY: Priority = 255;
+
+	/// A type that can be used as a scheduler.
+	pub trait Anon<BlockNumber, Call, Origin> {
+		/// An address which can be used for removing a scheduled task.
+		type Address: Codec + Clone + Eq + Debug;
+
+		/// Schedule a one-off dispatch to happen at the beginning of some block in the future,
+		/// dispatched with `origin`.
+		///
+		/// This is not named.
+		fn schedule(
+			when: BlockNumber,
+			maybe_periodic: Option<Period<BlockNumber>>,
+			priority: Priority,
+			origin: Origin,
+			call: Call,
+		) -> result::Result<Self::Address, &'static str>;
+
+		/// Cancel a scheduled task. If periodic, then it will cancel all further instances of that,
+		/// also.
+		///
+		/// Will return an error if the `address` is invalid or the task has already run.
+		fn cancel(address: Self::Address) -> result::Result<(), &'static str>;
+	}
+
+	/// A type that can be used as a scheduler of named tasks.
+	pub trait Named<BlockNumber, Call, Origin> {
+		/// An address which can be used for removing a scheduled task.
+		type Address: Codec + Clone + Eq + Debug;
+
+		/// Schedule a one-off dispatch to happen at the beginning of some block in the future,
+		/// dispatched with `origin`. The task can later be cancelled by its `id`.
+		///
+		/// Will return an error if a task with the same `id` is already scheduled.
+		fn schedule_named(
+			id: Vec<u8>,
+			when: BlockNumber,
+			maybe_periodic: Option<Period<BlockNumber>>,
+			priority: Priority,
+			origin: Origin,
+			call: Call,
+		) -> result::Result<Self::Address, &'static str>;
+
+		/// Cancel a scheduled, named task. If periodic, then it will cancel all further instances
+		/// of that, also.
+		///
+		/// Will return an error if the `id` is invalid or the task has already run.
+		fn cancel_named(id: Vec<u8>) -> result::Result<(), &'static str>;
+	}
+}
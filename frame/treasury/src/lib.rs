@@ -31,6 +31,26 @@
 //! By way of example, the Council could vote to fund the Treasury with a portion of the block
 //! reward and use the funds to pay developers.
 //!
+//! ### Tipping
+//!
+//! A separate subsystem exists to allow for an agile "tipping" process, whereby a reward may be
+//! given without first having a pre-determined stakeholder group come to consensus on how much
+//! should be paid.
+//!
+//! A group of `Tippers` is determined through the config `Trait`. After half of these have declared
+//! some amount that they believe a particular reported reason deserves, then a countdown period is
+//! entered where any remaining members can declare their tip amounts also. After the close of the
+//! countdown period, the median of all declared tips is paid to the reported beneficiary, along
+//! with any finders fee, in case of a public (and bonded) original report.
+//!
+//! ### Bounties
+//!
+//! A bounty is a reward for a specified body of work. Once approved by the `ApproveOrigin`, it is
+//! funded at the next spend period into an account of its own. A curator is then proposed, together
+//! with the fee they will earn, and must accept the role. The curator decides when the work is done
+//! and who should be paid for it; the payout can be claimed after a delay, during which the
+//! `RejectOrigin` may still step in.
+//!
 //! ### Terminology
 //!
 //! - **Proposal:** A suggestion to allocate funds from the pot to a beneficiary.
@@ -41,16 +61,54 @@
 //! respectively.
 //! - **Pot:** Unspent funds accumulated by the treasury module.
 //!
+//! Tipping protocol:
+//! - **Tipping:** The process of gathering declarations of amounts to tip and taking the median
+//!   amount to be transferred from the treasury to a beneficiary account.
+//! - **Tip Reason:** The reason for a tip; generally a URL which embodies or explains why a
+//!   particular individual (identified by an account ID) is worthy of a recognition by the
+//!   treasury.
+//! - **Finder:** The original public reporter of some reason for tipping.
+//! - **Finders Fee:** Some proportion of the tip amount that is paid to the reporter of the tip,
+//!   rather than the main beneficiary.
+//!
+//! Bounty protocol:
+//! - **Bounty:** A reward for a predefined body of work, paid from its own funded account.
+//! - **Curator:** An account managing the bounty and assigning a payout address once the work is
+//!   done.
+//! - **Curator Fee:** The reserved upfront payment for a curator for work related to the bounty.
+//! - **Payout Delay:** The delay period for which a bounty beneficiary needs to wait before
+//!   claiming.
+//!
 //! ## Interface
 //!
 //! ### Dispatchable Functions
 //!
+//! General spending/proposal protocol:
 //! - `propose_spend` - Make a spending proposal and stake the required deposit.
 //! - `set_pot` - Set the spendable balance of funds.
 //! - `configure` - Configure the module's proposal requirements.
 //! - `reject_proposal` - Reject a proposal, slashing the deposit.
 //! - `approve_proposal` - Accept the proposal, returning the deposit.
 //!
+//! Tipping protocol:
+//! - `report_awesome` - Report something worthy of a tip and register for a finders fee.
+//! - `retract_tip` - Retract a previous (finders fee registered) report.
+//! - `tip_new` - Report an item worthy of a tip and declare a specific amount to tip.
+//! - `tip` - Declare or redeclare an amount to tip for a particular reason.
+//! - `close_tip` - Close and pay out a tip.
+//!
+//! Bounty protocol:
+//! - `propose_bounty` - Propose a specific treasury amount to be earmarked for a predefined set of
+//!   tasks and stake the required deposit.
+//! - `approve_bounty` - Accept a specific treasury amount to be earmarked for a predefined body of
+//!   work.
+//! - `propose_curator` - Assign an account to a bounty as candidate curator.
+//! - `accept_curator` - Accept a bounty assignment from the `ApproveOrigin`.
+//! - `unassign_curator` - Unassign the curator of a bounty.
+//! - `award_bounty` - Close and pay out the specified amount for the completed work.
+//! - `claim_bounty` - Claim a specific bounty amount from the payout address.
+//! - `close_bounty` - Cancel the earmark for a specific treasury amount and close the bounty.
+//!
 //! ## GenesisConfig
 //!
 //! The Treasury module depends on the [`GenesisConfig`](./struct.GenesisConfig.html).
@@ -63,11 +121,11 @@ use sp_std::prelude::*;
 use support::{decl_module, decl_storage, decl_event, ensure, print};
 use support::traits::{
 	Currency, ExistenceRequirement, Get, Imbalance, OnUnbalanced,
-	ReservableCurrency, WithdrawReason
+	ReservableCurrency, WithdrawReason, Contains,
 };
-use sp_runtime::{Permill, ModuleId};
+use sp_runtime::{Permill, ModuleId, RuntimeDebug};
 use sp_runtime::traits::{
-	Zero, EnsureOrigin, StaticLookup, AccountIdConversion, Saturating
+	Zero, EnsureOrigin, StaticLookup, AccountIdConversion, Saturating, Hash,
 };
 use support::weights::SimpleDispatchInfo;
 use codec::{Encode, Decode};
@@ -89,6 +147,33 @@ pub trait Trait: system::Trait {
 	/// Origin from which rejections must come.
 	type RejectOrigin: EnsureOrigin<Self::Origin>;
 
+	/// Origin from which tippers must come.
+	type Tippers: Contains<Self::AccountId>;
+
+	/// The period for which a tip remains open after is has achieved threshold tippers.
+	type TipCountdown: Get<Self::BlockNumber>;
+
+	/// The percent of the final tip which goes to the original reporter of the tip.
+	type TipFindersFee: Get<Permill>;
+
+	/// The amount held on deposit for placing a tip report.
+	type TipReportDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount held on deposit per byte within the tip report reason or bounty description.
+	type DataDepositPerByte: Get<BalanceOf<Self>>;
+
+	/// Maximum acceptable reason length for tips and bounties.
+	type MaximumReasonLength: Get<u32>;
+
+	/// The amount held on deposit for placing a bounty proposal.
+	type BountyDepositBase: Get<BalanceOf<Self>>;
+
+	/// The delay period for which a bounty beneficiary need to wait before claim the payout.
+	type BountyDepositPayoutDelay: Get<Self::BlockNumber>;
+
+	/// Minimum value for a bounty.
+	type BountyValueMinimum: Get<BalanceOf<Self>>;
+
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 
@@ -111,6 +196,9 @@ pub trait Trait: system::Trait {
 
 type ProposalIndex = u32;
 
+/// An index of a bounty. Just a `u32`.
+pub type BountyIndex = u32;
+
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		/// Fraction of a proposal's value that should be bonded in order to place the proposal.
@@ -126,6 +214,30 @@ decl_module! {
 		/// Percentage of spare funds (if any) that are burnt per spend period.
 		const Burn: Permill = T::Burn::get();
 
+		/// The period for which a tip remains open after is has achieved threshold tippers.
+		const TipCountdown: T::BlockNumber = T::TipCountdown::get();
+
+		/// The amount of the final tip which goes to the original reporter of the tip.
+		const TipFindersFee: Permill = T::TipFindersFee::get();
+
+		/// The amount held on deposit for placing a tip report.
+		const TipReportDepositBase: BalanceOf<T> = T::TipReportDepositBase::get();
+
+		/// The amount held on deposit per byte within the tip report reason or bounty description.
+		const DataDepositPerByte: BalanceOf<T> = T::DataDepositPerByte::get();
+
+		/// Maximum acceptable reason length for tips and bounties.
+		const MaximumReasonLength: u32 = T::MaximumReasonLength::get();
+
+		/// The amount held on deposit for placing a bounty proposal.
+		const BountyDepositBase: BalanceOf<T> = T::BountyDepositBase::get();
+
+		/// The delay period for which a bounty beneficiary need to wait before claim the payout.
+		const BountyDepositPayoutDelay: T::BlockNumber = T::BountyDepositPayoutDelay::get();
+
+		/// Minimum value for a bounty.
+		const BountyValueMinimum: BalanceOf<T> = T::BountyValueMinimum::get();
+
 		fn deposit_event() = default;
 		/// Put forward a suggestion for spending. A deposit proportional to the value
 		/// is reserved and slashed if the proposal is rejected. It is returned once the
@@ -190,6 +302,457 @@ decl_module! {
 			Approvals::mutate(|v| v.push(proposal_id));
 		}
 
+		/// Report something `reason` that deserves a tip and claim any eventual the finder's fee.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Payment: `TipReportDepositBase` will be reserved from the origin account, as well as
+		/// `DataDepositPerByte` for each byte in `reason`.
+		///
+		/// - `reason`: The reason for, or the thing that deserves, the tip; generally this will be
+		///   a UTF-8-encoded URL.
+		/// - `who`: The account which should be credited for the tip.
+		///
+		/// Emits `NewTip` if successful.
+		///
+		/// # <weight>
+		/// - `O(R)` where `R` length of `reason`.
+		/// - One balance operation.
+		/// - One storage mutation (codec `O(R)`).
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn report_awesome(origin, reason: Vec<u8>, who: T::AccountId) {
+			let finder = ensure_signed(origin)?;
+
+			ensure!(reason.len() <= T::MaximumReasonLength::get() as usize, "reason too big");
+
+			let reason_hash = T::Hashing::hash(&reason[..]);
+			ensure!(!Reasons::<T>::exists(&reason_hash), "reason already noted");
+			let hash = T::Hashing::hash_of(&(&reason_hash, &who));
+			ensure!(!Tips::<T>::exists(&hash), "tip already exists");
+
+			let deposit = T::TipReportDepositBase::get()
+				+ T::DataDepositPerByte::get() * (reason.len() as u32).into();
+			T::Currency::reserve(&finder, deposit)?;
+
+			Reasons::<T>::insert(&reason_hash, &reason);
+			let finder = Some((finder, deposit));
+			let tip = OpenTip { reason: reason_hash, who, finder, closes: None, tips: vec![] };
+			Tips::<T>::insert(&hash, tip);
+			Self::deposit_event(RawEvent::NewTip(hash));
+		}
+
+		/// Retract a prior tip-report from `report_awesome`, and cancel the process of tipping.
+		///
+		/// If successful, the original deposit will be unreserved.
+		///
+		/// The dispatch origin for this call must be _Signed_ and the tip identified by `hash`
+		/// must have been reported by the signing account through `report_awesome` (and not
+		/// through `tip_new`).
+		///
+		/// - `hash`: The identity of the open tip for which a tip value is declared. This is formed
+		///   as the hash of the tuple of the original tip `reason` and the beneficiary account ID.
+		///
+		/// Emits `TipRetracted` if successful.
+		///
+		/// # <weight>
+		/// - `O(T)`
+		/// - One balance operation.
+		/// - Two storage removals (one read, codec `O(T)`).
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn retract_tip(origin, hash: T::Hash) {
+			let who = ensure_signed(origin)?;
+			let tip = Tips::<T>::get(&hash).ok_or("unknown tip")?;
+			let (finder, deposit) = tip.finder.ok_or("tip has no finder")?;
+			ensure!(finder == who, "not the finder");
+
+			Reasons::<T>::remove(&tip.reason);
+			Tips::<T>::remove(&hash);
+			let _ = T::Currency::unreserve(&who, deposit);
+			Self::deposit_event(RawEvent::TipRetracted(hash));
+		}
+
+		/// Give a tip for something new; no finder's fee will be taken.
+		///
+		/// The dispatch origin for this call must be _Signed_ and the signing account must be a
+		/// member of the `Tippers` set.
+		///
+		/// - `reason`: The reason for, or the thing that deserves, the tip; generally this will be
+		///   a UTF-8-encoded URL.
+		/// - `who`: The account which should be credited for the tip.
+		/// - `tip_value`: The amount of tip that the sender would like to give. The median tip
+		///   value of active tippers will be given to the `who`.
+		///
+		/// Emits `NewTip` if successful.
+		///
+		/// # <weight>
+		/// - `O(R + T)` where `R` length of `reason`, `T` is the number of tippers. `T` is
+		///   naturally capped as a membership set, `R` is limited through transaction-size.
+		/// - Two storage insertions (codecs `O(R)`, `O(T)`), one read `O(1)`.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(150_000)]
+		fn tip_new(origin, reason: Vec<u8>, who: T::AccountId, tip_value: BalanceOf<T>) {
+			let tipper = ensure_signed(origin)?;
+			ensure!(T::Tippers::contains(&tipper), "not a tipper");
+			ensure!(reason.len() <= T::MaximumReasonLength::get() as usize, "reason too big");
+
+			let reason_hash = T::Hashing::hash(&reason[..]);
+			ensure!(!Reasons::<T>::exists(&reason_hash), "reason already noted");
+			let hash = T::Hashing::hash_of(&(&reason_hash, &who));
+			ensure!(!Tips::<T>::exists(&hash), "tip already exists");
+
+			Reasons::<T>::insert(&reason_hash, &reason);
+			Self::deposit_event(RawEvent::NewTip(hash.clone()));
+			let tips = vec![(tipper, tip_value)];
+			let tip = OpenTip { reason: reason_hash, who, finder: None, closes: None, tips };
+			Tips::<T>::insert(&hash, tip);
+		}
+
+		/// Declare a tip value for an already-open tip.
+		///
+		/// The dispatch origin for this call must be _Signed_ and the signing account must be a
+		/// member of the `Tippers` set.
+		///
+		/// - `hash`: The identity of the open tip for which a tip value is declared. This is formed
+		///   as the hash of the tuple of the hash of the original tip `reason` and the beneficiary
+		///   account ID.
+		/// - `tip_value`: The amount of tip that the sender would like to give. The median tip
+		///   value of active tippers will be given to the `who`.
+		///
+		/// Emits `TipClosing` if the threshold of tippers has been reached and the countdown period
+		/// has started.
+		///
+		/// # <weight>
+		/// - `O(T)`
+		/// - One storage mutation (codec `O(T)`), one storage read `O(1)`.
+		/// - Up to one event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn tip(origin, hash: T::Hash, tip_value: BalanceOf<T>) {
+			let tipper = ensure_signed(origin)?;
+			ensure!(T::Tippers::contains(&tipper), "not a tipper");
+
+			let mut tip = Tips::<T>::get(&hash).ok_or("unknown tip")?;
+			if Self::insert_tip_and_check_closing(&mut tip, tipper, tip_value) {
+				Self::deposit_event(RawEvent::TipClosing(hash.clone()));
+			}
+			Tips::<T>::insert(&hash, tip);
+		}
+
+		/// Close and payout a tip.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// The tip identified by `hash` must have finished its countdown period.
+		///
+		/// - `hash`: The identity of the open tip for which a tip value is declared. This is formed
+		///   as the hash of the tuple of the original tip `reason` and the beneficiary account ID.
+		///
+		/// # <weight>
+		/// - `O(T)`
+		/// - One storage retrieval (codec `O(T)`) and two removals.
+		/// - Up to three balance operations.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn close_tip(origin, hash: T::Hash) {
+			ensure_signed(origin)?;
+
+			let tip = Tips::<T>::get(&hash).ok_or("unknown tip")?;
+			let n = tip.closes.as_ref().ok_or("tip not yet closing")?;
+			ensure!(system::Module::<T>::block_number() >= *n, "tip closed prematurely");
+			// closed.
+			Reasons::<T>::remove(&tip.reason);
+			Tips::<T>::remove(&hash);
+			Self::payout_tip(hash, tip);
+		}
+
+		/// Propose a new bounty.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Payment: `BountyDepositBase` will be reserved from the origin account, as well as
+		/// `DataDepositPerByte` for each byte in `description`. It will be unreserved upon
+		/// approval, or slashed when rejected.
+		///
+		/// - `value`: The total payment amount of this bounty, curator fee included.
+		/// - `description`: The description of this bounty.
+		///
+		/// Emits `BountyProposed` if successful.
+		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		fn propose_bounty(
+			origin,
+			#[compact] value: BalanceOf<T>,
+			description: Vec<u8>,
+		) {
+			let proposer = ensure_signed(origin)?;
+
+			ensure!(description.len() <= T::MaximumReasonLength::get() as usize, "reason too big");
+			ensure!(value >= T::BountyValueMinimum::get(), "invalid bounty value");
+
+			let bond = T::BountyDepositBase::get()
+				+ T::DataDepositPerByte::get() * (description.len() as u32).into();
+			T::Currency::reserve(&proposer, bond)
+				.map_err(|_| "Proposer's balance too low")?;
+
+			let index = Self::bounty_count();
+			BountyCount::put(index + 1);
+
+			let bounty = Bounty {
+				proposer,
+				value,
+				fee: Zero::zero(),
+				bond,
+				status: BountyStatus::Proposed,
+			};
+			<Bounties<T>>::insert(index, &bounty);
+			BountyDescriptions::insert(index, description);
+
+			Self::deposit_event(RawEvent::BountyProposed(index));
+		}
+
+		/// Approve a bounty proposal. At a later time, the bounty will be funded and become active
+		/// and the original deposit will be returned.
+		///
+		/// The dispatch origin for this call must be the `ApproveOrigin`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Limited storage reads.
+		/// - One DB change.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
+		fn approve_bounty(origin, #[compact] bounty_id: BountyIndex) {
+			T::ApproveOrigin::ensure_origin(origin)?;
+
+			let mut bounty = <Bounties<T>>::get(bounty_id).ok_or("No bounty at that index")?;
+			ensure!(bounty.status == BountyStatus::Proposed, "unexpected bounty status");
+
+			bounty.status = BountyStatus::Approved;
+			<Bounties<T>>::insert(bounty_id, &bounty);
+			BountyApprovals::mutate(|v| v.push(bounty_id));
+		}
+
+		/// Assign a curator to a funded bounty, replacing any curator not yet accepted.
+		///
+		/// The dispatch origin for this call must be the `ApproveOrigin`.
+		///
+		/// - `curator`: The account proposed to curate the bounty.
+		/// - `fee`: The fee paid to the curator out of the bounty; must be less than its value.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Limited storage reads.
+		/// - One DB change.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
+		fn propose_curator(
+			origin,
+			#[compact] bounty_id: BountyIndex,
+			curator: <T::Lookup as StaticLookup>::Source,
+			#[compact] fee: BalanceOf<T>,
+		) {
+			T::ApproveOrigin::ensure_origin(origin)?;
+
+			let curator = T::Lookup::lookup(curator)?;
+			let mut bounty = <Bounties<T>>::get(bounty_id).ok_or("No bounty at that index")?;
+			match bounty.status {
+				BountyStatus::Funded | BountyStatus::CuratorProposed { .. } => {},
+				_ => Err("unexpected bounty status")?,
+			}
+			ensure!(fee < bounty.value, "invalid fee");
+
+			bounty.status = BountyStatus::CuratorProposed { curator };
+			bounty.fee = fee;
+			<Bounties<T>>::insert(bounty_id, &bounty);
+		}
+
+		/// Unassign the curator of a bounty, returning the bounty to the funded state so that a
+		/// new curator may be proposed.
+		///
+		/// The dispatch origin for this call must be the `RejectOrigin`. A bounty which is pending
+		/// payout may be unassigned until it is claimed, which cancels the award.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Limited storage reads.
+		/// - One DB change.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
+		fn unassign_curator(origin, #[compact] bounty_id: BountyIndex) {
+			T::RejectOrigin::ensure_origin(origin)?;
+
+			let mut bounty = <Bounties<T>>::get(bounty_id).ok_or("No bounty at that index")?;
+			match bounty.status {
+				BountyStatus::CuratorProposed { .. } |
+				BountyStatus::Active { .. } |
+				BountyStatus::PendingPayout { .. } => {},
+				_ => Err("unexpected bounty status")?,
+			}
+
+			bounty.status = BountyStatus::Funded;
+			<Bounties<T>>::insert(bounty_id, &bounty);
+		}
+
+		/// Accept the curator role for a bounty.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the proposed curator.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Limited storage reads.
+		/// - One DB change.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn accept_curator(origin, #[compact] bounty_id: BountyIndex) {
+			let signer = ensure_signed(origin)?;
+
+			let mut bounty = <Bounties<T>>::get(bounty_id).ok_or("No bounty at that index")?;
+			let curator = match bounty.status {
+				BountyStatus::CuratorProposed { curator } => curator,
+				_ => Err("unexpected bounty status")?,
+			};
+			ensure!(signer == curator, "require curator");
+
+			bounty.status = BountyStatus::Active { curator };
+			<Bounties<T>>::insert(bounty_id, &bounty);
+		}
+
+		/// Award a bounty to a beneficiary account. The beneficiary will be able to claim the funds
+		/// after a delay of `BountyDepositPayoutDelay`.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the curator of this bounty.
+		///
+		/// - `bounty_id`: Bounty ID to award.
+		/// - `beneficiary`: The beneficiary account whom will receive the payout.
+		///
+		/// Emits `BountyAwarded` if successful.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Limited storage reads.
+		/// - One DB change.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn award_bounty(
+			origin,
+			#[compact] bounty_id: BountyIndex,
+			beneficiary: <T::Lookup as StaticLookup>::Source,
+		) {
+			let signer = ensure_signed(origin)?;
+			let beneficiary = T::Lookup::lookup(beneficiary)?;
+
+			let mut bounty = <Bounties<T>>::get(bounty_id).ok_or("No bounty at that index")?;
+			let curator = match bounty.status {
+				BountyStatus::Active { curator } => curator,
+				_ => Err("unexpected bounty status")?,
+			};
+			ensure!(signer == curator, "require curator");
+
+			let unlock_at = system::Module::<T>::block_number() + T::BountyDepositPayoutDelay::get();
+			bounty.status = BountyStatus::PendingPayout {
+				curator,
+				beneficiary: beneficiary.clone(),
+				unlock_at,
+			};
+			<Bounties<T>>::insert(bounty_id, &bounty);
+
+			Self::deposit_event(RawEvent::BountyAwarded(bounty_id, beneficiary));
+		}
+
+		/// Claim the payout from an awarded bounty after the payout delay.
+		///
+		/// The dispatch origin for this call must be _Signed_; anyone may trigger the payout.
+		///
+		/// The curator fee is paid to the curator, and the rest of the bounty account's funds to
+		/// the beneficiary.
+		///
+		/// Emits `BountyClaimed` if successful.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Limited storage reads.
+		/// - Two balance operations, two DB clears.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn claim_bounty(origin, #[compact] bounty_id: BountyIndex) {
+			ensure_signed(origin)?;
+
+			let bounty = <Bounties<T>>::get(bounty_id).ok_or("No bounty at that index")?;
+			let (curator, beneficiary, unlock_at) = match bounty.status {
+				BountyStatus::PendingPayout { curator, beneficiary, unlock_at } =>
+					(curator, beneficiary, unlock_at),
+				_ => Err("unexpected bounty status")?,
+			};
+			ensure!(system::Module::<T>::block_number() >= unlock_at, "bounty claimed prematurely");
+
+			let bounty_account = Self::bounty_account_id(bounty_id);
+			let balance = T::Currency::free_balance(&bounty_account);
+			let fee = bounty.fee.min(balance);
+			let payout = balance.saturating_sub(fee);
+			// These must succeed, as the bounty account holds exactly `fee + payout`.
+			let _ = T::Currency::transfer(&bounty_account, &curator, fee, ExistenceRequirement::AllowDeath);
+			let _ = T::Currency::transfer(&bounty_account, &beneficiary, payout, ExistenceRequirement::AllowDeath);
+
+			<Bounties<T>>::remove(bounty_id);
+			BountyDescriptions::remove(bounty_id);
+
+			Self::deposit_event(RawEvent::BountyClaimed(bounty_id, payout, beneficiary));
+		}
+
+		/// Cancel a bounty.
+		///
+		/// A bounty which is only proposed has its deposit slashed. A funded bounty returns its
+		/// funds to the treasury, even if it is pending payout as long as it isn't claimed.
+		/// Approved bounties awaiting funding cannot be cancelled.
+		///
+		/// The dispatch origin for this call must be the `RejectOrigin`.
+		///
+		/// Emits `BountyRejected` or `BountyCanceled` if successful.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Limited storage reads.
+		/// - Up to one balance operation, two DB clears.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
+		fn close_bounty(origin, #[compact] bounty_id: BountyIndex) {
+			T::RejectOrigin::ensure_origin(origin)?;
+
+			let bounty = <Bounties<T>>::get(bounty_id).ok_or("No bounty at that index")?;
+			match bounty.status {
+				BountyStatus::Proposed => {
+					// The reject origin would like to cancel a proposed bounty.
+					let value = bounty.bond;
+					let imbalance = T::Currency::slash_reserved(&bounty.proposer, value).0;
+					T::ProposalRejection::on_unbalanced(imbalance);
+					Self::deposit_event(RawEvent::BountyRejected(bounty_id, value));
+				}
+				BountyStatus::Approved => Err("unexpected bounty status")?,
+				BountyStatus::Funded |
+				BountyStatus::CuratorProposed { .. } |
+				BountyStatus::Active { .. } |
+				BountyStatus::PendingPayout { .. } => {
+					let bounty_account = Self::bounty_account_id(bounty_id);
+					let balance = T::Currency::free_balance(&bounty_account);
+					// The bounty account only ever holds the bounty funds, so it may be removed.
+					let _ = T::Currency::transfer(
+						&bounty_account,
+						&Self::account_id(),
+						balance,
+						ExistenceRequirement::AllowDeath,
+					);
+					Self::deposit_event(RawEvent::BountyCanceled(bounty_id));
+				}
+			}
+
+			<Bounties<T>>::remove(bounty_id);
+			BountyDescriptions::remove(bounty_id);
+		}
+
 		fn on_finalize(n: T::BlockNumber) {
 			// Check to see if we should spend some funds!
 			if (n % T::SpendPeriod::get()).is_zero() {
@@ -201,7 +764,7 @@ decl_module! {
 
 /// A spending proposal.
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-#[derive(Encode, Decode, Clone, PartialEq, Eq, sp_runtime::RuntimeDebug)]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
 pub struct Proposal<AccountId, Balance> {
 	proposer: AccountId,
 	value: Balance,
@@ -209,6 +772,70 @@ pub struct Proposal<AccountId, Balance> {
 	bond: Balance,
 }
 
+/// An open tipping "motion". Retains all details of a tip including information on the finder
+/// and the members who have voted.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct OpenTip<AccountId, Balance, BlockNumber, Hash> {
+	/// The hash of the reason for the tip. The reason should be a human-readable UTF-8 encoded
+	/// string. A URL would be sensible.
+	reason: Hash,
+	/// The account to be tipped.
+	who: AccountId,
+	/// The account who began this tip and the amount held on deposit.
+	finder: Option<(AccountId, Balance)>,
+	/// The block number at which this tip will close if `Some`. If `None`, then no closing is
+	/// scheduled.
+	closes: Option<BlockNumber>,
+	/// The members who have voted for this tip. Sorted by AccountId.
+	tips: Vec<(AccountId, Balance)>,
+}
+
+/// A bounty proposal.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Bounty<AccountId, Balance, BlockNumber> {
+	/// The account proposing it.
+	proposer: AccountId,
+	/// The (total) amount that should be paid if the bounty is rewarded.
+	value: Balance,
+	/// The curator fee. Included in value.
+	fee: Balance,
+	/// The amount held on deposit (reserved) for making this proposal.
+	bond: Balance,
+	/// The status of this bounty.
+	status: BountyStatus<AccountId, BlockNumber>,
+}
+
+/// The status of a bounty proposal.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum BountyStatus<AccountId, BlockNumber> {
+	/// The bounty is proposed and waiting for approval.
+	Proposed,
+	/// The bounty is approved and waiting to be funded at the next spend period.
+	Approved,
+	/// The bounty is funded and waiting for curator assignment.
+	Funded,
+	/// A curator has been proposed by the `ApproveOrigin`. Waiting for acceptance from the
+	/// curator.
+	CuratorProposed {
+		/// The assigned curator of this bounty.
+		curator: AccountId,
+	},
+	/// The bounty is active and waiting to be awarded.
+	Active {
+		/// The curator of this bounty.
+		curator: AccountId,
+	},
+	/// The bounty is awarded and waiting to be released after a delay.
+	PendingPayout {
+		/// The curator of this bounty.
+		curator: AccountId,
+		/// The beneficiary of the bounty.
+		beneficiary: AccountId,
+		/// When the bounty can be claimed.
+		unlock_at: BlockNumber,
+	},
+}
+
 decl_storage! {
 	trait Store for Module<T: Trait> as Treasury {
 		/// Number of proposals that have been made.
@@ -219,6 +846,29 @@ decl_storage! {
 
 		/// Proposal indices that have been approved but not yet awarded.
 		Approvals get(fn approvals): Vec<ProposalIndex>;
+
+		/// Tips that are not yet completed. Keyed by the hash of `(reason, who)` from the value.
+		/// This has the insecure enumerable hash function since the key itself is already
+		/// guaranteed to be a secure hash.
+		pub Tips get(fn tips): map hasher(twox_64_concat) T::Hash
+			=> Option<OpenTip<T::AccountId, BalanceOf<T>, T::BlockNumber, T::Hash>>;
+
+		/// Simple preimage lookup from the reason's hash to the original data. Again, has an
+		/// insecure enumerable hash since the key is guaranteed to be the result of a secure hash.
+		pub Reasons get(fn reasons): map hasher(twox_64_concat) T::Hash => Option<Vec<u8>>;
+
+		/// Number of bounty proposals that have been made.
+		pub BountyCount get(fn bounty_count): BountyIndex;
+
+		/// Bounties that have been made.
+		pub Bounties get(fn bounties):
+			map BountyIndex => Option<Bounty<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+
+		/// The description of each bounty.
+		pub BountyDescriptions get(fn bounty_descriptions): map BountyIndex => Option<Vec<u8>>;
+
+		/// Bounty indices that have been approved but not yet funded.
+		pub BountyApprovals get(fn bounty_approvals): Vec<BountyIndex>;
 	}
 	add_extra_genesis {
 		build(|_config| {
@@ -235,7 +885,8 @@ decl_event!(
 	pub enum Event<T>
 	where
 		Balance = BalanceOf<T>,
-		<T as system::Trait>::AccountId
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
 	{
 		/// New proposal.
 		Proposed(ProposalIndex),
@@ -249,6 +900,26 @@ decl_event!(
 		Rollover(Balance),
 		/// Some funds have been deposited.
 		Deposit(Balance),
+		/// A new tip suggestion has been opened.
+		NewTip(Hash),
+		/// A tip suggestion has reached threshold and is closing.
+		TipClosing(Hash),
+		/// A tip suggestion has been closed.
+		TipClosed(Hash, AccountId, Balance),
+		/// A tip suggestion has been retracted.
+		TipRetracted(Hash),
+		/// New bounty proposal.
+		BountyProposed(BountyIndex),
+		/// A bounty proposal was rejected; funds were slashed.
+		BountyRejected(BountyIndex, Balance),
+		/// A bounty proposal is funded and became active.
+		BountyBecameActive(BountyIndex),
+		/// A bounty is awarded to a beneficiary.
+		BountyAwarded(BountyIndex, AccountId),
+		/// A bounty is claimed by beneficiary.
+		BountyClaimed(BountyIndex, Balance, AccountId),
+		/// A bounty is cancelled.
+		BountyCanceled(BountyIndex),
 	}
 );
 
@@ -263,11 +934,89 @@ impl<T: Trait> Module<T> {
 		MODULE_ID.into_account()
 	}
 
+	/// The account ID of a bounty account.
+	pub fn bounty_account_id(id: BountyIndex) -> T::AccountId {
+		// only use two byte prefix to support 16 byte account id (used by test)
+		// "modl" ++ "py/trsry" ++ "bt" is 14 bytes, and two bytes remaining for bounty index
+		MODULE_ID.into_sub_account(("bt", id))
+	}
+
 	/// The needed bond for a proposal whose spend is `value`.
 	fn calculate_bond(value: BalanceOf<T>) -> BalanceOf<T> {
 		T::ProposalBondMinimum::get().max(T::ProposalBond::get() * value)
 	}
 
+	/// Given a mutable reference to an `OpenTip`, insert the tip into it and check whether it
+	/// closes, if so, then deposit the relevant event and set closing accordingly.
+	///
+	/// `O(T)` and one storage access.
+	fn insert_tip_and_check_closing(
+		tip: &mut OpenTip<T::AccountId, BalanceOf<T>, T::BlockNumber, T::Hash>,
+		tipper: T::AccountId,
+		tip_value: BalanceOf<T>,
+	) -> bool {
+		match tip.tips.binary_search_by_key(&&tipper, |x| &x.0) {
+			Ok(pos) => tip.tips[pos] = (tipper, tip_value),
+			Err(pos) => tip.tips.insert(pos, (tipper, tip_value)),
+		}
+		Self::retain_active_tips(&mut tip.tips);
+		let threshold = (T::Tippers::count() + 1) / 2;
+		if tip.tips.len() >= threshold && tip.closes.is_none() {
+			tip.closes = Some(system::Module::<T>::block_number() + T::TipCountdown::get());
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Remove any non-members of `Tippers` from a `tips` vector. `O(T)`.
+	fn retain_active_tips(tips: &mut Vec<(T::AccountId, BalanceOf<T>)>) {
+		let members = T::Tippers::sorted_members();
+		let mut members_iter = members.iter();
+		let mut member = members_iter.next();
+		tips.retain(|(a, _)| loop {
+			match member {
+				None => break false,
+				Some(m) if m > a => break false,
+				Some(m) => {
+					member = members_iter.next();
+					if m < a {
+						continue
+					} else {
+						break true;
+					}
+				}
+			}
+		});
+	}
+
+	/// Execute the payout of a tip.
+	///
+	/// Up to three balance operations.
+	/// Plus `O(T)` (`T` is Tippers length).
+	fn payout_tip(hash: T::Hash, tip: OpenTip<T::AccountId, BalanceOf<T>, T::BlockNumber, T::Hash>) {
+		let mut tips = tip.tips;
+		Self::retain_active_tips(&mut tips);
+		tips.sort_by_key(|i| i.1);
+		let treasury = Self::account_id();
+		let max_payout = Self::pot();
+		let mut payout = tips.get(tips.len() / 2).map_or_else(Zero::zero, |i| i.1).min(max_payout);
+		if let Some((finder, deposit)) = tip.finder {
+			let _ = T::Currency::unreserve(&finder, deposit);
+			if finder != tip.who {
+				// pay out the finder's fee.
+				let finders_fee = T::TipFindersFee::get() * payout;
+				payout -= finders_fee;
+				// this should go through given we checked it's at most the free balance, but still
+				// we only make a best-effort.
+				let _ = T::Currency::transfer(&treasury, &finder, finders_fee, ExistenceRequirement::KeepAlive);
+			}
+		}
+		// same as above: best-effort only.
+		let _ = T::Currency::transfer(&treasury, &tip.who, payout, ExistenceRequirement::KeepAlive);
+		Self::deposit_event(RawEvent::TipClosed(hash, tip.who, payout));
+	}
+
 	// Spend some money!
 	fn spend_funds() {
 		let mut budget_remaining = Self::pot();
@@ -301,6 +1050,36 @@ impl<T: Trait> Module<T> {
 			});
 		});
 
+		BountyApprovals::mutate(|v| {
+			v.retain(|&index| {
+				// Should always be true, but shouldn't panic if false or we're screwed.
+				if let Some(mut bounty) = Self::bounties(index) {
+					if bounty.value <= budget_remaining {
+						budget_remaining -= bounty.value;
+
+						bounty.status = BountyStatus::Funded;
+						// return their deposit.
+						let _ = T::Currency::unreserve(&bounty.proposer, bounty.bond);
+						<Bounties<T>>::insert(index, &bounty);
+
+						// fund the bounty account.
+						imbalance.subsume(T::Currency::deposit_creating(
+							&Self::bounty_account_id(index),
+							bounty.value,
+						));
+
+						Self::deposit_event(RawEvent::BountyBecameActive(index));
+						false
+					} else {
+						missed_any = true;
+						true
+					}
+				} else {
+					false
+				}
+			});
+		});
+
 		if !missed_any {
 			// burn some proportion of the remaining budget if we run a surplus.
 			let burn = (T::Burn::get() * budget_remaining).min(budget_remaining);
@@ -351,6 +1130,7 @@ impl<T: Trait> OnUnbalanced<NegativeImbalanceOf<T>> for Module<T> {
 mod tests {
 	use super::*;
 
+	use std::cell::RefCell;
 	use support::{assert_noop, assert_ok, impl_outer_origin, parameter_types, weights::Weight};
 	use primitives::H256;
 	use sp_runtime::{
@@ -376,7 +1156,7 @@ mod tests {
 		type Call = ();
 		type Hash = H256;
 		type Hashing = BlakeTwo256;
-		type AccountId = u64;
+		type AccountId = u128; // u64 is not enough to hold bytes used to generate bounty account
 		type Lookup = IdentityLookup<Self::AccountId>;
 		type Header = Header;
 		type Event = ();
@@ -402,16 +1182,45 @@ mod tests {
 		type TransferFee = TransferFee;
 		type CreationFee = CreationFee;
 	}
+	thread_local! {
+		static TEN_TO_FOURTEEN: RefCell<Vec<u128>> = RefCell::new(vec![10, 11, 12, 13, 14]);
+	}
+	pub struct TenToFourteen;
+	impl Contains<u128> for TenToFourteen {
+		fn contains(n: &u128) -> bool {
+			TEN_TO_FOURTEEN.with(|v| v.borrow().binary_search(n).is_ok())
+		}
+		fn sorted_members() -> Vec<u128> {
+			TEN_TO_FOURTEEN.with(|v| v.borrow().clone())
+		}
+	}
 	parameter_types! {
 		pub const ProposalBond: Permill = Permill::from_percent(5);
 		pub const ProposalBondMinimum: u64 = 1;
 		pub const SpendPeriod: u64 = 2;
 		pub const Burn: Permill = Permill::from_percent(50);
+		pub const TipCountdown: u64 = 1;
+		pub const TipFindersFee: Permill = Permill::from_percent(20);
+		pub const TipReportDepositBase: u64 = 1;
+		pub const DataDepositPerByte: u64 = 1;
+		pub const MaximumReasonLength: u32 = 16384;
+		pub const BountyDepositBase: u64 = 80;
+		pub const BountyDepositPayoutDelay: u64 = 3;
+		pub const BountyValueMinimum: u64 = 1;
 	}
 	impl Trait for Test {
 		type Currency = balances::Module<Test>;
-		type ApproveOrigin = system::EnsureRoot<u64>;
-		type RejectOrigin = system::EnsureRoot<u64>;
+		type ApproveOrigin = system::EnsureRoot<u128>;
+		type RejectOrigin = system::EnsureRoot<u128>;
+		type Tippers = TenToFourteen;
+		type TipCountdown = TipCountdown;
+		type TipFindersFee = TipFindersFee;
+		type TipReportDepositBase = TipReportDepositBase;
+		type DataDepositPerByte = DataDepositPerByte;
+		type MaximumReasonLength = MaximumReasonLength;
+		type BountyDepositBase = BountyDepositBase;
+		type BountyDepositPayoutDelay = BountyDepositPayoutDelay;
+		type BountyValueMinimum = BountyValueMinimum;
 		type Event = ();
 		type ProposalRejection = ();
 		type ProposalBond = ProposalBond;
@@ -419,6 +1228,7 @@ mod tests {
 		type SpendPeriod = SpendPeriod;
 		type Burn = Burn;
 	}
+	type System = system::Module<Test>;
 	type Balances = balances::Module<Test>;
 	type Treasury = Module<Test>;
 
@@ -427,7 +1237,6 @@ mod tests {
 		balances::GenesisConfig::<Test>{
 			// Total issuance will be 200 with treasury account initialized at ED.
 			balances: vec![(0, 100), (1, 98), (2, 1)],
-			vesting: vec![],
 		}.assimilate_storage(&mut t).unwrap();
 		GenesisConfig::default().assimilate_storage::<Test>(&mut t).unwrap();
 		t.into()
@@ -441,6 +1250,141 @@ mod tests {
 		});
 	}
 
+	fn tip_hash() -> H256 {
+		BlakeTwo256::hash_of(&(BlakeTwo256::hash(b"awesome.dot"), 3u128))
+	}
+
+	#[test]
+	fn tip_new_cannot_be_used_twice() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::tip_new(Origin::signed(10), b"awesome.dot".to_vec(), 3, 10));
+			assert_noop!(
+				Treasury::tip_new(Origin::signed(11), b"awesome.dot".to_vec(), 3, 10),
+				"reason already noted",
+			);
+		});
+	}
+
+	#[test]
+	fn report_awesome_and_tip_works() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::report_awesome(Origin::signed(0), b"awesome.dot".to_vec(), 3));
+			assert_eq!(Balances::reserved_balance(&0), 12);
+			assert_eq!(Balances::free_balance(&0), 88);
+
+			// other reports don't count.
+			assert_noop!(
+				Treasury::report_awesome(Origin::signed(1), b"awesome.dot".to_vec(), 3),
+				"reason already noted",
+			);
+
+			let h = tip_hash();
+			assert_ok!(Treasury::tip(Origin::signed(10), h.clone(), 10));
+			assert_ok!(Treasury::tip(Origin::signed(11), h.clone(), 10));
+			assert_ok!(Treasury::tip(Origin::signed(12), h.clone(), 10));
+			assert_noop!(Treasury::tip(Origin::signed(9), h.clone(), 10), "not a tipper");
+			System::set_block_number(2);
+			assert_ok!(Treasury::close_tip(Origin::signed(100), h.into()));
+			assert_eq!(Balances::reserved_balance(&0), 0);
+			assert_eq!(Balances::free_balance(&0), 102);
+			assert_eq!(Balances::free_balance(&3), 8);
+		});
+	}
+
+	#[test]
+	fn report_awesome_from_beneficiary_and_tip_works() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::report_awesome(Origin::signed(0), b"awesome.dot".to_vec(), 0));
+			assert_eq!(Balances::reserved_balance(&0), 12);
+			assert_eq!(Balances::free_balance(&0), 88);
+			let h = BlakeTwo256::hash_of(&(BlakeTwo256::hash(b"awesome.dot"), 0u128));
+			assert_ok!(Treasury::tip(Origin::signed(10), h.clone(), 10));
+			assert_ok!(Treasury::tip(Origin::signed(11), h.clone(), 10));
+			assert_ok!(Treasury::tip(Origin::signed(12), h.clone(), 10));
+			System::set_block_number(2);
+			assert_ok!(Treasury::close_tip(Origin::signed(100), h.into()));
+			assert_eq!(Balances::reserved_balance(&0), 0);
+			assert_eq!(Balances::free_balance(&0), 110);
+		});
+	}
+
+	#[test]
+	fn close_tip_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_eq!(Treasury::pot(), 100);
+
+			assert_ok!(Treasury::tip_new(Origin::signed(10), b"awesome.dot".to_vec(), 3, 10));
+			let h = tip_hash();
+			assert_ok!(Treasury::tip(Origin::signed(11), h.clone(), 10));
+			assert_noop!(Treasury::close_tip(Origin::signed(0), h.into()), "tip not yet closing");
+
+			assert_ok!(Treasury::tip(Origin::signed(12), h.clone(), 10));
+			assert_noop!(Treasury::close_tip(Origin::signed(0), h.into()), "tip closed prematurely");
+
+			System::set_block_number(2);
+			assert_ok!(Treasury::close_tip(Origin::signed(0), h.into()));
+			assert_eq!(Balances::free_balance(&3), 10);
+
+			assert_noop!(Treasury::close_tip(Origin::signed(100), h.into()), "unknown tip");
+		});
+	}
+
+	#[test]
+	fn retract_tip_works() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::report_awesome(Origin::signed(0), b"awesome.dot".to_vec(), 3));
+			let h = tip_hash();
+			assert_ok!(Treasury::tip(Origin::signed(10), h.clone(), 10));
+			assert_ok!(Treasury::tip(Origin::signed(11), h.clone(), 10));
+			assert_ok!(Treasury::tip(Origin::signed(12), h.clone(), 10));
+			assert_noop!(Treasury::retract_tip(Origin::signed(10), h.clone()), "not the finder");
+			assert_ok!(Treasury::retract_tip(Origin::signed(0), h.clone()));
+			assert_eq!(Balances::reserved_balance(&0), 0);
+			System::set_block_number(2);
+			assert_noop!(Treasury::close_tip(Origin::signed(0), h.into()), "unknown tip");
+		});
+	}
+
+	#[test]
+	fn tip_median_calculation_works() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::tip_new(Origin::signed(10), b"awesome.dot".to_vec(), 3, 0));
+			let h = tip_hash();
+			assert_ok!(Treasury::tip(Origin::signed(11), h.clone(), 10));
+			assert_ok!(Treasury::tip(Origin::signed(12), h.clone(), 1000000));
+			System::set_block_number(2);
+			assert_ok!(Treasury::close_tip(Origin::signed(0), h.into()));
+			assert_eq!(Balances::free_balance(&3), 10);
+		});
+	}
+
+	#[test]
+	fn tip_changing_works() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::tip_new(Origin::signed(10), b"awesome.dot".to_vec(), 3, 10000));
+			let h = tip_hash();
+			assert_ok!(Treasury::tip(Origin::signed(11), h.clone(), 10000));
+			assert_ok!(Treasury::tip(Origin::signed(12), h.clone(), 10000));
+			assert_ok!(Treasury::tip(Origin::signed(13), h.clone(), 0));
+			assert_ok!(Treasury::tip(Origin::signed(14), h.clone(), 0));
+			assert_ok!(Treasury::tip(Origin::signed(12), h.clone(), 1000));
+			assert_ok!(Treasury::tip(Origin::signed(11), h.clone(), 100));
+			assert_ok!(Treasury::tip(Origin::signed(10), h.clone(), 10));
+			System::set_block_number(2);
+			assert_ok!(Treasury::close_tip(Origin::signed(0), h.into()));
+			assert_eq!(Balances::free_balance(&3), 10);
+		});
+	}
+
 	#[test]
 	fn minting_works() {
 		new_test_ext().execute_with(|| {
@@ -617,7 +1561,6 @@ mod tests {
 		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 		balances::GenesisConfig::<Test>{
 			balances: vec![(0, 100), (1, 99), (2, 1)],
-			vesting: vec![],
 		}.assimilate_storage(&mut t).unwrap();
 		// Treasury genesis config is not build thus treasury account does not exist
 		let mut t: sp_io::TestExternalities = t.into();
@@ -644,4 +1587,236 @@ mod tests {
 			assert_eq!(Balances::free_balance(&3), 99); // Balance of `3` has changed
 		});
 	}
+
+	#[test]
+	fn propose_bounty_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_eq!(Treasury::pot(), 100);
+
+			assert_ok!(Treasury::propose_bounty(Origin::signed(0), 10, b"1234567890".to_vec()));
+
+			let deposit: u64 = 80 + 10;
+			assert_eq!(Balances::reserved_balance(&0), deposit);
+			assert_eq!(Balances::free_balance(&0), 100 - deposit);
+
+			assert_eq!(Treasury::bounties(0).unwrap(), Bounty {
+				proposer: 0,
+				fee: 0,
+				value: 10,
+				bond: deposit,
+				status: BountyStatus::Proposed,
+			});
+			assert_eq!(Treasury::bounty_descriptions(0).unwrap(), b"1234567890".to_vec());
+			assert_eq!(Treasury::bounty_count(), 1);
+		});
+	}
+
+	#[test]
+	fn propose_bounty_validation_works() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_eq!(Treasury::pot(), 100);
+
+			assert_noop!(
+				Treasury::propose_bounty(Origin::signed(1), 0, [0; 17_000].to_vec()),
+				"reason too big",
+			);
+			assert_noop!(
+				Treasury::propose_bounty(Origin::signed(1), 10, b"12345678901234567890".to_vec()),
+				"Proposer's balance too low",
+			);
+			assert_noop!(
+				Treasury::propose_bounty(Origin::signed(1), 0, b"12345678901234567890".to_vec()),
+				"invalid bounty value",
+			);
+		});
+	}
+
+	#[test]
+	fn close_bounty_works() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_noop!(Treasury::close_bounty(Origin::ROOT, 0), "No bounty at that index");
+
+			assert_ok!(Treasury::propose_bounty(Origin::signed(0), 10, b"12345".to_vec()));
+			assert_ok!(Treasury::close_bounty(Origin::ROOT, 0));
+
+			let deposit: u64 = 80 + 5;
+			assert_eq!(Balances::reserved_balance(&0), 0);
+			assert_eq!(Balances::free_balance(&0), 100 - deposit);
+
+			assert_eq!(Treasury::bounties(0), None);
+			assert_eq!(Treasury::bounty_descriptions(0), None);
+		});
+	}
+
+	#[test]
+	fn approve_bounty_works() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_noop!(Treasury::approve_bounty(Origin::ROOT, 0), "No bounty at that index");
+
+			assert_ok!(Treasury::propose_bounty(Origin::signed(0), 50, b"12345".to_vec()));
+			assert_ok!(Treasury::approve_bounty(Origin::ROOT, 0));
+
+			let deposit: u64 = 80 + 5;
+			assert_eq!(Treasury::bounties(0).unwrap(), Bounty {
+				proposer: 0,
+				fee: 0,
+				value: 50,
+				bond: deposit,
+				status: BountyStatus::Approved,
+			});
+			assert_eq!(Treasury::bounty_approvals(), vec![0]);
+
+			assert_noop!(Treasury::close_bounty(Origin::ROOT, 0), "unexpected bounty status");
+
+			// deposit not returned yet
+			assert_eq!(Balances::reserved_balance(&0), deposit);
+			assert_eq!(Balances::free_balance(&0), 100 - deposit);
+
+			<Treasury as OnFinalize<u64>>::on_finalize(2);
+
+			// return deposit
+			assert_eq!(Balances::reserved_balance(&0), 0);
+			assert_eq!(Balances::free_balance(&0), 100);
+
+			assert_eq!(Treasury::bounties(0).unwrap(), Bounty {
+				proposer: 0,
+				fee: 0,
+				value: 50,
+				bond: deposit,
+				status: BountyStatus::Funded,
+			});
+			assert_eq!(Treasury::pot(), 100 - 50 - 25); // burn 25
+			assert_eq!(Balances::free_balance(&Treasury::bounty_account_id(0)), 50);
+		});
+	}
+
+	#[test]
+	fn assign_curator_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+
+			assert_noop!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4), "No bounty at that index");
+
+			assert_ok!(Treasury::propose_bounty(Origin::signed(0), 50, b"12345".to_vec()));
+			assert_ok!(Treasury::approve_bounty(Origin::ROOT, 0));
+			assert_noop!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4), "unexpected bounty status");
+
+			<Treasury as OnFinalize<u64>>::on_finalize(2);
+
+			assert_noop!(Treasury::propose_curator(Origin::ROOT, 0, 4, 50), "invalid fee");
+			assert_ok!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4));
+
+			assert_eq!(Treasury::bounties(0).unwrap().status, BountyStatus::CuratorProposed {
+				curator: 4,
+			});
+
+			assert_noop!(Treasury::accept_curator(Origin::signed(1), 0), "require curator");
+			assert_ok!(Treasury::accept_curator(Origin::signed(4), 0));
+
+			assert_eq!(Treasury::bounties(0).unwrap().status, BountyStatus::Active {
+				curator: 4,
+			});
+
+			assert_ok!(Treasury::unassign_curator(Origin::ROOT, 0));
+			assert_eq!(Treasury::bounties(0).unwrap().status, BountyStatus::Funded);
+		});
+	}
+
+	#[test]
+	fn award_and_claim_bounty_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			Balances::make_free_balance_be(&4, 10);
+			assert_ok!(Treasury::propose_bounty(Origin::signed(0), 50, b"12345".to_vec()));
+			assert_ok!(Treasury::approve_bounty(Origin::ROOT, 0));
+
+			<Treasury as OnFinalize<u64>>::on_finalize(2);
+
+			assert_ok!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4));
+			assert_ok!(Treasury::accept_curator(Origin::signed(4), 0));
+
+			assert_noop!(Treasury::award_bounty(Origin::signed(1), 0, 3), "require curator");
+			assert_ok!(Treasury::award_bounty(Origin::signed(4), 0, 3));
+
+			assert_eq!(Treasury::bounties(0).unwrap().status, BountyStatus::PendingPayout {
+				curator: 4,
+				beneficiary: 3,
+				unlock_at: 4,
+			});
+
+			assert_noop!(Treasury::claim_bounty(Origin::signed(1), 0), "bounty claimed prematurely");
+
+			System::set_block_number(4);
+			assert_ok!(Treasury::claim_bounty(Origin::signed(1), 0));
+
+			assert_eq!(Balances::free_balance(&4), 10 + 4); // initial 10 + fee 4
+			assert_eq!(Balances::free_balance(&3), 50 - 4);
+			assert_eq!(Balances::free_balance(&Treasury::bounty_account_id(0)), 0);
+
+			assert_eq!(Treasury::bounties(0), None);
+			assert_eq!(Treasury::bounty_descriptions(0), None);
+		});
+	}
+
+	#[test]
+	fn reject_origin_can_stop_pending_payout() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::propose_bounty(Origin::signed(0), 50, b"12345".to_vec()));
+			assert_ok!(Treasury::approve_bounty(Origin::ROOT, 0));
+
+			<Treasury as OnFinalize<u64>>::on_finalize(2);
+
+			assert_ok!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4));
+			assert_ok!(Treasury::accept_curator(Origin::signed(4), 0));
+
+			// the curator awards the bounty to themself, which is undone during the delay.
+			assert_ok!(Treasury::award_bounty(Origin::signed(4), 0, 4));
+			assert_ok!(Treasury::unassign_curator(Origin::ROOT, 0));
+			assert_eq!(Treasury::bounties(0).unwrap().status, BountyStatus::Funded);
+
+			System::set_block_number(4);
+			assert_noop!(Treasury::claim_bounty(Origin::signed(1), 0), "unexpected bounty status");
+
+			// an award may also be cancelled along with the bounty.
+			assert_ok!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4));
+			assert_ok!(Treasury::accept_curator(Origin::signed(4), 0));
+			assert_ok!(Treasury::award_bounty(Origin::signed(4), 0, 4));
+			assert_ok!(Treasury::close_bounty(Origin::ROOT, 0));
+
+			assert_eq!(Treasury::pot(), 75);
+			assert_eq!(Balances::free_balance(&4), 0);
+			assert_eq!(Balances::free_balance(&Treasury::bounty_account_id(0)), 0);
+			assert_eq!(Treasury::bounties(0), None);
+		});
+	}
+
+	#[test]
+	fn cancel_funded_bounty_returns_funds() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::propose_bounty(Origin::signed(0), 50, b"12345".to_vec()));
+			assert_ok!(Treasury::approve_bounty(Origin::ROOT, 0));
+
+			<Treasury as OnFinalize<u64>>::on_finalize(2);
+			assert_eq!(Treasury::pot(), 25);
+
+			assert_ok!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4));
+			assert_ok!(Treasury::close_bounty(Origin::ROOT, 0));
+
+			assert_eq!(Treasury::pot(), 75);
+			assert_eq!(Balances::free_balance(&Treasury::bounty_account_id(0)), 0);
+			assert_eq!(Treasury::bounties(0), None);
+		});
+	}
 }
@@ -16,33 +16,167 @@
 
 //! # Utility Module
 //! A module full of useful helpers for practical chain management.
+//!
+//! ## Overview
+//!
+//! Alongside the root-only `batch` call, this module provides multi-signature dispatch. A
+//! multisig account is derived deterministically from its (sorted) signatories and its
+//! threshold, so it needs no on-chain setup: anyone knowing the composite can compute the
+//! account id with [`Module::multi_account_id`] and fund it.
+//!
+//! A signatory opens an operation with `as_multi` (or `approve_as_multi`, which only names the
+//! hash of the call), reserving a deposit. The other signatories then approve the same call
+//! hash, naming the [`Timepoint`] at which the operation was opened, so that an approval cannot
+//! be replayed on a later operation with the same call. Once the threshold is met, the call is
+//! dispatched from the multisig account by whoever submits it with `as_multi`, and the deposit
+//! is returned. The depositor may `cancel_as_multi` an operation at any time to get the deposit
+//! back.
 
 // Ensure we're `no_std` when compiling for Wasm.
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use sp_std::prelude::*;
-use support::{decl_module, decl_event, Parameter, weights::SimpleDispatchInfo};
-use system::ensure_root;
-use sp_runtime::{traits::Dispatchable, DispatchError};
+use codec::{Encode, Decode};
+use sp_io::hashing::blake2_256;
+use support::{
+	decl_module, decl_event, decl_storage, ensure, dispatch, Parameter,
+	weights::{
+		SimpleDispatchInfo, GetDispatchInfo, WeighData, ClassifyDispatch, DispatchClass, PaysFee,
+		Weight,
+	},
+	traits::{Currency, ReservableCurrency, Get},
+};
+use system::{ensure_signed, ensure_root};
+use sp_runtime::{traits::Dispatchable, DispatchError, RuntimeDebug};
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 
 /// Configuration trait.
 pub trait Trait: system::Trait {
 	/// The overarching event type.
-	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 
 	/// The overarching call type.
-	type Call: Parameter + Dispatchable<Origin=Self::Origin>;
+	type Call: Parameter + Dispatchable<Origin=Self::Origin> + GetDispatchInfo;
+
+	/// The currency mechanism, used to reserve the deposit of multisig operations.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The base amount of currency needed to reserve for opening a multisig operation.
+	type MultisigDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount of currency needed per unit threshold when opening a multisig operation.
+	type MultisigDepositFactor: Get<BalanceOf<Self>>;
+
+	/// The maximum amount of signatories allowed in a multisig composite.
+	type MaxSignatories: Get<u16>;
+}
+
+/// A global extrinsic index, formed as the extrinsic index within a block, together with that
+/// block's height. This uniquely identifies the transaction in which a multisig operation was
+/// opened.
+#[derive(Copy, Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug)]
+pub struct Timepoint<BlockNumber> {
+	/// The height of the chain at the point in time.
+	pub height: BlockNumber,
+	/// The index of the extrinsic at the point in time.
+	pub index: u32,
+}
+
+/// An open multisig operation.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug)]
+pub struct Multisig<BlockNumber, Balance, AccountId> {
+	/// The extrinsic when the multisig operation was opened.
+	pub when: Timepoint<BlockNumber>,
+	/// The amount held in reserve of the `depositor`, to be returned once the operation ends.
+	pub deposit: Balance,
+	/// The account who opened it (i.e. the first to approve it).
+	pub depositor: AccountId,
+	/// The approvals achieved so far, including the depositor. Always sorted.
+	pub approvals: Vec<AccountId>,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Utility {
+		/// The set of open multisig operations, keyed by multisig account and call hash.
+		pub Multisigs get(fn multisigs): double_map T::AccountId, blake2_256([u8; 32])
+			=> Option<Multisig<T::BlockNumber, BalanceOf<T>, T::AccountId>>;
+	}
 }
 
 decl_event!(
 	/// Events type.
-	pub enum Event {
+	pub enum Event<T> where
+		AccountId = <T as system::Trait>::AccountId,
+		BlockNumber = <T as system::Trait>::BlockNumber,
+	{
+		/// A batch of calls was dispatched, with their results.
 		BatchExecuted(Vec<Result<(), DispatchError>>),
+		/// A new multisig operation has begun. First param is the account that is approving,
+		/// second is the multisig account.
+		NewMultisig(AccountId, AccountId),
+		/// A multisig operation has been approved by someone. First param is the account that is
+		/// approving, third is the multisig account.
+		MultisigApproval(AccountId, Timepoint<BlockNumber>, AccountId),
+		/// A multisig operation has been executed. First param is the account that is
+		/// approving, third is the multisig account.
+		MultisigExecuted(AccountId, Timepoint<BlockNumber>, AccountId, Result<(), DispatchError>),
+		/// A multisig operation has been cancelled. First param is the account that is
+		/// cancelling, third is the multisig account.
+		MultisigCancelled(AccountId, Timepoint<BlockNumber>, AccountId),
 	}
 );
 
+/// The weight of `as_multi`: the weight of the call it may dispatch, plus the cost of checking
+/// the signatories and the operation.
+pub struct WeightForAsMulti;
+
+impl<BlockNumber, AccountId, Call: GetDispatchInfo>
+	WeighData<(&u16, &Vec<AccountId>, &Option<Timepoint<BlockNumber>>, &Box<Call>)>
+	for WeightForAsMulti
+{
+	fn weigh_data(
+		&self,
+		(_, other_signatories, _, call):
+			(&u16, &Vec<AccountId>, &Option<Timepoint<BlockNumber>>, &Box<Call>),
+	) -> Weight {
+		call.get_dispatch_info().weight
+			.saturating_add(1_000_000)
+			.saturating_add((other_signatories.len() as Weight).saturating_mul(10_000))
+	}
+}
+
+impl<BlockNumber, AccountId, Call: GetDispatchInfo>
+	ClassifyDispatch<(&u16, &Vec<AccountId>, &Option<Timepoint<BlockNumber>>, &Box<Call>)>
+	for WeightForAsMulti
+{
+	fn classify_dispatch(
+		&self,
+		(_, _, _, call): (&u16, &Vec<AccountId>, &Option<Timepoint<BlockNumber>>, &Box<Call>),
+	) -> DispatchClass {
+		call.get_dispatch_info().class
+	}
+}
+
+impl PaysFee for WeightForAsMulti {}
+
+/// A call to dispatch, or only its hash when approving.
+enum CallOrHash<Call> {
+	Call(Box<Call>),
+	Hash([u8; 32]),
+}
+
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The base amount of currency needed to reserve for opening a multisig operation.
+		const MultisigDepositBase: BalanceOf<T> = T::MultisigDepositBase::get();
+
+		/// The amount of currency needed per unit threshold when opening a multisig operation.
+		const MultisigDepositFactor: BalanceOf<T> = T::MultisigDepositFactor::get();
+
+		/// The maximum amount of signatories allowed in a multisig composite.
+		const MaxSignatories: u16 = T::MaxSignatories::get();
+
 		/// Deposit one of this module's events by using the default implementation.
 		fn deposit_event() = default;
 
@@ -54,8 +188,202 @@ decl_module! {
 				.map(|call| call.dispatch(system::RawOrigin::Root.into()))
 				.map(|res| res.map_err(Into::into))
 				.collect::<Vec<_>>();
-			Self::deposit_event(Event::BatchExecuted(results));
+			Self::deposit_event(RawEvent::BatchExecuted(results));
+		}
+
+		/// Register approval for a dispatch to be made from a deterministic composite account if
+		/// approved by a total of `threshold` of the sender and `other_signatories`. Dispatches
+		/// `call` if the threshold is met with this approval.
+		///
+		/// - `other_signatories`: the other accounts of the composite, in sorted order. The
+		/// sender must not be among them.
+		/// - `maybe_timepoint`: `None` if this is the first approval of the operation, otherwise
+		/// the `Timepoint` of the transaction which opened it.
+		///
+		/// The first approval reserves `MultisigDepositBase + threshold * MultisigDepositFactor`
+		/// from the sender, which is returned once the call is dispatched or the operation is
+		/// cancelled. With a threshold of one, `call` is dispatched straight away.
+		///
+		/// The weight of `call` is included in the weight of this call, whether or not it is
+		/// dispatched.
+		#[weight = WeightForAsMulti]
+		fn as_multi(origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+			call: Box<<T as Trait>::Call>,
+		) {
+			let who = ensure_signed(origin)?;
+			Self::operate(who, threshold, other_signatories, maybe_timepoint, CallOrHash::Call(call))?;
+		}
+
+		/// Register approval for a dispatch to be made from a deterministic composite account if
+		/// approved by a total of `threshold` of the sender and `other_signatories`.
+		///
+		/// Like `as_multi`, but only the hash of the call is given, so the call is never
+		/// dispatched by this approval: once the threshold is met, any signatory may dispatch it
+		/// with `as_multi`.
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		fn approve_as_multi(origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+			call_hash: [u8; 32],
+		) {
+			let who = ensure_signed(origin)?;
+			Self::operate(who, threshold, other_signatories, maybe_timepoint, CallOrHash::Hash(call_hash))?;
+		}
+
+		/// Cancel a pre-existing, on-going multisig operation, returning its deposit. Only the
+		/// depositor of the operation may cancel it.
+		///
+		/// - `timepoint`: the `Timepoint` of the transaction which opened the operation.
+		/// - `call_hash`: the blake2-256 hash of the call of the operation.
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		fn cancel_as_multi(origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			timepoint: Timepoint<T::BlockNumber>,
+			call_hash: [u8; 32],
+		) {
+			let who = ensure_signed(origin)?;
+			ensure!(threshold >= 1, "threshold must be at least one");
+			let signatories = Self::ensure_sorted_and_insert(other_signatories, who.clone())?;
+			let id = Self::multi_account_id(&signatories, threshold);
+
+			let m = <Multisigs<T>>::get(&id, call_hash).ok_or("multisig operation not found")?;
+			ensure!(m.when == timepoint, "timepoint does not match the operation");
+			ensure!(m.depositor == who, "only the depositor can cancel");
+
+			T::Currency::unreserve(&m.depositor, m.deposit);
+			<Multisigs<T>>::remove(&id, call_hash);
+
+			Self::deposit_event(RawEvent::MultisigCancelled(who, timepoint, id));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Derive a multi-account ID from the sorted list of accounts and the threshold that are
+	/// required.
+	///
+	/// NOTE: `who` must be sorted. If it is not, then you'll get the wrong answer.
+	pub fn multi_account_id(who: &[T::AccountId], threshold: u16) -> T::AccountId {
+		let entropy = (b"modlpy/utilisuba", who, threshold).using_encoded(blake2_256);
+		T::AccountId::decode(&mut &entropy[..]).unwrap_or_default()
+	}
+
+	/// The current `Timepoint`.
+	pub fn timepoint() -> Timepoint<T::BlockNumber> {
+		Timepoint {
+			height: <system::Module<T>>::block_number(),
+			index: <system::Module<T>>::extrinsic_index().unwrap_or_default(),
+		}
+	}
+
+	/// Approve `call_or_hash` on behalf of `who`, opening the operation if needed and
+	/// dispatching the call once the threshold is met.
+	fn operate(
+		who: T::AccountId,
+		threshold: u16,
+		other_signatories: Vec<T::AccountId>,
+		maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+		call_or_hash: CallOrHash<<T as Trait>::Call>,
+	) -> dispatch::Result {
+		ensure!(threshold >= 1, "threshold must be at least one");
+		ensure!(!other_signatories.is_empty(), "too few signatories");
+		ensure!(
+			other_signatories.len() < T::MaxSignatories::get() as usize,
+			"too many signatories"
+		);
+		ensure!(
+			threshold as usize <= other_signatories.len() + 1,
+			"threshold exceeds the number of signatories"
+		);
+		let signatories = Self::ensure_sorted_and_insert(other_signatories, who.clone())?;
+		let id = Self::multi_account_id(&signatories, threshold);
+
+		let (call_hash, maybe_call) = match call_or_hash {
+			CallOrHash::Call(call) => (call.using_encoded(blake2_256), Some(call)),
+			CallOrHash::Hash(hash) => (hash, None),
+		};
+
+		if let Some(mut m) = <Multisigs<T>>::get(&id, call_hash) {
+			let timepoint = maybe_timepoint.ok_or("timepoint required for an open operation")?;
+			ensure!(m.when == timepoint, "timepoint does not match the operation");
+
+			let newly_approved = match m.approvals.binary_search(&who) {
+				Ok(_) => false,
+				Err(pos) => {
+					m.approvals.insert(pos, who.clone());
+					true
+				}
+			};
+
+			if m.approvals.len() >= threshold as usize {
+				if let Some(call) = maybe_call {
+					T::Currency::unreserve(&m.depositor, m.deposit);
+					<Multisigs<T>>::remove(&id, call_hash);
+					let result = call.dispatch(system::RawOrigin::Signed(id.clone()).into());
+					Self::deposit_event(RawEvent::MultisigExecuted(
+						who, timepoint, id, result.map_err(Into::into),
+					));
+					return Ok(());
+				}
+			}
+
+			ensure!(newly_approved, "already approved");
+			<Multisigs<T>>::insert(&id, call_hash, m);
+			Self::deposit_event(RawEvent::MultisigApproval(who, timepoint, id));
+		} else {
+			ensure!(maybe_timepoint.is_none(), "unexpected timepoint for a new operation");
+
+			if threshold == 1 {
+				let call = maybe_call.ok_or("a call must be given with a threshold of one")?;
+				let result = call.dispatch(system::RawOrigin::Signed(id.clone()).into());
+				Self::deposit_event(RawEvent::MultisigExecuted(
+					who, Self::timepoint(), id, result.map_err(Into::into),
+				));
+				return Ok(());
+			}
+
+			let deposit = T::MultisigDepositBase::get()
+				+ T::MultisigDepositFactor::get() * threshold.into();
+			T::Currency::reserve(&who, deposit)?;
+
+			<Multisigs<T>>::insert(&id, call_hash, Multisig {
+				when: Self::timepoint(),
+				deposit,
+				depositor: who.clone(),
+				approvals: vec![who.clone()],
+			});
+			Self::deposit_event(RawEvent::NewMultisig(who, id));
+		}
+
+		Ok(())
+	}
+
+	/// Check that `other_signatories` is sorted and does not contain `who`, and return them
+	/// with `who` inserted in order.
+	fn ensure_sorted_and_insert(
+		other_signatories: Vec<T::AccountId>,
+		who: T::AccountId,
+	) -> Result<Vec<T::AccountId>, &'static str> {
+		let mut signatories = other_signatories;
+		let mut maybe_last = None;
+		let mut index = 0;
+		for item in signatories.iter() {
+			if let Some(last) = maybe_last {
+				ensure!(last < item, "signatories out of order");
+			}
+			if item <= &who {
+				ensure!(item != &who, "sender in signatories");
+				index += 1;
+			}
+			maybe_last = Some(item);
 		}
+		signatories.insert(index, who);
+		Ok(signatories)
 	}
 }
 
@@ -125,18 +453,27 @@ mod tests {
 		type TransferFee = TransferFee;
 		type CreationFee = CreationFee;
 	}
+	parameter_types! {
+		pub const MultisigDepositBase: u64 = 1;
+		pub const MultisigDepositFactor: u64 = 1;
+		pub const MaxSignatories: u16 = 3;
+	}
 	impl Trait for Test {
 		type Event = ();
 		type Call = Call;
+		type Currency = Balances;
+		type MultisigDepositBase = MultisigDepositBase;
+		type MultisigDepositFactor = MultisigDepositFactor;
+		type MaxSignatories = MaxSignatories;
 	}
+	type System = system::Module<Test>;
 	type Balances = balances::Module<Test>;
 	type Utility = Module<Test>;
 
 	fn new_test_ext() -> sp_io::TestExternalities {
 		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 		balances::GenesisConfig::<Test> {
-			balances: vec![(1, 10), (2, 0)],
-			vesting: vec![],
+			balances: vec![(1, 10), (2, 0), (3, 10), (4, 10), (5, 10)],
 		}.assimilate_storage(&mut t).unwrap();
 		t.into()
 	}
@@ -158,4 +495,186 @@ mod tests {
 			assert_eq!(Balances::free_balance(2), 10);
 		});
 	}
+
+	fn now() -> Timepoint<u64> {
+		Utility::timepoint()
+	}
+
+	fn transfer_call(dest: u64, value: u64) -> Box<Call> {
+		Box::new(Call::Balances(balances::Call::transfer(dest, value)))
+	}
+
+	#[test]
+	fn multisig_deposit_is_taken_and_returned() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let multi = Utility::multi_account_id(&[3, 4, 5][..], 2);
+			assert_ok!(Balances::transfer(Origin::signed(3), multi, 5));
+
+			let call = transfer_call(6, 5);
+			assert_ok!(Utility::as_multi(Origin::signed(3), 2, vec![4, 5], None, call.clone()));
+			assert_eq!(Balances::free_balance(3), 2);
+			assert_eq!(Balances::reserved_balance(3), 3);
+
+			assert_ok!(Utility::as_multi(Origin::signed(4), 2, vec![3, 5], Some(now()), call));
+			assert_eq!(Balances::free_balance(3), 5);
+			assert_eq!(Balances::reserved_balance(3), 0);
+			assert_eq!(Balances::free_balance(6), 5);
+		});
+	}
+
+	#[test]
+	fn cancel_multisig_returns_deposit() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let hash = transfer_call(6, 5).using_encoded(blake2_256);
+			assert_ok!(Utility::approve_as_multi(Origin::signed(3), 3, vec![4, 5], None, hash));
+			assert_ok!(Utility::approve_as_multi(Origin::signed(4), 3, vec![3, 5], Some(now()), hash));
+			assert_eq!(Balances::free_balance(3), 6);
+			assert_eq!(Balances::reserved_balance(3), 4);
+
+			assert_noop!(
+				Utility::cancel_as_multi(Origin::signed(4), 3, vec![3, 5], now(), hash),
+				"only the depositor can cancel",
+			);
+			assert_ok!(Utility::cancel_as_multi(Origin::signed(3), 3, vec![4, 5], now(), hash));
+			assert_eq!(Balances::free_balance(3), 10);
+			assert_eq!(Balances::reserved_balance(3), 0);
+
+			let multi = Utility::multi_account_id(&[3, 4, 5][..], 3);
+			assert_eq!(Utility::multisigs(&multi, &hash), None);
+		});
+	}
+
+	#[test]
+	fn timepoint_checking_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let call = transfer_call(6, 5);
+			let hash = call.using_encoded(blake2_256);
+
+			assert_noop!(
+				Utility::approve_as_multi(Origin::signed(4), 2, vec![3, 5], Some(now()), hash),
+				"unexpected timepoint for a new operation",
+			);
+			assert_ok!(Utility::approve_as_multi(Origin::signed(3), 2, vec![4, 5], None, hash));
+
+			assert_noop!(
+				Utility::as_multi(Origin::signed(4), 2, vec![3, 5], None, call.clone()),
+				"timepoint required for an open operation",
+			);
+			let later = Timepoint { height: 2, .. now() };
+			assert_noop!(
+				Utility::as_multi(Origin::signed(4), 2, vec![3, 5], Some(later), call),
+				"timepoint does not match the operation",
+			);
+		});
+	}
+
+	#[test]
+	fn multisig_3_of_3_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let multi = Utility::multi_account_id(&[3, 4, 5][..], 3);
+			assert_ok!(Balances::transfer(Origin::signed(3), multi, 5));
+
+			let call = transfer_call(6, 5);
+			let hash = call.using_encoded(blake2_256);
+			assert_ok!(Utility::approve_as_multi(Origin::signed(3), 3, vec![4, 5], None, hash));
+			assert_ok!(Utility::approve_as_multi(Origin::signed(4), 3, vec![3, 5], Some(now()), hash));
+			assert_noop!(
+				Utility::approve_as_multi(Origin::signed(4), 3, vec![3, 5], Some(now()), hash),
+				"already approved",
+			);
+			assert_eq!(Balances::free_balance(6), 0);
+
+			assert_ok!(Utility::as_multi(Origin::signed(5), 3, vec![3, 4], Some(now()), call));
+			assert_eq!(Balances::free_balance(6), 5);
+			assert_eq!(Utility::multisigs(&multi, &hash), None);
+		});
+	}
+
+	#[test]
+	fn approved_call_can_be_dispatched_by_a_previous_approver() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let multi = Utility::multi_account_id(&[3, 4, 5][..], 2);
+			assert_ok!(Balances::transfer(Origin::signed(3), multi, 5));
+
+			let call = transfer_call(6, 5);
+			let hash = call.using_encoded(blake2_256);
+			assert_ok!(Utility::approve_as_multi(Origin::signed(3), 2, vec![4, 5], None, hash));
+			assert_ok!(Utility::approve_as_multi(Origin::signed(4), 2, vec![3, 5], Some(now()), hash));
+			assert_eq!(Balances::free_balance(6), 0);
+
+			assert_ok!(Utility::as_multi(Origin::signed(3), 2, vec![4, 5], Some(now()), call));
+			assert_eq!(Balances::free_balance(6), 5);
+			assert_eq!(Balances::reserved_balance(3), 0);
+		});
+	}
+
+	#[test]
+	fn multisig_1_of_3_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let multi = Utility::multi_account_id(&[3, 4, 5][..], 1);
+			assert_ok!(Balances::transfer(Origin::signed(3), multi, 5));
+
+			let call = transfer_call(6, 5);
+			let hash = call.using_encoded(blake2_256);
+			assert_noop!(
+				Utility::approve_as_multi(Origin::signed(3), 1, vec![4, 5], None, hash),
+				"a call must be given with a threshold of one",
+			);
+			assert_ok!(Utility::as_multi(Origin::signed(4), 1, vec![3, 5], None, call));
+			assert_eq!(Balances::free_balance(6), 5);
+			assert_eq!(Balances::reserved_balance(4), 0);
+		});
+	}
+
+	#[test]
+	fn signatories_are_checked() {
+		new_test_ext().execute_with(|| {
+			let call = transfer_call(6, 5);
+			assert_noop!(
+				Utility::as_multi(Origin::signed(3), 0, vec![4, 5], None, call.clone()),
+				"threshold must be at least one",
+			);
+			assert_noop!(
+				Utility::as_multi(Origin::signed(3), 2, vec![], None, call.clone()),
+				"too few signatories",
+			);
+			assert_noop!(
+				Utility::as_multi(Origin::signed(3), 2, vec![1, 2, 4, 5], None, call.clone()),
+				"too many signatories",
+			);
+			assert_noop!(
+				Utility::as_multi(Origin::signed(3), 4, vec![4, 5], None, call.clone()),
+				"threshold exceeds the number of signatories",
+			);
+			assert_noop!(
+				Utility::approve_as_multi(Origin::signed(3), 4, vec![4, 5], None, [0; 32]),
+				"threshold exceeds the number of signatories",
+			);
+			assert_eq!(Balances::reserved_balance(3), 0);
+			assert_noop!(
+				Utility::as_multi(Origin::signed(3), 2, vec![5, 4], None, call.clone()),
+				"signatories out of order",
+			);
+			assert_noop!(
+				Utility::as_multi(Origin::signed(3), 2, vec![3, 4], None, call),
+				"sender in signatories",
+			);
+		});
+	}
+
+	#[test]
+	fn as_multi_weight_includes_the_call() {
+		let call = transfer_call(6, 5);
+		let call_info = call.get_dispatch_info();
+		let as_multi = Call::Utility(crate::Call::as_multi(2, vec![4, 5], None, call));
+		let info = as_multi.get_dispatch_info();
+		assert!(info.weight > call_info.weight);
+		assert_eq!(info.class, call_info.class);
+	}
 }
@@ -0,0 +1,852 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Vesting Module
+//!
+//! - [`vesting::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! A simple module providing a means of placing a linear curve on an account's locked balance.
+//! This module ensures that there is a lock in place preventing the balance to drop below the
+//! *unvested* amount for any reason other than transaction fee payment.
+//!
+//! As the amount vested increases over time, the amount unvested reduces. However, locks remain
+//! in place and explicit action is needed on behalf of the user to ensure that the amount locked
+//! is equivalent to the amount remaining to be vested. This is done through a dispatchable
+//! function, either `vest` (in typical case where the sender is calling on their own behalf) or
+//! `vest_other` in case the sender is calling on another account's behalf.
+//!
+//! An account may have several vesting schedules at once, up to `MaxVestingSchedules`: the amount
+//! locked is the sum of the amounts still unvested by each of them. New schedules are created
+//! either at genesis or with `vested_transfer`.
+//!
+//! ## Interface
+//!
+//! This module implements the `VestingSchedule` trait.
+//!
+//! ### Dispatchable Functions
+//!
+//! - `vest` - Update the lock, reducing it in line with the amount "vested" so far.
+//! - `vest_other` - Update the lock of another account, reducing it in line with the amount
+//!   "vested" so far.
+//! - `vested_transfer` - Transfer funds to an account, locked under a new vesting schedule.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::{prelude::*, collections::btree_map::BTreeMap};
+use codec::{Encode, Decode};
+use sp_runtime::{
+	RuntimeDebug,
+	traits::{StaticLookup, Zero, One, SimpleArithmetic, Saturating, Convert, Bounded},
+};
+use support::{
+	decl_module, decl_event, decl_storage, ensure, dispatch,
+	traits::{
+		Currency, LockableCurrency, VestingSchedule, WithdrawReason, LockIdentifier,
+		ExistenceRequirement, Get,
+	},
+	weights::SimpleDispatchInfo,
+};
+use system::ensure_signed;
+
+mod migration;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+const VESTING_ID: LockIdentifier = *b"vesting ";
+
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency trait.
+	type Currency: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber>;
+
+	/// Convert the block number into a balance.
+	type BlockNumberToBalance: Convert<Self::BlockNumber, BalanceOf<Self>>;
+
+	/// The minimum amount to be transferred to create a new vesting schedule.
+	type MinVestedTransfer: Get<BalanceOf<Self>>;
+
+	/// The maximum number of vesting schedules an account may have at a given moment.
+	type MaxVestingSchedules: Get<u32>;
+}
+
+/// Struct to encode the vesting schedule of an individual account.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct VestingInfo<Balance, BlockNumber> {
+	/// Locked amount at the start of the schedule.
+	pub locked: Balance,
+	/// Amount that gets unlocked every block after `starting_block`.
+	pub per_block: Balance,
+	/// Starting block for unlocking(vesting).
+	pub starting_block: BlockNumber,
+}
+
+impl<
+	Balance: SimpleArithmetic + Copy,
+	BlockNumber: SimpleArithmetic + Copy,
+> VestingInfo<Balance, BlockNumber> {
+	/// Amount locked at block `n`.
+	pub fn locked_at<
+		BlockNumberToBalance: Convert<BlockNumber, Balance>
+	>(&self, n: BlockNumber) -> Balance {
+		// Number of blocks that count toward vesting
+		// Saturating to 0 when n < starting_block
+		let vested_block_count = n.saturating_sub(self.starting_block);
+		let vested_block_count = BlockNumberToBalance::convert(vested_block_count);
+		// Return amount that is still locked in vesting
+		if let Some(x) = vested_block_count.checked_mul(&self.per_block) {
+			self.locked.max(x) - x
+		} else {
+			Zero::zero()
+		}
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Vesting {
+		/// Information regarding the vesting of a given account.
+		pub Vesting get(fn vesting):
+			map T::AccountId => Vec<VestingInfo<BalanceOf<T>, T::BlockNumber>>;
+
+		/// The version of storage for upgrade.
+		StorageVersion build(|_| migration::CURRENT_VERSION): u32;
+	}
+	add_extra_genesis {
+		config(vesting): Vec<(T::AccountId, T::BlockNumber, T::BlockNumber, BalanceOf<T>)>;
+		// ^^ begin, length, amount liquid at genesis
+		build(|config: &GenesisConfig<T>| {
+			// Generate initial vesting configuration
+			// * who - Account which we are generating vesting configuration for
+			// * begin - Block when the account will start to vest
+			// * length - Number of blocks from `begin` until fully vested
+			// * liquid - Number of units which can be spent before vesting begins, out of the
+			//   units not locked by the previous schedules of the account
+
+			// The funds of each account not locked by its previous schedules
+			let mut unlocked = BTreeMap::new();
+			for &(ref who, begin, length, liquid) in config.vesting.iter() {
+				let balance = T::Currency::free_balance(who);
+				assert!(!balance.is_zero(), "Currencies must be init'd before vesting");
+				// Unlocked funds minus `liquid` equals funds locked for vesting
+				let unlocked = unlocked.entry(who.clone()).or_insert(balance);
+				let locked = (*unlocked).saturating_sub(liquid);
+				*unlocked = (*unlocked).min(liquid);
+				let length_as_balance = T::BlockNumberToBalance::convert(length);
+				let per_block = locked / length_as_balance.max(One::one());
+
+				Vesting::<T>::mutate(who, |schedules| schedules.push(VestingInfo {
+					locked,
+					per_block,
+					starting_block: begin,
+				}));
+			}
+			for (who, _) in unlocked {
+				<Module<T>>::update_lock(who).expect("Vesting schedules were just added; qed");
+			}
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId, Balance = BalanceOf<T> {
+		/// The amount vested has been updated. This could indicate more funds are available. The
+		/// balance given is the amount which is left unvested (and thus locked).
+		VestingUpdated(AccountId, Balance),
+		/// An account (given) has become fully vested. No further vesting can happen.
+		VestingCompleted(AccountId),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The minimum amount to be transferred to create a new vesting schedule.
+		const MinVestedTransfer: BalanceOf<T> = T::MinVestedTransfer::get();
+
+		/// The maximum number of vesting schedules an account may have at a given moment.
+		const MaxVestingSchedules: u32 = T::MaxVestingSchedules::get();
+
+		fn deposit_event() = default;
+
+		fn on_initialize() {
+			migration::perform_migrations::<T>();
+		}
+
+		/// Unlock any vested funds of the sender account.
+		///
+		/// The dispatch origin for this call must be _Signed_ and the sender must have funds still
+		/// locked under this module.
+		///
+		/// Emits either `VestingCompleted` or `VestingUpdated`.
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+		fn vest(origin) -> dispatch::Result {
+			let who = ensure_signed(origin)?;
+			Self::update_lock(who)
+		}
+
+		/// Unlock any vested funds of a `target` account.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `target`: The account whose vested funds should be unlocked. Must have funds still
+		/// locked under this module.
+		///
+		/// Emits either `VestingCompleted` or `VestingUpdated`.
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+		fn vest_other(origin, target: <T::Lookup as StaticLookup>::Source) -> dispatch::Result {
+			ensure_signed(origin)?;
+			Self::update_lock(T::Lookup::lookup(target)?)
+		}
+
+		/// Create a vested transfer.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `target`: The account that should be transferred the vested funds.
+		/// - `schedule`: The vesting schedule attached to the transfer. Its `locked` amount is
+		/// transferred, and must be at least `MinVestedTransfer`. It must vest a non-zero amount
+		/// per block, so that it ends.
+		///
+		/// Emits `VestingUpdated`.
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		fn vested_transfer(
+			origin,
+			target: <T::Lookup as StaticLookup>::Source,
+			schedule: VestingInfo<BalanceOf<T>, T::BlockNumber>,
+		) {
+			let transactor = ensure_signed(origin)?;
+			ensure!(schedule.locked >= T::MinVestedTransfer::get(), "amount transferred is too low");
+			ensure!(!schedule.per_block.is_zero(), "schedule never ends");
+
+			let who = T::Lookup::lookup(target)?;
+			ensure!(
+				(Self::vesting(&who).len() as u32) < T::MaxVestingSchedules::get(),
+				"too many vesting schedules",
+			);
+
+			T::Currency::transfer(
+				&transactor,
+				&who,
+				schedule.locked,
+				ExistenceRequirement::AllowDeath,
+			)?;
+
+			Vesting::<T>::mutate(&who, |schedules| schedules.push(schedule));
+			Self::update_lock(who)?;
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// (Re)set or remove the module's currency lock on `who`'s account in accordance with their
+	/// current unvested amount, dropping the schedules which are fully vested.
+	fn update_lock(who: T::AccountId) -> dispatch::Result {
+		let mut schedules = Self::vesting(&who);
+		ensure!(!schedules.is_empty(), "account has no vesting schedule");
+
+		let now = <system::Module<T>>::block_number();
+		let mut locked_now: BalanceOf<T> = Zero::zero();
+		schedules.retain(|s| {
+			let locked = s.locked_at::<T::BlockNumberToBalance>(now);
+			locked_now = locked_now.saturating_add(locked);
+			!locked.is_zero()
+		});
+
+		if locked_now.is_zero() {
+			T::Currency::remove_lock(VESTING_ID, &who);
+			Vesting::<T>::remove(&who);
+			Self::deposit_event(RawEvent::VestingCompleted(who));
+		} else {
+			let reasons = WithdrawReason::Transfer | WithdrawReason::Reserve;
+			T::Currency::set_lock(VESTING_ID, &who, locked_now, T::BlockNumber::max_value(), reasons);
+			Vesting::<T>::insert(&who, schedules);
+			Self::deposit_event(RawEvent::VestingUpdated(who, locked_now));
+		}
+		Ok(())
+	}
+}
+
+impl<T: Trait> VestingSchedule<T::AccountId> for Module<T> {
+	type Moment = T::BlockNumber;
+	type Currency = T::Currency;
+
+	/// Get the amount that is currently being vested and cannot be transferred out of this account.
+	fn vesting_balance(who: &T::AccountId) -> Option<BalanceOf<T>> {
+		let schedules = Self::vesting(who);
+		if schedules.is_empty() {
+			return None;
+		}
+		let now = <system::Module<T>>::block_number();
+		let locked = schedules.iter()
+			.fold(Zero::zero(), |acc: BalanceOf<T>, s| {
+				acc.saturating_add(s.locked_at::<T::BlockNumberToBalance>(now))
+			});
+		Some(T::Currency::free_balance(who).min(locked))
+	}
+
+	/// Adds a vesting schedule to a given account.
+	///
+	/// If the account already has `MaxVestingSchedules` schedules, or if the schedule never ends,
+	/// an `Err` is returned and nothing is updated.
+	///
+	/// NOTE: This doesn't alter the free balance of the account.
+	fn add_vesting_schedule(
+		who: &T::AccountId,
+		locked: BalanceOf<T>,
+		per_block: BalanceOf<T>,
+		starting_block: T::BlockNumber,
+	) -> dispatch::Result {
+		if locked.is_zero() {
+			return Ok(());
+		}
+		ensure!(!per_block.is_zero(), "schedule never ends");
+		ensure!(
+			(Self::vesting(who).len() as u32) < T::MaxVestingSchedules::get(),
+			"too many vesting schedules",
+		);
+		let vesting_schedule = VestingInfo {
+			locked,
+			per_block,
+			starting_block,
+		};
+		Vesting::<T>::mutate(who, |schedules| schedules.push(vesting_schedule));
+		Self::update_lock(who.clone())
+	}
+
+	/// Remove all the vesting schedules of a given account, unlocking its funds.
+	fn remove_vesting_schedule(who: &T::AccountId) {
+		Vesting::<T>::remove(who);
+		T::Currency::remove_lock(VESTING_ID, who);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::cell::RefCell;
+	use support::{assert_ok, assert_noop, impl_outer_origin, parameter_types, weights::Weight};
+	use primitives::H256;
+	// The testing primitives are very useful for avoiding having to work with signatures
+	// or public keys. `u64` is used as the `AccountId` and no `Signature`s are required.
+	use sp_runtime::{
+		Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup, ConvertInto},
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	// For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+	}
+	parameter_types! {
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const MinVestedTransfer: u64 = 256 * 2;
+		pub const MaxVestingSchedules: u32 = 3;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Currency = Balances;
+		type BlockNumberToBalance = ConvertInto;
+		type MinVestedTransfer = MinVestedTransfer;
+		type MaxVestingSchedules = MaxVestingSchedules;
+	}
+	type System = system::Module<Test>;
+	type Balances = balances::Module<Test>;
+	type Vesting = Module<Test>;
+
+	thread_local! {
+		static EXISTENTIAL_DEPOSIT: RefCell<u64> = RefCell::new(0);
+	}
+	pub struct ExistentialDeposit;
+	impl Get<u64> for ExistentialDeposit {
+		fn get() -> u64 { EXISTENTIAL_DEPOSIT.with(|v| *v.borrow()) }
+	}
+
+	pub struct ExtBuilder {
+		existential_deposit: u64,
+	}
+	impl Default for ExtBuilder {
+		fn default() -> Self {
+			Self {
+				existential_deposit: 1,
+			}
+		}
+	}
+	impl ExtBuilder {
+		pub fn existential_deposit(mut self, existential_deposit: u64) -> Self {
+			self.existential_deposit = existential_deposit;
+			self
+		}
+		pub fn build(self) -> sp_io::TestExternalities {
+			EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = self.existential_deposit);
+			let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+			balances::GenesisConfig::<Test> {
+				balances: vec![
+					(1, 10 * self.existential_deposit),
+					(2, 20 * self.existential_deposit),
+					(3, 30 * self.existential_deposit),
+					(4, 40 * self.existential_deposit),
+					(12, 10 * self.existential_deposit),
+				],
+			}.assimilate_storage(&mut t).unwrap();
+			GenesisConfig::<Test> {
+				vesting: vec![
+					(1, 0, 10, 5 * self.existential_deposit),
+					(2, 10, 20, 0),
+					(12, 10, 20, 5 * self.existential_deposit),
+				],
+			}.assimilate_storage(&mut t).unwrap();
+			let mut ext = sp_io::TestExternalities::new(t);
+			ext.execute_with(|| System::set_block_number(1));
+			ext
+		}
+	}
+
+	#[test]
+	fn check_vesting_status() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				assert_eq!(System::block_number(), 1);
+				let user1_free_balance = Balances::free_balance(&1);
+				let user2_free_balance = Balances::free_balance(&2);
+				let user12_free_balance = Balances::free_balance(&12);
+				assert_eq!(user1_free_balance, 256 * 10); // Account 1 has free balance
+				assert_eq!(user2_free_balance, 256 * 20); // Account 2 has free balance
+				assert_eq!(user12_free_balance, 256 * 10); // Account 12 has free balance
+				let user1_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 128, // Vesting over 10 blocks
+					starting_block: 0,
+				};
+				let user2_vesting_schedule = VestingInfo {
+					locked: 256 * 20,
+					per_block: 256, // Vesting over 20 blocks
+					starting_block: 10,
+				};
+				let user12_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 64, // Vesting over 20 blocks
+					starting_block: 10,
+				};
+				assert_eq!(Vesting::vesting(&1), vec![user1_vesting_schedule]); // Account 1 has a vesting schedule
+				assert_eq!(Vesting::vesting(&2), vec![user2_vesting_schedule]); // Account 2 has a vesting schedule
+				assert_eq!(Vesting::vesting(&12), vec![user12_vesting_schedule]); // Account 12 has a vesting schedule
+
+				// Account 1 has only 128 units vested from their illiquid 256 * 5 units at block 1
+				assert_eq!(Vesting::vesting_balance(&1), Some(128 * 9));
+				// Account 2 has their full balance locked
+				assert_eq!(Vesting::vesting_balance(&2), Some(user2_free_balance));
+				// Account 12 has only their illiquid funds locked
+				assert_eq!(Vesting::vesting_balance(&12), Some(user12_free_balance - 256 * 5));
+				// Account 3 is not vesting
+				assert_eq!(Vesting::vesting_balance(&3), None);
+
+				System::set_block_number(10);
+				assert_eq!(System::block_number(), 10);
+
+				// Account 1 has fully vested by block 10
+				assert_eq!(Vesting::vesting_balance(&1), Some(0));
+				// Account 2 has started vesting by block 10
+				assert_eq!(Vesting::vesting_balance(&2), Some(user2_free_balance));
+				// Account 12 has started vesting by block 10
+				assert_eq!(Vesting::vesting_balance(&12), Some(user12_free_balance - 256 * 5));
+
+				System::set_block_number(30);
+				assert_eq!(System::block_number(), 30);
+
+				assert_eq!(Vesting::vesting_balance(&1), Some(0)); // Account 1 is still fully vested, and not negative
+				assert_eq!(Vesting::vesting_balance(&2), Some(0)); // Account 2 has fully vested by block 30
+				assert_eq!(Vesting::vesting_balance(&12), Some(0)); // Account 12 has fully vested by block 30
+			});
+	}
+
+	#[test]
+	fn unvested_balance_should_not_transfer() {
+		ExtBuilder::default()
+			.existential_deposit(10)
+			.build()
+			.execute_with(|| {
+				assert_eq!(System::block_number(), 1);
+				let user1_free_balance = Balances::free_balance(&1);
+				assert_eq!(user1_free_balance, 100); // Account 1 has free balance
+				// Account 1 has only 5 units vested at block 1 (plus 50 unvested)
+				assert_eq!(Vesting::vesting_balance(&1), Some(45));
+				assert_noop!(
+					Balances::transfer(Some(1).into(), 2, 56),
+					"account liquidity restrictions prevent withdrawal",
+				); // Account 1 cannot send more than vested amount
+			});
+	}
+
+	#[test]
+	fn vested_balance_should_transfer() {
+		ExtBuilder::default()
+			.existential_deposit(10)
+			.build()
+			.execute_with(|| {
+				assert_eq!(System::block_number(), 1);
+				let user1_free_balance = Balances::free_balance(&1);
+				assert_eq!(user1_free_balance, 100); // Account 1 has free balance
+				// Account 1 has only 5 units vested at block 1 (plus 50 unvested)
+				assert_eq!(Vesting::vesting_balance(&1), Some(45));
+				// The lock is only reduced once the vested funds are explicitly unlocked
+				assert_noop!(
+					Balances::transfer(Some(1).into(), 2, 55),
+					"account liquidity restrictions prevent withdrawal",
+				);
+				assert_ok!(Vesting::vest(Some(1).into()));
+				assert_ok!(Balances::transfer(Some(1).into(), 2, 55));
+			});
+	}
+
+	#[test]
+	fn vested_balance_should_transfer_using_vest_other() {
+		ExtBuilder::default()
+			.existential_deposit(10)
+			.build()
+			.execute_with(|| {
+				assert_eq!(System::block_number(), 1);
+				assert_eq!(Vesting::vesting_balance(&1), Some(45));
+				assert_ok!(Vesting::vest_other(Some(2).into(), 1));
+				assert_ok!(Balances::transfer(Some(1).into(), 2, 55));
+				// Accounts without a schedule cannot be vested
+				assert_noop!(Vesting::vest_other(Some(2).into(), 3), "account has no vesting schedule");
+			});
+	}
+
+	#[test]
+	fn extra_balance_should_transfer() {
+		ExtBuilder::default()
+			.existential_deposit(10)
+			.build()
+			.execute_with(|| {
+				assert_eq!(System::block_number(), 1);
+				assert_ok!(Balances::transfer(Some(3).into(), 1, 100));
+				assert_ok!(Balances::transfer(Some(3).into(), 2, 100));
+
+				let user1_free_balance = Balances::free_balance(&1);
+				assert_eq!(user1_free_balance, 200); // Account 1 has 100 more free balance than normal
+
+				let user2_free_balance = Balances::free_balance(&2);
+				assert_eq!(user2_free_balance, 300); // Account 2 has 100 more free balance than normal
+
+				// Account 1 has only 5 units vested at block 1 (plus 150 unvested)
+				assert_eq!(Vesting::vesting_balance(&1), Some(45));
+				assert_ok!(Vesting::vest(Some(1).into()));
+				assert_ok!(Balances::transfer(Some(1).into(), 3, 155)); // Account 1 can send extra units gained
+
+				// Account 2 has no units vested at block 1, but gained 100
+				assert_eq!(Vesting::vesting_balance(&2), Some(200));
+				assert_ok!(Vesting::vest(Some(2).into()));
+				assert_ok!(Balances::transfer(Some(2).into(), 3, 100)); // Account 2 can send extra units gained
+			});
+	}
+
+	#[test]
+	fn liquid_funds_should_transfer_with_delayed_vesting() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				assert_eq!(System::block_number(), 1);
+				let user12_free_balance = Balances::free_balance(&12);
+
+				assert_eq!(user12_free_balance, 2560); // Account 12 has free balance
+				// Account 12 has liquid funds
+				assert_eq!(Vesting::vesting_balance(&12), Some(user12_free_balance - 256 * 5));
+
+				// Account 12 has delayed vesting
+				let user12_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 64, // Vesting over 20 blocks
+					starting_block: 10,
+				};
+				assert_eq!(Vesting::vesting(&12), vec![user12_vesting_schedule]);
+
+				// Account 12 can still send liquid funds
+				assert_ok!(Balances::transfer(Some(12).into(), 3, 256 * 5));
+			});
+	}
+
+	#[test]
+	fn fully_vested_account_should_have_lock_removed() {
+		ExtBuilder::default()
+			.existential_deposit(10)
+			.build()
+			.execute_with(|| {
+				System::set_block_number(10);
+				assert_eq!(Vesting::vesting_balance(&1), Some(0));
+				assert_ok!(Vesting::vest(Some(1).into()));
+				// The schedule is gone, and with it the lock
+				assert!(Vesting::vesting(&1).is_empty());
+				assert_eq!(Vesting::vesting_balance(&1), None);
+				assert_ok!(Balances::transfer(Some(1).into(), 2, 100));
+			});
+	}
+
+	#[test]
+	fn vested_transfer_works() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				assert_eq!(System::block_number(), 1);
+				let user3_free_balance = Balances::free_balance(&3);
+				let user4_free_balance = Balances::free_balance(&4);
+				assert_eq!(user3_free_balance, 256 * 30);
+				assert_eq!(user4_free_balance, 256 * 40);
+				// Account 4 should not have any vesting yet.
+				assert!(Vesting::vesting(&4).is_empty());
+				// Make the schedule for the new transfer.
+				let new_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 64, // Vesting over 20 blocks
+					starting_block: 10,
+				};
+				assert_ok!(Vesting::vested_transfer(Some(3).into(), 4, new_vesting_schedule));
+				// Now account 4 should have vesting.
+				assert_eq!(Vesting::vesting(&4), vec![new_vesting_schedule]);
+				// Ensure the transfer happened correctly.
+				assert_eq!(Balances::free_balance(&3), user3_free_balance - 256 * 5);
+				assert_eq!(Balances::free_balance(&4), user4_free_balance + 256 * 5);
+				// Account 4 has 5 * 256 locked.
+				assert_eq!(Vesting::vesting_balance(&4), Some(256 * 5));
+
+				System::set_block_number(20);
+				assert_eq!(System::block_number(), 20);
+
+				// Account 4 has 5 * 64 units vested by block 20.
+				assert_eq!(Vesting::vesting_balance(&4), Some(10 * 64));
+
+				System::set_block_number(30);
+				assert_eq!(System::block_number(), 30);
+
+				// Account 4 has fully vested.
+				assert_eq!(Vesting::vesting_balance(&4), Some(0));
+			});
+	}
+
+	#[test]
+	fn vested_transfer_correctly_fails() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				let user2_free_balance = Balances::free_balance(&2);
+				let user4_free_balance = Balances::free_balance(&4);
+				assert_eq!(user2_free_balance, 256 * 20);
+				assert_eq!(user4_free_balance, 256 * 40);
+
+				// The amount transferred must cover the minimum.
+				let new_vesting_schedule = VestingInfo {
+					locked: 256,
+					per_block: 64,
+					starting_block: 10,
+				};
+				assert_noop!(
+					Vesting::vested_transfer(Some(4).into(), 3, new_vesting_schedule),
+					"amount transferred is too low",
+				);
+
+				// A schedule that never ends would hold a schedule slot of the target forever.
+				let new_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 0,
+					starting_block: 10,
+				};
+				assert_noop!(
+					Vesting::vested_transfer(Some(4).into(), 3, new_vesting_schedule),
+					"schedule never ends",
+				);
+				assert_noop!(
+					<Vesting as VestingSchedule<u64>>::add_vesting_schedule(&3, 256 * 5, 0, 10),
+					"schedule never ends",
+				);
+
+				// The transferred funds must not be locked in the sender's account.
+				let new_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 64,
+					starting_block: 10,
+				};
+				assert_noop!(
+					Vesting::vested_transfer(Some(2).into(), 4, new_vesting_schedule),
+					"account liquidity restrictions prevent withdrawal",
+				);
+
+				// Verify no currency transfer happened.
+				assert_eq!(Balances::free_balance(&2), user2_free_balance);
+				assert_eq!(Balances::free_balance(&4), user4_free_balance);
+			});
+	}
+
+	#[test]
+	fn multiple_schedules_lock_their_sum() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				let sched = VestingInfo {
+					locked: 256 * 2,
+					per_block: 256,
+					starting_block: 2,
+				};
+				// Account 12 already has one schedule locking 256 * 5.
+				assert_ok!(Vesting::vested_transfer(Some(3).into(), 12, sched));
+				assert_ok!(Vesting::vested_transfer(Some(4).into(), 12, sched));
+				assert_eq!(Vesting::vesting(&12).len(), 3);
+				assert_eq!(Vesting::vesting_balance(&12), Some(256 * 9));
+
+				// No more than `MaxVestingSchedules` schedules may be held at once.
+				assert_noop!(
+					Vesting::vested_transfer(Some(4).into(), 12, sched),
+					"too many vesting schedules",
+				);
+				assert_noop!(
+					<Vesting as VestingSchedule<u64>>::add_vesting_schedule(&12, 256, 256, 10),
+					"too many vesting schedules",
+				);
+
+				// The two transferred schedules are fully vested by block 4, and are dropped once
+				// the lock is updated.
+				System::set_block_number(4);
+				assert_eq!(Vesting::vesting_balance(&12), Some(256 * 5));
+				assert_ok!(Vesting::vest(Some(12).into()));
+				assert_eq!(Vesting::vesting(&12).len(), 1);
+				assert_ok!(Balances::transfer(Some(12).into(), 3, 256 * 9));
+				assert_noop!(
+					Balances::transfer(Some(12).into(), 3, 1),
+					"account liquidity restrictions prevent withdrawal",
+				);
+
+				// Removing the schedules unlocks everything.
+				<Vesting as VestingSchedule<u64>>::remove_vesting_schedule(&12);
+				assert_eq!(Vesting::vesting_balance(&12), None);
+				assert_ok!(Balances::transfer(Some(12).into(), 3, 256 * 5));
+			});
+	}
+
+	#[test]
+	fn genesis_schedules_of_an_account_lock_their_sum() {
+		EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = 1);
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 1000)],
+		}.assimilate_storage(&mut t).unwrap();
+		GenesisConfig::<Test> {
+			// 400 locked by the first schedule, and 500 of the remaining 600 by the second one.
+			vesting: vec![(1, 0, 10, 600), (1, 10, 10, 100)],
+		}.assimilate_storage(&mut t).unwrap();
+
+		sp_io::TestExternalities::new(t).execute_with(|| {
+			System::set_block_number(1);
+			assert_eq!(Vesting::vesting(&1), vec![
+				VestingInfo { locked: 400, per_block: 40, starting_block: 0 },
+				VestingInfo { locked: 500, per_block: 50, starting_block: 10 },
+			]);
+			assert_eq!(Vesting::vesting_balance(&1), Some(860));
+
+			// The lock covers both schedules as of genesis.
+			assert_noop!(
+				Balances::transfer(Some(1).into(), 2, 101),
+				"account liquidity restrictions prevent withdrawal",
+			);
+			assert_ok!(Balances::transfer(Some(1).into(), 2, 100));
+		});
+	}
+
+	#[test]
+	fn migration_copies_vesting_schedules_from_the_balances_module() {
+		use migration::inner::OldVesting;
+		use support::{StoragePrefixedMap, storage::unhashed};
+
+		ExtBuilder::default()
+			.existential_deposit(1)
+			.build()
+			.execute_with(|| {
+				System::set_block_number(10);
+
+				let old_key = |who: u64| [
+					&OldVesting::<Test>::final_prefix()[..],
+					&who.using_encoded(sp_io::hashing::blake2_256)[..],
+				].concat();
+
+				// 3 is still vesting, 4 is fully vested.
+				let schedule = VestingInfo { locked: 10, per_block: 1, starting_block: 5 };
+				unhashed::put(&old_key(3), &schedule);
+				let vested = VestingInfo { locked: 5, per_block: 1, starting_block: 0 };
+				unhashed::put(&old_key(4), &vested);
+				<Vesting as Store>::StorageVersion::put(0);
+
+				migration::perform_migrations::<Test>();
+				assert_eq!(<Vesting as Store>::StorageVersion::get(), 1);
+
+				// The old schedules are left for the balances module to lock and remove.
+				assert_eq!(Vesting::vesting(&3), vec![schedule]);
+				assert_eq!(unhashed::get(&old_key(3)), Some(schedule));
+				assert!(Vesting::vesting(&4).is_empty());
+			});
+	}
+}
@@ -0,0 +1,110 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage migrations for pallet-vesting.
+
+/// Indicator of a version of a storage layout.
+pub type VersionNumber = u32;
+
+// the current expected version of the storage
+pub const CURRENT_VERSION: VersionNumber = 1;
+
+#[cfg(any(test, feature = "migrate"))]
+pub(crate) mod inner {
+	use crate::{Store, Trait, Module, Vesting, VestingInfo, BalanceOf};
+	use support::{StorageValue, StoragePrefixedMap, storage::unhashed};
+	use sp_runtime::traits::Zero;
+	use sp_std::{prelude::*, marker::PhantomData};
+	use super::{CURRENT_VERSION, VersionNumber};
+
+	// the minimum supported version of the migration logic.
+	const MIN_SUPPORTED_VERSION: VersionNumber = 0;
+
+	/// `Vesting` of the balances module before it moved to this module: the single vesting
+	/// schedule of each account, encoded as a `VestingInfo`.
+	///
+	/// The schedules are expected in the default instance of the balances module.
+	pub(crate) struct OldVesting<T>(PhantomData<T>);
+
+	impl<T: Trait> StoragePrefixedMap<VestingInfo<BalanceOf<T>, T::BlockNumber>> for OldVesting<T> {
+		fn module_prefix() -> &'static [u8] { b"Balances" }
+		fn storage_prefix() -> &'static [u8] { b"Vesting" }
+	}
+
+	// migrate storage from v0 to v1.
+	//
+	// vesting moved out of the balances module. the schedules still vesting are copied to
+	// `Vesting`, the balances module locks their unvested amount and removes them in its own
+	// migration. as `on_initialize` of modules runs in the reverse order of their declaration in
+	// `construct_runtime!`, this module must be declared after the balances module.
+	//
+	// the accounts of the old map can't be recovered from its keys, but both maps use the same
+	// hasher, so each schedule is copied under the same hashed key.
+	pub fn to_v1<T: Trait>(version: &mut VersionNumber) {
+		if *version != 0 { return }
+		*version += 1;
+
+		let now = <system::Module<T>>::block_number();
+		let old_prefix = OldVesting::<T>::final_prefix();
+		let new_prefix = <Vesting<T> as StoragePrefixedMap<_>>::final_prefix();
+
+		let mut previous_key = old_prefix.to_vec();
+		while let Some(key) = sp_io::storage::next_key(&previous_key)
+			.filter(|key| key.starts_with(&old_prefix))
+		{
+			previous_key = key.clone();
+
+			let schedule: VestingInfo<BalanceOf<T>, T::BlockNumber> = match unhashed::get(&key) {
+				Some(schedule) => schedule,
+				None => continue,
+			};
+			if schedule.locked_at::<T::BlockNumberToBalance>(now).is_zero() {
+				continue
+			}
+
+			let vesting_key = [&new_prefix[..], &key[old_prefix.len()..]].concat();
+			unhashed::put(&vesting_key, &vec![schedule]);
+		}
+
+		support::print("Finished migrating Vesting storage to v1.");
+	}
+
+	pub(crate) fn perform_migrations<T: Trait>() {
+		<Module<T> as Store>::StorageVersion::mutate(|version| {
+			if *version < MIN_SUPPORTED_VERSION {
+				support::print("Cannot migrate vesting storage because version is less than\
+					minimum.");
+				support::print(*version);
+				return
+			}
+
+			if *version == CURRENT_VERSION { return }
+
+			to_v1::<T>(version);
+		});
+	}
+}
+
+#[cfg(not(any(test, feature = "migrate")))]
+mod inner {
+	pub(super) fn perform_migrations<T>() { }
+}
+
+/// Perform all necessary storage migrations to get storage into the expected state for current
+/// logic. No-op if fully upgraded.
+pub(crate) fn perform_migrations<T: crate::Trait>() {
+	inner::perform_migrations::<T>();
+}
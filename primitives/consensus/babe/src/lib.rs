@@ -22,9 +22,12 @@
 mod digest;
 pub mod inherents;
 
+use app_crypto::RuntimeAppPublic;
 use codec::{Encode, Decode};
+use merlin::Transcript;
+use schnorrkel::vrf::{VRFOutput, VRFProof};
 use sp_std::vec::Vec;
-use sp_runtime::{ConsensusEngineId, RuntimeDebug};
+use sp_runtime::{ConsensusEngineId, RuntimeDebug, traits::Header as HeaderT};
 
 #[cfg(feature = "std")]
 pub use digest::{BabePreDigest, CompatibleDigestItem};
@@ -47,6 +50,9 @@ pub type AuthoritySignature = app::Signature;
 /// the main Babe module. If that ever changes, then this must, too.
 pub type AuthorityId = app::Public;
 
+/// Key type for BABE module.
+pub const KEY_TYPE: app_crypto::KeyTypeId = app_crypto::key_types::BABE;
+
 /// The `ConsensusEngineId` of BABE.
 pub const BABE_ENGINE_ID: ConsensusEngineId = *b"BABE";
 
@@ -125,6 +131,104 @@ pub enum ConsensusLog {
 	OnDisabled(AuthorityIndex),
 }
 
+/// Proof of an authority authoring two different blocks in the same slot.
+#[derive(Clone, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub struct EquivocationProof<Header> {
+	/// The authority that authored both blocks.
+	pub offender: AuthorityId,
+	/// The slot in which both blocks were authored.
+	pub slot_number: SlotNumber,
+	/// The first header, including its seal.
+	pub first_header: Header,
+	/// The second header, including its seal.
+	pub second_header: Header,
+}
+
+/// Check that an equivocation proof is valid: both headers are different, claim the
+/// slot of the proof and are sealed by the offender.
+pub fn check_equivocation_proof<H: HeaderT>(proof: EquivocationProof<H>) -> bool {
+	let EquivocationProof { offender, slot_number, first_header, second_header } = proof;
+
+	// returns the slot claimed by the header if its seal is signed by the offender.
+	let check_header = |mut header: H| -> Option<SlotNumber> {
+		let seal = header.digest_mut().pop()?;
+		let signature = seal.as_seal()
+			.filter(|(id, _)| *id == BABE_ENGINE_ID)
+			.and_then(|(_, signature)| AuthoritySignature::decode(&mut &signature[..]).ok())?;
+
+		let pre_digest = header.digest().logs().iter()
+			.filter_map(|log| log.as_pre_runtime())
+			.find(|(id, _)| *id == BABE_ENGINE_ID)
+			.and_then(|(_, pre_digest)| RawBabePreDigest::decode(&mut &pre_digest[..]).ok())?;
+
+		let pre_hash = header.hash();
+		if offender.verify(&pre_hash, &signature) {
+			Some(pre_digest.slot_number())
+		} else {
+			None
+		}
+	};
+
+	first_header.hash() != second_header.hash() &&
+		check_header(first_header) == Some(slot_number) &&
+		check_header(second_header) == Some(slot_number)
+}
+
+/// Make the VRF transcript of a primary slot claim for the given slot and epoch.
+#[allow(deprecated)]
+pub fn make_transcript(randomness: &[u8], slot_number: SlotNumber, epoch: u64) -> Transcript {
+	let mut transcript = Transcript::new(&BABE_ENGINE_ID);
+	transcript.commit_bytes(b"slot number", &slot_number.to_le_bytes());
+	transcript.commit_bytes(b"current epoch", &epoch.to_le_bytes());
+	transcript.commit_bytes(b"chain randomness", randomness);
+	transcript
+}
+
+/// Verify that the given VRF output and proof of a primary slot claim were
+/// produced by the given authority for the given slot and epoch, returning the
+/// value that is compared against the primary threshold.
+pub fn verify_primary_vrf(
+	author: &AuthorityId,
+	randomness: &[u8],
+	slot_number: SlotNumber,
+	epoch: u64,
+	vrf_output: &[u8; VRF_OUTPUT_LENGTH],
+	vrf_proof: &[u8; VRF_PROOF_LENGTH],
+) -> Option<u128> {
+	let transcript = make_transcript(randomness, slot_number, epoch);
+	let vrf_output = VRFOutput::from_bytes(&vrf_output[..]).ok()?;
+	let vrf_proof = VRFProof::from_bytes(&vrf_proof[..]).ok()?;
+
+	let public = AsRef::<[u8]>::as_ref(author);
+	let (inout, _) = schnorrkel::PublicKey::from_bytes(public)
+		.and_then(|p| p.vrf_verify(transcript, &vrf_output, &vrf_proof))
+		.ok()?;
+
+	Some(u128::from_le_bytes(inout.make_bytes::<[u8; 16]>(BABE_VRF_PREFIX)))
+}
+
+/// An opaque type used to represent the key ownership proof at the runtime API
+/// boundary. The inner value is an encoded representation of the actual key
+/// ownership proof which will be parameterized when defining the runtime. At
+/// the runtime API boundary this type is unknown and as such we keep this
+/// opaque representation, implementors of the runtime API will have to make
+/// sure that all usages of `OpaqueKeyOwnershipProof` refer to the same type.
+#[derive(Decode, Encode, PartialEq, Eq, Clone, RuntimeDebug)]
+pub struct OpaqueKeyOwnershipProof(Vec<u8>);
+
+impl OpaqueKeyOwnershipProof {
+	/// Create a new `OpaqueKeyOwnershipProof` using the given encoded representation.
+	pub fn new(inner: Vec<u8>) -> OpaqueKeyOwnershipProof {
+		OpaqueKeyOwnershipProof(inner)
+	}
+
+	/// Try to decode this `OpaqueKeyOwnershipProof` into the given concrete key
+	/// ownership proof type.
+	pub fn decode<T: Decode>(self) -> Option<T> {
+		Decode::decode(&mut &self.0[..]).ok()
+	}
+}
+
 /// Configuration data used by the BABE consensus engine.
 #[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
 pub struct BabeConfiguration {
@@ -167,11 +271,33 @@ impl sp_consensus::SlotData for BabeConfiguration {
 
 sp_api::decl_runtime_apis! {
 	/// API necessary for block authorship with BABE.
+	#[api_version(2)]
 	pub trait BabeApi {
 		/// Return the configuration for BABE. Currently,
 		/// only the value provided by this type at genesis will be used.
 		///
 		/// Dynamic configuration may be supported in the future.
 		fn configuration() -> BabeConfiguration;
+
+		/// Generates a proof of key ownership for the given authority in the
+		/// epoch of the given slot. The proof can later be used to report an
+		/// equivocation, `None` is returned if it can't be generated (e.g. the
+		/// session of the slot has been pruned from the historical sessions).
+		fn generate_key_ownership_proof(
+			slot_number: SlotNumber,
+			authority_id: AuthorityId,
+		) -> Option<OpaqueKeyOwnershipProof>;
+
+		/// Submits an unsigned extrinsic to report an equivocation. The caller
+		/// must provide the equivocation proof and a key ownership proof
+		/// (should be obtained using `generate_key_ownership_proof`). This
+		/// method returns `None` when creation of the extrinsic fails, e.g. if
+		/// equivocation reporting is disabled for the given runtime (i.e. this
+		/// method is hardcoded to return `None`). Only useful in an offchain
+		/// context, with access to the transaction pool.
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: EquivocationProof<Block::Header>,
+			key_owner_proof: OpaqueKeyOwnershipProof,
+		) -> Option<()>;
 	}
 }
@@ -0,0 +1,293 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Primitives for Sassafras, a slot-based block production engine where
+//! exactly one author is assigned to each slot.
+//!
+//! During an epoch, authorities generate VRF-based tickets for the next epoch
+//! and submit the winning ones on-chain together with their VRF proofs, where
+//! they are verified against the submitting authority and sorted. At the start
+//! of the next epoch the sorted tickets are assigned to its slots, and only
+//! the owner of a slot's ticket can claim it. Slots without a ticket fall back
+//! to a round-robin assignment.
+//!
+//! The VRF and the authority keys are shared with BABE. Since tickets are
+//! submitted with a plain VRF proof, the owner of a ticket is known as soon as
+//! it is submitted, an anonymous submission would require a ring VRF.
+
+#![forbid(unsafe_code, missing_docs, unused_variables, unused_imports)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Encode, Decode};
+use merlin::Transcript;
+use schnorrkel::vrf::{VRFOutput, VRFProof};
+use sp_std::vec::Vec;
+use sp_runtime::{ConsensusEngineId, RuntimeDebug, generic::OpaqueDigestItemId};
+use sp_runtime::generic::DigestItem;
+
+pub use babe_primitives::{
+	AuthorityId, AuthorityIndex, AuthoritySignature, SlotNumber, VRF_OUTPUT_LENGTH,
+	VRF_PROOF_LENGTH, KEY_TYPE,
+};
+#[cfg(feature = "std")]
+pub use babe_primitives::AuthorityPair;
+
+pub mod inherents;
+
+/// The `ConsensusEngineId` of Sassafras.
+pub const SASSAFRAS_ENGINE_ID: ConsensusEngineId = *b"SASS";
+
+/// The prefix used by Sassafras to derive a ticket from a VRF output.
+pub const SASSAFRAS_TICKET_VRF_PREFIX: &[u8] = b"substrate-sassafras-ticket-vrf";
+
+/// Randomness used as input for the ticket VRFs of an epoch.
+pub type Randomness = [u8; 32];
+
+/// A ticket, i.e. the bytes derived from a ticket VRF output. Tickets are
+/// ordered by their value.
+pub type Ticket = [u8; 32];
+
+/// The VRF proof of a ticket, submitted on-chain by its owner. The ticket is
+/// derived from the VRF output, so it can only be produced by the authority
+/// holding the key the proof verifies against.
+#[derive(Copy, Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct TicketProof {
+	/// The attempt number used as VRF input.
+	pub attempt: u32,
+	/// The ticket VRF output.
+	pub vrf_output: [u8; VRF_OUTPUT_LENGTH],
+	/// The ticket VRF proof.
+	pub vrf_proof: [u8; VRF_PROOF_LENGTH],
+}
+
+/// A Sassafras pre-runtime digest, containing the claim of the block's slot.
+#[derive(Copy, Clone, Encode, Decode)]
+pub enum PreDigest {
+	/// The slot was claimed by revealing the VRF proof of its ticket.
+	Primary {
+		/// Authority index
+		authority_index: AuthorityIndex,
+		/// Slot number
+		slot_number: SlotNumber,
+		/// The attempt number used as VRF input when generating the ticket.
+		ticket_attempt: u32,
+		/// The ticket VRF output.
+		ticket_vrf_output: [u8; VRF_OUTPUT_LENGTH],
+		/// The ticket VRF proof.
+		ticket_vrf_proof: [u8; VRF_PROOF_LENGTH],
+	},
+	/// The slot had no ticket and was claimed by its round-robin author.
+	Secondary {
+		/// Authority index
+		authority_index: AuthorityIndex,
+		/// Slot number
+		slot_number: SlotNumber,
+	},
+}
+
+impl PreDigest {
+	/// Returns the authority index of the pre digest.
+	pub fn authority_index(&self) -> AuthorityIndex {
+		match self {
+			PreDigest::Primary { authority_index, .. } => *authority_index,
+			PreDigest::Secondary { authority_index, .. } => *authority_index,
+		}
+	}
+
+	/// Returns the slot number of the pre digest.
+	pub fn slot_number(&self) -> SlotNumber {
+		match self {
+			PreDigest::Primary { slot_number, .. } => *slot_number,
+			PreDigest::Secondary { slot_number, .. } => *slot_number,
+		}
+	}
+}
+
+/// A digest item which is usable with Sassafras consensus.
+pub trait CompatibleDigestItem: Sized {
+	/// Construct a digest item which contains a Sassafras pre-digest.
+	fn sassafras_pre_digest(digest: PreDigest) -> Self;
+
+	/// If this item is a Sassafras pre-digest, return it.
+	fn as_sassafras_pre_digest(&self) -> Option<PreDigest>;
+
+	/// Construct a digest item which contains a Sassafras seal.
+	fn sassafras_seal(signature: AuthoritySignature) -> Self;
+
+	/// If this item is a Sassafras signature, return the signature.
+	fn as_sassafras_seal(&self) -> Option<AuthoritySignature>;
+}
+
+impl<Hash> CompatibleDigestItem for DigestItem<Hash> where
+	Hash: Send + Sync + Eq + Clone + Encode + Decode + 'static
+{
+	fn sassafras_pre_digest(digest: PreDigest) -> Self {
+		DigestItem::PreRuntime(SASSAFRAS_ENGINE_ID, digest.encode())
+	}
+
+	fn as_sassafras_pre_digest(&self) -> Option<PreDigest> {
+		self.try_to(OpaqueDigestItemId::PreRuntime(&SASSAFRAS_ENGINE_ID))
+	}
+
+	fn sassafras_seal(signature: AuthoritySignature) -> Self {
+		DigestItem::Seal(SASSAFRAS_ENGINE_ID, signature.encode())
+	}
+
+	fn as_sassafras_seal(&self) -> Option<AuthoritySignature> {
+		self.try_to(OpaqueDigestItemId::Seal(&SASSAFRAS_ENGINE_ID))
+	}
+}
+
+/// Configuration data used by the Sassafras consensus engine.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct SassafrasConfiguration {
+	/// The slot duration in milliseconds.
+	pub slot_duration: u64,
+	/// The duration of epochs in slots.
+	pub epoch_duration: SlotNumber,
+	/// The number of tickets each authority tries to generate per epoch.
+	pub attempts_number: u32,
+	/// The expected number of winning tickets per slot, a value greater than
+	/// one leaves room for authorities that fail to submit or claim tickets.
+	pub redundancy_factor: u32,
+}
+
+#[cfg(feature = "std")]
+impl sp_consensus::SlotData for SassafrasConfiguration {
+	fn slot_duration(&self) -> u64 {
+		self.slot_duration
+	}
+
+	const SLOT_KEY: &'static [u8] = b"sassafras_configuration";
+}
+
+/// Sassafras epoch information.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct Epoch {
+	/// The epoch index.
+	pub epoch_index: u64,
+	/// The starting slot of the epoch.
+	pub start_slot: SlotNumber,
+	/// The duration of this epoch in slots.
+	pub duration: SlotNumber,
+	/// The authorities of this epoch.
+	pub authorities: Vec<AuthorityId>,
+	/// Randomness used as input for the ticket VRFs of this epoch.
+	pub randomness: Randomness,
+}
+
+impl Epoch {
+	/// Returns the round-robin author of the given slot, used when no ticket
+	/// was assigned to it.
+	pub fn secondary_slot_author(&self, slot_number: SlotNumber) -> Option<AuthorityIndex> {
+		if self.authorities.is_empty() {
+			return None;
+		}
+
+		Some((slot_number % self.authorities.len() as u64) as AuthorityIndex)
+	}
+}
+
+/// Computes the threshold under which a ticket is a winning ticket, so that
+/// roughly `redundancy_factor` tickets are submitted for each slot of an epoch
+/// given the number of authorities and attempts.
+pub fn ticket_threshold(
+	redundancy_factor: u32,
+	slots: SlotNumber,
+	attempts: u32,
+	authorities: u32,
+) -> u128 {
+	let tickets = (attempts as u128).saturating_mul(authorities as u128);
+	if tickets == 0 {
+		return 0;
+	}
+
+	let expected = (redundancy_factor as u128).saturating_mul(slots as u128);
+	if expected >= tickets {
+		return u128::max_value();
+	}
+
+	(u128::max_value() / tickets).saturating_mul(expected)
+}
+
+/// Returns the value of a ticket, which is compared against the threshold
+/// and used to sort tickets.
+pub fn ticket_value(ticket: &Ticket) -> u128 {
+	let mut bytes = [0u8; 16];
+	bytes.copy_from_slice(&ticket[..16]);
+	u128::from_le_bytes(bytes)
+}
+
+/// Make the VRF transcript of the given ticket attempt for the given epoch.
+#[allow(deprecated)]
+pub fn make_ticket_transcript(randomness: &[u8], attempt: u32, epoch_index: u64) -> Transcript {
+	let mut transcript = Transcript::new(&SASSAFRAS_ENGINE_ID);
+	transcript.commit_bytes(b"ticket attempt", &attempt.to_le_bytes());
+	transcript.commit_bytes(b"current epoch", &epoch_index.to_le_bytes());
+	transcript.commit_bytes(b"chain randomness", randomness);
+	transcript
+}
+
+/// Verify that the given ticket VRF output and proof were produced by the
+/// given authority for the given attempt and epoch, returning the ticket.
+pub fn verify_ticket(
+	author: &AuthorityId,
+	randomness: &Randomness,
+	epoch_index: u64,
+	attempt: u32,
+	vrf_output: &[u8; VRF_OUTPUT_LENGTH],
+	vrf_proof: &[u8; VRF_PROOF_LENGTH],
+) -> Option<Ticket> {
+	let transcript = make_ticket_transcript(randomness, attempt, epoch_index);
+	let vrf_output = VRFOutput::from_bytes(&vrf_output[..]).ok()?;
+	let vrf_proof = VRFProof::from_bytes(&vrf_proof[..]).ok()?;
+
+	let public = AsRef::<[u8]>::as_ref(author);
+	let (inout, _) = schnorrkel::PublicKey::from_bytes(public)
+		.and_then(|p| p.vrf_verify(transcript, &vrf_output, &vrf_proof))
+		.ok()?;
+
+	Some(inout.make_bytes::<Ticket>(SASSAFRAS_TICKET_VRF_PREFIX))
+}
+
+sp_api::decl_runtime_apis! {
+	/// API necessary for block authorship with Sassafras.
+	pub trait SassafrasApi {
+		/// Return the configuration for Sassafras. Currently, only the value
+		/// provided by this type at genesis will be used.
+		fn configuration() -> SassafrasConfiguration;
+
+		/// Returns the epoch a block authored at the given slot on top of the
+		/// current block would belong to.
+		fn slot_epoch(slot_number: SlotNumber) -> Epoch;
+
+		/// Returns the epoch for which tickets are currently being collected.
+		fn next_epoch() -> Epoch;
+
+		/// Returns the ticket assigned to the given slot, if any. Slots
+		/// without a ticket are claimed by their round-robin author.
+		fn slot_ticket(slot_number: SlotNumber) -> Option<Ticket>;
+
+		/// Submits an unsigned extrinsic with the ticket proofs of the given
+		/// next epoch authority. Only useful in an offchain context, with
+		/// access to the transaction pool.
+		fn submit_tickets_unsigned_extrinsic(
+			authority_index: AuthorityIndex,
+			tickets: Vec<TicketProof>,
+		) -> bool;
+	}
+}
@@ -131,6 +131,11 @@ pub struct Support<AccountId> {
 /// A linkage from a candidate and its [`Support`].
 pub type SupportMap<A> = BTreeMap<A, Support<A>>;
 
+/// The score of an election result, used to compare the quality of two results. In order, the
+/// minimum support of the winners, the sum of their supports and the sum of their supports
+/// squared.
+pub type PhragmenScore = [ExtendedBalance; 3];
+
 /// Perform election based on Phragmén algorithm.
 ///
 /// Returns an `Option` the set of winners and their detailed support ratio from each voter if
@@ -388,6 +393,33 @@ pub fn build_support_map<Balance, AccountId, FS, C>(
 	supports
 }
 
+/// Evaluate the score of an election result, given its support map.
+///
+/// The score is `[min(support.total), sum(support.total), sum(support.total^2)]`. An empty
+/// support map has a score of zero.
+pub fn evaluate_support<AccountId>(support: &SupportMap<AccountId>) -> PhragmenScore {
+	let mut min_support = ExtendedBalance::max_value();
+	let mut sum: ExtendedBalance = Zero::zero();
+	let mut sum_squared: ExtendedBalance = Zero::zero();
+	for (_, support) in support.iter() {
+		min_support = min_support.min(support.total);
+		sum = sum.saturating_add(support.total);
+		sum_squared = sum_squared.saturating_add(support.total.saturating_mul(support.total));
+	}
+	if support.is_empty() {
+		min_support = Zero::zero();
+	}
+	[min_support, sum, sum_squared]
+}
+
+/// Returns `true` if the score `new` is strictly better than `old`.
+///
+/// A score is better if its minimum support is higher, then if its sum of supports is higher,
+/// and finally if its sum of squared supports is lower, i.e. the supports are more even.
+pub fn is_score_better(new: PhragmenScore, old: PhragmenScore) -> bool {
+	(new[0], new[1], old[2]) > (old[0], old[1], new[2])
+}
+
 /// Performs equalize post-processing to the output of the election algorithm. This happens in
 /// rounds. The number of rounds and the maximum diff-per-round tolerance can be tuned through input
 /// parameters.
@@ -19,7 +19,9 @@
 #![cfg(test)]
 
 use crate::mock::*;
-use crate::{elect, PhragmenResult};
+use crate::{
+	elect, evaluate_support, is_score_better, PhragmenResult, Support, SupportMap,
+};
 use substrate_test_utils::assert_eq_uvec;
 use sp_runtime::Perbill;
 
@@ -354,3 +356,31 @@ fn phragmen_linear_equalize() {
 
 	run_and_compare(candidates, voters, stake_of, 2, 2);
 }
+
+#[test]
+fn score_evaluation_works() {
+	let mut supports = SupportMap::<AccountId>::new();
+	assert_eq!(evaluate_support(&supports), [0, 0, 0]);
+
+	supports.insert(10, Support { own: 10, total: 30, others: vec![(1, 20)] });
+	supports.insert(20, Support { own: 20, total: 20, others: vec![] });
+	assert_eq!(evaluate_support(&supports), [20, 50, 30 * 30 + 20 * 20]);
+}
+
+#[test]
+fn score_comparison_works() {
+	// a higher minimum support wins, whatever the rest.
+	assert!(is_score_better([10, 0, 1000], [9, 100, 0]));
+	assert!(!is_score_better([9, 100, 0], [10, 0, 1000]));
+
+	// then a higher sum of supports.
+	assert!(is_score_better([10, 21, 1000], [10, 20, 0]));
+	assert!(!is_score_better([10, 19, 0], [10, 20, 1000]));
+
+	// then a lower sum of squared supports.
+	assert!(is_score_better([10, 20, 99], [10, 20, 100]));
+	assert!(!is_score_better([10, 20, 101], [10, 20, 100]));
+
+	// an equal score is not better.
+	assert!(!is_score_better([10, 20, 100], [10, 20, 100]));
+}
@@ -619,6 +619,118 @@ where
 	Ok(proving_backend.extract_proof())
 }
 
+/// Generate a proof for a range of storage entries.
+///
+/// Entries are read in key order, starting with the first key strictly greater than `start_at`
+/// (or the first key of the trie if `start_at` is empty), until the accumulated size of keys and
+/// values exceeds `size_limit`. If `child_storage_key` is given, the child trie is read instead
+/// of the top trie.
+///
+/// Returns the proof, the entries and whether the range reaches the end of the trie.
+pub fn prove_range_read_with_size<B, H>(
+	mut backend: B,
+	child_storage_key: Option<&[u8]>,
+	size_limit: usize,
+	start_at: &[u8],
+) -> Result<(StorageProof, Vec<(Vec<u8>, Vec<u8>)>, bool), Box<dyn Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	H::Out: Ord + Codec,
+{
+	let trie_backend = backend.as_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<dyn Error>)?;
+	let proving_backend = proving_backend::ProvingBackend::<_, H>::new(trie_backend);
+	let (entries, complete) = read_range(&proving_backend, child_storage_key, size_limit, start_at)?;
+	Ok((proving_backend.extract_proof(), entries, complete))
+}
+
+/// Check a storage range proof, generated by `prove_range_read_with_size` call.
+///
+/// Succeeds if the proof shows that `entries` are exactly the storage entries following
+/// `start_at` and, if `complete` is `true`, that there are no entries after the last one.
+pub fn read_range_proof_check<H>(
+	root: H::Out,
+	proof: StorageProof,
+	child_storage_key: Option<&[u8]>,
+	start_at: &[u8],
+	entries: &[(Vec<u8>, Vec<u8>)],
+	complete: bool,
+) -> Result<(), Box<dyn Error>>
+where
+	H: Hasher,
+	H::Out: Ord + Codec,
+{
+	let proving_backend = create_proof_check_backend::<H>(root, proof)?;
+	let next_key = |key: &[u8]| match child_storage_key {
+		Some(storage_key) => proving_backend.next_child_storage_key(storage_key, key),
+		None => proving_backend.next_storage_key(key),
+	};
+	let value = |key: &[u8]| match child_storage_key {
+		Some(storage_key) => proving_backend.child_storage(storage_key, key),
+		None => proving_backend.storage(key),
+	};
+
+	let mut current = start_at.to_vec();
+	for (key, expected) in entries {
+		let next = next_key(&current).map_err(|e| Box::new(e) as Box<dyn Error>)?;
+		if next.as_ref() != Some(key) {
+			return Err(Box::new("Unexpected key in storage range".to_owned()));
+		}
+		let actual = value(key).map_err(|e| Box::new(e) as Box<dyn Error>)?;
+		if actual.as_ref() != Some(expected) {
+			return Err(Box::new("Unexpected value in storage range".to_owned()));
+		}
+		current = key.clone();
+	}
+
+	if complete {
+		let next = next_key(&current).map_err(|e| Box::new(e) as Box<dyn Error>)?;
+		if next.is_some() {
+			return Err(Box::new("Storage range is not complete".to_owned()));
+		}
+	}
+
+	Ok(())
+}
+
+/// Reads storage entries following `start_at` until `size_limit` is exceeded.
+fn read_range<B, H>(
+	backend: &B,
+	child_storage_key: Option<&[u8]>,
+	size_limit: usize,
+	start_at: &[u8],
+) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool), Box<dyn Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+{
+	let map_e = |e| Box::new(ExecutionError::Backend(format!("{}", e))) as Box<dyn Error>;
+	let mut entries = Vec::new();
+	let mut size = 0;
+	let mut current = start_at.to_vec();
+	loop {
+		let next = match child_storage_key {
+			Some(storage_key) => backend.next_child_storage_key(storage_key, &current),
+			None => backend.next_storage_key(&current),
+		}.map_err(map_e)?;
+		let key = match next {
+			Some(key) => key,
+			None => return Ok((entries, true)),
+		};
+		if size > size_limit {
+			return Ok((entries, false));
+		}
+		let value = match child_storage_key {
+			Some(storage_key) => backend.child_storage(storage_key, &key),
+			None => backend.storage(&key),
+		}.map_err(map_e)?.unwrap_or_default();
+		size += key.len() + value.len();
+		entries.push((key.clone(), value));
+		current = key;
+	}
+}
+
 /// Check storage read proof, generated by `prove_read` call.
 pub fn read_proof_check<H, I>(
 	root: H::Out,
@@ -1057,6 +1169,63 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn prove_range_read_and_proof_check_works() {
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let all = remote_backend.pairs();
+
+		// read the storage in small chunks and check every one of them
+		let mut start = Vec::new();
+		let mut collected = Vec::new();
+		loop {
+			let remote_backend = trie_backend::tests::test_trie();
+			let (proof, entries, complete) = prove_range_read_with_size(
+				remote_backend,
+				None,
+				8,
+				&start,
+			).unwrap();
+			read_range_proof_check::<Blake2Hasher>(
+				remote_root,
+				proof.clone(),
+				None,
+				&start,
+				&entries,
+				complete,
+			).unwrap();
+			// dropping an entry or claiming completeness early must be detected
+			if entries.len() > 1 {
+				assert!(read_range_proof_check::<Blake2Hasher>(
+					remote_root,
+					proof.clone(),
+					None,
+					&start,
+					&entries[1..],
+					complete,
+				).is_err());
+			}
+			if !complete {
+				assert!(read_range_proof_check::<Blake2Hasher>(
+					remote_root,
+					proof,
+					None,
+					&start,
+					&entries,
+					true,
+				).is_err());
+			}
+			if let Some((key, _)) = entries.last() {
+				start = key.clone();
+			}
+			collected.extend(entries);
+			if complete {
+				break;
+			}
+		}
+		assert_eq!(collected, all);
+	}
+
 	#[test]
 	fn cannot_change_changes_trie_config() {
 		let backend = trie_backend::tests::test_trie();
@@ -386,6 +386,8 @@ impl pallet_timestamp::Trait for Runtime {
 parameter_types! {
 	pub const EpochDuration: u64 = 6;
 	pub const ExpectedBlockTime: u64 = 10_000;
+	pub const PrimaryProbability: (u64, u64) = (3, 10);
+	pub const SecondarySlots: bool = true;
 }
 
 impl pallet_babe::Trait for Runtime {
@@ -395,6 +397,14 @@ impl pallet_babe::Trait for Runtime {
 	// are manually adding the digests. normally in this situation you'd use
 	// pallet_babe::SameAuthoritiesForever.
 	type EpochChangeTrigger = pallet_babe::ExternalTrigger;
+	type PrimaryProbability = PrimaryProbability;
+	type SecondarySlots = SecondarySlots;
+	type Call = pallet_babe::Call<Runtime>;
+	// equivocation reporting is disabled in the test runtime.
+	type KeyOwnerProofSystem = ();
+	type KeyOwnerProof = runtime_support::Void;
+	type KeyOwnerIdentification = runtime_support::Void;
+	type HandleEquivocation = ();
 }
 
 /// Adds one to the given input and returns the final result.
@@ -612,13 +622,50 @@ cfg_if! {
 					babe_primitives::BabeConfiguration {
 						slot_duration: 1000,
 						epoch_length: EpochDuration::get(),
-						c: (3, 10),
+						c: PrimaryProbability::get(),
 						genesis_authorities: system::authorities()
 							.into_iter().map(|x|(x, 1)).collect(),
 						randomness: <pallet_babe::Module<Runtime>>::randomness(),
-						secondary_slots: true,
+						secondary_slots: SecondarySlots::get(),
 					}
 				}
+
+				fn generate_key_ownership_proof(
+					_slot_number: babe_primitives::SlotNumber,
+					_authority_id: babe_primitives::AuthorityId,
+				) -> Option<babe_primitives::OpaqueKeyOwnershipProof> {
+					None
+				}
+
+				fn submit_report_equivocation_unsigned_extrinsic(
+					_equivocation_proof: babe_primitives::EquivocationProof<<Block as BlockT>::Header>,
+					_key_owner_proof: babe_primitives::OpaqueKeyOwnershipProof,
+				) -> Option<()> {
+					None
+				}
+			}
+
+			impl grandpa_primitives::GrandpaApi<Block> for Runtime {
+				fn grandpa_authorities() -> grandpa_primitives::AuthorityList {
+					Vec::new()
+				}
+
+				fn generate_key_ownership_proof(
+					_set_id: grandpa_primitives::SetId,
+					_authority_id: grandpa_primitives::AuthorityId,
+				) -> Option<grandpa_primitives::OpaqueKeyOwnershipProof> {
+					None
+				}
+
+				fn submit_report_equivocation_unsigned_extrinsic(
+					_equivocation_proof: grandpa_primitives::EquivocationProof<
+						<Block as BlockT>::Hash,
+						BlockNumber,
+					>,
+					_key_owner_proof: grandpa_primitives::OpaqueKeyOwnershipProof,
+				) -> Option<()> {
+					None
+				}
 			}
 
 			impl offchain_primitives::OffchainWorkerApi<Block> for Runtime {
@@ -828,13 +875,50 @@ cfg_if! {
 					babe_primitives::BabeConfiguration {
 						slot_duration: 1000,
 						epoch_length: EpochDuration::get(),
-						c: (3, 10),
+						c: PrimaryProbability::get(),
 						genesis_authorities: system::authorities()
 							.into_iter().map(|x|(x, 1)).collect(),
 						randomness: <pallet_babe::Module<Runtime>>::randomness(),
-						secondary_slots: true,
+						secondary_slots: SecondarySlots::get(),
 					}
 				}
+
+				fn generate_key_ownership_proof(
+					_slot_number: babe_primitives::SlotNumber,
+					_authority_id: babe_primitives::AuthorityId,
+				) -> Option<babe_primitives::OpaqueKeyOwnershipProof> {
+					None
+				}
+
+				fn submit_report_equivocation_unsigned_extrinsic(
+					_equivocation_proof: babe_primitives::EquivocationProof<<Block as BlockT>::Header>,
+					_key_owner_proof: babe_primitives::OpaqueKeyOwnershipProof,
+				) -> Option<()> {
+					None
+				}
+			}
+
+			impl grandpa_primitives::GrandpaApi<Block> for Runtime {
+				fn grandpa_authorities() -> grandpa_primitives::AuthorityList {
+					Vec::new()
+				}
+
+				fn generate_key_ownership_proof(
+					_set_id: grandpa_primitives::SetId,
+					_authority_id: grandpa_primitives::AuthorityId,
+				) -> Option<grandpa_primitives::OpaqueKeyOwnershipProof> {
+					None
+				}
+
+				fn submit_report_equivocation_unsigned_extrinsic(
+					_equivocation_proof: grandpa_primitives::EquivocationProof<
+						<Block as BlockT>::Hash,
+						BlockNumber,
+					>,
+					_key_owner_proof: grandpa_primitives::OpaqueKeyOwnershipProof,
+				) -> Option<()> {
+					None
+				}
 			}
 
 			impl offchain_primitives::OffchainWorkerApi<Block> for Runtime {